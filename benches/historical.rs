@@ -0,0 +1,85 @@
+//! Parsing of historical candle responses.
+//!
+//! Compares the typed `CandleRow` deserialization used by `get_historical_data` with walking
+//! a generic `serde_json::Value` tree, as the client did before. Run with
+//! `cargo bench --bench historical`.
+
+use criterion::{BenchmarkId, Criterion, Throughput, black_box, criterion_group, criterion_main};
+use kiteconnect_rs::models::time::Time;
+use kiteconnect_rs::{HistoricalData, HistoricalDataResponse};
+
+/// One trading day of minute candles.
+const CANDLES_PER_DAY: usize = 375;
+
+fn candles_json(days: usize) -> String {
+    let mut rows = Vec::with_capacity(days * CANDLES_PER_DAY);
+    for day in 0..days {
+        for minute in 0..CANDLES_PER_DAY {
+            let price = 1500.0 + (minute % 50) as f64 * 0.05;
+            rows.push(format!(
+                r#"["2024-{:02}-{:02}T{:02}:{:02}:00+0530",{},{},{},{},{},{}]"#,
+                1 + day / 28,
+                1 + day % 28,
+                9 + (15 + minute) / 60,
+                (15 + minute) % 60,
+                price,
+                price + 1.0,
+                price - 1.0,
+                price + 0.5,
+                1000 + minute,
+                50_000 + day
+            ));
+        }
+    }
+    format!(r#"{{"candles":[{}]}}"#, rows.join(","))
+}
+
+fn parse_typed(json: &str) -> Vec<HistoricalData> {
+    serde_json::from_str::<HistoricalDataResponse>(json)
+        .unwrap()
+        .into()
+}
+
+fn parse_value(json: &str) -> Vec<HistoricalData> {
+    let value: serde_json::Value = serde_json::from_str(json).unwrap();
+    value["candles"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|row| {
+            let row = row.as_array().unwrap();
+            let number = |i: usize| row.get(i).and_then(|v| v.as_f64()).unwrap_or_default();
+            HistoricalData {
+                date: Time::parse(row[0].as_str().unwrap()).unwrap(),
+                open: number(1),
+                high: number(2),
+                low: number(3),
+                close: number(4),
+                volume: number(5) as u32,
+                oi: number(6) as u32,
+            }
+        })
+        .collect()
+}
+
+fn bench_historical(c: &mut Criterion) {
+    let mut group = c.benchmark_group("historical_candles");
+    for days in [1, 20, 250] {
+        let json = candles_json(days);
+        group.throughput(Throughput::Elements((days * CANDLES_PER_DAY) as u64));
+        group.bench_with_input(BenchmarkId::new("typed_rows", days), &json, |b, json| {
+            b.iter(|| parse_typed(black_box(json)))
+        });
+        group.bench_with_input(BenchmarkId::new("value_tree", days), &json, |b, json| {
+            b.iter(|| parse_value(black_box(json)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = Criterion::default().sample_size(20);
+    targets = bench_historical
+}
+criterion_main!(benches);
@@ -42,7 +42,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
             println!("  Status: {:?}", alert.status);
             println!("  Type: {:?}", alert.r#type);
             println!(
-                "  Condition: {} {} {} {}",
+                "  Condition: {} {} {}",
                 alert.lhs_tradingsymbol,
                 match alert.operator {
                     AlertOperator::Ge => ">=",
@@ -51,8 +51,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
                     AlertOperator::Lt => "<",
                     AlertOperator::Eq => "==",
                 },
-                alert.rhs_constant.unwrap_or(0.0),
-                ""
+                alert.rhs_constant.unwrap_or(0.0)
             );
 
             // Example : Get specific alert
@@ -0,0 +1,134 @@
+//! End-to-end walkthrough that needs no Kite account: the REST calls go to a local mock
+//! API and the ticker connects to a local replay feed.
+//!
+//! ```sh
+//! cargo run --example demo --features test-utils
+//! ```
+
+use std::time::Duration;
+
+use kiteconnect_rs::{
+    orders::OrderParams,
+    test_utils::{KiteMockServer, MockDataGenerator, ReplayTicker},
+    ticker::{Mode, Ticker, TickerEvent},
+};
+use serde_json::json;
+
+const INFY: u32 = 408065;
+const RELIANCE: u32 = 738561;
+const NIFTY_50: u32 = 256265;
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut generator = MockDataGenerator::new(42);
+
+    // Serve generated fixtures from the mock API
+    let server = KiteMockServer::new().await;
+    let orders = vec![
+        generator.order(INFY, "INFY"),
+        generator.order(RELIANCE, "RELIANCE"),
+    ];
+    let positions = vec![
+        generator.position(INFY, "INFY"),
+        generator.position(RELIANCE, "RELIANCE"),
+    ];
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({ "order_id": orders[0].order_id }))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/orders")
+        .data(json!(orders))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({ "net": positions, "day": positions }))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/quote")
+        .data(json!({ "NSE:INFY": generator.quote(INFY) }))
+        .mount()
+        .await;
+
+    let kite = server.client();
+
+    println!("=== Orders ===");
+    let order_params = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        quantity: Some(1),
+        price: Some(1500.0),
+        product: Some("CNC".to_string()),
+        validity: Some("DAY".to_string()),
+        ..Default::default()
+    };
+    let response = kite.place_order("regular", order_params).await?;
+    println!("Placed order {}", response.order_id);
+    for order in kite.get_orders().await? {
+        println!(
+            "  {} {} {} x {} @ {:.2} - {}",
+            order.order_id,
+            order.transaction_type,
+            order.tradingsymbol,
+            order.quantity,
+            order.price,
+            order.status
+        );
+    }
+
+    println!("\n=== Positions ===");
+    for position in kite.get_positions().await?.net {
+        println!(
+            "  {} qty {} pnl {:.2}",
+            position.tradingsymbol, position.quantity, position.pnl
+        );
+    }
+
+    println!("\n=== Quotes ===");
+    for (key, quote) in kite.get_quote(&["NSE:INFY"]).await? {
+        println!(
+            "  {} last {:.2} volume {} OI {}",
+            key, quote.last_price, quote.volume, quote.oi
+        );
+    }
+
+    println!("\n=== Streaming ===");
+    let replay = ReplayTicker::generated(42, Duration::from_millis(200)).await?;
+    let (ticker, handle) = Ticker::builder("demo_api_key", "demo_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()?;
+    let events = handle.subscribe_events();
+    let serve_task = tokio::spawn(ticker.serve());
+
+    let mut ticks = 0;
+    while let Ok(event) = events.recv().await {
+        match event {
+            TickerEvent::Connect => {
+                let tokens = vec![INFY, RELIANCE, NIFTY_50];
+                handle.subscribe(tokens.clone()).await?;
+                handle.set_mode(Mode::Full, tokens).await?;
+            }
+            TickerEvent::Tick(tick) => {
+                println!(
+                    "  {} {:>5} ltp {:.2} volume {}",
+                    tick.instrument_token, tick.mode, tick.last_price, tick.volume_traded
+                );
+                ticks += 1;
+                if ticks == 12 {
+                    break;
+                }
+            }
+            TickerEvent::Error(e) => eprintln!("Ticker error: {}", e),
+            _ => {}
+        }
+    }
+
+    serve_task.abort();
+    Ok(())
+}
@@ -21,10 +21,10 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     let mut request_token = String::new();
     std::io::stdin().read_line(&mut request_token)?;
 
-    let access_token = kite
+    let session = kite
         .generate_session(request_token.trim(), &api_secret)
-        .await?
-        .access_token;
+        .await?;
+    let access_token = &session.access_token;
 
     println!("Access Token: {}", access_token);
 
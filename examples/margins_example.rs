@@ -25,9 +25,9 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
 
     // Get compact order margins
     match kite
-        .get_order_margins(GetMarginParams {
+        .get_order_margins_compact(GetMarginParams {
             order_params: vec![order_param.clone()],
-            compact: true,
+            mode: MarginMode::Compact,
         })
         .await
     {
@@ -45,7 +45,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     match kite
         .get_order_margins(GetMarginParams {
             order_params: vec![order_param.clone()],
-            compact: false,
+            mode: MarginMode::Full,
         })
         .await
     {
@@ -87,7 +87,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     match kite
         .get_basket_margins(GetBasketParams {
             order_params: order_params.clone(),
-            compact: false,
+            mode: MarginMode::Full,
             consider_positions: true,
         })
         .await
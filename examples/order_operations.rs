@@ -183,6 +183,7 @@ async fn main() -> Result<(), Box<dyn std::error::Error>> {
     }
 
     // Stop-loss order
+    #[allow(clippy::approx_constant)] // 6.28 is a price, not tau
     let sl_order_params = OrderParams {
         exchange: Some("NSE".to_string()),
         tradingsymbol: Some("IDEA".to_string()),
@@ -273,6 +273,17 @@ pub async fn start_ticker(api_key: String, access_token: String, tokens_str: Str
                     log(&msg);
                     append_to_output(&format!("<span class=\"order\">{}</span>", msg));
                 }
+                TickerEvent::Resubscribed(count) => {
+                    append_to_output(&format!("Resubscribed to {} tokens", count));
+                    set_status("Connected", "connected");
+                }
+                TickerEvent::Warning(msg) => {
+                    log(&format!("Ticker warning: {}", msg));
+                    append_to_output(&format!("<span class=\"warning\">{}</span>", msg));
+                }
+                TickerEvent::SubscriberLagged { missed } => {
+                    log(&format!("Missed {} events", missed));
+                }
                 TickerEvent::Message(_) => {
                     // Raw message, usually not needed for display
                 }
@@ -0,0 +1,19 @@
+#![no_main]
+
+use kiteconnect_rs::Ticker;
+use libfuzzer_sys::fuzz_target;
+
+fuzz_target!(|data: &[u8]| {
+    if let Ok(ticks) = Ticker::parse_binary(data) {
+        for tick in ticks {
+            assert!(tick.last_price >= 0.0);
+            for item in tick.depth.buy.iter().chain(tick.depth.sell.iter()) {
+                assert!(item.price >= 0.0);
+            }
+        }
+    }
+
+    for packet in Ticker::split_packets(data) {
+        let _ = Ticker::parse_packet(&packet);
+    }
+});
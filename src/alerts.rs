@@ -1,7 +1,13 @@
+use crate::markets::Instrument;
+use crate::models::Tick;
 use crate::models::time::Time;
+use crate::ticker::{TickerError, TickerEvent, TickerHandle};
 use crate::{KiteConnect, KiteConnectError, constants::Endpoints, models::OHLC};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
+
+/// Prices closer than this are treated as equal by `AlertOperator::Eq`.
+const PRICE_EPSILON: f64 = 1e-6;
 
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 #[serde(rename_all = "snake_case")]
@@ -216,3 +222,209 @@ impl KiteConnect {
             .await
     }
 }
+
+impl AlertOperator {
+    /// Evaluate `lhs <operator> rhs`.
+    pub fn compare(&self, lhs: f64, rhs: f64) -> bool {
+        match self {
+            AlertOperator::Le => lhs <= rhs,
+            AlertOperator::Ge => lhs >= rhs,
+            AlertOperator::Lt => lhs < rhs,
+            AlertOperator::Gt => lhs > rhs,
+            AlertOperator::Eq => (lhs - rhs).abs() < PRICE_EPSILON,
+        }
+    }
+}
+
+/// AlertCrossed is emitted by [`AlertWatcher`] when a tick satisfies an alert's condition.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AlertCrossed {
+    pub alert: Alert,
+    /// Token of the alert's left-hand instrument.
+    pub instrument_token: u32,
+    /// Left-hand side of the condition, the instrument's last price.
+    pub last_price: f64,
+    /// Right-hand side: the constant, or the other instrument's last price.
+    pub threshold: f64,
+}
+
+#[derive(Debug, Clone)]
+struct WatchedAlert {
+    alert: Alert,
+    lhs_token: u32,
+    // None for constant alerts
+    rhs_token: Option<u32>,
+    triggered: bool,
+}
+
+/// AlertWatcher evaluates enabled `last_price` alerts locally against live ticks, so a UI
+/// can show an alert as hit before the server-side alert fires.
+///
+/// The ticker's event channel hands each event to a single receiver, so the watcher does
+/// not read it itself; pass every event to [`on_event`](Self::on_event) from the loop that
+/// already consumes them:
+///
+/// ```ignore
+/// let mut watcher = kite.alert_watcher().await?;
+/// watcher.subscribe(&handle).await?;
+/// while let Ok(event) = events.recv().await {
+///     for crossed in watcher.on_event(&event) {
+///         println!("{} hit at {}", crossed.alert.name, crossed.last_price);
+///     }
+/// }
+/// ```
+///
+/// Each alert is emitted once when its condition becomes true and again only after it has
+/// been false in between.
+#[derive(Debug, Clone, Default)]
+pub struct AlertWatcher {
+    alerts: Vec<WatchedAlert>,
+    last_prices: HashMap<u32, f64>,
+    skipped: Vec<Alert>,
+}
+
+impl AlertWatcher {
+    /// Watch `alerts`, resolving their instruments through `instruments`.
+    ///
+    /// Disabled alerts, alerts on attributes other than `last_price`, and alerts whose
+    /// instruments are missing from `instruments` are not watched; see
+    /// [`skipped`](Self::skipped).
+    pub fn new(alerts: Vec<Alert>, instruments: &[Instrument]) -> Self {
+        let tokens: HashMap<(&str, &str), u32> = instruments
+            .iter()
+            .map(|i| {
+                (
+                    (i.exchange.as_str(), i.tradingsymbol.as_str()),
+                    i.instrument_token,
+                )
+            })
+            .collect();
+        let token =
+            |exchange: &str, tradingsymbol: &str| tokens.get(&(exchange, tradingsymbol)).copied();
+
+        let mut watcher = Self::default();
+        for alert in alerts {
+            let resolved = (alert.status == AlertStatus::Enabled
+                && alert.lhs_attribute == "last_price")
+                .then(|| token(&alert.lhs_exchange, &alert.lhs_tradingsymbol))
+                .flatten()
+                .and_then(|lhs_token| match alert.rhs_type.as_str() {
+                    "constant" => alert.rhs_constant.map(|_| (lhs_token, None)),
+                    "instrument" if alert.rhs_attribute == "last_price" => {
+                        token(&alert.rhs_exchange, &alert.rhs_tradingsymbol)
+                            .map(|rhs_token| (lhs_token, Some(rhs_token)))
+                    }
+                    _ => None,
+                });
+
+            match resolved {
+                Some((lhs_token, rhs_token)) => watcher.alerts.push(WatchedAlert {
+                    alert,
+                    lhs_token,
+                    rhs_token,
+                    triggered: false,
+                }),
+                None => watcher.skipped.push(alert),
+            }
+        }
+        watcher
+    }
+
+    /// Instrument tokens the watched alerts need ticks for.
+    pub fn tokens(&self) -> Vec<u32> {
+        let tokens: HashSet<u32> = self
+            .alerts
+            .iter()
+            .flat_map(|w| std::iter::once(w.lhs_token).chain(w.rhs_token))
+            .collect();
+        let mut tokens: Vec<u32> = tokens.into_iter().collect();
+        tokens.sort_unstable();
+        tokens
+    }
+
+    /// Alerts that cannot be evaluated locally.
+    pub fn skipped(&self) -> &[Alert] {
+        &self.skipped
+    }
+
+    /// Subscribe the ticker to [`tokens`](Self::tokens).
+    pub async fn subscribe(&self, handle: &TickerHandle) -> Result<(), TickerError> {
+        let tokens = self.tokens();
+        if tokens.is_empty() {
+            return Ok(());
+        }
+        handle.subscribe(tokens).await
+    }
+
+    /// Feed a ticker event; returns the alerts it made true.
+    pub fn on_event(&mut self, event: &TickerEvent) -> Vec<AlertCrossed> {
+        match event {
+            TickerEvent::Tick(tick) => self.on_tick(tick),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Feed a tick; returns the alerts it made true.
+    pub fn on_tick(&mut self, tick: &Tick) -> Vec<AlertCrossed> {
+        self.last_prices
+            .insert(tick.instrument_token, tick.last_price);
+
+        let mut crossed = Vec::new();
+        for watched in &mut self.alerts {
+            if watched.lhs_token != tick.instrument_token
+                && watched.rhs_token != Some(tick.instrument_token)
+            {
+                continue;
+            }
+            let Some(&last_price) = self.last_prices.get(&watched.lhs_token) else {
+                continue;
+            };
+            let threshold = match watched.rhs_token {
+                Some(token) => match self.last_prices.get(&token) {
+                    Some(&price) => price,
+                    None => continue,
+                },
+                None => watched.alert.rhs_constant.unwrap_or_default(),
+            };
+
+            let hit = watched.alert.operator.compare(last_price, threshold);
+            if hit && !watched.triggered {
+                crossed.push(AlertCrossed {
+                    alert: watched.alert.clone(),
+                    instrument_token: watched.lhs_token,
+                    last_price,
+                    threshold,
+                });
+            }
+            watched.triggered = hit;
+        }
+        crossed
+    }
+}
+
+impl KiteConnect {
+    /// Build an [`AlertWatcher`] for the user's enabled alerts, resolving instruments from
+    /// the instrument dumps of the exchanges involved.
+    pub async fn alert_watcher(&self) -> Result<AlertWatcher, KiteConnectError> {
+        let alerts: Vec<Alert> = self
+            .get_alerts(None)
+            .await?
+            .into_iter()
+            .filter(|alert| alert.status == AlertStatus::Enabled)
+            .collect();
+
+        let mut exchanges: Vec<&str> = alerts
+            .iter()
+            .flat_map(|a| [a.lhs_exchange.as_str(), a.rhs_exchange.as_str()])
+            .filter(|exchange| !exchange.is_empty())
+            .collect();
+        exchanges.sort_unstable();
+        exchanges.dedup();
+
+        let mut instruments = Vec::new();
+        for exchange in exchanges {
+            instruments.extend(self.get_instruments_by_exchange(exchange).await?);
+        }
+        Ok(AlertWatcher::new(alerts, &instruments))
+    }
+}
@@ -0,0 +1,142 @@
+//! After market orders.
+//!
+//! Orders placed with the `amo` variety are queued by Zerodha and sent to the exchange
+//! when it opens. Each exchange only accepts them in its own overnight window, and only for
+//! a subset of order types. [`KiteConnect::place_amo`] picks `regular` or `amo` from the
+//! [`MarketClock`] and validates AMO orders before sending them.
+
+use chrono::{DateTime, NaiveTime};
+use chrono_tz::{Asia::Kolkata, Tz};
+use serde::{Deserialize, Serialize};
+
+use crate::KiteConnect;
+use crate::clock::{MarketClock, MarketPhase, hm};
+use crate::labels::{Exchange, OrderType, Product, Validity, Variety};
+use crate::models::KiteConnectError;
+use crate::orders::{OrderParams, OrderResponse};
+use crate::risk::ist_now_datetime;
+
+/// AmoWindow is the IST time range in which an exchange accepts after market orders. It
+/// wraps past midnight; on non-trading days AMOs are accepted all day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AmoWindow {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl AmoWindow {
+    /// The window of `exchange`, `None` for exchanges that do not take AMOs.
+    pub fn for_exchange(exchange: Exchange) -> Option<Self> {
+        let (start, end) = match exchange {
+            Exchange::Nse | Exchange::Bse => (hm(15, 45), hm(8, 57)),
+            Exchange::Nfo | Exchange::Bfo => (hm(15, 45), hm(9, 10)),
+            Exchange::Cds => (hm(16, 45), hm(8, 59)),
+            Exchange::Mcx => return None,
+        };
+        Some(Self { start, end })
+    }
+
+    /// Whether `time` falls within the window on a trading day.
+    pub fn contains(&self, time: NaiveTime) -> bool {
+        time >= self.start || time < self.end
+    }
+}
+
+/// Check that `params` can be placed as an after market order, regardless of time.
+pub fn validate_amo(params: &OrderParams) -> Result<(), KiteConnectError> {
+    let exchange = parse_exchange(params)?;
+    if AmoWindow::for_exchange(exchange).is_none() {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} does not accept after market orders",
+            exchange
+        )));
+    }
+
+    let order_type: OrderType = params
+        .order_type
+        .as_deref()
+        .ok_or_else(|| KiteConnectError::invalid_params("AMO requires an order_type"))?
+        .parse()?;
+    if order_type == OrderType::SlM && matches!(exchange, Exchange::Nfo | Exchange::Bfo) {
+        return Err(KiteConnectError::invalid_params(
+            "SL-M after market orders are not allowed for F&O",
+        ));
+    }
+
+    if let Some(validity) = params.validity.as_deref() {
+        if validity.parse::<Validity>()? != Validity::Day {
+            return Err(KiteConnectError::invalid_params(format!(
+                "AMO validity must be DAY, got {}",
+                validity
+            )));
+        }
+    }
+
+    if let Some(product) = params.product.as_deref() {
+        if matches!(product.parse::<Product>()?, Product::Bo | Product::Co) {
+            return Err(KiteConnectError::invalid_params(format!(
+                "{} orders cannot be placed as AMO",
+                product
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// The variety an order for `exchange` has to use at `at`: `regular` while the market
+/// takes orders, `amo` inside the AMO window. Fails in the gaps between the two, e.g.
+/// right after the close.
+pub fn select_variety(
+    clock: &MarketClock,
+    exchange: Exchange,
+    at: DateTime<Tz>,
+) -> Result<Variety, KiteConnectError> {
+    let at = at.with_timezone(&Kolkata);
+    if matches!(
+        clock.phase_at(at),
+        MarketPhase::PreOpen | MarketPhase::Normal
+    ) {
+        return Ok(Variety::Regular);
+    }
+
+    let in_window =
+        AmoWindow::for_exchange(exchange).is_some_and(|window| window.contains(at.time()));
+    if in_window || !clock.is_trading_day(at.date_naive()) {
+        return Ok(Variety::Amo);
+    }
+    Err(KiteConnectError::invalid_params(format!(
+        "{} accepts neither regular nor after market orders at {}",
+        exchange,
+        at.time()
+    )))
+}
+
+impl KiteConnect {
+    /// Place an order as `regular` while the market is open and as `amo` outside market
+    /// hours, returning the variety used.
+    ///
+    /// AMOs are validated with [`validate_amo`] first. Client-side risk limits apply as
+    /// for [`place_order`](Self::place_order).
+    pub async fn place_amo(
+        &self,
+        order_params: OrderParams,
+        clock: &MarketClock,
+    ) -> Result<(Variety, OrderResponse), KiteConnectError> {
+        let exchange = parse_exchange(&order_params)?;
+        let variety = select_variety(clock, exchange, ist_now_datetime())?;
+        if variety == Variety::Amo {
+            validate_amo(&order_params)?;
+        }
+
+        let response = self.place_order(variety.as_str(), order_params).await?;
+        Ok((variety, response))
+    }
+}
+
+fn parse_exchange(params: &OrderParams) -> Result<Exchange, KiteConnectError> {
+    params
+        .exchange
+        .as_deref()
+        .ok_or_else(|| KiteConnectError::invalid_params("order has no exchange"))?
+        .parse()
+}
@@ -0,0 +1,52 @@
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{AuditLog, AuditRecord};
+use crate::models::KiteConnectError;
+
+/// JsonlAuditLog appends one JSON record per line to a file.
+pub struct JsonlAuditLog {
+    file: Mutex<File>,
+    sync: bool,
+}
+
+impl JsonlAuditLog {
+    /// Open `path` for appending, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        let file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)
+            .map_err(io_error)?;
+        Ok(Self {
+            file: Mutex::new(file),
+            sync: false,
+        })
+    }
+
+    /// fsync after every record so entries survive a crash or power loss.
+    pub fn sync_on_write(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+}
+
+impl AuditLog for JsonlAuditLog {
+    fn append(&self, record: &AuditRecord) -> Result<(), KiteConnectError> {
+        let mut line = serde_json::to_vec(record)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&line).map_err(io_error)?;
+        if self.sync {
+            file.sync_data().map_err(io_error)?;
+        }
+        Ok(())
+    }
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("audit log: {}", e))
+}
@@ -0,0 +1,153 @@
+//! Append-only audit trail of order activity.
+//!
+//! When an [`AuditLog`] is configured on the client, every place/modify/cancel request is
+//! recorded together with its response or error. Order updates from the ticker can be
+//! added with `KiteConnect::audit_order_update`. Records are never rewritten.
+//!
+//! Built-in logs:
+//! - `JsonlAuditLog`, one JSON record per line (native targets)
+//! - `SqliteAuditLog` (feature `sqlite`)
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use crate::KiteConnect;
+use crate::models::{ErrorCategory, KiteConnectError, Order};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod jsonl;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use jsonl::JsonlAuditLog;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteAuditLog;
+
+static NEXT_REQUEST_SEQ: AtomicU64 = AtomicU64::new(1);
+
+/// The kind of order activity an audit record describes.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum AuditAction {
+    Place,
+    Modify,
+    Cancel,
+    OrderUpdate,
+}
+
+impl AuditAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AuditAction::Place => "place",
+            AuditAction::Modify => "modify",
+            AuditAction::Cancel => "cancel",
+            AuditAction::OrderUpdate => "order_update",
+        }
+    }
+}
+
+/// AuditRecord is a single entry in the audit trail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AuditRecord {
+    /// Unique within the process: `<unix millis>-<sequence>`.
+    pub request_id: String,
+    pub timestamp: DateTime<Utc>,
+    pub action: AuditAction,
+    pub variety: Option<String>,
+    pub order_id: Option<String>,
+    pub request: Option<serde_json::Value>,
+    pub response: Option<serde_json::Value>,
+    pub error: Option<String>,
+    /// The request failed after it may have reached Kite, see [`ErrorCategory::Ambiguous`].
+    #[serde(default)]
+    pub ambiguous: bool,
+}
+
+impl AuditRecord {
+    pub fn new(action: AuditAction) -> Self {
+        let timestamp = Utc::now();
+        let seq = NEXT_REQUEST_SEQ.fetch_add(1, Ordering::Relaxed);
+        Self {
+            request_id: format!("{}-{}", timestamp.timestamp_millis(), seq),
+            timestamp,
+            action,
+            variety: None,
+            order_id: None,
+            request: None,
+            response: None,
+            error: None,
+            ambiguous: false,
+        }
+    }
+}
+
+/// AuditLog is an append-only store for audit records.
+///
+/// The client calls `append` on tokio's blocking pool, so it may block on disk IO.
+pub trait AuditLog: Send + Sync {
+    fn append(&self, record: &AuditRecord) -> Result<(), KiteConnectError>;
+}
+
+impl KiteConnect {
+    /// Record an order update received from the ticker in the audit log, if one is set.
+    pub async fn audit_order_update(&self, order: &Order) {
+        let mut record = AuditRecord::new(AuditAction::OrderUpdate);
+        record.variety = Some(order.variety.clone());
+        record.order_id = Some(order.order_id.clone());
+        record.response = serde_json::to_value(order).ok();
+        self.write_audit(record).await;
+    }
+
+    /// Record an order request and its outcome. Failures to write are logged, never
+    /// returned, since the request has already been sent by the time it is recorded.
+    pub(crate) async fn audit_request<Req, Resp>(
+        &self,
+        action: AuditAction,
+        variety: &str,
+        order_id: Option<&str>,
+        request: &Req,
+        result: &Result<Resp, KiteConnectError>,
+    ) where
+        Req: Serialize,
+        Resp: Serialize,
+    {
+        if self.audit_log.is_none() {
+            return;
+        }
+
+        let mut record = AuditRecord::new(action);
+        record.variety = Some(variety.to_owned());
+        record.order_id = order_id.map(str::to_owned);
+        record.request = serde_json::to_value(request).ok();
+        match result {
+            Ok(response) => record.response = serde_json::to_value(response).ok(),
+            Err(e) => {
+                record.error = Some(e.to_string());
+                record.ambiguous = e.category() == ErrorCategory::Ambiguous;
+            }
+        }
+        self.write_audit(record).await;
+    }
+
+    /// Append `record` on the blocking pool, so a slow disk does not stall the runtime.
+    /// The write is awaited, which keeps the records of one task in order.
+    async fn write_audit(&self, record: AuditRecord) {
+        let Some(log) = self.audit_log.clone() else {
+            return;
+        };
+        let request_id = record.request_id.clone();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let result = tokio::task::spawn_blocking(move || log.append(&record))
+            .await
+            .unwrap_or_else(|e| Err(KiteConnectError::other(format!("audit task failed: {}", e))));
+        #[cfg(target_arch = "wasm32")]
+        let result = log.append(&record);
+
+        if let Err(e) = result {
+            log::error!("failed to write audit record {}: {}", request_id, e);
+        }
+    }
+}
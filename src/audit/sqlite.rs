@@ -0,0 +1,78 @@
+use rusqlite::{Connection, params};
+use std::path::Path;
+use std::sync::Mutex;
+
+use super::{AuditLog, AuditRecord};
+use crate::models::KiteConnectError;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS order_audit (
+    seq INTEGER PRIMARY KEY AUTOINCREMENT,
+    request_id TEXT NOT NULL,
+    timestamp TEXT NOT NULL,
+    action TEXT NOT NULL,
+    variety TEXT,
+    order_id TEXT,
+    request TEXT,
+    response TEXT,
+    error TEXT
+);
+CREATE INDEX IF NOT EXISTS order_audit_order_id ON order_audit (order_id);
+CREATE TRIGGER IF NOT EXISTS order_audit_no_update BEFORE UPDATE ON order_audit
+BEGIN SELECT RAISE(ABORT, 'order_audit is append-only'); END;
+CREATE TRIGGER IF NOT EXISTS order_audit_no_delete BEFORE DELETE ON order_audit
+BEGIN SELECT RAISE(ABORT, 'order_audit is append-only'); END;
+";
+
+/// SqliteAuditLog appends records to an `order_audit` table.
+///
+/// Triggers reject UPDATE and DELETE on the table to keep it append-only.
+pub struct SqliteAuditLog {
+    conn: Mutex<Connection>,
+}
+
+impl SqliteAuditLog {
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        Self::from_connection(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    pub fn from_connection(conn: Connection) -> Result<Self, KiteConnectError> {
+        conn.execute_batch(SCHEMA).map_err(sqlite_error)?;
+        Ok(Self {
+            conn: Mutex::new(conn),
+        })
+    }
+
+    /// Run a closure against the underlying connection, e.g. to query the trail.
+    pub fn with_connection<R>(&self, f: impl FnOnce(&Connection) -> R) -> R {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        f(&conn)
+    }
+}
+
+impl AuditLog for SqliteAuditLog {
+    fn append(&self, record: &AuditRecord) -> Result<(), KiteConnectError> {
+        let json = |v: &Option<serde_json::Value>| v.as_ref().map(|v| v.to_string());
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        conn.execute(
+            "INSERT INTO order_audit (request_id, timestamp, action, variety, order_id, request, response, error)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8)",
+            params![
+                record.request_id,
+                record.timestamp.to_rfc3339(),
+                record.action.as_str(),
+                record.variety,
+                record.order_id,
+                json(&record.request),
+                json(&record.response),
+                record.error,
+            ],
+        )
+        .map(|_| ())
+        .map_err(sqlite_error)
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("audit log: sqlite: {}", e))
+}
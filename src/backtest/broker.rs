@@ -0,0 +1,695 @@
+//! The simulated order book behind a backtest.
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use super::OrderApi;
+use super::report::{BacktestReport, EquityPoint};
+use crate::labels::{OrderType, TransactionType, Variety};
+use crate::markets::HistoricalData;
+use crate::models::time::Time;
+use crate::models::{Depth, KiteConnectError, Tick};
+use crate::orders::{Order, OrderParams, OrderResponse, Orders, Trade, Trades};
+
+/// FillModel controls how simulated orders fill.
+///
+/// By default orders fill completely against the first bar, or tick, after the one they
+/// were placed on, so a strategy never trades on the prices it is reacting to:
+///
+/// - MARKET fills at the open
+/// - LIMIT fills at the open when that is at or better than the limit, else at the limit
+///   once the bar reaches it
+/// - SL-M triggers once the bar reaches the trigger and fills at the worse of the open
+///   and the trigger
+/// - SL triggers the same way and then fills like a LIMIT order
+///
+/// The other fields make fills more realistic: see [`Slippage`], [`LimitFills`] and
+/// `participation` for partial fills.
+#[derive(Debug, Clone, Default)]
+pub struct FillModel {
+    /// Fill orders that can execute right away when they are placed, at the last price
+    /// seen for the instrument, instead of on the next bar.
+    pub immediate: bool,
+    /// How much worse than the reference price MARKET and SL-M orders fill.
+    pub slippage: Slippage,
+    pub limit_fills: LimitFills,
+    /// Largest fraction of a bar's volume the open orders of an instrument can fill,
+    /// e.g. `0.1` for 10%. The rest stays open for later bars, so large orders fill in
+    /// parts. Tick volume is the change in `volume_traded` since the previous tick.
+    /// `None` fills orders completely.
+    pub participation: Option<f64>,
+    /// Charges per executed order, taken off the PnL on its first fill.
+    pub charges_per_order: f64,
+}
+
+/// Slippage of MARKET and SL-M fills, always against the order.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub enum Slippage {
+    #[default]
+    None,
+    /// A fixed amount per share, e.g. `0.05` for 5 paise.
+    Fixed(f64),
+    /// A percentage of the price, e.g. `0.05` for 5 bps.
+    Percent(f64),
+}
+
+impl Slippage {
+    fn apply(self, buy: bool, price: f64) -> f64 {
+        let slippage = match self {
+            Slippage::None => 0.0,
+            Slippage::Fixed(amount) => amount,
+            Slippage::Percent(percent) => price * percent / 100.0,
+        };
+        if buy {
+            price + slippage
+        } else {
+            price - slippage
+        }
+    }
+}
+
+/// LimitFills is when a resting LIMIT order fills.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LimitFills {
+    /// As soon as the price touches the limit.
+    #[default]
+    Touch,
+    /// Only once the orders ahead of it at its price have filled. The queue ahead is the
+    /// quantity at the order's price in the depth of the last full mode tick when the
+    /// order was placed or modified, shrinking as those orders are cancelled and as
+    /// volume trades at the limit. Prices trading through the limit fill it regardless.
+    /// Without depth, as with candles, the price has to trade through the limit.
+    Queue,
+}
+
+/// Prices an order is matched against. Ticks are bars with all four at the last price.
+#[derive(Debug, Clone, Copy)]
+struct Bar {
+    open: f64,
+    high: f64,
+    low: f64,
+    close: f64,
+    volume: f64,
+}
+
+impl Bar {
+    fn at(price: f64, volume: f64) -> Self {
+        Self {
+            open: price,
+            high: price,
+            low: price,
+            close: price,
+            volume,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default)]
+struct Position {
+    // Signed: negative when short
+    quantity: f64,
+    average_price: f64,
+}
+
+#[derive(Debug, Default)]
+struct BrokerState {
+    now: Time,
+    next_id: u64,
+    orders: Vec<Order>,
+    trades: Vec<Trade>,
+    // Order updates not yet handed to the strategy
+    updates: Vec<Order>,
+    positions: HashMap<u32, Position>,
+    last_prices: HashMap<u32, f64>,
+    // Depth of the last full mode tick and day volume of the last tick, per instrument
+    depths: HashMap<u32, Depth>,
+    volumes: HashMap<u32, u32>,
+    // Quantity ahead of each resting LIMIT order, by order id
+    queues: HashMap<String, f64>,
+    realized_pnl: f64,
+    charges: f64,
+    equity_curve: Vec<EquityPoint>,
+    peak: f64,
+    max_drawdown: f64,
+}
+
+impl BrokerState {
+    fn next_id(&mut self) -> String {
+        self.next_id += 1;
+        self.next_id.to_string()
+    }
+
+    fn unrealized_pnl(&self) -> f64 {
+        self.positions
+            .iter()
+            .map(|(token, position)| {
+                let last = self
+                    .last_prices
+                    .get(token)
+                    .copied()
+                    .unwrap_or(position.average_price);
+                position.quantity * (last - position.average_price)
+            })
+            .sum()
+    }
+
+    // Fill `quantity` of the order at `index`, completing it once nothing is pending
+    fn fill(&mut self, index: usize, price: f64, quantity: f64, charges: f64) {
+        let now = self.now;
+        let trade_id = self.next_id();
+        let order = &mut self.orders[index];
+        if order.filled_quantity == 0.0 {
+            self.charges += charges;
+        }
+        order.average_price = (order.average_price * order.filled_quantity + price * quantity)
+            / (order.filled_quantity + quantity);
+        order.filled_quantity += quantity;
+        order.pending_quantity -= quantity;
+        if order.pending_quantity <= 0.0 {
+            order.status = "COMPLETE".to_string();
+            order.pending_quantity = 0.0;
+            self.queues.remove(&order.order_id);
+        }
+        order.exchange_update_timestamp = now;
+        let instrument_token = order.instrument_token;
+        let signed = match order.transaction_type.as_str() {
+            "BUY" => quantity,
+            _ => -quantity,
+        };
+        let trade = Trade {
+            average_price: price,
+            quantity,
+            trade_id,
+            product: order.product.clone(),
+            fill_timestamp: now,
+            exchange_timestamp: now,
+            exchange_order_id: order.order_id.clone(),
+            order_id: order.order_id.clone(),
+            transaction_type: order.transaction_type.clone(),
+            tradingsymbol: order.tradingsymbol.clone(),
+            exchange: order.exchange.clone(),
+            instrument_token: order.instrument_token,
+            order_timestamp: Some(order.order_timestamp.to_string()),
+        };
+        let update = order.clone();
+        self.updates.push(update);
+        self.trades.push(trade);
+
+        let position = self.positions.entry(instrument_token).or_default();
+        if position.quantity == 0.0 || position.quantity.signum() == signed.signum() {
+            let quantity = position.quantity + signed;
+            position.average_price = (position.average_price * position.quantity.abs()
+                + price * signed.abs())
+                / quantity.abs();
+            position.quantity = quantity;
+        } else {
+            let closed = signed.abs().min(position.quantity.abs());
+            self.realized_pnl +=
+                closed * (price - position.average_price) * position.quantity.signum();
+            position.quantity += signed;
+            if position.quantity == 0.0 {
+                position.average_price = 0.0;
+            } else if position.quantity.signum() == signed.signum() {
+                // The fill flipped the position
+                position.average_price = price;
+            }
+        }
+    }
+
+    // Match the open order at `index` against `bar`, filling what it can. `immediate`
+    // matches a new order against the last price, where a limit at that price is
+    // marketable.
+    fn match_order(
+        &mut self,
+        index: usize,
+        bar: Bar,
+        model: &FillModel,
+        immediate: bool,
+        available: &mut Option<f64>,
+    ) {
+        let Some((price, most)) = self.fill_price(index, bar, model, immediate) else {
+            return;
+        };
+        let mut quantity = self.orders[index].pending_quantity.min(most);
+        if let Some(available) = available.as_mut() {
+            quantity = quantity.min(*available);
+            *available -= quantity;
+        }
+        if quantity > 0.0 {
+            self.fill(index, price, quantity, model.charges_per_order);
+        }
+    }
+
+    // Price and largest quantity the order at `index` can fill at on `bar`
+    fn fill_price(
+        &mut self,
+        index: usize,
+        bar: Bar,
+        model: &FillModel,
+        immediate: bool,
+    ) -> Option<(f64, f64)> {
+        let now = self.now;
+        let order = &mut self.orders[index];
+        let buy = order.transaction_type == TransactionType::Buy.as_str();
+        let slipped = |price: f64| (model.slippage.apply(buy, price), f64::INFINITY);
+        match (order.order_type.as_str(), order.status.as_str()) {
+            ("MARKET", _) => Some(slipped(bar.open)),
+            ("SL-M", _) => stop_reference(buy, bar, order.trigger_price).map(slipped),
+            ("LIMIT", _) | ("SL", "OPEN") => {
+                if immediate || model.limit_fills == LimitFills::Touch {
+                    limit_fill(buy, bar.open, bar, order.price).map(|p| (p, f64::INFINITY))
+                } else {
+                    self.queue_fill(index, bar)
+                }
+            }
+            ("SL", _) => {
+                let reference = stop_reference(buy, bar, order.trigger_price)?;
+                let price = limit_fill(buy, reference, bar, order.price);
+                if price.is_none() {
+                    // Triggered but not filled: a LIMIT order from now on
+                    order.status = "OPEN".to_string();
+                    order.exchange_update_timestamp = now;
+                    let update = order.clone();
+                    self.updates.push(update);
+                    self.join_queue(index);
+                }
+                price.map(|p| (p, f64::INFINITY))
+            }
+            _ => None,
+        }
+    }
+
+    // Fill of the resting limit order at `index` behind the queue at its price
+    fn queue_fill(&mut self, index: usize, bar: Bar) -> Option<(f64, f64)> {
+        let order = &self.orders[index];
+        let buy = order.transaction_type == TransactionType::Buy.as_str();
+        let limit = order.price;
+        // Opening or trading through the limit fills it whatever the queue
+        if (buy && bar.open < limit) || (!buy && bar.open > limit) {
+            return Some((bar.open, f64::INFINITY));
+        }
+        if (buy && bar.low < limit) || (!buy && bar.high > limit) {
+            return Some((limit, f64::INFINITY));
+        }
+        // Without a queue from depth, trading at the limit is not enough
+        let ahead = self.queues.get_mut(&order.order_id)?;
+        if let Some(depth) = self.depths.get(&order.instrument_token) {
+            if let Some(queued) = queue_at(depth, buy, limit) {
+                // Orders ahead were cancelled
+                *ahead = ahead.min(queued);
+            }
+        }
+        let at_limit = if buy {
+            bar.low <= limit
+        } else {
+            bar.high >= limit
+        };
+        if !at_limit {
+            return None;
+        }
+        let fillable = bar.volume - *ahead;
+        *ahead = (*ahead - bar.volume).max(0.0);
+        (fillable > 0.0).then_some((limit, fillable))
+    }
+
+    // Put the order at `index` at the back of the queue at its limit price
+    fn join_queue(&mut self, index: usize) {
+        let order = &self.orders[index];
+        if let Some(depth) = self.depths.get(&order.instrument_token) {
+            let buy = order.transaction_type == TransactionType::Buy.as_str();
+            // Beyond the visible depth, the queue is known once it comes into view
+            let ahead = queue_at(depth, buy, order.price).unwrap_or(f64::INFINITY);
+            self.queues.insert(order.order_id.clone(), ahead);
+        }
+    }
+}
+
+/// SimulatedBroker implements [`OrderApi`] on an in-memory order book.
+pub(crate) struct SimulatedBroker {
+    instruments: HashMap<(String, String), u32>,
+    fill_model: FillModel,
+    state: Mutex<BrokerState>,
+}
+
+impl SimulatedBroker {
+    pub fn new(instruments: HashMap<(String, String), u32>, fill_model: FillModel) -> Self {
+        Self {
+            instruments,
+            fill_model,
+            state: Mutex::new(BrokerState::default()),
+        }
+    }
+
+    fn state(&self) -> std::sync::MutexGuard<'_, BrokerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn set_time(&self, now: Time) {
+        if !now.is_null() {
+            self.state().now = now;
+        }
+    }
+
+    /// Match the open orders for `instrument_token` against a candle, then mark the
+    /// position to its close.
+    pub fn match_candle(&self, instrument_token: u32, candle: &HistoricalData) {
+        let bar = Bar {
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume as f64,
+        };
+        self.match_bar(instrument_token, bar);
+    }
+
+    /// Match the open orders for the tick's instrument against its last price, keeping
+    /// its depth for queue positions. Indicative pre-open ticks carry no trades and are
+    /// skipped.
+    pub fn match_tick(&self, tick: &Tick) {
+        if tick.indicative {
+            return;
+        }
+        let token = tick.instrument_token;
+        let volume = {
+            let mut state = self.state();
+            if tick
+                .depth
+                .buy
+                .iter()
+                .chain(&tick.depth.sell)
+                .any(|d| d.quantity > 0)
+            {
+                state.depths.insert(token, tick.depth.clone());
+            }
+            match state.volumes.insert(token, tick.volume_traded) {
+                Some(previous) if previous <= tick.volume_traded => tick.volume_traded - previous,
+                _ => tick.last_traded_quantity,
+            }
+        };
+        self.match_bar(token, Bar::at(tick.last_price, volume as f64));
+    }
+
+    fn match_bar(&self, instrument_token: u32, bar: Bar) {
+        let mut state = self.state();
+        let mut available = self
+            .fill_model
+            .participation
+            .map(|fraction| (bar.volume * fraction).floor());
+        for index in 0..state.orders.len() {
+            let order = &state.orders[index];
+            if order.instrument_token == instrument_token && is_open(order) {
+                state.match_order(index, bar, &self.fill_model, false, &mut available);
+            }
+        }
+        state.last_prices.insert(instrument_token, bar.close);
+    }
+
+    /// Order updates since the last call.
+    pub fn take_updates(&self) -> Vec<Order> {
+        std::mem::take(&mut self.state().updates)
+    }
+
+    /// Add a point to the PnL curve at the current time.
+    pub fn record_equity(&self) {
+        let mut state = self.state();
+        let pnl = state.realized_pnl + state.unrealized_pnl() - state.charges;
+        state.peak = state.peak.max(pnl);
+        state.max_drawdown = state.max_drawdown.max(state.peak - pnl);
+        let time = state.now;
+        state.equity_curve.push(EquityPoint { time, pnl });
+    }
+
+    pub fn into_report(self) -> BacktestReport {
+        let state = self.state.into_inner().unwrap_or_else(|e| e.into_inner());
+        BacktestReport {
+            unrealized_pnl: state.unrealized_pnl(),
+            orders: state.orders,
+            trades: state.trades,
+            equity_curve: state.equity_curve,
+            realized_pnl: state.realized_pnl,
+            charges: state.charges,
+            max_drawdown: state.max_drawdown,
+        }
+    }
+
+    fn instrument_token(&self, params: &OrderParams) -> Result<u32, KiteConnectError> {
+        let exchange = params.exchange.clone().unwrap_or_default();
+        let tradingsymbol = params.tradingsymbol.clone().unwrap_or_default();
+        let key = (exchange, tradingsymbol);
+        self.instruments.get(&key).copied().ok_or_else(|| {
+            KiteConnectError::invalid_params(format!(
+                "{}:{} is not an instrument of this backtest",
+                key.0, key.1
+            ))
+        })
+    }
+}
+
+// Fill price of a limit order on `bar`, starting from `reference` rather than the open
+fn limit_fill(buy: bool, reference: f64, bar: Bar, limit: f64) -> Option<f64> {
+    if buy {
+        if reference <= limit {
+            Some(reference)
+        } else if bar.low <= limit {
+            Some(limit)
+        } else {
+            None
+        }
+    } else if reference >= limit {
+        Some(reference)
+    } else if bar.high >= limit {
+        Some(limit)
+    } else {
+        None
+    }
+}
+
+// Quantity queued at `price` on the order's side of `depth`, or None when the price is
+// beyond the visible levels
+fn queue_at(depth: &Depth, buy: bool, price: f64) -> Option<f64> {
+    let levels = if buy { &depth.buy } else { &depth.sell };
+    let visible: Vec<_> = levels.iter().filter(|level| level.quantity > 0).collect();
+    let worst = visible.last()?;
+    if (buy && price < worst.price) || (!buy && price > worst.price) {
+        return None;
+    }
+    let queued = visible
+        .iter()
+        .find(|level| (level.price - price).abs() < 1e-9)
+        .map_or(0.0, |level| level.quantity as f64);
+    Some(queued)
+}
+
+// Price a stop order triggers at on `bar`, if it triggers at all
+fn stop_reference(buy: bool, bar: Bar, trigger: f64) -> Option<f64> {
+    if buy {
+        (bar.high >= trigger).then(|| bar.open.max(trigger))
+    } else {
+        (bar.low <= trigger).then(|| bar.open.min(trigger))
+    }
+}
+
+// Check what Kite would reject, returning the order type and quantity
+fn validate(params: &OrderParams) -> Result<(OrderType, i32), KiteConnectError> {
+    let required = |field: &Option<String>, name: &str| {
+        field
+            .clone()
+            .ok_or_else(|| KiteConnectError::invalid_params(format!("{} is required", name)))
+    };
+    required(&params.product, "product")?;
+    let _: TransactionType = required(&params.transaction_type, "transaction_type")?.parse()?;
+    let order_type: OrderType = required(&params.order_type, "order_type")?.parse()?;
+    let quantity = params.quantity.unwrap_or_default();
+    if quantity <= 0 {
+        return Err(KiteConnectError::invalid_params(
+            "quantity must be positive",
+        ));
+    }
+    let positive = |value: Option<f64>| value.is_some_and(|v| v > 0.0);
+    if matches!(order_type, OrderType::Limit | OrderType::Sl) && !positive(params.price) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} orders need a price",
+            order_type
+        )));
+    }
+    if matches!(order_type, OrderType::Sl | OrderType::SlM) && !positive(params.trigger_price) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} orders need a trigger_price",
+            order_type
+        )));
+    }
+    Ok((order_type, quantity))
+}
+
+fn initial_status(order_type: OrderType) -> &'static str {
+    match order_type {
+        OrderType::Sl | OrderType::SlM => "TRIGGER PENDING",
+        OrderType::Market | OrderType::Limit => "OPEN",
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl OrderApi for SimulatedBroker {
+    async fn place_order(
+        &self,
+        variety: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let variety: Variety = variety.parse()?;
+        if !matches!(variety, Variety::Regular | Variety::Amo) {
+            return Err(KiteConnectError::invalid_params(format!(
+                "backtests do not simulate {} orders",
+                variety
+            )));
+        }
+        let instrument_token = self.instrument_token(&order_params)?;
+        let (order_type, quantity) = validate(&order_params)?;
+
+        let mut state = self.state();
+        let order_id = state.next_id();
+        let now = state.now;
+        let order = Order {
+            account_id: None,
+            placed_by: "BACKTEST".to_string(),
+            order_id: order_id.clone(),
+            exchange_order_id: Some(order_id.clone()),
+            parent_order_id: None,
+            status: initial_status(order_type).to_string(),
+            status_message: None,
+            status_message_raw: None,
+            order_timestamp: now,
+            exchange_update_timestamp: now,
+            exchange_timestamp: now,
+            variety: variety.to_string(),
+            modified: false,
+            meta: HashMap::new(),
+            exchange: order_params.exchange.unwrap_or_default(),
+            tradingsymbol: order_params.tradingsymbol.unwrap_or_default(),
+            instrument_token,
+            order_type: order_type.to_string(),
+            transaction_type: order_params.transaction_type.unwrap_or_default(),
+            validity: order_params.validity.unwrap_or_else(|| "DAY".to_string()),
+            validity_ttl: order_params.validity_ttl,
+            product: order_params.product.unwrap_or_default(),
+            quantity: quantity as f64,
+            disclosed_quantity: order_params.disclosed_quantity.unwrap_or_default() as f64,
+            price: order_params.price.unwrap_or_default(),
+            trigger_price: order_params.trigger_price.unwrap_or_default(),
+            average_price: 0.0,
+            filled_quantity: 0.0,
+            pending_quantity: quantity as f64,
+            cancelled_quantity: 0.0,
+            auction_number: None,
+            tags: order_params.tag.clone().map(|tag| vec![tag]),
+            tag: order_params.tag,
+            market_protection: None,
+            guid: None,
+        };
+        state.updates.push(order.clone());
+        state.orders.push(order);
+        let index = state.orders.len() - 1;
+        if order_type == OrderType::Limit {
+            state.join_queue(index);
+        }
+        if self.fill_model.immediate {
+            if let Some(&price) = state.last_prices.get(&instrument_token) {
+                let bar = Bar::at(price, f64::INFINITY);
+                state.match_order(index, bar, &self.fill_model, true, &mut None);
+            }
+        }
+        Ok(OrderResponse::new(order_id))
+    }
+
+    async fn modify_order(
+        &self,
+        _variety: &str,
+        order_id: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let mut guard = self.state();
+        let state = &mut *guard;
+        let now = state.now;
+        let order = open_order(&mut state.orders, order_id)?;
+        let params = OrderParams::from_order(order).merge(order_params)?;
+        let (order_type, quantity) = validate(&params)?;
+        if quantity as f64 <= order.filled_quantity {
+            return Err(KiteConnectError::invalid_params(format!(
+                "quantity must be more than the {} already filled",
+                order.filled_quantity
+            )));
+        }
+        order.order_type = order_type.to_string();
+        order.quantity = quantity as f64;
+        order.pending_quantity = quantity as f64 - order.filled_quantity;
+        order.price = params.price.unwrap_or_default();
+        order.trigger_price = params.trigger_price.unwrap_or_default();
+        if order.status == "TRIGGER PENDING" || order_type != OrderType::Sl {
+            order.status = initial_status(order_type).to_string();
+        }
+        order.modified = true;
+        order.exchange_update_timestamp = now;
+        let update = order.clone();
+        // A modified order loses its place in the queue
+        state.queues.remove(order_id);
+        if update.status == "OPEN" && matches!(order_type, OrderType::Limit | OrderType::Sl) {
+            let index = state.orders.iter().position(|o| o.order_id == order_id);
+            if let Some(index) = index {
+                state.join_queue(index);
+            }
+        }
+        state.updates.push(update);
+        Ok(OrderResponse::new(order_id))
+    }
+
+    async fn cancel_order(
+        &self,
+        _variety: &str,
+        order_id: &str,
+        _parent_order_id: Option<&str>,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let mut guard = self.state();
+        let state = &mut *guard;
+        let now = state.now;
+        let order = open_order(&mut state.orders, order_id)?;
+        order.status = "CANCELLED".to_string();
+        order.cancelled_quantity = order.pending_quantity;
+        order.pending_quantity = 0.0;
+        order.exchange_update_timestamp = now;
+        let update = order.clone();
+        state.updates.push(update);
+        state.queues.remove(order_id);
+        Ok(OrderResponse::new(order_id))
+    }
+
+    async fn get_orders(&self) -> Result<Orders, KiteConnectError> {
+        Ok(self.state().orders.clone())
+    }
+
+    async fn get_trades(&self) -> Result<Trades, KiteConnectError> {
+        Ok(self.state().trades.clone())
+    }
+}
+
+fn is_open(order: &Order) -> bool {
+    matches!(order.status.as_str(), "OPEN" | "TRIGGER PENDING")
+}
+
+fn open_order<'a>(
+    orders: &'a mut [Order],
+    order_id: &str,
+) -> Result<&'a mut Order, KiteConnectError> {
+    let order = orders
+        .iter_mut()
+        .find(|order| order.order_id == order_id)
+        .ok_or_else(|| KiteConnectError::invalid_params(format!("no order {}", order_id)))?;
+    if !is_open(order) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "order {} is {}",
+            order_id, order.status
+        )));
+    }
+    Ok(order)
+}
@@ -0,0 +1,271 @@
+//! Running a [`Strategy`] against the live ticker and client.
+
+use async_trait::async_trait;
+use std::collections::{HashMap, HashSet};
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use web_time::Duration;
+
+use super::{OrderApi, Strategy};
+use crate::KiteConnect;
+use crate::markets::HistoricalData;
+use crate::models::time::Time;
+use crate::models::{KiteConnectError, Tick};
+use crate::orders::{OrderParams, OrderResponse, Orders, Trades};
+use crate::risk::{RiskLimits, RiskViolation};
+use crate::series::interval_duration;
+use crate::ticker::{TickerEvent, TickerHandle};
+
+/// IST offset, so day candles start at midnight IST rather than UTC.
+const IST_OFFSET_SECS: i64 = 5 * 3600 + 1800;
+
+/// LiveRunner feeds a [`Strategy`] the events of a live ticker and lets it trade through a
+/// [`KiteConnect`], so a strategy moves from a [`Backtest`](super::Backtest) to live
+/// trading unchanged.
+///
+/// On top of any limits configured on the client, the runner can hold the strategy to its
+/// own [`RiskLimits`], which also apply to modifications, and to a maximum number of
+/// orders per run. Orders refused by either fail with a `RiskViolation` error. Only updates of orders the strategy placed reach
+/// `on_order_update`.
+///
+/// ```ignore
+/// let (ticker, handle) = Ticker::builder(&api_key, &access_token).build()?;
+/// tokio::spawn(ticker.serve());
+/// handle.subscribe(vec![408065]).await?;
+/// LiveRunner::new(kite, handle)
+///     .risk_limits(RiskLimits::new().max_quantity(50))
+///     .max_orders(20)
+///     .candles("minute")?
+///     .run(&mut strategy)
+///     .await?;
+/// ```
+pub struct LiveRunner {
+    kite: KiteConnect,
+    ticker: TickerHandle,
+    risk_limits: Option<RiskLimits>,
+    max_orders: Option<usize>,
+    candle_interval: Option<Duration>,
+    indicative_candles: bool,
+}
+
+impl LiveRunner {
+    pub fn new(kite: KiteConnect, ticker: TickerHandle) -> Self {
+        Self {
+            kite,
+            ticker,
+            risk_limits: None,
+            max_orders: None,
+            candle_interval: None,
+            indicative_candles: false,
+        }
+    }
+
+    pub fn risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk_limits = Some(limits);
+        self
+    }
+
+    /// Refuse orders once the strategy has placed `count` in this run. An order split at
+    /// the freeze quantity counts once.
+    pub fn max_orders(mut self, count: usize) -> Self {
+        self.max_orders = Some(count);
+        self
+    }
+
+    /// Build candles of a Kite `interval` (`minute`, `5minute`, `day`, ...) from the ticks
+    /// and pass them to `on_candle`. A candle is passed on once the first tick of the next
+    /// one arrives. Candles follow the exchange time, which only full mode ticks carry;
+    /// other ticks are left out, as are indicative pre-open ticks.
+    pub fn candles(mut self, interval: &str) -> Result<Self, KiteConnectError> {
+        let duration = interval_duration(interval).ok_or_else(|| {
+            KiteConnectError::invalid_params(format!("unknown candle interval {}", interval))
+        })?;
+        self.candle_interval = Some(duration);
+        Ok(self)
+    }
+
+    /// Build candles from indicative pre-open ticks too, so the first candle of the day
+    /// opens at the auction's equilibrium price. Off by default.
+    pub fn indicative_candles(mut self, include: bool) -> Self {
+        self.indicative_candles = include;
+        self
+    }
+
+    /// Run `strategy` until the ticker stops or a callback fails, returning that error.
+    pub async fn run<S: Strategy + ?Sized>(self, strategy: &mut S) -> Result<(), KiteConnectError> {
+        let events = self.ticker.subscribe_broadcast();
+        let mut candles = self
+            .candle_interval
+            .map(|interval| CandleBuilder::new(interval, self.indicative_candles));
+        let orders = GuardedOrders {
+            kite: self.kite,
+            risk_limits: self.risk_limits,
+            max_orders: self.max_orders,
+            placed: Mutex::new(HashSet::new()),
+            orders_placed: AtomicUsize::new(0),
+        };
+        while let Ok(event) = events.recv().await {
+            match event {
+                TickerEvent::Tick(tick) => {
+                    if let Some(candle) = candles.as_mut().and_then(|c| c.push(&tick)) {
+                        strategy
+                            .on_candle(&orders, tick.instrument_token, &candle)
+                            .await?;
+                    }
+                    strategy.on_tick(&orders, &tick).await?;
+                }
+                TickerEvent::OrderUpdate(order) if orders.placed().contains(&order.order_id) => {
+                    strategy.on_order_update(&orders, &order.into()).await?;
+                }
+                _ => {}
+            }
+        }
+        Ok(())
+    }
+}
+
+// The client as the strategy sees it, behind the runner's guardrails
+struct GuardedOrders {
+    kite: KiteConnect,
+    risk_limits: Option<RiskLimits>,
+    max_orders: Option<usize>,
+    // Every order ID placed, split children included, to pick out the strategy's updates
+    placed: Mutex<HashSet<String>>,
+    // Orders the strategy asked for, which `max_orders` counts
+    orders_placed: AtomicUsize,
+}
+
+impl GuardedOrders {
+    fn placed(&self) -> std::sync::MutexGuard<'_, HashSet<String>> {
+        self.placed.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl OrderApi for GuardedOrders {
+    async fn place_order(
+        &self,
+        variety: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        if let Some(limit) = self.max_orders {
+            let placed = self.orders_placed.load(Ordering::Relaxed);
+            if placed >= limit {
+                return Err(RiskViolation::MaxOrders { placed, limit }.into());
+            }
+        }
+        if let Some(limits) = &self.risk_limits {
+            self.kite.check_risk_limits(limits, &order_params).await?;
+        }
+        let response = self.kite.place_order(variety, order_params).await?;
+        self.orders_placed.fetch_add(1, Ordering::Relaxed);
+        let mut placed = self.placed();
+        placed.insert(response.order_id.clone());
+        placed.extend(response.split_order_ids.iter().cloned());
+        Ok(response)
+    }
+
+    async fn modify_order(
+        &self,
+        variety: &str,
+        order_id: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        if let Some(limits) = &self.risk_limits {
+            let modified = self.kite.modified_order(order_id, &order_params).await?;
+            self.kite.check_modify_limits(limits, &modified).await?;
+        }
+        self.kite
+            .modify_order(variety, order_id, order_params)
+            .await
+    }
+
+    async fn cancel_order(
+        &self,
+        variety: &str,
+        order_id: &str,
+        parent_order_id: Option<&str>,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        self.kite
+            .cancel_order(variety, order_id, parent_order_id)
+            .await
+    }
+
+    async fn get_orders(&self) -> Result<Orders, KiteConnectError> {
+        self.kite.get_orders().await
+    }
+
+    async fn get_trades(&self) -> Result<Trades, KiteConnectError> {
+        self.kite.get_trades().await
+    }
+}
+
+// Candles of one interval built from last prices, per instrument
+struct CandleBuilder {
+    interval: i64,
+    indicative: bool,
+    current: HashMap<u32, OpenCandle>,
+}
+
+struct OpenCandle {
+    start: i64,
+    candle: HistoricalData,
+    // Day volume before the candle's first tick
+    base_volume: u32,
+}
+
+impl CandleBuilder {
+    fn new(interval: Duration, indicative: bool) -> Self {
+        Self {
+            interval: (interval.as_secs() as i64).max(1),
+            indicative,
+            current: HashMap::new(),
+        }
+    }
+
+    // Add a tick, returning the candle it completed if any
+    fn push(&mut self, tick: &Tick) -> Option<HistoricalData> {
+        if tick.indicative && !self.indicative {
+            return None;
+        }
+        // Only full mode ticks carry the exchange time
+        let now = tick.timestamp.as_datetime()?.timestamp();
+        let start =
+            (now + IST_OFFSET_SECS).div_euclid(self.interval) * self.interval - IST_OFFSET_SECS;
+        let price = tick.last_price;
+
+        if let Some(open) = self.current.get_mut(&tick.instrument_token) {
+            if open.start == start {
+                let candle = &mut open.candle;
+                candle.high = candle.high.max(price);
+                candle.low = candle.low.min(price);
+                candle.close = price;
+                candle.volume = tick.volume_traded.saturating_sub(open.base_volume);
+                candle.oi = tick.oi;
+                return None;
+            }
+            if start < open.start {
+                // Late tick of a candle already passed on
+                return None;
+            }
+        }
+
+        let next = OpenCandle {
+            start,
+            candle: HistoricalData {
+                date: Time::from_timestamp(start),
+                open: price,
+                high: price,
+                low: price,
+                close: price,
+                volume: 0,
+                oi: tick.oi,
+            },
+            base_volume: tick.volume_traded,
+        };
+        self.current
+            .insert(tick.instrument_token, next)
+            .map(|done| done.candle)
+    }
+}
@@ -0,0 +1,263 @@
+//! Backtesting strategies against historical candles or recorded ticks.
+//!
+//! A [`Strategy`] reacts to candles, ticks and order updates and trades through an
+//! [`OrderApi`]. [`KiteConnect`] implements `OrderApi` for live trading; a [`Backtest`]
+//! hands the strategy a simulated order book instead, filled by a [`FillModel`], and
+//! returns a [`BacktestReport`] with the orders, trades and PnL curve. A [`LiveRunner`]
+//! runs the same strategy on the live ticker:
+//!
+//! ```ignore
+//! let candles = kite.get_historical_data(params).await?;
+//! let report = Backtest::new()
+//!     .instrument(408065, "NSE", "INFY")
+//!     .fill_model(FillModel {
+//!         slippage: Slippage::Percent(0.05),
+//!         participation: Some(0.1),
+//!         charges_per_order: 20.0,
+//!         ..Default::default()
+//!     })
+//!     .run_candles(&mut strategy, candles.into_iter().map(|c| (408065, c)))
+//!     .await?;
+//! println!("{} over {} trades", report.net_pnl(), report.trades.len());
+//! ```
+//!
+//! Only `regular` and `amo` orders are simulated, without margin checks.
+
+mod broker;
+mod live;
+mod report;
+
+use async_trait::async_trait;
+use std::collections::HashMap;
+
+pub use broker::{FillModel, LimitFills, Slippage};
+pub use live::LiveRunner;
+pub use report::{BacktestReport, EquityPoint};
+
+use self::broker::SimulatedBroker;
+use crate::KiteConnect;
+use crate::markets::HistoricalData;
+use crate::models::{KiteConnectError, Tick};
+use crate::orders::{Order, OrderParams, OrderResponse, Orders, Trades};
+
+/// OrderApi is the part of the client a [`Strategy`] trades through, with the same
+/// signatures as the [`KiteConnect`] methods.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait OrderApi: Send + Sync {
+    async fn place_order(
+        &self,
+        variety: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError>;
+    async fn modify_order(
+        &self,
+        variety: &str,
+        order_id: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError>;
+    async fn cancel_order(
+        &self,
+        variety: &str,
+        order_id: &str,
+        parent_order_id: Option<&str>,
+    ) -> Result<OrderResponse, KiteConnectError>;
+    async fn get_orders(&self) -> Result<Orders, KiteConnectError>;
+    async fn get_trades(&self) -> Result<Trades, KiteConnectError>;
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl OrderApi for KiteConnect {
+    async fn place_order(
+        &self,
+        variety: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        KiteConnect::place_order(self, variety, order_params).await
+    }
+
+    async fn modify_order(
+        &self,
+        variety: &str,
+        order_id: &str,
+        order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        KiteConnect::modify_order(self, variety, order_id, order_params).await
+    }
+
+    async fn cancel_order(
+        &self,
+        variety: &str,
+        order_id: &str,
+        parent_order_id: Option<&str>,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        KiteConnect::cancel_order(self, variety, order_id, parent_order_id).await
+    }
+
+    async fn get_orders(&self) -> Result<Orders, KiteConnectError> {
+        KiteConnect::get_orders(self).await
+    }
+
+    async fn get_trades(&self) -> Result<Trades, KiteConnectError> {
+        KiteConnect::get_trades(self).await
+    }
+}
+
+/// Strategy is trading logic that runs the same live and in a [`Backtest`].
+///
+/// Callbacks left out do nothing. An error from a callback ends a backtest with that
+/// error.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Strategy: Send {
+    /// A completed candle of `instrument_token`.
+    async fn on_candle(
+        &mut self,
+        orders: &dyn OrderApi,
+        instrument_token: u32,
+        candle: &HistoricalData,
+    ) -> Result<(), KiteConnectError> {
+        let _ = (orders, instrument_token, candle);
+        Ok(())
+    }
+
+    async fn on_tick(
+        &mut self,
+        orders: &dyn OrderApi,
+        tick: &Tick,
+    ) -> Result<(), KiteConnectError> {
+        let _ = (orders, tick);
+        Ok(())
+    }
+
+    /// A change of one of the strategy's orders, in the form `get_orders` returns.
+    async fn on_order_update(
+        &mut self,
+        orders: &dyn OrderApi,
+        order: &Order,
+    ) -> Result<(), KiteConnectError> {
+        let _ = (orders, order);
+        Ok(())
+    }
+}
+
+/// Backtest drives a [`Strategy`] through historical data; see the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct Backtest {
+    instruments: HashMap<(String, String), u32>,
+    fill_model: FillModel,
+}
+
+enum MarketEvent {
+    Candle(u32, HistoricalData),
+    Tick(Box<Tick>),
+}
+
+impl Backtest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Let orders for `exchange:tradingsymbol` fill against the data of
+    /// `instrument_token`. Orders for instruments not added are rejected.
+    pub fn instrument(
+        mut self,
+        instrument_token: u32,
+        exchange: impl Into<String>,
+        tradingsymbol: impl Into<String>,
+    ) -> Self {
+        self.instruments
+            .insert((exchange.into(), tradingsymbol.into()), instrument_token);
+        self
+    }
+
+    pub fn fill_model(mut self, fill_model: FillModel) -> Self {
+        self.fill_model = fill_model;
+        self
+    }
+
+    /// Run `strategy` over `(instrument_token, candle)` pairs, taken in time order.
+    pub async fn run_candles<S: Strategy + ?Sized>(
+        &self,
+        strategy: &mut S,
+        candles: impl IntoIterator<Item = (u32, HistoricalData)>,
+    ) -> Result<BacktestReport, KiteConnectError> {
+        let mut candles: Vec<_> = candles.into_iter().collect();
+        candles.sort_by_key(|(_, candle)| candle.date.as_datetime());
+        let events = candles
+            .into_iter()
+            .map(|(token, candle)| MarketEvent::Candle(token, candle));
+        self.run(strategy, events).await
+    }
+
+    /// Run `strategy` over recorded ticks, in the order given.
+    pub async fn run_ticks<S: Strategy + ?Sized>(
+        &self,
+        strategy: &mut S,
+        ticks: impl IntoIterator<Item = Tick>,
+    ) -> Result<BacktestReport, KiteConnectError> {
+        self.run(
+            strategy,
+            ticks
+                .into_iter()
+                .map(|tick| MarketEvent::Tick(Box::new(tick))),
+        )
+        .await
+    }
+
+    async fn run<S: Strategy + ?Sized>(
+        &self,
+        strategy: &mut S,
+        events: impl Iterator<Item = MarketEvent>,
+    ) -> Result<BacktestReport, KiteConnectError> {
+        let broker = SimulatedBroker::new(self.instruments.clone(), self.fill_model.clone());
+        for event in events {
+            match &event {
+                MarketEvent::Candle(token, candle) => {
+                    broker.set_time(candle.date);
+                    broker.match_candle(*token, candle);
+                }
+                MarketEvent::Tick(tick) => {
+                    broker.set_time(tick.timestamp);
+                    broker.match_tick(tick);
+                }
+            }
+            deliver_updates(&broker, strategy).await?;
+            match &event {
+                MarketEvent::Candle(token, candle) => {
+                    strategy.on_candle(&broker, *token, candle).await?
+                }
+                MarketEvent::Tick(tick) => strategy.on_tick(&broker, tick).await?,
+            }
+            deliver_updates(&broker, strategy).await?;
+            broker.record_equity();
+        }
+        Ok(broker.into_report())
+    }
+}
+
+/// Most rounds of order updates handed to a strategy for one candle or tick. A strategy
+/// that answers every update with another order change never settles, and fails the
+/// backtest once it reaches this.
+pub const MAX_UPDATE_ROUNDS: usize = 100;
+
+// Hand over order updates until the strategy stops placing and changing orders
+async fn deliver_updates<S: Strategy + ?Sized>(
+    broker: &SimulatedBroker,
+    strategy: &mut S,
+) -> Result<(), KiteConnectError> {
+    for _ in 0..MAX_UPDATE_ROUNDS {
+        let updates = broker.take_updates();
+        if updates.is_empty() {
+            return Ok(());
+        }
+        for order in &updates {
+            strategy.on_order_update(broker, order).await?;
+        }
+    }
+    Err(KiteConnectError::other(format!(
+        "strategy kept changing orders after {} rounds of order updates at one event",
+        MAX_UPDATE_ROUNDS
+    )))
+}
@@ -0,0 +1,36 @@
+//! The results of a backtest.
+
+use serde::Serialize;
+
+use crate::models::time::Time;
+use crate::orders::{Order, Trade};
+
+/// EquityPoint is the net PnL after one bar or tick.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct EquityPoint {
+    pub time: Time,
+    /// Realized plus unrealized PnL, net of charges.
+    pub pnl: f64,
+}
+
+/// BacktestReport is what a backtest run returns.
+#[derive(Debug, Clone, Serialize)]
+pub struct BacktestReport {
+    /// Every order placed, in its final state.
+    pub orders: Vec<Order>,
+    /// One trade per fill; orders filled in parts have several.
+    pub trades: Vec<Trade>,
+    pub equity_curve: Vec<EquityPoint>,
+    pub realized_pnl: f64,
+    /// PnL of the positions still open, marked to the last price seen.
+    pub unrealized_pnl: f64,
+    pub charges: f64,
+    /// Largest fall of the PnL curve from an earlier peak.
+    pub max_drawdown: f64,
+}
+
+impl BacktestReport {
+    pub fn net_pnl(&self) -> f64 {
+        self.realized_pnl + self.unrealized_pnl - self.charges
+    }
+}
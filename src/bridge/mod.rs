@@ -0,0 +1,206 @@
+//! Bridges that republish ticker data to message brokers.
+//!
+//! A single ticker connection can fan out to many downstream processes this way, none of
+//! which need Kite credentials of their own. Any broker can be plugged in by implementing
+//! [`Publisher`] and wrapping it in a [`TickPublisher`], which handles encoding and
+//! per-instrument topics.
+//!
+//! Built-in bridges:
+//! - `RedisBridge` over Redis pub/sub (feature `redis`)
+//! - `NatsPublisher` (feature `nats`)
+//! - `MqttPublisher` (feature `mqtt`)
+
+use async_trait::async_trait;
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use std::sync::Arc;
+
+use crate::codec::TickCodec;
+use crate::markets::HistoricalData;
+use crate::models::{KiteConnectError, Order, Tick};
+use crate::ticker::{TickerEvent, token_exchange};
+
+#[cfg(feature = "mqtt")]
+mod mqtt;
+#[cfg(feature = "nats")]
+mod nats;
+#[cfg(feature = "redis")]
+mod redis;
+
+#[cfg(feature = "mqtt")]
+pub use self::mqtt::MqttPublisher;
+#[cfg(feature = "nats")]
+pub use self::nats::NatsPublisher;
+#[cfg(feature = "redis")]
+pub use self::redis::RedisBridge;
+
+const DEFAULT_TICK_TOPIC: &str = "kite/ticks/{exchange}/{token}";
+const DEFAULT_ORDER_TOPIC: &str = "kite/orders/{exchange}/{tradingsymbol}";
+
+/// Payload encoding for bridged messages.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Encoding {
+    #[default]
+    Json,
+    /// MessagePack, with struct fields encoded by name. Requires the `msgpack` feature.
+    #[cfg(feature = "msgpack")]
+    MsgPack,
+}
+
+impl Encoding {
+    pub fn encode<T: Serialize>(&self, value: &T) -> Result<Vec<u8>, KiteConnectError> {
+        match self {
+            Encoding::Json => Ok(serde_json::to_vec(value)?),
+            #[cfg(feature = "msgpack")]
+            Encoding::MsgPack => rmp_serde::to_vec_named(value)
+                .map_err(|e| KiteConnectError::other(format!("msgpack: {}", e))),
+        }
+    }
+
+    pub fn decode<T: DeserializeOwned>(&self, bytes: &[u8]) -> Result<T, KiteConnectError> {
+        match self {
+            Encoding::Json => Ok(serde_json::from_slice(bytes)?),
+            #[cfg(feature = "msgpack")]
+            Encoding::MsgPack => rmp_serde::from_slice(bytes)
+                .map_err(|e| KiteConnectError::other(format!("msgpack: {}", e))),
+        }
+    }
+}
+
+impl TickCodec for Encoding {
+    fn name(&self) -> &'static str {
+        match self {
+            Encoding::Json => "json",
+            #[cfg(feature = "msgpack")]
+            Encoding::MsgPack => "msgpack",
+        }
+    }
+
+    fn encode_tick(&self, tick: &Tick) -> Result<Vec<u8>, KiteConnectError> {
+        self.encode(tick)
+    }
+
+    fn decode_tick(&self, bytes: &[u8]) -> Result<Tick, KiteConnectError> {
+        self.decode(bytes)
+    }
+
+    fn encode_candle(&self, candle: &HistoricalData) -> Result<Vec<u8>, KiteConnectError> {
+        self.encode(candle)
+    }
+
+    fn decode_candle(&self, bytes: &[u8]) -> Result<HistoricalData, KiteConnectError> {
+        self.decode(bytes)
+    }
+}
+
+/// Publisher sends an encoded payload to a topic (subject, channel) on a broker.
+#[async_trait]
+pub trait Publisher: Send + Sync {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> Result<(), KiteConnectError>;
+}
+
+/// TopicTemplate renders topic names from ticks and order updates.
+///
+/// Tick placeholders: `{token}`, `{exchange}`, `{mode}`.
+/// Order placeholders: `{exchange}`, `{tradingsymbol}`, `{order_id}`, `{status}`.
+/// `{exchange}` for ticks is derived from the instrument token's segment.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TopicTemplate(String);
+
+impl TopicTemplate {
+    pub fn new(template: &str) -> Self {
+        Self(template.to_owned())
+    }
+
+    pub fn render_tick(&self, tick: &Tick) -> String {
+        self.0
+            .replace("{token}", &tick.instrument_token.to_string())
+            .replace(
+                "{exchange}",
+                token_exchange(tick.instrument_token).unwrap_or("UNKNOWN"),
+            )
+            .replace("{mode}", &tick.mode)
+    }
+
+    pub fn render_order(&self, order: &Order) -> String {
+        self.0
+            .replace("{exchange}", &order.exchange)
+            .replace("{tradingsymbol}", &order.tradingsymbol)
+            .replace("{order_id}", &order.order_id)
+            .replace("{status}", &order.status)
+    }
+}
+
+/// TickPublisher encodes ticks and order updates and publishes them on templated topics.
+pub struct TickPublisher<P: Publisher> {
+    publisher: P,
+    encoding: Encoding,
+    tick_codec: Option<Arc<dyn TickCodec>>,
+    tick_topic: TopicTemplate,
+    order_topic: TopicTemplate,
+}
+
+impl<P: Publisher> TickPublisher<P> {
+    /// Wrap a publisher with JSON encoding and the default
+    /// `kite/ticks/{exchange}/{token}` and `kite/orders/{exchange}/{tradingsymbol}` topics.
+    pub fn new(publisher: P) -> Self {
+        Self {
+            publisher,
+            encoding: Encoding::default(),
+            tick_codec: None,
+            tick_topic: TopicTemplate::new(DEFAULT_TICK_TOPIC),
+            order_topic: TopicTemplate::new(DEFAULT_ORDER_TOPIC),
+        }
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Encode ticks with `codec`, e.g. `ProtobufCodec`, instead of the encoding. Order
+    /// updates keep the encoding.
+    pub fn tick_codec(mut self, codec: impl TickCodec + 'static) -> Self {
+        self.tick_codec = Some(Arc::new(codec));
+        self
+    }
+
+    pub fn tick_topic(mut self, template: &str) -> Self {
+        self.tick_topic = TopicTemplate::new(template);
+        self
+    }
+
+    pub fn order_topic(mut self, template: &str) -> Self {
+        self.order_topic = TopicTemplate::new(template);
+        self
+    }
+
+    pub fn publisher(&self) -> &P {
+        &self.publisher
+    }
+
+    pub async fn publish_tick(&self, tick: &Tick) -> Result<(), KiteConnectError> {
+        let topic = self.tick_topic.render_tick(tick);
+        let payload = match &self.tick_codec {
+            Some(codec) => codec.encode_tick(tick)?,
+            None => self.encoding.encode(tick)?,
+        };
+        self.publisher.publish(&topic, payload).await
+    }
+
+    pub async fn publish_order(&self, order: &Order) -> Result<(), KiteConnectError> {
+        let topic = self.order_topic.render_order(order);
+        self.publisher
+            .publish(&topic, self.encoding.encode(order)?)
+            .await
+    }
+
+    /// Publish the event if it is a tick or an order update; other events are ignored.
+    pub async fn forward(&self, event: &TickerEvent) -> Result<(), KiteConnectError> {
+        match event {
+            TickerEvent::Tick(tick) => self.publish_tick(tick).await,
+            TickerEvent::OrderUpdate(order) => self.publish_order(order).await,
+            _ => Ok(()),
+        }
+    }
+}
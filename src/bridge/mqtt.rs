@@ -0,0 +1,75 @@
+use async_trait::async_trait;
+use rumqttc::{AsyncClient, MqttOptions, QoS};
+use web_time::Duration;
+
+use super::Publisher;
+use crate::compat::{self, TaskHandle};
+use crate::models::KiteConnectError;
+
+// Requests buffered by the MQTT client before publish starts waiting.
+const MQTT_REQUEST_CAPACITY: usize = 1024;
+const MQTT_RETRY_DELAY: Duration = Duration::from_secs(1);
+
+/// MqttPublisher publishes to an MQTT broker.
+///
+/// The MQTT event loop runs on a background task and reconnects on its own; it stops when
+/// the publisher is dropped.
+pub struct MqttPublisher {
+    client: AsyncClient,
+    qos: QoS,
+    retain: bool,
+    event_loop: TaskHandle,
+}
+
+impl MqttPublisher {
+    pub fn connect(options: MqttOptions) -> Self {
+        let (client, mut event_loop) = AsyncClient::new(options, MQTT_REQUEST_CAPACITY);
+        let event_loop = compat::spawn(async move {
+            loop {
+                if let Err(e) = event_loop.poll().await {
+                    log::warn!("mqtt connection error: {}", e);
+                    compat::sleep(MQTT_RETRY_DELAY).await;
+                }
+            }
+        });
+
+        Self {
+            client,
+            qos: QoS::AtMostOnce,
+            retain: false,
+            event_loop,
+        }
+    }
+
+    /// Delivery guarantee for published messages (default at most once).
+    pub fn qos(mut self, qos: QoS) -> Self {
+        self.qos = qos;
+        self
+    }
+
+    /// Ask the broker to keep the last message per topic for new subscribers.
+    pub fn retain(mut self, retain: bool) -> Self {
+        self.retain = retain;
+        self
+    }
+
+    pub fn client(&self) -> &AsyncClient {
+        &self.client
+    }
+}
+
+impl Drop for MqttPublisher {
+    fn drop(&mut self) {
+        self.event_loop.abort();
+    }
+}
+
+#[async_trait]
+impl Publisher for MqttPublisher {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> Result<(), KiteConnectError> {
+        self.client
+            .publish(topic, self.qos, self.retain, payload)
+            .await
+            .map_err(|e| KiteConnectError::other(format!("mqtt: {}", e)))
+    }
+}
@@ -0,0 +1,40 @@
+use async_trait::async_trait;
+
+use super::Publisher;
+use crate::models::KiteConnectError;
+
+/// NatsPublisher publishes to NATS subjects.
+///
+/// NATS separates subject tokens with `.`, so use a template such as
+/// `kite.ticks.{exchange}.{token}` with `TickPublisher::tick_topic`.
+#[derive(Clone)]
+pub struct NatsPublisher {
+    client: async_nats::Client,
+}
+
+impl NatsPublisher {
+    pub async fn connect(url: &str) -> Result<Self, KiteConnectError> {
+        let client = async_nats::connect(url)
+            .await
+            .map_err(|e| KiteConnectError::other(format!("nats: {}", e)))?;
+        Ok(Self::from_client(client))
+    }
+
+    pub fn from_client(client: async_nats::Client) -> Self {
+        Self { client }
+    }
+
+    pub fn client(&self) -> &async_nats::Client {
+        &self.client
+    }
+}
+
+#[async_trait]
+impl Publisher for NatsPublisher {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> Result<(), KiteConnectError> {
+        self.client
+            .publish(topic.to_owned(), payload.into())
+            .await
+            .map_err(|e| KiteConnectError::other(format!("nats: {}", e)))
+    }
+}
@@ -0,0 +1,119 @@
+use async_trait::async_trait;
+use redis::aio::MultiplexedConnection;
+
+use super::{Encoding, Publisher};
+use crate::models::{KiteConnectError, Order, Tick};
+use crate::ticker::TickerEvent;
+
+const DEFAULT_TICK_CHANNEL: &str = "kite:ticks";
+const DEFAULT_ORDER_CHANNEL: &str = "kite:orders";
+
+/// RedisBridge republishes ticks and order updates to Redis pub/sub channels.
+///
+/// Forward events from the ticker event loop:
+///
+/// ```ignore
+/// let bridge = RedisBridge::connect("redis://127.0.0.1/").await?;
+/// while let Ok(event) = events.recv().await {
+///     bridge.forward(&event).await?;
+/// }
+/// ```
+#[derive(Clone)]
+pub struct RedisBridge {
+    conn: MultiplexedConnection,
+    encoding: Encoding,
+    tick_channel: String,
+    order_channel: String,
+}
+
+impl RedisBridge {
+    pub async fn connect(url: &str) -> Result<Self, KiteConnectError> {
+        let client = redis::Client::open(url).map_err(redis_error)?;
+        let conn = client
+            .get_multiplexed_async_connection()
+            .await
+            .map_err(redis_error)?;
+        Ok(Self::from_connection(conn))
+    }
+
+    pub fn from_connection(conn: MultiplexedConnection) -> Self {
+        Self {
+            conn,
+            encoding: Encoding::default(),
+            tick_channel: DEFAULT_TICK_CHANNEL.to_string(),
+            order_channel: DEFAULT_ORDER_CHANNEL.to_string(),
+        }
+    }
+
+    pub fn encoding(mut self, encoding: Encoding) -> Self {
+        self.encoding = encoding;
+        self
+    }
+
+    /// Channel ticks are published to (default `kite:ticks`).
+    pub fn tick_channel(mut self, channel: &str) -> Self {
+        self.tick_channel = channel.to_owned();
+        self
+    }
+
+    /// Channel order updates are published to (default `kite:orders`).
+    pub fn order_channel(mut self, channel: &str) -> Self {
+        self.order_channel = channel.to_owned();
+        self
+    }
+
+    pub async fn publish_tick(&self, tick: &Tick) -> Result<(), KiteConnectError> {
+        self.publish_ticks(std::slice::from_ref(tick)).await
+    }
+
+    /// Publish a batch of ticks in a single pipelined round trip.
+    pub async fn publish_ticks(&self, ticks: &[Tick]) -> Result<(), KiteConnectError> {
+        if ticks.is_empty() {
+            return Ok(());
+        }
+        let mut pipe = redis::pipe();
+        for tick in ticks {
+            pipe.publish(&self.tick_channel, self.encoding.encode(tick)?)
+                .ignore();
+        }
+        pipe.query_async::<()>(&mut self.conn.clone())
+            .await
+            .map_err(redis_error)
+    }
+
+    pub async fn publish_order(&self, order: &Order) -> Result<(), KiteConnectError> {
+        redis::cmd("PUBLISH")
+            .arg(&self.order_channel)
+            .arg(self.encoding.encode(order)?)
+            .query_async::<()>(&mut self.conn.clone())
+            .await
+            .map_err(redis_error)
+    }
+
+    /// Publish the event if it is a tick or an order update; other events are ignored.
+    pub async fn forward(&self, event: &TickerEvent) -> Result<(), KiteConnectError> {
+        match event {
+            TickerEvent::Tick(tick) => self.publish_tick(tick).await,
+            TickerEvent::OrderUpdate(order) => self.publish_order(order).await,
+            _ => Ok(()),
+        }
+    }
+}
+
+/// Publishes to the Redis channel named by the topic, so the bridge can also back a
+/// `TickPublisher` for per-instrument channels.
+#[async_trait]
+impl Publisher for RedisBridge {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> Result<(), KiteConnectError> {
+        redis::cmd("PUBLISH")
+            .arg(topic)
+            .arg(payload)
+            .query_async::<()>(&mut self.conn.clone())
+            .await
+            .map_err(redis_error)
+    }
+}
+
+fn redis_error(e: redis::RedisError) -> KiteConnectError {
+    KiteConnectError::other(format!("redis: {}", e))
+}
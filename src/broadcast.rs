@@ -0,0 +1,337 @@
+//! Re-broadcasting ticker events to local dashboards.
+//!
+//! [`BroadcastServer`] relays the events of one ticker connection as JSON, over
+//! server-sent events at `/events` and plain WebSocket at `/ws`. Clients narrow what they
+//! receive with query parameters:
+//!
+//! - `tokens=408065,738561`: only ticks of these instruments; other events are unaffected
+//! - `types=tick,order`: only these event types
+//!
+//! WebSocket clients can change their filter later by sending
+//! `{"tokens": [408065], "types": ["tick"]}`; a field left out is kept and an empty list
+//! removes the restriction. Every event is a JSON object with a `type` field, which is also
+//! the SSE event name:
+//!
+//! | `type`         | Fields                                  |
+//! |----------------|-----------------------------------------|
+//! | `tick`         | `data`: the [`Tick`]                    |
+//! | `order`        | `data`: the order update                |
+//! | `connect`      |                                         |
+//! | `close`        | `code`, `reason`                        |
+//! | `error`        | `message`                               |
+//! | `reconnect`    | `attempt`, `delay_ms`                   |
+//! | `no_reconnect` | `attempt`                               |
+//! | `resubscribed` | `tokens`                                |
+//! | `warning`      | `message`                               |
+//! | `lagged`       | `missed`                                |
+//!
+//! A `lagged` event is also sent to a client that fell more than the server's capacity
+//! behind, with the number of events it skipped, before the next event it receives.
+//!
+//! The server only relays: instruments are subscribed on the ticker as usual.
+//!
+//! ```ignore
+//! let (ticker, handle) = Ticker::builder(&api_key, &access_token).build()?;
+//! tokio::spawn(ticker.serve());
+//! handle.subscribe(vec![408065]).await?;
+//! BroadcastServer::new(handle).serve("127.0.0.1:8080".parse()?).await?;
+//! ```
+
+use std::collections::HashSet;
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::Arc;
+
+use axum::Router;
+use axum::extract::ws::{Message, WebSocket, WebSocketUpgrade};
+use axum::extract::{Query, State};
+use axum::http::{HeaderValue, StatusCode, header};
+use axum::response::sse::{Event, KeepAlive, Sse};
+use axum::response::{IntoResponse, Response};
+use axum::routing::get;
+use futures_util::Stream;
+use serde::Deserialize;
+use serde_json::json;
+use tokio::sync::broadcast;
+
+use crate::compat;
+use crate::models::{KiteConnectError, Tick};
+use crate::ticker::{EventReceiver, TickerEvent, TickerHandle};
+
+/// Events kept for each client. A client further behind skips the oldest events.
+const DEFAULT_CAPACITY: usize = 4096;
+
+/// BroadcastServer configures the relay; see the [module docs](self).
+pub struct BroadcastServer {
+    ticker: TickerHandle,
+    capacity: usize,
+    cors_origin: Option<HeaderValue>,
+}
+
+impl BroadcastServer {
+    pub fn new(ticker: TickerHandle) -> Self {
+        Self {
+            ticker,
+            capacity: DEFAULT_CAPACITY,
+            cors_origin: None,
+        }
+    }
+
+    pub fn capacity(mut self, events: usize) -> Self {
+        self.capacity = events.max(1);
+        self
+    }
+
+    /// Allow dashboards served from `origin`, e.g. `http://localhost:3000`, to read
+    /// `/events` from the browser. Fails if `origin` is not a valid header value.
+    pub fn cors_origin(mut self, origin: &str) -> Result<Self, KiteConnectError> {
+        let origin = HeaderValue::from_str(origin)
+            .map_err(|e| KiteConnectError::invalid_params(format!("cors origin: {}", e)))?;
+        self.cors_origin = Some(origin);
+        Ok(self)
+    }
+
+    /// The routes, e.g. to nest them in another axum app. Starts reading the ticker's
+    /// events.
+    pub fn router(self) -> Router {
+        let (sender, _) = broadcast::channel(self.capacity);
+        let events = self.ticker.subscribe_broadcast();
+        let _ = compat::spawn(relay_events(events, sender.clone()));
+        Router::new()
+            .route("/events", get(sse))
+            .route("/ws", get(websocket))
+            .with_state(Arc::new(Shared {
+                sender,
+                cors_origin: self.cors_origin,
+            }))
+    }
+
+    /// Serve on `addr` until the server fails.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), KiteConnectError> {
+        let listener = tokio::net::TcpListener::bind(addr)
+            .await
+            .map_err(|e| KiteConnectError::other(format!("broadcast server: {}", e)))?;
+        axum::serve(listener, self.router())
+            .await
+            .map_err(|e| KiteConnectError::other(format!("broadcast server: {}", e)))
+    }
+}
+
+struct Shared {
+    sender: broadcast::Sender<Arc<BroadcastEvent>>,
+    cors_origin: Option<HeaderValue>,
+}
+
+// An event serialized once for all clients
+struct BroadcastEvent {
+    kind: &'static str,
+    instrument_token: Option<u32>,
+    json: String,
+}
+
+impl BroadcastEvent {
+    fn new(event: &TickerEvent) -> Option<Self> {
+        let (kind, body) = match event {
+            TickerEvent::Tick(tick) => ("tick", json!({"data": tick})),
+            TickerEvent::OrderUpdate(order) => ("order", json!({"data": order})),
+            TickerEvent::Connect => ("connect", json!({})),
+            TickerEvent::Close(code, reason) => ("close", json!({"code": code, "reason": reason})),
+            TickerEvent::Error(message) => ("error", json!({"message": message})),
+            TickerEvent::Reconnect(attempt, delay) => (
+                "reconnect",
+                json!({"attempt": attempt, "delay_ms": delay.as_millis() as u64}),
+            ),
+            TickerEvent::NoReconnect(attempt) => ("no_reconnect", json!({"attempt": attempt})),
+            TickerEvent::Resubscribed(tokens) => ("resubscribed", json!({"tokens": tokens})),
+            TickerEvent::Warning(message) => ("warning", json!({"message": message})),
+            TickerEvent::SubscriberLagged { missed } => ("lagged", json!({"missed": missed})),
+            // Raw text frames from Kite other than order updates
+            TickerEvent::Message(_) => return None,
+        };
+        let mut body = body;
+        body["type"] = kind.into();
+        let instrument_token = match event {
+            TickerEvent::Tick(Tick {
+                instrument_token, ..
+            }) => Some(*instrument_token),
+            _ => None,
+        };
+        Some(Self {
+            kind,
+            instrument_token,
+            json: body.to_string(),
+        })
+    }
+}
+
+// Serialize the ticker's events for the clients until the ticker stops
+async fn relay_events(events: EventReceiver, sender: broadcast::Sender<Arc<BroadcastEvent>>) {
+    while let Ok(event) = events.recv().await {
+        if sender.receiver_count() == 0 {
+            continue;
+        }
+        if let Some(event) = BroadcastEvent::new(&event) {
+            let _ = sender.send(Arc::new(event));
+        }
+    }
+}
+
+/// Query parameters of `/events` and `/ws`, as comma separated lists.
+#[derive(Debug, Default, Deserialize)]
+struct FilterQuery {
+    tokens: Option<String>,
+    types: Option<String>,
+}
+
+/// Filter update a WebSocket client sends.
+#[derive(Debug, Default, Deserialize)]
+struct FilterUpdate {
+    tokens: Option<Vec<u32>>,
+    types: Option<Vec<String>>,
+}
+
+// What a client receives; `None` lets everything through
+#[derive(Debug, Clone, Default)]
+struct EventFilter {
+    tokens: Option<HashSet<u32>>,
+    types: Option<HashSet<String>>,
+}
+
+impl EventFilter {
+    fn parse(query: FilterQuery) -> Result<Self, String> {
+        let list = |value: Option<String>| {
+            value
+                .map(|v| {
+                    v.split(',')
+                        .map(str::trim)
+                        .filter(|item| !item.is_empty())
+                        .map(str::to_string)
+                        .collect::<Vec<_>>()
+                })
+                .unwrap_or_default()
+        };
+        let tokens = list(query.tokens)
+            .iter()
+            .map(|token| {
+                token
+                    .parse()
+                    .map_err(|_| format!("invalid instrument token '{}'", token))
+            })
+            .collect::<Result<Vec<u32>, _>>()?;
+        let mut filter = Self::default();
+        filter.update(FilterUpdate {
+            tokens: Some(tokens),
+            types: Some(list(query.types)),
+        });
+        Ok(filter)
+    }
+
+    fn update(&mut self, update: FilterUpdate) {
+        if let Some(tokens) = update.tokens {
+            self.tokens = (!tokens.is_empty()).then(|| tokens.into_iter().collect());
+        }
+        if let Some(types) = update.types {
+            self.types = (!types.is_empty()).then(|| types.into_iter().collect());
+        }
+    }
+
+    fn matches(&self, event: &BroadcastEvent) -> bool {
+        if self.types.as_ref().is_some_and(|t| !t.contains(event.kind)) {
+            return false;
+        }
+        match (&self.tokens, event.instrument_token) {
+            (Some(tokens), Some(token)) => tokens.contains(&token),
+            _ => true,
+        }
+    }
+}
+
+// The next event for a client, or `None` once the relay has stopped. A client that fell
+// behind gets a `lagged` event with the number of events it skipped.
+async fn next_event(
+    receiver: &mut broadcast::Receiver<Arc<BroadcastEvent>>,
+    filter: &EventFilter,
+) -> Option<Arc<BroadcastEvent>> {
+    loop {
+        let event = match receiver.recv().await {
+            Ok(event) => event,
+            Err(broadcast::error::RecvError::Lagged(missed)) => {
+                let lagged = TickerEvent::SubscriberLagged { missed };
+                Arc::new(BroadcastEvent::new(&lagged)?)
+            }
+            Err(broadcast::error::RecvError::Closed) => return None,
+        };
+        if filter.matches(&event) {
+            return Some(event);
+        }
+    }
+}
+
+async fn sse(
+    State(shared): State<Arc<Shared>>,
+    Query(query): Query<FilterQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let filter = EventFilter::parse(query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let receiver = shared.sender.subscribe();
+    let stream = futures_util::stream::unfold(receiver, move |mut receiver| {
+        let filter = filter.clone();
+        async move {
+            let event = next_event(&mut receiver, &filter).await?;
+            let sse_event = Event::default().event(event.kind).data(&event.json);
+            Some((Ok::<_, Infallible>(sse_event), receiver))
+        }
+    });
+    let mut response = sse_response(stream).into_response();
+    if let Some(origin) = &shared.cors_origin {
+        response
+            .headers_mut()
+            .insert(header::ACCESS_CONTROL_ALLOW_ORIGIN, origin.clone());
+    }
+    Ok(response)
+}
+
+fn sse_response<S>(stream: S) -> Sse<S>
+where
+    S: Stream<Item = Result<Event, Infallible>> + Send + 'static,
+{
+    Sse::new(stream).keep_alive(KeepAlive::default())
+}
+
+async fn websocket(
+    upgrade: WebSocketUpgrade,
+    State(shared): State<Arc<Shared>>,
+    Query(query): Query<FilterQuery>,
+) -> Result<Response, (StatusCode, String)> {
+    let filter = EventFilter::parse(query).map_err(|e| (StatusCode::BAD_REQUEST, e))?;
+    let receiver = shared.sender.subscribe();
+    Ok(upgrade.on_upgrade(move |socket| relay_to_socket(socket, receiver, filter)))
+}
+
+async fn relay_to_socket(
+    mut socket: WebSocket,
+    mut receiver: broadcast::Receiver<Arc<BroadcastEvent>>,
+    mut filter: EventFilter,
+) {
+    loop {
+        tokio::select! {
+            event = next_event(&mut receiver, &filter) => {
+                let Some(event) = event else {
+                    return;
+                };
+                if socket.send(Message::Text(event.json.clone())).await.is_err() {
+                    return;
+                }
+            }
+            message = socket.recv() => match message {
+                Some(Ok(Message::Text(text))) => {
+                    // Anything that is not a filter update is ignored
+                    if let Ok(update) = serde_json::from_str::<FilterUpdate>(&text) {
+                        filter.update(update);
+                    }
+                }
+                Some(Ok(_)) => {}
+                Some(Err(_)) | None => return,
+            },
+        }
+    }
+}
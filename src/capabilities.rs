@@ -0,0 +1,202 @@
+//! What the API key and account are allowed to do.
+//!
+//! The user profile lists the products, order types and exchanges enabled for the
+//! account. [`Capabilities`] turns those lists into flags, so an app can tell up front
+//! that it cannot trade F&O or that the key has no MF scope instead of finding out from
+//! a rejected order. The profile does not reflect the permissions of the API key itself;
+//! [`KiteConnect::probe_capabilities`] also makes read calls to the order and MF APIs and
+//! clears the flags whose calls are refused.
+//!
+//! Once capabilities are known to the client, order placement fails fast with
+//! `MissingCapability` for products, order types and exchanges the account lacks, as do
+//! modifications and cancellations without order access and MF calls without MF access.
+
+use serde::{Deserialize, Serialize};
+
+use crate::KiteConnect;
+use crate::constants::Endpoints;
+use crate::models::{KiteConnectError, KiteConnectErrorKind};
+use crate::orders::OrderParams;
+use crate::users::UserProfile;
+
+/// Error type Kite returns for calls outside the API key's permissions.
+const PERMISSION_ERROR_TYPE: &str = "PermissionException";
+
+/// Capabilities are the trading permissions of an account and API key.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Capabilities {
+    pub products: Vec<String>,
+    pub order_types: Vec<String>,
+    pub exchanges: Vec<String>,
+    /// Orders can be placed: the profile lists products and order types, and the order
+    /// API was not refused when probed.
+    pub orders: bool,
+    /// The MF exchange is enabled and the MF API was not refused when probed.
+    pub mutual_funds: bool,
+    /// NSE or BSE.
+    pub equity: bool,
+    /// NFO or BFO.
+    pub derivatives: bool,
+    /// MCX.
+    pub commodity: bool,
+    /// CDS or BCD.
+    pub currency: bool,
+    /// Whether the flags were confirmed by probing the API.
+    pub probed: bool,
+}
+
+impl Capabilities {
+    pub fn from_profile(profile: &UserProfile) -> Self {
+        let has = |exchanges: &[&str]| {
+            profile
+                .exchanges
+                .iter()
+                .any(|e| exchanges.contains(&e.as_str()))
+        };
+        Self {
+            orders: !profile.products.is_empty() && !profile.order_types.is_empty(),
+            mutual_funds: has(&["MF"]),
+            equity: has(&["NSE", "BSE"]),
+            derivatives: has(&["NFO", "BFO"]),
+            commodity: has(&["MCX"]),
+            currency: has(&["CDS", "BCD"]),
+            products: profile.products.clone(),
+            order_types: profile.order_types.clone(),
+            exchanges: profile.exchanges.clone(),
+            probed: false,
+        }
+    }
+
+    pub fn supports_exchange(&self, exchange: &str) -> bool {
+        self.exchanges
+            .iter()
+            .any(|e| e.eq_ignore_ascii_case(exchange))
+    }
+
+    pub fn supports_product(&self, product: &str) -> bool {
+        self.products
+            .iter()
+            .any(|p| p.eq_ignore_ascii_case(product))
+    }
+
+    pub fn supports_order_type(&self, order_type: &str) -> bool {
+        self.order_types
+            .iter()
+            .any(|t| t.eq_ignore_ascii_case(order_type))
+    }
+
+    /// Fail with `MissingCapability` unless orders can be placed.
+    pub fn require_orders(&self) -> Result<(), KiteConnectError> {
+        if !self.orders {
+            return Err(KiteConnectError::missing_capability(
+                "the API key or account cannot place orders",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail with `MissingCapability` unless the MF API can be used.
+    pub fn require_mutual_funds(&self) -> Result<(), KiteConnectError> {
+        if !self.mutual_funds {
+            return Err(KiteConnectError::missing_capability(
+                "the API key or account has no mutual fund access",
+            ));
+        }
+        Ok(())
+    }
+
+    /// Fail with `MissingCapability` when the order's exchange, product or order type is
+    /// not enabled. Fields the order leaves unset are not checked.
+    pub fn check_order(&self, params: &OrderParams) -> Result<(), KiteConnectError> {
+        self.require_orders()?;
+        let checks = [
+            (
+                "exchange",
+                &params.exchange,
+                Self::supports_exchange as fn(&Self, &str) -> bool,
+            ),
+            ("product", &params.product, Self::supports_product),
+            ("order type", &params.order_type, Self::supports_order_type),
+        ];
+        for (name, value, supported) in checks {
+            if let Some(value) = value {
+                if !supported(self, value) {
+                    return Err(KiteConnectError::missing_capability(format!(
+                        "{} {} is not enabled for this account",
+                        name, value
+                    )));
+                }
+            }
+        }
+        Ok(())
+    }
+}
+
+impl KiteConnect {
+    /// The account's capabilities, derived from the user profile on first use and then
+    /// kept for this client and its clones.
+    pub async fn capabilities(&self) -> Result<Capabilities, KiteConnectError> {
+        if let Some(capabilities) = self.known_capabilities() {
+            return Ok(capabilities);
+        }
+        let capabilities = Capabilities::from_profile(&self.get_user_profile().await?);
+        self.set_capabilities(Some(capabilities.clone()));
+        Ok(capabilities)
+    }
+
+    /// Like [`capabilities`](Self::capabilities), but always refetches the profile and
+    /// confirms order and MF access with a read call to each API.
+    pub async fn probe_capabilities(&self) -> Result<Capabilities, KiteConnectError> {
+        let mut capabilities = Capabilities::from_profile(&self.get_user_profile().await?);
+        if capabilities.orders {
+            capabilities.orders = self.probe(Endpoints::GET_ORDERS).await?;
+        }
+        if capabilities.mutual_funds {
+            capabilities.mutual_funds = self.probe(Endpoints::GET_MF_ORDERS).await?;
+        }
+        capabilities.probed = true;
+        self.set_capabilities(Some(capabilities.clone()));
+        Ok(capabilities)
+    }
+
+    /// Capabilities already fetched, without making a request.
+    pub fn known_capabilities(&self) -> Option<Capabilities> {
+        self.capabilities
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Fail with `MissingCapability` when the known capabilities rule out orders.
+    pub(crate) fn require_known_orders(&self) -> Result<(), KiteConnectError> {
+        match self.known_capabilities() {
+            Some(capabilities) => capabilities.require_orders(),
+            None => Ok(()),
+        }
+    }
+
+    /// Fail with `MissingCapability` when the known capabilities rule out the MF API.
+    pub(crate) fn require_known_mutual_funds(&self) -> Result<(), KiteConnectError> {
+        match self.known_capabilities() {
+            Some(capabilities) => capabilities.require_mutual_funds(),
+            None => Ok(()),
+        }
+    }
+
+    /// Set or clear the known capabilities, e.g. to load them from a cache.
+    pub fn set_capabilities(&self, capabilities: Option<Capabilities>) {
+        *self.capabilities.write().unwrap_or_else(|e| e.into_inner()) = capabilities;
+    }
+
+    // Whether a GET of `endpoint` is allowed; errors other than a permission refusal fail
+    async fn probe(&self, endpoint: &str) -> Result<bool, KiteConnectError> {
+        match self.get::<serde_json::Value>(endpoint).await {
+            Ok(_) => Ok(true),
+            Err(KiteConnectError {
+                kind: KiteConnectErrorKind::ApiError(e),
+                ..
+            }) if e.error_type == PERMISSION_ERROR_TYPE => Ok(false),
+            Err(e) => Err(e),
+        }
+    }
+}
@@ -0,0 +1,235 @@
+//! Market session clock.
+//!
+//! [`MarketClock`] knows the IST session timings of an exchange and tells which phase the
+//! market is in, how long until the next open or the close, and can wait for either. All
+//! `*_at` methods take an explicit instant; the others use the current time.
+//!
+//! ```
+//! use chrono::TimeZone;
+//! use chrono_tz::Asia::Kolkata;
+//! use kiteconnect_rs::clock::{MarketClock, MarketPhase};
+//!
+//! let clock = MarketClock::nse();
+//! let morning = Kolkata.with_ymd_and_hms(2024, 6, 3, 9, 10, 0).unwrap();
+//! assert_eq!(clock.phase_at(morning), MarketPhase::PreOpen);
+//! assert_eq!(clock.time_to_open_at(morning).as_secs(), 5 * 60);
+//! ```
+
+use chrono::{DateTime, Datelike, Duration as ChronoDuration, NaiveDate, NaiveTime, Weekday};
+use chrono_tz::{Asia::Kolkata, Tz};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use web_time::Duration;
+
+use crate::compat;
+use crate::risk::ist_now_datetime;
+
+/// Longest single sleep while waiting, so waits recover from clock jumps and suspends.
+const MAX_WAIT_STEP: Duration = Duration::from_secs(60);
+
+/// Phase of a trading day.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarketPhase {
+    /// Pre-open call auction, before the normal session.
+    PreOpen,
+    /// Normal continuous trading.
+    Normal,
+    /// Closing price calculation, right after the normal session.
+    Closing,
+    /// Post-close session, orders at the closing price only.
+    PostClose,
+    /// Outside all sessions, including weekends and holidays.
+    Closed,
+}
+
+/// MarketHours are the IST session boundaries of an exchange.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct MarketHours {
+    pub pre_open: NaiveTime,
+    pub open: NaiveTime,
+    pub close: NaiveTime,
+    pub post_close: NaiveTime,
+    pub post_close_end: NaiveTime,
+}
+
+impl MarketHours {
+    /// NSE and BSE equity: pre-open 09:00, normal 09:15-15:30, post-close 15:40-16:00.
+    pub fn equity() -> Self {
+        Self {
+            pre_open: hm(9, 0),
+            open: hm(9, 15),
+            close: hm(15, 30),
+            post_close: hm(15, 40),
+            post_close_end: hm(16, 0),
+        }
+    }
+
+    /// MCX: 09:00-23:30 with no pre-open or post-close session.
+    pub fn mcx() -> Self {
+        Self {
+            pre_open: hm(9, 0),
+            open: hm(9, 0),
+            close: hm(23, 30),
+            post_close: hm(23, 30),
+            post_close_end: hm(23, 30),
+        }
+    }
+}
+
+/// MarketClock answers session questions for one exchange.
+///
+/// Weekends are closed. Exchange holidays are not known to the API, so add them with
+/// [`with_holidays`](Self::with_holidays).
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MarketClock {
+    hours: MarketHours,
+    holidays: BTreeSet<NaiveDate>,
+}
+
+impl MarketClock {
+    pub fn new(hours: MarketHours) -> Self {
+        Self {
+            hours,
+            holidays: BTreeSet::new(),
+        }
+    }
+
+    /// Clock for NSE/BSE equity and F&O.
+    pub fn nse() -> Self {
+        Self::new(MarketHours::equity())
+    }
+
+    pub fn with_holidays<I>(mut self, holidays: I) -> Self
+    where
+        I: IntoIterator<Item = NaiveDate>,
+    {
+        self.holidays.extend(holidays);
+        self
+    }
+
+    pub fn hours(&self) -> &MarketHours {
+        &self.hours
+    }
+
+    /// Whether the exchange trades on `date` at all.
+    pub fn is_trading_day(&self, date: NaiveDate) -> bool {
+        !matches!(date.weekday(), Weekday::Sat | Weekday::Sun) && !self.holidays.contains(&date)
+    }
+
+    pub fn phase(&self) -> MarketPhase {
+        self.phase_at(ist_now_datetime())
+    }
+
+    pub fn phase_at(&self, at: DateTime<Tz>) -> MarketPhase {
+        let at = at.with_timezone(&Kolkata);
+        if !self.is_trading_day(at.date_naive()) {
+            return MarketPhase::Closed;
+        }
+
+        let t = at.time();
+        let h = &self.hours;
+        if t >= h.pre_open && t < h.open {
+            MarketPhase::PreOpen
+        } else if t >= h.open && t < h.close {
+            MarketPhase::Normal
+        } else if t >= h.close && t < h.post_close {
+            MarketPhase::Closing
+        } else if t >= h.post_close && t < h.post_close_end {
+            MarketPhase::PostClose
+        } else {
+            MarketPhase::Closed
+        }
+    }
+
+    /// Whether the normal session is running.
+    pub fn is_open(&self) -> bool {
+        self.phase() == MarketPhase::Normal
+    }
+
+    /// Start of the next normal session, or of the current one if it is running.
+    pub fn next_open_at(&self, at: DateTime<Tz>) -> DateTime<Tz> {
+        let at = at.with_timezone(&Kolkata);
+        let mut date = at.date_naive();
+        if at.time() >= self.hours.close {
+            date = date.succ_opt().unwrap_or(date);
+        }
+        while !self.is_trading_day(date) {
+            date = date.succ_opt().unwrap_or(date);
+        }
+        ist(date, self.hours.open)
+    }
+
+    /// End of the running normal session, if any.
+    pub fn close_at(&self, at: DateTime<Tz>) -> Option<DateTime<Tz>> {
+        let at = at.with_timezone(&Kolkata);
+        (self.phase_at(at) == MarketPhase::Normal).then(|| ist(at.date_naive(), self.hours.close))
+    }
+
+    /// Time until the normal session opens; zero while it is open.
+    pub fn time_to_open(&self) -> Duration {
+        self.time_to_open_at(ist_now_datetime())
+    }
+
+    pub fn time_to_open_at(&self, at: DateTime<Tz>) -> Duration {
+        if self.phase_at(at) == MarketPhase::Normal {
+            return Duration::ZERO;
+        }
+        until(at, self.next_open_at(at))
+    }
+
+    /// Time until the running normal session closes, `None` outside of it.
+    pub fn time_to_close(&self) -> Option<Duration> {
+        self.time_to_close_at(ist_now_datetime())
+    }
+
+    pub fn time_to_close_at(&self, at: DateTime<Tz>) -> Option<Duration> {
+        self.close_at(at).map(|close| until(at, close))
+    }
+
+    /// Wait until the normal session is running. Returns immediately if it already is.
+    pub async fn wait_for_open(&self) {
+        loop {
+            let remaining = self.time_to_open();
+            if remaining.is_zero() {
+                return;
+            }
+            compat::sleep(remaining.min(MAX_WAIT_STEP)).await;
+        }
+    }
+
+    /// Wait until the running normal session closes. Returns immediately outside of it.
+    pub async fn wait_for_close(&self) {
+        while let Some(remaining) = self.time_to_close() {
+            if remaining.is_zero() {
+                return;
+            }
+            compat::sleep(remaining.min(MAX_WAIT_STEP)).await;
+        }
+    }
+}
+
+impl Default for MarketClock {
+    fn default() -> Self {
+        Self::nse()
+    }
+}
+
+pub(crate) fn hm(hour: u32, minute: u32) -> NaiveTime {
+    NaiveTime::from_hms_opt(hour, minute, 0).unwrap_or_default()
+}
+
+fn ist(date: NaiveDate, time: NaiveTime) -> DateTime<Tz> {
+    // IST has no DST, so every local time maps to exactly one instant
+    date.and_time(time)
+        .and_local_timezone(Kolkata)
+        .single()
+        .unwrap_or_else(|| date.and_time(time).and_utc().with_timezone(&Kolkata))
+}
+
+fn until(from: DateTime<Tz>, to: DateTime<Tz>) -> Duration {
+    (to - from)
+        .max(ChronoDuration::zero())
+        .to_std()
+        .unwrap_or_default()
+}
@@ -0,0 +1,31 @@
+//! Binary and text formats for recorded and bridged market data.
+//!
+//! A [`TickCodec`] turns ticks and candles into bytes and back, so pipelines that store
+//! or republish market data can pick a format without JSON's size and parsing overhead.
+//!
+//! Built-in codecs:
+//! - `bridge::Encoding::Json`, and `bridge::Encoding::MsgPack` (feature `msgpack`)
+//! - [`ProtobufCodec`], following the schema in `proto/ticks.proto` (feature `protobuf`)
+
+use crate::markets::HistoricalData;
+use crate::models::{KiteConnectError, Tick};
+
+#[cfg(feature = "protobuf")]
+pub mod proto;
+
+#[cfg(feature = "protobuf")]
+pub use self::proto::ProtobufCodec;
+
+/// TickCodec encodes and decodes ticks and candles in one format.
+pub trait TickCodec: Send + Sync {
+    /// Short name of the format, e.g. `json`, usable as a file extension.
+    fn name(&self) -> &'static str;
+
+    fn encode_tick(&self, tick: &Tick) -> Result<Vec<u8>, KiteConnectError>;
+
+    fn decode_tick(&self, bytes: &[u8]) -> Result<Tick, KiteConnectError>;
+
+    fn encode_candle(&self, candle: &HistoricalData) -> Result<Vec<u8>, KiteConnectError>;
+
+    fn decode_candle(&self, bytes: &[u8]) -> Result<HistoricalData, KiteConnectError>;
+}
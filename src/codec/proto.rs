@@ -0,0 +1,327 @@
+//! Protocol Buffers messages for ticks and candles.
+//!
+//! The messages follow `proto/ticks.proto` (package `kiteconnect`), so consumers can
+//! generate readers for them in any language. They are written out by hand rather than
+//! generated, which keeps `protoc` out of the build.
+
+use prost::Message;
+
+use super::TickCodec;
+use crate::markets::HistoricalData;
+use crate::models::time::Time;
+use crate::models::{self, KiteConnectError};
+
+/// ProtobufCodec encodes ticks as [`Tick`] and candles as [`Candle`] messages.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ProtobufCodec;
+
+impl TickCodec for ProtobufCodec {
+    fn name(&self) -> &'static str {
+        "protobuf"
+    }
+
+    fn encode_tick(&self, tick: &models::Tick) -> Result<Vec<u8>, KiteConnectError> {
+        Ok(Tick::from(tick).encode_to_vec())
+    }
+
+    fn decode_tick(&self, bytes: &[u8]) -> Result<models::Tick, KiteConnectError> {
+        Ok(Tick::decode(bytes).map_err(decode_error)?.into())
+    }
+
+    fn encode_candle(&self, candle: &HistoricalData) -> Result<Vec<u8>, KiteConnectError> {
+        Ok(Candle::from(candle).encode_to_vec())
+    }
+
+    fn decode_candle(&self, bytes: &[u8]) -> Result<HistoricalData, KiteConnectError> {
+        Ok(Candle::decode(bytes).map_err(decode_error)?.into())
+    }
+}
+
+fn decode_error(e: prost::DecodeError) -> KiteConnectError {
+    KiteConnectError::other(format!("protobuf: {}", e))
+}
+
+// Times travel as Unix seconds, 0 when unset
+pub(crate) fn seconds(time: &Time) -> i64 {
+    time.as_datetime().map_or(0, |dt| dt.timestamp())
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Tick {
+    #[prost(string, tag = "1")]
+    pub mode: String,
+    #[prost(uint32, tag = "2")]
+    pub instrument_token: u32,
+    #[prost(bool, tag = "3")]
+    pub is_tradable: bool,
+    #[prost(bool, tag = "4")]
+    pub is_index: bool,
+    #[prost(int64, tag = "5")]
+    pub timestamp: i64,
+    #[prost(int64, tag = "6")]
+    pub last_trade_time: i64,
+    #[prost(double, tag = "7")]
+    pub last_price: f64,
+    #[prost(uint32, tag = "8")]
+    pub last_traded_quantity: u32,
+    #[prost(uint32, tag = "9")]
+    pub total_buy_quantity: u32,
+    #[prost(uint32, tag = "10")]
+    pub total_sell_quantity: u32,
+    #[prost(uint32, tag = "11")]
+    pub volume_traded: u32,
+    #[prost(uint32, tag = "12")]
+    pub total_buy: u32,
+    #[prost(uint32, tag = "13")]
+    pub total_sell: u32,
+    #[prost(double, tag = "14")]
+    pub average_trade_price: f64,
+    #[prost(uint32, tag = "15")]
+    pub oi: u32,
+    #[prost(uint32, tag = "16")]
+    pub oi_day_high: u32,
+    #[prost(uint32, tag = "17")]
+    pub oi_day_low: u32,
+    #[prost(double, tag = "18")]
+    pub net_change: f64,
+    #[prost(message, optional, tag = "19")]
+    pub ohlc: Option<Ohlc>,
+    #[prost(message, optional, tag = "20")]
+    pub depth: Option<Depth>,
+    #[prost(message, optional, tag = "21")]
+    pub change: Option<TickChange>,
+    #[prost(bool, tag = "22")]
+    pub indicative: bool,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Ohlc {
+    #[prost(uint32, optional, tag = "1")]
+    pub instrument_token: Option<u32>,
+    #[prost(double, tag = "2")]
+    pub open: f64,
+    #[prost(double, tag = "3")]
+    pub high: f64,
+    #[prost(double, tag = "4")]
+    pub low: f64,
+    #[prost(double, tag = "5")]
+    pub close: f64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct DepthItem {
+    #[prost(double, tag = "1")]
+    pub price: f64,
+    #[prost(uint32, tag = "2")]
+    pub quantity: u32,
+    #[prost(uint32, tag = "3")]
+    pub orders: u32,
+}
+
+/// Five entries per side, in the order the ticker sends them.
+#[derive(Clone, PartialEq, Message)]
+pub struct Depth {
+    #[prost(message, repeated, tag = "1")]
+    pub buy: Vec<DepthItem>,
+    #[prost(message, repeated, tag = "2")]
+    pub sell: Vec<DepthItem>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct TickChange {
+    #[prost(sint64, tag = "1")]
+    pub oi_change: i64,
+    #[prost(uint32, tag = "2")]
+    pub volume_delta: u32,
+    #[prost(double, tag = "3")]
+    pub traded_value_delta: f64,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Candle {
+    #[prost(int64, tag = "1")]
+    pub date: i64,
+    #[prost(double, tag = "2")]
+    pub open: f64,
+    #[prost(double, tag = "3")]
+    pub high: f64,
+    #[prost(double, tag = "4")]
+    pub low: f64,
+    #[prost(double, tag = "5")]
+    pub close: f64,
+    #[prost(uint32, tag = "6")]
+    pub volume: u32,
+    #[prost(uint32, tag = "7")]
+    pub oi: u32,
+}
+
+impl From<&models::Tick> for Tick {
+    fn from(tick: &models::Tick) -> Self {
+        Self {
+            mode: tick.mode.clone(),
+            instrument_token: tick.instrument_token,
+            is_tradable: tick.is_tradable,
+            is_index: tick.is_index,
+            timestamp: seconds(&tick.timestamp),
+            last_trade_time: seconds(&tick.last_trade_time),
+            last_price: tick.last_price,
+            last_traded_quantity: tick.last_traded_quantity,
+            total_buy_quantity: tick.total_buy_quantity,
+            total_sell_quantity: tick.total_sell_quantity,
+            volume_traded: tick.volume_traded,
+            total_buy: tick.total_buy,
+            total_sell: tick.total_sell,
+            average_trade_price: tick.average_trade_price,
+            oi: tick.oi,
+            oi_day_high: tick.oi_day_high,
+            oi_day_low: tick.oi_day_low,
+            net_change: tick.net_change,
+            ohlc: Some((&tick.ohlc).into()),
+            // LTP and quote mode ticks carry no depth
+            depth: (tick.depth != models::Depth::default()).then(|| (&tick.depth).into()),
+            change: tick.change.as_ref().map(TickChange::from),
+            indicative: tick.indicative,
+        }
+    }
+}
+
+impl From<Tick> for models::Tick {
+    fn from(tick: Tick) -> Self {
+        Self {
+            mode: tick.mode,
+            instrument_token: tick.instrument_token,
+            is_tradable: tick.is_tradable,
+            is_index: tick.is_index,
+            timestamp: Time::from_timestamp(tick.timestamp),
+            last_trade_time: Time::from_timestamp(tick.last_trade_time),
+            last_price: tick.last_price,
+            last_traded_quantity: tick.last_traded_quantity,
+            total_buy_quantity: tick.total_buy_quantity,
+            total_sell_quantity: tick.total_sell_quantity,
+            volume_traded: tick.volume_traded,
+            total_buy: tick.total_buy,
+            total_sell: tick.total_sell,
+            average_trade_price: tick.average_trade_price,
+            oi: tick.oi,
+            oi_day_high: tick.oi_day_high,
+            oi_day_low: tick.oi_day_low,
+            net_change: tick.net_change,
+            ohlc: tick.ohlc.unwrap_or_default().into(),
+            depth: tick.depth.map(Into::into).unwrap_or_default(),
+            change: tick.change.map(Into::into),
+            indicative: tick.indicative,
+        }
+    }
+}
+
+impl From<&models::OHLC> for Ohlc {
+    fn from(ohlc: &models::OHLC) -> Self {
+        Self {
+            instrument_token: ohlc.instrument_token,
+            open: ohlc.open,
+            high: ohlc.high,
+            low: ohlc.low,
+            close: ohlc.close,
+        }
+    }
+}
+
+impl From<Ohlc> for models::OHLC {
+    fn from(ohlc: Ohlc) -> Self {
+        Self {
+            instrument_token: ohlc.instrument_token,
+            open: ohlc.open,
+            high: ohlc.high,
+            low: ohlc.low,
+            close: ohlc.close,
+        }
+    }
+}
+
+impl From<&models::Depth> for Depth {
+    fn from(depth: &models::Depth) -> Self {
+        let side = |items: &[models::DepthItem]| {
+            items
+                .iter()
+                .map(|item| DepthItem {
+                    price: item.price,
+                    quantity: item.quantity,
+                    orders: item.orders,
+                })
+                .collect()
+        };
+        Self {
+            buy: side(&depth.buy),
+            sell: side(&depth.sell),
+        }
+    }
+}
+
+impl From<Depth> for models::Depth {
+    fn from(depth: Depth) -> Self {
+        // Entries past the fifth are dropped, like the ticker does
+        let side = |items: Vec<DepthItem>| {
+            let mut side = [models::DepthItem::default(); 5];
+            for (slot, item) in side.iter_mut().zip(items) {
+                *slot = models::DepthItem {
+                    price: item.price,
+                    quantity: item.quantity,
+                    orders: item.orders,
+                };
+            }
+            side
+        };
+        Self {
+            buy: side(depth.buy),
+            sell: side(depth.sell),
+        }
+    }
+}
+
+impl From<&models::TickChange> for TickChange {
+    fn from(change: &models::TickChange) -> Self {
+        Self {
+            oi_change: change.oi_change,
+            volume_delta: change.volume_delta,
+            traded_value_delta: change.traded_value_delta,
+        }
+    }
+}
+
+impl From<TickChange> for models::TickChange {
+    fn from(change: TickChange) -> Self {
+        Self {
+            oi_change: change.oi_change,
+            volume_delta: change.volume_delta,
+            traded_value_delta: change.traded_value_delta,
+        }
+    }
+}
+
+impl From<&HistoricalData> for Candle {
+    fn from(candle: &HistoricalData) -> Self {
+        Self {
+            date: seconds(&candle.date),
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            oi: candle.oi,
+        }
+    }
+}
+
+impl From<Candle> for HistoricalData {
+    fn from(candle: Candle) -> Self {
+        Self {
+            date: Time::from_timestamp(candle.date),
+            open: candle.open,
+            high: candle.high,
+            low: candle.low,
+            close: candle.close,
+            volume: candle.volume,
+            oi: candle.oi,
+        }
+    }
+}
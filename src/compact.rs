@@ -0,0 +1,157 @@
+//! Compact JSON forms for charting frontends.
+//!
+//! The regular `Serialize` impls mirror the Kite API and carry every field, including
+//! five levels of depth on each tick. Browser charting libraries need far less, so these
+//! wrappers serialize a pruned form instead:
+//!
+//! - [`CompactCandle`]: `[t, o, h, l, c, v]`, with `t` in Unix seconds
+//! - [`CompactTick`]: an object with short keys, zero and empty fields left out
+//! - [`CompactQuote`]: like [`CompactTick`] plus circuit limits, without depth
+//!
+//! Object keys: `i` instrument token, `t` exchange timestamp (Unix seconds), `p` last
+//! price, `q` last traded quantity, `v` volume, `ap` average traded price, `ch` net change,
+//! `oi` open interest, `bq`/`sq` total buy/sell quantity, `ohlc` `[o, h, l, c]`, `ind`
+//! `true` on indicative pre-open ticks, and for quotes `lc`/`uc` lower/upper circuit
+//! limits.
+//!
+//! Fields can opt in with `serialize_with`:
+//!
+//! ```ignore
+//! #[derive(Serialize)]
+//! struct ChartPayload {
+//!     #[serde(serialize_with = "kiteconnect_rs::compact::candles::serialize")]
+//!     candles: Vec<HistoricalData>,
+//! }
+//! ```
+
+use serde::ser::{SerializeMap, SerializeTuple};
+use serde::{Serialize, Serializer};
+
+use crate::markets::{HistoricalData, QuoteData};
+use crate::models::{OHLC, Tick, time::Time};
+
+/// CompactCandle serializes a candle as `[t, o, h, l, c, v]`.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactCandle<'a>(pub &'a HistoricalData);
+
+impl Serialize for CompactCandle<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let c = self.0;
+        let mut tuple = serializer.serialize_tuple(6)?;
+        tuple.serialize_element(&unix_seconds(&c.date))?;
+        tuple.serialize_element(&c.open)?;
+        tuple.serialize_element(&c.high)?;
+        tuple.serialize_element(&c.low)?;
+        tuple.serialize_element(&c.close)?;
+        tuple.serialize_element(&c.volume)?;
+        tuple.end()
+    }
+}
+
+/// CompactTick serializes a tick as a pruned object with short keys.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactTick<'a>(pub &'a Tick);
+
+impl Serialize for CompactTick<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let t = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("i", &t.instrument_token)?;
+        if let Some(ts) = unix_seconds(&t.timestamp) {
+            map.serialize_entry("t", &ts)?;
+        }
+        map.serialize_entry("p", &t.last_price)?;
+        entry_if_nonzero(&mut map, "q", t.last_traded_quantity)?;
+        entry_if_nonzero(&mut map, "v", t.volume_traded)?;
+        entry_if_nonzero(&mut map, "ap", t.average_trade_price)?;
+        entry_if_nonzero(&mut map, "ch", t.net_change)?;
+        entry_if_nonzero(&mut map, "oi", t.oi)?;
+        entry_if_nonzero(&mut map, "bq", t.total_buy_quantity)?;
+        entry_if_nonzero(&mut map, "sq", t.total_sell_quantity)?;
+        ohlc_entry(&mut map, &t.ohlc)?;
+        entry_if_nonzero(&mut map, "ind", t.indicative)?;
+        map.end()
+    }
+}
+
+/// CompactQuote serializes a quote as a pruned object with short keys and no depth.
+#[derive(Debug, Clone, Copy)]
+pub struct CompactQuote<'a>(pub &'a QuoteData);
+
+impl Serialize for CompactQuote<'_> {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let q = self.0;
+        let mut map = serializer.serialize_map(None)?;
+        map.serialize_entry("i", &q.instrument_token)?;
+        if let Some(ts) = unix_seconds(&q.timestamp) {
+            map.serialize_entry("t", &ts)?;
+        }
+        map.serialize_entry("p", &q.last_price)?;
+        entry_if_nonzero(&mut map, "q", q.last_quantity)?;
+        entry_if_nonzero(&mut map, "v", q.volume)?;
+        entry_if_nonzero(&mut map, "ap", q.average_price)?;
+        entry_if_nonzero(&mut map, "ch", q.net_change)?;
+        entry_if_nonzero(&mut map, "oi", q.oi)?;
+        entry_if_nonzero(&mut map, "bq", q.buy_quantity)?;
+        entry_if_nonzero(&mut map, "sq", q.sell_quantity)?;
+        ohlc_entry(&mut map, &q.ohlc)?;
+        entry_if_nonzero(&mut map, "lc", q.lower_circuit_limit)?;
+        entry_if_nonzero(&mut map, "uc", q.upper_circuit_limit)?;
+        map.end()
+    }
+}
+
+fn unix_seconds(time: &Time) -> Option<i64> {
+    time.as_datetime().map(|dt| dt.timestamp())
+}
+
+fn entry_if_nonzero<M, T>(map: &mut M, key: &str, value: T) -> Result<(), M::Error>
+where
+    M: SerializeMap,
+    T: Serialize + Default + PartialEq,
+{
+    if value != T::default() {
+        map.serialize_entry(key, &value)?;
+    }
+    Ok(())
+}
+
+fn ohlc_entry<M: SerializeMap>(map: &mut M, ohlc: &OHLC) -> Result<(), M::Error> {
+    if ohlc.open != 0.0 || ohlc.high != 0.0 || ohlc.low != 0.0 || ohlc.close != 0.0 {
+        map.serialize_entry("ohlc", &[ohlc.open, ohlc.high, ohlc.low, ohlc.close])?;
+    }
+    Ok(())
+}
+
+/// `serialize_with` helper for candle lists in [`CompactCandle`] form.
+pub mod candles {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        candles: &[HistoricalData],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(candles.iter().map(CompactCandle))
+    }
+}
+
+/// `serialize_with` helper for tick lists in [`CompactTick`] form.
+pub mod ticks {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(ticks: &[Tick], serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(ticks.iter().map(CompactTick))
+    }
+}
+
+/// `serialize_with` helper for quote lists in [`CompactQuote`] form.
+pub mod quotes {
+    use super::*;
+
+    pub fn serialize<S: Serializer>(
+        quotes: &[QuoteData],
+        serializer: S,
+    ) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq(quotes.iter().map(CompactQuote))
+    }
+}
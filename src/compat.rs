@@ -5,18 +5,116 @@
 //! - `spawn`: Task spawning that works on both native (tokio) and WASM (wasm-bindgen-futures)
 //! - `timeout`: Async timeout wrapper
 //! - `WebSocketStream`: WebSocket abstraction over tokio-tungstenite (native) and gloo-net (WASM)
+//!
+//! On native targets all of these go through a [`Runtime`], tokio unless another one was
+//! installed with [`set_runtime`].
 
 use async_trait::async_trait;
 use std::future::Future;
+#[cfg(not(target_arch = "wasm32"))]
+use std::pin::Pin;
+#[cfg(not(target_arch = "wasm32"))]
+use std::sync::OnceLock;
 use web_time::Duration;
 
+// ============================================================================
+// Runtime
+// ============================================================================
+
+/// Boxed future taken and returned by [`Runtime`].
+#[cfg(not(target_arch = "wasm32"))]
+pub type BoxFuture<'a, T> = Pin<Box<dyn Future<Output = T> + Send + 'a>>;
+
+/// Runtime supplies task spawning, timers and WebSocket connections on native targets.
+///
+/// The ticker, watchers and writers use it for their background tasks. Without one
+/// installed everything runs on tokio ([`TokioRuntime`]); users on async-std, smol or
+/// another executor can implement this trait and install it with [`set_runtime`]. HTTP
+/// calls still go through reqwest, which needs a tokio reactor, e.g. via `async-compat`.
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+pub trait Runtime: Send + Sync + 'static {
+    /// Run `future` in the background.
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> TaskHandle;
+
+    async fn sleep(&self, duration: Duration);
+
+    /// Open a WebSocket connection for the ticker.
+    async fn connect_ws(
+        &self,
+        url: &str,
+        options: &WsConnectOptions,
+    ) -> Result<Box<dyn WebSocketStream>, WsError>;
+}
+
+/// The default runtime: tokio tasks and timers, tokio-tungstenite WebSockets.
+#[cfg(not(target_arch = "wasm32"))]
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TokioRuntime;
+
+#[cfg(not(target_arch = "wasm32"))]
+#[async_trait]
+impl Runtime for TokioRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> TaskHandle {
+        TaskHandle {
+            inner: Some(TaskHandleInner::Native(tokio::spawn(future))),
+        }
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        tokio::time::sleep(duration).await;
+    }
+
+    async fn connect_ws(
+        &self,
+        url: &str,
+        options: &WsConnectOptions,
+    ) -> Result<Box<dyn WebSocketStream>, WsError> {
+        let ws = native_ws::NativeWebSocket::connect(url, options).await?;
+        Ok(Box::new(ws))
+    }
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+static RUNTIME: OnceLock<Box<dyn Runtime>> = OnceLock::new();
+
+/// Install the runtime used for the rest of the process.
+///
+/// Call it before starting a ticker or any background task. Fails if a runtime was
+/// already installed.
+#[cfg(not(target_arch = "wasm32"))]
+pub fn set_runtime<R: Runtime>(runtime: R) -> Result<(), RuntimeAlreadySet> {
+    RUNTIME
+        .set(Box::new(runtime))
+        .map_err(|_| RuntimeAlreadySet)
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+fn runtime() -> Option<&'static dyn Runtime> {
+    RUNTIME.get().map(|runtime| runtime.as_ref())
+}
+
+#[derive(Debug, Clone)]
+pub struct RuntimeAlreadySet;
+
+impl std::fmt::Display for RuntimeAlreadySet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "a runtime is already installed")
+    }
+}
+
+impl std::error::Error for RuntimeAlreadySet {}
+
 // ============================================================================
 // Sleep
 // ============================================================================
 
 #[cfg(not(target_arch = "wasm32"))]
 pub async fn sleep(duration: Duration) {
-    tokio::time::sleep(duration).await;
+    match runtime() {
+        Some(runtime) => runtime.sleep(duration).await,
+        None => tokio::time::sleep(duration).await,
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
@@ -33,6 +131,9 @@ pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, TimeoutEr
 where
     F: Future<Output = T>,
 {
+    if runtime().is_some() {
+        return select_timeout(duration, future).await;
+    }
     tokio::time::timeout(duration, future)
         .await
         .map_err(|_| TimeoutError)
@@ -40,6 +141,14 @@ where
 
 #[cfg(target_arch = "wasm32")]
 pub async fn timeout<F, T>(duration: Duration, future: F) -> Result<T, TimeoutError>
+where
+    F: Future<Output = T>,
+{
+    select_timeout(duration, future).await
+}
+
+// Race `future` against `sleep`, for runtimes without a native timeout
+async fn select_timeout<F, T>(duration: Duration, future: F) -> Result<T, TimeoutError>
 where
     F: Future<Output = T>,
 {
@@ -75,9 +184,11 @@ pub fn spawn<F>(future: F) -> TaskHandle
 where
     F: Future<Output = ()> + Send + 'static,
 {
-    let handle = tokio::spawn(future);
-    TaskHandle {
-        inner: Some(TaskHandleInner::Native(handle)),
+    match runtime() {
+        Some(runtime) => runtime.spawn(Box::pin(future)),
+        None => TaskHandle {
+            inner: Some(TaskHandleInner::Native(tokio::spawn(future))),
+        },
     }
 }
 
@@ -86,30 +197,52 @@ pub fn spawn<F>(future: F) -> TaskHandle
 where
     F: Future<Output = ()> + 'static,
 {
-    wasm_bindgen_futures::spawn_local(future);
-    TaskHandle { inner: None }
+    let (future, abort) = futures_util::future::abortable(future);
+    wasm_bindgen_futures::spawn_local(async move {
+        let _ = future.await;
+    });
+    TaskHandle { inner: Some(abort) }
 }
 
 pub struct TaskHandle {
     #[cfg(not(target_arch = "wasm32"))]
     inner: Option<TaskHandleInner>,
     #[cfg(target_arch = "wasm32")]
-    #[allow(dead_code)]
-    inner: Option<()>,
+    inner: Option<futures_util::future::AbortHandle>,
 }
 
 #[cfg(not(target_arch = "wasm32"))]
 enum TaskHandleInner {
     Native(tokio::task::JoinHandle<()>),
+    Custom(Box<dyn Fn() + Send + Sync>),
 }
 
 impl TaskHandle {
+    /// Handle for a task spawned by a custom [`Runtime`]; `abort` calls `abort`.
+    #[cfg(not(target_arch = "wasm32"))]
+    pub fn new(abort: impl Fn() + Send + Sync + 'static) -> Self {
+        Self {
+            inner: Some(TaskHandleInner::Custom(Box::new(abort))),
+        }
+    }
+
+    /// Handle for a task that cannot be aborted.
+    pub fn detached() -> Self {
+        Self { inner: None }
+    }
+
     pub fn abort(&self) {
         #[cfg(not(target_arch = "wasm32"))]
-        if let Some(TaskHandleInner::Native(ref handle)) = self.inner {
+        match self.inner {
+            Some(TaskHandleInner::Native(ref handle)) => handle.abort(),
+            Some(TaskHandleInner::Custom(ref abort)) => abort(),
+            None => {}
+        }
+        // WASM: spawn_local tasks are wrapped in `Abortable`
+        #[cfg(target_arch = "wasm32")]
+        if let Some(ref handle) = self.inner {
             handle.abort();
         }
-        // WASM: spawn_local tasks cannot be aborted, this is a no-op
     }
 }
 
@@ -118,16 +251,71 @@ impl TaskHandle {
 // ============================================================================
 
 #[derive(Debug, Clone)]
-pub struct WsError(pub String);
+pub struct WsError {
+    pub message: String,
+    /// HTTP status the server refused the handshake with, when it answered one.
+    pub status: Option<u16>,
+}
+
+impl WsError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: None,
+        }
+    }
+
+    /// A handshake the server refused with HTTP `status`.
+    pub fn handshake(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            status: Some(status),
+        }
+    }
+}
 
 impl std::fmt::Display for WsError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(f, "WebSocket error: {}", self.0)
+        write!(f, "WebSocket error: {}", self.message)
     }
 }
 
 impl std::error::Error for WsError {}
 
+/// Options applied when opening a WebSocket connection.
+#[derive(Debug, Clone, Default)]
+pub struct WsConnectOptions {
+    /// TLS settings for `wss://` connections. Ignored on WASM, where the browser owns TLS.
+    pub tls: TlsOptions,
+}
+
+/// TLS settings for the native ticker connection.
+///
+/// The native stack always uses rustls and connects directly, without consulting
+/// `HTTP(S)_PROXY`, so no proxy setting is needed for locked-down hosts.
+#[derive(Debug, Clone)]
+pub struct TlsOptions {
+    /// Extra trusted root certificates in DER form, e.g. a pinned corporate CA.
+    pub root_certificates: Vec<Vec<u8>>,
+    /// Trust the platform's certificate store in addition to `root_certificates`.
+    pub use_system_roots: bool,
+}
+
+impl Default for TlsOptions {
+    fn default() -> Self {
+        Self {
+            root_certificates: Vec::new(),
+            use_system_roots: true,
+        }
+    }
+}
+
+impl TlsOptions {
+    fn is_default(&self) -> bool {
+        self.root_certificates.is_empty() && self.use_system_roots
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum WsMessage {
     Text(String),
@@ -162,9 +350,11 @@ mod native_ws {
     use super::*;
     use futures_util::{SinkExt, StreamExt};
     use tokio::net::TcpStream;
+    use std::sync::Arc;
     use tokio_tungstenite::{
-        connect_async, tungstenite::Message, MaybeTlsStream,
-        WebSocketStream as TungsteniteWs,
+        Connector, MaybeTlsStream, WebSocketStream as TungsteniteWs, connect_async,
+        connect_async_tls_with_config,
+        tungstenite::{Error, Message},
     };
 
     pub struct NativeWebSocket {
@@ -172,28 +362,63 @@ mod native_ws {
     }
 
     impl NativeWebSocket {
-        pub async fn connect(url: &str) -> Result<Self, WsError> {
-            let (ws_stream, _) = connect_async(url)
-                .await
-                .map_err(|e| WsError(e.to_string()))?;
+        pub async fn connect(url: &str, options: &WsConnectOptions) -> Result<Self, WsError> {
+            let connected = if options.tls.is_default() {
+                connect_async(url).await
+            } else {
+                let connector = Connector::Rustls(Arc::new(rustls_config(&options.tls)?));
+                connect_async_tls_with_config(url, None, false, Some(connector)).await
+            };
+            let (ws_stream, _) = connected.map_err(|e| match &e {
+                Error::Http(response) => {
+                    WsError::handshake(response.status().as_u16(), e.to_string())
+                }
+                _ => WsError::new(e.to_string()),
+            })?;
             Ok(Self { inner: ws_stream })
         }
     }
 
+    fn rustls_config(tls: &TlsOptions) -> Result<rustls::ClientConfig, WsError> {
+        let mut roots = rustls::RootCertStore::empty();
+        if tls.use_system_roots {
+            let native = rustls_native_certs::load_native_certs();
+            for err in native.errors {
+                log::warn!("failed to load a system root certificate: {}", err);
+            }
+            roots.add_parsable_certificates(native.certs);
+        }
+        for der in &tls.root_certificates {
+            roots
+                .add(der.clone().into())
+                .map_err(|e| WsError::new(format!("invalid root certificate: {}", e)))?;
+        }
+        if roots.is_empty() {
+            return Err(WsError::new("no trusted root certificates configured"));
+        }
+
+        let provider = Arc::new(rustls::crypto::ring::default_provider());
+        Ok(rustls::ClientConfig::builder_with_provider(provider)
+            .with_safe_default_protocol_versions()
+            .map_err(|e| WsError::new(e.to_string()))?
+            .with_root_certificates(roots)
+            .with_no_client_auth())
+    }
+
     #[async_trait]
     impl WebSocketStream for NativeWebSocket {
         async fn send_text(&mut self, msg: String) -> Result<(), WsError> {
             self.inner
                 .send(Message::Text(msg.into()))
                 .await
-                .map_err(|e| WsError(e.to_string()))
+                .map_err(|e| WsError::new(e.to_string()))
         }
 
         async fn send_binary(&mut self, msg: Vec<u8>) -> Result<(), WsError> {
             self.inner
                 .send(Message::Binary(msg.into()))
                 .await
-                .map_err(|e| WsError(e.to_string()))
+                .map_err(|e| WsError::new(e.to_string()))
         }
 
         async fn recv(&mut self) -> Option<Result<WsMessage, WsError>> {
@@ -212,7 +437,7 @@ mod native_ws {
                     // Skip raw frames, get next message
                     Box::pin(self.recv()).await
                 }
-                Some(Err(e)) => Some(Err(WsError(e.to_string()))),
+                Some(Err(e)) => Some(Err(WsError::new(e.to_string()))),
                 None => None,
             }
         }
@@ -221,7 +446,7 @@ mod native_ws {
             self.inner
                 .close(None)
                 .await
-                .map_err(|e| WsError(e.to_string()))
+                .map_err(|e| WsError::new(e.to_string()))
         }
     }
 }
@@ -234,16 +459,30 @@ mod native_ws {
 mod wasm_ws {
     use super::*;
     use futures_util::{SinkExt, StreamExt};
-    use gloo_net::websocket::{futures::WebSocket, Message};
+    use gloo_net::websocket::{Message, State, WebSocketError, futures::WebSocket};
+
+    // How often the handshake state is checked while connecting
+    const OPEN_POLL_INTERVAL: Duration = Duration::from_millis(10);
 
     pub struct WasmWebSocket {
         inner: Option<WebSocket>,
     }
 
     impl WasmWebSocket {
-        pub fn connect(url: &str) -> Result<Self, WsError> {
-            let ws = WebSocket::open(url).map_err(|e| WsError(e.to_string()))?;
-            Ok(Self { inner: Some(ws) })
+        pub async fn connect(url: &str, _options: &WsConnectOptions) -> Result<Self, WsError> {
+            // Browsers negotiate permessage-deflate themselves.
+            let ws = WebSocket::open(url).map_err(|e| WsError::new(e.to_string()))?;
+            // `open` returns before the handshake; wait for it so the ticker's connect
+            // timeout and failure handling behave as on native
+            loop {
+                match ws.state() {
+                    State::Connecting => sleep(OPEN_POLL_INTERVAL).await,
+                    State::Open => return Ok(Self { inner: Some(ws) }),
+                    State::Closing | State::Closed => {
+                        return Err(WsError::new("WebSocket connection failed"));
+                    }
+                }
+            }
         }
     }
 
@@ -253,9 +492,9 @@ mod wasm_ws {
             if let Some(ref mut ws) = self.inner {
                 ws.send(Message::Text(msg))
                     .await
-                    .map_err(|e| WsError(e.to_string()))
+                    .map_err(|e| WsError::new(e.to_string()))
             } else {
-                Err(WsError("WebSocket is closed".to_string()))
+                Err(WsError::new("WebSocket is closed"))
             }
         }
 
@@ -263,9 +502,9 @@ mod wasm_ws {
             if let Some(ref mut ws) = self.inner {
                 ws.send(Message::Bytes(msg))
                     .await
-                    .map_err(|e| WsError(e.to_string()))
+                    .map_err(|e| WsError::new(e.to_string()))
             } else {
-                Err(WsError("WebSocket is closed".to_string()))
+                Err(WsError::new("WebSocket is closed"))
             }
         }
 
@@ -274,7 +513,11 @@ mod wasm_ws {
                 match ws.next().await {
                     Some(Ok(Message::Text(text))) => Some(Ok(WsMessage::Text(text))),
                     Some(Ok(Message::Bytes(data))) => Some(Ok(WsMessage::Binary(data))),
-                    Some(Err(e)) => Some(Err(WsError(e.to_string()))),
+                    // gloo reports the close frame as an error
+                    Some(Err(WebSocketError::ConnectionClose(event))) => {
+                        Some(Ok(WsMessage::Close(Some((event.code, event.reason)))))
+                    }
+                    Some(Err(e)) => Some(Err(WsError::new(e.to_string()))),
                     None => None,
                 }
             } else {
@@ -285,7 +528,7 @@ mod wasm_ws {
         async fn close(&mut self) -> Result<(), WsError> {
             if let Some(ws) = self.inner.take() {
                 ws.close(None, None)
-                    .map_err(|e| WsError(format!("{:?}", e)))
+                    .map_err(|e| WsError::new(format!("{:?}", e)))
             } else {
                 Ok(())
             }
@@ -298,13 +541,21 @@ mod wasm_ws {
 // ============================================================================
 
 #[cfg(not(target_arch = "wasm32"))]
-pub async fn connect_ws(url: &str) -> Result<Box<dyn WebSocketStream>, WsError> {
-    let ws = native_ws::NativeWebSocket::connect(url).await?;
-    Ok(Box::new(ws))
+pub async fn connect_ws(
+    url: &str,
+    options: &WsConnectOptions,
+) -> Result<Box<dyn WebSocketStream>, WsError> {
+    match runtime() {
+        Some(runtime) => runtime.connect_ws(url, options).await,
+        None => TokioRuntime.connect_ws(url, options).await,
+    }
 }
 
 #[cfg(target_arch = "wasm32")]
-pub async fn connect_ws(url: &str) -> Result<Box<dyn WebSocketStream>, WsError> {
-    let ws = wasm_ws::WasmWebSocket::connect(url)?;
+pub async fn connect_ws(
+    url: &str,
+    options: &WsConnectOptions,
+) -> Result<Box<dyn WebSocketStream>, WsError> {
+    let ws = wasm_ws::WasmWebSocket::connect(url, options).await?;
     Ok(Box::new(ws))
 }
@@ -1,12 +1,108 @@
+use crate::audit::AuditLog;
+use crate::capabilities::Capabilities;
 use crate::constants::{Endpoints, app_constants::*};
+use crate::freeze::FreezeQuantities;
+use crate::instruments::OrderRounding;
+use crate::models::{ConfigError, KiteConnectError, KiteError};
+use crate::risk::{DailyLossLimiter, RiskLimits};
+use crate::session::TokenProvider;
+use crate::tags::TagRegistry;
+use crate::transport::Transport;
+use crate::usage::UsagePool;
+use crate::validation::OrderValidator;
+use crate::version::{ApiVersion, ApiVersions};
 use reqwest::Client;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use url::{Url, form_urlencoded};
 use web_time::Duration;
+use zeroize::Zeroizing;
 
+/// Query parameter used to round-trip a caller supplied state through the login redirect.
+pub const LOGIN_STATE_PARAM: &str = "state";
+
+/// LoginCallback holds the parameters Kite appends to the redirect URL after login.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LoginCallback {
+    pub request_token: String,
+    pub status: Option<String>,
+    pub action: Option<String>,
+    pub state: Option<String>,
+    /// Any other parameters, including custom `redirect_params`.
+    pub params: HashMap<String, String>,
+}
+
+impl LoginCallback {
+    /// Parse the redirect URL (or just its query string) that Kite sent the user back to.
+    pub fn parse(callback: &str) -> Result<Self, KiteConnectError> {
+        let query = match Url::parse(callback) {
+            Ok(url) => url.query().unwrap_or_default().to_string(),
+            Err(_) => callback.trim_start_matches('?').to_string(),
+        };
+
+        let mut params: HashMap<String, String> = form_urlencoded::parse(query.as_bytes())
+            .into_owned()
+            .collect();
+
+        let request_token = params
+            .remove("request_token")
+            .filter(|token| !token.is_empty())
+            .ok_or_else(|| KiteConnectError::other("callback URL has no request_token"))?;
+
+        if let Some(status) = params.get("status") {
+            if status != "success" {
+                return Err(KiteConnectError::other(format!(
+                    "login was not successful, status: {}",
+                    status
+                )));
+            }
+        }
+
+        Ok(Self {
+            request_token,
+            status: params.remove("status"),
+            action: params.remove("action"),
+            state: params.remove(LOGIN_STATE_PARAM),
+            params,
+        })
+    }
+}
+
+/// Maximum length of an order tag accepted by Kite.
+pub const MAX_TAG_LENGTH: usize = 20;
+
+/// KiteConnect is the API client.
+///
+/// Cloning is cheap: clones share the HTTP client, the access token, the audit log, the
+/// daily loss limiter, the kill switch state, the known capabilities and the usage pool, so a token renewed through
+/// one clone is used by all of them. The `with_*` methods override settings on a single clone, e.g. to give each
+/// strategy in a process its own order tag prefix and timeout.
+#[derive(Clone)]
 pub struct KiteConnect {
     pub(crate) api_key: String,
     pub(crate) base_url: String,
     pub(crate) http_client: Client,
-    pub(crate) access_token: Option<String>,
+    pub(crate) access_token: Arc<RwLock<Option<Zeroizing<String>>>>,
+    // Set when Kite rejects the access token, cleared by the next successful request
+    pub(crate) token_rejected: Arc<AtomicBool>,
+    pub(crate) risk_limits: Option<RiskLimits>,
+    pub(crate) loss_limiter: Option<DailyLossLimiter>,
+    pub(crate) order_rounding: Option<OrderRounding>,
+    pub(crate) freeze_quantities: Option<FreezeQuantities>,
+    pub(crate) order_validator: Option<OrderValidator>,
+    pub(crate) tag_registry: Option<TagRegistry>,
+    pub(crate) trading_block: Arc<Mutex<Option<KiteError>>>,
+    pub(crate) audit_log: Option<Arc<dyn AuditLog>>,
+    pub(crate) usage: UsagePool,
+    pub(crate) tag_prefix: Option<String>,
+    pub(crate) request_timeout: Option<Duration>,
+    pub(crate) transport: Option<Arc<dyn Transport>>,
+    pub(crate) token_provider: Option<Arc<dyn TokenProvider>>,
+    pub(crate) max_response_size: Option<usize>,
+    pub(crate) api_versions: ApiVersions,
+    pub(crate) read_only: bool,
+    pub(crate) capabilities: Arc<RwLock<Option<Capabilities>>>,
 }
 
 impl KiteConnect {
@@ -20,22 +116,176 @@ impl KiteConnect {
             KITE_BASE_URL,
             Endpoints::LOGIN_URL,
             self.api_key,
-            KITE_HEADER_VERSION
+            self.api_version()
         )
     }
 
+    /// Login URL with extra `redirect_params` that Kite passes back on the redirect URL.
+    pub fn get_login_url_with_params(&self, redirect_params: &[(&str, &str)]) -> String {
+        let mut url = self.get_login_url();
+        if redirect_params.is_empty() {
+            return url;
+        }
+
+        let encoded = form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(redirect_params)
+            .finish();
+        let wrapped = form_urlencoded::Serializer::new(String::new())
+            .append_pair("redirect_params", &encoded)
+            .finish();
+
+        url.push('&');
+        url.push_str(&wrapped);
+        url
+    }
+
+    /// Login URL carrying a caller supplied state/nonce, returned as `state` in the callback.
+    pub fn get_login_url_with_state(&self, state: &str) -> String {
+        self.get_login_url_with_params(&[(LOGIN_STATE_PARAM, state)])
+    }
+
     pub fn set_access_token(&mut self, token: &str) {
-        self.access_token = Some(token.to_owned());
+        *self.access_token.write().unwrap_or_else(|e| e.into_inner()) =
+            Some(Zeroizing::new(token.to_owned()));
+        self.token_rejected.store(false, Ordering::Relaxed);
     }
 
     pub fn clear_access_token(&mut self) {
-        self.access_token = None;
+        *self.access_token.write().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    /// Whether the client has an access token, or a provider for one, that Kite has not
+    /// rejected. Sending no request, it only knows of rejections seen so far.
+    pub fn is_authenticated(&self) -> bool {
+        (self.token_provider.is_some() || self.access_token().is_some())
+            && !self.token_rejected.load(Ordering::Relaxed)
+    }
+
+    pub(crate) fn access_token(&self) -> Option<Zeroizing<String>> {
+        self.access_token
+            .read()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Prefix every order tag placed through this clone with `prefix`.
+    ///
+    /// Orders without a tag get the prefix as their tag. The combined tag is cut to
+    /// [`MAX_TAG_LENGTH`] characters.
+    pub fn with_tag_prefix(mut self, prefix: &str) -> Self {
+        self.tag_prefix = Some(prefix.to_owned());
+        self
+    }
+
+    /// Override the request timeout for this clone. Ignored on WASM.
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.request_timeout = Some(timeout);
+        self
+    }
+
+    /// Count this clone's requests in `pool` instead of the pool it was cloned with.
+    pub fn with_usage_pool(mut self, pool: UsagePool) -> Self {
+        self.usage = pool;
+        self
+    }
+
+    pub fn tag_prefix(&self) -> Option<&str> {
+        self.tag_prefix.as_deref()
+    }
+
+    pub fn usage_pool(&self) -> &UsagePool {
+        &self.usage
+    }
+
+    /// Whether the client was built read-only. There is no setter, so a read-only client
+    /// and its clones stay read-only.
+    pub fn is_read_only(&self) -> bool {
+        self.read_only
+    }
+
+    /// Apply the tag prefix of this clone to an order tag.
+    pub(crate) fn prefixed_tag(&self, tag: Option<String>) -> Option<String> {
+        let Some(prefix) = &self.tag_prefix else {
+            return tag;
+        };
+        let tag = match tag {
+            Some(tag) if tag.starts_with(prefix.as_str()) => tag,
+            Some(tag) => format!("{}{}", prefix, tag),
+            None => prefix.clone(),
+        };
+        Some(tag.chars().take(MAX_TAG_LENGTH).collect())
+    }
+
+    /// Set or clear the client-side risk limits enforced on order placement.
+    pub fn set_risk_limits(&mut self, limits: Option<RiskLimits>) {
+        self.risk_limits = limits;
+    }
+
+    pub fn risk_limits(&self) -> Option<&RiskLimits> {
+        self.risk_limits.as_ref()
+    }
+
+    /// Set or clear the daily loss limiter that blocks orders after a drawdown.
+    pub fn set_daily_loss_limiter(&mut self, limiter: Option<DailyLossLimiter>) {
+        self.loss_limiter = limiter;
+    }
+
+    pub fn daily_loss_limiter(&self) -> Option<&DailyLossLimiter> {
+        self.loss_limiter.as_ref()
+    }
+
+    /// Set or clear the rounding applied to orders before they are placed.
+    pub fn set_order_rounding(&mut self, rounding: Option<OrderRounding>) {
+        self.order_rounding = rounding;
+    }
+
+    pub fn order_rounding(&self) -> Option<&OrderRounding> {
+        self.order_rounding.as_ref()
+    }
+
+    /// Set or clear the freeze quantities at which `place_order` splits F&O orders.
+    pub fn set_freeze_quantities(&mut self, quantities: Option<FreezeQuantities>) {
+        self.freeze_quantities = quantities;
+    }
+
+    pub fn freeze_quantities(&self) -> Option<&FreezeQuantities> {
+        self.freeze_quantities.as_ref()
+    }
+
+    /// Set or clear the registry that the tags of new orders are checked against.
+    pub fn set_tag_registry(&mut self, registry: Option<TagRegistry>) {
+        self.tag_registry = registry;
+    }
+
+    pub fn tag_registry(&self) -> Option<&TagRegistry> {
+        self.tag_registry.as_ref()
+    }
+
+    /// Set or clear the audit log that records order requests and updates.
+    pub fn set_audit_log(&mut self, log: Option<Arc<dyn AuditLog>>) {
+        self.audit_log = log;
+    }
+
+    /// Set or clear the transport requests are sent through. Without one, requests go
+    /// out through the client's `reqwest::Client`.
+    pub fn set_transport(&mut self, transport: Option<Arc<dyn Transport>>) {
+        self.transport = transport;
+    }
+
+    /// Set or clear the largest response body, in bytes, the client accepts. Longer
+    /// responses fail with `ResponseTooLarge`. `stream_instruments` is not limited.
+    pub fn set_max_response_size(&mut self, bytes: Option<usize>) {
+        self.max_response_size = bytes;
+    }
+
+    pub fn max_response_size(&self) -> Option<usize> {
+        self.max_response_size
     }
 
     /// Get the current access token (for testing purposes)
     #[cfg(test)]
-    pub fn get_access_token(&self) -> Option<&String> {
-        self.access_token.as_ref()
+    pub fn get_access_token(&self) -> Option<String> {
+        self.access_token().map(|token| token.to_string())
     }
 
     /// Get the API key (for testing purposes)
@@ -45,12 +295,51 @@ impl KiteConnect {
     }
 }
 
+/// Shown in place of credentials in Debug output.
+pub(crate) const REDACTED: &str = "REDACTED";
+
+// The API key as Debug output shows it. It identifies the app rather than granting
+// access, so it is only hidden with the `redact` feature; the access token never shows.
+pub(crate) fn debug_api_key(api_key: &str) -> &str {
+    if cfg!(feature = "redact") {
+        REDACTED
+    } else {
+        api_key
+    }
+}
+
+impl std::fmt::Debug for KiteConnect {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KiteConnect")
+            .field("api_key", &debug_api_key(&self.api_key))
+            .field("access_token", &self.access_token().map(|_| REDACTED))
+            .field("base_url", &self.base_url)
+            .field("api_version", &self.api_version())
+            .field("read_only", &self.read_only)
+            .field("tag_prefix", &self.tag_prefix)
+            .field("request_timeout", &self.request_timeout)
+            .field("max_response_size", &self.max_response_size)
+            .finish_non_exhaustive()
+    }
+}
+
 pub struct KiteConnectBuilder {
     api_key: String,
-    access_token: Option<String>,
+    access_token: Option<Zeroizing<String>>,
     base_url: Option<String>,
     http_client: Option<Client>,
     timeout: Option<Duration>,
+    risk_limits: Option<RiskLimits>,
+    loss_limiter: Option<DailyLossLimiter>,
+    order_rounding: Option<OrderRounding>,
+    freeze_quantities: Option<FreezeQuantities>,
+    order_validator: Option<OrderValidator>,
+    tag_registry: Option<TagRegistry>,
+    audit_log: Option<Arc<dyn AuditLog>>,
+    transport: Option<Arc<dyn Transport>>,
+    max_response_size: Option<usize>,
+    api_versions: ApiVersions,
+    read_only: bool,
 }
 
 impl KiteConnectBuilder {
@@ -61,11 +350,22 @@ impl KiteConnectBuilder {
             base_url: None,
             http_client: None,
             timeout: None,
+            risk_limits: None,
+            loss_limiter: None,
+            order_rounding: None,
+            freeze_quantities: None,
+            order_validator: None,
+            tag_registry: None,
+            audit_log: None,
+            transport: None,
+            max_response_size: None,
+            api_versions: ApiVersions::default(),
+            read_only: false,
         }
     }
 
     pub fn access_token(mut self, token: &str) -> Self {
-        self.access_token = Some(token.to_owned());
+        self.access_token = Some(Zeroizing::new(token.to_owned()));
         self
     }
 
@@ -84,7 +384,90 @@ impl KiteConnectBuilder {
         self
     }
 
-    pub fn build(self) -> Result<KiteConnect, reqwest::Error> {
+    pub fn risk_limits(mut self, limits: RiskLimits) -> Self {
+        self.risk_limits = Some(limits);
+        self
+    }
+
+    pub fn daily_loss_limiter(mut self, limiter: DailyLossLimiter) -> Self {
+        self.loss_limiter = Some(limiter);
+        self
+    }
+
+    pub fn order_rounding(mut self, rounding: OrderRounding) -> Self {
+        self.order_rounding = Some(rounding);
+        self
+    }
+
+    pub fn freeze_quantities(mut self, quantities: FreezeQuantities) -> Self {
+        self.freeze_quantities = Some(quantities);
+        self
+    }
+
+    pub fn order_validator(mut self, validator: OrderValidator) -> Self {
+        self.order_validator = Some(validator);
+        self
+    }
+
+    /// Refuse new orders whose tag `registry` did not generate, and remember the orders
+    /// placed with its tags so their trades resolve. Untagged orders are let through.
+    pub fn tag_registry(mut self, registry: TagRegistry) -> Self {
+        self.tag_registry = Some(registry);
+        self
+    }
+
+    pub fn audit_log<L: AuditLog + 'static>(mut self, log: L) -> Self {
+        self.audit_log = Some(Arc::new(log));
+        self
+    }
+
+    pub fn transport<T: Transport + 'static>(mut self, transport: T) -> Self {
+        self.transport = Some(Arc::new(transport));
+        self
+    }
+
+    pub fn max_response_size(mut self, bytes: usize) -> Self {
+        self.max_response_size = Some(bytes);
+        self
+    }
+
+    pub fn api_version(mut self, version: ApiVersion) -> Self {
+        self.api_versions.default = version;
+        self
+    }
+
+    pub fn endpoint_api_version(mut self, prefix: &str, version: ApiVersion) -> Self {
+        self.api_versions.set_override(prefix, Some(version));
+        self
+    }
+
+    /// Refuse every request that places, modifies or cancels orders, GTTs, alerts or MF
+    /// orders and SIPs, or converts positions, with a `ReadOnlyMode` error before it is
+    /// sent. Meant for analytics services sharing credentials with a trading one.
+    pub fn read_only(mut self, read_only: bool) -> Self {
+        self.read_only = read_only;
+        self
+    }
+
+    pub fn build(self) -> Result<KiteConnect, KiteConnectError> {
+        validate_api_key(&self.api_key)?;
+
+        if let Some(ref token) = self.access_token {
+            if token.trim().is_empty() {
+                return Err(ConfigError::EmptyAccessToken.into());
+            }
+        }
+
+        if let Some(ref url) = self.base_url {
+            validate_base_url(url)?;
+        }
+
+        if let Some(timeout) = self.timeout {
+            if timeout < Duration::from_millis(1) || timeout > MAX_TIMEOUT {
+                return Err(ConfigError::InvalidTimeout(timeout).into());
+            }
+        }
+
         let http_client = match self.http_client {
             None => {
                 #[cfg(not(target_arch = "wasm32"))]
@@ -102,11 +485,69 @@ impl KiteConnectBuilder {
         };
         Ok(KiteConnect {
             api_key: self.api_key,
-            access_token: self.access_token,
+            access_token: Arc::new(RwLock::new(self.access_token)),
             base_url: self
                 .base_url
                 .unwrap_or_else(|| DEFAULT_BASE_URL.to_string()),
             http_client,
+            token_rejected: Arc::new(AtomicBool::new(false)),
+            risk_limits: self.risk_limits,
+            loss_limiter: self.loss_limiter,
+            order_rounding: self.order_rounding,
+            freeze_quantities: self.freeze_quantities,
+            order_validator: self.order_validator,
+            tag_registry: self.tag_registry,
+            trading_block: Arc::new(Mutex::new(None)),
+            audit_log: self.audit_log,
+            usage: UsagePool::new(),
+            tag_prefix: None,
+            request_timeout: None,
+            transport: self.transport,
+            token_provider: None,
+            max_response_size: self.max_response_size,
+            api_versions: self.api_versions,
+            read_only: self.read_only,
+            capabilities: Arc::new(RwLock::new(None)),
         })
     }
 }
+
+impl std::fmt::Debug for KiteConnectBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("KiteConnectBuilder")
+            .field("api_key", &debug_api_key(&self.api_key))
+            .field(
+                "access_token",
+                &self.access_token.as_ref().map(|_| REDACTED),
+            )
+            .field("base_url", &self.base_url)
+            .field("timeout", &self.timeout)
+            .field("max_response_size", &self.max_response_size)
+            .field("read_only", &self.read_only)
+            .finish_non_exhaustive()
+    }
+}
+
+/// Checks that the api_key is non-empty and safe to send in a header or query string.
+pub(crate) fn validate_api_key(api_key: &str) -> Result<(), ConfigError> {
+    if api_key.is_empty() {
+        return Err(ConfigError::EmptyApiKey);
+    }
+    if api_key.chars().any(|c| c.is_whitespace() || c.is_control()) {
+        return Err(ConfigError::InvalidApiKey(
+            "must not contain whitespace or control characters".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+fn validate_base_url(base_url: &str) -> Result<(), ConfigError> {
+    let url = Url::parse(base_url).map_err(|e| ConfigError::InvalidBaseUrl(e.to_string()))?;
+    match url.scheme() {
+        "http" | "https" => Ok(()),
+        scheme => Err(ConfigError::InvalidBaseUrl(format!(
+            "unsupported scheme '{}', expected http or https",
+            scheme
+        ))),
+    }
+}
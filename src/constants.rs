@@ -1,3 +1,8 @@
+use crate::labels::{
+    Exchange, HoldingsAuthType, HoldingsTransferType, OrderType, Product, TransactionType, Validity,
+    Variety,
+};
+
 pub mod app_constants {
     use web_time::Duration;
 
@@ -5,6 +10,7 @@ pub mod app_constants {
     pub const KITE_BASE_URL: &str = "https://kite.zerodha.com";
 
     pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(7);
+    pub const MAX_TIMEOUT: Duration = Duration::from_secs(300);
 
     pub const KITE_HEADER_VERSION: &str = "3";
     pub const KITE_CONNECT_RS_NAME: &str = "kiteconnect-rs";
@@ -16,6 +22,7 @@ pub struct Endpoints;
 
 impl Endpoints {
     pub const LOGIN_URL: &'static str = "/connect/login";
+    pub const PUBLISHER_BASKET: &'static str = "/connect/basket";
     pub const SESSION_GENERATE: &'static str = "/session/token";
     pub const INVALIDATE_TOKEN: &'static str = "/session/token";
     pub const RENEW_ACCESS: &'static str = "/session/refresh_token";
@@ -69,59 +76,75 @@ impl Endpoints {
     pub const GET_HISTORICAL: &'static str =
         "/instruments/historical/{instrument_token}/{interval}";
 
+    // GTT endpoints
+    pub const GTT_TRIGGERS: &'static str = "/gtt/triggers";
+    pub const GTT_TRIGGER: &'static str = "/gtt/triggers/{trigger_id}";
+
     // Alerts endpoints
     pub const ALERTS_URL: &'static str = "/alerts";
     pub const ALERT_URL: &'static str = "/alerts/{alert_id}";
     pub const GET_ALERT_HISTORY: &'static str = "/alerts/{alert_id}/history";
+
+    // Market watch endpoints, not part of the documented API
+    pub const WATCHLISTS: &'static str = "/marketwatch";
+    pub const WATCHLIST: &'static str = "/marketwatch/{watchlist_id}";
+    pub const WATCHLIST_ITEMS: &'static str = "/marketwatch/{watchlist_id}/items";
+    pub const WATCHLIST_ITEM: &'static str = "/marketwatch/{watchlist_id}/items/{item_id}";
 }
 
+/// String constants for request parameters.
+///
+/// Each constant is derived from its typed counterpart in [`crate::labels`], which new code
+/// should prefer: a `match` on the enums is checked for exhaustiveness. These constants
+/// stay for now and will be deprecated in a later release.
 pub struct Labels;
 
 impl Labels {
     // Order varieties
-    pub const VARIETY_REGULAR: &str = "regular";
-    pub const VARIETY_AMO: &str = "amo";
-    pub const VARIETY_ICEBERG: &str = "iceberg";
-    pub const VARIETY_BRACKET: &str = "bo";
-    pub const VARIETY_COVER: &str = "co";
-    pub const VARIETY_AUCTION: &str = "auction";
+    pub const VARIETY_REGULAR: &str = Variety::Regular.as_str();
+    pub const VARIETY_AMO: &str = Variety::Amo.as_str();
+    pub const VARIETY_ICEBERG: &str = Variety::Iceberg.as_str();
+    pub const VARIETY_BRACKET: &str = Variety::Bracket.as_str();
+    pub const VARIETY_COVER: &str = Variety::Cover.as_str();
+    pub const VARIETY_AUCTION: &str = Variety::Auction.as_str();
 
     // Order types
-    pub const ORDER_TYPE_MARKET: &str = "MARKET";
-    pub const ORDER_TYPE_LIMIT: &str = "LIMIT";
-    pub const ORDER_TYPE_SL: &str = "SL";
-    pub const ORDER_TYPE_SL_M: &str = "SL-M";
+    pub const ORDER_TYPE_MARKET: &str = OrderType::Market.as_str();
+    pub const ORDER_TYPE_LIMIT: &str = OrderType::Limit.as_str();
+    pub const ORDER_TYPE_SL: &str = OrderType::Sl.as_str();
+    pub const ORDER_TYPE_SL_M: &str = OrderType::SlM.as_str();
 
     // Transaction types
-    pub const TRANSACTION_TYPE_BUY: &str = "BUY";
-    pub const TRANSACTION_TYPE_SELL: &str = "SELL";
+    pub const TRANSACTION_TYPE_BUY: &str = TransactionType::Buy.as_str();
+    pub const TRANSACTION_TYPE_SELL: &str = TransactionType::Sell.as_str();
 
     // Products
-    pub const PRODUCT_CNC: &str = "CNC";
-    pub const PRODUCT_MIS: &str = "MIS";
-    pub const PRODUCT_NRML: &str = "NRML";
-    pub const PRODUCT_BO: &str = "BO";
-    pub const PRODUCT_CO: &str = "CO";
+    pub const PRODUCT_CNC: &str = Product::Cnc.as_str();
+    pub const PRODUCT_MIS: &str = Product::Mis.as_str();
+    pub const PRODUCT_NRML: &str = Product::Nrml.as_str();
+    pub const PRODUCT_BO: &str = Product::Bo.as_str();
+    pub const PRODUCT_CO: &str = Product::Co.as_str();
+    pub const PRODUCT_MTF: &str = Product::Mtf.as_str();
 
     // Validity
-    pub const VALIDITY_DAY: &str = "DAY";
-    pub const VALIDITY_IOC: &str = "IOC";
-    pub const VALIDITY_TTL: &str = "TTL";
+    pub const VALIDITY_DAY: &str = Validity::Day.as_str();
+    pub const VALIDITY_IOC: &str = Validity::Ioc.as_str();
+    pub const VALIDITY_TTL: &str = Validity::Ttl.as_str();
 
     // Exchanges
-    pub const EXCHANGE_NSE: &str = "NSE";
-    pub const EXCHANGE_BSE: &str = "BSE";
-    pub const EXCHANGE_NFO: &str = "NFO";
-    pub const EXCHANGE_BFO: &str = "BFO";
-    pub const EXCHANGE_MCX: &str = "MCX";
-    pub const EXCHANGE_CDS: &str = "CDS";
+    pub const EXCHANGE_NSE: &str = Exchange::Nse.as_str();
+    pub const EXCHANGE_BSE: &str = Exchange::Bse.as_str();
+    pub const EXCHANGE_NFO: &str = Exchange::Nfo.as_str();
+    pub const EXCHANGE_BFO: &str = Exchange::Bfo.as_str();
+    pub const EXCHANGE_MCX: &str = Exchange::Mcx.as_str();
+    pub const EXCHANGE_CDS: &str = Exchange::Cds.as_str();
 
     // Constants for Holdings Auth types
-    pub const HOL_AUTH_TYPE_MF: &str = "mf";
-    pub const HOL_AUTH_TYPE_EQUITY: &str = "equity";
+    pub const HOL_AUTH_TYPE_MF: &str = HoldingsAuthType::Mf.as_str();
+    pub const HOL_AUTH_TYPE_EQUITY: &str = HoldingsAuthType::Equity.as_str();
 
-    pub const HOL_AUTH_TRANSFER_TYPE_PRE_TRADE: &str = "pre";
-    pub const HOL_AUTH_TRANSFER_TYPE_POST_TRADE: &str = "post";
-    pub const HOL_AUTH_TRANSFER_TYPE_OFF_MARKET: &str = "off";
-    pub const HOL_AUTH_TRANSFER_TYPE_GIFT: &str = "gift";
+    pub const HOL_AUTH_TRANSFER_TYPE_PRE_TRADE: &str = HoldingsTransferType::PreTrade.as_str();
+    pub const HOL_AUTH_TRANSFER_TYPE_POST_TRADE: &str = HoldingsTransferType::PostTrade.as_str();
+    pub const HOL_AUTH_TRANSFER_TYPE_OFF_MARKET: &str = HoldingsTransferType::OffMarket.as_str();
+    pub const HOL_AUTH_TRANSFER_TYPE_GIFT: &str = HoldingsTransferType::Gift.as_str();
 }
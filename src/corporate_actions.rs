@@ -0,0 +1,209 @@
+//! Adjusting prices and quantities for splits and bonuses.
+//!
+//! After a split or bonus issue, candles and holdings recorded before the ex-date are no
+//! longer comparable with later ones: prices drop by the adjustment factor and quantities
+//! grow by it. [`CorporateActions`] holds the actions of some symbols, supplied directly or
+//! loaded from a [`CorporateActionSource`], and rescales older candles and holdings so that
+//! a series reads as if the current share structure had always been in place. Values
+//! (price times quantity) and PnL are unchanged by the adjustment.
+//!
+//! ```
+//! use chrono::NaiveDate;
+//! use kiteconnect_rs::corporate_actions::{CorporateAction, CorporateActions};
+//!
+//! let ex_date = NaiveDate::from_ymd_opt(2024, 6, 3).unwrap();
+//! let actions = CorporateActions::new().with(CorporateAction::split("INFY", ex_date, 1, 5));
+//! let before = ex_date.pred_opt().unwrap();
+//! assert_eq!(actions.price_factor("INFY", before), 0.2);
+//! assert_eq!(actions.price_factor("INFY", ex_date), 1.0);
+//! ```
+
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use chrono_tz::Asia::Kolkata;
+use serde::{Deserialize, Serialize};
+
+use crate::markets::HistoricalData;
+use crate::models::KiteConnectError;
+use crate::portfolio::Holding;
+
+/// CorporateActionKind is the change an action makes to the share structure.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum CorporateActionKind {
+    /// `from` shares become `to` shares, e.g. a face value split from 10 to 2 is 1:5.
+    Split { from: u32, to: u32 },
+    /// `bonus` new shares for every `held` shares.
+    Bonus { bonus: u32, held: u32 },
+    /// Any other adjustment, given as the factor prices before the ex-date are
+    /// multiplied by.
+    Factor { factor: f64 },
+}
+
+/// CorporateAction is a split, bonus or other adjustment of one symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CorporateAction {
+    pub tradingsymbol: String,
+    /// First trading day on the new share structure.
+    pub ex_date: NaiveDate,
+    pub kind: CorporateActionKind,
+}
+
+impl CorporateAction {
+    pub fn new(tradingsymbol: &str, ex_date: NaiveDate, kind: CorporateActionKind) -> Self {
+        Self {
+            tradingsymbol: tradingsymbol.to_string(),
+            ex_date,
+            kind,
+        }
+    }
+
+    pub fn split(tradingsymbol: &str, ex_date: NaiveDate, from: u32, to: u32) -> Self {
+        Self::new(
+            tradingsymbol,
+            ex_date,
+            CorporateActionKind::Split { from, to },
+        )
+    }
+
+    pub fn bonus(tradingsymbol: &str, ex_date: NaiveDate, bonus: u32, held: u32) -> Self {
+        Self::new(
+            tradingsymbol,
+            ex_date,
+            CorporateActionKind::Bonus { bonus, held },
+        )
+    }
+
+    /// The factor prices before the ex-date are multiplied by; quantities are divided by
+    /// it. 1.0 for a malformed ratio.
+    pub fn price_factor(&self) -> f64 {
+        let factor = match self.kind {
+            CorporateActionKind::Split { from, to } => from as f64 / to as f64,
+            CorporateActionKind::Bonus { bonus, held } => held as f64 / (held + bonus) as f64,
+            CorporateActionKind::Factor { factor } => factor,
+        };
+        if factor.is_finite() && factor > 0.0 {
+            factor
+        } else {
+            1.0
+        }
+    }
+}
+
+/// CorporateActionSource looks up the corporate actions of a symbol, e.g. from an
+/// exchange feed or a file kept by the application.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait CorporateActionSource: Send + Sync {
+    async fn corporate_actions(
+        &self,
+        tradingsymbol: &str,
+    ) -> Result<Vec<CorporateAction>, KiteConnectError>;
+}
+
+/// CorporateActions is a set of corporate actions applied to candles and holdings.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct CorporateActions {
+    actions: Vec<CorporateAction>,
+}
+
+impl CorporateActions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with(mut self, action: CorporateAction) -> Self {
+        self.push(action);
+        self
+    }
+
+    pub fn push(&mut self, action: CorporateAction) {
+        self.actions.push(action);
+    }
+
+    /// The actions of `tradingsymbols` as reported by `source`.
+    pub async fn load(
+        source: &dyn CorporateActionSource,
+        tradingsymbols: &[&str],
+    ) -> Result<Self, KiteConnectError> {
+        let mut actions = Self::new();
+        for tradingsymbol in tradingsymbols {
+            actions
+                .actions
+                .extend(source.corporate_actions(tradingsymbol).await?);
+        }
+        Ok(actions)
+    }
+
+    pub fn actions(&self) -> &[CorporateAction] {
+        &self.actions
+    }
+
+    /// The combined factor of all actions of `tradingsymbol` that went ex after `date`.
+    pub fn price_factor(&self, tradingsymbol: &str, date: NaiveDate) -> f64 {
+        self.actions
+            .iter()
+            .filter(|a| a.tradingsymbol == tradingsymbol && a.ex_date > date)
+            .map(CorporateAction::price_factor)
+            .product()
+    }
+
+    /// Rescale candles of `tradingsymbol` dated before its ex-dates. Candles without a
+    /// date are left as they are.
+    pub fn adjust_candles(&self, tradingsymbol: &str, candles: &mut [HistoricalData]) {
+        for candle in candles {
+            let Some(date) = candle.date.as_datetime() else {
+                continue;
+            };
+            let factor =
+                self.price_factor(tradingsymbol, date.with_timezone(&Kolkata).date_naive());
+            if factor == 1.0 {
+                continue;
+            }
+            candle.open *= factor;
+            candle.high *= factor;
+            candle.low *= factor;
+            candle.close *= factor;
+            candle.volume = (candle.volume as f64 / factor).round() as u32;
+        }
+    }
+
+    /// Rescale holdings recorded on `as_of` for the actions that went ex after it, so they
+    /// compare with holdings recorded today.
+    pub fn adjust_holdings(&self, as_of: NaiveDate, holdings: &mut [Holding]) {
+        for holding in holdings {
+            let factor = self.price_factor(&holding.tradingsymbol, as_of);
+            if factor == 1.0 {
+                continue;
+            }
+            let quantity = |q: i32| (q as f64 / factor).round() as i32;
+            holding.quantity = quantity(holding.quantity);
+            holding.t1_quantity = quantity(holding.t1_quantity);
+            holding.used_quantity = quantity(holding.used_quantity);
+            holding.realised_quantity = quantity(holding.realised_quantity);
+            holding.authorised_quantity = quantity(holding.authorised_quantity);
+            holding.opening_quantity = quantity(holding.opening_quantity);
+            holding.collateral_quantity = quantity(holding.collateral_quantity);
+            holding.average_price *= factor;
+            holding.last_price *= factor;
+            holding.close_price *= factor;
+            holding.price *= factor;
+        }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl CorporateActionSource for CorporateActions {
+    async fn corporate_actions(
+        &self,
+        tradingsymbol: &str,
+    ) -> Result<Vec<CorporateAction>, KiteConnectError> {
+        Ok(self
+            .actions
+            .iter()
+            .filter(|a| a.tradingsymbol == tradingsymbol)
+            .cloned()
+            .collect())
+    }
+}
@@ -0,0 +1,147 @@
+//! Derived tick fields that need the previous tick.
+//!
+//! [`TickEnricher`] remembers the last open interest, volume and traded value of every
+//! instrument and fills [`Tick::change`] with the differences, so strategies don't each
+//! keep their own previous-tick maps. The ticker runs one between the parser and the event
+//! channel when `TickerBuilder::enrich_ticks(true)` is set; it can also be used on its own,
+//! e.g. on replayed ticks.
+//!
+//! During the pre-open call auction the feed carries no trades: the last price is the
+//! indicative equilibrium price the auction would open at and the volume is the quantity
+//! that would match. In pre-open mode the enricher marks such ticks
+//! [`Tick::indicative`], leaves them out of the change computation and keeps the last
+//! indicative price of every instrument apart, see
+//! [`equilibrium_price`](TickEnricher::equilibrium_price).
+
+use chrono_tz::Asia::Kolkata;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::clock::{MarketClock, MarketPhase};
+use crate::models::time::Time;
+use crate::models::{Tick, TickChange};
+use crate::risk::ist_now_datetime;
+use crate::ticker::{BSE_CM, NSE_CM};
+
+#[derive(Debug, Clone, Copy)]
+struct Previous {
+    oi: u32,
+    volume: u32,
+    traded_value: f64,
+}
+
+/// IndicativePrice is the last pre-open price discovery of an instrument.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct IndicativePrice {
+    /// Indicative equilibrium price.
+    pub price: f64,
+    /// Quantity that would match at that price.
+    pub quantity: u32,
+    /// Exchange time of the tick, zero for LTP mode ticks.
+    pub timestamp: Time,
+}
+
+/// TickEnricher sets [`Tick::change`] from the previous tick of the same instrument.
+#[derive(Debug, Clone, Default)]
+pub struct TickEnricher {
+    previous: HashMap<u32, Previous>,
+    pre_open: Option<MarketClock>,
+    indicative: HashMap<u32, IndicativePrice>,
+}
+
+impl TickEnricher {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Mark ticks that `clock` places in the pre-open session as indicative. The session is
+    /// taken from the exchange timestamp, or from the local clock for ticks without one.
+    /// Only NSE and BSE cash-segment instruments hold the auction, so ticks of other
+    /// segments are never indicative.
+    pub fn pre_open(mut self, clock: MarketClock) -> Self {
+        self.pre_open = Some(clock);
+        self
+    }
+
+    /// The last indicative equilibrium price seen for `instrument_token` in pre-open mode.
+    /// It is kept after the session ends, as the price the auction settled at.
+    pub fn equilibrium_price(&self, instrument_token: u32) -> Option<&IndicativePrice> {
+        self.indicative.get(&instrument_token)
+    }
+
+    /// Fill `tick.change` and remember the tick.
+    ///
+    /// The first tick of an instrument has no change. LTP mode ticks carry no volume or
+    /// open interest, so they are left alone. A volume lower than the previous one starts a
+    /// new session, and the whole volume counts as traded since. Indicative ticks have no
+    /// change and are not remembered as the previous tick.
+    pub fn enrich(&mut self, tick: &mut Tick) {
+        if self.is_pre_open(tick) {
+            tick.indicative = true;
+            tick.change = None;
+            self.indicative.insert(
+                tick.instrument_token,
+                IndicativePrice {
+                    price: tick.last_price,
+                    quantity: tick.volume_traded,
+                    timestamp: tick.timestamp,
+                },
+            );
+            return;
+        }
+        if tick.mode == "ltp" {
+            return;
+        }
+
+        let current = Previous {
+            oi: tick.oi,
+            volume: tick.volume_traded,
+            traded_value: tick.traded_value(),
+        };
+        let Some(previous) = self.previous.insert(tick.instrument_token, current) else {
+            tick.change = None;
+            return;
+        };
+
+        let (volume_delta, traded_value_delta) = if current.volume >= previous.volume {
+            (
+                current.volume - previous.volume,
+                current.traded_value - previous.traded_value,
+            )
+        } else {
+            (current.volume, current.traded_value)
+        };
+        tick.change = Some(TickChange {
+            oi_change: current.oi as i64 - previous.oi as i64,
+            volume_delta,
+            traded_value_delta,
+        });
+    }
+
+    /// Forget the previous tick and indicative price of every instrument.
+    pub fn reset(&mut self) {
+        self.previous.clear();
+        self.indicative.clear();
+    }
+
+    /// Forget the previous tick and indicative price of one instrument, e.g. after
+    /// unsubscribing it.
+    pub fn forget(&mut self, instrument_token: u32) {
+        self.previous.remove(&instrument_token);
+        self.indicative.remove(&instrument_token);
+    }
+
+    fn is_pre_open(&self, tick: &Tick) -> bool {
+        let Some(clock) = &self.pre_open else {
+            return false;
+        };
+        if !matches!(tick.instrument_token & 0xff, NSE_CM | BSE_CM) {
+            return false;
+        }
+        let at = match tick.timestamp.as_datetime() {
+            Some(at) => at.with_timezone(&Kolkata),
+            None => ist_now_datetime(),
+        };
+        clock.phase_at(at) == MarketPhase::PreOpen
+    }
+}
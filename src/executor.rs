@@ -0,0 +1,323 @@
+//! Limit-chase order execution.
+//!
+//! [`Executor`] works a LIMIT order against the market depth streamed by the ticker. It
+//! joins the best price on its own side of the book, and every `reprice_interval` it
+//! re-pegs the order one tick further towards the opposite touch until the order fills.
+//! When the price would leave the configured band, or Kite's modification limit is
+//! reached, the order is converted to MARKET or cancelled.
+//!
+//! The executor does not own a ticker connection. Subscribe the instrument in full mode
+//! and feed events from the event loop with [`Executor::on_event`]:
+//!
+//! ```ignore
+//! let executor = Executor::new(kite.clone(), ExecutorConfig::default().market_on_band(true));
+//! handle.subscribe(vec![token]).await?;
+//! handle.set_mode(Mode::Full, vec![token]).await?;
+//!
+//! let feed = executor.clone();
+//! tokio::spawn(async move {
+//!     while let Ok(event) = events.recv().await {
+//!         feed.on_event(&event);
+//!     }
+//! });
+//! let report = executor.execute(token, order_params, 0.05).await?;
+//! ```
+
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use web_time::Duration;
+
+use crate::KiteConnect;
+use crate::compat;
+use crate::instruments::{Rounding, round_price};
+use crate::labels::{OrderType, TransactionType, Variety};
+use crate::models::{Depth, DepthItem, KiteConnectError, Tick};
+use crate::orders::{Order, OrderParams};
+use crate::ticker::TickerEvent;
+
+/// Kite rejects modifications of an order after this many.
+pub const MAX_ORDER_MODIFICATIONS: u32 = 25;
+
+/// ExecutorConfig configures how an [`Executor`] chases the book.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutorConfig {
+    /// Time the order rests at a price before it is re-pegged.
+    pub reprice_interval: Duration,
+    /// How far, in percent of the touch when the order was placed, the limit price may
+    /// move before the chase gives up.
+    pub band_pct: f64,
+    /// Convert to a MARKET order instead of cancelling when the chase gives up.
+    pub market_on_band: bool,
+    /// Modifications after which the chase gives up, capped at
+    /// [`MAX_ORDER_MODIFICATIONS`] with one left for the MARKET conversion.
+    pub max_modifications: u32,
+}
+
+impl Default for ExecutorConfig {
+    fn default() -> Self {
+        Self {
+            reprice_interval: Duration::from_millis(500),
+            band_pct: 0.5,
+            market_on_band: false,
+            max_modifications: 20,
+        }
+    }
+}
+
+impl ExecutorConfig {
+    pub fn reprice_interval(mut self, interval: Duration) -> Self {
+        self.reprice_interval = interval;
+        self
+    }
+
+    pub fn band_pct(mut self, pct: f64) -> Self {
+        self.band_pct = pct;
+        self
+    }
+
+    pub fn market_on_band(mut self, enable: bool) -> Self {
+        self.market_on_band = enable;
+        self
+    }
+
+    pub fn max_modifications(mut self, modifications: u32) -> Self {
+        self.max_modifications = modifications;
+        self
+    }
+}
+
+/// How an execution ended.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ExecutionOutcome {
+    /// The limit order filled completely.
+    Filled,
+    /// The chase gave up and the rest of the order was converted to MARKET.
+    ConvertedToMarket,
+    /// The chase gave up and the rest of the order was cancelled.
+    Cancelled,
+    /// The order was rejected or cancelled outside the executor, with its status message.
+    Terminated(String),
+}
+
+/// ExecutionReport is returned by [`Executor::execute`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct ExecutionReport {
+    pub order_id: String,
+    pub outcome: ExecutionOutcome,
+    /// Number of times the limit price was modified.
+    pub reprices: u32,
+    /// The last limit price sent.
+    pub last_price: f64,
+    pub filled_quantity: f64,
+    pub average_price: f64,
+}
+
+/// Limit price for a chase `steps` ticks in: the best price on the order's own side of
+/// `depth` moved `steps` ticks towards the opposite touch, never crossing it. None when
+/// the book is empty on both sides.
+pub fn chase_price(
+    transaction_type: TransactionType,
+    depth: &Depth,
+    steps: u32,
+    tick_size: f64,
+) -> Option<f64> {
+    let best = |levels: &[DepthItem]| {
+        levels
+            .first()
+            .map(|level| level.price)
+            .filter(|price| *price > 0.0)
+    };
+    let (own, opposite) = match transaction_type {
+        TransactionType::Buy => (best(&depth.buy), best(&depth.sell)),
+        TransactionType::Sell => (best(&depth.sell), best(&depth.buy)),
+    };
+    let price = match (own, opposite) {
+        (Some(own), Some(opposite)) => {
+            let offset = steps as f64 * tick_size;
+            match transaction_type {
+                TransactionType::Buy => (own + offset).min(opposite),
+                TransactionType::Sell => (own - offset).max(opposite),
+            }
+        }
+        (Some(own), None) => own,
+        (None, Some(opposite)) => opposite,
+        (None, None) => return None,
+    };
+    Some(round_price(
+        price,
+        tick_size,
+        Rounding::Passive,
+        Some(transaction_type),
+    ))
+}
+
+/// Executor places and chases LIMIT orders using the latest depth fed to it. It is cheap
+/// to clone; clones share the depth they are fed.
+#[derive(Clone)]
+pub struct Executor {
+    kite: KiteConnect,
+    config: ExecutorConfig,
+    depth: Arc<Mutex<HashMap<u32, Depth>>>,
+}
+
+impl Executor {
+    pub fn new(kite: KiteConnect, config: ExecutorConfig) -> Self {
+        Self {
+            kite,
+            config,
+            depth: Arc::new(Mutex::new(HashMap::new())),
+        }
+    }
+
+    /// Feed a ticker event; ticks update the book of their instrument.
+    pub fn on_event(&self, event: &TickerEvent) {
+        if let TickerEvent::Tick(tick) = event {
+            self.on_tick(tick);
+        }
+    }
+
+    /// Feed a tick. Ticks without depth (LTP and quote mode) are ignored.
+    pub fn on_tick(&self, tick: &Tick) {
+        if tick.depth == Depth::default() {
+            return;
+        }
+        self.depth
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(tick.instrument_token, tick.depth.clone());
+    }
+
+    fn depth(&self, instrument_token: u32) -> Option<Depth> {
+        self.depth
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .get(&instrument_token)
+            .cloned()
+    }
+
+    /// Place `order_params` as a regular LIMIT order at the touch of `instrument_token`
+    /// and chase it until it fills or the chase gives up. The order's `price` and
+    /// `order_type` are overridden.
+    ///
+    /// A request failing once the order is placed returns
+    /// [`ExecutionFailed`](crate::KiteConnectErrorKind::ExecutionFailed) with the report so
+    /// far, so the order can still be found.
+    pub async fn execute(
+        &self,
+        instrument_token: u32,
+        mut order_params: OrderParams,
+        tick_size: f64,
+    ) -> Result<ExecutionReport, KiteConnectError> {
+        let transaction_type: TransactionType = order_params
+            .transaction_type
+            .as_deref()
+            .ok_or_else(|| KiteConnectError::invalid_params("order has no transaction_type"))?
+            .parse()?;
+        let price = self
+            .depth(instrument_token)
+            .and_then(|depth| chase_price(transaction_type, &depth, 0, tick_size))
+            .ok_or_else(|| {
+                KiteConnectError::invalid_params(format!(
+                    "no market depth for instrument {}",
+                    instrument_token
+                ))
+            })?;
+        order_params.order_type = Some(OrderType::Limit.into());
+        order_params.price = Some(price);
+        let variety = Variety::Regular.as_str();
+        let order_id = self.kite.place_order(variety, order_params).await?.order_id;
+
+        let mut report = ExecutionReport {
+            order_id,
+            outcome: ExecutionOutcome::Filled,
+            reprices: 0,
+            last_price: price,
+            filled_quantity: 0.0,
+            average_price: 0.0,
+        };
+        let chased = self
+            .chase(instrument_token, transaction_type, tick_size, &mut report)
+            .await;
+        match chased {
+            Ok(()) => Ok(report),
+            Err(e) => Err(KiteConnectError::execution_failed(report, e)),
+        }
+    }
+
+    async fn chase(
+        &self,
+        instrument_token: u32,
+        transaction_type: TransactionType,
+        tick_size: f64,
+        report: &mut ExecutionReport,
+    ) -> Result<(), KiteConnectError> {
+        let variety = Variety::Regular.as_str();
+        let price = report.last_price;
+        let band = price * self.config.band_pct / 100.0;
+        let max_modifications = self
+            .config
+            .max_modifications
+            .min(MAX_ORDER_MODIFICATIONS - 1);
+        let mut steps = 0;
+        loop {
+            compat::sleep(self.config.reprice_interval).await;
+
+            let order = self.order_state(&report.order_id).await?;
+            report.filled_quantity = order.filled_quantity;
+            report.average_price = order.average_price;
+            match order.status.as_str() {
+                "COMPLETE" => return Ok(()),
+                "REJECTED" | "CANCELLED" => {
+                    report.outcome =
+                        ExecutionOutcome::Terminated(order.status_message.unwrap_or(order.status));
+                    return Ok(());
+                }
+                _ => {}
+            }
+
+            steps += 1;
+            let next = self
+                .depth(instrument_token)
+                .and_then(|depth| chase_price(transaction_type, &depth, steps, tick_size))
+                .unwrap_or(report.last_price);
+            if (next - price).abs() > band + 1e-9 || report.reprices >= max_modifications {
+                return self.give_up(report).await;
+            }
+            if (next - report.last_price).abs() > 1e-9 {
+                self.kite
+                    .modify_price(variety, &report.order_id, next)
+                    .await?;
+                report.reprices += 1;
+                report.last_price = next;
+            }
+        }
+    }
+
+    async fn give_up(&self, report: &mut ExecutionReport) -> Result<(), KiteConnectError> {
+        let variety = Variety::Regular.as_str();
+        if self.config.market_on_band {
+            let params = OrderParams {
+                order_type: Some(OrderType::Market.into()),
+                ..Default::default()
+            };
+            self.kite
+                .modify_order(variety, &report.order_id, params)
+                .await?;
+            report.outcome = ExecutionOutcome::ConvertedToMarket;
+        } else {
+            self.kite
+                .cancel_order(variety, &report.order_id, None)
+                .await?;
+            report.outcome = ExecutionOutcome::Cancelled;
+        }
+        Ok(())
+    }
+
+    async fn order_state(&self, order_id: &str) -> Result<Order, KiteConnectError> {
+        self.kite
+            .get_order_history(order_id)
+            .await?
+            .pop()
+            .ok_or_else(|| KiteConnectError::other(format!("order {} not found", order_id)))
+    }
+}
@@ -0,0 +1,188 @@
+//! Freeze quantity splitting for F&O orders.
+//!
+//! NSE and BSE reject F&O orders larger than the freeze quantity of their underlying.
+//! [`FreezeQuantities`] holds the largest quantity allowed per order for each underlying.
+//! Set on the client with [`KiteConnect::set_freeze_quantities`], it makes `place_order`
+//! split oversized NFO and BFO orders into child orders under the limit; the ids of the
+//! extra children are returned in [`OrderResponse::split_order_ids`].
+//!
+//! The exchanges revise freeze quantities from time to time, so the defaults should be
+//! checked against the current circulars and overridden with [`FreezeQuantities::set`].
+
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use crate::KiteConnect;
+use crate::instruments::InstrumentStore;
+use crate::labels::Exchange;
+use crate::models::KiteConnectError;
+use crate::orders::{OrderParams, OrderResponse};
+
+/// Largest quantity per order for the index derivatives, as published when this table
+/// was last updated.
+pub const DEFAULT_FREEZE_QUANTITIES: [(&str, i32); 6] = [
+    ("NIFTY", 1800),
+    ("BANKNIFTY", 900),
+    ("FINNIFTY", 1800),
+    ("MIDCPNIFTY", 4200),
+    ("SENSEX", 1000),
+    ("BANKEX", 900),
+];
+
+/// FreezeQuantities maps underlyings to the largest quantity allowed in one order.
+#[derive(Debug, Clone)]
+pub struct FreezeQuantities {
+    limits: HashMap<String, i32>,
+    instruments: Option<Arc<InstrumentStore>>,
+}
+
+impl Default for FreezeQuantities {
+    fn default() -> Self {
+        let mut quantities = Self::empty();
+        for (underlying, quantity) in DEFAULT_FREEZE_QUANTITIES {
+            quantities.set(underlying, quantity);
+        }
+        quantities
+    }
+}
+
+impl FreezeQuantities {
+    /// A table without any limits.
+    pub fn empty() -> Self {
+        Self {
+            limits: HashMap::new(),
+            instruments: None,
+        }
+    }
+
+    /// Set the largest quantity per order for `underlying`, e.g. `NIFTY`.
+    pub fn set(&mut self, underlying: &str, quantity: i32) -> &mut Self {
+        self.limits.insert(underlying.to_uppercase(), quantity);
+        self
+    }
+
+    /// Resolve underlyings and lot sizes from `instruments`. Without a store the
+    /// underlying is taken from the start of the trading symbol and children are not
+    /// aligned to lots.
+    pub fn with_instruments(mut self, instruments: Arc<InstrumentStore>) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    pub fn get(&self, underlying: &str) -> Option<i32> {
+        self.limits.get(&underlying.to_uppercase()).copied()
+    }
+
+    /// Freeze quantity and lot size of a contract, None when no limit applies.
+    fn limit_for(&self, exchange: &str, tradingsymbol: &str) -> Option<(i32, i32)> {
+        let exchange: Exchange = exchange.parse().ok()?;
+        if !matches!(exchange, Exchange::Nfo | Exchange::Bfo) {
+            return None;
+        }
+        let instrument = self
+            .instruments
+            .as_ref()
+            .and_then(|store| store.get_by_symbol(exchange.as_str(), tradingsymbol));
+        match instrument {
+            Some(instrument) => self
+                .get(&instrument.name)
+                .map(|limit| (limit, instrument.lot_size.round() as i32)),
+            None => self.symbol_limit(tradingsymbol).map(|limit| (limit, 1)),
+        }
+    }
+
+    /// Limit of the longest underlying that `tradingsymbol` starts with, followed by the
+    /// expiry digits, so `NIFTY` does not match `NIFTYNXT50` contracts.
+    fn symbol_limit(&self, tradingsymbol: &str) -> Option<i32> {
+        self.limits
+            .iter()
+            .filter(|(underlying, _)| {
+                tradingsymbol
+                    .strip_prefix(underlying.as_str())
+                    .is_some_and(|rest| rest.starts_with(|c: char| c.is_ascii_digit()))
+            })
+            .max_by_key(|(underlying, _)| underlying.len())
+            .map(|(_, limit)| *limit)
+    }
+
+    /// Child quantities for `params`: the order's quantity when it is within the freeze
+    /// quantity of its underlying, otherwise as many full children as needed and the
+    /// remainder.
+    pub fn split(&self, params: &OrderParams) -> Result<Vec<i32>, KiteConnectError> {
+        let quantity = params.quantity.unwrap_or_default();
+        let limit = match (&params.exchange, &params.tradingsymbol) {
+            (Some(exchange), Some(tradingsymbol)) => self.limit_for(exchange, tradingsymbol),
+            _ => None,
+        };
+        match limit {
+            Some((limit, lot_size)) if quantity > limit => {
+                split_quantity(quantity, limit, lot_size)
+            }
+            _ => Ok(vec![quantity]),
+        }
+    }
+}
+
+/// Split `quantity` into children of at most `freeze_quantity`, each a multiple of
+/// `lot_size`.
+pub fn split_quantity(
+    quantity: i32,
+    freeze_quantity: i32,
+    lot_size: i32,
+) -> Result<Vec<i32>, KiteConnectError> {
+    let lot_size = lot_size.max(1);
+    let child = freeze_quantity / lot_size * lot_size;
+    if child <= 0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "freeze quantity {} is smaller than a lot of {}",
+            freeze_quantity, lot_size
+        )));
+    }
+    if quantity % lot_size != 0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "quantity {} is not a multiple of the lot size {}",
+            quantity, lot_size
+        )));
+    }
+
+    let mut children = vec![child; (quantity / child) as usize];
+    if quantity % child > 0 {
+        children.push(quantity % child);
+    }
+    Ok(children)
+}
+
+impl KiteConnect {
+    /// Place `order_params` as child orders under the freeze quantity set with
+    /// [`set_freeze_quantities`](Self::set_freeze_quantities), one result per child.
+    /// Order rounding is applied before the split, and the validator and risk limits check
+    /// the whole order once, so a split order cannot pass limits its children would.
+    /// Children are placed in order and placement stops at the first failure.
+    pub async fn place_order_split(
+        &self,
+        variety: &str,
+        mut order_params: OrderParams,
+    ) -> Result<Vec<Result<OrderResponse, KiteConnectError>>, KiteConnectError> {
+        self.round_order(variety, &mut order_params).await?;
+        let children = match (&self.freeze_quantities, order_params.quantity) {
+            (Some(freeze), Some(_)) => freeze.split(&order_params)?,
+            _ => return Ok(vec![self.place_order_checked(variety, order_params).await]),
+        };
+        self.check_order(variety, &order_params).await?;
+
+        let mut results = Vec::with_capacity(children.len());
+        for quantity in children {
+            let params = OrderParams {
+                quantity: Some(quantity),
+                ..order_params.clone()
+            };
+            let result = self.place_order_unchecked(variety, params).await;
+            let failed = result.is_err();
+            results.push(result);
+            if failed {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
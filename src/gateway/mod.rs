@@ -0,0 +1,414 @@
+//! gRPC gateway sharing one Kite session with internal services.
+//!
+//! [`MarketDataGateway`] serves the `kiteconnect.MarketData` service described in
+//! `proto/gateway.proto` with tonic. Every caller is served from the gateway's single
+//! [`KiteConnect`] client and ticker connection, so internal services need neither Kite
+//! credentials nor connections of their own:
+//!
+//! - `SubscribeTicks` streams ticks for a set of instruments. Instruments are subscribed
+//!   on the ticker while at least one stream wants them.
+//! - `GetQuote` proxies [`KiteConnect::get_quote`].
+//! - `PlaceOrder` proxies [`KiteConnect::place_order`], and only when the gateway is
+//!   configured with an auth token.
+//!
+//! ```ignore
+//! let (ticker, handle) = Ticker::builder(&api_key, &access_token).build()?;
+//! tokio::spawn(ticker.serve());
+//! MarketDataGateway::new(kite, handle)
+//!     .auth_token(&gateway_token)
+//!     .serve("127.0.0.1:50051".parse()?)
+//!     .await?;
+//! ```
+
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
+
+use async_channel::Sender;
+use tonic::body::BoxBody;
+use tonic::codec::ProstCodec;
+use tonic::codegen::{Body, BoxFuture, BoxStream, Context, Poll, Service, StdError, http};
+use tonic::server::{Grpc, NamedService, ServerStreamingService, UnaryService};
+use tonic::{Request, Response, Status};
+use zeroize::Zeroizing;
+
+use crate::KiteConnect;
+use crate::codec::proto::Tick as TickMessage;
+use crate::compat;
+use crate::models::{ErrorCategory, KiteConnectError, KiteConnectErrorKind, Tick};
+use crate::ticker::{EventReceiver, Mode, TickerEvent, TickerHandle};
+
+pub mod proto;
+
+use self::proto::{
+    GetQuoteRequest, GetQuoteResponse, PlaceOrderRequest, PlaceOrderResponse, SubscribeTicksRequest,
+};
+
+/// Ticks buffered per stream. Ticks for a client that falls further behind are dropped.
+const DEFAULT_STREAM_BUFFER: usize = 1024;
+
+/// MarketDataGateway configures the gateway; see the [module docs](self).
+pub struct MarketDataGateway {
+    kite: KiteConnect,
+    ticker: TickerHandle,
+    auth_token: Option<Zeroizing<String>>,
+    mode: Mode,
+    stream_buffer: usize,
+}
+
+impl MarketDataGateway {
+    pub fn new(kite: KiteConnect, ticker: TickerHandle) -> Self {
+        Self {
+            kite,
+            ticker,
+            auth_token: None,
+            mode: Mode::Quote,
+            stream_buffer: DEFAULT_STREAM_BUFFER,
+        }
+    }
+
+    /// Require `authorization: Bearer <token>` metadata on every call. Without a token,
+    /// market data is served to anyone who can connect and `PlaceOrder` is refused.
+    pub fn auth_token(mut self, token: &str) -> Self {
+        self.auth_token = Some(Zeroizing::new(token.to_string()));
+        self
+    }
+
+    /// Mode instruments are subscribed in. Defaults to quote.
+    pub fn mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn stream_buffer(mut self, ticks: usize) -> Self {
+        self.stream_buffer = ticks.max(1);
+        self
+    }
+
+    /// The tonic service, e.g. to serve it next to other services. Starts reading the
+    /// ticker's events.
+    pub fn into_service(self) -> MarketDataServer {
+        let events = self.ticker.subscribe_broadcast();
+        let gateway = Arc::new(Gateway {
+            kite: self.kite,
+            ticker: self.ticker,
+            auth_token: self.auth_token,
+            mode: self.mode,
+            stream_buffer: self.stream_buffer,
+            streams: Mutex::new(HashMap::new()),
+            subscription_lock: tokio::sync::Mutex::new(()),
+            next_stream: AtomicU64::new(0),
+        });
+        let _ = compat::spawn(dispatch(Arc::downgrade(&gateway), events));
+        MarketDataServer { gateway }
+    }
+
+    /// Serve the gateway on `addr` until the server fails.
+    pub async fn serve(self, addr: SocketAddr) -> Result<(), KiteConnectError> {
+        tonic::transport::Server::builder()
+            .add_service(self.into_service())
+            .serve(addr)
+            .await
+            .map_err(|e| KiteConnectError::other(format!("grpc gateway: {}", e)))
+    }
+}
+
+struct TickStream {
+    tokens: HashSet<u32>,
+    sender: Sender<Tick>,
+}
+
+struct Gateway {
+    kite: KiteConnect,
+    ticker: TickerHandle,
+    auth_token: Option<Zeroizing<String>>,
+    mode: Mode,
+    stream_buffer: usize,
+    streams: Mutex<HashMap<u64, TickStream>>,
+    // Held while streams are added or removed and the ticker told about it, so a token
+    // wanted by a new stream is never unsubscribed for a closing one
+    subscription_lock: tokio::sync::Mutex<()>,
+    next_stream: AtomicU64,
+}
+
+// Forward ticks to the streams that want them, until the gateway is dropped
+async fn dispatch(gateway: Weak<Gateway>, events: EventReceiver) {
+    while let Ok(event) = events.recv().await {
+        let Some(gateway) = gateway.upgrade() else {
+            return;
+        };
+        if let TickerEvent::Tick(tick) = event {
+            let streams = gateway.streams.lock().unwrap_or_else(|e| e.into_inner());
+            for stream in streams.values() {
+                if stream.tokens.contains(&tick.instrument_token) {
+                    let _ = stream.sender.try_send(tick.clone());
+                }
+            }
+        }
+    }
+}
+
+impl Gateway {
+    #[allow(clippy::result_large_err)] // tonic's Status is the service error type
+    fn authorize<T>(&self, request: &Request<T>) -> Result<(), Status> {
+        let Some(expected) = &self.auth_token else {
+            return Ok(());
+        };
+        let provided = request
+            .metadata()
+            .get("authorization")
+            .and_then(|value| value.to_str().ok())
+            .and_then(|value| value.strip_prefix("Bearer "));
+        match provided {
+            Some(token) if constant_time_eq(token.as_bytes(), expected.as_bytes()) => Ok(()),
+            _ => Err(Status::unauthenticated("missing or invalid gateway token")),
+        }
+    }
+
+    async fn subscribe_ticks(
+        self: &Arc<Self>,
+        request: Request<SubscribeTicksRequest>,
+    ) -> Result<Response<BoxStream<TickMessage>>, Status> {
+        self.authorize(&request)?;
+        let tokens: HashSet<u32> = request.into_inner().instrument_tokens.into_iter().collect();
+        if tokens.is_empty() {
+            return Err(Status::invalid_argument("no instrument tokens"));
+        }
+
+        let (sender, receiver) = async_channel::bounded(self.stream_buffer);
+        let guard = self.add_stream(tokens, sender).await?;
+        let stream = futures_util::stream::unfold((receiver, guard), |(receiver, guard)| async {
+            let tick = receiver.recv().await.ok()?;
+            Some((Ok(TickMessage::from(&tick)), (receiver, guard)))
+        });
+        Ok(Response::new(Box::pin(stream)))
+    }
+
+    // Register a stream and subscribe the tokens no other stream wanted yet
+    async fn add_stream(
+        self: &Arc<Self>,
+        tokens: HashSet<u32>,
+        sender: Sender<Tick>,
+    ) -> Result<StreamGuard, Status> {
+        let _lock = self.subscription_lock.lock().await;
+        let id = self.next_stream.fetch_add(1, Ordering::Relaxed);
+        let new_tokens = {
+            let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+            let new_tokens = unwanted(&streams, &tokens);
+            streams.insert(id, TickStream { tokens, sender });
+            new_tokens
+        };
+        let guard = StreamGuard {
+            gateway: self.clone(),
+            id,
+        };
+        if !new_tokens.is_empty() {
+            let subscribed = match self.ticker.subscribe(new_tokens.clone()).await {
+                Ok(()) if self.mode != Mode::Quote => {
+                    self.ticker.set_mode(self.mode, new_tokens).await
+                }
+                result => result,
+            };
+            // Dropping the guard unregisters the stream
+            subscribed.map_err(|e| Status::unavailable(e.to_string()))?;
+        }
+        Ok(guard)
+    }
+
+    // Unregister a stream and unsubscribe the tokens no other stream wants
+    async fn remove_stream(&self, id: u64) {
+        let _lock = self.subscription_lock.lock().await;
+        let released = {
+            let mut streams = self.streams.lock().unwrap_or_else(|e| e.into_inner());
+            match streams.remove(&id) {
+                Some(stream) => unwanted(&streams, &stream.tokens),
+                None => return,
+            }
+        };
+        if !released.is_empty() {
+            let _ = self.ticker.unsubscribe(released).await;
+        }
+    }
+
+    async fn get_quote(
+        &self,
+        request: Request<GetQuoteRequest>,
+    ) -> Result<Response<GetQuoteResponse>, Status> {
+        self.authorize(&request)?;
+        let instruments: Vec<&str> = request
+            .get_ref()
+            .instruments
+            .iter()
+            .map(String::as_str)
+            .collect();
+        if instruments.is_empty() {
+            return Err(Status::invalid_argument("no instruments"));
+        }
+        let quotes = self.kite.get_quote(&instruments).await.map_err(status)?;
+        Ok(Response::new(GetQuoteResponse {
+            quotes: quotes
+                .iter()
+                .map(|(instrument, quote)| (instrument.clone(), quote.into()))
+                .collect(),
+        }))
+    }
+
+    async fn place_order(
+        &self,
+        request: Request<PlaceOrderRequest>,
+    ) -> Result<Response<PlaceOrderResponse>, Status> {
+        if self.auth_token.is_none() {
+            return Err(Status::permission_denied(
+                "orders are only proxied by a gateway with an auth token",
+            ));
+        }
+        self.authorize(&request)?;
+        let order = request.into_inner();
+        let response = self
+            .kite
+            .place_order(order.variety(), order.order_params())
+            .await
+            .map_err(status)?;
+        Ok(Response::new(PlaceOrderResponse {
+            order_id: response.order_id,
+        }))
+    }
+}
+
+// Tokens of `tokens` that none of `streams` wants
+fn unwanted(streams: &HashMap<u64, TickStream>, tokens: &HashSet<u32>) -> Vec<u32> {
+    tokens
+        .iter()
+        .copied()
+        .filter(|token| !streams.values().any(|s| s.tokens.contains(token)))
+        .collect()
+}
+
+fn constant_time_eq(a: &[u8], b: &[u8]) -> bool {
+    a.len() == b.len() && a.iter().zip(b).fold(0, |acc, (x, y)| acc | (x ^ y)) == 0
+}
+
+/// The gRPC status for a failed Kite call.
+pub fn status(e: KiteConnectError) -> Status {
+    let message = e.to_string();
+    match e.kind {
+        KiteConnectErrorKind::ApiError(ref api) => match api.error_type.as_str() {
+            "TokenException" => Status::unauthenticated(message),
+            "PermissionException" => Status::permission_denied(message),
+            "InputException" => Status::invalid_argument(message),
+            "OrderException" | "MarginException" => Status::failed_precondition(message),
+            _ if e.is_retriable() => Status::unavailable(message),
+            _ => Status::unknown(message),
+        },
+        KiteConnectErrorKind::InvalidParams(_)
+        | KiteConnectErrorKind::InvalidConfig(_)
+        | KiteConnectErrorKind::ValidationFailed(_) => Status::invalid_argument(message),
+        KiteConnectErrorKind::RiskViolation(_) | KiteConnectErrorKind::TradingBlocked(_) => {
+            Status::failed_precondition(message)
+        }
+        KiteConnectErrorKind::ReadOnlyMode(_) | KiteConnectErrorKind::MissingCapability(_) => {
+            Status::permission_denied(message)
+        }
+        _ if e.is_retriable() => Status::unavailable(message),
+        _ if e.category() == ErrorCategory::Ambiguous => Status::unknown(message),
+        _ => Status::internal(message),
+    }
+}
+
+// Unregisters its stream when the client goes away and tonic drops the response stream
+struct StreamGuard {
+    gateway: Arc<Gateway>,
+    id: u64,
+}
+
+impl Drop for StreamGuard {
+    fn drop(&mut self) {
+        let gateway = self.gateway.clone();
+        let id = self.id;
+        let _ = compat::spawn(async move { gateway.remove_stream(id).await });
+    }
+}
+
+/// MarketDataServer is the tonic service of a [`MarketDataGateway`].
+#[derive(Clone)]
+pub struct MarketDataServer {
+    gateway: Arc<Gateway>,
+}
+
+impl NamedService for MarketDataServer {
+    const NAME: &'static str = "kiteconnect.MarketData";
+}
+
+impl<B> Service<http::Request<B>> for MarketDataServer
+where
+    B: Body + Send + 'static,
+    B::Error: Into<StdError> + Send + 'static,
+{
+    type Response = http::Response<BoxBody>;
+    type Error = Infallible;
+    type Future = BoxFuture<Self::Response, Self::Error>;
+
+    fn poll_ready(&mut self, _cx: &mut Context<'_>) -> Poll<Result<(), Self::Error>> {
+        Poll::Ready(Ok(()))
+    }
+
+    fn call(&mut self, request: http::Request<B>) -> Self::Future {
+        let gateway = self.gateway.clone();
+        match request.uri().path() {
+            "/kiteconnect.MarketData/SubscribeTicks" => Box::pin(async move {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(grpc
+                    .server_streaming(SubscribeTicks(gateway), request)
+                    .await)
+            }),
+            "/kiteconnect.MarketData/GetQuote" => Box::pin(async move {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(grpc.unary(GetQuote(gateway), request).await)
+            }),
+            "/kiteconnect.MarketData/PlaceOrder" => Box::pin(async move {
+                let mut grpc = Grpc::new(ProstCodec::default());
+                Ok(grpc.unary(PlaceOrder(gateway), request).await)
+            }),
+            _ => Box::pin(async move { Ok(Status::unimplemented("unknown method").into_http()) }),
+        }
+    }
+}
+
+struct SubscribeTicks(Arc<Gateway>);
+
+impl ServerStreamingService<SubscribeTicksRequest> for SubscribeTicks {
+    type Response = TickMessage;
+    type ResponseStream = BoxStream<TickMessage>;
+    type Future = BoxFuture<Response<Self::ResponseStream>, Status>;
+
+    fn call(&mut self, request: Request<SubscribeTicksRequest>) -> Self::Future {
+        let gateway = self.0.clone();
+        Box::pin(async move { gateway.subscribe_ticks(request).await })
+    }
+}
+
+struct GetQuote(Arc<Gateway>);
+
+impl UnaryService<GetQuoteRequest> for GetQuote {
+    type Response = GetQuoteResponse;
+    type Future = BoxFuture<Response<Self::Response>, Status>;
+
+    fn call(&mut self, request: Request<GetQuoteRequest>) -> Self::Future {
+        let gateway = self.0.clone();
+        Box::pin(async move { gateway.get_quote(request).await })
+    }
+}
+
+struct PlaceOrder(Arc<Gateway>);
+
+impl UnaryService<PlaceOrderRequest> for PlaceOrder {
+    type Response = PlaceOrderResponse;
+    type Future = BoxFuture<Response<Self::Response>, Status>;
+
+    fn call(&mut self, request: Request<PlaceOrderRequest>) -> Self::Future {
+        let gateway = self.0.clone();
+        Box::pin(async move { gateway.place_order(request).await })
+    }
+}
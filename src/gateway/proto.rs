@@ -0,0 +1,155 @@
+//! Request and response messages of the `kiteconnect.MarketData` service, following
+//! `proto/gateway.proto`. Ticks are streamed as [`codec::proto::Tick`](crate::codec::proto::Tick).
+
+use prost::Message;
+use std::collections::HashMap;
+
+use crate::codec::proto::{Depth, Ohlc, seconds};
+use crate::markets::QuoteData;
+use crate::orders::OrderParams;
+
+#[derive(Clone, PartialEq, Message)]
+pub struct SubscribeTicksRequest {
+    #[prost(uint32, repeated, tag = "1")]
+    pub instrument_tokens: Vec<u32>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct GetQuoteRequest {
+    /// Instruments as `exchange:tradingsymbol`, e.g. `NSE:INFY`.
+    #[prost(string, repeated, tag = "1")]
+    pub instruments: Vec<String>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct GetQuoteResponse {
+    #[prost(map = "string, message", tag = "1")]
+    pub quotes: HashMap<String, Quote>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct Quote {
+    #[prost(uint32, tag = "1")]
+    pub instrument_token: u32,
+    #[prost(int64, tag = "2")]
+    pub timestamp: i64,
+    #[prost(double, tag = "3")]
+    pub last_price: f64,
+    #[prost(uint32, tag = "4")]
+    pub last_quantity: u32,
+    #[prost(int64, tag = "5")]
+    pub last_trade_time: i64,
+    #[prost(double, tag = "6")]
+    pub average_price: f64,
+    #[prost(uint32, tag = "7")]
+    pub volume: u32,
+    #[prost(uint32, tag = "8")]
+    pub buy_quantity: u32,
+    #[prost(uint32, tag = "9")]
+    pub sell_quantity: u32,
+    #[prost(message, optional, tag = "10")]
+    pub ohlc: Option<Ohlc>,
+    #[prost(double, tag = "11")]
+    pub net_change: f64,
+    #[prost(double, tag = "12")]
+    pub oi: f64,
+    #[prost(double, tag = "13")]
+    pub oi_day_high: f64,
+    #[prost(double, tag = "14")]
+    pub oi_day_low: f64,
+    #[prost(double, tag = "15")]
+    pub lower_circuit_limit: f64,
+    #[prost(double, tag = "16")]
+    pub upper_circuit_limit: f64,
+    #[prost(message, optional, tag = "17")]
+    pub depth: Option<Depth>,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PlaceOrderRequest {
+    /// `regular` when empty.
+    #[prost(string, tag = "1")]
+    pub variety: String,
+    #[prost(string, tag = "2")]
+    pub exchange: String,
+    #[prost(string, tag = "3")]
+    pub tradingsymbol: String,
+    #[prost(string, tag = "4")]
+    pub transaction_type: String,
+    #[prost(string, tag = "5")]
+    pub order_type: String,
+    #[prost(string, tag = "6")]
+    pub product: String,
+    #[prost(string, tag = "7")]
+    pub validity: String,
+    #[prost(int32, tag = "8")]
+    pub quantity: i32,
+    #[prost(double, optional, tag = "9")]
+    pub price: Option<f64>,
+    #[prost(double, optional, tag = "10")]
+    pub trigger_price: Option<f64>,
+    #[prost(int32, optional, tag = "11")]
+    pub disclosed_quantity: Option<i32>,
+    #[prost(string, tag = "12")]
+    pub tag: String,
+}
+
+#[derive(Clone, PartialEq, Message)]
+pub struct PlaceOrderResponse {
+    #[prost(string, tag = "1")]
+    pub order_id: String,
+}
+
+impl From<&QuoteData> for Quote {
+    fn from(quote: &QuoteData) -> Self {
+        Self {
+            instrument_token: quote.instrument_token,
+            timestamp: seconds(&quote.timestamp),
+            last_price: quote.last_price,
+            last_quantity: quote.last_quantity,
+            last_trade_time: seconds(&quote.last_trade_time),
+            average_price: quote.average_price,
+            volume: quote.volume,
+            buy_quantity: quote.buy_quantity,
+            sell_quantity: quote.sell_quantity,
+            ohlc: Some((&quote.ohlc).into()),
+            net_change: quote.net_change,
+            oi: quote.oi,
+            oi_day_high: quote.oi_day_high,
+            oi_day_low: quote.oi_day_low,
+            lower_circuit_limit: quote.lower_circuit_limit,
+            upper_circuit_limit: quote.upper_circuit_limit,
+            depth: Some((&quote.depth).into()),
+        }
+    }
+}
+
+impl PlaceOrderRequest {
+    /// The variety, defaulting to `regular`.
+    pub fn variety(&self) -> &str {
+        if self.variety.is_empty() {
+            "regular"
+        } else {
+            &self.variety
+        }
+    }
+
+    /// Order params with empty fields left unset.
+    pub fn order_params(&self) -> OrderParams {
+        let non_empty = |s: &str| (!s.is_empty()).then(|| s.to_string());
+        OrderParams {
+            exchange: non_empty(&self.exchange),
+            tradingsymbol: non_empty(&self.tradingsymbol),
+            transaction_type: non_empty(&self.transaction_type),
+            order_type: non_empty(&self.order_type),
+            product: non_empty(&self.product),
+            validity: non_empty(&self.validity),
+            quantity: Some(self.quantity),
+            price: self.price,
+            trigger_price: self.trigger_price,
+            disclosed_quantity: self.disclosed_quantity,
+            tag: non_empty(&self.tag),
+            ..Default::default()
+        }
+    }
+}
@@ -0,0 +1,343 @@
+//! GTT (good till triggered) orders and bulk housekeeping.
+//!
+//! Besides the single-trigger calls, this module has helpers for the GTTs that pile up
+//! unnoticed: [`KiteConnect::get_gtts_expiring_within`] lists the ones about to lapse,
+//! [`orphaned_gtts`] finds exits whose position is already closed, and
+//! [`KiteConnect::delete_gtts`] and [`KiteConnect::modify_gtts`] act on many at once:
+//!
+//! ```ignore
+//! let gtts = kite.get_gtts().await?;
+//! tracker.refresh(&kite).await?;
+//! let stale: Vec<u64> = orphaned_gtts(&gtts, &tracker).iter().map(|g| g.id).collect();
+//! for result in kite.delete_gtts(&stale).await {
+//!     if let Err(error) = result.result {
+//!         eprintln!("GTT {} not deleted: {}", result.trigger_id, error);
+//!     }
+//! }
+//! ```
+//!
+//! Placing or modifying a GTT runs each of its orders through the client's risk checks,
+//! as if it were placed now. Placing, modifying and deleting a GTT are recorded in the
+//! audit log.
+
+use chrono::{DateTime, Utc};
+use reqwest::Method;
+use serde::ser::Error as _;
+use serde::{Deserialize, Serialize, Serializer};
+
+use crate::KiteConnect;
+use crate::audit::AuditAction;
+use crate::constants::Endpoints;
+use crate::labels::TransactionType;
+use crate::models::{KiteConnectError, time::Time};
+use crate::orders::OrderParams;
+use crate::portfolio_tracker::PortfolioTracker;
+use crate::usage::ApiCategory;
+
+/// GTT type with one trigger.
+pub const GTT_TYPE_SINGLE: &str = "single";
+/// GTT type with a stoploss and a target trigger, one cancelling the other.
+pub const GTT_TYPE_OCO: &str = "two-leg";
+
+/// Variety recorded in the audit log for GTT requests.
+pub(crate) const GTT_AUDIT_VARIETY: &str = "gtt";
+
+/// GttCondition is the instrument a GTT watches and its trigger prices.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GttCondition {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    #[serde(default)]
+    pub instrument_token: u32,
+    /// Last price when the GTT was placed or modified.
+    pub last_price: f64,
+    pub trigger_values: Vec<f64>,
+}
+
+/// GttOrder is an order a GTT places when its trigger is hit.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GttOrder {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub product: String,
+    pub order_type: String,
+    pub transaction_type: String,
+    pub quantity: i32,
+    pub price: f64,
+    /// Outcome of the order once triggered.
+    #[serde(default, skip_serializing)]
+    pub result: Option<serde_json::Value>,
+}
+
+impl GttOrder {
+    /// Parameters of the regular order placed when the trigger is hit.
+    pub fn order_params(&self) -> OrderParams {
+        OrderParams {
+            exchange: Some(self.exchange.clone()),
+            tradingsymbol: Some(self.tradingsymbol.clone()),
+            product: Some(self.product.clone()),
+            order_type: Some(self.order_type.clone()),
+            transaction_type: Some(self.transaction_type.clone()),
+            quantity: Some(self.quantity),
+            price: (self.price > 0.0).then_some(self.price),
+            ..Default::default()
+        }
+    }
+}
+
+/// Gtt represents a GTT trigger.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Gtt {
+    pub id: u64,
+    #[serde(default)]
+    pub user_id: String,
+    #[serde(default)]
+    pub parent_trigger: Option<serde_json::Value>,
+    pub r#type: String,
+    #[serde(default)]
+    pub created_at: Time,
+    #[serde(default)]
+    pub updated_at: Time,
+    #[serde(default)]
+    pub expires_at: Time,
+    pub status: String,
+    pub condition: GttCondition,
+    pub orders: Vec<GttOrder>,
+    #[serde(default)]
+    pub meta: Option<serde_json::Value>,
+}
+
+impl Gtt {
+    /// Whether the GTT is still waiting for its trigger.
+    pub fn is_active(&self) -> bool {
+        self.status == "active"
+    }
+
+    /// Whether the GTT is active and lapses within `days` days after `now`.
+    pub fn expires_within(&self, days: u32, now: DateTime<Utc>) -> bool {
+        let Some(expires_at) = self.expires_at.as_datetime() else {
+            return false;
+        };
+        self.is_active() && expires_at <= now + chrono::Duration::days(days as i64)
+    }
+
+    /// Whether the GTT closes a position rather than opening one: every two-leg GTT, and
+    /// single GTTs that sell.
+    pub fn is_exit(&self) -> bool {
+        self.r#type == GTT_TYPE_OCO
+            || self
+                .orders
+                .iter()
+                .all(|o| o.transaction_type == TransactionType::Sell.as_str())
+    }
+
+    /// Parameters that place or modify a GTT like this one.
+    pub fn params(&self) -> GttParams {
+        GttParams {
+            r#type: self.r#type.clone(),
+            condition: self.condition.clone(),
+            orders: self.orders.clone(),
+        }
+    }
+}
+
+/// GttParams are the parameters to place or modify a GTT.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GttParams {
+    /// [`GTT_TYPE_SINGLE`] or [`GTT_TYPE_OCO`].
+    pub r#type: String,
+    pub condition: GttCondition,
+    pub orders: Vec<GttOrder>,
+}
+
+// The API takes the condition and orders as JSON strings inside the form
+impl Serialize for GttParams {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        #[derive(Serialize)]
+        struct Form<'a> {
+            r#type: &'a str,
+            condition: String,
+            orders: String,
+        }
+        let condition = serde_json::to_string(&self.condition).map_err(S::Error::custom)?;
+        let orders = serde_json::to_string(&self.orders).map_err(S::Error::custom)?;
+        Form {
+            r#type: &self.r#type,
+            condition,
+            orders,
+        }
+        .serialize(serializer)
+    }
+}
+
+/// GttResponse is the response of placing, modifying or deleting a GTT.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GttResponse {
+    pub trigger_id: u64,
+}
+
+/// GttBulkResult is the outcome of one GTT in a bulk delete or modify.
+#[derive(Debug)]
+pub struct GttBulkResult {
+    pub trigger_id: u64,
+    pub result: Result<GttResponse, KiteConnectError>,
+}
+
+impl GttBulkResult {
+    pub fn is_ok(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
+/// Active exit GTTs, see [`Gtt::is_exit`], whose position is already closed: a sell with
+/// neither holdings nor a long net position in the symbol, or a two-leg buy without a
+/// short net position. Holdings count on either exchange, as delivery shares can be sold
+/// on both. `tracker` should be refreshed first.
+pub fn orphaned_gtts<'a>(gtts: &'a [Gtt], tracker: &PortfolioTracker) -> Vec<&'a Gtt> {
+    gtts.iter()
+        .filter(|gtt| gtt.is_active() && gtt.is_exit())
+        .filter(|gtt| {
+            let condition = &gtt.condition;
+            let position = tracker.position_quantity(&condition.exchange, &condition.tradingsymbol);
+            let sells = gtt
+                .orders
+                .iter()
+                .any(|o| o.transaction_type == TransactionType::Sell.as_str());
+            if sells {
+                position + tracker.holding_quantity(&condition.tradingsymbol) <= 0
+            } else {
+                position >= 0
+            }
+        })
+        .collect()
+}
+
+impl KiteConnect {
+    pub async fn get_gtts(&self) -> Result<Vec<Gtt>, KiteConnectError> {
+        self.get(Endpoints::GTT_TRIGGERS).await
+    }
+
+    pub async fn get_gtt(&self, trigger_id: u64) -> Result<Gtt, KiteConnectError> {
+        self.get(&gtt_endpoint(trigger_id)).await
+    }
+
+    pub async fn place_gtt(&self, params: &GttParams) -> Result<GttResponse, KiteConnectError> {
+        self.check_gtt(AuditAction::Place, None, params).await?;
+        let result = self.post_form(Endpoints::GTT_TRIGGERS, params).await;
+        let trigger_id = result
+            .as_ref()
+            .ok()
+            .map(|r: &GttResponse| r.trigger_id.to_string());
+        self.audit_request(
+            AuditAction::Place,
+            GTT_AUDIT_VARIETY,
+            trigger_id.as_deref(),
+            params,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    pub async fn modify_gtt(
+        &self,
+        trigger_id: u64,
+        params: &GttParams,
+    ) -> Result<GttResponse, KiteConnectError> {
+        let trigger_id_str = trigger_id.to_string();
+        self.check_gtt(AuditAction::Modify, Some(&trigger_id_str), params)
+            .await?;
+        let result = self.put_form(&gtt_endpoint(trigger_id), params).await;
+        self.audit_request(
+            AuditAction::Modify,
+            GTT_AUDIT_VARIETY,
+            Some(&trigger_id_str),
+            params,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Runs each order of a GTT through the risk checks, recording a rejected GTT in the
+    /// audit log.
+    async fn check_gtt(
+        &self,
+        action: AuditAction,
+        trigger_id: Option<&str>,
+        params: &GttParams,
+    ) -> Result<(), KiteConnectError> {
+        for order in &params.orders {
+            if let Err(e) = self.enforce_risk_limits(&order.order_params()).await {
+                let rejected: Result<GttResponse, _> = Err(e);
+                self.audit_request(action, GTT_AUDIT_VARIETY, trigger_id, params, &rejected)
+                    .await;
+                return rejected.map(|_| ());
+            }
+        }
+        Ok(())
+    }
+
+    pub async fn delete_gtt(&self, trigger_id: u64) -> Result<GttResponse, KiteConnectError> {
+        let result = self.delete(&gtt_endpoint(trigger_id)).await;
+        self.audit_request(
+            AuditAction::Cancel,
+            GTT_AUDIT_VARIETY,
+            Some(&trigger_id.to_string()),
+            &(),
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Active GTTs that lapse within `days` days, soonest first.
+    pub async fn get_gtts_expiring_within(&self, days: u32) -> Result<Vec<Gtt>, KiteConnectError> {
+        let now = Utc::now();
+        let mut gtts: Vec<Gtt> = self
+            .get_gtts()
+            .await?
+            .into_iter()
+            .filter(|gtt| gtt.expires_within(days, now))
+            .collect();
+        gtts.sort_by_key(|gtt| gtt.expires_at.as_datetime());
+        Ok(gtts)
+    }
+
+    /// Delete every GTT in `trigger_ids`, concurrently, as many at a time as the per-second
+    /// limit has room for. A failure does not stop the others; the results are in the order
+    /// of `trigger_ids`.
+    pub async fn delete_gtts(&self, trigger_ids: &[u64]) -> Vec<GttBulkResult> {
+        let trigger_ids = trigger_ids.to_vec();
+        self.within_rate_limit(gtt_category(), trigger_ids, |trigger_id| async move {
+            GttBulkResult {
+                trigger_id,
+                result: self.delete_gtt(trigger_id).await,
+            }
+        })
+        .await
+    }
+
+    /// Modify each GTT to its paired parameters, concurrently, as many at a time as the
+    /// per-second limit has room for. A failure does not stop the others; the results are
+    /// in the order of `updates`.
+    pub async fn modify_gtts(&self, updates: &[(u64, GttParams)]) -> Vec<GttBulkResult> {
+        let updates = updates.iter().collect();
+        self.within_rate_limit(gtt_category(), updates, |(trigger_id, params)| async move {
+            GttBulkResult {
+                trigger_id: *trigger_id,
+                result: self.modify_gtt(*trigger_id, params).await,
+            }
+        })
+        .await
+    }
+}
+
+// The rate limit GTT changes count against
+fn gtt_category() -> ApiCategory {
+    ApiCategory::of(&Method::DELETE, Endpoints::GTT_TRIGGER)
+}
+
+fn gtt_endpoint(trigger_id: u64) -> String {
+    Endpoints::GTT_TRIGGER.replace("{trigger_id}", &trigger_id.to_string())
+}
@@ -0,0 +1,273 @@
+//! Circuit limit and trading halt detection.
+//!
+//! A scrip at its upper or lower circuit limit trades one way only, and a halted scrip
+//! stops ticking altogether; orders placed on either sit unfilled or are rejected.
+//! [`HaltDetector`] watches the circuit limits from a quote against the live ticks and the
+//! gaps between ticks, and returns a [`HaltEvent`] when an instrument hits a limit, comes
+//! off it, goes silent or starts ticking again, so a strategy can pause its orders:
+//!
+//! ```ignore
+//! let mut halts = HaltDetector::new(Duration::from_secs(120)).market_clock(MarketClock::nse());
+//! halts.watch_quotes(&kite.get_quote(&["NSE:INFY"]).await?);
+//! loop {
+//!     select! {
+//!         Ok(event) = events.recv() => halts.on_event(&event),
+//!         _ = interval.tick() => halts.check(),
+//!     }
+//!     .into_iter()
+//!     .for_each(|halt| strategy.on_halt(halt));
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use web_time::{Duration, Instant};
+
+use crate::clock::MarketClock;
+use crate::markets::{Quote, QuoteData};
+use crate::models::Tick;
+use crate::ticker::TickerEvent;
+
+/// Which circuit limit a price is at.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum CircuitSide {
+    Upper,
+    Lower,
+}
+
+/// HaltEvent is a change in whether an instrument can trade normally.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum HaltEvent {
+    /// The last price reached a circuit limit.
+    CircuitHit {
+        instrument_token: u32,
+        side: CircuitSide,
+        last_price: f64,
+        limit: f64,
+    },
+    /// The last price moved back inside the circuit limits.
+    CircuitReleased {
+        instrument_token: u32,
+        last_price: f64,
+    },
+    /// No tick for longer than the detector's silence limit while the market was open.
+    Stalled {
+        instrument_token: u32,
+        silent_for_ms: u64,
+    },
+    /// A tick arrived for a stalled instrument.
+    Resumed { instrument_token: u32 },
+}
+
+impl HaltEvent {
+    pub fn instrument_token(&self) -> u32 {
+        match self {
+            HaltEvent::CircuitHit {
+                instrument_token, ..
+            }
+            | HaltEvent::CircuitReleased {
+                instrument_token, ..
+            }
+            | HaltEvent::Stalled {
+                instrument_token, ..
+            }
+            | HaltEvent::Resumed { instrument_token } => *instrument_token,
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Watched {
+    lower: f64,
+    upper: f64,
+    // Last tick, or when the silence count last restarted
+    last_seen: Instant,
+    circuit: Option<CircuitSide>,
+    stalled: bool,
+}
+
+/// The circuit limit `last_price` is at, and its price. Limits of 0, as on F&O
+/// contracts, are never hit.
+pub(crate) fn circuit_side(last_price: f64, lower: f64, upper: f64) -> Option<(CircuitSide, f64)> {
+    if upper > 0.0 && last_price >= upper {
+        Some((CircuitSide::Upper, upper))
+    } else if lower > 0.0 && last_price <= lower {
+        Some((CircuitSide::Lower, lower))
+    } else {
+        None
+    }
+}
+
+/// HaltDetector turns ticks and silences into [`HaltEvent`]s; see the
+/// [module docs](self).
+///
+/// Like [`AlertWatcher`](crate::alerts::AlertWatcher) it does not read the ticker itself:
+/// pass every event to [`on_event`](Self::on_event), and call [`check`](Self::check)
+/// every few seconds to catch instruments that stopped ticking. Each event is returned
+/// once per change.
+#[derive(Debug, Clone)]
+pub struct HaltDetector {
+    instruments: HashMap<u32, Watched>,
+    max_silence: Duration,
+    market_clock: Option<MarketClock>,
+}
+
+impl Default for HaltDetector {
+    fn default() -> Self {
+        Self::new(Duration::from_secs(120))
+    }
+}
+
+impl HaltDetector {
+    /// Flag instruments that go `max_silence` without a tick. Defaults to 2 minutes.
+    pub fn new(max_silence: Duration) -> Self {
+        Self {
+            instruments: HashMap::new(),
+            max_silence,
+            market_clock: None,
+        }
+    }
+
+    /// Only count silences while `clock` says the market is open. Without a clock every
+    /// silence counts, so set one unless the detector only runs during market hours.
+    pub fn market_clock(mut self, clock: MarketClock) -> Self {
+        self.market_clock = Some(clock);
+        self
+    }
+
+    /// Watch `instrument_token` with its day's circuit limits; 0 for no limit. Watching
+    /// an instrument again updates its limits.
+    pub fn watch(&mut self, instrument_token: u32, lower: f64, upper: f64) {
+        let watched = self
+            .instruments
+            .entry(instrument_token)
+            .or_insert_with(|| Watched {
+                lower,
+                upper,
+                last_seen: Instant::now(),
+                circuit: None,
+                stalled: false,
+            });
+        watched.lower = lower;
+        watched.upper = upper;
+    }
+
+    /// Watch every instrument of a `get_quote` response with its circuit limits. Returns
+    /// the instruments already at a limit.
+    pub fn watch_quotes(&mut self, quotes: &Quote) -> Vec<HaltEvent> {
+        let mut events = Vec::new();
+        let mut quotes: Vec<&QuoteData> = quotes.values().collect();
+        quotes.sort_by_key(|q| q.instrument_token);
+        for quote in quotes {
+            let token = quote.instrument_token;
+            self.watch(token, quote.lower_circuit_limit, quote.upper_circuit_limit);
+            events.extend(self.update_circuit(token, quote.last_price));
+        }
+        events
+    }
+
+    /// Stop watching `instrument_token`.
+    pub fn unwatch(&mut self, instrument_token: u32) {
+        self.instruments.remove(&instrument_token);
+    }
+
+    /// Feed a ticker event; ticks of watched instruments are checked and other events are
+    /// ignored.
+    pub fn on_event(&mut self, event: &TickerEvent) -> Vec<HaltEvent> {
+        match event {
+            TickerEvent::Tick(tick) => self.on_tick(tick),
+            _ => Vec::new(),
+        }
+    }
+
+    pub fn on_tick(&mut self, tick: &Tick) -> Vec<HaltEvent> {
+        let token = tick.instrument_token;
+        let Some(watched) = self.instruments.get_mut(&token) else {
+            return Vec::new();
+        };
+        watched.last_seen = Instant::now();
+        let mut events = Vec::new();
+        if watched.stalled {
+            watched.stalled = false;
+            events.push(HaltEvent::Resumed {
+                instrument_token: token,
+            });
+        }
+        events.extend(self.update_circuit(token, tick.last_price));
+        events
+    }
+
+    /// Flag watched instruments that have gone silent for too long.
+    pub fn check(&mut self) -> Vec<HaltEvent> {
+        let now = Instant::now();
+        let market_open = self.market_clock.as_ref().is_none_or(|c| c.is_open());
+        let mut events = Vec::new();
+        for (&token, watched) in &mut self.instruments {
+            if !market_open {
+                // Overnight and weekend gaps are not halts
+                watched.last_seen = now;
+                continue;
+            }
+            let silent_for = now.saturating_duration_since(watched.last_seen);
+            if !watched.stalled && silent_for > self.max_silence {
+                watched.stalled = true;
+                events.push(HaltEvent::Stalled {
+                    instrument_token: token,
+                    silent_for_ms: silent_for.as_millis() as u64,
+                });
+            }
+        }
+        events.sort_by_key(HaltEvent::instrument_token);
+        events
+    }
+
+    /// Whether `instrument_token` is at a circuit limit or stalled.
+    pub fn is_halted(&self, instrument_token: u32) -> bool {
+        self.instruments
+            .get(&instrument_token)
+            .is_some_and(|w| w.circuit.is_some() || w.stalled)
+    }
+
+    /// The circuit limit `instrument_token` is at, if any.
+    pub fn circuit(&self, instrument_token: u32) -> Option<CircuitSide> {
+        self.instruments
+            .get(&instrument_token)
+            .and_then(|w| w.circuit)
+    }
+
+    /// Watched instruments that are at a circuit limit or stalled, in token order.
+    pub fn halted(&self) -> Vec<u32> {
+        let mut tokens: Vec<u32> = self
+            .instruments
+            .keys()
+            .copied()
+            .filter(|&token| self.is_halted(token))
+            .collect();
+        tokens.sort_unstable();
+        tokens
+    }
+
+    fn update_circuit(&mut self, token: u32, last_price: f64) -> Option<HaltEvent> {
+        let watched = self.instruments.get_mut(&token)?;
+        let at = circuit_side(last_price, watched.lower, watched.upper);
+        let side = at.map(|(side, _)| side);
+        if side == watched.circuit {
+            return None;
+        }
+        watched.circuit = side;
+        Some(match at {
+            Some((side, limit)) => HaltEvent::CircuitHit {
+                instrument_token: token,
+                side,
+                last_price,
+                limit,
+            },
+            None => HaltEvent::CircuitReleased {
+                instrument_token: token,
+                last_price,
+            },
+        })
+    }
+}
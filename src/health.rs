@@ -0,0 +1,190 @@
+//! Health checks for services built on the client and ticker.
+//!
+//! A [`HealthMonitor`] combines the ticker connection, the age of the last tick, whether
+//! Kite rejected the access token and how close requests are to Kite's rate limits into
+//! a [`HealthState`]. Taking one only reads shared state and sends no requests, so it can
+//! back Kubernetes liveness and readiness probes that run every few seconds.
+//!
+//! The two probes ask different questions. The service is not ready while any problem is
+//! found, so traffic stays away during a reconnect, a rejected token or throttling, which
+//! clear on their own or need an operator. It is only not live when the ticker is
+//! connected but ticks stopped during market hours, a hung feed that a restart fixes:
+//!
+//! ```ignore
+//! let health = HealthMonitor::new()
+//!     .kite(kite.clone())
+//!     .ticker(ticker_handle.clone())
+//!     .market_clock(MarketClock::nse());
+//!
+//! // In the probe handlers
+//! let state = health.state();
+//! (StatusCode::from_u16(state.liveness_status())?, Json(state))
+//! (StatusCode::from_u16(state.readiness_status())?, Json(state))
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use web_time::Duration;
+
+use crate::KiteConnect;
+use crate::clock::MarketClock;
+use crate::ticker::TickerHandle;
+
+/// HealthState is a point-in-time view of the service's health, serializable as the body
+/// of a probe response.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct HealthState {
+    /// False when the feed is stalled, which a restart fixes.
+    pub live: bool,
+    /// False when any problem is found.
+    pub ready: bool,
+    /// `None` when no ticker is monitored.
+    pub ticker_connected: Option<bool>,
+    /// Milliseconds since the last tick, `None` before the first one.
+    pub last_tick_age_ms: Option<u64>,
+    /// `None` when no client is monitored.
+    pub authenticated: Option<bool>,
+    /// Highest fraction of any Kite rate limit in use, e.g. `0.5` at half of a limit.
+    pub rate_limit_utilization: Option<f64>,
+    /// Why the state is not ready; empty when ready.
+    pub problems: Vec<String>,
+    pub checked_at: DateTime<Utc>,
+}
+
+impl HealthState {
+    pub fn is_live(&self) -> bool {
+        self.live
+    }
+
+    pub fn is_ready(&self) -> bool {
+        self.ready
+    }
+
+    /// HTTP status for a liveness probe: 200 when live, else 503.
+    pub fn liveness_status(&self) -> u16 {
+        if self.live { 200 } else { 503 }
+    }
+
+    /// HTTP status for a readiness probe: 200 when ready, else 503.
+    pub fn readiness_status(&self) -> u16 {
+        if self.ready { 200 } else { 503 }
+    }
+}
+
+/// HealthMonitor takes [`HealthState`]s of a client and ticker; see the
+/// [module docs](self).
+#[derive(Clone)]
+pub struct HealthMonitor {
+    kite: Option<KiteConnect>,
+    ticker: Option<TickerHandle>,
+    max_tick_age: Duration,
+    max_rate_limit_utilization: f64,
+    market_clock: Option<MarketClock>,
+}
+
+impl Default for HealthMonitor {
+    fn default() -> Self {
+        Self {
+            kite: None,
+            ticker: None,
+            max_tick_age: Duration::from_secs(60),
+            max_rate_limit_utilization: 0.9,
+            market_clock: None,
+        }
+    }
+}
+
+impl HealthMonitor {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check the access token and rate limit usage of `kite`.
+    pub fn kite(mut self, kite: KiteConnect) -> Self {
+        self.kite = Some(kite);
+        self
+    }
+
+    /// Check the connection and tick flow of the ticker behind `handle`.
+    pub fn ticker(mut self, handle: TickerHandle) -> Self {
+        self.ticker = Some(handle);
+        self
+    }
+
+    /// Longest gap between ticks while connected before the feed counts as stalled.
+    /// Defaults to 60 seconds.
+    pub fn max_tick_age(mut self, age: Duration) -> Self {
+        self.max_tick_age = age;
+        self
+    }
+
+    /// Rate limit utilization at or above which the client counts as throttled.
+    /// Defaults to `0.9`.
+    pub fn max_rate_limit_utilization(mut self, utilization: f64) -> Self {
+        self.max_rate_limit_utilization = utilization;
+        self
+    }
+
+    /// Only hold the tick age against the feed while `clock` says the market is open.
+    pub fn market_clock(mut self, clock: MarketClock) -> Self {
+        self.market_clock = Some(clock);
+        self
+    }
+
+    pub fn state(&self) -> HealthState {
+        let mut problems = Vec::new();
+        let mut live = true;
+
+        let mut ticker_connected = None;
+        let mut last_tick_age = None;
+        if let Some(ticker) = &self.ticker {
+            let connected = ticker.is_connected();
+            last_tick_age = ticker.last_tick_age();
+            let market_open = self.market_clock.as_ref().is_none_or(|c| c.is_open());
+            if !connected {
+                problems.push("ticker is disconnected".to_string());
+            } else if let Some(age) = last_tick_age.filter(|age| *age > self.max_tick_age) {
+                if market_open {
+                    problems.push(format!("no tick for {}s", age.as_secs()));
+                    live = false;
+                }
+            }
+            ticker_connected = Some(connected);
+        }
+
+        let mut authenticated = None;
+        let mut rate_limit_utilization = None;
+        if let Some(kite) = &self.kite {
+            let valid = kite.is_authenticated();
+            if !valid {
+                problems.push("access token is missing or was rejected".to_string());
+            }
+            let usage = kite.usage_stats();
+            for category in usage.near_limit(self.max_rate_limit_utilization) {
+                problems.push(format!(
+                    "{:?} requests at {:.0}% of the rate limit",
+                    category.category,
+                    category.utilization() * 100.0
+                ));
+            }
+            let highest = usage
+                .categories
+                .iter()
+                .map(|category| category.utilization())
+                .fold(0.0, f64::max);
+            authenticated = Some(valid);
+            rate_limit_utilization = Some(highest);
+        }
+
+        HealthState {
+            live,
+            ready: problems.is_empty(),
+            ticker_connected,
+            last_tick_age_ms: last_tick_age.map(|age| age.as_millis() as u64),
+            authenticated,
+            rate_limit_utilization,
+            problems,
+            checked_at: Utc::now(),
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use reqwest::{
-    Method, Response,
+    Method,
     header::{HeaderMap, HeaderValue},
 };
 use serde::{
@@ -7,14 +7,24 @@ use serde::{
     de::{DeserializeOwned, Error},
 };
 use std::collections::HashMap;
+use std::sync::atomic::Ordering;
+use zeroize::Zeroizing;
 
 use crate::{
     KiteConnect,
     KiteConnectErrorKind::SerializationError,
     constants::app_constants::*,
-    models::{KiteConnectError, KiteError},
+    models::{KiteConnectError, KiteConnectErrorKind, KiteError},
+    transport::TransportResponse,
 };
 
+/// Path prefixes whose mutating requests a read-only client refuses.
+const READ_ONLY_BLOCKED_PREFIXES: [&str; 5] =
+    ["/orders", "/gtt", "/alerts", "/mf/orders", "/mf/sips"];
+
+/// Position conversion, refused by a read-only client.
+const CONVERT_POSITION_PATH: &str = "/portfolio/positions";
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 struct ApiResponse<T> {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -40,16 +50,10 @@ impl KiteConnect {
     where
         T: DeserializeOwned,
     {
+        self.check_read_only(&method, endpoint)?;
         let url = format!("{}{}", self.base_url, endpoint);
-        let mut request_headers = self.get_default_headers()?;
-
-        // Add Authorization header if access token is available
-        if let Some(ref token) = self.access_token {
-            request_headers.insert(
-                "Authorization",
-                HeaderValue::from_str(&format!("token {}:{}", self.api_key, token))?,
-            );
-        }
+        self.usage.record(&method, endpoint);
+        let mut request_headers = self.request_headers(endpoint).await?;
 
         // Merge custom headers if provided
         if let Some(custom_headers) = headers {
@@ -60,9 +64,14 @@ impl KiteConnect {
 
         let mut request_builder = self
             .http_client
-            .request(method, &url)
+            .request(method.clone(), &url)
             .headers(request_headers);
 
+        #[cfg(not(target_arch = "wasm32"))]
+        if let Some(timeout) = self.request_timeout {
+            request_builder = request_builder.timeout(timeout);
+        }
+
         // Handle query parameters if present
         if let Some(query) = query_params {
             request_builder = request_builder.query(&query);
@@ -80,17 +89,131 @@ impl KiteConnect {
             }
         }
 
-        let response = request_builder.send().await?;
-        self.handle_response(response).await
+        let request = request_builder.build()?;
+        #[cfg(all(feature = "prometheus", not(target_arch = "wasm32")))]
+        let started = web_time::Instant::now();
+        let response = match &self.transport {
+            Some(transport) => transport.execute(request).await,
+            None => match self.http_client.execute(request).await {
+                Ok(response) => {
+                    TransportResponse::read_limited(response, self.max_response_size).await
+                }
+                Err(e) => Err(e.into()),
+            },
+        };
+        #[cfg(all(feature = "prometheus", not(target_arch = "wasm32")))]
+        crate::metrics::record_request(
+            &method,
+            endpoint,
+            response.as_ref().ok().map(|r| r.status),
+            started.elapsed(),
+        );
+        // A mutating request that may have reached Kite cannot be retried blindly; a dropped
+        // connection before sending or a rate limited request is safe to retry
+        let write = method != Method::GET;
+        let response = response.map_err(|e| match &e.kind {
+            KiteConnectErrorKind::HttpError(error) if write && may_have_sent(error) => {
+                e.into_ambiguous()
+            }
+            _ => e,
+        })?;
+        let server_error = response.status.is_server_error();
+        let response = self.adapt_response(endpoint, response)?;
+        let mut result = self.handle_response(response);
+        if write && server_error {
+            result = result.map_err(KiteConnectError::into_ambiguous);
+        }
+        if let Err(KiteConnectError {
+            kind: KiteConnectErrorKind::TradingBlocked(error),
+            ..
+        }) = &result
+        {
+            self.block_trading(error.as_ref().clone());
+        }
+        // Note a rejected token, dropping a provided one so the next request fetches a new one
+        match &result {
+            Err(KiteConnectError {
+                kind: KiteConnectErrorKind::ApiError(error),
+                ..
+            }) if error.error_type == "TokenException" => {
+                self.token_rejected.store(true, Ordering::Relaxed);
+                if self.token_provider.is_some() {
+                    *self.access_token.write().unwrap_or_else(|e| e.into_inner()) = None;
+                }
+            }
+            Ok(_) => {
+                self.token_rejected.store(false, Ordering::Relaxed);
+            }
+            Err(_) => {}
+        }
+        result
+    }
+
+    // Refuse a mutating trading request on a read-only client before it is sent
+    fn check_read_only(&self, method: &Method, endpoint: &str) -> Result<(), KiteConnectError> {
+        if !self.read_only || method == Method::GET {
+            return Ok(());
+        }
+        let path = endpoint.split('?').next().unwrap_or_default();
+        let under = |prefix: &str| {
+            path == prefix
+                || path
+                    .strip_prefix(prefix)
+                    .is_some_and(|rest| rest.starts_with('/'))
+        };
+        if READ_ONLY_BLOCKED_PREFIXES
+            .iter()
+            .any(|prefix| under(prefix))
+            || path == CONVERT_POSITION_PATH
+        {
+            return Err(KiteConnectError::read_only_mode(method, endpoint));
+        }
+        Ok(())
+    }
+
+    /// Default headers for `endpoint` plus the Authorization header when an access token
+    /// is available
+    pub(crate) async fn request_headers(
+        &self,
+        endpoint: &str,
+    ) -> Result<HeaderMap, KiteConnectError> {
+        let mut headers = self.get_default_headers(endpoint)?;
+
+        // Fetch a token from the provider when none is cached
+        if self.token_provider.is_some() && self.access_token().is_none() {
+            self.refresh_provided_token().await?;
+        }
+
+        if let Some(token) = self.access_token() {
+            let authorization =
+                Zeroizing::new(format!("token {}:{}", self.api_key, token.as_str()));
+            let mut value = HeaderValue::from_str(&authorization)?;
+            // Keeps the token out of the Debug output of the request
+            value.set_sensitive(true);
+            headers.insert("Authorization", value);
+        }
+        Ok(headers)
     }
 
     /// Handle the response and parse it into the expected type
-    async fn handle_response<T>(&self, response: Response) -> Result<T, KiteConnectError>
+    pub(crate) fn handle_response<T>(
+        &self,
+        response: TransportResponse,
+    ) -> Result<T, KiteConnectError>
     where
         T: DeserializeOwned,
     {
-        let status = response.status();
-        let response_text = response.text().await?;
+        let TransportResponse {
+            status,
+            body: response_text,
+        } = response;
+
+        // Custom transports buffer the whole body, so it can only be checked here
+        if let Some(limit) = self.max_response_size {
+            if response_text.len() > limit {
+                return Err(KiteConnectError::response_too_large(limit));
+            }
+        }
 
         if status.is_success() {
             // Try to parse as wrapped response first
@@ -117,17 +240,39 @@ impl KiteConnect {
             }
         } else {
             // Parse error response
-            let error: KiteError = serde_json::from_str(&response_text)?;
-            Err(error.into())
+            match serde_json::from_str::<KiteError>(&response_text) {
+                Ok(error) => Err(error.into()),
+                // Proxies and load balancers answer rate limiting and outages without a
+                // Kite envelope; keep those retriable
+                Err(_) if status.is_server_error() || status.as_u16() == 429 => {
+                    let error_type = if status.as_u16() == 429 {
+                        "NetworkException"
+                    } else {
+                        "GeneralException"
+                    };
+                    Err(KiteError {
+                        status: "error".to_string(),
+                        message: format!(
+                            "HTTP {}: {}",
+                            status,
+                            response_text.chars().take(200).collect::<String>()
+                        ),
+                        data: None,
+                        error_type: error_type.to_string(),
+                    }
+                    .into())
+                }
+                Err(e) => Err(e.into()),
+            }
         }
     }
 
-    /// Get default headers for all requests
-    fn get_default_headers(&self) -> Result<HeaderMap, KiteConnectError> {
+    /// Get default headers for requests to `endpoint`
+    fn get_default_headers(&self, endpoint: &str) -> Result<HeaderMap, KiteConnectError> {
         let mut headers = HeaderMap::new();
         headers.insert(
             "X-Kite-Version",
-            HeaderValue::from_static(KITE_HEADER_VERSION),
+            HeaderValue::from(self.api_version_for(endpoint).as_u16()),
         );
 
         let user_agent = HeaderValue::from_str(&format!(
@@ -290,3 +435,16 @@ impl KiteConnect {
             .await
     }
 }
+
+// Whether a failed request may have reached the server: anything but a refused connection
+fn may_have_sent(error: &reqwest::Error) -> bool {
+    #[cfg(not(target_arch = "wasm32"))]
+    {
+        !error.is_connect()
+    }
+    #[cfg(target_arch = "wasm32")]
+    {
+        let _ = error;
+        true
+    }
+}
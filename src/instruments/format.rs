@@ -0,0 +1,88 @@
+//! Displaying prices the way the exchange quotes them.
+//!
+//! Currency derivatives on CDS and BCD are quoted to 4 decimals, MCX contracts to their
+//! tick size (whole rupees for most of them) and everything else to 2 decimals.
+//! [`format_price`] rounds a price to the instrument's tick and prints it with those
+//! decimals; [`PriceFormatter`] does the same for instruments looked up in a store.
+
+use std::sync::Arc;
+
+use super::InstrumentStore;
+use super::rounding::round_to_tick;
+use crate::markets::Instrument;
+
+// Decimals used when the instrument is unknown
+const DEFAULT_DECIMALS: usize = 2;
+const CURRENCY_DECIMALS: usize = 4;
+const MAX_DECIMALS: usize = 6;
+
+/// Number of decimals prices of `instrument` are displayed with.
+pub fn price_decimals(instrument: &Instrument) -> usize {
+    let tick = tick_decimals(instrument.tick_size);
+    if is_currency(instrument) {
+        tick.max(CURRENCY_DECIMALS)
+    } else if instrument.exchange == "MCX" {
+        tick
+    } else {
+        tick.max(DEFAULT_DECIMALS)
+    }
+}
+
+/// `price` rounded to the tick size of `instrument` and printed with
+/// [`price_decimals`] decimals.
+pub fn format_price(instrument: &Instrument, price: f64) -> String {
+    format!(
+        "{:.*}",
+        price_decimals(instrument),
+        round_to_tick(price, instrument)
+    )
+}
+
+/// PriceFormatter formats prices of the instruments in a store.
+#[derive(Debug, Clone)]
+pub struct PriceFormatter {
+    instruments: Arc<InstrumentStore>,
+}
+
+impl PriceFormatter {
+    pub fn new(instruments: Arc<InstrumentStore>) -> Self {
+        Self { instruments }
+    }
+
+    /// [`format_price`] for `instrument_token`, or `price` with 2 decimals when the
+    /// instrument is not in the store.
+    pub fn format(&self, instrument_token: u32, price: f64) -> String {
+        match self.instruments.get(instrument_token) {
+            Some(instrument) => format_price(instrument, price),
+            None => format!("{:.*}", DEFAULT_DECIMALS, price),
+        }
+    }
+
+    /// [`format_price`] for `EXCHANGE:TRADINGSYMBOL`, or `price` with 2 decimals when the
+    /// instrument is not in the store.
+    pub fn format_symbol(&self, exchange: &str, tradingsymbol: &str, price: f64) -> String {
+        match self.instruments.get_by_symbol(exchange, tradingsymbol) {
+            Some(instrument) => format_price(instrument, price),
+            None => format!("{:.*}", DEFAULT_DECIMALS, price),
+        }
+    }
+}
+
+fn is_currency(instrument: &Instrument) -> bool {
+    ["CDS", "BCD"]
+        .iter()
+        .any(|prefix| instrument.exchange == *prefix || instrument.segment.starts_with(prefix))
+}
+
+// Fewest decimals that represent `tick_size` exactly
+pub(crate) fn tick_decimals(tick_size: f64) -> usize {
+    if tick_size <= 0.0 {
+        return DEFAULT_DECIMALS;
+    }
+    (0..MAX_DECIMALS)
+        .find(|&decimals| {
+            let scaled = tick_size * 10f64.powi(decimals as i32);
+            (scaled - scaled.round()).abs() < 1e-6
+        })
+        .unwrap_or(MAX_DECIMALS)
+}
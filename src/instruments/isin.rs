@@ -0,0 +1,232 @@
+//! The same security across exchanges, keyed by ISIN.
+//!
+//! A stock listed on both NSE and BSE has one ISIN but a trading symbol, and always an
+//! instrument token, per exchange. [`IsinMap`] groups the listings of each ISIN so a quote
+//! or order can move to the other exchange, e.g. when the NSE scrip is stuck at a circuit
+//! limit. The instrument dump has no ISIN column, so the ISINs come from elsewhere, usually
+//! holdings, and the map links them to instruments in the dump. Listings are only grouped
+//! by the ISINs given; a BSE scrip is never assumed to be the NSE one because it shares its
+//! trading symbol:
+//!
+//! ```ignore
+//! let map = IsinMap::from_holdings(&store, &kite.get_holdings().await?);
+//! let (listing, quote) = kite
+//!     .get_quote_by_isin(&map, "INE009A01021", &ExchangePolicy::nse_first())
+//!     .await?;
+//! ```
+
+use std::collections::{HashMap, HashSet};
+
+use super::{InstrumentStore, symbol_key};
+use crate::KiteConnect;
+use crate::markets::QuoteData;
+use crate::models::KiteConnectError;
+use crate::portfolio::Holding;
+
+/// ListedSymbol is one listing of an ISIN.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ListedSymbol {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub instrument_token: u32,
+}
+
+impl ListedSymbol {
+    /// `EXCHANGE:TRADINGSYMBOL`, as used by the quote APIs.
+    pub fn key(&self) -> String {
+        symbol_key(&self.exchange, &self.tradingsymbol)
+    }
+}
+
+/// ExchangePolicy orders the listings [`IsinMap::preferred_exchange`] picks from and
+/// marks the ones to skip.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ExchangePolicy {
+    /// Exchanges in order of preference. Listings on other exchanges are never picked.
+    pub exchanges: Vec<String>,
+    /// `EXCHANGE:TRADINGSYMBOL` of listings that are halted or otherwise not tradable.
+    pub unavailable: HashSet<String>,
+}
+
+impl Default for ExchangePolicy {
+    fn default() -> Self {
+        Self::nse_first()
+    }
+}
+
+impl ExchangePolicy {
+    pub fn nse_first() -> Self {
+        Self::new(["NSE", "BSE"])
+    }
+
+    pub fn bse_first() -> Self {
+        Self::new(["BSE", "NSE"])
+    }
+
+    pub fn new<I, S>(exchanges: I) -> Self
+    where
+        I: IntoIterator<Item = S>,
+        S: Into<String>,
+    {
+        Self {
+            exchanges: exchanges.into_iter().map(Into::into).collect(),
+            unavailable: HashSet::new(),
+        }
+    }
+
+    /// Skip the listing of `tradingsymbol` on `exchange`.
+    pub fn unavailable(mut self, exchange: &str, tradingsymbol: &str) -> Self {
+        self.unavailable.insert(symbol_key(exchange, tradingsymbol));
+        self
+    }
+
+    fn rank(&self, listing: &ListedSymbol) -> Option<usize> {
+        if self.unavailable.contains(&listing.key()) {
+            return None;
+        }
+        self.exchanges.iter().position(|e| *e == listing.exchange)
+    }
+}
+
+/// IsinMap maps ISINs to their listings and back; see the [module docs](self).
+#[derive(Debug, Clone, Default)]
+pub struct IsinMap {
+    by_isin: HashMap<String, Vec<ListedSymbol>>,
+    // `EXCHANGE:TRADINGSYMBOL` to ISIN
+    by_symbol: HashMap<String, String>,
+}
+
+impl IsinMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Map the `(exchange, tradingsymbol, isin)` triples found in `store`. Each listing,
+    /// on NSE and BSE alike, needs a triple of its own.
+    pub fn build<I, S>(store: &InstrumentStore, isins: I) -> Self
+    where
+        I: IntoIterator<Item = (S, S, S)>,
+        S: AsRef<str>,
+    {
+        let mut map = Self::new();
+        for (exchange, tradingsymbol, isin) in isins {
+            let (exchange, tradingsymbol, isin) =
+                (exchange.as_ref(), tradingsymbol.as_ref(), isin.as_ref());
+            if let Some(token) = store.token_of(exchange, tradingsymbol) {
+                map.insert(isin, exchange, tradingsymbol, token);
+            }
+        }
+        map
+    }
+
+    /// Map the ISINs of `holdings`, which carry one for every stock held.
+    pub fn from_holdings(store: &InstrumentStore, holdings: &[Holding]) -> Self {
+        Self::build(
+            store,
+            holdings.iter().filter(|h| !h.isin.is_empty()).map(|h| {
+                (
+                    h.exchange.as_str(),
+                    h.tradingsymbol.as_str(),
+                    h.isin.as_str(),
+                )
+            }),
+        )
+    }
+
+    /// Add a listing of `isin`. A symbol already mapped to another ISIN moves to this one.
+    pub fn insert(
+        &mut self,
+        isin: &str,
+        exchange: &str,
+        tradingsymbol: &str,
+        instrument_token: u32,
+    ) {
+        let key = symbol_key(exchange, tradingsymbol);
+        if let Some(previous) = self.by_symbol.insert(key.clone(), isin.to_string()) {
+            if let Some(listings) = self.by_isin.get_mut(&previous) {
+                listings.retain(|l| l.key() != key);
+                if listings.is_empty() {
+                    self.by_isin.remove(&previous);
+                }
+            }
+        }
+        self.by_isin
+            .entry(isin.to_string())
+            .or_default()
+            .push(ListedSymbol {
+                exchange: exchange.to_string(),
+                tradingsymbol: tradingsymbol.to_string(),
+                instrument_token,
+            });
+    }
+
+    /// Every listing of `isin`, in the order they were mapped.
+    pub fn equivalent_symbols(&self, isin: &str) -> &[ListedSymbol] {
+        self.by_isin.get(isin).map_or(&[], Vec::as_slice)
+    }
+
+    pub fn isin_of(&self, exchange: &str, tradingsymbol: &str) -> Option<&str> {
+        self.by_symbol
+            .get(&symbol_key(exchange, tradingsymbol))
+            .map(String::as_str)
+    }
+
+    /// The listing of `isin` on the first exchange in `policy` whose listing is not marked
+    /// unavailable.
+    pub fn preferred_exchange(&self, isin: &str, policy: &ExchangePolicy) -> Option<&ListedSymbol> {
+        self.equivalent_symbols(isin)
+            .iter()
+            .filter_map(|listing| policy.rank(listing).map(|rank| (rank, listing)))
+            .min_by_key(|(rank, _)| *rank)
+            .map(|(_, listing)| listing)
+    }
+
+    /// Number of ISINs mapped.
+    pub fn len(&self) -> usize {
+        self.by_isin.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.by_isin.is_empty()
+    }
+}
+
+impl KiteConnect {
+    /// Quote `isin` on the first exchange in `policy` where it can trade. All listings are
+    /// quoted in one request; a listing marked unavailable or whose last price is at a
+    /// circuit limit is passed over for the next one.
+    pub async fn get_quote_by_isin(
+        &self,
+        map: &IsinMap,
+        isin: &str,
+        policy: &ExchangePolicy,
+    ) -> Result<(ListedSymbol, QuoteData), KiteConnectError> {
+        let mut listings: Vec<(usize, &ListedSymbol)> = map
+            .equivalent_symbols(isin)
+            .iter()
+            .filter_map(|listing| policy.rank(listing).map(|rank| (rank, listing)))
+            .collect();
+        if listings.is_empty() {
+            return Err(KiteConnectError::invalid_params(format!(
+                "no tradable listing of {} on {:?}",
+                isin, policy.exchanges
+            )));
+        }
+        listings.sort_by_key(|(rank, _)| *rank);
+
+        let keys: Vec<String> = listings.iter().map(|(_, l)| l.key()).collect();
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let mut quotes = self.get_quote(&keys).await?;
+        for (_, listing) in &listings {
+            if let Some(quote) = quotes.remove(&listing.key()) {
+                if !quote.at_circuit_limit() {
+                    return Ok(((*listing).clone(), quote));
+                }
+            }
+        }
+        Err(KiteConnectError::other(format!(
+            "every listing of {} is at a circuit limit or has no quote",
+            isin
+        )))
+    }
+}
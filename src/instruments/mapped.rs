@@ -0,0 +1,255 @@
+use fst::Map;
+use memmap2::Mmap;
+use serde::{Deserialize, Serialize};
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use web_time::{Duration, SystemTime, UNIX_EPOCH};
+
+use super::{InstrumentStore, symbol_key};
+use crate::markets::Instrument;
+use crate::models::{KiteConnectError, time::Time};
+
+/// File magic, the last byte is the format version.
+const MAGIC: &[u8; 8] = b"KITEINS\x01";
+/// Magic, save time in unix seconds and index length.
+const HEADER_LEN: usize = 24;
+
+/// Instrument as written to disk. `Time` does not round-trip through bincode.
+#[derive(Serialize, Deserialize)]
+struct Record {
+    instrument_token: u32,
+    exchange_token: u32,
+    tradingsymbol: String,
+    name: String,
+    last_price: f64,
+    expiry: Option<i64>,
+    strike: f64,
+    tick_size: f64,
+    lot_size: f64,
+    instrument_type: String,
+    segment: String,
+    exchange: String,
+}
+
+impl From<&Instrument> for Record {
+    fn from(instrument: &Instrument) -> Self {
+        Record {
+            instrument_token: instrument.instrument_token,
+            exchange_token: instrument.exchange_token,
+            tradingsymbol: instrument.tradingsymbol.clone(),
+            name: instrument.name.clone(),
+            last_price: instrument.last_price,
+            expiry: instrument.expiry.as_datetime().map(|dt| dt.timestamp()),
+            strike: instrument.strike,
+            tick_size: instrument.tick_size,
+            lot_size: instrument.lot_size,
+            instrument_type: instrument.instrument_type.clone(),
+            segment: instrument.segment.clone(),
+            exchange: instrument.exchange.clone(),
+        }
+    }
+}
+
+impl From<Record> for Instrument {
+    fn from(record: Record) -> Self {
+        Instrument {
+            instrument_token: record.instrument_token,
+            exchange_token: record.exchange_token,
+            tradingsymbol: record.tradingsymbol,
+            name: record.name,
+            last_price: record.last_price,
+            expiry: record.expiry.map(Time::from_timestamp).unwrap_or_default(),
+            strike: record.strike,
+            tick_size: record.tick_size,
+            lot_size: record.lot_size,
+            instrument_type: record.instrument_type,
+            segment: record.segment,
+            exchange: record.exchange,
+        }
+    }
+}
+
+/// A byte range of the mapped file, so the index can share the mapping.
+#[derive(Clone)]
+struct Slice {
+    mmap: Arc<Mmap>,
+    start: usize,
+    end: usize,
+}
+
+impl AsRef<[u8]> for Slice {
+    fn as_ref(&self) -> &[u8] {
+        &self.mmap[self.start..self.end]
+    }
+}
+
+impl InstrumentStore {
+    /// Write the store to `path` in the format read by [`MappedInstruments::open`].
+    ///
+    /// The file holds a header, an `fst` index from `EXCHANGE:TRADINGSYMBOL` to record
+    /// offset, and the bincode encoded instruments. It is synced to disk and then renamed
+    /// into place, replacing any previous dump.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KiteConnectError> {
+        let mut records = Vec::new();
+        let mut offsets: Vec<(String, u64)> = Vec::with_capacity(self.by_symbol.len());
+        for (i, instrument) in self.instruments.iter().enumerate() {
+            let key = symbol_key(&instrument.exchange, &instrument.tradingsymbol);
+            // Only the instrument a symbol lookup would return
+            if self.by_symbol.get(&key) != Some(&i) {
+                continue;
+            }
+            offsets.push((key, records.len() as u64));
+            bincode::serialize_into(&mut records, &Record::from(instrument))
+                .map_err(encode_error)?;
+        }
+        // fst keys must be inserted in order
+        offsets.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        let index = Map::from_iter(offsets).map_err(index_error)?;
+        let index = index.as_fst().as_bytes();
+
+        let saved_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        // Written under a temporary name in the same directory and renamed, so a crash
+        // never leaves a truncated dump at `path`
+        let path = path.as_ref();
+        let mut partial = path.as_os_str().to_owned();
+        partial.push(".partial");
+        let partial = PathBuf::from(partial);
+        let write = || -> std::io::Result<()> {
+            let mut out = BufWriter::new(File::create(&partial)?);
+            out.write_all(MAGIC)?;
+            out.write_all(&saved_at.to_le_bytes())?;
+            out.write_all(&(index.len() as u64).to_le_bytes())?;
+            out.write_all(index)?;
+            out.write_all(&records)?;
+            out.flush()?;
+            out.get_ref().sync_all()
+        };
+        if let Err(e) = write() {
+            let _ = std::fs::remove_file(&partial);
+            return Err(io_error(e));
+        }
+        std::fs::rename(&partial, path).map_err(io_error)
+    }
+}
+
+/// MappedInstruments is a memory-mapped instrument dump written by [`InstrumentStore::save`].
+///
+/// Opening does not read the instruments, lookups decode only the matching record.
+/// Clones share the mapping.
+#[derive(Clone)]
+pub struct MappedInstruments {
+    index: Map<Slice>,
+    mmap: Arc<Mmap>,
+    records_start: usize,
+    saved_at: i64,
+}
+
+impl std::fmt::Debug for MappedInstruments {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("MappedInstruments")
+            .field("len", &self.len())
+            .field("saved_at", &self.saved_at)
+            .finish()
+    }
+}
+
+impl MappedInstruments {
+    /// Map the dump at `path`.
+    ///
+    /// The file must not be modified in place while mapped. [`InstrumentStore::save`]
+    /// replaces it by rename, which leaves existing mappings on the old file.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        let file = File::open(path).map_err(io_error)?;
+        // SAFETY: dumps are replaced by rename, never written in place
+        let mmap = Arc::new(unsafe { Mmap::map(&file) }.map_err(io_error)?);
+        if mmap.len() < HEADER_LEN || &mmap[..8] != MAGIC {
+            return Err(KiteConnectError::other(
+                "instrument dump: not an instrument dump or unsupported version",
+            ));
+        }
+        let saved_at = i64::from_le_bytes(mmap[8..16].try_into().unwrap());
+        let index_len = u64::from_le_bytes(mmap[16..24].try_into().unwrap()) as usize;
+        let records_start = HEADER_LEN
+            .checked_add(index_len)
+            .filter(|&end| end <= mmap.len())
+            .ok_or_else(|| KiteConnectError::other("instrument dump: truncated index"))?;
+        let index = Map::new(Slice {
+            mmap: mmap.clone(),
+            start: HEADER_LEN,
+            end: records_start,
+        })
+        .map_err(index_error)?;
+        Ok(Self {
+            index,
+            mmap,
+            records_start,
+            saved_at,
+        })
+    }
+
+    pub fn get_by_symbol(&self, exchange: &str, tradingsymbol: &str) -> Option<Instrument> {
+        let offset = self.index.get(symbol_key(exchange, tradingsymbol))? as usize;
+        let record = self.mmap.get(self.records_start + offset..)?;
+        bincode::deserialize::<Record>(record)
+            .ok()
+            .map(Instrument::from)
+    }
+
+    /// Resolve a trading symbol to its instrument token.
+    pub fn token_of(&self, exchange: &str, tradingsymbol: &str) -> Option<u32> {
+        self.get_by_symbol(exchange, tradingsymbol)
+            .map(|instrument| instrument.instrument_token)
+    }
+
+    /// Number of distinct trading symbols in the dump.
+    pub fn len(&self) -> usize {
+        self.index.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.index.is_empty()
+    }
+
+    /// When the dump was saved.
+    pub fn saved_at(&self) -> Time {
+        Time::from_timestamp(self.saved_at)
+    }
+
+    /// Whether the dump was saved more than `max_age` ago. Kite regenerates the instrument
+    /// list daily, so CLI tools typically refresh once a day.
+    pub fn is_older_than(&self, max_age: Duration) -> bool {
+        let now = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        now.saturating_sub(self.saved_at) > max_age.as_secs() as i64
+    }
+
+    /// Decode every instrument into an in-memory store.
+    pub fn to_store(&self) -> Result<InstrumentStore, KiteConnectError> {
+        let mut instruments = Vec::with_capacity(self.len());
+        let mut records = &self.mmap[self.records_start..];
+        while !records.is_empty() {
+            let record: Record = bincode::deserialize_from(&mut records).map_err(encode_error)?;
+            instruments.push(record.into());
+        }
+        Ok(InstrumentStore::new(instruments))
+    }
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("instrument dump: {}", e))
+}
+
+fn encode_error(e: bincode::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("instrument dump: {}", e))
+}
+
+fn index_error(e: fst::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("instrument dump index: {}", e))
+}
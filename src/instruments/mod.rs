@@ -0,0 +1,175 @@
+//! Instrument lookup by token and by `EXCHANGE:TRADINGSYMBOL`.
+//!
+//! [`InstrumentStore`] indexes a parsed instrument dump in memory. With the `mmap` feature
+//! the store can be saved to disk in a compact binary format and opened again as
+//! [`MappedInstruments`], which memory-maps the file and resolves symbols through an `fst`
+//! index without parsing the dump, so CLI tools can resolve tokens on a cold start.
+//!
+//! ```no_run
+//! # #[cfg(feature = "mmap")]
+//! # async fn run(kite: &kiteconnect_rs::KiteConnect) -> Result<(), kiteconnect_rs::KiteConnectError> {
+//! use kiteconnect_rs::instruments::{InstrumentStore, MappedInstruments};
+//! use std::time::Duration;
+//!
+//! let path = "instruments.bin";
+//! let dump = match MappedInstruments::open(path) {
+//!     Ok(dump) if !dump.is_older_than(Duration::from_secs(12 * 3600)) => dump,
+//!     _ => {
+//!         InstrumentStore::new(kite.get_instruments().await?).save(path)?;
+//!         MappedInstruments::open(path)?
+//!     }
+//! };
+//! let token = dump.token_of("NSE", "INFY");
+//! # Ok(())
+//! # }
+//! ```
+
+use chrono::NaiveDate;
+use chrono_tz::Asia::Kolkata;
+use std::collections::HashMap;
+
+use crate::markets::{Instrument, Instruments};
+
+mod format;
+mod isin;
+#[cfg(feature = "mmap")]
+mod mapped;
+mod rounding;
+
+pub(crate) use format::tick_decimals;
+pub use format::{PriceFormatter, format_price, price_decimals};
+pub use isin::{ExchangePolicy, IsinMap, ListedSymbol};
+
+#[cfg(feature = "mmap")]
+pub use mapped::MappedInstruments;
+pub use rounding::{
+    OrderRounding, Rounding, round_price, round_quantity, round_to_lot, round_to_tick,
+};
+
+/// InstrumentStore holds an instrument dump indexed by token and by trading symbol.
+#[derive(Debug, Clone, Default)]
+pub struct InstrumentStore {
+    instruments: Instruments,
+    by_token: HashMap<u32, usize>,
+    // `EXCHANGE:TRADINGSYMBOL` to index, first occurrence wins
+    by_symbol: HashMap<String, usize>,
+}
+
+impl InstrumentStore {
+    pub fn new(instruments: Instruments) -> Self {
+        let mut by_token = HashMap::with_capacity(instruments.len());
+        let mut by_symbol = HashMap::with_capacity(instruments.len());
+        for (i, instrument) in instruments.iter().enumerate() {
+            by_token.entry(instrument.instrument_token).or_insert(i);
+            by_symbol
+                .entry(symbol_key(&instrument.exchange, &instrument.tradingsymbol))
+                .or_insert(i);
+        }
+        Self {
+            instruments,
+            by_token,
+            by_symbol,
+        }
+    }
+
+    pub fn get(&self, instrument_token: u32) -> Option<&Instrument> {
+        self.by_token
+            .get(&instrument_token)
+            .map(|&i| &self.instruments[i])
+    }
+
+    pub fn get_by_symbol(&self, exchange: &str, tradingsymbol: &str) -> Option<&Instrument> {
+        self.by_symbol
+            .get(&symbol_key(exchange, tradingsymbol))
+            .map(|&i| &self.instruments[i])
+    }
+
+    /// Resolve a trading symbol to its instrument token.
+    pub fn token_of(&self, exchange: &str, tradingsymbol: &str) -> Option<u32> {
+        self.get_by_symbol(exchange, tradingsymbol)
+            .map(|instrument| instrument.instrument_token)
+    }
+
+    pub fn contains(&self, instrument_token: u32) -> bool {
+        self.by_token.contains_key(&instrument_token)
+    }
+
+    pub fn len(&self) -> usize {
+        self.instruments.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.instruments.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Instrument> {
+        self.instruments.iter()
+    }
+
+    pub fn instruments(&self) -> &Instruments {
+        &self.instruments
+    }
+
+    /// Sort `tokens` into ones missing from the store and ones whose contract expired
+    /// before `today`.
+    pub fn check_tokens(&self, tokens: &[u32], today: NaiveDate) -> TokenCheck {
+        let mut check = TokenCheck::default();
+        for &token in tokens {
+            match self.get(token) {
+                None => check.unknown.push(token),
+                Some(instrument) if is_expired(instrument, today) => check.expired.push(token),
+                Some(_) => {}
+            }
+        }
+        check
+    }
+}
+
+/// TokenCheck lists the tokens rejected by [`InstrumentStore::check_tokens`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TokenCheck {
+    /// Not in the instrument dump.
+    pub unknown: Vec<u32>,
+    /// Contracts that expired before the day checked.
+    pub expired: Vec<u32>,
+}
+
+impl TokenCheck {
+    pub fn is_ok(&self) -> bool {
+        self.unknown.is_empty() && self.expired.is_empty()
+    }
+}
+
+impl std::fmt::Display for TokenCheck {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match (self.unknown.is_empty(), self.expired.is_empty()) {
+            (true, true) => write!(f, "all tokens valid"),
+            (false, true) => write!(f, "unknown tokens {:?}", self.unknown),
+            (true, false) => write!(f, "expired tokens {:?}", self.expired),
+            (false, false) => write!(
+                f,
+                "unknown tokens {:?}, expired tokens {:?}",
+                self.unknown, self.expired
+            ),
+        }
+    }
+}
+
+/// Whether the contract expired before `today`. Instruments without an expiry never do;
+/// contracts trade through their expiry day.
+pub fn is_expired(instrument: &Instrument, today: NaiveDate) -> bool {
+    instrument
+        .expiry
+        .as_datetime()
+        .is_some_and(|expiry| expiry.with_timezone(&Kolkata).date_naive() < today)
+}
+
+impl From<Instruments> for InstrumentStore {
+    fn from(instruments: Instruments) -> Self {
+        Self::new(instruments)
+    }
+}
+
+fn symbol_key(exchange: &str, tradingsymbol: &str) -> String {
+    format!("{}:{}", exchange, tradingsymbol)
+}
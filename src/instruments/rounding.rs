@@ -0,0 +1,148 @@
+//! Rounding order prices to the tick size and quantities to the lot size.
+//!
+//! Orders priced off the tick grid or sized in part lots are rejected by the exchange.
+//! [`round_to_tick`] and [`round_to_lot`] fix single values; [`OrderRounding`] fixes whole
+//! orders and, set on the client with
+//! [`KiteConnect::set_order_rounding`](crate::KiteConnect::set_order_rounding), is
+//! applied to every order passed to `place_order`.
+
+use std::sync::Arc;
+
+use super::InstrumentStore;
+use crate::labels::TransactionType;
+use crate::markets::Instrument;
+use crate::models::KiteConnectError;
+use crate::orders::OrderParams;
+
+/// Direction a value off the grid is moved in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Rounding {
+    #[default]
+    Nearest,
+    Down,
+    Up,
+    /// Away from the market: down for buys and up for sells, so rounding never makes a
+    /// limit price more aggressive.
+    Passive,
+}
+
+/// Round `price` to the nearest multiple of the instrument's tick size.
+pub fn round_to_tick(price: f64, instrument: &Instrument) -> f64 {
+    round_price(price, instrument.tick_size, Rounding::Nearest, None)
+}
+
+/// Round `quantity` down to a whole number of lots. Quantities under one lot round to 0.
+pub fn round_to_lot(quantity: i32, instrument: &Instrument) -> i32 {
+    round_quantity(quantity, lot_size(instrument), Rounding::Down)
+}
+
+/// Round `price` to a multiple of `tick_size`. `transaction_type` decides the direction of
+/// [`Rounding::Passive`], which rounds to the nearest tick without it.
+pub fn round_price(
+    price: f64,
+    tick_size: f64,
+    rounding: Rounding,
+    transaction_type: Option<TransactionType>,
+) -> f64 {
+    if tick_size <= 0.0 {
+        return price;
+    }
+    // Absorb float noise so prices already on the grid stay put
+    let ticks = ((price / tick_size) * 1e6).round() / 1e6;
+    let ticks = match (rounding, transaction_type) {
+        (Rounding::Nearest, _) | (Rounding::Passive, None) => ticks.round(),
+        (Rounding::Down, _) | (Rounding::Passive, Some(TransactionType::Buy)) => ticks.floor(),
+        (Rounding::Up, _) | (Rounding::Passive, Some(TransactionType::Sell)) => ticks.ceil(),
+    };
+    (ticks * tick_size * 1e6).round() / 1e6
+}
+
+/// Round `quantity` to a multiple of `lot_size`. [`Rounding::Passive`] rounds down.
+pub fn round_quantity(quantity: i32, lot_size: i32, rounding: Rounding) -> i32 {
+    if lot_size <= 1 {
+        return quantity;
+    }
+    let lots = quantity as f64 / lot_size as f64;
+    let lots = match rounding {
+        Rounding::Nearest => lots.round(),
+        Rounding::Down | Rounding::Passive => lots.floor(),
+        Rounding::Up => lots.ceil(),
+    };
+    lots as i32 * lot_size
+}
+
+fn lot_size(instrument: &Instrument) -> i32 {
+    instrument.lot_size.round() as i32
+}
+
+/// OrderRounding rounds the prices and quantity of orders for the instruments in a store.
+#[derive(Debug, Clone)]
+pub struct OrderRounding {
+    instruments: Arc<InstrumentStore>,
+    pub price: Rounding,
+    pub quantity: Rounding,
+}
+
+impl OrderRounding {
+    /// Round prices passively and quantities down to whole lots.
+    pub fn new(instruments: Arc<InstrumentStore>) -> Self {
+        Self {
+            instruments,
+            price: Rounding::Passive,
+            quantity: Rounding::Down,
+        }
+    }
+
+    pub fn price(mut self, rounding: Rounding) -> Self {
+        self.price = rounding;
+        self
+    }
+
+    pub fn quantity(mut self, rounding: Rounding) -> Self {
+        self.quantity = rounding;
+        self
+    }
+
+    /// Round the price, trigger price, quantity and disclosed quantity of `params`.
+    /// Orders for instruments missing from the store are left as they are; an order that
+    /// rounds to zero lots is an error.
+    pub fn apply(&self, params: &mut OrderParams) -> Result<(), KiteConnectError> {
+        let (Some(exchange), Some(tradingsymbol)) = (&params.exchange, &params.tradingsymbol)
+        else {
+            return Ok(());
+        };
+        let Some(instrument) = self.instruments.get_by_symbol(exchange, tradingsymbol) else {
+            return Ok(());
+        };
+
+        let side = params
+            .transaction_type
+            .as_deref()
+            .and_then(|t| t.parse::<TransactionType>().ok());
+        let tick_size = instrument.tick_size;
+        for price in [&mut params.price, &mut params.trigger_price]
+            .into_iter()
+            .flatten()
+        {
+            if *price > 0.0 {
+                *price = round_price(*price, tick_size, self.price, side);
+            }
+        }
+
+        let lot_size = lot_size(instrument);
+        if let Some(quantity) = params.quantity.as_mut() {
+            let rounded = round_quantity(*quantity, lot_size, self.quantity);
+            if rounded <= 0 {
+                return Err(KiteConnectError::invalid_params(format!(
+                    "quantity {} of {} is less than a lot of {}",
+                    quantity, tradingsymbol, lot_size
+                )));
+            }
+            *quantity = rounded;
+        }
+        if let Some(disclosed) = params.disclosed_quantity.as_mut() {
+            *disclosed = round_quantity(*disclosed, lot_size, self.quantity);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,516 @@
+//! Append-only order journal for recovering from crashes.
+//!
+//! An [`OrderJournal`] is an [`AuditLog`]: set on the client, it records every place, modify
+//! and cancel request, and the order updates passed to `KiteConnect::audit_order_update`.
+//! The application adds its own [`Intent`]s next to them, such as an OCO pair whose second
+//! leg is not placed yet or a GTT it means to set, and marks them done once carried out.
+//!
+//! After a restart, [`OrderJournal::replay`] rebuilds the orders the bot placed and its
+//! pending intents from the file, and [`OrderJournal::recover`] also brings the orders up
+//! to date with `get_orders`, since updates sent while the bot was down were never
+//! journaled. A placement that failed after it may have reached Kite is kept as an
+//! [`UnknownPlacement`] until recovery finds its order by tag, so give orders unique tags:
+//!
+//! ```no_run
+//! # async fn run(api_key: &str) -> Result<(), kiteconnect_rs::KiteConnectError> {
+//! use kiteconnect_rs::KiteConnect;
+//! use kiteconnect_rs::journal::{Intent, OrderJournal};
+//!
+//! let journal = OrderJournal::open("orders.journal")?.sync_on_write(true);
+//! let kite = KiteConnect::builder(api_key).audit_log(journal.clone()).build()?;
+//!
+//! let (state, reconciliation) = journal.recover(&kite).await?;
+//! for order in state.open_orders() {
+//!     println!("{} is still {:?}", order.order_id, order.status);
+//! }
+//! for intent in state.intents.values() {
+//!     println!("resuming {} {}", intent.kind, intent.id);
+//! }
+//!
+//! journal.record_intent(&Intent::new("exit-1", "oco").order_ids(vec!["1".into()]))?;
+//! # Ok(())
+//! # }
+//! ```
+//!
+//! Records are never rewritten. A line cut short by a crash at the end of the file is
+//! dropped when the journal is next opened, and skipped by replay until then.
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::KiteConnect;
+use crate::audit::{AuditAction, AuditLog, AuditRecord};
+use crate::gtt::GTT_AUDIT_VARIETY;
+use crate::models::KiteConnectError;
+use crate::order_state::TERMINAL_ORDER_STATUSES;
+use crate::orders::{Order, OrderParams, OrderResponse};
+
+/// Intent is something the application means to do across several orders, journaled so
+/// it can be resumed after a restart.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Intent {
+    /// Recording an intent with the id of a pending one replaces it.
+    pub id: String,
+    /// What the intent is, e.g. `oco` or `gtt`.
+    pub kind: String,
+    /// Orders placed for the intent so far.
+    #[serde(default)]
+    pub order_ids: Vec<String>,
+    /// Whatever else the application needs to carry the intent out.
+    #[serde(default)]
+    pub data: serde_json::Value,
+    pub created_at: DateTime<Utc>,
+}
+
+impl Intent {
+    pub fn new(id: impl Into<String>, kind: impl Into<String>) -> Self {
+        Self {
+            id: id.into(),
+            kind: kind.into(),
+            order_ids: Vec::new(),
+            data: serde_json::Value::Null,
+            created_at: Utc::now(),
+        }
+    }
+
+    pub fn order_ids(mut self, order_ids: Vec<String>) -> Self {
+        self.order_ids = order_ids;
+        self
+    }
+
+    pub fn data(mut self, data: serde_json::Value) -> Self {
+        self.data = data;
+        self
+    }
+}
+
+/// JournalEvent is one line of the journal.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "event", content = "data", rename_all = "snake_case")]
+pub enum JournalEvent {
+    /// A request or order update recorded by the client.
+    Order(AuditRecord),
+    Intent(Intent),
+    /// The intent with this id was carried out or given up.
+    IntentDone(String),
+}
+
+/// JournalOrder is what the journal knows of an order placed through the client.
+#[derive(Debug, Clone)]
+pub struct JournalOrder {
+    pub order_id: String,
+    pub variety: String,
+    /// The parameters it was placed with, with later modifications merged in.
+    pub params: Option<OrderParams>,
+    /// The last status seen. `None` until the first update arrives.
+    pub status: Option<String>,
+    pub filled_quantity: f64,
+    /// A cancellation was accepted, though not necessarily carried out yet.
+    pub cancel_requested: bool,
+}
+
+impl JournalOrder {
+    /// Open until an update reports it complete, cancelled or rejected.
+    pub fn is_open(&self) -> bool {
+        !self
+            .status
+            .as_deref()
+            .is_some_and(|status| TERMINAL_ORDER_STATUSES.contains(&status))
+    }
+}
+
+/// UnknownPlacement is a placement that failed after it may have reached Kite, e.g. on
+/// a timeout, so the order may exist without the journal knowing its id.
+#[derive(Debug, Clone)]
+pub struct UnknownPlacement {
+    /// Id of the audit record of the failed request.
+    pub request_id: String,
+    pub variety: String,
+    pub params: Option<OrderParams>,
+    pub timestamp: DateTime<Utc>,
+}
+
+impl UnknownPlacement {
+    fn tag(&self) -> Option<&str> {
+        self.params.as_ref()?.tag.as_deref()
+    }
+}
+
+/// JournalState is the state rebuilt by replaying a journal.
+#[derive(Debug, Clone, Default)]
+pub struct JournalState {
+    /// Orders placed through the client, by order id.
+    pub orders: HashMap<String, JournalOrder>,
+    /// Placements whose outcome is unknown, in the order sent.
+    pub unknown: Vec<UnknownPlacement>,
+    /// Intents recorded and not done yet, by id.
+    pub intents: HashMap<String, Intent>,
+}
+
+impl JournalState {
+    pub fn open_orders(&self) -> impl Iterator<Item = &JournalOrder> {
+        self.orders.values().filter(|order| order.is_open())
+    }
+
+    /// Apply one journal event, as replay does.
+    pub fn apply(&mut self, event: &JournalEvent) {
+        match event {
+            JournalEvent::Order(record) => self.apply_record(record),
+            JournalEvent::Intent(intent) => {
+                self.intents.insert(intent.id.clone(), intent.clone());
+            }
+            JournalEvent::IntentDone(id) => {
+                self.intents.remove(id);
+            }
+        }
+    }
+
+    fn apply_record(&mut self, record: &AuditRecord) {
+        // GTTs are not orders
+        if record.variety.as_deref() == Some(GTT_AUDIT_VARIETY) {
+            return;
+        }
+        if record.error.is_some() {
+            // Other failed requests changed nothing
+            if record.action == AuditAction::Place && record.ambiguous {
+                self.unknown.push(UnknownPlacement {
+                    request_id: record.request_id.clone(),
+                    variety: record.variety.clone().unwrap_or_default(),
+                    params: record
+                        .request
+                        .clone()
+                        .and_then(|r| serde_json::from_value(r).ok()),
+                    timestamp: record.timestamp,
+                });
+            }
+            return;
+        }
+        let params = || {
+            let request = record.request.clone()?;
+            serde_json::from_value::<OrderParams>(request).ok()
+        };
+        match record.action {
+            AuditAction::Place => {
+                let Some(response) = record.response.clone() else {
+                    return;
+                };
+                let Ok(response) = serde_json::from_value::<OrderResponse>(response) else {
+                    return;
+                };
+                let params = params();
+                // Recovery journals the order it found for an unknown placement under the
+                // placement's request id
+                self.unknown.retain(|p| p.request_id != record.request_id);
+                let order_ids = std::iter::once(response.order_id).chain(response.split_order_ids);
+                for order_id in order_ids {
+                    // Split orders are also journaled one by one
+                    self.orders
+                        .entry(order_id.clone())
+                        .or_insert_with(|| JournalOrder {
+                            order_id,
+                            variety: record.variety.clone().unwrap_or_default(),
+                            params: params.clone(),
+                            status: None,
+                            filled_quantity: 0.0,
+                            cancel_requested: false,
+                        });
+                }
+            }
+            AuditAction::Modify => {
+                let Some(order) = self.order_mut(record) else {
+                    return;
+                };
+                if let (Some(placed), Some(changes)) = (order.params.clone(), params()) {
+                    if let Ok(merged) = placed.merge(changes) {
+                        order.params = Some(merged);
+                    }
+                }
+            }
+            AuditAction::Cancel => {
+                if let Some(order) = self.order_mut(record) {
+                    order.cancel_requested = true;
+                }
+            }
+            AuditAction::OrderUpdate => {
+                let update = record.response.as_ref();
+                let status = update.and_then(|u| u["status"].as_str()).map(str::to_owned);
+                let filled = update.and_then(|u| u["filled_quantity"].as_f64());
+                if let Some(order) = self.order_mut(record) {
+                    if status.is_some() {
+                        order.status = status;
+                    }
+                    if let Some(filled) = filled {
+                        order.filled_quantity = filled;
+                    }
+                }
+            }
+        }
+    }
+
+    // Only orders placed through the client are tracked
+    fn order_mut(&mut self, record: &AuditRecord) -> Option<&mut JournalOrder> {
+        let order_id = record.order_id.as_deref()?;
+        self.orders.get_mut(order_id)
+    }
+
+    /// Bring the open orders up to date with `orders`, as returned by `get_orders`, and
+    /// resolve unknown placements: an order the journal does not know, tagged like an
+    /// unknown placement, is taken to be its order and added to the state.
+    pub fn reconcile(&mut self, orders: &[Order]) -> JournalReconciliation {
+        let current: HashMap<&str, &Order> = orders
+            .iter()
+            .map(|order| (order.order_id.as_str(), order))
+            .collect();
+        let mut reconciliation = JournalReconciliation::default();
+        let mut open: Vec<_> = self.open_orders().map(|o| o.order_id.clone()).collect();
+        open.sort();
+        for order_id in open {
+            let Some(order) = self.orders.get_mut(&order_id) else {
+                continue;
+            };
+            let Some(latest) = current.get(order_id.as_str()) else {
+                reconciliation.missing.push(order_id);
+                continue;
+            };
+            order.filled_quantity = latest.filled_quantity;
+            if order.status.as_deref() != Some(latest.status.as_str()) {
+                reconciliation.changed.push(OrderStatusChange {
+                    order_id,
+                    journaled: order.status.replace(latest.status.clone()),
+                    current: latest.status.clone(),
+                });
+            }
+        }
+        for order in orders {
+            if self.orders.contains_key(&order.order_id) {
+                continue;
+            }
+            let Some(tag) = order.tag.as_deref() else {
+                continue;
+            };
+            let Some(index) = self.unknown.iter().position(|p| p.tag() == Some(tag)) else {
+                continue;
+            };
+            let placement = self.unknown.remove(index);
+            self.orders.insert(
+                order.order_id.clone(),
+                JournalOrder {
+                    order_id: order.order_id.clone(),
+                    variety: placement.variety,
+                    params: placement.params,
+                    status: Some(order.status.clone()),
+                    filled_quantity: order.filled_quantity,
+                    cancel_requested: false,
+                },
+            );
+            reconciliation.untracked.push(UntrackedOrder {
+                order_id: order.order_id.clone(),
+                request_id: placement.request_id,
+            });
+        }
+        reconciliation
+    }
+}
+
+/// OrderStatusChange is an order whose status moved on while it was not journaled.
+#[derive(Debug, Clone, PartialEq)]
+pub struct OrderStatusChange {
+    pub order_id: String,
+    pub journaled: Option<String>,
+    pub current: String,
+}
+
+/// UntrackedOrder is an order in the order book the journal had no id for, matched by tag
+/// to an [`UnknownPlacement`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct UntrackedOrder {
+    pub order_id: String,
+    /// Id of the audit record of the placement it resolves.
+    pub request_id: String,
+}
+
+/// JournalReconciliation lists what [`JournalState::reconcile`] found, by order id.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct JournalReconciliation {
+    pub changed: Vec<OrderStatusChange>,
+    /// Open orders not in the order book, such as those of an earlier day.
+    pub missing: Vec<String>,
+    /// Orders placed by requests whose outcome was unknown.
+    pub untracked: Vec<UntrackedOrder>,
+}
+
+/// OrderJournal appends [`JournalEvent`]s to a file, one JSON object per line. Clones
+/// write to the same file.
+#[derive(Clone)]
+pub struct OrderJournal {
+    path: PathBuf,
+    file: Arc<Mutex<File>>,
+    sync: bool,
+}
+
+impl OrderJournal {
+    /// Open `path` for appending, creating it if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        let path = path.as_ref().to_path_buf();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .read(true)
+            .append(true)
+            .open(&path)
+            .map_err(io_error)?;
+        trim_partial_line(&mut file).map_err(io_error)?;
+        Ok(Self {
+            path,
+            file: Arc::new(Mutex::new(file)),
+            sync: false,
+        })
+    }
+
+    /// fsync after every event so entries survive a crash or power loss.
+    pub fn sync_on_write(mut self, sync: bool) -> Self {
+        self.sync = sync;
+        self
+    }
+
+    pub fn record_intent(&self, intent: &Intent) -> Result<(), KiteConnectError> {
+        self.append_event(&JournalEvent::Intent(intent.clone()))
+    }
+
+    pub fn complete_intent(&self, id: &str) -> Result<(), KiteConnectError> {
+        self.append_event(&JournalEvent::IntentDone(id.to_owned()))
+    }
+
+    pub fn append_event(&self, event: &JournalEvent) -> Result<(), KiteConnectError> {
+        let mut line = serde_json::to_vec(event)?;
+        line.push(b'\n');
+
+        let mut file = self.file.lock().unwrap_or_else(|e| e.into_inner());
+        file.write_all(&line).map_err(io_error)?;
+        if self.sync {
+            file.sync_data().map_err(io_error)?;
+        }
+        Ok(())
+    }
+
+    /// Every event in the journal, in the order written.
+    pub fn events(&self) -> Result<Vec<JournalEvent>, KiteConnectError> {
+        let file = File::open(&self.path).map_err(io_error)?;
+        let lines: Vec<String> = BufReader::new(file)
+            .lines()
+            .collect::<Result<_, _>>()
+            .map_err(io_error)?;
+        let mut events = Vec::with_capacity(lines.len());
+        for (index, line) in lines.iter().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            match serde_json::from_str(line) {
+                Ok(event) => events.push(event),
+                Err(e) if index + 1 == lines.len() => {
+                    log::warn!("skipping incomplete last journal line: {}", e);
+                }
+                Err(e) => {
+                    return Err(KiteConnectError::other(format!(
+                        "order journal line {}: {}",
+                        index + 1,
+                        e
+                    )));
+                }
+            }
+        }
+        Ok(events)
+    }
+
+    /// Rebuild the state from the journal.
+    pub fn replay(&self) -> Result<JournalState, KiteConnectError> {
+        let mut state = JournalState::default();
+        for event in self.events()? {
+            state.apply(&event);
+        }
+        Ok(state)
+    }
+
+    /// Replay the journal and reconcile it with `get_orders`. The current state of every
+    /// changed order, and every order found for an unknown placement, is journaled, so the
+    /// next replay starts from it.
+    pub async fn recover(
+        &self,
+        kite: &KiteConnect,
+    ) -> Result<(JournalState, JournalReconciliation), KiteConnectError> {
+        let mut state = self.replay()?;
+        let orders = kite.get_orders().await?;
+        let reconciliation = state.reconcile(&orders);
+        for change in &reconciliation.changed {
+            let Some(order) = orders.iter().find(|o| o.order_id == change.order_id) else {
+                continue;
+            };
+            self.append_event(&JournalEvent::Order(order_update(order)))?;
+        }
+        for untracked in &reconciliation.untracked {
+            let (Some(order), Some(journaled)) = (
+                orders.iter().find(|o| o.order_id == untracked.order_id),
+                state.orders.get(&untracked.order_id),
+            ) else {
+                continue;
+            };
+            let mut placed = AuditRecord::new(AuditAction::Place);
+            placed.request_id = untracked.request_id.clone();
+            placed.variety = Some(journaled.variety.clone());
+            placed.order_id = Some(order.order_id.clone());
+            placed.request = serde_json::to_value(&journaled.params).ok();
+            placed.response = Some(serde_json::json!({ "order_id": order.order_id }));
+            self.append_event(&JournalEvent::Order(placed))?;
+            self.append_event(&JournalEvent::Order(order_update(order)))?;
+        }
+        Ok((state, reconciliation))
+    }
+}
+
+impl AuditLog for OrderJournal {
+    fn append(&self, record: &AuditRecord) -> Result<(), KiteConnectError> {
+        self.append_event(&JournalEvent::Order(record.clone()))
+    }
+}
+
+fn order_update(order: &Order) -> AuditRecord {
+    let mut record = AuditRecord::new(AuditAction::OrderUpdate);
+    record.variety = Some(order.variety.clone());
+    record.order_id = Some(order.order_id.clone());
+    record.response = serde_json::to_value(order).ok();
+    record
+}
+
+// Drop a last line left incomplete by a crash, so new events start on a line of their own
+fn trim_partial_line(file: &mut File) -> std::io::Result<()> {
+    let len = file.metadata()?.len();
+    if len == 0 {
+        return Ok(());
+    }
+    let mut last = [0u8];
+    file.seek(SeekFrom::Start(len - 1))?;
+    file.read_exact(&mut last)?;
+    if last[0] == b'\n' {
+        return Ok(());
+    }
+    let mut contents = Vec::new();
+    file.seek(SeekFrom::Start(0))?;
+    file.read_to_end(&mut contents)?;
+    let end = contents
+        .iter()
+        .rposition(|&b| b == b'\n')
+        .map_or(0, |i| i + 1) as u64;
+    log::warn!(
+        "dropping an incomplete order journal line of {} bytes",
+        len - end
+    );
+    file.set_len(end)
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("order journal: {}", e))
+}
@@ -0,0 +1,170 @@
+//! Converting ticks into JavaScript values without going through JSON.
+//!
+//! Handing ticks to JavaScript as JSON strings costs a `serde_json` encode in WASM and a
+//! `JSON.parse` in JavaScript for every tick, which falls behind full-mode feeds. These
+//! helpers build the JavaScript values directly:
+//!
+//! - [`tick_to_js`] and [`ticks_to_js`] produce plain objects with the same field names as
+//!   the JSON form of [`Tick`], except that times are Unix milliseconds (`null` when
+//!   unset) so they can be passed to `new Date()` as they are.
+//! - [`pack_ticks`] writes the scalar fields of many ticks into one `Float64Array`, one
+//!   row of [`PACKED_TICK_FIELDS`] per tick, for charts that only need numbers.
+
+use js_sys::{Array, Float64Array, Object, Reflect};
+use wasm_bindgen::JsValue;
+
+use crate::models::time::Time;
+use crate::models::{Depth, DepthItem, OHLC, Tick};
+
+/// Columns of a [`pack_ticks`] row, in order.
+pub const PACKED_TICK_FIELDS: [&str; 15] = [
+    "instrument_token",
+    "timestamp",
+    "last_trade_time",
+    "last_price",
+    "last_traded_quantity",
+    "volume_traded",
+    "average_trade_price",
+    "total_buy_quantity",
+    "total_sell_quantity",
+    "oi",
+    "net_change",
+    "open",
+    "high",
+    "low",
+    "close",
+];
+
+/// A JavaScript object with the fields of `tick`.
+pub fn tick_to_js(tick: &Tick) -> Object {
+    let object = Object::new();
+    set_all(
+        &object,
+        [
+            ("mode", tick.mode.as_str().into()),
+            ("instrument_token", tick.instrument_token.into()),
+            ("is_tradable", tick.is_tradable.into()),
+            ("is_index", tick.is_index.into()),
+            ("timestamp", time_to_js(&tick.timestamp)),
+            ("last_trade_time", time_to_js(&tick.last_trade_time)),
+            ("last_price", tick.last_price.into()),
+            ("last_traded_quantity", tick.last_traded_quantity.into()),
+            ("total_buy_quantity", tick.total_buy_quantity.into()),
+            ("total_sell_quantity", tick.total_sell_quantity.into()),
+            ("volume_traded", tick.volume_traded.into()),
+            ("total_buy", tick.total_buy.into()),
+            ("total_sell", tick.total_sell.into()),
+            ("average_trade_price", tick.average_trade_price.into()),
+            ("oi", tick.oi.into()),
+            ("oi_day_high", tick.oi_day_high.into()),
+            ("oi_day_low", tick.oi_day_low.into()),
+            ("net_change", tick.net_change.into()),
+            ("ohlc", ohlc_to_js(&tick.ohlc).into()),
+            ("depth", depth_to_js(&tick.depth).into()),
+        ],
+    );
+    if let Some(change) = &tick.change {
+        let js = Object::new();
+        set_all(
+            &js,
+            [
+                ("oi_change", (change.oi_change as f64).into()),
+                ("volume_delta", change.volume_delta.into()),
+                ("traded_value_delta", change.traded_value_delta.into()),
+            ],
+        );
+        set_all(&object, [("change", js.into())]);
+    }
+    object
+}
+
+/// An array of [`tick_to_js`] objects.
+pub fn ticks_to_js(ticks: &[Tick]) -> Array {
+    let array = Array::new_with_length(ticks.len() as u32);
+    for (i, tick) in ticks.iter().enumerate() {
+        array.set(i as u32, tick_to_js(tick).into());
+    }
+    array
+}
+
+/// The [`PACKED_TICK_FIELDS`] of `ticks`, row after row. Unset times are `NaN`.
+pub fn pack_ticks(ticks: &[Tick]) -> Float64Array {
+    let mut values = Vec::with_capacity(ticks.len() * PACKED_TICK_FIELDS.len());
+    for tick in ticks {
+        values.extend_from_slice(&[
+            tick.instrument_token as f64,
+            time_to_millis(&tick.timestamp).unwrap_or(f64::NAN),
+            time_to_millis(&tick.last_trade_time).unwrap_or(f64::NAN),
+            tick.last_price,
+            tick.last_traded_quantity as f64,
+            tick.volume_traded as f64,
+            tick.average_trade_price,
+            tick.total_buy_quantity as f64,
+            tick.total_sell_quantity as f64,
+            tick.oi as f64,
+            tick.net_change,
+            tick.ohlc.open,
+            tick.ohlc.high,
+            tick.ohlc.low,
+            tick.ohlc.close,
+        ]);
+    }
+    Float64Array::from(values.as_slice())
+}
+
+fn ohlc_to_js(ohlc: &OHLC) -> Object {
+    let object = Object::new();
+    set_all(
+        &object,
+        [
+            ("open", ohlc.open.into()),
+            ("high", ohlc.high.into()),
+            ("low", ohlc.low.into()),
+            ("close", ohlc.close.into()),
+        ],
+    );
+    object
+}
+
+fn depth_to_js(depth: &Depth) -> Object {
+    let side = |items: &[DepthItem]| {
+        let array = Array::new_with_length(items.len() as u32);
+        for (i, item) in items.iter().enumerate() {
+            let level = Object::new();
+            set_all(
+                &level,
+                [
+                    ("price", item.price.into()),
+                    ("quantity", item.quantity.into()),
+                    ("orders", item.orders.into()),
+                ],
+            );
+            array.set(i as u32, level.into());
+        }
+        array
+    };
+    let object = Object::new();
+    set_all(
+        &object,
+        [
+            ("buy", side(&depth.buy).into()),
+            ("sell", side(&depth.sell).into()),
+        ],
+    );
+    object
+}
+
+fn time_to_millis(time: &Time) -> Option<f64> {
+    time.as_datetime().map(|t| t.timestamp_millis() as f64)
+}
+
+fn time_to_js(time: &Time) -> JsValue {
+    time_to_millis(time).map_or(JsValue::NULL, JsValue::from)
+}
+
+// Setting properties on a fresh plain object cannot fail
+fn set_all<const N: usize>(object: &Object, fields: [(&str, JsValue); N]) {
+    for (key, value) in fields {
+        let _ = Reflect::set(object, &JsValue::from_str(key), &value);
+    }
+}
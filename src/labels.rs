@@ -0,0 +1,199 @@
+//! Typed counterparts of the string constants in [`Labels`](crate::Labels).
+//!
+//! Each enum converts to and from the exact string the API uses (`as_str`, `FromStr`,
+//! `Display`, serde), and lists every variant in `ALL`, so a `match` on one of them is
+//! checked for exhaustiveness by the compiler:
+//!
+//! ```
+//! use kiteconnect_rs::labels::TransactionType;
+//!
+//! let side: TransactionType = "SELL".parse().unwrap();
+//! let sign = match side {
+//!     TransactionType::Buy => 1,
+//!     TransactionType::Sell => -1,
+//! };
+//! assert_eq!(sign, -1);
+//! assert_eq!(side.as_str(), "SELL");
+//! ```
+
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use std::fmt;
+use std::str::FromStr;
+
+use crate::models::KiteConnectError;
+
+macro_rules! label_enum {
+    (
+        $(#[$meta:meta])*
+        $name:ident {
+            $($(#[$vmeta:meta])* $variant:ident => $value:literal,)+
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+        pub enum $name {
+            $($(#[$vmeta])* $variant,)+
+        }
+
+        impl $name {
+            /// Every variant, in declaration order.
+            pub const ALL: &'static [$name] = &[$($name::$variant,)+];
+
+            /// The string used by the Kite API.
+            pub const fn as_str(&self) -> &'static str {
+                match self {
+                    $($name::$variant => $value,)+
+                }
+            }
+        }
+
+        impl FromStr for $name {
+            type Err = KiteConnectError;
+
+            fn from_str(s: &str) -> Result<Self, Self::Err> {
+                match s {
+                    $($value => Ok($name::$variant),)+
+                    other => Err(KiteConnectError::invalid_params(format!(
+                        "unknown {} `{}`",
+                        stringify!($name),
+                        other
+                    ))),
+                }
+            }
+        }
+
+        impl TryFrom<&str> for $name {
+            type Error = KiteConnectError;
+
+            fn try_from(s: &str) -> Result<Self, Self::Error> {
+                s.parse()
+            }
+        }
+
+        impl fmt::Display for $name {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.write_str(self.as_str())
+            }
+        }
+
+        impl AsRef<str> for $name {
+            fn as_ref(&self) -> &str {
+                self.as_str()
+            }
+        }
+
+        impl From<$name> for String {
+            fn from(value: $name) -> String {
+                value.as_str().to_owned()
+            }
+        }
+
+        impl Serialize for $name {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.serialize_str(self.as_str())
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $name {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(serde::de::Error::custom)
+            }
+        }
+    };
+}
+
+label_enum! {
+    /// Order variety, the `{variety}` path segment of order endpoints.
+    Variety {
+        Regular => "regular",
+        /// After market order.
+        Amo => "amo",
+        Iceberg => "iceberg",
+        Bracket => "bo",
+        Cover => "co",
+        Auction => "auction",
+    }
+}
+
+label_enum! {
+    OrderType {
+        Market => "MARKET",
+        Limit => "LIMIT",
+        /// Stop-loss limit.
+        Sl => "SL",
+        /// Stop-loss market.
+        SlM => "SL-M",
+    }
+}
+
+label_enum! {
+    TransactionType {
+        Buy => "BUY",
+        Sell => "SELL",
+    }
+}
+
+label_enum! {
+    Product {
+        /// Cash and carry, for delivery.
+        Cnc => "CNC",
+        /// Margin intraday squareoff.
+        Mis => "MIS",
+        /// Normal, for carry-forward F&O.
+        Nrml => "NRML",
+        Bo => "BO",
+        Co => "CO",
+        /// Margin trading facility, for delivery partly funded by the broker.
+        Mtf => "MTF",
+    }
+}
+
+label_enum! {
+    Validity {
+        Day => "DAY",
+        /// Immediate or cancel.
+        Ioc => "IOC",
+        /// Time to live, in minutes set by `validity_ttl`.
+        Ttl => "TTL",
+    }
+}
+
+label_enum! {
+    Exchange {
+        Nse => "NSE",
+        Bse => "BSE",
+        Nfo => "NFO",
+        Bfo => "BFO",
+        Mcx => "MCX",
+        Cds => "CDS",
+    }
+}
+
+label_enum! {
+    /// Instrument type for holdings authorisation.
+    HoldingsAuthType {
+        Mf => "mf",
+        Equity => "equity",
+    }
+}
+
+label_enum! {
+    /// Transfer type for holdings authorisation.
+    HoldingsTransferType {
+        PreTrade => "pre",
+        PostTrade => "post",
+        OffMarket => "off",
+        Gift => "gift",
+    }
+}
+
+impl TransactionType {
+    /// The other side of the trade.
+    pub const fn opposite(&self) -> Self {
+        match self {
+            TransactionType::Buy => TransactionType::Sell,
+            TransactionType::Sell => TransactionType::Buy,
+        }
+    }
+}
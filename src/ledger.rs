@@ -0,0 +1,288 @@
+//! Parsing of Zerodha Console ledger exports.
+//!
+//! Console exports the funds ledger as CSV with the columns `particulars`, `posting_date`,
+//! `cost_center`, `voucher_type`, `debit`, `credit` and `net_balance` (header spelling and
+//! case vary between exports). [`parse_ledger_csv`] turns such a file into typed
+//! [`LedgerEntry`] values, [`LedgerSummary`] totals them per kind, and
+//! `KiteConnect::reconcile_ledger` checks the result against the margins API.
+
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use std::io::Read;
+
+use crate::KiteConnect;
+use crate::models::KiteConnectError;
+use crate::users::Margins;
+
+/// Differences below this are treated as rounding.
+const RECONCILE_TOLERANCE: f64 = 0.01;
+
+/// What a ledger line is for, derived from its particulars and voucher type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LedgerEntryKind {
+    OpeningBalance,
+    ClosingBalance,
+    /// Funds added to the trading account.
+    Payin,
+    /// Funds withdrawn to the bank.
+    Payout,
+    /// Net obligation of trades settled on the day.
+    Settlement,
+    /// Brokerage, DP, call & trade and other charges, including GST on them.
+    Charges,
+    /// Annual maintenance charge of the demat account.
+    Amc,
+    /// Interest on debit balance or delayed payment.
+    Interest,
+    Other,
+}
+
+impl LedgerEntryKind {
+    fn classify(particulars: &str, voucher_type: &str) -> Self {
+        let p = particulars.to_lowercase();
+        let v = voucher_type.to_lowercase();
+
+        if p.starts_with("opening balance") {
+            LedgerEntryKind::OpeningBalance
+        } else if p.starts_with("closing balance") {
+            LedgerEntryKind::ClosingBalance
+        } else if p.contains("amc") || p.contains("account maintenance") {
+            LedgerEntryKind::Amc
+        } else if p.contains("interest") || p.contains("delayed payment") {
+            LedgerEntryKind::Interest
+        } else if p.contains("payout") || p.contains("withdraw") || v.contains("bank payment") {
+            LedgerEntryKind::Payout
+        } else if p.contains("funds added")
+            || p.contains("payin")
+            || p.contains("pay-in")
+            || v.contains("bank receipt")
+        {
+            LedgerEntryKind::Payin
+        } else if p.contains("settlement") || p.contains("net obligation") {
+            LedgerEntryKind::Settlement
+        } else if p.contains("charge") || p.contains("brokerage") || p.contains("gst") {
+            LedgerEntryKind::Charges
+        } else {
+            LedgerEntryKind::Other
+        }
+    }
+}
+
+/// LedgerEntry is a single line of a ledger export.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerEntry {
+    pub particulars: String,
+    /// Missing on opening and closing balance lines in some exports.
+    pub posting_date: Option<NaiveDate>,
+    pub cost_center: String,
+    pub voucher_type: String,
+    pub debit: f64,
+    pub credit: f64,
+    pub net_balance: f64,
+    pub kind: LedgerEntryKind,
+}
+
+impl LedgerEntry {
+    /// Credit minus debit.
+    pub fn amount(&self) -> f64 {
+        self.credit - self.debit
+    }
+}
+
+/// Parse a Console ledger CSV export.
+pub fn parse_ledger_csv<R: Read>(reader: R) -> Result<Vec<LedgerEntry>, KiteConnectError> {
+    let mut reader = csv::ReaderBuilder::new()
+        .flexible(true)
+        .trim(csv::Trim::All)
+        .from_reader(reader);
+
+    let headers = reader.headers().map_err(csv_error)?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| normalize_header(h) == name)
+            .ok_or_else(|| KiteConnectError::other(format!("ledger CSV has no `{}` column", name)))
+    };
+    let particulars = column("particulars")?;
+    let posting_date = column("postingdate")?;
+    let debit = column("debit")?;
+    let credit = column("credit")?;
+    let net_balance = column("netbalance")?;
+    let cost_center = column("costcenter").ok();
+    let voucher_type = column("vouchertype").ok();
+
+    let mut entries = Vec::new();
+    for (line, record) in reader.records().enumerate() {
+        let record = record.map_err(csv_error)?;
+        let field = |idx: Option<usize>| {
+            idx.and_then(|i| record.get(i))
+                .unwrap_or_default()
+                .to_owned()
+        };
+        // Header row counts as line 1
+        let amount = |idx: usize, name: &str| {
+            parse_amount(record.get(idx).unwrap_or_default()).ok_or_else(|| {
+                KiteConnectError::other(format!(
+                    "ledger line {}: invalid {} `{}`",
+                    line + 2,
+                    name,
+                    record.get(idx).unwrap_or_default()
+                ))
+            })
+        };
+
+        let particulars = field(Some(particulars));
+        if particulars.is_empty() && record.iter().all(str::is_empty) {
+            continue;
+        }
+        let voucher_type = field(voucher_type);
+        let date = field(Some(posting_date));
+
+        entries.push(LedgerEntry {
+            kind: LedgerEntryKind::classify(&particulars, &voucher_type),
+            posting_date: parse_date(&date),
+            cost_center: field(cost_center),
+            debit: amount(debit, "debit")?,
+            credit: amount(credit, "credit")?,
+            net_balance: amount(net_balance, "net balance")?,
+            particulars,
+            voucher_type,
+        });
+    }
+    Ok(entries)
+}
+
+/// LedgerSummary totals a ledger by kind. Debits are positive in `payouts`, `charges`,
+/// `amc` and `interest`; `settlements` and `other` are net credit.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct LedgerSummary {
+    pub opening_balance: Option<f64>,
+    pub closing_balance: Option<f64>,
+    pub payins: f64,
+    pub payouts: f64,
+    pub settlements: f64,
+    pub charges: f64,
+    pub amc: f64,
+    pub interest: f64,
+    pub other: f64,
+}
+
+impl LedgerSummary {
+    pub fn from_entries(entries: &[LedgerEntry]) -> Self {
+        let mut summary = Self::default();
+        for entry in entries {
+            match entry.kind {
+                LedgerEntryKind::OpeningBalance => {
+                    summary.opening_balance.get_or_insert(entry.net_balance);
+                }
+                LedgerEntryKind::ClosingBalance => {
+                    summary.closing_balance = Some(entry.net_balance)
+                }
+                LedgerEntryKind::Payin => summary.payins += entry.amount(),
+                LedgerEntryKind::Payout => summary.payouts -= entry.amount(),
+                LedgerEntryKind::Settlement => summary.settlements += entry.amount(),
+                LedgerEntryKind::Charges => summary.charges -= entry.amount(),
+                LedgerEntryKind::Amc => summary.amc -= entry.amount(),
+                LedgerEntryKind::Interest => summary.interest -= entry.amount(),
+                LedgerEntryKind::Other => summary.other += entry.amount(),
+            }
+        }
+        summary
+    }
+
+    /// Balance implied by the opening balance and every movement.
+    pub fn computed_balance(&self) -> f64 {
+        self.opening_balance.unwrap_or_default() + self.payins - self.payouts + self.settlements
+            - self.charges
+            - self.amc
+            - self.interest
+            + self.other
+    }
+
+    /// The closing balance line if the export has one, otherwise the computed balance.
+    pub fn balance(&self) -> f64 {
+        self.closing_balance
+            .unwrap_or_else(|| self.computed_balance())
+    }
+}
+
+/// LedgerReconciliation compares a ledger against the margins API.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct LedgerReconciliation {
+    pub summary: LedgerSummary,
+    /// Closing balance per the ledger.
+    pub ledger_balance: f64,
+    /// `available.opening_balance` of the equity segment.
+    pub margins_opening_balance: f64,
+    /// `margins_opening_balance - ledger_balance`.
+    pub difference: f64,
+    /// Whether the ledger's own lines add up to its closing balance.
+    pub internally_consistent: bool,
+}
+
+impl LedgerReconciliation {
+    /// Reconcile against segment margins fetched separately. The ledger should run up to
+    /// the previous trading day, whose closing balance is today's opening balance.
+    pub fn new(entries: &[LedgerEntry], equity: &Margins) -> Self {
+        let summary = LedgerSummary::from_entries(entries);
+        let ledger_balance = summary.balance();
+        let internally_consistent = summary.closing_balance.is_none_or(|closing| {
+            (closing - summary.computed_balance()).abs() < RECONCILE_TOLERANCE
+        });
+        let margins_opening_balance = equity.available.opening_balance;
+
+        Self {
+            difference: margins_opening_balance - ledger_balance,
+            summary,
+            ledger_balance,
+            margins_opening_balance,
+            internally_consistent,
+        }
+    }
+
+    /// Whether the ledger matches the margins API, allowing for rounding.
+    pub fn matches(&self) -> bool {
+        self.internally_consistent && self.difference.abs() < RECONCILE_TOLERANCE
+    }
+}
+
+impl KiteConnect {
+    /// Reconcile a parsed ledger against the equity segment margins.
+    pub async fn reconcile_ledger(
+        &self,
+        entries: &[LedgerEntry],
+    ) -> Result<LedgerReconciliation, KiteConnectError> {
+        let equity = self.get_user_segment_margins("equity").await?;
+        Ok(LedgerReconciliation::new(entries, &equity))
+    }
+}
+
+fn normalize_header(header: &str) -> String {
+    header
+        .chars()
+        .filter(|c| c.is_ascii_alphanumeric())
+        .map(|c| c.to_ascii_lowercase())
+        .collect()
+}
+
+fn parse_amount(value: &str) -> Option<f64> {
+    let cleaned: String = value
+        .chars()
+        .filter(|c| !matches!(c, ',' | '₹' | ' '))
+        .collect();
+    if cleaned.is_empty() || cleaned == "-" {
+        return Some(0.0);
+    }
+    cleaned.parse().ok()
+}
+
+fn parse_date(value: &str) -> Option<NaiveDate> {
+    ["%Y-%m-%d", "%d-%m-%Y", "%d/%m/%Y", "%d-%b-%Y"]
+        .iter()
+        .find_map(|format| NaiveDate::parse_from_str(value, format).ok())
+}
+
+fn csv_error(e: csv::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("CSV parsing error: {}", e))
+}
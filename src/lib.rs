@@ -1,23 +1,96 @@
+pub mod capabilities;
+pub mod clock;
+pub mod codec;
+pub mod compact;
 pub mod compat;
 pub mod connect;
+pub mod corporate_actions;
+pub mod enrich;
+pub mod executor;
+pub mod freeze;
+#[cfg(all(feature = "grpc-gateway", not(target_arch = "wasm32")))]
+pub mod gateway;
+pub mod gtt;
+pub mod halts;
+pub mod health;
 
 pub mod http;
+pub mod instruments;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod journal;
+#[cfg(target_arch = "wasm32")]
+pub mod js;
+pub mod labels;
+pub mod ledger;
+pub mod margin_watch;
 pub mod margins;
 pub mod markets;
+#[cfg(all(feature = "prometheus", not(target_arch = "wasm32")))]
+pub mod metrics;
 pub mod mf;
+pub mod mtf;
+pub mod ohlcv;
 
 pub mod alerts;
+pub mod amo;
+pub mod audit;
+pub mod backtest;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod bridge;
+#[cfg(all(feature = "axum", not(target_arch = "wasm32")))]
+pub mod broadcast;
+pub mod order_state;
 pub mod orders;
+pub mod pagination;
 pub mod portfolio;
+pub mod portfolio_tracker;
+pub mod publisher;
+pub mod risk;
+pub mod series;
+pub mod session;
+pub mod sizing;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod snapshot;
+pub mod strategies;
+pub mod subscriptions;
+pub mod supervisor;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod sinks;
+pub mod tags;
 pub mod ticker;
+pub mod transport;
+pub mod twap;
+pub mod usage;
 pub mod users;
+pub mod validation;
+#[cfg(all(feature = "vcr", not(target_arch = "wasm32")))]
+pub mod vcr;
+pub mod version;
+#[cfg(feature = "watchlists")]
+pub mod watchlists;
 
-pub use connect::{KiteConnect, KiteConnectBuilder};
+#[cfg(feature = "test-utils")]
+pub mod test_utils;
+
+pub use connect::{KiteConnect, KiteConnectBuilder, LoginCallback};
 pub use models::*;
-pub use ticker::{Mode, Ticker, TickerBuilder, TickerError, TickerEvent};
+pub use compat::TlsOptions;
+pub use ticker::{
+    EventReceiver, LatencyStats, Mode, StampedEvent, Ticker, TickerBuilder, TickerError,
+    TickerErrorKind, TickerEvent, TokenValidation,
+};
+
+// Re-export capability types
+pub use capabilities::Capabilities;
+
+// Re-export API version types
+pub use version::{ApiVersion, ResponseAdapter};
 
 // Re-export order types
-pub use orders::{Order, OrderParams, OrderResponse, Orders, Trade, Trades};
+pub use orders::{
+    CancelFilter, CancelResult, CoverOrderIds, IcebergParams, Order, OrderParams,
+    OrderParamsBuilder, OrderResponse, Orders, Trade, Trades,
+};
 
 pub mod constants;
 #[path = "models/mod.rs"]
@@ -26,12 +99,58 @@ pub use constants::Endpoints;
 pub use constants::Labels;
 pub use constants::app_constants::*;
 
+// Re-export pagination types
+pub use pagination::{PageParams, Paginated};
+
 // Re-export portfolio types
 pub use portfolio::{
     AuctionInstrument, ConvertPositionParams, Holding, HoldingAuthParams, Holdings,
-    HoldingsAuthInstruments, HoldingsAuthResp, MTFHolding, Position, Positions,
+    HoldingsAuthInstruments, HoldingsAuthResp, MTFHolding, Position, Positions, SquareOffOutcome,
+    SquareOffParams, SquareOffResult,
 };
 
+// Re-export portfolio tracker types
+pub use portfolio_tracker::{PortfolioChange, PortfolioTracker, QuantityUpdate};
+
+// Re-export audit types
+pub use audit::{AuditAction, AuditLog, AuditRecord};
+
+// Re-export instrument lookup types
+pub use instruments::InstrumentStore;
+
+// Re-export tick enrichment
+pub use enrich::TickEnricher;
+
+// Re-export clock types
+pub use clock::{MarketClock, MarketHours, MarketPhase};
+
+// Re-export series types
+pub use series::{OhlcSeries, SeriesCandle};
+
+// Re-export TA adapter traits
+pub use ohlcv::{Ohlcv, OhlcvExt};
+
+// Re-export tag types
+pub use tags::{StrategyTag, TagRegistry};
+
+// Re-export usage types
+pub use usage::{ApiCategory, CategoryUsage, UsagePool, UsageStats};
+
+// Re-export margin watch types
+pub use margin_watch::{MarginEvent, MarginWatchConfig, MarginWatcher};
+
+// Re-export executor types
+pub use executor::{ExecutionOutcome, ExecutionReport, Executor, ExecutorConfig};
+
+// Re-export subscription manager types
+pub use subscriptions::{SubscriptionChange, SubscriptionManager, SubscriptionPriority};
+
+// Re-export TWAP types
+pub use twap::{Twap, TwapConfig, TwapEvent, TwapProgress, TwapState};
+
+// Re-export risk types
+pub use risk::{DailyLossLimiter, RiskLimits, RiskViolation};
+
 // Re-export user types
 pub use users::{
     AllMargins, AvailableMargins, Bank, FullUserMeta, FullUserProfile, Margins, UsedMargins,
@@ -47,18 +166,20 @@ pub use mf::{
 
 // Re-export margins types
 pub use margins::{
-    BasketMargins, Charges, GST, GetBasketParams, GetChargesParams, GetMarginParams, OrderCharges,
-    OrderChargesParam, OrderMarginParam, OrderMargins, PNL,
+    BasketMargins, BasketMarginsCompact, Charges, GST, GetBasketParams, GetChargesParams,
+    GetMarginParams, MarginMode, OrderCharges, OrderChargesParam, OrderMarginParam, OrderMargins,
+    OrderMarginsCompact, PNL,
 };
 
 // Re-export market data types
 pub use markets::{
-    HistoricalData, HistoricalDataParams, Instrument, Instruments, MFInstrument, MFInstruments,
-    Quote, QuoteData, QuoteLTP, QuoteLTPData, QuoteOHLC, QuoteOHLCData,
+    CandleRow, HistoricalData, HistoricalDataParams, HistoricalDataResponse, Instrument,
+    InstrumentFilter, Instruments, MFInstrument, MFInstruments, Quote, QuoteData, QuoteLTP,
+    QuoteLTPData, QuoteOHLC, QuoteOHLCData,
 };
 
 // Re-export alerts types
 pub use alerts::{
-    Alert, AlertHistory, AlertHistoryMeta, AlertOperator, AlertOrderParams, AlertParams,
-    AlertStatus, AlertType, Basket, BasketItem, OrderGTTParams,
+    Alert, AlertCrossed, AlertHistory, AlertHistoryMeta, AlertOperator, AlertOrderParams,
+    AlertParams, AlertStatus, AlertType, AlertWatcher, Basket, BasketItem, OrderGTTParams,
 };
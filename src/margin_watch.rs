@@ -0,0 +1,242 @@
+//! Local margin utilisation alerts.
+//!
+//! [`MarginWatcher`] polls `get_user_margins` from a background task and emits a
+//! [`MarginEvent`] whenever utilisation crosses one of the configured thresholds, so a bot
+//! can cut exposure before the broker starts rejecting orders or squaring off.
+//!
+//! ```ignore
+//! let watcher = MarginWatcher::spawn(kite.clone(), MarginWatchConfig::default());
+//! let events = watcher.subscribe_events();
+//! while let Ok(event) = events.recv().await {
+//!     if let MarginEvent::Critical(pct) = event {
+//!         kite.square_off_all(params.clone()).await?;
+//!     }
+//! }
+//! ```
+
+use async_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::sync::{Arc, Mutex};
+use web_time::Duration;
+
+use crate::KiteConnect;
+use crate::compat::{self, TaskHandle};
+use crate::usage::ApiCategory;
+use crate::users::{AllMargins, Margins};
+
+/// Shortest allowed interval between polls.
+pub const MIN_POLL_INTERVAL: Duration = Duration::from_secs(1);
+
+/// The margin segment whose utilisation is watched.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginSegment {
+    #[default]
+    Equity,
+    Commodity,
+    /// Equity and commodity summed.
+    Combined,
+}
+
+/// MarginWatchConfig configures a [`MarginWatcher`]. Thresholds are percentages.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MarginWatchConfig {
+    pub segment: MarginSegment,
+    pub warning_pct: f64,
+    pub critical_pct: f64,
+    pub poll_interval: Duration,
+}
+
+impl Default for MarginWatchConfig {
+    fn default() -> Self {
+        Self {
+            segment: MarginSegment::Equity,
+            warning_pct: 75.0,
+            critical_pct: 90.0,
+            poll_interval: Duration::from_secs(15),
+        }
+    }
+}
+
+impl MarginWatchConfig {
+    pub fn segment(mut self, segment: MarginSegment) -> Self {
+        self.segment = segment;
+        self
+    }
+
+    pub fn warning_pct(mut self, pct: f64) -> Self {
+        self.warning_pct = pct;
+        self
+    }
+
+    pub fn critical_pct(mut self, pct: f64) -> Self {
+        self.critical_pct = pct;
+        self
+    }
+
+    /// Raised to [`MIN_POLL_INTERVAL`] if shorter.
+    pub fn poll_interval(mut self, interval: Duration) -> Self {
+        self.poll_interval = interval;
+        self
+    }
+}
+
+/// MarginEvent is emitted by [`MarginWatcher`] when the utilisation level changes.
+/// Each variant carries the utilisation in percent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum MarginEvent {
+    Warning(f64),
+    Critical(f64),
+    /// Back below the warning threshold.
+    Recovered(f64),
+    /// Polling failed; the watcher keeps polling.
+    Error(String),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Normal,
+    Warning,
+    Critical,
+}
+
+/// Percentage of a segment's margin in use: debits over debits plus net available.
+pub fn utilisation_pct(margins: &Margins) -> f64 {
+    ratio_pct(margins.used.debits, margins.net)
+}
+
+/// Utilisation of `segment` in percent.
+pub fn segment_utilisation_pct(margins: &AllMargins, segment: MarginSegment) -> f64 {
+    match segment {
+        MarginSegment::Equity => utilisation_pct(&margins.equity),
+        MarginSegment::Commodity => utilisation_pct(&margins.commodity),
+        MarginSegment::Combined => ratio_pct(
+            margins.equity.used.debits + margins.commodity.used.debits,
+            margins.equity.net + margins.commodity.net,
+        ),
+    }
+}
+
+fn ratio_pct(debits: f64, net: f64) -> f64 {
+    let total = debits + net;
+    if total <= 0.0 {
+        return if debits > 0.0 { 100.0 } else { 0.0 };
+    }
+    debits / total * 100.0
+}
+
+/// MarginLevels tracks the threshold level and turns utilisation samples into events.
+#[derive(Debug, Clone)]
+pub struct MarginLevels {
+    warning_pct: f64,
+    critical_pct: f64,
+    level: Level,
+}
+
+impl MarginLevels {
+    pub fn new(warning_pct: f64, critical_pct: f64) -> Self {
+        Self {
+            warning_pct,
+            critical_pct,
+            level: Level::Normal,
+        }
+    }
+
+    /// Feed a utilisation sample; returns an event when the level changed.
+    pub fn update(&mut self, pct: f64) -> Option<MarginEvent> {
+        let level = if pct >= self.critical_pct {
+            Level::Critical
+        } else if pct >= self.warning_pct {
+            Level::Warning
+        } else {
+            Level::Normal
+        };
+        if level == self.level {
+            return None;
+        }
+        self.level = level;
+        Some(match level {
+            Level::Critical => MarginEvent::Critical(pct),
+            Level::Warning => MarginEvent::Warning(pct),
+            Level::Normal => MarginEvent::Recovered(pct),
+        })
+    }
+}
+
+/// MarginWatcher polls user margins from a background task and publishes [`MarginEvent`]s.
+///
+/// Like the ticker, events go to an unbounded channel; every receiver from
+/// [`subscribe_events`](Self::subscribe_events) shares it, so each event is delivered to
+/// one of them. The task stops when the watcher is dropped.
+pub struct MarginWatcher {
+    event_receiver: Receiver<MarginEvent>,
+    last: Arc<Mutex<Option<f64>>>,
+    task: TaskHandle,
+}
+
+impl MarginWatcher {
+    pub fn spawn(kite: KiteConnect, config: MarginWatchConfig) -> Self {
+        let (event_sender, event_receiver) = async_channel::unbounded();
+        let last = Arc::new(Mutex::new(None));
+        let task = compat::spawn(run_watcher(kite, config, event_sender, last.clone()));
+        Self {
+            event_receiver,
+            last,
+            task,
+        }
+    }
+
+    pub fn subscribe_events(&self) -> Receiver<MarginEvent> {
+        self.event_receiver.clone()
+    }
+
+    /// Utilisation in percent from the most recent successful poll.
+    pub fn last_utilisation(&self) -> Option<f64> {
+        *self.last.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    pub fn stop(&self) {
+        self.task.abort();
+        self.event_receiver.close();
+    }
+}
+
+impl Drop for MarginWatcher {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+async fn run_watcher(
+    kite: KiteConnect,
+    config: MarginWatchConfig,
+    events: Sender<MarginEvent>,
+    last: Arc<Mutex<Option<f64>>>,
+) {
+    let interval = config.poll_interval.max(MIN_POLL_INTERVAL);
+    let mut levels = MarginLevels::new(config.warning_pct, config.critical_pct);
+    loop {
+        // Leave the per-second budget to order and portfolio calls made by the same client
+        let usage = kite.usage_stats();
+        let other = usage.category(ApiCategory::Other);
+        if other.last_second >= other.limit_per_second {
+            compat::sleep(MIN_POLL_INTERVAL).await;
+            continue;
+        }
+
+        let event = match kite.get_user_margins().await {
+            Ok(margins) => {
+                let pct = segment_utilisation_pct(&margins, config.segment);
+                *last.lock().unwrap_or_else(|e| e.into_inner()) = Some(pct);
+                levels.update(pct)
+            }
+            Err(e) => Some(MarginEvent::Error(e.to_string())),
+        };
+        if let Some(event) = event {
+            if events.send(event).await.is_err() {
+                return;
+            }
+        }
+        compat::sleep(interval).await;
+    }
+}
@@ -42,7 +42,7 @@ pub struct PNL {
 }
 
 /// GST represents the various GST charges
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct GST {
     pub igst: f64,
     pub cgst: f64,
@@ -51,7 +51,7 @@ pub struct GST {
 }
 
 /// Charges represents breakdown of various charges that are applied to an order
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct Charges {
     pub transaction_tax: f64,
     pub transaction_tax_type: String,
@@ -63,6 +63,28 @@ pub struct Charges {
     pub total: f64,
 }
 
+/// Adds up the charges of several orders, e.g. every trade of a position.
+impl<'a> std::iter::Sum<&'a Charges> for Charges {
+    fn sum<I: Iterator<Item = &'a Charges>>(iter: I) -> Self {
+        iter.fold(Charges::default(), |mut sum, c| {
+            if sum.transaction_tax_type.is_empty() {
+                sum.transaction_tax_type = c.transaction_tax_type.clone();
+            }
+            sum.transaction_tax += c.transaction_tax;
+            sum.exchange_turnover_charge += c.exchange_turnover_charge;
+            sum.sebi_turnover_charge += c.sebi_turnover_charge;
+            sum.brokerage += c.brokerage;
+            sum.stamp_duty += c.stamp_duty;
+            sum.gst.igst += c.gst.igst;
+            sum.gst.cgst += c.gst.cgst;
+            sum.gst.sgst += c.gst.sgst;
+            sum.gst.total += c.gst.total;
+            sum.total += c.total;
+            sum
+        })
+    }
+}
+
 /// OrderMargins represents response from the Margin Calculator API.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderMargins {
@@ -90,10 +112,24 @@ pub struct OrderMargins {
     pub pnl: Option<PNL>,
     #[serde(default)]
     pub leverage: f64,
+    /// Not returned in [`MarginMode::Compact`].
+    #[serde(default)]
     pub charges: Charges,
     pub total: f64,
 }
 
+/// OrderMarginsCompact represents response from the Margin Calculator API in
+/// [`MarginMode::Compact`], which only carries the total.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct OrderMarginsCompact {
+    #[serde(rename = "type")]
+    pub order_type: String,
+    #[serde(rename = "tradingsymbol")]
+    pub trading_symbol: String,
+    pub exchange: String,
+    pub total: f64,
+}
+
 /// OrderCharges represent an item's response from the Charges calculator API
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct OrderCharges {
@@ -120,18 +156,51 @@ pub struct BasketMargins {
     pub orders: Vec<OrderMargins>,
 }
 
+/// BasketMarginsCompact represents response from the Margin Calculator API for Basket orders
+/// in [`MarginMode::Compact`]
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BasketMarginsCompact {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub initial: Option<OrderMarginsCompact>,
+    #[serde(rename = "final")]
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub final_margins: Option<OrderMarginsCompact>,
+    pub orders: Vec<OrderMarginsCompact>,
+}
+
+/// MarginMode selects the shape of Margin Calculator API responses
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum MarginMode {
+    /// Every margin component and the charges
+    #[default]
+    Full,
+    /// Only the total margin
+    Compact,
+}
+
+impl MarginMode {
+    // Query string selecting the mode, if it is not the default
+    fn query(&self) -> Option<&'static str> {
+        match self {
+            MarginMode::Full => None,
+            MarginMode::Compact => Some("mode=compact"),
+        }
+    }
+}
+
 /// Parameters for getting order margins
 #[derive(Debug, Clone)]
 pub struct GetMarginParams {
     pub order_params: Vec<OrderMarginParam>,
-    pub compact: bool,
+    pub mode: MarginMode,
 }
 
 /// Parameters for getting basket margins
 #[derive(Debug, Clone)]
 pub struct GetBasketParams {
     pub order_params: Vec<OrderMarginParam>,
-    pub compact: bool,
+    pub mode: MarginMode,
     pub consider_positions: bool,
 }
 
@@ -141,40 +210,73 @@ pub struct GetChargesParams {
     pub order_params: Vec<OrderChargesParam>,
 }
 
+fn order_margins_endpoint(mode: MarginMode) -> String {
+    let mut endpoint = Endpoints::ORDER_MARGINS.to_string();
+    if let Some(query) = mode.query() {
+        endpoint.push('?');
+        endpoint.push_str(query);
+    }
+    endpoint
+}
+
+fn basket_margins_endpoint(mode: MarginMode, consider_positions: bool) -> String {
+    let mut endpoint = Endpoints::BASKET_MARGINS.to_string();
+    let mut query_params = Vec::new();
+
+    if let Some(query) = mode.query() {
+        query_params.push(query);
+    }
+    if consider_positions {
+        query_params.push("consider_positions=true");
+    }
+
+    if !query_params.is_empty() {
+        endpoint.push('?');
+        endpoint.push_str(&query_params.join("&"));
+    }
+    endpoint
+}
+
 impl KiteConnect {
-    /// Get order margins for a list of orders
+    /// Get order margins for a list of orders. In [`MarginMode::Compact`] only the total is
+    /// set; [`get_order_margins_compact`](Self::get_order_margins_compact) types that shape.
     pub async fn get_order_margins(
         &self,
         params: GetMarginParams,
     ) -> Result<Vec<OrderMargins>, KiteConnectError> {
-        let mut endpoint = Endpoints::ORDER_MARGINS.to_string();
-        if params.compact {
-            endpoint.push_str("?mode=compact");
-        }
+        self.post_json(&order_margins_endpoint(params.mode), params.order_params)
+            .await
+    }
 
-        self.post_json(&endpoint, params.order_params).await
+    /// Get compact order margins for a list of orders, whatever the mode of `params`
+    pub async fn get_order_margins_compact(
+        &self,
+        params: GetMarginParams,
+    ) -> Result<Vec<OrderMarginsCompact>, KiteConnectError> {
+        self.post_json(
+            &order_margins_endpoint(MarginMode::Compact),
+            params.order_params,
+        )
+        .await
     }
 
-    /// Get basket margins for a list of orders
+    /// Get basket margins for a list of orders. In [`MarginMode::Compact`] only the totals
+    /// are set; [`get_basket_margins_compact`](Self::get_basket_margins_compact) types that
+    /// shape.
     pub async fn get_basket_margins(
         &self,
         params: GetBasketParams,
     ) -> Result<BasketMargins, KiteConnectError> {
-        let mut endpoint = Endpoints::BASKET_MARGINS.to_string();
-        let mut query_params = Vec::new();
-
-        if params.compact {
-            query_params.push("mode=compact");
-        }
-        if params.consider_positions {
-            query_params.push("consider_positions=true");
-        }
-
-        if !query_params.is_empty() {
-            endpoint.push('?');
-            endpoint.push_str(&query_params.join("&"));
-        }
+        let endpoint = basket_margins_endpoint(params.mode, params.consider_positions);
+        self.post_json(&endpoint, &params.order_params).await
+    }
 
+    /// Get compact basket margins for a list of orders, whatever the mode of `params`
+    pub async fn get_basket_margins_compact(
+        &self,
+        params: GetBasketParams,
+    ) -> Result<BasketMarginsCompact, KiteConnectError> {
+        let endpoint = basket_margins_endpoint(MarginMode::Compact, params.consider_positions);
         self.post_json(&endpoint, &params.order_params).await
     }
 
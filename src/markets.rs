@@ -1,11 +1,17 @@
+use chrono::{Days, NaiveDate};
+use chrono_tz::Asia::Kolkata;
 use serde::{Deserialize, Deserializer, Serialize};
-use std::collections::HashMap;
+use std::collections::{BTreeSet, HashMap, HashSet};
 
 use crate::{
     KiteConnect,
+    clock::MarketClock,
     constants::Endpoints,
+    halts::circuit_side,
     models::{Depth, KiteConnectError, OHLC, time},
 };
+#[cfg(not(target_arch = "wasm32"))]
+use crate::transport::TransportResponse;
 
 /// Custom deserializer to convert integer (0/1) to boolean
 fn bool_from_int<'de, D>(deserializer: D) -> Result<bool, D::Error>
@@ -46,6 +52,19 @@ pub struct QuoteData {
     pub depth: Depth,
 }
 
+impl QuoteData {
+    /// Whether the last price is at the upper or lower circuit limit, where the scrip
+    /// trades one way only or not at all. Limits of 0, as on F&O contracts, never match.
+    pub fn at_circuit_limit(&self) -> bool {
+        circuit_side(
+            self.last_price,
+            self.lower_circuit_limit,
+            self.upper_circuit_limit,
+        )
+        .is_some()
+    }
+}
+
 /// Quote represents a map of instrument symbols to their quote data.
 pub type Quote = HashMap<String, QuoteData>;
 
@@ -83,10 +102,111 @@ pub struct HistoricalData {
     pub oi: u32,
 }
 
+/// CandleRow is a candle as sent by the API: `[timestamp, open, high, low, close, volume]`,
+/// followed by open interest when requested with `oi=1`.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct CandleRow(
+    pub time::Time,
+    pub f64,
+    pub f64,
+    pub f64,
+    pub f64,
+    pub f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")] pub Option<f64>,
+);
+
+impl From<CandleRow> for HistoricalData {
+    fn from(row: CandleRow) -> Self {
+        let CandleRow(date, open, high, low, close, volume, oi) = row;
+        HistoricalData {
+            date,
+            open,
+            high,
+            low,
+            close,
+            volume: volume as u32,
+            oi: oi.unwrap_or_default() as u32,
+        }
+    }
+}
+
 /// HistoricalDataResponse represents the response wrapper for historical data.
 #[derive(Debug, Clone, Serialize, Deserialize)]
-struct HistoricalDataResponse {
-    pub candles: Vec<Vec<serde_json::Value>>,
+pub struct HistoricalDataResponse {
+    pub candles: Vec<CandleRow>,
+}
+
+impl From<HistoricalDataResponse> for Vec<HistoricalData> {
+    fn from(response: HistoricalDataResponse) -> Self {
+        response
+            .candles
+            .into_iter()
+            .map(HistoricalData::from)
+            .collect()
+    }
+}
+
+/// HistoricalDataFull is a date range of candles fetched in several requests by
+/// [`KiteConnect::get_historical_data_full`].
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct HistoricalDataFull {
+    pub candles: Vec<HistoricalData>,
+    /// Trading days in the range without a single candle, e.g. while the instrument was
+    /// suspended or before it listed.
+    pub gaps: Vec<NaiveDate>,
+}
+
+impl HistoricalDataFull {
+    pub fn is_gap(&self, date: NaiveDate) -> bool {
+        self.gaps.binary_search(&date).is_ok()
+    }
+}
+
+/// Most calendar days one historical data request may span for `interval`.
+pub fn historical_max_days(interval: &str) -> u64 {
+    match interval {
+        "minute" => 60,
+        "3minute" | "5minute" | "10minute" => 100,
+        "15minute" | "30minute" => 200,
+        "60minute" => 400,
+        "day" => 2000,
+        _ => 60,
+    }
+}
+
+/// Split `from..=to` into request windows for `interval`. Windows start and end on
+/// trading days of `clock`, and stretches without any trading day are left out.
+pub fn historical_windows(
+    interval: &str,
+    from: NaiveDate,
+    to: NaiveDate,
+    clock: &MarketClock,
+) -> Vec<(NaiveDate, NaiveDate)> {
+    let max_days = historical_max_days(interval);
+    let next_day = |date: NaiveDate| date.checked_add_days(Days::new(1));
+    let mut windows = Vec::new();
+    let mut start = Some(from);
+    while let Some(mut first) = start {
+        while first <= to && !clock.is_trading_day(first) {
+            first = match next_day(first) {
+                Some(date) => date,
+                None => return windows,
+            };
+        }
+        if first > to {
+            break;
+        }
+        let limit = first
+            .checked_add_days(Days::new(max_days - 1))
+            .map_or(to, |date| date.min(to));
+        let mut last = limit;
+        while !clock.is_trading_day(last) {
+            last = last.pred_opt().unwrap_or(first);
+        }
+        windows.push((first, last));
+        start = next_day(limit);
+    }
+    windows
 }
 
 /// HistoricalDataParams represents parameters for historical data requests.
@@ -119,6 +239,71 @@ pub struct Instrument {
 /// Instruments represents list of instruments.
 pub type Instruments = Vec<Instrument>;
 
+/// InstrumentFilter selects instruments from the instrument dump while it is parsed.
+///
+/// Each set that is non-empty must contain the instrument's value; empty sets match
+/// everything. Rows are checked on their raw CSV fields, so rejected rows are never
+/// deserialized.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct InstrumentFilter {
+    pub exchanges: HashSet<String>,
+    /// e.g. `NFO-OPT`, `NSE`, `INDICES`.
+    pub segments: HashSet<String>,
+    /// e.g. `EQ`, `FUT`, `CE`, `PE`.
+    pub instrument_types: HashSet<String>,
+    /// Underlying names, e.g. `NIFTY`.
+    pub names: HashSet<String>,
+}
+
+impl InstrumentFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn exchange(mut self, exchange: impl Into<String>) -> Self {
+        self.exchanges.insert(exchange.into());
+        self
+    }
+
+    pub fn segment(mut self, segment: impl Into<String>) -> Self {
+        self.segments.insert(segment.into());
+        self
+    }
+
+    pub fn instrument_type(mut self, instrument_type: impl Into<String>) -> Self {
+        self.instrument_types.insert(instrument_type.into());
+        self
+    }
+
+    pub fn name(mut self, name: impl Into<String>) -> Self {
+        self.names.insert(name.into());
+        self
+    }
+
+    pub fn matches(&self, instrument: &Instrument) -> bool {
+        self.matches_fields(
+            &instrument.exchange,
+            &instrument.segment,
+            &instrument.instrument_type,
+            &instrument.name,
+        )
+    }
+
+    fn matches_fields(
+        &self,
+        exchange: &str,
+        segment: &str,
+        instrument_type: &str,
+        name: &str,
+    ) -> bool {
+        let allowed = |set: &HashSet<String>, value: &str| set.is_empty() || set.contains(value);
+        allowed(&self.exchanges, exchange)
+            && allowed(&self.segments, segment)
+            && allowed(&self.instrument_types, instrument_type)
+            && allowed(&self.names, name)
+    }
+}
+
 /// MFInstrument represents individual mutual fund instrument response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct MFInstrument {
@@ -201,90 +386,52 @@ impl KiteConnect {
         params.insert("oi".to_string(), if oi { "1" } else { "0" }.to_string());
 
         let response: HistoricalDataResponse = self.get_with_query(endpoint, params).await?;
-        self.format_historical_data(response)
+        Ok(response.into())
     }
 
-    /// Formats historical data response into structured data.
-    fn format_historical_data(
+    /// Gets the candles of every trading day from `from` to `to`, both inclusive, in as
+    /// many requests as the interval's range limit needs.
+    ///
+    /// Requests cover only trading days of `clock`, so weekends and listed holidays cost
+    /// nothing. Trading days that come back without candles are listed in
+    /// [`HistoricalDataFull::gaps`].
+    #[allow(clippy::too_many_arguments)]
+    pub async fn get_historical_data_full(
         &self,
-        response: HistoricalDataResponse,
-    ) -> Result<Vec<HistoricalData>, KiteConnectError> {
-        let mut data = Vec::new();
-
-        for candle in response.candles {
-            if candle.len() < 6 {
-                return Err(KiteConnectError::other(
-                    "Invalid candle data format".to_string(),
-                ));
-            }
-
-            let date_str = candle[0]
-                .as_str()
-                .ok_or_else(|| KiteConnectError::other("Invalid date format".to_string()))?;
-
-            let open = candle[1]
-                .as_f64()
-                .ok_or_else(|| KiteConnectError::other("Invalid open price".to_string()))?;
-
-            let high = candle[2]
-                .as_f64()
-                .ok_or_else(|| KiteConnectError::other("Invalid high price".to_string()))?;
-
-            let low = candle[3]
-                .as_f64()
-                .ok_or_else(|| KiteConnectError::other("Invalid low price".to_string()))?;
-
-            let close = candle[4]
-                .as_f64()
-                .ok_or_else(|| KiteConnectError::other("Invalid close price".to_string()))?;
-
-            let volume = candle[5]
-                .as_f64()
-                .ok_or_else(|| KiteConnectError::other("Invalid volume".to_string()))?
-                as u32;
-
-            // OI is optional (7th element)
-            let oi = if candle.len() > 6 {
-                candle[6].as_f64().unwrap_or(0.0) as u32
-            } else {
-                0
-            };
-
-            // Parse date - handle different timezone formats
-            let parsed_date = if date_str.len() > 19 {
-                // Try with colon in timezone first (RFC3339 standard)
-                let date_with_colon = if date_str.ends_with("+0530") {
-                    date_str.replace("+0530", "+05:30")
-                } else if date_str.ends_with("-0530") {
-                    date_str.replace("-0530", "-05:30")
-                } else {
-                    date_str.to_string()
-                };
-
-                chrono::DateTime::parse_from_rfc3339(&date_with_colon)
-                    .or_else(|_| chrono::DateTime::parse_from_rfc3339(date_str))
-            } else {
-                chrono::DateTime::parse_from_rfc3339(date_str)
-            };
-
-            let date = parsed_date
-                .map_err(|e| {
-                    KiteConnectError::other(format!("Failed to parse date '{}': {}", date_str, e))
-                })?
-                .with_timezone(&chrono::Utc);
-
-            data.push(HistoricalData {
-                date: time::Time::new(date),
-                open,
-                high,
-                low,
-                close,
-                volume,
-                oi,
-            });
+        instrument_token: u32,
+        interval: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        continuous: bool,
+        oi: bool,
+        clock: &MarketClock,
+    ) -> Result<HistoricalDataFull, KiteConnectError> {
+        let mut candles = Vec::new();
+        for (first, last) in historical_windows(interval, from, to, clock) {
+            candles.extend(
+                self.get_historical_data(
+                    instrument_token,
+                    interval,
+                    &format!("{} 00:00:00", first),
+                    &format!("{} 23:59:59", last),
+                    continuous,
+                    oi,
+                )
+                .await?,
+            );
         }
 
-        Ok(data)
+        let covered: BTreeSet<NaiveDate> = candles
+            .iter()
+            .filter_map(|c: &HistoricalData| c.date.as_datetime())
+            .map(|date| date.with_timezone(&Kolkata).date_naive())
+            .collect();
+        let gaps = from
+            .iter_days()
+            .take_while(|date| *date <= to)
+            .filter(|date| clock.is_trading_day(*date) && !covered.contains(date))
+            .collect();
+        Ok(HistoricalDataFull { candles, gaps })
     }
 
     /// Gets all instruments.
@@ -302,6 +449,23 @@ impl KiteConnect {
         Ok(instruments)
     }
 
+    /// Gets the instruments matching `filter`, skipping other rows during parsing.
+    ///
+    /// Downloads only the exchange's dump when the filter names a single exchange.
+    pub async fn get_instruments_filtered(
+        &self,
+        filter: &InstrumentFilter,
+    ) -> Result<Instruments, KiteConnectError> {
+        let csv_text: String = match filter.exchanges.iter().next() {
+            Some(exchange) if filter.exchanges.len() == 1 => {
+                self.get(&Endpoints::GET_INSTRUMENTS_EXCHANGE.replace("{exchange}", exchange))
+                    .await?
+            }
+            _ => self.get(Endpoints::GET_INSTRUMENTS).await?,
+        };
+        parse_instruments_filtered(csv_text.as_bytes(), filter)
+    }
+
     /// Gets instruments by exchange.
     pub async fn get_instruments_by_exchange(
         &self,
@@ -336,3 +500,168 @@ impl KiteConnect {
         Ok(instruments)
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+impl KiteConnect {
+    /// Passes the instruments matching `filter` to `on_instrument` as their rows arrive and
+    /// returns how many were passed, so the dump is never held in memory.
+    ///
+    /// The download goes straight through the client's `reqwest::Client`, bypassing a
+    /// custom transport, and is not subject to `max_response_size`.
+    pub async fn stream_instruments<F>(
+        &self,
+        filter: &InstrumentFilter,
+        mut on_instrument: F,
+    ) -> Result<usize, KiteConnectError>
+    where
+        F: FnMut(Instrument),
+    {
+        let endpoint = match filter.exchanges.iter().next() {
+            Some(exchange) if filter.exchanges.len() == 1 => {
+                Endpoints::GET_INSTRUMENTS_EXCHANGE.replace("{exchange}", exchange)
+            }
+            _ => Endpoints::GET_INSTRUMENTS.to_string(),
+        };
+        self.usage.record(&reqwest::Method::GET, &endpoint);
+        let mut request = self
+            .http_client
+            .get(format!("{}{}", self.base_url, endpoint))
+            .headers(self.request_headers(&endpoint).await?);
+        if let Some(timeout) = self.request_timeout {
+            request = request.timeout(timeout);
+        }
+        let mut response = request.send().await?;
+        if !response.status().is_success() {
+            let response =
+                TransportResponse::read_limited(response, self.max_response_size).await?;
+            // handle_response turns every error status into an error
+            let response = self.adapt_response(&endpoint, response)?;
+            self.handle_response::<serde_json::Value>(response)?;
+            return Ok(0);
+        }
+
+        let mut rows = InstrumentRows::new(filter);
+        let mut buffer = Vec::new();
+        let mut count = 0;
+        while let Some(chunk) = response.chunk().await? {
+            buffer.extend_from_slice(&chunk);
+            while let Some(end) = buffer.iter().position(|&b| b == b'\n') {
+                let line: Vec<u8> = buffer.drain(..=end).collect();
+                if let Some(instrument) = rows.parse(&line)? {
+                    on_instrument(instrument);
+                    count += 1;
+                }
+            }
+        }
+        if let Some(instrument) = rows.parse(&buffer)? {
+            on_instrument(instrument);
+            count += 1;
+        }
+        Ok(count)
+    }
+}
+
+// Parses an instrument dump one line at a time; the first line is the header
+#[cfg(not(target_arch = "wasm32"))]
+struct InstrumentRows<'a> {
+    filter: &'a InstrumentFilter,
+    headers: Option<(csv::ByteRecord, [usize; 4])>,
+}
+
+#[cfg(not(target_arch = "wasm32"))]
+impl<'a> InstrumentRows<'a> {
+    fn new(filter: &'a InstrumentFilter) -> Self {
+        Self {
+            filter,
+            headers: None,
+        }
+    }
+
+    fn parse(&mut self, line: &[u8]) -> Result<Option<Instrument>, KiteConnectError> {
+        let csv_error =
+            |e: csv::Error| KiteConnectError::other(format!("CSV parsing error: {}", e));
+        let line = line.strip_suffix(b"\n").unwrap_or(line);
+        let line = line.strip_suffix(b"\r").unwrap_or(line);
+        if line.is_empty() {
+            return Ok(None);
+        }
+        let mut record = csv::ByteRecord::new();
+        csv::ReaderBuilder::new()
+            .has_headers(false)
+            .from_reader(line)
+            .read_byte_record(&mut record)
+            .map_err(csv_error)?;
+
+        let Some((headers, [exchange, segment, instrument_type, name])) = &self.headers else {
+            let column = |name: &str| {
+                record
+                    .iter()
+                    .position(|h| h == name.as_bytes())
+                    .ok_or_else(|| {
+                        KiteConnectError::other(format!("instrument CSV has no `{}` column", name))
+                    })
+            };
+            let columns = [
+                column("exchange")?,
+                column("segment")?,
+                column("instrument_type")?,
+                column("name")?,
+            ];
+            self.headers = Some((record, columns));
+            return Ok(None);
+        };
+        let field =
+            |i: usize| std::str::from_utf8(record.get(i).unwrap_or_default()).unwrap_or_default();
+        if !self.filter.matches_fields(
+            field(*exchange),
+            field(*segment),
+            field(*instrument_type),
+            field(*name),
+        ) {
+            return Ok(None);
+        }
+        record
+            .deserialize(Some(headers))
+            .map(Some)
+            .map_err(csv_error)
+    }
+}
+
+/// Parse an instrument dump, deserializing only the rows that match `filter`.
+pub fn parse_instruments_filtered<R: std::io::Read>(
+    reader: R,
+    filter: &InstrumentFilter,
+) -> Result<Instruments, KiteConnectError> {
+    let csv_error = |e: csv::Error| KiteConnectError::other(format!("CSV parsing error: {}", e));
+    let mut reader = csv::Reader::from_reader(reader);
+    let headers = reader.byte_headers().map_err(csv_error)?.clone();
+    let column = |name: &str| {
+        headers
+            .iter()
+            .position(|h| h == name.as_bytes())
+            .ok_or_else(|| {
+                KiteConnectError::other(format!("instrument CSV has no `{}` column", name))
+            })
+    };
+    let exchange = column("exchange")?;
+    let segment = column("segment")?;
+    let instrument_type = column("instrument_type")?;
+    let name = column("name")?;
+
+    let mut instruments = Vec::new();
+    let mut record = csv::ByteRecord::new();
+    while reader.read_byte_record(&mut record).map_err(csv_error)? {
+        let field =
+            |i: usize| std::str::from_utf8(record.get(i).unwrap_or_default()).unwrap_or_default();
+        if !filter.matches_fields(
+            field(exchange),
+            field(segment),
+            field(instrument_type),
+            field(name),
+        ) {
+            continue;
+        }
+        instruments.push(record.deserialize(Some(&headers)).map_err(csv_error)?);
+    }
+    Ok(instruments)
+}
@@ -0,0 +1,211 @@
+//! Prometheus metrics for the API client and the ticker.
+//!
+//! With the `prometheus` feature every [`KiteConnect`](crate::KiteConnect) request and
+//! every ticker event is recorded into a registry owned by this module:
+//!
+//! | Metric | Type | Labels |
+//! |---|---|---|
+//! | `kite_http_requests_total` | counter | `method`, `endpoint`, `status` |
+//! | `kite_http_request_duration_seconds` | histogram | `category` |
+//! | `kite_http_throttled_total` | counter | `category` |
+//! | `kite_ticker_events_total` | counter | `event` |
+//!
+//! `endpoint` is the request path with order, GTT and other ids replaced by `:id`, and
+//! `status` is the HTTP status code or `error` when no response was received.
+//! `kite_http_throttled_total` counts HTTP 429 responses per [`ApiCategory`].
+//!
+//! [`gather`] renders the metrics in the text exposition format, [`registry`] lets them be
+//! merged into an application's own registry, and [`serve`] answers scrapes on
+//! `/metrics`:
+//!
+//! ```no_run
+//! # async fn run() -> std::io::Result<()> {
+//! let listener = tokio::net::TcpListener::bind("0.0.0.0:9100").await?;
+//! tokio::spawn(kiteconnect_rs::metrics::serve(listener));
+//! # Ok(())
+//! # }
+//! ```
+
+use prometheus::{
+    Encoder, HistogramOpts, HistogramVec, IntCounterVec, Opts, Registry, TextEncoder,
+};
+use reqwest::{Method, StatusCode};
+use std::sync::OnceLock;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpListener;
+use web_time::Duration;
+
+use crate::ticker::TickerEvent;
+use crate::usage::ApiCategory;
+
+struct Metrics {
+    registry: Registry,
+    http_requests: IntCounterVec,
+    http_duration: HistogramVec,
+    http_throttled: IntCounterVec,
+    ticker_events: IntCounterVec,
+}
+
+impl Metrics {
+    fn new() -> Self {
+        let registry = Registry::new();
+        let http_requests = IntCounterVec::new(
+            Opts::new("kite_http_requests_total", "Kite Connect API requests."),
+            &["method", "endpoint", "status"],
+        )
+        .expect("valid metric");
+        let http_duration = HistogramVec::new(
+            HistogramOpts::new(
+                "kite_http_request_duration_seconds",
+                "Time from sending a Kite Connect API request to reading its response.",
+            )
+            .buckets(vec![0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]),
+            &["category"],
+        )
+        .expect("valid metric");
+        let http_throttled = IntCounterVec::new(
+            Opts::new(
+                "kite_http_throttled_total",
+                "Kite Connect API requests rejected with HTTP 429.",
+            ),
+            &["category"],
+        )
+        .expect("valid metric");
+        let ticker_events = IntCounterVec::new(
+            Opts::new("kite_ticker_events_total", "Events emitted by tickers."),
+            &["event"],
+        )
+        .expect("valid metric");
+
+        for collector in [
+            Box::new(http_requests.clone()) as Box<dyn prometheus::core::Collector>,
+            Box::new(http_duration.clone()),
+            Box::new(http_throttled.clone()),
+            Box::new(ticker_events.clone()),
+        ] {
+            registry
+                .register(collector)
+                .expect("metric registered once");
+        }
+
+        Self {
+            registry,
+            http_requests,
+            http_duration,
+            http_throttled,
+            ticker_events,
+        }
+    }
+}
+
+fn metrics() -> &'static Metrics {
+    static METRICS: OnceLock<Metrics> = OnceLock::new();
+    METRICS.get_or_init(Metrics::new)
+}
+
+/// The registry the client and ticker record into.
+pub fn registry() -> &'static Registry {
+    &metrics().registry
+}
+
+/// All metrics in the Prometheus text exposition format.
+pub fn gather() -> String {
+    let mut buffer = Vec::new();
+    // Encoding into a Vec only fails on malformed metric families, which are not built here
+    let _ = TextEncoder::new().encode(&registry().gather(), &mut buffer);
+    String::from_utf8(buffer).unwrap_or_default()
+}
+
+/// Answer `GET /metrics` on `listener` with [`gather`] until the task is dropped. Other
+/// paths get a 404.
+pub async fn serve(listener: TcpListener) -> std::io::Result<()> {
+    loop {
+        let (mut stream, _) = listener.accept().await?;
+        tokio::spawn(async move {
+            let mut request = [0; 1024];
+            let Ok(read) = stream.read(&mut request).await else {
+                return;
+            };
+            let request_line = String::from_utf8_lossy(&request[..read]);
+            let response = if request_line.starts_with("GET /metrics ") {
+                let body = gather();
+                format!(
+                    "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                    TextEncoder::new().format_type(),
+                    body.len(),
+                    body
+                )
+            } else {
+                "HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                    .to_string()
+            };
+            let _ = stream.write_all(response.as_bytes()).await;
+            let _ = stream.shutdown().await;
+        });
+    }
+}
+
+/// Record a finished API request; `status` is None when no response was received.
+pub(crate) fn record_request(
+    method: &Method,
+    endpoint: &str,
+    status: Option<StatusCode>,
+    elapsed: Duration,
+) {
+    let metrics = metrics();
+    let category = category_label(ApiCategory::of(method, endpoint));
+    let status_label = status.map_or_else(|| "error".to_string(), |s| s.as_u16().to_string());
+    metrics
+        .http_requests
+        .with_label_values(&[method.as_str(), &endpoint_label(endpoint), &status_label])
+        .inc();
+    metrics
+        .http_duration
+        .with_label_values(&[category])
+        .observe(elapsed.as_secs_f64());
+    if status == Some(StatusCode::TOO_MANY_REQUESTS) {
+        metrics.http_throttled.with_label_values(&[category]).inc();
+    }
+}
+
+/// Record an event emitted by a ticker.
+pub(crate) fn record_ticker_event(event: &TickerEvent) {
+    let label = match event {
+        TickerEvent::Tick(_) => "tick",
+        TickerEvent::Message(_) => "message",
+        TickerEvent::Connect => "connect",
+        TickerEvent::Close(..) => "close",
+        TickerEvent::Error(_) => "error",
+        TickerEvent::Reconnect(..) => "reconnect",
+        TickerEvent::NoReconnect(_) => "no_reconnect",
+        TickerEvent::OrderUpdate(_) => "order_update",
+        TickerEvent::Resubscribed(_) => "resubscribed",
+        TickerEvent::Warning(_) => "warning",
+        TickerEvent::SubscriberLagged { .. } => "subscriber_lagged",
+    };
+    metrics().ticker_events.with_label_values(&[label]).inc();
+}
+
+fn category_label(category: ApiCategory) -> &'static str {
+    match category {
+        ApiCategory::Quote => "quote",
+        ApiCategory::Historical => "historical",
+        ApiCategory::Orders => "orders",
+        ApiCategory::Other => "other",
+    }
+}
+
+/// The path of `endpoint` with numeric ids and UUIDs replaced by `:id`, keeping the number
+/// of label values bounded.
+pub fn endpoint_label(endpoint: &str) -> String {
+    let path = endpoint.split('?').next().unwrap_or_default();
+    path.split('/')
+        .map(|segment| {
+            let numeric = !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit());
+            let uuid =
+                segment.len() >= 16 && segment.chars().all(|c| c.is_ascii_hexdigit() || c == '-');
+            if numeric || uuid { ":id" } else { segment }
+        })
+        .collect::<Vec<_>>()
+        .join("/")
+}
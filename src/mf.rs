@@ -1,3 +1,5 @@
+use futures_util::future::Either;
+use futures_util::{Stream, stream};
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
@@ -5,6 +7,7 @@ use crate::{
     KiteConnect,
     constants::Endpoints,
     models::{KiteConnectError, time},
+    pagination::PageParams,
 };
 
 /// MFHolding represents an individual mutual fund holding.
@@ -163,6 +166,7 @@ pub struct MFSIPModifyParams {
 impl KiteConnect {
     /// Gets list of mutual fund orders.
     pub async fn get_mf_orders(&self) -> Result<MFOrders, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         self.get(Endpoints::GET_MF_ORDERS).await
     }
 
@@ -172,6 +176,7 @@ impl KiteConnect {
         from_date: &str,
         to_date: &str,
     ) -> Result<MFOrders, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         let mut params = HashMap::new();
         params.insert("from".to_string(), from_date.to_string());
         params.insert("to".to_string(), to_date.to_string());
@@ -179,30 +184,46 @@ impl KiteConnect {
         self.get_with_query(Endpoints::GET_MF_ORDERS, params).await
     }
 
+    /// Streams mutual fund orders for a long date range, one window at a time.
+    pub fn get_mf_orders_paginated(
+        &self,
+        params: PageParams,
+    ) -> impl Stream<Item = Result<MFOrder, KiteConnectError>> + '_ {
+        match self.require_known_mutual_funds() {
+            Ok(()) => Either::Left(self.paginate(Endpoints::GET_MF_ORDERS, params)),
+            Err(e) => Either::Right(stream::once(async { Err(e) })),
+        }
+    }
+
     /// Gets individual mutual fund order info.
     pub async fn get_mf_order_info(&self, order_id: &str) -> Result<MFOrder, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         let endpoint = &Endpoints::GET_MF_ORDER_INFO.replace("{order_id}", order_id);
         self.get(endpoint).await
     }
 
     /// Gets list of user mutual fund holdings.
     pub async fn get_mf_holdings(&self) -> Result<MFHoldings, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         self.get(Endpoints::GET_MF_HOLDINGS).await
     }
 
     /// Gets list of mutual fund SIPs.
     pub async fn get_mf_sips(&self) -> Result<MFSIPs, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         self.get(Endpoints::GET_MF_SIPS).await
     }
 
     /// Gets individual SIP info.
     pub async fn get_mf_sip_info(&self, sip_id: &str) -> Result<MFSIP, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         let endpoint = &Endpoints::GET_MF_SIP_INFO.replace("{sip_id}", sip_id);
         self.get(endpoint).await
     }
 
     /// Gets list of user mutual fund allotted ISINs.
     pub async fn get_mf_allotted_isins(&self) -> Result<MFAllottedISINs, KiteConnectError> {
+        self.require_known_mutual_funds()?;
         self.get(Endpoints::GET_MF_ALLOTTED_ISINS).await
     }
 
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 use std::fmt;
+use web_time::Duration;
+
+use crate::executor::ExecutionReport;
+use crate::risk::RiskViolation;
+use crate::validation::Violation;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct KiteError {
@@ -17,18 +22,150 @@ impl fmt::Display for KiteError {
 
 impl std::error::Error for KiteError {}
 
+/// Error types under which Kite reports blocked accounts and segments.
+const BLOCKED_ERROR_TYPES: [&str; 4] = [
+    "PermissionException",
+    "UserException",
+    "OrderException",
+    "InputException",
+];
+
+/// Lowercase message fragments of kill switch and blocked account rejections.
+const BLOCKED_MESSAGE_PATTERNS: [&str; 7] = [
+    "kill switch",
+    "killswitch",
+    "blocked for trading",
+    "trading is blocked",
+    "trading has been blocked",
+    "trading has been disabled",
+    "account is blocked",
+];
+
+/// Error types Kite reports for failures on its side, which may succeed when retried:
+/// rate limiting and OMS or upstream trouble (`GeneralException` is the catch-all for
+/// unexpected server errors).
+const RETRIABLE_ERROR_TYPES: [&str; 3] = ["NetworkException", "DataException", "GeneralException"];
+
+/// ErrorCategory tells whether a failed request may succeed when retried unchanged.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum ErrorCategory {
+    /// Network failures, timeouts, rate limiting and server errors.
+    Retriable,
+    /// Authentication, validation, risk and configuration errors, and anything else that
+    /// fails the same way on every attempt.
+    Terminal,
+    /// A POST, PUT or DELETE, e.g. placing, modifying or cancelling an order, that failed
+    /// after it may have reached Kite: a timeout, dropped connection or server error. It may have taken effect, so check,
+    /// e.g. the order book by tag, before sending it again.
+    Ambiguous,
+}
+
+impl KiteError {
+    pub fn category(&self) -> ErrorCategory {
+        if RETRIABLE_ERROR_TYPES.contains(&self.error_type.as_str()) {
+            ErrorCategory::Retriable
+        } else {
+            ErrorCategory::Terminal
+        }
+    }
+
+    pub fn is_retriable(&self) -> bool {
+        self.category() == ErrorCategory::Retriable
+    }
+
+    /// Whether this is a rejection due to the account kill switch or a blocked account,
+    /// which will not go away by retrying.
+    pub fn is_trading_blocked(&self) -> bool {
+        if !BLOCKED_ERROR_TYPES.contains(&self.error_type.as_str()) {
+            return false;
+        }
+        let message = self.message.to_lowercase();
+        BLOCKED_MESSAGE_PATTERNS
+            .iter()
+            .any(|pattern| message.contains(pattern))
+    }
+}
+
+/// ConfigError describes an invalid client configuration detected before any request is made.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ConfigError {
+    EmptyApiKey,
+    InvalidApiKey(String),
+    EmptyAccessToken,
+    InvalidBaseUrl(String),
+    InvalidTimeout(Duration),
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::EmptyApiKey => write!(f, "api_key must not be empty"),
+            ConfigError::InvalidApiKey(reason) => write!(f, "invalid api_key: {}", reason),
+            ConfigError::EmptyAccessToken => write!(f, "access_token must not be empty"),
+            ConfigError::InvalidBaseUrl(reason) => write!(f, "invalid base_url: {}", reason),
+            ConfigError::InvalidTimeout(timeout) => write!(
+                f,
+                "timeout must be between 1ms and {}s, got {:?}",
+                crate::constants::app_constants::MAX_TIMEOUT.as_secs(),
+                timeout
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 #[derive(Debug)]
 pub struct KiteConnectError {
     pub kind: KiteConnectErrorKind,
     pub backtrace: std::backtrace::Backtrace,
+    // Set on failed mutating requests that may have reached Kite
+    pub(crate) ambiguous: bool,
 }
 
 #[derive(Debug)]
 pub enum KiteConnectErrorKind {
-    ApiError(KiteError),
+    ApiError(Box<KiteError>),
     HttpError(reqwest::Error),
     SerializationError(serde_json::Error),
     InvalidHeader(reqwest::header::InvalidHeaderValue),
+    InvalidConfig(ConfigError),
+    InvalidParams(String),
+    RiskViolation(RiskViolation),
+    /// The kill switch is active or the account is blocked; see [`KiteError::is_trading_blocked`].
+    TradingBlocked(Box<KiteError>),
+    /// The response body was longer than the client's `max_response_size`.
+    ResponseTooLarge {
+        limit: usize,
+    },
+    /// A mutating request, e.g. `DELETE /orders/regular/123`, refused by a read-only client.
+    ReadOnlyMode(String),
+    /// The account or API key lacks a product, exchange or permission the call needs.
+    MissingCapability(String),
+    /// A tick sink or other store failed to read or write, e.g. a database or file error.
+    StorageError(String),
+    /// The order breaks exchange rules checked by the client's
+    /// [`OrderValidator`](crate::validation::OrderValidator).
+    ValidationFailed(Vec<Violation>),
+    /// A child of an order split at the freeze quantity failed after the children in
+    /// `placed` went through; those orders are live.
+    PartialSplit {
+        placed: Vec<String>,
+        source: Box<KiteConnectError>,
+    },
+    /// A request failed while [`Executor`](crate::executor::Executor) was working an
+    /// order; `report` has the order id and how far the chase got. The order may still be
+    /// open.
+    ExecutionFailed {
+        report: Box<ExecutionReport>,
+        source: Box<KiteConnectError>,
+    },
+    /// The replacement of the order `cancelled` failed after the order was cancelled; the
+    /// position it covered is left without an order.
+    ReplaceFailed {
+        cancelled: String,
+        source: Box<KiteConnectError>,
+    },
     Other(String),
 }
 
@@ -39,6 +176,36 @@ impl fmt::Display for KiteConnectError {
             KiteConnectErrorKind::HttpError(e) => write!(f, "HTTP Error: {}", e),
             KiteConnectErrorKind::SerializationError(e) => write!(f, "Serialization Error: {}", e),
             KiteConnectErrorKind::InvalidHeader(e) => write!(f, "Invalid Header: {}", e),
+            KiteConnectErrorKind::InvalidConfig(e) => write!(f, "Invalid Config: {}", e),
+            KiteConnectErrorKind::InvalidParams(e) => write!(f, "Invalid Params: {}", e),
+            KiteConnectErrorKind::RiskViolation(e) => write!(f, "Risk Violation: {}", e),
+            KiteConnectErrorKind::TradingBlocked(e) => write!(f, "Trading Blocked: {}", e),
+            KiteConnectErrorKind::ResponseTooLarge { limit } => {
+                write!(f, "Response Too Large: body exceeds {} bytes", limit)
+            }
+            KiteConnectErrorKind::ReadOnlyMode(e) => {
+                write!(f, "Read Only Mode: {} is not allowed", e)
+            }
+            KiteConnectErrorKind::MissingCapability(e) => write!(f, "Missing Capability: {}", e),
+            KiteConnectErrorKind::StorageError(e) => write!(f, "Storage Error: {}", e),
+            KiteConnectErrorKind::ValidationFailed(violations) => {
+                let violations: Vec<String> = violations.iter().map(|v| v.to_string()).collect();
+                write!(f, "Validation Failed: {}", violations.join("; "))
+            }
+            KiteConnectErrorKind::PartialSplit { placed, source } => write!(
+                f,
+                "Partial Split: failed after placing {}: {}",
+                placed.join(", "),
+                source
+            ),
+            KiteConnectErrorKind::ExecutionFailed { report, source } => {
+                write!(f, "Execution Failed: order {}: {}", report.order_id, source)
+            }
+            KiteConnectErrorKind::ReplaceFailed { cancelled, source } => write!(
+                f,
+                "Replace Failed: order {} was cancelled but not replaced: {}",
+                cancelled, source
+            ),
             KiteConnectErrorKind::Other(e) => write!(f, "Error: {}", e),
         }
     }
@@ -47,11 +214,23 @@ impl fmt::Display for KiteConnectError {
 impl std::error::Error for KiteConnectError {
     fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
         match &self.kind {
-            KiteConnectErrorKind::ApiError(e) => Some(e),
+            KiteConnectErrorKind::ApiError(e) => Some(e.as_ref()),
             KiteConnectErrorKind::HttpError(e) => Some(e),
             KiteConnectErrorKind::SerializationError(e) => Some(e),
             KiteConnectErrorKind::InvalidHeader(e) => Some(e),
-            KiteConnectErrorKind::Other(_) => None,
+            KiteConnectErrorKind::InvalidConfig(e) => Some(e),
+            KiteConnectErrorKind::RiskViolation(e) => Some(e),
+            KiteConnectErrorKind::TradingBlocked(e) => Some(e.as_ref()),
+            KiteConnectErrorKind::PartialSplit { source, .. }
+            | KiteConnectErrorKind::ExecutionFailed { source, .. }
+            | KiteConnectErrorKind::ReplaceFailed { source, .. } => Some(source.as_ref()),
+            KiteConnectErrorKind::InvalidParams(_)
+            | KiteConnectErrorKind::ResponseTooLarge { .. }
+            | KiteConnectErrorKind::ReadOnlyMode(_)
+            | KiteConnectErrorKind::MissingCapability(_)
+            | KiteConnectErrorKind::StorageError(_)
+            | KiteConnectErrorKind::ValidationFailed(_)
+            | KiteConnectErrorKind::Other(_) => None,
         }
     }
 }
@@ -62,6 +241,7 @@ impl KiteConnectError {
         KiteConnectError {
             kind,
             backtrace: std::backtrace::Backtrace::capture(),
+            ambiguous: false,
         }
     }
 
@@ -70,6 +250,139 @@ impl KiteConnectError {
         Self::new(KiteConnectErrorKind::Other(msg.into()))
     }
 
+    /// Create a new InvalidParams error for requests rejected before being sent
+    pub fn invalid_params(msg: impl Into<String>) -> Self {
+        Self::new(KiteConnectErrorKind::InvalidParams(msg.into()))
+    }
+
+    /// Create a new ResponseTooLarge error for a body longer than `limit` bytes
+    pub fn response_too_large(limit: usize) -> Self {
+        Self::new(KiteConnectErrorKind::ResponseTooLarge { limit })
+    }
+
+    /// Create a new ReadOnlyMode error for a mutating `method` on `endpoint`
+    pub fn read_only_mode(method: impl fmt::Display, endpoint: &str) -> Self {
+        Self::new(KiteConnectErrorKind::ReadOnlyMode(format!(
+            "{} {}",
+            method, endpoint
+        )))
+    }
+
+    /// Create a new MissingCapability error for a call the account cannot make
+    pub fn missing_capability(msg: impl Into<String>) -> Self {
+        Self::new(KiteConnectErrorKind::MissingCapability(msg.into()))
+    }
+
+    /// Create a new StorageError for a failed database or file operation
+    pub fn storage(msg: impl Into<String>) -> Self {
+        Self::new(KiteConnectErrorKind::StorageError(msg.into()))
+    }
+
+    /// Create a new ValidationFailed error for an order breaking exchange rules
+    pub fn validation_failed(violations: Vec<Violation>) -> Self {
+        Self::new(KiteConnectErrorKind::ValidationFailed(violations))
+    }
+
+    /// Create a new PartialSplit error for a split order that failed after placing the
+    /// children in `placed`
+    pub fn partial_split(placed: Vec<String>, source: KiteConnectError) -> Self {
+        Self::new(KiteConnectErrorKind::PartialSplit {
+            placed,
+            source: Box::new(source),
+        })
+    }
+
+    /// Create a new ExecutionFailed error for a chase that failed after placing the order
+    /// in `report`
+    pub fn execution_failed(report: ExecutionReport, source: KiteConnectError) -> Self {
+        Self::new(KiteConnectErrorKind::ExecutionFailed {
+            report: Box::new(report),
+            source: Box::new(source),
+        })
+    }
+
+    /// Create a new ReplaceFailed error for a replacement that failed after the order
+    /// `cancelled` was cancelled
+    pub fn replace_failed(cancelled: &str, source: KiteConnectError) -> Self {
+        Self::new(KiteConnectErrorKind::ReplaceFailed {
+            cancelled: cancelled.to_string(),
+            source: Box::new(source),
+        })
+    }
+
+    /// The exchange rules a rejected order breaks, empty for other errors.
+    pub fn violations(&self) -> &[Violation] {
+        match &self.kind {
+            KiteConnectErrorKind::ValidationFailed(violations) => violations,
+            _ => &[],
+        }
+    }
+
+    /// Mark a failed mutating request that may have reached Kite as
+    /// [`ErrorCategory::Ambiguous`].
+    pub(crate) fn into_ambiguous(mut self) -> Self {
+        self.ambiguous = true;
+        self
+    }
+
+    /// Whether retrying the request unchanged may succeed.
+    ///
+    /// Network failures, timeouts, HTTP 429 and 5xx responses, Kite's `NetworkException`,
+    /// `DataException` and `GeneralException` and storage errors are retriable, except
+    /// that the same failures of a POST, PUT or DELETE after it was sent are ambiguous:
+    /// placing, modifying or cancelling an order that timed out may have taken effect.
+    /// Rate limited requests were not executed and stay retriable.
+    /// Token, input, order, margin and other API rejections, invalid configuration or
+    /// parameters, risk and validation violations, blocked trading, oversized responses,
+    /// requests refused in read-only mode, missing capabilities, partly placed split
+    /// orders, failed executions and replacements and [`Other`](KiteConnectErrorKind::Other)
+    /// errors are terminal.
+    pub fn category(&self) -> ErrorCategory {
+        let retriable = match &self.kind {
+            KiteConnectErrorKind::ApiError(e) => e.is_retriable(),
+            KiteConnectErrorKind::HttpError(e) => match e.status() {
+                Some(status) => status.is_server_error() || status.as_u16() == 429,
+                None => !e.is_builder() && !e.is_redirect(),
+            },
+            KiteConnectErrorKind::StorageError(_) => true,
+            KiteConnectErrorKind::SerializationError(_)
+            | KiteConnectErrorKind::InvalidHeader(_)
+            | KiteConnectErrorKind::InvalidConfig(_)
+            | KiteConnectErrorKind::InvalidParams(_)
+            | KiteConnectErrorKind::RiskViolation(_)
+            | KiteConnectErrorKind::TradingBlocked(_)
+            | KiteConnectErrorKind::ResponseTooLarge { .. }
+            | KiteConnectErrorKind::ReadOnlyMode(_)
+            | KiteConnectErrorKind::MissingCapability(_)
+            | KiteConnectErrorKind::ValidationFailed(_)
+            | KiteConnectErrorKind::PartialSplit { .. }
+            | KiteConnectErrorKind::ExecutionFailed { .. }
+            | KiteConnectErrorKind::ReplaceFailed { .. }
+            | KiteConnectErrorKind::Other(_) => false,
+        };
+        if retriable && self.ambiguous {
+            ErrorCategory::Ambiguous
+        } else if retriable {
+            ErrorCategory::Retriable
+        } else {
+            ErrorCategory::Terminal
+        }
+    }
+
+    pub fn is_retriable(&self) -> bool {
+        self.category() == ErrorCategory::Retriable
+    }
+
+    /// Whether trading is blocked by the kill switch or a blocked account
+    pub fn is_trading_blocked(&self) -> bool {
+        matches!(self.kind, KiteConnectErrorKind::TradingBlocked(_))
+    }
+
+    /// Whether the request was refused because the client is read-only
+    pub fn is_read_only_mode(&self) -> bool {
+        matches!(self.kind, KiteConnectErrorKind::ReadOnlyMode(_))
+    }
+
     /// Get the backtrace for this error
     pub fn backtrace(&self) -> &std::backtrace::Backtrace {
         &self.backtrace
@@ -126,8 +439,24 @@ impl From<reqwest::header::InvalidHeaderValue> for KiteConnectError {
     }
 }
 
+impl From<ConfigError> for KiteConnectError {
+    fn from(error: ConfigError) -> Self {
+        Self::new(KiteConnectErrorKind::InvalidConfig(error))
+    }
+}
+
+impl From<RiskViolation> for KiteConnectError {
+    fn from(error: RiskViolation) -> Self {
+        Self::new(KiteConnectErrorKind::RiskViolation(error))
+    }
+}
+
 impl From<KiteError> for KiteConnectError {
     fn from(error: KiteError) -> Self {
-        Self::new(KiteConnectErrorKind::ApiError(error))
+        if error.is_trading_blocked() {
+            Self::new(KiteConnectErrorKind::TradingBlocked(Box::new(error)))
+        } else {
+            Self::new(KiteConnectErrorKind::ApiError(Box::new(error)))
+        }
     }
 }
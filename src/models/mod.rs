@@ -3,7 +3,7 @@ use serde::{Deserialize, Serialize};
 pub mod error;
 pub mod time;
 
-pub use error::{KiteConnectError, KiteConnectErrorKind, KiteError};
+pub use error::{ConfigError, ErrorCategory, KiteConnectError, KiteConnectErrorKind, KiteError};
 
 // OHLC represents OHLC packets.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
@@ -35,21 +35,12 @@ impl Default for DepthItem {
 }
 
 // Depth represents a group of buy/sell market depths.
-#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, PartialEq)]
 pub struct Depth {
     pub buy: [DepthItem; 5],
     pub sell: [DepthItem; 5],
 }
 
-impl Default for Depth {
-    fn default() -> Self {
-        Self {
-            buy: [DepthItem::default(); 5],
-            sell: [DepthItem::default(); 5],
-        }
-    }
-}
-
 // Tick represents a single packet in the market feed.
 #[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
 pub struct Tick {
@@ -76,6 +67,58 @@ pub struct Tick {
 
     pub ohlc: OHLC,
     pub depth: Depth,
+
+    // Change since the previous tick of the instrument, set by the ticker's tick enrichment.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub change: Option<TickChange>,
+
+    // Pre-open tick whose last price and volume are indicative; set by the tick enricher
+    // in pre-open mode.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub indicative: bool,
+}
+
+impl Tick {
+    /// Value traded during the day: volume times the average traded price.
+    pub fn traded_value(&self) -> f64 {
+        self.volume_traded as f64 * self.average_trade_price
+    }
+
+    /// The indicative equilibrium price of a pre-open tick: the price the call auction
+    /// would open at, carried in `last_price`. `None` for regular ticks.
+    pub fn equilibrium_price(&self) -> Option<f64> {
+        self.indicative.then_some(self.last_price)
+    }
+}
+
+/// One-line summary for logs, e.g. `408065 1500.5 (+12.25) vol 120000`. The volume is
+/// left out of LTP mode ticks, which do not carry it.
+impl std::fmt::Display for Tick {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "{} {} ({:+})",
+            self.instrument_token, self.last_price, self.net_change
+        )?;
+        if self.volume_traded > 0 {
+            write!(f, " vol {}", self.volume_traded)?;
+        }
+        if !self.timestamp.is_null() {
+            write!(f, " at {}", self.timestamp)?;
+        }
+        Ok(())
+    }
+}
+
+/// TickChange holds the differences between a tick and the previous tick of the same
+/// instrument. See `enrich::TickEnricher`.
+#[derive(Debug, Clone, Copy, Default, Serialize, Deserialize, PartialEq)]
+pub struct TickChange {
+    pub oi_change: i64,
+    /// Volume traded since the previous tick.
+    pub volume_delta: u32,
+    /// Value traded since the previous tick.
+    pub traded_value_delta: f64,
 }
 
 impl Default for Tick {
@@ -107,6 +150,8 @@ impl Default for Tick {
                 close: 0.0,
             },
             depth: Depth::default(),
+            change: None,
+            indicative: false,
         }
     }
 }
@@ -154,3 +199,21 @@ pub struct Order {
     pub tag: String,
     pub tags: Vec<String>,
 }
+
+impl std::fmt::Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        crate::orders::OrderSummary {
+            order_id: &self.order_id,
+            status: &self.status,
+            exchange: &self.exchange,
+            tradingsymbol: &self.tradingsymbol,
+            order_type: &self.order_type,
+            transaction_type: &self.transaction_type,
+            quantity: self.quantity,
+            filled_quantity: self.filled_quantity,
+            price: self.price,
+            trigger_price: self.trigger_price,
+        }
+        .fmt(f)
+    }
+}
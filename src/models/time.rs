@@ -2,23 +2,73 @@ use chrono::{DateTime, NaiveDate, NaiveDateTime, TimeZone, Utc};
 use chrono_tz::Asia::Kolkata;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::fmt;
+use std::str::FromStr;
+use std::sync::RwLock;
 
-/// Custom time format used in all responses
-#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+/// TimeLayout is a `chrono` format string used to parse API timestamps.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TimeLayout {
+    /// A layout with an offset (`%z`, `%:z`).
+    Zoned(String),
+    /// A date and time without offset, taken as IST.
+    Ist(String),
+    /// A date without time, taken as IST midnight.
+    IstDate(String),
+}
+
+/// Layouts registered with [`Time::register_layout`], tried after the built-in ones.
+static EXTRA_LAYOUTS: RwLock<Vec<TimeLayout>> = RwLock::new(Vec::new());
+
+/// Timestamp type used for every date and time field in API responses.
+///
+/// Kite is not consistent about timestamp formats across endpoints, so parsing tries the
+/// following in order and takes the first match:
+///
+/// 1. empty strings and `null` parse as a null time
+/// 2. offset layouts: `2024-01-15T09:15:00+0530` (historical candles),
+///    `2024-01-15T09:15:00+05:30` and RFC 3339 with fractional seconds
+/// 3. IST layouts without offset: `2024-01-15 09:15:00` (orders, trades, GTT),
+///    `2024-01-15 09:15:00.123` (alerts) and `2024-01-15T09:15:00`
+/// 4. IST dates: `2024-01-15` (MF, instruments) and `15-01-2024`
+/// 5. layouts added with [`Time::register_layout`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
 pub struct Time {
     inner: Option<DateTime<Utc>>,
 }
 
 impl Time {
-    /// List of known time formats without timezone
-    const LAYOUTS: &'static [&'static str] = &["%Y-%m-%d", "%Y-%m-%d %H:%M:%S"];
-
-    /// List of known time formats with timezone
+    /// Built-in layouts with an offset
     const ZONED_LAYOUTS: &'static [&'static str] = &[
         "%Y-%m-%dT%H:%M:%S%z",
         "%Y-%m-%dT%H:%M:%S%.f%:z", // RFC3339-like
+        "%Y-%m-%d %H:%M:%S%z",
+    ];
+
+    /// Built-in layouts without timezone, in IST
+    const IST_LAYOUTS: &'static [&'static str] = &[
+        "%Y-%m-%d %H:%M:%S",
+        "%Y-%m-%d %H:%M:%S%.f",
+        "%Y-%m-%dT%H:%M:%S",
+        "%Y-%m-%dT%H:%M:%S%.f",
     ];
 
+    /// Built-in date-only layouts, in IST
+    const IST_DATE_LAYOUTS: &'static [&'static str] = &["%Y-%m-%d", "%d-%m-%Y"];
+
+    /// Register an additional layout for formats not covered by the built-in cascade.
+    /// Applies process-wide, to every `Time` parsed afterwards.
+    pub fn register_layout(layout: TimeLayout) {
+        let mut layouts = EXTRA_LAYOUTS.write().unwrap_or_else(|e| e.into_inner());
+        if !layouts.contains(&layout) {
+            layouts.push(layout);
+        }
+    }
+
+    /// Parse a timestamp string with the format-detection cascade.
+    pub fn parse(s: &str) -> Result<Self, String> {
+        Self::parse_time(s).map(Time::from)
+    }
+
     /// Create a new Time instance
     pub fn new(dt: DateTime<Utc>) -> Self {
         Time { inner: Some(dt) }
@@ -60,36 +110,69 @@ impl Time {
             return Ok(None);
         }
 
-        // Try parsing with zoneless layouts (assuming IST/Kolkata timezone)
-        for layout in Self::LAYOUTS {
-            if let Ok(naive_dt) = NaiveDateTime::parse_from_str(s, layout) {
-                // Convert to IST then to UTC
-                if let Some(ist_dt) = Kolkata.from_local_datetime(&naive_dt).single() {
-                    return Ok(Some(ist_dt.with_timezone(&Utc)));
-                }
-            }
-            // Also try parsing as date only
-            if let Ok(naive_date) = NaiveDate::parse_from_str(s, layout) {
-                let naive_dt = naive_date.and_hms_opt(0, 0, 0).unwrap();
-                if let Some(ist_dt) = Kolkata.from_local_datetime(&naive_dt).single() {
-                    return Ok(Some(ist_dt.with_timezone(&Utc)));
-                }
-            }
-        }
+        let zoned = Self::ZONED_LAYOUTS
+            .iter()
+            .find_map(|l| parse_zoned(s, l))
+            .or_else(|| {
+                DateTime::parse_from_rfc3339(s)
+                    .ok()
+                    .map(|dt| dt.with_timezone(&Utc))
+            });
+        let ist = || Self::IST_LAYOUTS.iter().find_map(|l| parse_ist(s, l));
+        let ist_date = || {
+            Self::IST_DATE_LAYOUTS
+                .iter()
+                .find_map(|l| parse_ist_date(s, l))
+        };
+        let extra = || {
+            EXTRA_LAYOUTS
+                .read()
+                .unwrap_or_else(|e| e.into_inner())
+                .iter()
+                .find_map(|layout| match layout {
+                    TimeLayout::Zoned(l) => parse_zoned(s, l),
+                    TimeLayout::Ist(l) => parse_ist(s, l),
+                    TimeLayout::IstDate(l) => parse_ist_date(s, l),
+                })
+        };
 
-        // Try parsing with zoned layouts
-        for layout in Self::ZONED_LAYOUTS {
-            if let Ok(dt) = DateTime::parse_from_str(s, layout) {
-                return Ok(Some(dt.with_timezone(&Utc)));
-            }
-        }
+        zoned
+            .or_else(ist)
+            .or_else(ist_date)
+            .or_else(extra)
+            .map(Some)
+            .ok_or_else(|| format!("unknown time format `{}`", s))
+    }
+}
 
-        // Try parsing RFC3339 directly
-        if let Ok(dt) = DateTime::parse_from_rfc3339(s) {
-            return Ok(Some(dt.with_timezone(&Utc)));
-        }
+fn parse_zoned(s: &str, layout: &str) -> Option<DateTime<Utc>> {
+    DateTime::parse_from_str(s, layout)
+        .ok()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+fn parse_ist(s: &str, layout: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDateTime::parse_from_str(s, layout).ok()?;
+    from_ist(naive)
+}
 
-        Err("unknown time format".to_string())
+fn parse_ist_date(s: &str, layout: &str) -> Option<DateTime<Utc>> {
+    let naive = NaiveDate::parse_from_str(s, layout).ok()?;
+    from_ist(naive.and_hms_opt(0, 0, 0)?)
+}
+
+fn from_ist(naive: NaiveDateTime) -> Option<DateTime<Utc>> {
+    Kolkata
+        .from_local_datetime(&naive)
+        .single()
+        .map(|dt| dt.with_timezone(&Utc))
+}
+
+impl FromStr for Time {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Self::parse(s)
     }
 }
 
@@ -139,13 +222,6 @@ impl fmt::Display for Time {
     }
 }
 
-// Optional: Implement Default for Time
-impl Default for Time {
-    fn default() -> Self {
-        Time { inner: None }
-    }
-}
-
 // Optional: Conversion traits
 impl From<DateTime<Utc>> for Time {
     fn from(dt: DateTime<Utc>) -> Self {
@@ -181,6 +257,64 @@ mod tests {
         assert!(result.is_some());
     }
 
+    fn ist(y: i32, mo: u32, d: u32, h: u32, mi: u32, s: u32) -> DateTime<Utc> {
+        Kolkata
+            .with_ymd_and_hms(y, mo, d, h, mi, s)
+            .unwrap()
+            .with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_parse_observed_formats() {
+        let expected = ist(2024, 1, 15, 9, 15, 0);
+        for s in [
+            "2024-01-15T09:15:00+0530",
+            "2024-01-15T09:15:00+05:30",
+            "2024-01-15T03:45:00Z",
+            "2024-01-15T09:15:00.000+05:30",
+            "2024-01-15 09:15:00+0530",
+            "2024-01-15 09:15:00",
+            "2024-01-15T09:15:00",
+            " 2024-01-15 09:15:00 ",
+        ] {
+            assert_eq!(
+                Time::parse(s).unwrap().as_datetime(),
+                Some(expected),
+                "{}",
+                s
+            );
+        }
+
+        let with_millis = Time::parse("2024-01-15 09:15:00.155").unwrap();
+        assert_eq!(
+            with_millis.as_datetime().unwrap().timestamp_millis(),
+            expected.timestamp_millis() + 155
+        );
+
+        let midnight = Some(ist(2024, 1, 15, 0, 0, 0));
+        assert_eq!(Time::parse("2024-01-15").unwrap().as_datetime(), midnight);
+        assert_eq!(Time::parse("15-01-2024").unwrap().as_datetime(), midnight);
+        assert!("not a time".parse::<Time>().is_err());
+    }
+
+    #[test]
+    fn test_register_layout() {
+        assert!(Time::parse("15/01/2024 09:15").is_err());
+        Time::register_layout(TimeLayout::Ist("%d/%m/%Y %H:%M".to_string()));
+        assert_eq!(
+            Time::parse("15/01/2024 09:15").unwrap().as_datetime(),
+            Some(ist(2024, 1, 15, 9, 15, 0))
+        );
+    }
+
+    #[test]
+    fn test_deserialize_quoted_and_null() {
+        let t: Time = serde_json::from_str("\"2024-01-15 09:15:00\"").unwrap();
+        assert_eq!(t.as_datetime(), Some(ist(2024, 1, 15, 9, 15, 0)));
+        let t: Time = serde_json::from_str("null").unwrap();
+        assert!(t.is_null());
+    }
+
     #[test]
     fn test_parse_null() {
         let result = Time::parse_time("null").unwrap();
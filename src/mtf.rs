@@ -0,0 +1,159 @@
+//! Margin trading facility (MTF).
+//!
+//! MTF buys equity for delivery with part of the value funded by Zerodha. Orders use the
+//! `MTF` product and are only accepted on NSE and BSE. [`KiteConnect::place_mtf_order`]
+//! validates and places them, [`KiteConnect::get_mtf_margins`] asks the margin calculator
+//! what has to be paid up front, and [`mtf_conversion`] builds the request that moves an
+//! open position between MTF and CNC.
+
+use crate::KiteConnect;
+use crate::labels::{Exchange, OrderType, Product, TransactionType, Variety};
+use crate::margins::{GetMarginParams, MarginMode, OrderMarginParam, OrderMargins};
+use crate::models::KiteConnectError;
+use crate::orders::{OrderParams, OrderResponse};
+use crate::portfolio::{ConvertPositionParams, Position};
+
+/// Check that `params` can be placed as an MTF order: an NSE or BSE buy or sell of a
+/// MARKET, LIMIT, SL or SL-M order whose product, if set, is MTF.
+pub fn validate_mtf(params: &OrderParams) -> Result<(), KiteConnectError> {
+    let exchange: Exchange = params
+        .exchange
+        .as_deref()
+        .ok_or_else(|| KiteConnectError::invalid_params("order has no exchange"))?
+        .parse()?;
+    if !matches!(exchange, Exchange::Nse | Exchange::Bse) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "MTF is only available on NSE and BSE, got {}",
+            exchange
+        )));
+    }
+
+    if let Some(product) = params.product.as_deref() {
+        if product.parse::<Product>()? != Product::Mtf {
+            return Err(KiteConnectError::invalid_params(format!(
+                "MTF order has product {}",
+                product
+            )));
+        }
+    }
+    if let Some(order_type) = params.order_type.as_deref() {
+        order_type.parse::<OrderType>()?;
+    }
+    Ok(())
+}
+
+/// The request converting `position` from MTF to CNC or back, `to` being the product it
+/// ends up in. Only long NSE and BSE positions can be converted.
+pub fn mtf_conversion(
+    position: &Position,
+    to: Product,
+) -> Result<ConvertPositionParams, KiteConnectError> {
+    let from: Product = position.product.parse()?;
+    if !matches!(
+        (from, to),
+        (Product::Mtf, Product::Cnc) | (Product::Cnc, Product::Mtf)
+    ) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "cannot convert {} to {}; only MTF and CNC convert into each other",
+            from, to
+        )));
+    }
+    if position.quantity <= 0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} has no long quantity to convert",
+            position.tradingsymbol
+        )));
+    }
+    let exchange: Exchange = position.exchange.parse()?;
+    if !matches!(exchange, Exchange::Nse | Exchange::Bse) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "MTF is only available on NSE and BSE, got {}",
+            exchange
+        )));
+    }
+
+    let position_type = if position.overnight_quantity > 0 {
+        "overnight"
+    } else {
+        "day"
+    };
+    Ok(ConvertPositionParams {
+        exchange: position.exchange.clone(),
+        tradingsymbol: position.tradingsymbol.clone(),
+        old_product: from.as_str().to_string(),
+        new_product: to.as_str().to_string(),
+        position_type: position_type.to_string(),
+        transaction_type: TransactionType::Buy.as_str().to_string(),
+        quantity: position.quantity,
+    })
+}
+
+impl KiteConnect {
+    /// Place an order with the `MTF` product after checking it with [`validate_mtf`].
+    /// Client-side risk limits apply as for [`place_order`](Self::place_order).
+    pub async fn place_mtf_order(
+        &self,
+        mut order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        validate_mtf(&order_params)?;
+        order_params.product = Some(Product::Mtf.into());
+        self.place_order(Variety::Regular.as_str(), order_params)
+            .await
+    }
+
+    /// Margins required for `orders` placed with the `MTF` product. The product of each
+    /// order is overridden.
+    pub async fn get_mtf_margins(
+        &self,
+        mut orders: Vec<OrderMarginParam>,
+    ) -> Result<Vec<OrderMargins>, KiteConnectError> {
+        for order in &mut orders {
+            order.product = Product::Mtf.as_str().to_string();
+        }
+        self.get_order_margins(GetMarginParams {
+            order_params: orders,
+            mode: MarginMode::Full,
+        })
+        .await
+    }
+
+    /// Total margin blocked to buy `quantity` of `tradingsymbol` on MTF, at `price` or at
+    /// market when `None`.
+    pub async fn mtf_margin_required(
+        &self,
+        exchange: Exchange,
+        tradingsymbol: &str,
+        quantity: u32,
+        price: Option<f64>,
+    ) -> Result<f64, KiteConnectError> {
+        let order_type = if price.is_some() {
+            OrderType::Limit
+        } else {
+            OrderType::Market
+        };
+        let margins = self
+            .get_mtf_margins(vec![OrderMarginParam {
+                exchange: exchange.as_str().to_string(),
+                trading_symbol: tradingsymbol.to_string(),
+                transaction_type: TransactionType::Buy.as_str().to_string(),
+                variety: Variety::Regular.as_str().to_string(),
+                product: Product::Mtf.as_str().to_string(),
+                order_type: order_type.as_str().to_string(),
+                quantity: quantity as f64,
+                price,
+                trigger_price: None,
+            }])
+            .await?;
+        Ok(margins.iter().map(|m| m.total).sum())
+    }
+
+    /// Convert an open MTF position to CNC, or a CNC position to MTF; see
+    /// [`mtf_conversion`].
+    pub async fn convert_mtf_position(
+        &self,
+        position: &Position,
+        to: Product,
+    ) -> Result<bool, KiteConnectError> {
+        self.convert_position(mtf_conversion(position, to)?).await
+    }
+}
@@ -0,0 +1,165 @@
+//! Candles as input for technical analysis crates.
+//!
+//! [`Ohlcv`] reads prices and volume the same way off [`HistoricalData`],
+//! [`SeriesCandle`] and [`CandleRow`], and [`OhlcvExt`] turns an iterator over any of
+//! them into what TA crates take, without this crate depending on them:
+//!
+//! - `ta` indicators implement `Next<f64>`: feed them [`OhlcvExt::closes`] or
+//!   [`OhlcvExt::typical_prices`]
+//! - `yata` implements `OHLCV` for `(open, high, low, close, volume)` tuples: feed it
+//!   [`OhlcvExt::ohlcv_tuples`], or convert a single candle with `.into()`
+//!
+//! ```ignore
+//! use kiteconnect_rs::OhlcvExt;
+//! use ta::{Next, indicators::ExponentialMovingAverage};
+//!
+//! let mut ema = ExponentialMovingAverage::new(20)?;
+//! let last = candles.iter().closes().map(|close| ema.next(close)).last();
+//! ```
+
+use crate::markets::{CandleRow, HistoricalData};
+use crate::series::SeriesCandle;
+
+/// Ohlcv is a candle with open, high, low, close and volume.
+pub trait Ohlcv {
+    fn open(&self) -> f64;
+    fn high(&self) -> f64;
+    fn low(&self) -> f64;
+    fn close(&self) -> f64;
+    fn volume(&self) -> f64;
+
+    /// `(high + low + close) / 3`, the input of CCI and VWAP style indicators.
+    fn typical_price(&self) -> f64 {
+        (self.high() + self.low() + self.close()) / 3.0
+    }
+
+    /// `(open, high, low, close, volume)`.
+    fn to_tuple(&self) -> (f64, f64, f64, f64, f64) {
+        (
+            self.open(),
+            self.high(),
+            self.low(),
+            self.close(),
+            self.volume(),
+        )
+    }
+}
+
+impl<T: Ohlcv + ?Sized> Ohlcv for &T {
+    fn open(&self) -> f64 {
+        (**self).open()
+    }
+
+    fn high(&self) -> f64 {
+        (**self).high()
+    }
+
+    fn low(&self) -> f64 {
+        (**self).low()
+    }
+
+    fn close(&self) -> f64 {
+        (**self).close()
+    }
+
+    fn volume(&self) -> f64 {
+        (**self).volume()
+    }
+}
+
+impl Ohlcv for HistoricalData {
+    fn open(&self) -> f64 {
+        self.open
+    }
+
+    fn high(&self) -> f64 {
+        self.high
+    }
+
+    fn low(&self) -> f64 {
+        self.low
+    }
+
+    fn close(&self) -> f64 {
+        self.close
+    }
+
+    fn volume(&self) -> f64 {
+        self.volume as f64
+    }
+}
+
+impl Ohlcv for SeriesCandle {
+    fn open(&self) -> f64 {
+        self.candle.open
+    }
+
+    fn high(&self) -> f64 {
+        self.candle.high
+    }
+
+    fn low(&self) -> f64 {
+        self.candle.low
+    }
+
+    fn close(&self) -> f64 {
+        self.candle.close
+    }
+
+    fn volume(&self) -> f64 {
+        self.candle.volume as f64
+    }
+}
+
+impl Ohlcv for CandleRow {
+    fn open(&self) -> f64 {
+        self.1
+    }
+
+    fn high(&self) -> f64 {
+        self.2
+    }
+
+    fn low(&self) -> f64 {
+        self.3
+    }
+
+    fn close(&self) -> f64 {
+        self.4
+    }
+
+    fn volume(&self) -> f64 {
+        self.5
+    }
+}
+
+impl From<&HistoricalData> for (f64, f64, f64, f64, f64) {
+    fn from(candle: &HistoricalData) -> Self {
+        candle.to_tuple()
+    }
+}
+
+/// OhlcvExt adapts iterators over candles; see the [module docs](self).
+pub trait OhlcvExt: Iterator + Sized
+where
+    Self::Item: Ohlcv,
+{
+    fn closes(self) -> impl Iterator<Item = f64> {
+        self.map(|candle| candle.close())
+    }
+
+    fn typical_prices(self) -> impl Iterator<Item = f64> {
+        self.map(|candle| candle.typical_price())
+    }
+
+    fn ohlcv_tuples(self) -> impl Iterator<Item = (f64, f64, f64, f64, f64)> {
+        self.map(|candle| candle.to_tuple())
+    }
+}
+
+impl<I> OhlcvExt for I
+where
+    I: Iterator,
+    I::Item: Ohlcv,
+{
+}
@@ -0,0 +1,299 @@
+//! Deduplicating and ordering order updates.
+//!
+//! Order updates reach an application twice: as postbacks on the ticker websocket and as
+//! rows of `get_orders` when it polls. The two race each other, the websocket can repeat
+//! an update after a reconnect, and a poll can return a state older than the last
+//! postback. [`OrderStateMachine`] keeps the last accepted state of every order and turns
+//! both sources into one stream of [`OrderStateEvent`]s: repeated and stale updates are
+//! dropped, and an update that cannot follow the accepted state (a completed order
+//! reported as cancelled, say) is flagged as a [`OrderStateEvent::Gap`] so the
+//! application can reconcile it with `get_order_history`.
+//!
+//! ```ignore
+//! let mut orders = OrderStateMachine::new();
+//! while let Ok(event) = ticker_events.recv().await {
+//!     match orders.on_event(&event) {
+//!         Some(OrderStateEvent::Gap { state, .. }) => {
+//!             kite.get_order_history(&state.order_id).await?;
+//!         }
+//!         Some(change) => println!("{} is {}", change.state().order_id, change.state().status),
+//!         None => {}
+//!     }
+//! }
+//! ```
+
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use crate::models;
+use crate::orders::Order;
+use crate::ticker::TickerEvent;
+
+/// Statuses an order is in before it is live on the exchange order book. A stop-loss
+/// order waiting for its trigger, TRIGGER PENDING, is already live.
+pub const RECEIVED_ORDER_STATUSES: [&str; 4] = [
+    "PUT ORDER REQ RECEIVED",
+    "AMO REQ RECEIVED",
+    "VALIDATION PENDING",
+    "OPEN PENDING",
+];
+
+/// Statuses an order never leaves.
+pub const TERMINAL_ORDER_STATUSES: [&str; 3] = ["COMPLETE", "CANCELLED", "REJECTED"];
+
+/// OrderPhase is how far along its lifecycle an order is. Any status that is neither
+/// received nor terminal, including OPEN and the modify and cancel request statuses,
+/// counts as working.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum OrderPhase {
+    Received,
+    Working,
+    Terminal,
+}
+
+impl OrderPhase {
+    pub fn of(status: &str) -> Self {
+        if TERMINAL_ORDER_STATUSES.contains(&status) {
+            OrderPhase::Terminal
+        } else if RECEIVED_ORDER_STATUSES.contains(&status) {
+            OrderPhase::Received
+        } else {
+            OrderPhase::Working
+        }
+    }
+}
+
+/// OrderState is the part of an order that changes over its lifecycle, taken from either
+/// an order book row or a ticker order update.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OrderState {
+    pub order_id: String,
+    pub status: String,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    pub pending_quantity: f64,
+    pub cancelled_quantity: f64,
+    pub price: f64,
+    pub trigger_price: f64,
+    pub average_price: f64,
+    /// Exchange update time, or the order time before the exchange has seen the order.
+    pub updated_at: Option<DateTime<Utc>>,
+}
+
+impl OrderState {
+    pub fn phase(&self) -> OrderPhase {
+        OrderPhase::of(&self.status)
+    }
+
+    // Everything but the timestamp, which a repeated update may carry afresh
+    fn same_as(&self, other: &OrderState) -> bool {
+        self.status == other.status
+            && self.quantity == other.quantity
+            && self.filled_quantity == other.filled_quantity
+            && self.pending_quantity == other.pending_quantity
+            && self.cancelled_quantity == other.cancelled_quantity
+            && self.price == other.price
+            && self.trigger_price == other.trigger_price
+            && self.average_price == other.average_price
+    }
+}
+
+impl From<&Order> for OrderState {
+    fn from(order: &Order) -> Self {
+        Self {
+            order_id: order.order_id.clone(),
+            status: order.status.clone(),
+            quantity: order.quantity,
+            filled_quantity: order.filled_quantity,
+            pending_quantity: order.pending_quantity,
+            cancelled_quantity: order.cancelled_quantity,
+            price: order.price,
+            trigger_price: order.trigger_price,
+            average_price: order.average_price,
+            updated_at: order
+                .exchange_update_timestamp
+                .as_datetime()
+                .or_else(|| order.order_timestamp.as_datetime()),
+        }
+    }
+}
+
+impl From<&models::Order> for OrderState {
+    fn from(order: &models::Order) -> Self {
+        Self {
+            order_id: order.order_id.clone(),
+            status: order.status.clone(),
+            quantity: order.quantity,
+            filled_quantity: order.filled_quantity,
+            pending_quantity: order.pending_quantity,
+            cancelled_quantity: order.cancelled_quantity,
+            price: order.price,
+            trigger_price: order.trigger_price,
+            average_price: order.average_price,
+            updated_at: order
+                .exchange_update_timestamp
+                .as_datetime()
+                .or_else(|| order.order_timestamp.as_datetime()),
+        }
+    }
+}
+
+/// OrderStateEvent is an accepted change to an order.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum OrderStateEvent {
+    /// The first update seen for the order.
+    New { state: OrderState },
+    /// The order moved on from status `from`: a new status, a fill or a modification.
+    Changed { from: String, state: OrderState },
+    /// The update cannot follow the accepted state, e.g. a terminal order changing again.
+    /// It is accepted as the order's state, but updates in between were probably missed.
+    Gap { from: String, state: OrderState },
+}
+
+impl OrderStateEvent {
+    pub fn state(&self) -> &OrderState {
+        match self {
+            OrderStateEvent::New { state }
+            | OrderStateEvent::Changed { state, .. }
+            | OrderStateEvent::Gap { state, .. } => state,
+        }
+    }
+
+    pub fn is_gap(&self) -> bool {
+        matches!(self, OrderStateEvent::Gap { .. })
+    }
+}
+
+/// OrderStateStats counts the updates an [`OrderStateMachine`] did not pass on as they
+/// were.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct OrderStateStats {
+    /// Updates identical to the accepted state.
+    pub duplicates: u64,
+    /// Updates older than the accepted state.
+    pub stale: u64,
+    /// Updates reported as [`OrderStateEvent::Gap`].
+    pub gaps: u64,
+}
+
+/// OrderStateMachine tracks the last accepted state of each order.
+#[derive(Debug, Clone, Default)]
+pub struct OrderStateMachine {
+    orders: HashMap<String, OrderState>,
+    stats: OrderStateStats,
+}
+
+enum Verdict {
+    Duplicate,
+    Stale,
+    Changed,
+    Gap,
+}
+
+impl OrderStateMachine {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Apply one update, from a postback or a poll. Returns `None` when the update is a
+    /// duplicate or older than the accepted state.
+    pub fn apply(&mut self, state: OrderState) -> Option<OrderStateEvent> {
+        let Some(current) = self.orders.get(&state.order_id) else {
+            self.orders.insert(state.order_id.clone(), state.clone());
+            return Some(OrderStateEvent::New { state });
+        };
+        let from = current.status.clone();
+        let event = match verdict(current, &state) {
+            Verdict::Duplicate => {
+                self.stats.duplicates += 1;
+                return None;
+            }
+            Verdict::Stale => {
+                self.stats.stale += 1;
+                return None;
+            }
+            Verdict::Changed => OrderStateEvent::Changed {
+                from,
+                state: state.clone(),
+            },
+            Verdict::Gap => {
+                self.stats.gaps += 1;
+                OrderStateEvent::Gap {
+                    from,
+                    state: state.clone(),
+                }
+            }
+        };
+        self.orders.insert(state.order_id.clone(), state);
+        Some(event)
+    }
+
+    /// Apply an order book row.
+    pub fn apply_order(&mut self, order: &Order) -> Option<OrderStateEvent> {
+        self.apply(order.into())
+    }
+
+    /// Apply the result of a `get_orders` poll. Orders are applied oldest first so the
+    /// events come out in the order the changes happened.
+    pub fn apply_orders(&mut self, orders: &[Order]) -> Vec<OrderStateEvent> {
+        let mut states: Vec<OrderState> = orders.iter().map(OrderState::from).collect();
+        states.sort_by_key(|s| s.updated_at);
+        states.into_iter().filter_map(|s| self.apply(s)).collect()
+    }
+
+    /// Apply the order update carried by a ticker event; other events are ignored.
+    pub fn on_event(&mut self, event: &TickerEvent) -> Option<OrderStateEvent> {
+        match event {
+            TickerEvent::OrderUpdate(order) => self.apply(order.into()),
+            _ => None,
+        }
+    }
+
+    /// The accepted state of `order_id`.
+    pub fn state(&self, order_id: &str) -> Option<&OrderState> {
+        self.orders.get(order_id)
+    }
+
+    /// Accepted states of all orders seen so far.
+    pub fn states(&self) -> impl Iterator<Item = &OrderState> {
+        self.orders.values()
+    }
+
+    pub fn stats(&self) -> OrderStateStats {
+        self.stats
+    }
+
+    /// Forget orders that are terminal, e.g. at the end of the day.
+    pub fn clear_terminal(&mut self) {
+        self.orders.retain(|_, s| s.phase() != OrderPhase::Terminal);
+    }
+}
+
+fn verdict(current: &OrderState, update: &OrderState) -> Verdict {
+    if current.same_as(update) {
+        return Verdict::Duplicate;
+    }
+    let newer = match (current.updated_at, update.updated_at) {
+        (Some(at), Some(new_at)) if new_at < at => return Verdict::Stale,
+        (Some(at), Some(new_at)) => new_at > at,
+        _ => false,
+    };
+    // Without a later exchange time, an earlier phase is a late copy of an older update
+    let regressed = update.phase() < current.phase() && !newer;
+    if regressed || update.filled_quantity < current.filled_quantity {
+        return Verdict::Stale;
+    }
+    if current.phase() == OrderPhase::Terminal {
+        // Nothing follows a terminal status; any other terminal report means missed updates
+        return Verdict::Gap;
+    }
+    if update.filled_quantity > update.quantity
+        || (update.status == "REJECTED" && update.filled_quantity > 0.0)
+    {
+        return Verdict::Gap;
+    }
+    Verdict::Changed
+}
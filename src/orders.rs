@@ -1,12 +1,26 @@
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
+use web_time::Duration;
+
 use crate::{
     KiteConnect,
+    audit::AuditAction,
+    compat,
     constants::Endpoints,
-    models::{KiteConnectError, time},
+    labels::{OrderType, Product, TransactionType, Validity, Variety},
+    models::{self, KiteConnectError, time},
+    order_state::OrderPhase,
+    usage::ApiCategory,
 };
 
+/// How often to look for the stop-loss leg of a new cover order.
+const COVER_ORDER_LEG_ATTEMPTS: usize = 5;
+const COVER_ORDER_LEG_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Exchanges that accept the `iceberg` variety.
+pub(crate) const ICEBERG_EXCHANGES: [&str; 4] = ["NSE", "BSE", "NFO", "BFO"];
+
 /// Order represents an individual order response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Order {
@@ -61,11 +75,109 @@ pub struct Order {
     pub guid: Option<String>,
 }
 
+impl std::fmt::Display for Order {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        OrderSummary {
+            order_id: &self.order_id,
+            status: &self.status,
+            exchange: &self.exchange,
+            tradingsymbol: &self.tradingsymbol,
+            order_type: &self.order_type,
+            transaction_type: &self.transaction_type,
+            quantity: self.quantity,
+            filled_quantity: self.filled_quantity,
+            price: self.price,
+            trigger_price: self.trigger_price,
+        }
+        .fmt(f)
+    }
+}
+
+/// Converts a ticker order update into the order book form. Empty strings become `None`.
+impl From<models::Order> for Order {
+    fn from(order: models::Order) -> Self {
+        let non_empty = |s: String| if s.is_empty() { None } else { Some(s) };
+        Self {
+            account_id: non_empty(order.account_id),
+            placed_by: order.placed_by,
+            order_id: order.order_id,
+            exchange_order_id: non_empty(order.exchange_order_id),
+            parent_order_id: non_empty(order.parent_order_id),
+            status: order.status,
+            status_message: non_empty(order.status_message),
+            status_message_raw: non_empty(order.status_message_raw),
+            order_timestamp: order.order_timestamp,
+            exchange_update_timestamp: order.exchange_update_timestamp,
+            exchange_timestamp: order.exchange_timestamp,
+            variety: order.variety,
+            modified: order.modified,
+            meta: order.meta.into_iter().collect(),
+            exchange: order.exchange,
+            tradingsymbol: order.tradingsymbol,
+            instrument_token: order.instrument_token,
+            order_type: order.order_type,
+            transaction_type: order.transaction_type,
+            validity: order.validity,
+            validity_ttl: (order.validity_ttl > 0).then_some(order.validity_ttl),
+            product: order.product,
+            quantity: order.quantity,
+            disclosed_quantity: order.disclosed_quantity,
+            price: order.price,
+            trigger_price: order.trigger_price,
+            average_price: order.average_price,
+            filled_quantity: order.filled_quantity,
+            pending_quantity: order.pending_quantity,
+            cancelled_quantity: order.cancelled_quantity,
+            auction_number: non_empty(order.auction_number),
+            tag: non_empty(order.tag),
+            tags: (!order.tags.is_empty()).then_some(order.tags),
+            market_protection: None,
+            guid: None,
+        }
+    }
+}
+
+// One-line summary of an order for logs, e.g. `BUY 5/10 NSE:INFY LIMIT @ 1500 [OPEN] #1512`.
+// The filled quantity shows only for partly filled orders, the price and trigger only when set.
+pub(crate) struct OrderSummary<'a> {
+    pub order_id: &'a str,
+    pub status: &'a str,
+    pub exchange: &'a str,
+    pub tradingsymbol: &'a str,
+    pub order_type: &'a str,
+    pub transaction_type: &'a str,
+    pub quantity: f64,
+    pub filled_quantity: f64,
+    pub price: f64,
+    pub trigger_price: f64,
+}
+
+impl std::fmt::Display for OrderSummary<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{} ", self.transaction_type)?;
+        if self.filled_quantity > 0.0 && self.filled_quantity < self.quantity {
+            write!(f, "{}/", self.filled_quantity)?;
+        }
+        write!(
+            f,
+            "{} {}:{} {}",
+            self.quantity, self.exchange, self.tradingsymbol, self.order_type
+        )?;
+        if self.price > 0.0 {
+            write!(f, " @ {}", self.price)?;
+        }
+        if self.trigger_price > 0.0 {
+            write!(f, " trigger {}", self.trigger_price)?;
+        }
+        write!(f, " [{}] #{}", self.status, self.order_id)
+    }
+}
+
 /// Orders is a list of orders.
 pub type Orders = Vec<Order>;
 
 /// OrderParams represents parameters for placing an order.
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct OrderParams {
     pub exchange: Option<String>,
     pub tradingsymbol: Option<String>,
@@ -92,10 +204,337 @@ pub struct OrderParams {
     pub tag: Option<String>,
 }
 
+impl OrderParams {
+    pub fn builder() -> OrderParamsBuilder {
+        OrderParamsBuilder::default()
+    }
+
+    /// Build params that recreate an existing order.
+    pub fn from_order(order: &Order) -> Self {
+        let non_zero = |v: f64| if v > 0.0 { Some(v) } else { None };
+        Self {
+            exchange: Some(order.exchange.clone()),
+            tradingsymbol: Some(order.tradingsymbol.clone()),
+            validity: Some(order.validity.clone()),
+            validity_ttl: order.validity_ttl,
+            product: Some(order.product.clone()),
+            order_type: Some(order.order_type.clone()),
+            transaction_type: Some(order.transaction_type.clone()),
+            quantity: Some(order.quantity as i32),
+            disclosed_quantity: non_zero(order.disclosed_quantity).map(|q| q as i32),
+            price: non_zero(order.price),
+            trigger_price: non_zero(order.trigger_price),
+            squareoff: None,
+            stoploss: None,
+            trailing_stoploss: None,
+            iceberg_legs: None,
+            iceberg_quantity: None,
+            auction_number: order.auction_number.clone(),
+            tag: order.tag.clone(),
+        }
+    }
+
+    /// Overlay the fields set in `changes` on top of these params.
+    ///
+    /// Fails if `changes` tries to alter a field that identifies the order
+    /// (exchange, tradingsymbol, transaction_type or product).
+    pub fn merge(mut self, changes: OrderParams) -> Result<Self, KiteConnectError> {
+        let immutable = [
+            ("exchange", &self.exchange, &changes.exchange),
+            ("tradingsymbol", &self.tradingsymbol, &changes.tradingsymbol),
+            (
+                "transaction_type",
+                &self.transaction_type,
+                &changes.transaction_type,
+            ),
+            ("product", &self.product, &changes.product),
+        ];
+        for (name, current, new) in immutable {
+            if let (Some(current), Some(new)) = (current, new) {
+                if current != new {
+                    return Err(KiteConnectError::invalid_params(format!(
+                        "{} cannot be changed from {} to {}",
+                        name, current, new
+                    )));
+                }
+            }
+        }
+
+        macro_rules! overlay {
+            ($($field:ident),*) => {
+                $(if changes.$field.is_some() {
+                    self.$field = changes.$field;
+                })*
+            };
+        }
+        overlay!(
+            validity,
+            validity_ttl,
+            order_type,
+            quantity,
+            disclosed_quantity,
+            price,
+            trigger_price,
+            squareoff,
+            stoploss,
+            trailing_stoploss,
+            iceberg_legs,
+            iceberg_quantity,
+            auction_number,
+            tag
+        );
+
+        Ok(self)
+    }
+}
+
+/// IcebergParams splits an order into legs for the `iceberg` variety.
+///
+/// Kite sends `iceberg_quantity` per leg and the remainder as the last leg, so the leg
+/// quantity must be large enough to cover the order in `legs` legs and small enough that
+/// the last leg is not empty.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct IcebergParams {
+    pub legs: i32,
+    pub leg_quantity: i32,
+}
+
+impl IcebergParams {
+    pub const MIN_LEGS: i32 = 2;
+    pub const MAX_LEGS: i32 = 10;
+
+    /// Split `total_quantity` into `legs` legs of as equal size as possible.
+    pub fn split(total_quantity: i32, legs: i32) -> Result<Self, KiteConnectError> {
+        Self::split_lots(total_quantity, legs, 1)
+    }
+
+    /// Split `total_quantity` into `legs` legs whose quantities are multiples of
+    /// `lot_size`, as required for F&O.
+    pub fn split_lots(
+        total_quantity: i32,
+        legs: i32,
+        lot_size: i32,
+    ) -> Result<Self, KiteConnectError> {
+        check_legs(legs)?;
+        if lot_size <= 0 {
+            return Err(KiteConnectError::invalid_params(format!(
+                "lot size must be positive, got {}",
+                lot_size
+            )));
+        }
+        if total_quantity <= 0 || total_quantity % lot_size != 0 {
+            return Err(KiteConnectError::invalid_params(format!(
+                "iceberg quantity {} is not a positive multiple of the lot size {}",
+                total_quantity, lot_size
+            )));
+        }
+
+        let lots = total_quantity / lot_size;
+        if lots < legs {
+            return Err(KiteConnectError::invalid_params(format!(
+                "{} lots cannot be split into {} iceberg legs",
+                lots, legs
+            )));
+        }
+        let params = Self {
+            legs,
+            leg_quantity: (lots + legs - 1) / legs * lot_size,
+        };
+        params.validate(total_quantity)?;
+        Ok(params)
+    }
+
+    /// Check these legs against an order of `total_quantity`.
+    pub fn validate(&self, total_quantity: i32) -> Result<(), KiteConnectError> {
+        check_legs(self.legs)?;
+        if self.leg_quantity <= 0 {
+            return Err(KiteConnectError::invalid_params(
+                "iceberg leg quantity must be positive",
+            ));
+        }
+        let covered = self.leg_quantity as i64 * self.legs as i64;
+        let before_last = self.leg_quantity as i64 * (self.legs - 1) as i64;
+        if covered < total_quantity as i64 || before_last >= total_quantity as i64 {
+            return Err(KiteConnectError::invalid_params(format!(
+                "{} legs of {} do not add up to a quantity of {}",
+                self.legs, self.leg_quantity, total_quantity
+            )));
+        }
+        Ok(())
+    }
+
+    /// Quantity of each leg as the exchange will receive them.
+    pub fn leg_quantities(&self, total_quantity: i32) -> Vec<i32> {
+        let mut remaining = total_quantity;
+        (0..self.legs)
+            .map(|_| {
+                let quantity = remaining.min(self.leg_quantity);
+                remaining -= quantity;
+                quantity
+            })
+            .filter(|quantity| *quantity > 0)
+            .collect()
+    }
+}
+
+fn check_legs(legs: i32) -> Result<(), KiteConnectError> {
+    if !(IcebergParams::MIN_LEGS..=IcebergParams::MAX_LEGS).contains(&legs) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "iceberg legs must be between {} and {}, got {}",
+            IcebergParams::MIN_LEGS,
+            IcebergParams::MAX_LEGS,
+            legs
+        )));
+    }
+    Ok(())
+}
+
+/// OrderParamsBuilder builds [`OrderParams`]. String fields accept the typed enums from
+/// [`labels`](crate::labels) as well as plain strings.
+#[derive(Debug, Clone, Default)]
+pub struct OrderParamsBuilder {
+    params: OrderParams,
+    iceberg: Option<IcebergParams>,
+}
+
+impl OrderParamsBuilder {
+    pub fn exchange(mut self, exchange: impl Into<String>) -> Self {
+        self.params.exchange = Some(exchange.into());
+        self
+    }
+
+    pub fn tradingsymbol(mut self, tradingsymbol: impl Into<String>) -> Self {
+        self.params.tradingsymbol = Some(tradingsymbol.into());
+        self
+    }
+
+    pub fn transaction_type(mut self, transaction_type: impl Into<String>) -> Self {
+        self.params.transaction_type = Some(transaction_type.into());
+        self
+    }
+
+    pub fn order_type(mut self, order_type: impl Into<String>) -> Self {
+        self.params.order_type = Some(order_type.into());
+        self
+    }
+
+    pub fn product(mut self, product: impl Into<String>) -> Self {
+        self.params.product = Some(product.into());
+        self
+    }
+
+    pub fn validity(mut self, validity: impl Into<String>) -> Self {
+        self.params.validity = Some(validity.into());
+        self
+    }
+
+    pub fn validity_ttl(mut self, minutes: i32) -> Self {
+        self.params.validity_ttl = Some(minutes);
+        self
+    }
+
+    pub fn quantity(mut self, quantity: i32) -> Self {
+        self.params.quantity = Some(quantity);
+        self
+    }
+
+    pub fn disclosed_quantity(mut self, quantity: i32) -> Self {
+        self.params.disclosed_quantity = Some(quantity);
+        self
+    }
+
+    pub fn price(mut self, price: f64) -> Self {
+        self.params.price = Some(price);
+        self
+    }
+
+    pub fn trigger_price(mut self, trigger_price: f64) -> Self {
+        self.params.trigger_price = Some(trigger_price);
+        self
+    }
+
+    pub fn squareoff(mut self, squareoff: f64) -> Self {
+        self.params.squareoff = Some(squareoff);
+        self
+    }
+
+    pub fn stoploss(mut self, stoploss: f64) -> Self {
+        self.params.stoploss = Some(stoploss);
+        self
+    }
+
+    pub fn trailing_stoploss(mut self, trailing_stoploss: f64) -> Self {
+        self.params.trailing_stoploss = Some(trailing_stoploss);
+        self
+    }
+
+    /// Split the order into iceberg legs; place it with the `iceberg` variety.
+    pub fn iceberg(mut self, iceberg: IcebergParams) -> Self {
+        self.iceberg = Some(iceberg);
+        self
+    }
+
+    pub fn auction_number(mut self, auction_number: impl Into<String>) -> Self {
+        self.params.auction_number = Some(auction_number.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.params.tag = Some(tag.into());
+        self
+    }
+
+    /// Build the params, validating iceberg legs against the exchange and quantity.
+    pub fn build(self) -> Result<OrderParams, KiteConnectError> {
+        let mut params = self.params;
+        if let Some(iceberg) = self.iceberg {
+            let exchange = params.exchange.as_deref().unwrap_or_default();
+            if !ICEBERG_EXCHANGES.contains(&exchange) {
+                return Err(KiteConnectError::invalid_params(format!(
+                    "iceberg orders are not supported on exchange `{}`",
+                    exchange
+                )));
+            }
+            let quantity = params.quantity.ok_or_else(|| {
+                KiteConnectError::invalid_params("iceberg orders require a quantity")
+            })?;
+            iceberg.validate(quantity)?;
+            params.iceberg_legs = Some(iceberg.legs);
+            params.iceberg_quantity = Some(iceberg.leg_quantity);
+        }
+        Ok(params)
+    }
+}
+
 /// OrderResponse represents the order place success response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[non_exhaustive]
 pub struct OrderResponse {
     pub order_id: String,
+    /// Ids of the further child orders when `place_order` split the order at the freeze
+    /// quantity.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub split_order_ids: Vec<String>,
+}
+
+impl OrderResponse {
+    /// The response for a single order, e.g. from an [`OrderApi`](crate::backtest::OrderApi)
+    /// implementation.
+    pub fn new(order_id: impl Into<String>) -> Self {
+        Self {
+            order_id: order_id.into(),
+            split_order_ids: Vec::new(),
+        }
+    }
+}
+
+/// CoverOrderIds are the two legs of a cover order.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct CoverOrderIds {
+    pub entry_order_id: String,
+    /// The pending stop-loss leg. `None` if it did not show up in the order book in time,
+    /// e.g. because the entry was rejected, or the order book could not be fetched.
+    pub stoploss_order_id: Option<String>,
 }
 
 /// Trade represents an individual trade response.
@@ -123,6 +562,75 @@ pub struct Trade {
 /// Trades is a list of trades.
 pub type Trades = Vec<Trade>;
 
+/// CancelFilter selects the open orders cancelled by `cancel_all_orders`. Unset fields
+/// match every order.
+#[derive(Debug, Clone, Default)]
+pub struct CancelFilter {
+    pub tradingsymbol: Option<String>,
+    /// Matches the order's `tag` or any of its `tags`.
+    pub tag: Option<String>,
+    pub variety: Option<String>,
+    pub product: Option<String>,
+}
+
+impl CancelFilter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn tradingsymbol(mut self, tradingsymbol: impl Into<String>) -> Self {
+        self.tradingsymbol = Some(tradingsymbol.into());
+        self
+    }
+
+    pub fn tag(mut self, tag: impl Into<String>) -> Self {
+        self.tag = Some(tag.into());
+        self
+    }
+
+    pub fn variety(mut self, variety: impl Into<String>) -> Self {
+        self.variety = Some(variety.into());
+        self
+    }
+
+    pub fn product(mut self, product: impl Into<String>) -> Self {
+        self.product = Some(product.into());
+        self
+    }
+
+    /// Whether `order` is open and passes the filter.
+    pub fn matches(&self, order: &Order) -> bool {
+        let field = |want: &Option<String>, value: &str| want.as_deref().is_none_or(|w| w == value);
+        let tagged = self.tag.as_deref().is_none_or(|tag| {
+            order.tag.as_deref() == Some(tag)
+                || order
+                    .tags
+                    .as_ref()
+                    .is_some_and(|tags| tags.iter().any(|t| t == tag))
+        });
+        OrderPhase::of(&order.status) != OrderPhase::Terminal
+            && field(&self.tradingsymbol, &order.tradingsymbol)
+            && field(&self.variety, &order.variety)
+            && field(&self.product, &order.product)
+            && tagged
+    }
+}
+
+/// CancelResult is the outcome of cancelling one order in `cancel_all_orders`.
+#[derive(Debug)]
+pub struct CancelResult {
+    pub order_id: String,
+    pub tradingsymbol: String,
+    pub variety: String,
+    pub result: Result<OrderResponse, KiteConnectError>,
+}
+
+impl CancelResult {
+    pub fn is_cancelled(&self) -> bool {
+        self.result.is_ok()
+    }
+}
+
 impl KiteConnect {
     /// Gets list of orders.
     pub async fn get_orders(&self) -> Result<Orders, KiteConnectError> {
@@ -147,28 +655,304 @@ impl KiteConnect {
     }
 
     /// Places an order.
+    ///
+    /// With [`OrderRounding`](crate::instruments::OrderRounding) set on the client, prices
+    /// and quantities are rounded to the instrument's tick and lot size first. With
+    /// [`FreezeQuantities`](crate::freeze::FreezeQuantities) set, F&O orders above the
+    /// freeze quantity are placed as several child orders: the response holds the first
+    /// child's id and the others are in `split_order_ids`. If a child fails after others
+    /// were placed, the error is [`PartialSplit`](crate::KiteConnectErrorKind::PartialSplit)
+    /// with the ids of the live children. See
+    /// [`place_order_split`](Self::place_order_split) for per-child results. With an
+    /// [`OrderValidator`](crate::validation::OrderValidator) set, orders breaking an
+    /// exchange rule fail with every violation before being sent.
     pub async fn place_order(
+        &self,
+        variety: &str,
+        mut order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        if self.freeze_quantities.is_none() {
+            self.round_order(variety, &mut order_params).await?;
+            return self.place_order_checked(variety, order_params).await;
+        }
+
+        let mut placed = Vec::new();
+        for result in self.place_order_split(variety, order_params).await? {
+            match result {
+                Ok(response) => placed.push(response.order_id),
+                Err(e) if placed.is_empty() => return Err(e),
+                Err(e) => return Err(KiteConnectError::partial_split(placed, e)),
+            }
+        }
+        let order_id = placed.remove(0);
+        Ok(OrderResponse {
+            order_id,
+            split_order_ids: placed,
+        })
+    }
+
+    /// Applies the client's order rounding, recording a rejected order in the audit log.
+    pub(crate) async fn round_order(
+        &self,
+        variety: &str,
+        order_params: &mut OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        let Some(rounding) = &self.order_rounding else {
+            return Ok(());
+        };
+        if let Err(e) = rounding.apply(order_params) {
+            let rejected: Result<OrderResponse, _> = Err(e);
+            self.audit_request(AuditAction::Place, variety, None, order_params, &rejected)
+                .await;
+            return rejected.map(|_| ());
+        }
+        Ok(())
+    }
+
+    /// Places an order after the client-side risk checks.
+    pub(crate) async fn place_order_checked(
         &self,
         variety: &str,
         order_params: OrderParams,
     ) -> Result<OrderResponse, KiteConnectError> {
+        self.check_order(variety, &order_params).await?;
+        self.place_order_unchecked(variety, order_params).await
+    }
+
+    /// Runs the client's validator and risk checks on an order, recording a rejected order
+    /// in the audit log.
+    pub(crate) async fn check_order(
+        &self,
+        variety: &str,
+        order_params: &OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        self.check_order_as(variety, order_params, true).await
+    }
+
+    /// [`check_order`](Self::check_order) for a new order, or for one taking the place of
+    /// an open order, which is not held to `max_open_orders`.
+    async fn check_order_as(
+        &self,
+        variety: &str,
+        order_params: &OrderParams,
+        new_order: bool,
+    ) -> Result<(), KiteConnectError> {
+        let checked = match &self.order_validator {
+            Some(validator) => validator.check(variety, order_params),
+            None => Ok(()),
+        };
+        let checked = match (checked, &self.tag_registry, &order_params.tag) {
+            (Ok(()), Some(registry), Some(tag)) => registry.check(tag).map(|_| ()),
+            (checked, _, _) => checked,
+        };
+        let checked = match checked {
+            Ok(()) if new_order => self.enforce_risk_limits(order_params).await,
+            Ok(()) => self.enforce_modify_limits(order_params).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = checked {
+            let rejected: Result<OrderResponse, _> = Err(e);
+            self.audit_request(AuditAction::Place, variety, None, order_params, &rejected)
+                .await;
+            return rejected.map(|_| ());
+        }
+        Ok(())
+    }
+
+    /// Places an order without applying the client-side risk checks.
+    ///
+    /// Used for exits such as square-offs, which must go through even once a limit is hit.
+    pub(crate) async fn place_order_unchecked(
+        &self,
+        variety: &str,
+        mut order_params: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        order_params.tag = self.prefixed_tag(order_params.tag.take());
         let endpoint = &Endpoints::PLACE_ORDER.replace("{variety}", variety);
-        println!("{:?} ", order_params);
-        self.post_form(endpoint, order_params).await
+        log::debug!("placing {} order {:?}", variety, order_params);
+        let result: Result<OrderResponse, _> = self.post_form(endpoint, &order_params).await;
+        if let (Ok(response), Some(registry), Some(tag)) =
+            (&result, &self.tag_registry, &order_params.tag)
+        {
+            registry.record_order(&response.order_id, tag);
+        }
+        let order_id = result.as_ref().ok().map(|r| r.order_id.as_str());
+        self.audit_request(
+            AuditAction::Place,
+            variety,
+            order_id,
+            &order_params,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Places a cover order: `entry` with a compulsory stop-loss at `trigger_price`.
+    ///
+    /// The trigger is checked against the LTP first, as it has to be below it for buys and
+    /// above it for sells. Sets the `co` variety, `MIS` product and `DAY` validity.
+    /// `entry` must be a MARKET or LIMIT order.
+    pub async fn place_cover_order(
+        &self,
+        mut entry: OrderParams,
+        trigger_price: f64,
+    ) -> Result<CoverOrderIds, KiteConnectError> {
+        let side: TransactionType = entry
+            .transaction_type
+            .as_deref()
+            .ok_or_else(|| {
+                KiteConnectError::invalid_params("cover order needs a transaction_type")
+            })?
+            .parse()?;
+        let order_type: OrderType = entry
+            .order_type
+            .as_deref()
+            .unwrap_or(OrderType::Market.as_str())
+            .parse()?;
+        if !matches!(order_type, OrderType::Market | OrderType::Limit) {
+            return Err(KiteConnectError::invalid_params(format!(
+                "cover order entry must be MARKET or LIMIT, got {}",
+                order_type
+            )));
+        }
+
+        let last_price = self.last_price_for(&entry).await?;
+        let valid = match side {
+            TransactionType::Buy => trigger_price < last_price,
+            TransactionType::Sell => trigger_price > last_price,
+        };
+        if !valid {
+            return Err(KiteConnectError::invalid_params(format!(
+                "{} cover order trigger {} must be {} the LTP {}",
+                side,
+                trigger_price,
+                if side == TransactionType::Buy {
+                    "below"
+                } else {
+                    "above"
+                },
+                last_price
+            )));
+        }
+
+        entry.order_type = Some(order_type.into());
+        entry.trigger_price = Some(trigger_price);
+        entry.product = Some(Product::Mis.into());
+        entry.validity = Some(Validity::Day.into());
+        let response = self.place_order(Variety::Cover.as_str(), entry).await?;
+
+        // The entry is placed by now, so a failed lookup must not lose its id
+        let stoploss_order_id = match self.find_child_order(&response.order_id).await {
+            Ok(child) => child,
+            Err(e) => {
+                log::warn!(
+                    "stop-loss leg of cover order {} not found: {}",
+                    response.order_id,
+                    e
+                );
+                None
+            }
+        };
+        Ok(CoverOrderIds {
+            entry_order_id: response.order_id,
+            stoploss_order_id,
+        })
+    }
+
+    /// Look up the first order whose parent is `order_id`, retrying briefly as child legs
+    /// are created after the parent.
+    async fn find_child_order(&self, order_id: &str) -> Result<Option<String>, KiteConnectError> {
+        for attempt in 0..COVER_ORDER_LEG_ATTEMPTS {
+            if attempt > 0 {
+                compat::sleep(COVER_ORDER_LEG_INTERVAL).await;
+            }
+            let child = self
+                .get_orders()
+                .await?
+                .into_iter()
+                .find(|o| o.parent_order_id.as_deref() == Some(order_id));
+            if let Some(child) = child {
+                return Ok(Some(child.order_id));
+            }
+        }
+        Ok(None)
     }
 
     /// Modifies an order.
+    ///
+    /// The order as modified goes through the same client-side risk checks as a new one,
+    /// except `max_open_orders`; with limits set, it is looked up first.
     pub async fn modify_order(
         &self,
         variety: &str,
         order_id: &str,
         order_params: OrderParams,
     ) -> Result<OrderResponse, KiteConnectError> {
+        if let Err(e) = self.check_modify(variety, order_id, &order_params).await {
+            let rejected: Result<OrderResponse, _> = Err(e);
+            self.audit_request(
+                AuditAction::Modify,
+                variety,
+                Some(order_id),
+                &order_params,
+                &rejected,
+            )
+            .await;
+            return rejected;
+        }
         let endpoint = &Endpoints::MODIFY_ORDER
             .replace("{variety}", variety)
             .replace("{order_id}", order_id);
-        println!("{:?} ", order_params);
-        self.put_form(endpoint, order_params).await
+        log::debug!("modifying order {} with {:?}", order_id, order_params);
+        let result = self.put_form(endpoint, &order_params).await;
+        self.audit_request(
+            AuditAction::Modify,
+            variety,
+            Some(order_id),
+            &order_params,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Runs the client's validator and risk checks on an order as it will be after
+    /// applying `changes`. The order is only looked up when a validator or limits are set.
+    pub(crate) async fn check_modify(
+        &self,
+        variety: &str,
+        order_id: &str,
+        changes: &OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        self.require_known_orders()?;
+        if self.order_validator.is_none()
+            && self.risk_limits.is_none()
+            && self.known_capabilities().is_none()
+        {
+            return self.enforce_modify_limits(changes).await;
+        }
+        let params = self.modified_order(order_id, changes).await?;
+        if let Some(validator) = &self.order_validator {
+            validator.check(variety, &params)?;
+        }
+        self.enforce_modify_limits(&params).await
+    }
+
+    /// The order as it will be after applying `changes`, from its latest state.
+    pub(crate) async fn modified_order(
+        &self,
+        order_id: &str,
+        changes: &OrderParams,
+    ) -> Result<OrderParams, KiteConnectError> {
+        let order = self
+            .get_order_history(order_id)
+            .await?
+            .pop()
+            .ok_or_else(|| {
+                KiteConnectError::invalid_params(format!("order {} not found", order_id))
+            })?;
+        OrderParams::from_order(&order).merge(changes.clone())
     }
 
     /// Cancels/exits an order.
@@ -187,7 +971,114 @@ impl KiteConnect {
             params.insert("parent_order_id".to_string(), parent_id.to_string());
         }
 
-        self.delete_form(endpoint, params).await
+        let result = match self.require_known_orders() {
+            Ok(()) => self.delete_form(endpoint, &params).await,
+            Err(e) => Err(e),
+        };
+        self.audit_request(
+            AuditAction::Cancel,
+            variety,
+            Some(order_id),
+            &params,
+            &result,
+        )
+        .await;
+        result
+    }
+
+    /// Modifies only the price of an order.
+    pub async fn modify_price(
+        &self,
+        variety: &str,
+        order_id: &str,
+        new_price: f64,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let params = OrderParams {
+            price: Some(new_price),
+            ..Default::default()
+        };
+        self.modify_order(variety, order_id, params).await
+    }
+
+    /// Modifies only the quantity of an order.
+    pub async fn modify_quantity(
+        &self,
+        variety: &str,
+        order_id: &str,
+        new_quantity: i32,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        if new_quantity <= 0 {
+            return Err(KiteConnectError::invalid_params(
+                "quantity must be greater than zero",
+            ));
+        }
+        let params = OrderParams {
+            quantity: Some(new_quantity),
+            ..Default::default()
+        };
+        self.modify_order(variety, order_id, params).await
+    }
+
+    /// Shifts the price of an order by `delta` relative to its current price.
+    pub async fn modify_price_by(
+        &self,
+        variety: &str,
+        order_id: &str,
+        delta: f64,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let order = self.latest_order_state(order_id).await?;
+        let new_price = order.price + delta;
+        if new_price <= 0.0 {
+            return Err(KiteConnectError::invalid_params(format!(
+                "price {} shifted by {} is not positive",
+                order.price, delta
+            )));
+        }
+        self.modify_price(variety, order_id, new_price).await
+    }
+
+    /// Cancels an order and places a replacement built from the original order and `changes`.
+    ///
+    /// Fields not set in `changes` are copied from the existing order, with the quantity
+    /// still pending as the quantity. Only an order that is OPEN or TRIGGER PENDING can be
+    /// replaced, and changing the exchange, tradingsymbol, transaction type or product is
+    /// rejected before anything is cancelled. The replacement is rounded and put through
+    /// the validator, tag registry and risk checks before the cancel too; should it still
+    /// fail to place, the error is [`ReplaceFailed`](KiteConnectErrorKind::ReplaceFailed),
+    /// as the original order is gone.
+    pub async fn cancel_replace(
+        &self,
+        order_id: &str,
+        changes: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let order = self.latest_order_state(order_id).await?;
+        if !matches!(order.status.as_str(), "OPEN" | "TRIGGER PENDING") {
+            return Err(KiteConnectError::invalid_params(format!(
+                "order {} is {}, not open",
+                order_id, order.status
+            )));
+        }
+        let mut params = OrderParams::from_order(&order);
+        params.quantity = Some(order.pending_quantity as i32);
+        let mut params = params.merge(changes)?;
+        self.round_order(&order.variety, &mut params).await?;
+        self.check_order_as(&order.variety, &params, false).await?;
+
+        self.cancel_order(&order.variety, order_id, order.parent_order_id.as_deref())
+            .await?;
+        self.place_order(&order.variety, params)
+            .await
+            .map_err(|e| KiteConnectError::replace_failed(order_id, e))
+    }
+
+    /// Fetches the most recent state of an order from its history.
+    async fn latest_order_state(&self, order_id: &str) -> Result<Order, KiteConnectError> {
+        self.get_order_history(order_id)
+            .await?
+            .pop()
+            .ok_or_else(|| {
+                KiteConnectError::invalid_params(format!("order {} not found", order_id))
+            })
     }
 
     /// Alias for cancel_order which is used to cancel/exit an order.
@@ -200,3 +1091,40 @@ impl KiteConnect {
         self.cancel_order(variety, order_id, parent_order_id).await
     }
 }
+
+impl KiteConnect {
+    /// Cancels every open order that passes `filter`, e.g. all MIS orders before the
+    /// intraday square-off.
+    ///
+    /// Orders are cancelled concurrently, as many at a time as the per-second order limit
+    /// has room for given the requests this client's usage pool has already made. A failed
+    /// cancel does not stop the others; each order gets its own result.
+    pub async fn cancel_all_orders(
+        &self,
+        filter: &CancelFilter,
+    ) -> Result<Vec<CancelResult>, KiteConnectError> {
+        let pending: Vec<Order> = self
+            .get_orders()
+            .await?
+            .into_iter()
+            .filter(|order| filter.matches(order))
+            .collect();
+
+        let results = self.within_rate_limit(ApiCategory::Orders, pending, |order| async move {
+            let result = self
+                .cancel_order(
+                    &order.variety,
+                    &order.order_id,
+                    order.parent_order_id.as_deref(),
+                )
+                .await;
+            CancelResult {
+                order_id: order.order_id,
+                tradingsymbol: order.tradingsymbol,
+                variety: order.variety,
+                result,
+            }
+        });
+        Ok(results.await)
+    }
+}
@@ -0,0 +1,168 @@
+//! Walking date-range endpoints in fixed windows.
+//!
+//! [`KiteConnect::paginate`] splits a [`PageParams`] range into windows and requests each
+//! with `from`/`to` query parameters, yielding the items as a stream. Of the Kite APIs,
+//! only the MF order history takes a date range, see `get_mf_orders_paginated`. The
+//! order book, trades, GTTs and alert history take no date or page parameters: each
+//! returns everything Kite keeps in one response, so they have no paginated variant.
+
+use chrono::{Days, NaiveDate};
+use futures_util::stream::{self, Stream, StreamExt};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+
+use crate::{KiteConnect, models::KiteConnectError};
+
+/// Date format used for `from`/`to` query parameters.
+const PAGE_DATE_FORMAT: &str = "%Y-%m-%d";
+
+/// Default number of days covered by a single page.
+pub const DEFAULT_PAGE_WINDOW_DAYS: u64 = 30;
+
+/// PageParams describes a date range that is walked in fixed-size windows.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageParams {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub window_days: u64,
+    pub extra: HashMap<String, String>,
+}
+
+impl PageParams {
+    pub fn new(from: NaiveDate, to: NaiveDate) -> Self {
+        Self {
+            from,
+            to,
+            window_days: DEFAULT_PAGE_WINDOW_DAYS,
+            extra: HashMap::new(),
+        }
+    }
+
+    /// Set the number of days fetched per request. Values below 1 are treated as 1.
+    pub fn window_days(mut self, days: u64) -> Self {
+        self.window_days = days.max(1);
+        self
+    }
+
+    /// Add an extra query parameter sent with every page request.
+    pub fn param(mut self, key: &str, value: &str) -> Self {
+        self.extra.insert(key.to_string(), value.to_string());
+        self
+    }
+
+    /// Returns the params for the first window, or None if the range is empty.
+    fn first_window(&self) -> Option<PageWindow> {
+        if self.from > self.to {
+            return None;
+        }
+        Some(self.window_starting_at(self.from))
+    }
+
+    fn window_starting_at(&self, start: NaiveDate) -> PageWindow {
+        let end = start
+            .checked_add_days(Days::new(self.window_days.max(1) - 1))
+            .map_or(self.to, |end| end.min(self.to));
+        PageWindow {
+            from: start,
+            to: end,
+        }
+    }
+
+    fn next_window(&self, current: &PageWindow) -> Option<PageWindow> {
+        let start = current.to.checked_add_days(Days::new(1))?;
+        if start > self.to {
+            return None;
+        }
+        Some(self.window_starting_at(start))
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PageWindow {
+    from: NaiveDate,
+    to: NaiveDate,
+}
+
+/// Paginated represents a single page of results for a date window.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Paginated<T> {
+    pub items: Vec<T>,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub has_more: bool,
+}
+
+impl KiteConnect {
+    /// Fetch a single page for the given date window.
+    async fn fetch_page<T>(
+        &self,
+        endpoint: &str,
+        params: &PageParams,
+        window: PageWindow,
+    ) -> Result<Paginated<T>, KiteConnectError>
+    where
+        T: DeserializeOwned,
+    {
+        let mut query = params.extra.clone();
+        query.insert(
+            "from".to_string(),
+            window.from.format(PAGE_DATE_FORMAT).to_string(),
+        );
+        query.insert(
+            "to".to_string(),
+            window.to.format(PAGE_DATE_FORMAT).to_string(),
+        );
+
+        let items: Vec<T> = self.get_with_query(endpoint, query).await?;
+
+        Ok(Paginated {
+            items,
+            from: window.from,
+            to: window.to,
+            has_more: window.to < params.to,
+        })
+    }
+
+    /// Walks a date-range endpoint page by page.
+    ///
+    /// The range in `params` is split into windows of `window_days` and each window is
+    /// requested with `from`/`to` query parameters. Pages are fetched lazily, so dropping
+    /// the stream stops further requests.
+    pub fn paginate_pages<'a, T>(
+        &'a self,
+        endpoint: &'a str,
+        params: PageParams,
+    ) -> impl Stream<Item = Result<Paginated<T>, KiteConnectError>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        let first = params.first_window();
+        stream::unfold((first, params), move |(window, params)| async move {
+            let window = window?;
+            let page = self.fetch_page::<T>(endpoint, &params, window).await;
+            let next = match page {
+                Ok(ref p) if p.has_more => params.next_window(&window),
+                _ => None,
+            };
+            Some((page, (next, params)))
+        })
+    }
+
+    /// Walks a date-range endpoint and yields the individual items of every page.
+    ///
+    /// An error for a page is yielded once and ends the stream.
+    pub fn paginate<'a, T>(
+        &'a self,
+        endpoint: &'a str,
+        params: PageParams,
+    ) -> impl Stream<Item = Result<T, KiteConnectError>> + 'a
+    where
+        T: DeserializeOwned + 'a,
+    {
+        self.paginate_pages(endpoint, params)
+            .flat_map(|page| match page {
+                Ok(page) => stream::iter(page.items.into_iter().map(Ok).collect::<Vec<_>>()),
+                Err(e) => stream::iter(vec![Err(e)]),
+            })
+    }
+}
@@ -1,10 +1,14 @@
+use futures_util::future::join_all;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
 
 use crate::{
     KiteConnect,
     constants::{Endpoints, app_constants::*},
+    labels::{OrderType, Product, TransactionType, Validity, Variety},
+    margins::{Charges, GetChargesParams, OrderChargesParam},
     models::{KiteConnectError, time},
+    orders::{OrderParams, OrderResponse},
 };
 
 // MTFHolding represents the mtf details for a holding
@@ -51,6 +55,46 @@ pub struct Holding {
 // Holdings is a list of holdings
 pub type Holdings = Vec<Holding>;
 
+impl Holding {
+    /// Quantity that can be sold today without an "insufficient holdings" rejection.
+    ///
+    /// Shares pledged as collateral have to be unpledged first. Under CDSL rules settled
+    /// shares held without DDPI or POA can only be sold up to the quantity authorised
+    /// through TPIN, so a non-zero `authorised_quantity` caps them; zero is taken to mean
+    /// no authorisation is needed. T1 shares are not in the demat account yet and need
+    /// none. Shares already sold or blocked by open sell orders (`used_quantity`) are
+    /// deducted.
+    pub fn sellable_quantity(&self) -> i32 {
+        let mut settled = (self.quantity - self.collateral_quantity).max(0);
+        if self.authorised_quantity > 0 {
+            settled = settled.min(self.authorised_quantity);
+        }
+        (settled + self.t1_quantity.max(0) - self.used_quantity).max(0)
+    }
+}
+
+/// Checks that `quantity` shares of `tradingsymbol` can be sold from `holdings`, so a
+/// sell is rejected locally instead of by the exchange. The exchange is not compared, as
+/// delivery shares can be sold on either.
+pub fn check_sellable(
+    holdings: &[Holding],
+    tradingsymbol: &str,
+    quantity: i32,
+) -> Result<(), KiteConnectError> {
+    let sellable: i32 = holdings
+        .iter()
+        .filter(|h| h.tradingsymbol == tradingsymbol)
+        .map(Holding::sellable_quantity)
+        .sum();
+    if quantity > sellable {
+        return Err(KiteConnectError::invalid_params(format!(
+            "insufficient holdings: {} sellable of {}, order is for {}",
+            sellable, tradingsymbol, quantity
+        )));
+    }
+    Ok(())
+}
+
 // Position represents an individual position response.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Position {
@@ -164,6 +208,117 @@ pub struct HoldingsAuthResp {
     pub redirect_url: Option<String>,
 }
 
+// SquareOffParams controls which net positions square_off_all closes and how.
+#[derive(Debug, Clone, Default)]
+pub struct SquareOffParams {
+    // Only close positions with this product (e.g. MIS). All products when None.
+    pub product: Option<String>,
+    // Compute the offsetting orders without placing them.
+    pub dry_run: bool,
+    // Skip instruments with no opposite side in the market depth.
+    pub skip_illiquid: bool,
+}
+
+// SquareOffOutcome is the result of squaring off a single position.
+#[derive(Debug)]
+pub enum SquareOffOutcome {
+    Placed(String),
+    DryRun,
+    Skipped(String),
+    Failed(KiteConnectError),
+}
+
+// SquareOffResult pairs a generated offsetting order with its outcome.
+#[derive(Debug)]
+pub struct SquareOffResult {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub product: String,
+    pub transaction_type: String,
+    pub quantity: i32,
+    pub outcome: SquareOffOutcome,
+}
+
+impl Position {
+    /// Returns the MARKET order that would flatten this position, or None if it is already flat.
+    pub fn square_off_params(&self) -> Option<OrderParams> {
+        let transaction_type = match self.quantity {
+            0 => return None,
+            q if q > 0 => TransactionType::Sell,
+            _ => TransactionType::Buy,
+        };
+
+        Some(OrderParams {
+            exchange: Some(self.exchange.clone()),
+            tradingsymbol: Some(self.tradingsymbol.clone()),
+            transaction_type: Some(transaction_type.into()),
+            order_type: Some(OrderType::Market.into()),
+            product: Some(self.product.clone()),
+            validity: Some(Validity::Day.into()),
+            quantity: Some(self.quantity.abs()),
+            ..Default::default()
+        })
+    }
+
+    /// The price at which closing the position leaves a net PnL of zero once `charges` are
+    /// paid, or None for a flat position.
+    ///
+    /// `charges` should cover every trade of the position, plus the exit if the price is
+    /// meant to be the one to exit at. See [`charges_params`](Self::charges_params).
+    pub fn breakeven(&self, charges: &Charges) -> Option<f64> {
+        if self.quantity == 0 {
+            return None;
+        }
+        let multiplier = if self.multiplier > 0.0 {
+            self.multiplier
+        } else {
+            1.0
+        };
+        // value is sell value minus buy value; closing at P adds quantity * P * multiplier
+        Some((charges.total - self.value) / (self.quantity as f64 * multiplier))
+    }
+
+    /// Charges calculator requests for the buy and sell trades of the position, with an
+    /// exit at the last price added for open positions when `include_exit` is set.
+    pub fn charges_params(&self, include_exit: bool) -> Vec<OrderChargesParam> {
+        let leg =
+            |transaction_type: TransactionType, quantity: i32, price: f64| OrderChargesParam {
+                order_id: format!("{}-{}", self.tradingsymbol, transaction_type),
+                exchange: self.exchange.clone(),
+                trading_symbol: self.tradingsymbol.clone(),
+                transaction_type: transaction_type.into(),
+                variety: Variety::Regular.into(),
+                product: self.product.clone(),
+                order_type: OrderType::Market.into(),
+                quantity: quantity as f64,
+                average_price: price,
+            };
+
+        let mut legs = Vec::new();
+        if self.buy_quantity > 0 {
+            legs.push(leg(TransactionType::Buy, self.buy_quantity, self.buy_price));
+        }
+        if self.sell_quantity > 0 {
+            legs.push(leg(
+                TransactionType::Sell,
+                self.sell_quantity,
+                self.sell_price,
+            ));
+        }
+        if include_exit && self.quantity != 0 {
+            let side = if self.quantity > 0 {
+                TransactionType::Sell
+            } else {
+                TransactionType::Buy
+            };
+            let mut exit = leg(side, self.quantity.abs(), self.last_price);
+            exit.order_id.push_str("-exit");
+            legs.push(exit);
+        }
+        legs
+    }
+}
+
 impl KiteConnect {
     /// Get a list of holdings
     pub async fn get_holdings(&self) -> Result<Holdings, KiteConnectError> {
@@ -182,6 +337,142 @@ impl KiteConnect {
         self.get(Endpoints::GET_POSITIONS).await
     }
 
+    /// Flatten all open net positions with offsetting MARKET orders.
+    ///
+    /// Orders are placed concurrently and every position gets its own result, so one
+    /// rejected order does not prevent the rest from being closed.
+    pub async fn square_off_all(
+        &self,
+        params: SquareOffParams,
+    ) -> Result<Vec<SquareOffResult>, KiteConnectError> {
+        let positions = self.get_positions().await?;
+
+        let candidates: Vec<(Position, OrderParams)> = positions
+            .net
+            .into_iter()
+            .filter(|p| {
+                params
+                    .product
+                    .as_ref()
+                    .is_none_or(|prod| &p.product == prod)
+            })
+            .filter_map(|p| p.square_off_params().map(|order| (p, order)))
+            .collect();
+
+        let illiquid = if params.skip_illiquid && !candidates.is_empty() {
+            self.illiquid_instruments(&candidates).await?
+        } else {
+            Vec::new()
+        };
+
+        let tasks = candidates.into_iter().map(|(position, order)| {
+            let key = format!("{}:{}", position.exchange, position.tradingsymbol);
+            let skip = illiquid.contains(&key);
+            let dry_run = params.dry_run;
+            async move {
+                let transaction_type = order.transaction_type.clone().unwrap_or_default();
+                let quantity = order.quantity.unwrap_or_default();
+                let outcome = if skip {
+                    SquareOffOutcome::Skipped("no liquidity on the opposite side".to_string())
+                } else if dry_run {
+                    SquareOffOutcome::DryRun
+                } else {
+                    match self
+                        .place_order_unchecked(Variety::Regular.as_str(), order)
+                        .await
+                    {
+                        Ok(resp) => SquareOffOutcome::Placed(resp.order_id),
+                        Err(e) => SquareOffOutcome::Failed(e),
+                    }
+                };
+                SquareOffResult {
+                    exchange: position.exchange,
+                    tradingsymbol: position.tradingsymbol,
+                    product: position.product,
+                    transaction_type,
+                    quantity,
+                    outcome,
+                }
+            }
+        });
+
+        Ok(join_all(tasks).await)
+    }
+
+    /// Breakeven price of an open position including brokerage, STT, GST and the other
+    /// charges of its trades and of exiting at the last price, from the charges API.
+    /// None for a flat position.
+    pub async fn get_position_breakeven(
+        &self,
+        position: &Position,
+    ) -> Result<Option<f64>, KiteConnectError> {
+        if position.quantity == 0 {
+            return Ok(None);
+        }
+        let charges: Charges = self
+            .get_order_charges(GetChargesParams {
+                order_params: position.charges_params(true),
+            })
+            .await?
+            .iter()
+            .map(|order| &order.charges)
+            .sum();
+        Ok(position.breakeven(&charges))
+    }
+
+    /// Returns the `EXCHANGE:SYMBOL` keys that have no depth on the side an exit would hit.
+    async fn illiquid_instruments(
+        &self,
+        candidates: &[(Position, OrderParams)],
+    ) -> Result<Vec<String>, KiteConnectError> {
+        let keys: Vec<String> = candidates
+            .iter()
+            .map(|(p, _)| format!("{}:{}", p.exchange, p.tradingsymbol))
+            .collect();
+        let key_refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+        let quotes = self.get_quote(&key_refs).await?;
+
+        Ok(candidates
+            .iter()
+            .zip(keys)
+            .filter(|((position, _), key)| {
+                let Some(quote) = quotes.get(key) else {
+                    return true;
+                };
+                let side = if position.quantity > 0 {
+                    &quote.depth.buy
+                } else {
+                    &quote.depth.sell
+                };
+                side.iter().all(|level| level.quantity == 0)
+            })
+            .map(|(_, key)| key)
+            .collect())
+    }
+
+    /// Sells delivery holdings after checking them with [`check_sellable`].
+    ///
+    /// Sets the transaction type to SELL and the product to CNC unless one is given.
+    /// `tradingsymbol` and `quantity` are required.
+    pub async fn sell_holding(
+        &self,
+        mut order: OrderParams,
+    ) -> Result<OrderResponse, KiteConnectError> {
+        let (Some(tradingsymbol), Some(quantity)) =
+            (order.tradingsymbol.as_deref(), order.quantity)
+        else {
+            return Err(KiteConnectError::invalid_params(
+                "tradingsymbol and quantity are required",
+            ));
+        };
+        let holdings = self.get_holdings().await?;
+        check_sellable(&holdings, tradingsymbol, quantity)?;
+
+        order.transaction_type = Some(TransactionType::Sell.into());
+        order.product.get_or_insert_with(|| Product::Cnc.into());
+        self.place_order(Variety::Regular.as_str(), order).await
+    }
+
     /// Convert position's product type
     pub async fn convert_position(
         &self,
@@ -0,0 +1,501 @@
+//! Incremental holdings and positions updates.
+//!
+//! A [`PortfolioTracker`] keeps the quantity of every holding and net position and turns
+//! REST refreshes and order fills into [`PortfolioChange`]s, so a UI can patch the rows
+//! that changed instead of re-rendering the full lists. A refresh diffs `get_holdings` and
+//! `get_positions` against the tracked quantities; a fill from a ticker order update moves
+//! the position right away, without waiting for the next refresh:
+//!
+//! ```ignore
+//! let tracker = PortfolioTracker::new();
+//! let changes = tracker.changes();
+//! tracker.refresh(&kite).await?;
+//!
+//! // Next to the ticker loop
+//! tracker.on_event(&ticker_event);
+//!
+//! while let Ok(change) = changes.recv().await {
+//!     if let PortfolioChange::PositionClosed(position) = change {
+//!         table.remove(&position.tradingsymbol);
+//!     }
+//! }
+//! ```
+
+use async_channel::{Receiver, Sender};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::sync::{Arc, Mutex};
+
+use crate::KiteConnect;
+use crate::labels::TransactionType;
+use crate::models::{self, KiteConnectError};
+use crate::orders::Order;
+use crate::portfolio::{Holding, Position};
+use crate::risk::DailyLossLimiter;
+use crate::ticker::TickerEvent;
+
+/// QuantityUpdate is a holding or net position whose quantity moved. `before` is 0 for a
+/// new one and `after` is 0 for a closed one. Holding quantities include T1 shares.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuantityUpdate {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub product: String,
+    pub instrument_token: u32,
+    pub before: i32,
+    pub after: i32,
+}
+
+impl QuantityUpdate {
+    pub fn delta(&self) -> i32 {
+        self.after - self.before
+    }
+}
+
+/// PortfolioChange is one change to the tracked holdings or net positions.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", content = "data", rename_all = "snake_case")]
+pub enum PortfolioChange {
+    /// A net position went from flat to a quantity.
+    PositionOpened(QuantityUpdate),
+    /// An open net position grew, shrank or flipped sides.
+    QuantityChanged(QuantityUpdate),
+    /// A net position went flat or left the positions list.
+    PositionClosed(QuantityUpdate),
+    HoldingAdded(QuantityUpdate),
+    HoldingChanged(QuantityUpdate),
+    /// A holding was sold off or left the holdings list.
+    HoldingRemoved(QuantityUpdate),
+}
+
+impl PortfolioChange {
+    pub fn update(&self) -> &QuantityUpdate {
+        match self {
+            PortfolioChange::PositionOpened(update)
+            | PortfolioChange::QuantityChanged(update)
+            | PortfolioChange::PositionClosed(update)
+            | PortfolioChange::HoldingAdded(update)
+            | PortfolioChange::HoldingChanged(update)
+            | PortfolioChange::HoldingRemoved(update) => update,
+        }
+    }
+
+    pub fn is_position(&self) -> bool {
+        matches!(
+            self,
+            PortfolioChange::PositionOpened(_)
+                | PortfolioChange::QuantityChanged(_)
+                | PortfolioChange::PositionClosed(_)
+        )
+    }
+}
+
+/// TrackedQuantity is the tracked quantity of one holding or net position.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TrackedQuantity {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub product: String,
+    pub instrument_token: u32,
+    pub quantity: i32,
+}
+
+type Key = (String, String, String);
+
+/// Changes kept for [`PortfolioTracker::changes`]; older ones are dropped when nobody
+/// reads them.
+pub const CHANGE_CAPACITY: usize = 1024;
+
+#[derive(Debug, Default)]
+struct TrackerState {
+    holdings: BTreeMap<Key, (u32, i32)>,
+    positions: BTreeMap<Key, (u32, i32)>,
+    // Filled quantity of each order already counted in the positions
+    filled: HashMap<String, f64>,
+    // Refreshes waiting on the API, and the fills applied meanwhile
+    refreshing: usize,
+    recent_fills: Vec<RecentFill>,
+}
+
+// A fill applied while a refresh was fetching, which its snapshot may not include
+#[derive(Debug, Clone)]
+struct RecentFill {
+    order_id: String,
+    key: Key,
+    instrument_token: u32,
+    buy: bool,
+    from: f64,
+    to: f64,
+}
+
+// The parts of an order update that move a position
+struct Fill<'a> {
+    order_id: &'a str,
+    exchange: &'a str,
+    tradingsymbol: &'a str,
+    product: &'a str,
+    instrument_token: u32,
+    buy: bool,
+    filled_quantity: f64,
+}
+
+impl<'a> From<&'a Order> for Fill<'a> {
+    fn from(order: &'a Order) -> Self {
+        Self {
+            order_id: &order.order_id,
+            exchange: &order.exchange,
+            tradingsymbol: &order.tradingsymbol,
+            product: &order.product,
+            instrument_token: order.instrument_token,
+            buy: order.transaction_type == TransactionType::Buy.as_str(),
+            filled_quantity: order.filled_quantity,
+        }
+    }
+}
+
+impl<'a> From<&'a models::Order> for Fill<'a> {
+    fn from(order: &'a models::Order) -> Self {
+        Self {
+            order_id: &order.order_id,
+            exchange: &order.exchange,
+            tradingsymbol: &order.tradingsymbol,
+            product: &order.product,
+            instrument_token: order.instrument_token,
+            buy: order.transaction_type == TransactionType::Buy.as_str(),
+            filled_quantity: order.filled_quantity,
+        }
+    }
+}
+
+/// PortfolioTracker publishes [`PortfolioChange`]s for holdings and net positions; see the
+/// [module docs](self).
+///
+/// Like the ticker, changes go to a channel that keeps the latest [`CHANGE_CAPACITY`]
+/// unread ones; every receiver from [`changes`](Self::changes) shares it, so each change
+/// is delivered to one of them. The tracker is cheap to clone and clones share their
+/// state.
+#[derive(Clone)]
+pub struct PortfolioTracker {
+    state: Arc<Mutex<TrackerState>>,
+    sender: Sender<PortfolioChange>,
+    receiver: Receiver<PortfolioChange>,
+    loss_limiter: Option<DailyLossLimiter>,
+}
+
+impl Default for PortfolioTracker {
+    fn default() -> Self {
+        let (sender, receiver) = async_channel::bounded(CHANGE_CAPACITY);
+        Self {
+            state: Arc::default(),
+            sender,
+            receiver,
+            loss_limiter: None,
+        }
+    }
+}
+
+impl PortfolioTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Feed the day PnL, the M2M of the net positions, to `limiter` on every refresh. A
+    /// refresh that breaches it squares off through the refreshing client if the limiter
+    /// is set to. Pass the limiter set on the client, which shares its state, so a breach
+    /// also blocks the client's new orders.
+    pub fn feed(mut self, limiter: &DailyLossLimiter) -> Self {
+        self.loss_limiter = Some(limiter.clone());
+        self
+    }
+
+    /// Receiver of the changes found by refreshes and fills from now on.
+    pub fn changes(&self) -> Receiver<PortfolioChange> {
+        self.receiver.clone()
+    }
+
+    /// Fetch holdings, positions and the order book and publish what changed since the
+    /// last refresh or fill. The first refresh reports everything as added or opened.
+    ///
+    /// The order book marks the fills the positions already include, so an order update
+    /// for one of them arriving after the refresh does not move the position again. Fills
+    /// applied while the refresh was fetching are added back to positions fetched before
+    /// them.
+    pub async fn refresh(
+        &self,
+        kite: &KiteConnect,
+    ) -> Result<Vec<PortfolioChange>, KiteConnectError> {
+        let start = {
+            let mut state = self.lock();
+            state.refreshing += 1;
+            state.recent_fills.len()
+        };
+        let fetched =
+            futures_util::try_join!(kite.get_holdings(), kite.get_positions(), kite.get_orders());
+
+        let (changes, pnl) = {
+            let mut state = self.lock();
+            let recent = state.recent_fills[start..].to_vec();
+            state.refreshing -= 1;
+            if state.refreshing == 0 {
+                state.recent_fills.clear();
+            }
+            let (holdings, positions, orders) = fetched?;
+
+            let pnl: f64 = positions.net.iter().map(|p| p.m2m).sum();
+            let mut positions = position_quantities(&positions.net);
+            let mut in_book = HashMap::new();
+            for order in &orders {
+                in_book.insert(order.order_id.as_str(), order.filled_quantity);
+                state
+                    .filled
+                    .insert(order.order_id.clone(), order.filled_quantity);
+            }
+            for fill in recent {
+                let booked = in_book.get(fill.order_id.as_str()).copied().unwrap_or(0.0);
+                let missing = (fill.to - fill.from.max(booked)).round() as i32;
+                if missing > 0 {
+                    let entry = positions
+                        .entry(fill.key.clone())
+                        .or_insert((fill.instrument_token, 0));
+                    entry.1 += if fill.buy { missing } else { -missing };
+                    if entry.1 == 0 {
+                        positions.remove(&fill.key);
+                    }
+                }
+                let counted = state.filled.entry(fill.order_id).or_insert(0.0);
+                *counted = counted.max(fill.to);
+            }
+            let changes = self.replace(state, holding_quantities(&holdings), positions);
+            (changes, pnl)
+        };
+
+        if let Some(limiter) = &self.loss_limiter {
+            kite.feed_loss_limiter(limiter, pnl).await?;
+        }
+        Ok(changes)
+    }
+
+    /// Publish the changes from the tracked quantities to `holdings` and net `positions`,
+    /// which replace them.
+    pub fn update(&self, holdings: &[Holding], positions: &[Position]) -> Vec<PortfolioChange> {
+        self.replace(
+            self.lock(),
+            holding_quantities(holdings),
+            position_quantities(positions),
+        )
+    }
+
+    fn replace(
+        &self,
+        mut state: std::sync::MutexGuard<'_, TrackerState>,
+        holdings: BTreeMap<Key, (u32, i32)>,
+        positions: BTreeMap<Key, (u32, i32)>,
+    ) -> Vec<PortfolioChange> {
+        let mut changes = diff(&state.holdings, &holdings, holding_change);
+        changes.extend(diff(&state.positions, &positions, position_change));
+        state.holdings = holdings;
+        state.positions = positions;
+        drop(state);
+        self.publish(changes)
+    }
+
+    /// Move the net position by the part of `order`'s fill not counted yet.
+    pub fn on_order_update(&self, order: &Order) -> Option<PortfolioChange> {
+        self.apply_fill(order.into())
+    }
+
+    /// Apply the order update carried by a ticker event; other events are ignored.
+    pub fn on_event(&self, event: &TickerEvent) -> Option<PortfolioChange> {
+        match event {
+            TickerEvent::OrderUpdate(order) => self.apply_fill(order.into()),
+            _ => None,
+        }
+    }
+
+    /// Tracked holdings, sorted by exchange and symbol.
+    pub fn holdings(&self) -> Vec<TrackedQuantity> {
+        tracked(&self.lock().holdings)
+    }
+
+    /// Tracked net positions that are not flat, sorted by exchange and symbol.
+    pub fn positions(&self) -> Vec<TrackedQuantity> {
+        tracked(&self.lock().positions)
+    }
+
+    /// Quantity of `tradingsymbol` held, including T1 shares, across exchanges.
+    pub fn holding_quantity(&self, tradingsymbol: &str) -> i32 {
+        self.lock()
+            .holdings
+            .iter()
+            .filter(|((_, s, _), _)| s == tradingsymbol)
+            .map(|(_, (_, quantity))| quantity)
+            .sum()
+    }
+
+    /// Net quantity of `tradingsymbol` on `exchange` across products.
+    pub fn position_quantity(&self, exchange: &str, tradingsymbol: &str) -> i32 {
+        self.lock()
+            .positions
+            .iter()
+            .filter(|((e, s, _), _)| e == exchange && s == tradingsymbol)
+            .map(|(_, (_, quantity))| quantity)
+            .sum()
+    }
+
+    fn apply_fill(&self, fill: Fill<'_>) -> Option<PortfolioChange> {
+        let mut state = self.lock();
+        let counted = state.filled.get(fill.order_id).copied().unwrap_or(0.0);
+        if fill.filled_quantity <= counted {
+            return None;
+        }
+        state
+            .filled
+            .insert(fill.order_id.to_string(), fill.filled_quantity);
+        let mut delta = (fill.filled_quantity - counted).round() as i32;
+        if !fill.buy {
+            delta = -delta;
+        }
+
+        let key = (
+            fill.exchange.to_string(),
+            fill.tradingsymbol.to_string(),
+            fill.product.to_string(),
+        );
+        if state.refreshing > 0 {
+            state.recent_fills.push(RecentFill {
+                order_id: fill.order_id.to_string(),
+                key: key.clone(),
+                instrument_token: fill.instrument_token,
+                buy: fill.buy,
+                from: counted,
+                to: fill.filled_quantity,
+            });
+        }
+        let entry = state
+            .positions
+            .entry(key.clone())
+            .or_insert((fill.instrument_token, 0));
+        let before = entry.1;
+        entry.1 += delta;
+        let after = entry.1;
+        if after == 0 {
+            state.positions.remove(&key);
+        }
+        drop(state);
+
+        let change = position_change(key, fill.instrument_token, before, after)?;
+        self.publish(vec![change]).pop()
+    }
+
+    fn publish(&self, changes: Vec<PortfolioChange>) -> Vec<PortfolioChange> {
+        for change in &changes {
+            // The tracker holds a receiver, so the channel never closes; when full, the
+            // oldest change is dropped
+            let _ = self.sender.force_send(change.clone());
+        }
+        changes
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, TrackerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn holding_quantities(holdings: &[Holding]) -> BTreeMap<Key, (u32, i32)> {
+    holdings
+        .iter()
+        .map(|h| {
+            let key = (
+                h.exchange.clone(),
+                h.tradingsymbol.clone(),
+                h.product.clone(),
+            );
+            (key, (h.instrument_token, h.quantity + h.t1_quantity))
+        })
+        .collect()
+}
+
+fn position_quantities(positions: &[Position]) -> BTreeMap<Key, (u32, i32)> {
+    positions
+        .iter()
+        .map(|p| {
+            let key = (
+                p.exchange.clone(),
+                p.tradingsymbol.clone(),
+                p.product.clone(),
+            );
+            (key, (p.instrument_token, p.quantity))
+        })
+        .collect()
+}
+
+fn diff(
+    before: &BTreeMap<Key, (u32, i32)>,
+    after: &BTreeMap<Key, (u32, i32)>,
+    change: fn(Key, u32, i32, i32) -> Option<PortfolioChange>,
+) -> Vec<PortfolioChange> {
+    let mut changes = Vec::new();
+    for (key, (token, quantity)) in after {
+        let previous = before.get(key).map_or(0, |(_, q)| *q);
+        changes.extend(change(key.clone(), *token, previous, *quantity));
+    }
+    for (key, (token, quantity)) in before {
+        if !after.contains_key(key) {
+            changes.extend(change(key.clone(), *token, *quantity, 0));
+        }
+    }
+    changes
+}
+
+fn holding_change(key: Key, token: u32, before: i32, after: i32) -> Option<PortfolioChange> {
+    let update = quantity_update(key, token, before, after)?;
+    Some(if before == 0 {
+        PortfolioChange::HoldingAdded(update)
+    } else if after == 0 {
+        PortfolioChange::HoldingRemoved(update)
+    } else {
+        PortfolioChange::HoldingChanged(update)
+    })
+}
+
+fn position_change(key: Key, token: u32, before: i32, after: i32) -> Option<PortfolioChange> {
+    let update = quantity_update(key, token, before, after)?;
+    Some(if before == 0 {
+        PortfolioChange::PositionOpened(update)
+    } else if after == 0 {
+        PortfolioChange::PositionClosed(update)
+    } else {
+        PortfolioChange::QuantityChanged(update)
+    })
+}
+
+fn quantity_update(key: Key, token: u32, before: i32, after: i32) -> Option<QuantityUpdate> {
+    if before == after {
+        return None;
+    }
+    let (exchange, tradingsymbol, product) = key;
+    Some(QuantityUpdate {
+        exchange,
+        tradingsymbol,
+        product,
+        instrument_token: token,
+        before,
+        after,
+    })
+}
+
+fn tracked(quantities: &BTreeMap<Key, (u32, i32)>) -> Vec<TrackedQuantity> {
+    quantities
+        .iter()
+        .filter(|(_, (_, quantity))| *quantity != 0)
+        .map(
+            |((exchange, tradingsymbol, product), (token, quantity))| TrackedQuantity {
+                exchange: exchange.clone(),
+                tradingsymbol: tradingsymbol.clone(),
+                product: product.clone(),
+                instrument_token: *token,
+                quantity: *quantity,
+            },
+        )
+        .collect()
+}
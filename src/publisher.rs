@@ -0,0 +1,177 @@
+//! Kite Publisher baskets.
+//!
+//! Kite Publisher lets a web page hand a basket of orders to Kite, where the user logs in,
+//! reviews and places them. The page only needs the app's API key: orders are placed by
+//! the user in Kite, so the app never holds an access token or order permissions.
+//!
+//! A basket is posted as a form with two fields, `api_key` and `data`, the latter a JSON
+//! array of orders. There is no signature; Kite identifies the app by its API key.
+//! [`Basket`] builds that payload server side and can render it as a self-submitting
+//! HTML form:
+//!
+//! ```ignore
+//! let mut basket = kite.publisher_basket();
+//! basket.add(Variety::Regular, order_params)?;
+//! let html = basket.html_form()?;
+//! ```
+
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::KiteConnect;
+use crate::constants::{Endpoints, app_constants::KITE_BASE_URL};
+use crate::labels::Variety;
+use crate::models::KiteConnectError;
+use crate::orders::OrderParams;
+
+/// BasketOrder is one order of a publisher basket.
+#[derive(Debug, Clone, Serialize)]
+pub struct BasketOrder {
+    pub variety: String,
+    #[serde(flatten)]
+    pub params: OrderParams,
+    /// Prevent the user from editing the order in Kite before placing it.
+    pub readonly: bool,
+}
+
+/// Basket is a list of orders handed to Kite through Kite Publisher.
+#[derive(Debug, Clone)]
+pub struct Basket {
+    api_key: String,
+    orders: Vec<BasketOrder>,
+}
+
+impl Basket {
+    pub fn new(api_key: &str) -> Self {
+        Self {
+            api_key: api_key.to_owned(),
+            orders: Vec::new(),
+        }
+    }
+
+    /// Add an editable order. `exchange`, `tradingsymbol`, `transaction_type` and a
+    /// positive `quantity` are required.
+    pub fn add(
+        &mut self,
+        variety: Variety,
+        params: OrderParams,
+    ) -> Result<&mut Self, KiteConnectError> {
+        self.push(variety, params, false)
+    }
+
+    /// Add an order the user cannot edit before placing it.
+    pub fn add_readonly(
+        &mut self,
+        variety: Variety,
+        params: OrderParams,
+    ) -> Result<&mut Self, KiteConnectError> {
+        self.push(variety, params, true)
+    }
+
+    fn push(
+        &mut self,
+        variety: Variety,
+        params: OrderParams,
+        readonly: bool,
+    ) -> Result<&mut Self, KiteConnectError> {
+        let missing = [
+            ("exchange", params.exchange.is_none()),
+            ("tradingsymbol", params.tradingsymbol.is_none()),
+            ("transaction_type", params.transaction_type.is_none()),
+            ("quantity", params.quantity.is_none_or(|q| q <= 0)),
+        ];
+        if let Some((field, _)) = missing.iter().find(|(_, missing)| *missing) {
+            return Err(KiteConnectError::invalid_params(format!(
+                "basket order needs {}",
+                field
+            )));
+        }
+
+        self.orders.push(BasketOrder {
+            variety: variety.as_str().to_string(),
+            params,
+            readonly,
+        });
+        Ok(self)
+    }
+
+    pub fn orders(&self) -> &[BasketOrder] {
+        &self.orders
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.orders.is_empty()
+    }
+
+    /// The `data` field: the orders as a JSON array, without unset fields.
+    pub fn payload(&self) -> Result<String, KiteConnectError> {
+        let mut orders = serde_json::to_value(&self.orders)?;
+        if let Value::Array(orders) = &mut orders {
+            for order in orders.iter_mut() {
+                if let Value::Object(fields) = order {
+                    fields.retain(|_, value| !value.is_null());
+                }
+            }
+        }
+        Ok(orders.to_string())
+    }
+
+    /// URL the basket form is posted to.
+    pub fn url(&self) -> String {
+        format!("{}{}", KITE_BASE_URL, Endpoints::PUBLISHER_BASKET)
+    }
+
+    /// The form fields to post to [`url`](Self::url).
+    pub fn form_fields(&self) -> Result<Vec<(&'static str, String)>, KiteConnectError> {
+        if self.orders.is_empty() {
+            return Err(KiteConnectError::invalid_params("basket has no orders"));
+        }
+        Ok(vec![
+            ("api_key", self.api_key.clone()),
+            ("data", self.payload()?),
+        ])
+    }
+
+    /// An HTML form that posts the basket to Kite as soon as the page loads.
+    pub fn html_form(&self) -> Result<String, KiteConnectError> {
+        let inputs: String = self
+            .form_fields()?
+            .iter()
+            .map(|(name, value)| {
+                format!(
+                    "<input type=\"hidden\" name=\"{}\" value=\"{}\">",
+                    name,
+                    escape_html(value)
+                )
+            })
+            .collect();
+        Ok(format!(
+            "<form id=\"kite-basket\" method=\"post\" action=\"{}\">{}</form>\
+             <script>document.getElementById(\"kite-basket\").submit();</script>",
+            self.url(),
+            inputs
+        ))
+    }
+}
+
+impl KiteConnect {
+    /// An empty publisher basket for this client's API key.
+    pub fn publisher_basket(&self) -> Basket {
+        Basket::new(&self.api_key)
+    }
+}
+
+fn escape_html(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,458 @@
+use chrono::{DateTime, NaiveDate, NaiveTime, Utc};
+use chrono_tz::Asia::Kolkata;
+use std::collections::HashSet;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::{
+    KiteConnect,
+    models::{KiteConnectError, KiteConnectErrorKind, KiteError},
+    order_state::TERMINAL_ORDER_STATUSES,
+    orders::OrderParams,
+    portfolio::{SquareOffParams, SquareOffResult},
+};
+
+/// RiskLimits are client-side guardrails checked before an order is sent to the exchange.
+///
+/// Every limit is optional; an empty `RiskLimits` allows everything.
+#[derive(Debug, Clone, Default)]
+pub struct RiskLimits {
+    pub max_order_value: Option<f64>,
+    pub max_quantity: Option<i32>,
+    pub max_open_orders: Option<usize>,
+    /// Symbols allowed to trade, as `TRADINGSYMBOL` or `EXCHANGE:TRADINGSYMBOL`.
+    /// An empty list allows every symbol not in `deny_symbols`.
+    pub allow_symbols: HashSet<String>,
+    pub deny_symbols: HashSet<String>,
+    /// Window (IST) in which orders may be placed. The end time is exclusive.
+    pub trading_hours: Option<(NaiveTime, NaiveTime)>,
+}
+
+/// RiskViolation describes why an order was blocked by the configured `RiskLimits`.
+#[derive(Debug, Clone, PartialEq)]
+pub enum RiskViolation {
+    MaxOrderValue { value: f64, limit: f64 },
+    MaxQuantity { quantity: i32, limit: i32 },
+    MaxOpenOrders { open: usize, limit: usize },
+    SymbolNotAllowed(String),
+    SymbolDenied(String),
+    OutsideTradingHours { now: NaiveTime },
+    DailyLossLimit { pnl: f64, limit: f64 },
+    /// More orders than a [`LiveRunner`](crate::backtest::LiveRunner) allows per run.
+    MaxOrders { placed: usize, limit: usize },
+}
+
+impl fmt::Display for RiskViolation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RiskViolation::MaxOrderValue { value, limit } => {
+                write!(f, "order value {:.2} exceeds limit {:.2}", value, limit)
+            }
+            RiskViolation::MaxQuantity { quantity, limit } => {
+                write!(f, "order quantity {} exceeds limit {}", quantity, limit)
+            }
+            RiskViolation::MaxOpenOrders { open, limit } => {
+                write!(f, "{} open orders, limit is {}", open, limit)
+            }
+            RiskViolation::SymbolNotAllowed(symbol) => {
+                write!(f, "{} is not in the allowed symbols", symbol)
+            }
+            RiskViolation::SymbolDenied(symbol) => write!(f, "{} is a denied symbol", symbol),
+            RiskViolation::OutsideTradingHours { now } => {
+                write!(f, "{} is outside the allowed trading hours", now)
+            }
+            RiskViolation::DailyLossLimit { pnl, limit } => {
+                write!(
+                    f,
+                    "day PnL {:.2} breached the loss limit of {:.2}",
+                    pnl, limit
+                )
+            }
+            RiskViolation::MaxOrders { placed, limit } => {
+                write!(f, "{} orders placed, limit is {}", placed, limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for RiskViolation {}
+
+impl RiskLimits {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn max_order_value(mut self, value: f64) -> Self {
+        self.max_order_value = Some(value);
+        self
+    }
+
+    pub fn max_quantity(mut self, quantity: i32) -> Self {
+        self.max_quantity = Some(quantity);
+        self
+    }
+
+    pub fn max_open_orders(mut self, count: usize) -> Self {
+        self.max_open_orders = Some(count);
+        self
+    }
+
+    pub fn allow_symbol(mut self, symbol: &str) -> Self {
+        self.allow_symbols.insert(symbol.to_owned());
+        self
+    }
+
+    pub fn deny_symbol(mut self, symbol: &str) -> Self {
+        self.deny_symbols.insert(symbol.to_owned());
+        self
+    }
+
+    pub fn trading_hours(mut self, start: NaiveTime, end: NaiveTime) -> Self {
+        self.trading_hours = Some((start, end));
+        self
+    }
+
+    /// Check an order against the limits.
+    ///
+    /// `order_value` and `open_orders` are only consulted when the matching limit is set;
+    /// pass None when they are unknown. `now` is the current IST wall-clock time.
+    pub fn check(
+        &self,
+        params: &OrderParams,
+        order_value: Option<f64>,
+        open_orders: Option<usize>,
+        now: NaiveTime,
+    ) -> Result<(), RiskViolation> {
+        if let Some((start, end)) = self.trading_hours {
+            if now < start || now >= end {
+                return Err(RiskViolation::OutsideTradingHours { now });
+            }
+        }
+
+        let symbol = params.tradingsymbol.clone().unwrap_or_default();
+        let key = format!(
+            "{}:{}",
+            params.exchange.as_deref().unwrap_or_default(),
+            symbol
+        );
+        let listed = |set: &HashSet<String>| set.contains(&symbol) || set.contains(&key);
+
+        if listed(&self.deny_symbols) {
+            return Err(RiskViolation::SymbolDenied(key));
+        }
+        if !self.allow_symbols.is_empty() && !listed(&self.allow_symbols) {
+            return Err(RiskViolation::SymbolNotAllowed(key));
+        }
+
+        if let (Some(limit), Some(quantity)) = (self.max_quantity, params.quantity) {
+            if quantity > limit {
+                return Err(RiskViolation::MaxQuantity { quantity, limit });
+            }
+        }
+
+        if let (Some(limit), Some(value)) = (self.max_order_value, order_value) {
+            if value > limit {
+                return Err(RiskViolation::MaxOrderValue { value, limit });
+            }
+        }
+
+        if let (Some(limit), Some(open)) = (self.max_open_orders, open_orders) {
+            if open >= limit {
+                return Err(RiskViolation::MaxOpenOrders { open, limit });
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// DailyLossLimiter blocks new orders once the day's PnL falls below `-max_loss`.
+///
+/// The limiter is fed PnL updates, either from `KiteConnect::refresh_daily_loss`, from a
+/// live PnL source via `KiteConnect::feed_daily_pnl`, or from the refreshes of a
+/// [`PortfolioTracker`](crate::PortfolioTracker) set up with `feed`. Clones share the same
+/// state, and the breach resets automatically when the IST trading day changes.
+#[derive(Debug, Clone)]
+pub struct DailyLossLimiter {
+    max_loss: f64,
+    square_off: Option<SquareOffParams>,
+    state: Arc<Mutex<LossState>>,
+}
+
+#[derive(Debug)]
+struct LossState {
+    day: NaiveDate,
+    pnl: f64,
+    breached: bool,
+}
+
+impl DailyLossLimiter {
+    /// `max_loss` is the largest tolerated loss for the day, as a positive amount.
+    pub fn new(max_loss: f64) -> Self {
+        Self {
+            max_loss: max_loss.abs(),
+            square_off: None,
+            state: Arc::new(Mutex::new(LossState {
+                day: ist_now_datetime().date_naive(),
+                pnl: 0.0,
+                breached: false,
+            })),
+        }
+    }
+
+    /// Flatten positions with `square_off_all` when the limit is first breached.
+    pub fn square_off_on_breach(mut self, params: SquareOffParams) -> Self {
+        self.square_off = Some(params);
+        self
+    }
+
+    pub fn max_loss(&self) -> f64 {
+        self.max_loss
+    }
+
+    pub fn pnl(&self) -> f64 {
+        self.lock().pnl
+    }
+
+    pub fn is_breached(&self) -> bool {
+        self.lock().breached
+    }
+
+    /// Record the latest day PnL. Returns true only for the update that breaches the limit.
+    pub fn update(&self, pnl: f64) -> bool {
+        let mut state = self.lock();
+        state.pnl = pnl;
+        if !state.breached && pnl <= -self.max_loss {
+            state.breached = true;
+            return true;
+        }
+        false
+    }
+
+    /// Clear a breach manually, e.g. after the limit was raised out of band.
+    pub fn reset(&self) {
+        let mut state = self.lock();
+        state.pnl = 0.0;
+        state.breached = false;
+    }
+
+    pub fn check(&self) -> Result<(), RiskViolation> {
+        let state = self.lock();
+        if state.breached {
+            return Err(RiskViolation::DailyLossLimit {
+                pnl: state.pnl,
+                limit: self.max_loss,
+            });
+        }
+        Ok(())
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, LossState> {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let today = ist_now_datetime().date_naive();
+        if state.day != today {
+            *state = LossState {
+                day: today,
+                pnl: 0.0,
+                breached: false,
+            };
+        }
+        state
+    }
+}
+
+pub(crate) fn ist_now_datetime() -> DateTime<chrono_tz::Tz> {
+    let since_epoch = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default();
+    DateTime::<Utc>::from_timestamp(since_epoch.as_secs() as i64, since_epoch.subsec_nanos())
+        .unwrap_or_default()
+        .with_timezone(&Kolkata)
+}
+
+/// Current wall-clock time in IST.
+pub(crate) fn ist_now() -> NaiveTime {
+    ist_now_datetime().time()
+}
+
+impl KiteConnect {
+    /// Enforce the configured `RiskLimits` for an order about to be placed, and the
+    /// account's capabilities when they are known.
+    pub(crate) async fn enforce_risk_limits(
+        &self,
+        params: &OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        self.enforce_limits(params, true).await
+    }
+
+    /// Enforce the same limits on an order as it will be after a modification. The order
+    /// is already open, so `max_open_orders` does not apply.
+    pub(crate) async fn enforce_modify_limits(
+        &self,
+        params: &OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        self.enforce_limits(params, false).await
+    }
+
+    async fn enforce_limits(
+        &self,
+        params: &OrderParams,
+        new_order: bool,
+    ) -> Result<(), KiteConnectError> {
+        if let Some(error) = self.trading_block() {
+            return Err(KiteConnectError::new(KiteConnectErrorKind::TradingBlocked(
+                Box::new(error),
+            )));
+        }
+        if let Some(capabilities) = self.known_capabilities() {
+            capabilities.check_order(params)?;
+        }
+        if let Some(limiter) = &self.loss_limiter {
+            limiter.check()?;
+        }
+
+        match &self.risk_limits {
+            Some(limits) if new_order => self.check_risk_limits(limits, params).await,
+            Some(limits) => self.check_modify_limits(limits, params).await,
+            None => Ok(()),
+        }
+    }
+
+    /// Check a modified order against `limits`, leaving out `max_open_orders`.
+    pub(crate) async fn check_modify_limits(
+        &self,
+        limits: &RiskLimits,
+        params: &OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        let limits = RiskLimits {
+            max_open_orders: None,
+            ..limits.clone()
+        };
+        self.check_risk_limits(&limits, params).await
+    }
+
+    /// Check an order against `limits`, looking up the LTP for priceless orders and the
+    /// order book only when the corresponding limits are set.
+    pub(crate) async fn check_risk_limits(
+        &self,
+        limits: &RiskLimits,
+        params: &OrderParams,
+    ) -> Result<(), KiteConnectError> {
+        let order_value = match (limits.max_order_value, params.quantity) {
+            (Some(_), Some(quantity)) => {
+                let price = match params.price.or(params.trigger_price) {
+                    Some(price) if price > 0.0 => price,
+                    _ => self.last_price_for(params).await?,
+                };
+                Some(price * quantity as f64)
+            }
+            _ => None,
+        };
+
+        let open_orders = match limits.max_open_orders {
+            Some(_) => Some(
+                self.get_orders()
+                    .await?
+                    .iter()
+                    .filter(|o| !TERMINAL_ORDER_STATUSES.contains(&o.status.as_str()))
+                    .count(),
+            ),
+            None => None,
+        };
+
+        limits
+            .check(params, order_value, open_orders, ist_now())
+            .map_err(KiteConnectError::from)
+    }
+
+    /// The kill switch or blocked account rejection that halted order placement, if any.
+    ///
+    /// Once any request is rejected this way, `place_order` fails with
+    /// `TradingBlocked` without contacting the API until [`clear_trading_block`] is
+    /// called. Exits placed internally, such as square-offs, are not held back.
+    ///
+    /// [`clear_trading_block`]: Self::clear_trading_block
+    pub fn trading_block(&self) -> Option<KiteError> {
+        self.trading_block
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone()
+    }
+
+    /// Resume order placement, e.g. after the kill switch was turned off in Console.
+    pub fn clear_trading_block(&self) {
+        *self.trading_block.lock().unwrap_or_else(|e| e.into_inner()) = None;
+    }
+
+    pub(crate) fn block_trading(&self, error: KiteError) {
+        log::error!(
+            "trading blocked, halting order placement: {}",
+            error.message
+        );
+        *self.trading_block.lock().unwrap_or_else(|e| e.into_inner()) = Some(error);
+    }
+
+    /// Feed the day PnL from a live source into the configured `DailyLossLimiter`.
+    ///
+    /// On the update that breaches the limit, positions are squared off if the limiter
+    /// was configured to do so and the results are returned.
+    pub async fn feed_daily_pnl(
+        &self,
+        pnl: f64,
+    ) -> Result<Option<Vec<SquareOffResult>>, KiteConnectError> {
+        match &self.loss_limiter {
+            Some(limiter) => self.feed_loss_limiter(limiter, pnl).await,
+            None => Ok(None),
+        }
+    }
+
+    /// Feed the day PnL into `limiter`, squaring off on the update that breaches it.
+    pub(crate) async fn feed_loss_limiter(
+        &self,
+        limiter: &DailyLossLimiter,
+        pnl: f64,
+    ) -> Result<Option<Vec<SquareOffResult>>, KiteConnectError> {
+        if !limiter.update(pnl) {
+            return Ok(None);
+        }
+
+        log::warn!(
+            "daily loss limit of {:.2} breached at PnL {:.2}, blocking new orders",
+            limiter.max_loss(),
+            pnl
+        );
+        match &limiter.square_off {
+            Some(params) => self.square_off_all(params.clone()).await.map(Some),
+            None => Ok(None),
+        }
+    }
+
+    /// Recompute the day PnL as the M2M of net positions and feed it to the limiter.
+    pub async fn refresh_daily_loss(
+        &self,
+    ) -> Result<Option<Vec<SquareOffResult>>, KiteConnectError> {
+        if self.loss_limiter.is_none() {
+            return Ok(None);
+        }
+        let positions = self.get_positions().await?;
+        let pnl = positions.net.iter().map(|p| p.m2m).sum();
+        self.feed_daily_pnl(pnl).await
+    }
+
+    pub(crate) async fn last_price_for(
+        &self,
+        params: &OrderParams,
+    ) -> Result<f64, KiteConnectError> {
+        let key = format!(
+            "{}:{}",
+            params.exchange.as_deref().unwrap_or_default(),
+            params.tradingsymbol.as_deref().unwrap_or_default()
+        );
+        self.get_ltp(&[key.as_str()])
+            .await?
+            .get(&key)
+            .map(|q| q.last_price)
+            .ok_or_else(|| KiteConnectError::other(format!("no LTP for {}", key)))
+    }
+}
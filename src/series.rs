@@ -0,0 +1,189 @@
+//! In-memory OHLC series for charting.
+//!
+//! [`OhlcSeries`] keeps candles per `(instrument_token, interval)`, keyed by candle start
+//! time. Backfilled history and live candles can be fed in any order and with overlaps;
+//! reads always come back sorted and deduplicated, with gaps annotated.
+
+use chrono::{DateTime, Datelike, Utc, Weekday};
+use chrono_tz::Asia::Kolkata;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use web_time::Duration;
+
+use crate::markets::HistoricalData;
+
+/// Length of a Kite historical data interval (`minute`, `5minute`, `day`, ...).
+pub fn interval_duration(interval: &str) -> Option<Duration> {
+    let minutes = match interval {
+        "minute" => 1,
+        "day" => 24 * 60,
+        other => other.strip_suffix("minute")?.parse().ok()?,
+    };
+    Some(Duration::from_secs(minutes * 60))
+}
+
+/// SeriesCandle is a candle as returned by [`OhlcSeries::candles`].
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SeriesCandle {
+    #[serde(flatten)]
+    pub candle: HistoricalData,
+    /// Candles missing between the previous candle and this one. Always 0 for the first
+    /// candle, across overnight breaks for intraday intervals, and across weekends for
+    /// `day`.
+    pub missing_before: u32,
+}
+
+#[derive(Debug, Clone, Default)]
+struct Series {
+    candles: BTreeMap<i64, HistoricalData>,
+}
+
+/// OhlcSeries stores sorted, deduplicated candles per instrument and interval.
+///
+/// Candles with the same start time replace each other, so re-sending an in-progress live
+/// candle updates it and overlapping backfills do not create duplicates. Candles with a
+/// null timestamp are ignored.
+#[derive(Debug, Clone, Default)]
+pub struct OhlcSeries {
+    series: HashMap<(u32, String), Series>,
+    max_len: Option<usize>,
+}
+
+impl OhlcSeries {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Keep at most `max_len` candles per series, dropping the oldest first.
+    pub fn with_max_len(mut self, max_len: usize) -> Self {
+        self.max_len = Some(max_len.max(1));
+        self
+    }
+
+    /// Insert or update a single candle, typically from a live candle builder.
+    pub fn append(&mut self, instrument_token: u32, interval: &str, candle: HistoricalData) {
+        self.extend(instrument_token, interval, std::iter::once(candle));
+    }
+
+    /// Merge a batch of candles, typically a `get_historical_data` backfill.
+    pub fn extend<I>(&mut self, instrument_token: u32, interval: &str, candles: I)
+    where
+        I: IntoIterator<Item = HistoricalData>,
+    {
+        let series = self
+            .series
+            .entry((instrument_token, interval.to_owned()))
+            .or_default();
+        for candle in candles {
+            if let Some(ts) = candle.date.as_datetime() {
+                series.candles.insert(ts.timestamp(), candle);
+            }
+        }
+
+        if let Some(max_len) = self.max_len {
+            while series.candles.len() > max_len {
+                series.candles.pop_first();
+            }
+        }
+    }
+
+    /// Sorted candles for a series, with gap annotations.
+    pub fn candles(&self, instrument_token: u32, interval: &str) -> Vec<SeriesCandle> {
+        self.range(
+            instrument_token,
+            interval,
+            DateTime::<Utc>::MIN_UTC,
+            DateTime::<Utc>::MAX_UTC,
+        )
+    }
+
+    /// Sorted candles starting within `[from, to]`, with gap annotations.
+    pub fn range(
+        &self,
+        instrument_token: u32,
+        interval: &str,
+        from: DateTime<Utc>,
+        to: DateTime<Utc>,
+    ) -> Vec<SeriesCandle> {
+        let Some(series) = self.get(instrument_token, interval) else {
+            return Vec::new();
+        };
+        let step = interval_duration(interval);
+
+        let mut previous: Option<DateTime<Utc>> = None;
+        series
+            .candles
+            .range(from.timestamp()..=to.timestamp())
+            .map(|(&ts, candle)| {
+                let start = DateTime::from_timestamp(ts, 0).unwrap_or_default();
+                let missing_before = match (previous, step) {
+                    (Some(prev), Some(step)) => missing_between(prev, start, step, interval),
+                    _ => 0,
+                };
+                previous = Some(start);
+                SeriesCandle {
+                    candle: candle.clone(),
+                    missing_before,
+                }
+            })
+            .collect()
+    }
+
+    /// The most recent candle of a series.
+    pub fn latest(&self, instrument_token: u32, interval: &str) -> Option<&HistoricalData> {
+        self.get(instrument_token, interval)?
+            .candles
+            .last_key_value()
+            .map(|(_, candle)| candle)
+    }
+
+    /// Start time of the most recent candle, i.e. where the next backfill should resume.
+    pub fn last_timestamp(&self, instrument_token: u32, interval: &str) -> Option<DateTime<Utc>> {
+        self.latest(instrument_token, interval)?.date.as_datetime()
+    }
+
+    pub fn len(&self, instrument_token: u32, interval: &str) -> usize {
+        self.get(instrument_token, interval)
+            .map_or(0, |series| series.candles.len())
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.series.values().all(|series| series.candles.is_empty())
+    }
+
+    /// Drop a series entirely.
+    pub fn remove(&mut self, instrument_token: u32, interval: &str) {
+        self.series.remove(&(instrument_token, interval.to_owned()));
+    }
+
+    fn get(&self, instrument_token: u32, interval: &str) -> Option<&Series> {
+        self.series.get(&(instrument_token, interval.to_owned()))
+    }
+}
+
+fn missing_between(
+    prev: DateTime<Utc>,
+    next: DateTime<Utc>,
+    step: Duration,
+    interval: &str,
+) -> u32 {
+    let prev_ist = prev.with_timezone(&Kolkata).date_naive();
+    let next_ist = next.with_timezone(&Kolkata).date_naive();
+
+    if interval == "day" {
+        // Count weekdays strictly between the two candles; holidays still show up as gaps.
+        return prev_ist
+            .iter_days()
+            .skip(1)
+            .take_while(|day| *day < next_ist)
+            .filter(|day| !matches!(day.weekday(), Weekday::Sat | Weekday::Sun))
+            .count() as u32;
+    }
+
+    // Intraday gaps across sessions are expected
+    if prev_ist != next_ist {
+        return 0;
+    }
+    let elapsed = (next - prev).num_seconds().max(0) as u64;
+    (elapsed / step.as_secs().max(1)).saturating_sub(1) as u32
+}
@@ -0,0 +1,59 @@
+use async_trait::async_trait;
+use std::io::ErrorKind;
+use std::path::{Path, PathBuf};
+use zeroize::Zeroizing;
+
+use super::SessionStore;
+use crate::models::KiteConnectError;
+use crate::users::UserSessionTokens;
+
+/// FileSessionStore keeps the tokens in a JSON file. On Unix the file is readable by its
+/// owner only.
+#[derive(Debug, Clone)]
+pub struct FileSessionStore {
+    path: PathBuf,
+}
+
+impl FileSessionStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+        }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+#[async_trait]
+impl SessionStore for FileSessionStore {
+    async fn load(&self) -> Result<Option<UserSessionTokens>, KiteConnectError> {
+        match std::fs::read(&self.path) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&Zeroizing::new(data))?)),
+            Err(e) if e.kind() == ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    async fn save(&self, tokens: &UserSessionTokens) -> Result<(), KiteConnectError> {
+        let data = Zeroizing::new(serde_json::to_vec(tokens)?);
+        let mut options = std::fs::OpenOptions::new();
+        options.write(true).create(true).truncate(true);
+        #[cfg(unix)]
+        std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+        let mut file = options.open(&self.path).map_err(io_error)?;
+        std::io::Write::write_all(&mut file, &data).map_err(io_error)
+    }
+
+    async fn clear(&self) -> Result<(), KiteConnectError> {
+        match std::fs::remove_file(&self.path) {
+            Err(e) if e.kind() != ErrorKind::NotFound => Err(io_error(e)),
+            _ => Ok(()),
+        }
+    }
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("session file: {}", e))
+}
@@ -0,0 +1,211 @@
+//! Logging out at a fixed time of day.
+
+use async_channel::{Receiver, Sender};
+use chrono::{DateTime, Days, NaiveTime};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+use web_time::Duration;
+
+use super::SessionStore;
+use crate::KiteConnect;
+use crate::compat::{self, TaskHandle};
+use crate::models::KiteConnectError;
+use crate::risk::ist_now_datetime;
+
+// Longest single sleep, so wall clock jumps (suspend, NTP) are noticed
+const MAX_WAIT_STEP: Duration = Duration::from_secs(60);
+
+/// Why a [`LogoutScheduler`] logged out.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum LogoutReason {
+    /// The configured time of day was reached.
+    Scheduled,
+    /// [`LogoutScheduler::shutdown`] was called.
+    Shutdown,
+}
+
+/// SessionEvent is emitted by a [`LogoutScheduler`].
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SessionEvent {
+    /// The access token was invalidated, or there was none to invalidate when
+    /// `invalidated` is false. Either way the client no longer holds a token.
+    LoggedOut {
+        reason: LogoutReason,
+        invalidated: bool,
+    },
+    /// Invalidating the token or clearing the session store failed. The client and the
+    /// store no longer hold the token either way.
+    Error(String),
+}
+
+/// LogoutConfig configures a [`LogoutScheduler`].
+#[derive(Clone)]
+pub struct LogoutConfig {
+    /// Time of day in IST.
+    pub at: NaiveTime,
+    /// Store cleared after each logout, so a restart cannot restore the old token.
+    pub store: Option<Arc<dyn SessionStore>>,
+}
+
+impl Default for LogoutConfig {
+    /// 23:30 IST, without a store.
+    fn default() -> Self {
+        Self {
+            at: NaiveTime::from_hms_opt(23, 30, 0).unwrap_or_default(),
+            store: None,
+        }
+    }
+}
+
+impl LogoutConfig {
+    pub fn daily_at(at: NaiveTime) -> Self {
+        Self {
+            at,
+            ..Self::default()
+        }
+    }
+
+    pub fn clear_store(mut self, store: Arc<dyn SessionStore>) -> Self {
+        self.store = Some(store);
+        self
+    }
+}
+
+/// The first time `at` IST is reached after `now`.
+pub fn next_logout_after(now: DateTime<Tz>, at: NaiveTime) -> DateTime<Tz> {
+    let mut date = now.date_naive();
+    loop {
+        if let Some(logout) = date
+            .and_time(at)
+            .and_local_timezone(chrono_tz::Asia::Kolkata)
+            .earliest()
+        {
+            if logout > now {
+                return logout;
+            }
+        }
+        date = date.checked_add_days(Days::new(1)).unwrap_or(date);
+    }
+}
+
+/// LogoutScheduler invalidates the client's access token every day at a configured time.
+///
+/// Clones of the client share the token, so every clone is logged out. Call
+/// [`shutdown`](Self::shutdown) when the process exits to log out early as well.
+pub struct LogoutScheduler {
+    kite: KiteConnect,
+    config: LogoutConfig,
+    event_sender: Sender<SessionEvent>,
+    event_receiver: Receiver<SessionEvent>,
+    task: TaskHandle,
+}
+
+impl LogoutScheduler {
+    pub fn spawn(kite: KiteConnect, config: LogoutConfig) -> Self {
+        let (event_sender, event_receiver) = async_channel::unbounded();
+        let task = compat::spawn(run_scheduler(
+            kite.clone(),
+            config.clone(),
+            event_sender.clone(),
+        ));
+        Self {
+            kite,
+            config,
+            event_sender,
+            event_receiver,
+            task,
+        }
+    }
+
+    pub fn subscribe_events(&self) -> Receiver<SessionEvent> {
+        self.event_receiver.clone()
+    }
+
+    /// When the next scheduled logout happens.
+    pub fn next_logout_at(&self) -> DateTime<Tz> {
+        next_logout_after(ist_now_datetime(), self.config.at)
+    }
+
+    /// Stop the schedule and log out now.
+    pub async fn shutdown(&self) -> Result<bool, KiteConnectError> {
+        self.task.abort();
+        let mut kite = self.kite.clone();
+        let result = logout(
+            &mut kite,
+            &self.config,
+            LogoutReason::Shutdown,
+            &self.event_sender,
+        )
+        .await;
+        self.event_receiver.close();
+        result
+    }
+
+    /// Stop the schedule without logging out.
+    pub fn stop(&self) {
+        self.task.abort();
+        self.event_receiver.close();
+    }
+}
+
+impl Drop for LogoutScheduler {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+async fn run_scheduler(kite: KiteConnect, config: LogoutConfig, events: Sender<SessionEvent>) {
+    let mut kite = kite;
+    loop {
+        let logout_at = next_logout_after(ist_now_datetime(), config.at);
+        loop {
+            let remaining = (logout_at - ist_now_datetime())
+                .to_std()
+                .unwrap_or_default();
+            if remaining.is_zero() {
+                break;
+            }
+            compat::sleep(remaining.min(MAX_WAIT_STEP)).await;
+        }
+        let _ = logout(&mut kite, &config, LogoutReason::Scheduled, &events).await;
+        if events.is_closed() {
+            return;
+        }
+    }
+}
+
+async fn logout(
+    kite: &mut KiteConnect,
+    config: &LogoutConfig,
+    reason: LogoutReason,
+    events: &Sender<SessionEvent>,
+) -> Result<bool, KiteConnectError> {
+    let result = invalidate_and_clear(kite, config).await;
+    let event = match &result {
+        Ok(invalidated) => SessionEvent::LoggedOut {
+            reason,
+            invalidated: *invalidated,
+        },
+        Err(e) => SessionEvent::Error(e.to_string()),
+    };
+    let _ = events.send(event).await;
+    result
+}
+
+async fn invalidate_and_clear(
+    kite: &mut KiteConnect,
+    config: &LogoutConfig,
+) -> Result<bool, KiteConnectError> {
+    let invalidated = kite.invalidate_access_token().await;
+    // Clear even if invalidating failed, so the token is not used past the logout time
+    kite.clear_access_token();
+    let cleared = match &config.store {
+        Some(store) => store.clear().await,
+        None => Ok(()),
+    };
+    let invalidated = invalidated?;
+    cleared?;
+    Ok(invalidated)
+}
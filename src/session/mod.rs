@@ -0,0 +1,158 @@
+//! Persisting session tokens across restarts.
+//!
+//! A [`SessionStore`] keeps the [`UserSessionTokens`] of a login so an app can pick the
+//! session up again instead of going through the login flow on every start. Save the
+//! tokens after `generate_session` and restore them with
+//! [`KiteConnect::restore_session`]:
+//!
+//! ```ignore
+//! let session = kite.generate_session(&request_token, &api_secret).await?;
+//! store.save(&UserSessionTokens::from(&session)).await?;
+//!
+//! // On the next start
+//! if !kite.restore_session(&store).await? {
+//!     // redirect to kite.get_login_url()
+//! }
+//! ```
+//!
+//! Built-in stores:
+//! - `MemorySessionStore`, for tests and short-lived processes
+//! - `FileSessionStore`, a JSON file (native targets)
+//! - `BrowserStorage`, `localStorage` or `sessionStorage` with optional encryption (WASM)
+//!
+//! When an auth service holds the API secret and hands out access tokens, give the client
+//! a [`TokenProvider`] with [`KiteConnect::with_token_provider`] instead; the client then
+//! never needs the secret.
+//!
+//! For a guaranteed daily expiry, a [`LogoutScheduler`] invalidates the access token at a
+//! set time of day (23:30 IST by default) and clears the store.
+
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use zeroize::Zeroizing;
+
+use crate::KiteConnect;
+use crate::models::KiteConnectError;
+use crate::users::{UserSession, UserSessionTokens};
+
+#[cfg(not(target_arch = "wasm32"))]
+mod file;
+mod logout;
+#[cfg(target_arch = "wasm32")]
+mod storage;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use file::FileSessionStore;
+pub use logout::{LogoutConfig, LogoutReason, LogoutScheduler, SessionEvent, next_logout_after};
+#[cfg(target_arch = "wasm32")]
+pub use storage::{BrowserStorage, StorageArea};
+
+/// SessionStore loads and saves the tokens of one session.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait SessionStore: Send + Sync {
+    /// The saved tokens, or None when nothing was saved.
+    async fn load(&self) -> Result<Option<UserSessionTokens>, KiteConnectError>;
+    async fn save(&self, tokens: &UserSessionTokens) -> Result<(), KiteConnectError>;
+    async fn clear(&self) -> Result<(), KiteConnectError>;
+}
+
+/// TokenProvider hands out access tokens obtained elsewhere, e.g. from an auth service
+/// that holds the API secret.
+///
+/// The client asks for a token when it has none and again after a request fails with a
+/// `TokenException`, so a provider can fetch on every call or cache as it sees fit.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait TokenProvider: Send + Sync {
+    async fn access_token(&self) -> Result<String, KiteConnectError>;
+}
+
+/// MemorySessionStore keeps the tokens in memory.
+#[derive(Debug, Default)]
+pub struct MemorySessionStore {
+    tokens: Mutex<Option<UserSessionTokens>>,
+}
+
+impl MemorySessionStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl SessionStore for MemorySessionStore {
+    async fn load(&self) -> Result<Option<UserSessionTokens>, KiteConnectError> {
+        Ok(self
+            .tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .clone())
+    }
+
+    async fn save(&self, tokens: &UserSessionTokens) -> Result<(), KiteConnectError> {
+        *self.tokens.lock().unwrap_or_else(|e| e.into_inner()) = Some(tokens.clone());
+        Ok(())
+    }
+
+    async fn clear(&self) -> Result<(), KiteConnectError> {
+        *self.tokens.lock().unwrap_or_else(|e| e.into_inner()) = None;
+        Ok(())
+    }
+}
+
+impl From<&UserSession> for UserSessionTokens {
+    fn from(session: &UserSession) -> Self {
+        Self {
+            user_id: session.user_id.clone(),
+            access_token: session.access_token.clone(),
+            refresh_token: session.refresh_token.clone(),
+        }
+    }
+}
+
+impl KiteConnect {
+    /// Set the access token saved in `store`. Returns false, leaving the client as it is,
+    /// when the store is empty.
+    ///
+    /// Kite access tokens expire every morning; a restored token is not checked until the
+    /// first request fails with a token error.
+    pub async fn restore_session(
+        &mut self,
+        store: &dyn SessionStore,
+    ) -> Result<bool, KiteConnectError> {
+        match store.load().await? {
+            Some(tokens) => {
+                self.set_access_token(&tokens.access_token);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    /// Take access tokens from `provider` instead of `set_access_token`.
+    ///
+    /// The first request fetches a token. A request rejected with a `TokenException` drops
+    /// the token, and the next request fetches a new one; the rejected request is not
+    /// retried.
+    pub fn with_token_provider<P: TokenProvider + 'static>(mut self, provider: P) -> Self {
+        self.token_provider = Some(Arc::new(provider));
+        self
+    }
+
+    /// Replace the current access token with a fresh one from the token provider.
+    pub async fn refresh_provided_token(&self) -> Result<(), KiteConnectError> {
+        let Some(provider) = &self.token_provider else {
+            return Err(KiteConnectError::other("no token provider is set"));
+        };
+        let token = provider.access_token().await?;
+        if token.trim().is_empty() {
+            return Err(KiteConnectError::other(
+                "token provider returned an empty access token",
+            ));
+        }
+        *self.access_token.write().unwrap_or_else(|e| e.into_inner()) = Some(Zeroizing::new(token));
+        Ok(())
+    }
+}
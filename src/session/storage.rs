@@ -0,0 +1,251 @@
+use async_trait::async_trait;
+use js_sys::{Array, ArrayBuffer, Object, Promise, Reflect, Uint8Array};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use wasm_bindgen::{JsCast, JsValue};
+use wasm_bindgen_futures::JsFuture;
+use web_sys::{CryptoKey, Storage, SubtleCrypto};
+use zeroize::Zeroizing;
+
+use super::SessionStore;
+use crate::models::KiteConnectError;
+use crate::users::UserSessionTokens;
+
+// PBKDF2 rounds deriving the AES key from the passphrase
+const PBKDF2_ITERATIONS: u32 = 210_000;
+const SALT_LEN: usize = 16;
+const IV_LEN: usize = 12;
+
+/// The browser storage area tokens are kept in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StorageArea {
+    /// `localStorage`, kept across browser restarts.
+    Local,
+    /// `sessionStorage`, cleared when the tab is closed.
+    Session,
+}
+
+/// BrowserStorage keeps the tokens under one key of `localStorage` or `sessionStorage`.
+///
+/// With a passphrase the tokens are encrypted with AES-GCM under a key derived from the
+/// passphrase with PBKDF2, using the browser's WebCrypto. Storage is readable by any script
+/// on the page's origin, so without a passphrase the access token is only as safe as the
+/// page is from XSS.
+#[derive(Clone)]
+pub struct BrowserStorage {
+    area: StorageArea,
+    key: String,
+    passphrase: Option<Zeroizing<String>>,
+}
+
+impl fmt::Debug for BrowserStorage {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("BrowserStorage")
+            .field("area", &self.area)
+            .field("key", &self.key)
+            .field("encrypted", &self.passphrase.is_some())
+            .finish()
+    }
+}
+
+// Stored form of encrypted tokens, hex encoded
+#[derive(Debug, Serialize, Deserialize)]
+struct Sealed {
+    salt: String,
+    iv: String,
+    data: String,
+}
+
+impl BrowserStorage {
+    pub fn local(key: &str) -> Self {
+        Self::new(StorageArea::Local, key)
+    }
+
+    pub fn session(key: &str) -> Self {
+        Self::new(StorageArea::Session, key)
+    }
+
+    pub fn new(area: StorageArea, key: &str) -> Self {
+        Self {
+            area,
+            key: key.to_string(),
+            passphrase: None,
+        }
+    }
+
+    /// Encrypt the stored tokens with a key derived from `passphrase`.
+    pub fn passphrase(mut self, passphrase: &str) -> Self {
+        self.passphrase = Some(Zeroizing::new(passphrase.to_string()));
+        self
+    }
+
+    fn storage(&self) -> Result<Storage, KiteConnectError> {
+        let window = web_sys::window().ok_or_else(|| KiteConnectError::other("no window"))?;
+        let storage = match self.area {
+            StorageArea::Local => window.local_storage(),
+            StorageArea::Session => window.session_storage(),
+        };
+        storage
+            .map_err(js_error)?
+            .ok_or_else(|| KiteConnectError::other("browser storage is not available"))
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionStore for BrowserStorage {
+    async fn load(&self) -> Result<Option<UserSessionTokens>, KiteConnectError> {
+        let Some(value) = self.storage()?.get_item(&self.key).map_err(js_error)? else {
+            return Ok(None);
+        };
+        let json = Zeroizing::new(match &self.passphrase {
+            Some(passphrase) => open(passphrase, &serde_json::from_str(&value)?).await?,
+            None => value.into_bytes(),
+        });
+        Ok(Some(serde_json::from_slice(&json)?))
+    }
+
+    async fn save(&self, tokens: &UserSessionTokens) -> Result<(), KiteConnectError> {
+        let json = Zeroizing::new(serde_json::to_vec(tokens)?);
+        let value = Zeroizing::new(match &self.passphrase {
+            Some(passphrase) => serde_json::to_string(&seal(passphrase, &json).await?)?,
+            None => String::from_utf8(json.to_vec()).unwrap_or_default(),
+        });
+        self.storage()?
+            .set_item(&self.key, &value)
+            .map_err(js_error)
+    }
+
+    async fn clear(&self) -> Result<(), KiteConnectError> {
+        self.storage()?.remove_item(&self.key).map_err(js_error)
+    }
+}
+
+async fn seal(passphrase: &str, plain: &[u8]) -> Result<Sealed, KiteConnectError> {
+    let salt = random_bytes(SALT_LEN)?;
+    let iv = random_bytes(IV_LEN)?;
+    let subtle = subtle()?;
+    let key = derive_key(&subtle, passphrase, &salt).await?;
+    let encrypted: ArrayBuffer = resolve(subtle.encrypt_with_object_and_buffer_source(
+        &aes_gcm(&iv)?,
+        &key,
+        &Uint8Array::from(plain),
+    ))
+    .await?;
+    Ok(Sealed {
+        salt: to_hex(&salt),
+        iv: to_hex(&iv),
+        data: to_hex(&Uint8Array::new(&encrypted).to_vec()),
+    })
+}
+
+async fn open(passphrase: &str, sealed: &Sealed) -> Result<Vec<u8>, KiteConnectError> {
+    let (salt, iv, data) = (
+        from_hex(&sealed.salt)?,
+        from_hex(&sealed.iv)?,
+        from_hex(&sealed.data)?,
+    );
+    let subtle = subtle()?;
+    let key = derive_key(&subtle, passphrase, &salt).await?;
+    let decrypted: ArrayBuffer = resolve(subtle.decrypt_with_object_and_buffer_source(
+        &aes_gcm(&iv)?,
+        &key,
+        &Uint8Array::from(data.as_slice()),
+    ))
+    .await
+    .map_err(|_| KiteConnectError::other("stored session could not be decrypted"))?;
+    Ok(Uint8Array::new(&decrypted).to_vec())
+}
+
+async fn derive_key(
+    subtle: &SubtleCrypto,
+    passphrase: &str,
+    salt: &[u8],
+) -> Result<CryptoKey, KiteConnectError> {
+    let base: CryptoKey = resolve(subtle.import_key_with_str(
+        "raw",
+        &Uint8Array::from(passphrase.as_bytes()),
+        "PBKDF2",
+        false,
+        &Array::of1(&"deriveKey".into()),
+    ))
+    .await?;
+    let pbkdf2 = object(&[
+        ("name", "PBKDF2".into()),
+        ("salt", Uint8Array::from(salt).into()),
+        ("iterations", PBKDF2_ITERATIONS.into()),
+        ("hash", "SHA-256".into()),
+    ])?;
+    let aes = object(&[("name", "AES-GCM".into()), ("length", 256_u32.into())])?;
+    resolve(subtle.derive_key_with_object_and_object(
+        &pbkdf2,
+        &base,
+        &aes,
+        false,
+        &Array::of2(&"encrypt".into(), &"decrypt".into()),
+    ))
+    .await
+}
+
+fn aes_gcm(iv: &[u8]) -> Result<Object, KiteConnectError> {
+    object(&[
+        ("name", "AES-GCM".into()),
+        ("iv", Uint8Array::from(iv).into()),
+    ])
+}
+
+fn subtle() -> Result<SubtleCrypto, KiteConnectError> {
+    let window = web_sys::window().ok_or_else(|| KiteConnectError::other("no window"))?;
+    Ok(window.crypto().map_err(js_error)?.subtle())
+}
+
+fn random_bytes(len: usize) -> Result<Vec<u8>, KiteConnectError> {
+    let window = web_sys::window().ok_or_else(|| KiteConnectError::other("no window"))?;
+    let mut bytes = vec![0; len];
+    window
+        .crypto()
+        .map_err(js_error)?
+        .get_random_values_with_u8_array(&mut bytes)
+        .map_err(js_error)?;
+    Ok(bytes)
+}
+
+// Await a WebCrypto promise and cast its result
+async fn resolve<T: JsCast>(promise: Result<Promise, JsValue>) -> Result<T, KiteConnectError> {
+    JsFuture::from(promise.map_err(js_error)?)
+        .await
+        .map_err(js_error)?
+        .dyn_into()
+        .map_err(|_| KiteConnectError::other("unexpected WebCrypto result"))
+}
+
+fn object(fields: &[(&str, JsValue)]) -> Result<Object, KiteConnectError> {
+    let object = Object::new();
+    for (name, value) in fields {
+        Reflect::set(&object, &(*name).into(), value).map_err(js_error)?;
+    }
+    Ok(object)
+}
+
+fn js_error(e: JsValue) -> KiteConnectError {
+    KiteConnectError::other(format!("browser storage: {:?}", e))
+}
+
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+fn from_hex(hex: &str) -> Result<Vec<u8>, KiteConnectError> {
+    let invalid = || KiteConnectError::other("stored session is not valid hex");
+    if hex.len() % 2 != 0 {
+        return Err(invalid());
+    }
+    hex.as_bytes()
+        .chunks(2)
+        .map(|pair| {
+            std::str::from_utf8(pair)
+                .ok()
+                .and_then(|pair| u8::from_str_radix(pair, 16).ok())
+                .ok_or_else(invalid)
+        })
+        .collect()
+}
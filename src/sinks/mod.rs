@@ -0,0 +1,226 @@
+//! Persistence sinks for market data.
+//!
+//! A [`TickSink`] stores ticks and candles somewhere durable. [`SinkWriter`] sits in front
+//! of a sink and batches ticks from the ticker event loop, applying backpressure when the
+//! sink falls behind and retrying failed batches.
+//!
+//! Built-in sinks:
+//! - `SqliteSink` (feature `sqlite`)
+//! - `PostgresSink` for Postgres/TimescaleDB (feature `postgres`)
+
+use async_channel::{Receiver, Sender};
+use async_trait::async_trait;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicU64, Ordering};
+use web_time::{Duration, Instant};
+
+use crate::compat::{self, TaskHandle};
+use crate::markets::HistoricalData;
+use crate::models::{KiteConnectError, Tick};
+
+#[cfg(feature = "postgres")]
+mod postgres;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+
+#[cfg(feature = "postgres")]
+pub use postgres::PostgresSink;
+#[cfg(feature = "sqlite")]
+pub use sqlite::SqliteSink;
+
+/// TickSink is a destination for ticks and candles.
+#[async_trait]
+pub trait TickSink: Send + Sync + 'static {
+    /// Store a batch of ticks.
+    async fn write_ticks(&self, ticks: &[Tick]) -> Result<(), KiteConnectError>;
+
+    /// Store candles for an instrument. Sinks that only keep ticks can leave the default.
+    async fn write_candles(
+        &self,
+        instrument_token: u32,
+        interval: &str,
+        candles: &[HistoricalData],
+    ) -> Result<(), KiteConnectError> {
+        let _ = (instrument_token, interval, candles);
+        Err(KiteConnectError::other("this sink does not store candles"))
+    }
+}
+
+/// SinkConfig controls batching, buffering and retries of a [`SinkWriter`].
+#[derive(Debug, Clone)]
+pub struct SinkConfig {
+    pub batch_size: usize,
+    pub flush_interval: Duration,
+    /// Ticks buffered before `SinkWriter::write` starts waiting on the sink.
+    pub buffer: usize,
+    /// Retries of a failed batch. Storage errors and retriable errors (see
+    /// `KiteConnectError::is_retriable`) are retried; other batches are dropped.
+    pub max_retries: u32,
+    pub retry_backoff: Duration,
+}
+
+impl Default for SinkConfig {
+    fn default() -> Self {
+        Self {
+            batch_size: 500,
+            flush_interval: Duration::from_secs(1),
+            buffer: 10_000,
+            max_retries: 3,
+            retry_backoff: Duration::from_millis(500),
+        }
+    }
+}
+
+impl SinkConfig {
+    pub fn batch_size(mut self, size: usize) -> Self {
+        self.batch_size = size.max(1);
+        self
+    }
+
+    pub fn flush_interval(mut self, interval: Duration) -> Self {
+        self.flush_interval = interval;
+        self
+    }
+
+    pub fn buffer(mut self, capacity: usize) -> Self {
+        self.buffer = capacity.max(1);
+        self
+    }
+
+    pub fn max_retries(mut self, retries: u32) -> Self {
+        self.max_retries = retries;
+        self
+    }
+
+    pub fn retry_backoff(mut self, backoff: Duration) -> Self {
+        self.retry_backoff = backoff;
+        self
+    }
+}
+
+#[derive(Debug, Default)]
+struct SinkCounters {
+    written: AtomicU64,
+    dropped: AtomicU64,
+}
+
+/// SinkWriter batches ticks into a [`TickSink`] from a background task.
+///
+/// Feed it from the ticker event loop:
+///
+/// ```ignore
+/// if let TickerEvent::Tick(tick) = event {
+///     writer.write(tick).await?;
+/// }
+/// ```
+pub struct SinkWriter {
+    sender: Sender<Tick>,
+    done: Receiver<()>,
+    counters: Arc<SinkCounters>,
+    _task: TaskHandle,
+}
+
+impl SinkWriter {
+    pub fn spawn<S: TickSink>(sink: S, config: SinkConfig) -> Self {
+        let (sender, receiver) = async_channel::bounded(config.buffer.max(1));
+        let (done_tx, done) = async_channel::bounded(1);
+        let counters = Arc::new(SinkCounters::default());
+
+        let task_counters = counters.clone();
+        let task = compat::spawn(async move {
+            run_writer(sink, config, receiver, task_counters).await;
+            let _ = done_tx.send(()).await;
+        });
+
+        Self {
+            sender,
+            done,
+            counters,
+            _task: task,
+        }
+    }
+
+    /// Queue a tick, waiting while the buffer is full.
+    pub async fn write(&self, tick: Tick) -> Result<(), KiteConnectError> {
+        self.sender
+            .send(tick)
+            .await
+            .map_err(|_| KiteConnectError::other("sink writer has stopped"))
+    }
+
+    /// Queue a tick without waiting. Fails when the buffer is full.
+    pub fn try_write(&self, tick: Tick) -> Result<(), KiteConnectError> {
+        self.sender
+            .try_send(tick)
+            .map_err(|e| KiteConnectError::other(format!("sink buffer: {}", e)))
+    }
+
+    /// Ticks stored successfully so far.
+    pub fn written(&self) -> u64 {
+        self.counters.written.load(Ordering::Relaxed)
+    }
+
+    /// Ticks discarded after a batch ran out of retries.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Flush everything still buffered and stop the writer.
+    pub async fn close(self) {
+        self.sender.close();
+        let _ = self.done.recv().await;
+    }
+}
+
+async fn run_writer<S: TickSink>(
+    sink: S,
+    config: SinkConfig,
+    receiver: Receiver<Tick>,
+    counters: Arc<SinkCounters>,
+) {
+    let mut batch = Vec::with_capacity(config.batch_size);
+
+    while let Ok(first) = receiver.recv().await {
+        batch.push(first);
+
+        let deadline = Instant::now() + config.flush_interval;
+        while batch.len() < config.batch_size {
+            let remaining = deadline.saturating_duration_since(Instant::now());
+            match compat::timeout(remaining, receiver.recv()).await {
+                Ok(Ok(tick)) => batch.push(tick),
+                // Closed or flush interval elapsed
+                Ok(Err(_)) | Err(_) => break,
+            }
+        }
+
+        let count = batch.len() as u64;
+        if write_with_retry(&sink, &batch, &config).await {
+            counters.written.fetch_add(count, Ordering::Relaxed);
+        } else {
+            counters.dropped.fetch_add(count, Ordering::Relaxed);
+        }
+        batch.clear();
+    }
+}
+
+async fn write_with_retry<S: TickSink>(sink: &S, batch: &[Tick], config: &SinkConfig) -> bool {
+    let mut attempt = 0;
+    loop {
+        match sink.write_ticks(batch).await {
+            Ok(()) => return true,
+            Err(e) if e.is_retriable() && attempt < config.max_retries => {
+                log::warn!("tick sink write failed (attempt {}): {}", attempt + 1, e);
+                compat::sleep(config.retry_backoff * 2_u32.saturating_pow(attempt)).await;
+                attempt += 1;
+            }
+            Err(e) => {
+                log::error!(
+                    "dropping {} ticks after sink write failures: {}",
+                    batch.len(),
+                    e
+                );
+                return false;
+            }
+        }
+    }
+}
@@ -0,0 +1,162 @@
+use async_trait::async_trait;
+use sqlx::{PgPool, Postgres, QueryBuilder};
+
+use super::TickSink;
+use crate::markets::HistoricalData;
+use crate::models::{KiteConnectError, Tick};
+
+// Keeps every INSERT well under the 65535 bind parameter limit.
+const ROWS_PER_INSERT: usize = 1000;
+
+const SCHEMA: &[&str] = &[
+    "CREATE TABLE IF NOT EXISTS ticks (
+        instrument_token BIGINT NOT NULL,
+        exchange_ts TIMESTAMPTZ,
+        last_trade_ts TIMESTAMPTZ,
+        received_at TIMESTAMPTZ NOT NULL DEFAULT now(),
+        mode TEXT NOT NULL,
+        last_price DOUBLE PRECISION NOT NULL,
+        last_traded_quantity BIGINT NOT NULL,
+        volume BIGINT NOT NULL,
+        total_buy_quantity BIGINT NOT NULL,
+        total_sell_quantity BIGINT NOT NULL,
+        average_trade_price DOUBLE PRECISION NOT NULL,
+        oi BIGINT NOT NULL,
+        open DOUBLE PRECISION NOT NULL,
+        high DOUBLE PRECISION NOT NULL,
+        low DOUBLE PRECISION NOT NULL,
+        close DOUBLE PRECISION NOT NULL
+    )",
+    "CREATE INDEX IF NOT EXISTS ticks_token_received ON ticks (instrument_token, received_at)",
+    "CREATE TABLE IF NOT EXISTS candles (
+        instrument_token BIGINT NOT NULL,
+        interval TEXT NOT NULL,
+        ts TIMESTAMPTZ NOT NULL,
+        open DOUBLE PRECISION NOT NULL,
+        high DOUBLE PRECISION NOT NULL,
+        low DOUBLE PRECISION NOT NULL,
+        close DOUBLE PRECISION NOT NULL,
+        volume BIGINT NOT NULL,
+        oi BIGINT NOT NULL,
+        PRIMARY KEY (instrument_token, interval, ts)
+    )",
+];
+
+/// PostgresSink stores ticks and candles in Postgres, optionally as TimescaleDB hypertables.
+#[derive(Clone)]
+pub struct PostgresSink {
+    pool: PgPool,
+}
+
+impl PostgresSink {
+    pub async fn connect(url: &str) -> Result<Self, KiteConnectError> {
+        let pool = PgPool::connect(url).await.map_err(pg_error)?;
+        Ok(Self::from_pool(pool))
+    }
+
+    pub fn from_pool(pool: PgPool) -> Self {
+        Self { pool }
+    }
+
+    pub fn pool(&self) -> &PgPool {
+        &self.pool
+    }
+
+    /// Create the `ticks` and `candles` tables. With `timescale`, both are turned into
+    /// hypertables, which requires the TimescaleDB extension.
+    pub async fn create_tables(&self, timescale: bool) -> Result<(), KiteConnectError> {
+        for statement in SCHEMA {
+            sqlx::query(statement)
+                .execute(&self.pool)
+                .await
+                .map_err(pg_error)?;
+        }
+
+        if timescale {
+            for (table, column) in [("ticks", "received_at"), ("candles", "ts")] {
+                sqlx::query("SELECT create_hypertable($1, $2, if_not_exists => TRUE)")
+                    .bind(table)
+                    .bind(column)
+                    .execute(&self.pool)
+                    .await
+                    .map_err(pg_error)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl TickSink for PostgresSink {
+    async fn write_ticks(&self, ticks: &[Tick]) -> Result<(), KiteConnectError> {
+        let mut tx = self.pool.begin().await.map_err(pg_error)?;
+        for chunk in ticks.chunks(ROWS_PER_INSERT) {
+            let mut query: QueryBuilder<Postgres> = QueryBuilder::new(
+                "INSERT INTO ticks (instrument_token, exchange_ts, last_trade_ts, mode, \
+                 last_price, last_traded_quantity, volume, total_buy_quantity, \
+                 total_sell_quantity, average_trade_price, oi, open, high, low, close) ",
+            );
+            query.push_values(chunk, |mut row, t| {
+                row.push_bind(t.instrument_token as i64)
+                    .push_bind(t.timestamp.as_datetime())
+                    .push_bind(t.last_trade_time.as_datetime())
+                    .push_bind(t.mode.clone())
+                    .push_bind(t.last_price)
+                    .push_bind(t.last_traded_quantity as i64)
+                    .push_bind(t.volume_traded as i64)
+                    .push_bind(t.total_buy_quantity as i64)
+                    .push_bind(t.total_sell_quantity as i64)
+                    .push_bind(t.average_trade_price)
+                    .push_bind(t.oi as i64)
+                    .push_bind(t.ohlc.open)
+                    .push_bind(t.ohlc.high)
+                    .push_bind(t.ohlc.low)
+                    .push_bind(t.ohlc.close);
+            });
+            query.build().execute(&mut *tx).await.map_err(pg_error)?;
+        }
+        tx.commit().await.map_err(pg_error)
+    }
+
+    async fn write_candles(
+        &self,
+        instrument_token: u32,
+        interval: &str,
+        candles: &[HistoricalData],
+    ) -> Result<(), KiteConnectError> {
+        let rows: Vec<_> = candles
+            .iter()
+            .filter_map(|c| c.date.as_datetime().map(|ts| (ts, c)))
+            .collect();
+
+        let mut tx = self.pool.begin().await.map_err(pg_error)?;
+        for chunk in rows.chunks(ROWS_PER_INSERT) {
+            let mut query: QueryBuilder<Postgres> = QueryBuilder::new(
+                "INSERT INTO candles (instrument_token, interval, ts, open, high, low, close, \
+                 volume, oi) ",
+            );
+            query.push_values(chunk, |mut row, (ts, c)| {
+                row.push_bind(instrument_token as i64)
+                    .push_bind(interval.to_owned())
+                    .push_bind(*ts)
+                    .push_bind(c.open)
+                    .push_bind(c.high)
+                    .push_bind(c.low)
+                    .push_bind(c.close)
+                    .push_bind(c.volume as i64)
+                    .push_bind(c.oi as i64);
+            });
+            query.push(
+                " ON CONFLICT (instrument_token, interval, ts) DO UPDATE SET \
+                 open = EXCLUDED.open, high = EXCLUDED.high, low = EXCLUDED.low, \
+                 close = EXCLUDED.close, volume = EXCLUDED.volume, oi = EXCLUDED.oi",
+            );
+            query.build().execute(&mut *tx).await.map_err(pg_error)?;
+        }
+        tx.commit().await.map_err(pg_error)
+    }
+}
+
+fn pg_error(e: sqlx::Error) -> KiteConnectError {
+    KiteConnectError::storage(format!("postgres: {}", e))
+}
@@ -0,0 +1,163 @@
+use async_trait::async_trait;
+use rusqlite::{Connection, params};
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use super::TickSink;
+use crate::markets::HistoricalData;
+use crate::models::{KiteConnectError, Tick};
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS ticks (
+    instrument_token INTEGER NOT NULL,
+    exchange_ts INTEGER,
+    last_trade_ts INTEGER,
+    mode TEXT NOT NULL,
+    last_price REAL NOT NULL,
+    last_traded_quantity INTEGER NOT NULL,
+    volume INTEGER NOT NULL,
+    total_buy_quantity INTEGER NOT NULL,
+    total_sell_quantity INTEGER NOT NULL,
+    average_trade_price REAL NOT NULL,
+    oi INTEGER NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL
+);
+CREATE INDEX IF NOT EXISTS ticks_token_ts ON ticks (instrument_token, exchange_ts);
+CREATE TABLE IF NOT EXISTS candles (
+    instrument_token INTEGER NOT NULL,
+    interval TEXT NOT NULL,
+    ts INTEGER NOT NULL,
+    open REAL NOT NULL,
+    high REAL NOT NULL,
+    low REAL NOT NULL,
+    close REAL NOT NULL,
+    volume INTEGER NOT NULL,
+    oi INTEGER NOT NULL,
+    PRIMARY KEY (instrument_token, interval, ts)
+);
+";
+
+/// SqliteSink stores ticks and candles in a SQLite database.
+///
+/// Timestamps are stored as Unix seconds. Writes run on the blocking thread pool.
+#[derive(Clone)]
+pub struct SqliteSink {
+    conn: Arc<Mutex<Connection>>,
+}
+
+impl SqliteSink {
+    /// Open (or create) the database at `path` and create the tables if needed.
+    pub fn open(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        Self::from_connection(Connection::open(path).map_err(sqlite_error)?)
+    }
+
+    pub fn open_in_memory() -> Result<Self, KiteConnectError> {
+        Self::from_connection(Connection::open_in_memory().map_err(sqlite_error)?)
+    }
+
+    pub fn from_connection(conn: Connection) -> Result<Self, KiteConnectError> {
+        conn.execute_batch(SCHEMA).map_err(sqlite_error)?;
+        Ok(Self {
+            conn: Arc::new(Mutex::new(conn)),
+        })
+    }
+
+    /// Run a closure against the underlying connection, e.g. to query stored data.
+    pub fn with_connection<R>(&self, f: impl FnOnce(&Connection) -> R) -> R {
+        let conn = self.conn.lock().unwrap_or_else(|e| e.into_inner());
+        f(&conn)
+    }
+
+    async fn blocking<F>(&self, f: F) -> Result<(), KiteConnectError>
+    where
+        F: FnOnce(&mut Connection) -> rusqlite::Result<()> + Send + 'static,
+    {
+        let conn = self.conn.clone();
+        tokio::task::spawn_blocking(move || {
+            let mut conn = conn.lock().unwrap_or_else(|e| e.into_inner());
+            f(&mut conn)
+        })
+        .await
+        .map_err(|e| KiteConnectError::other(format!("sqlite task failed: {}", e)))?
+        .map_err(sqlite_error)
+    }
+}
+
+#[async_trait]
+impl TickSink for SqliteSink {
+    async fn write_ticks(&self, ticks: &[Tick]) -> Result<(), KiteConnectError> {
+        let ticks = ticks.to_vec();
+        self.blocking(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT INTO ticks VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
+                )?;
+                for t in &ticks {
+                    stmt.execute(params![
+                        t.instrument_token,
+                        t.timestamp.as_datetime().map(|d| d.timestamp()),
+                        t.last_trade_time.as_datetime().map(|d| d.timestamp()),
+                        t.mode,
+                        t.last_price,
+                        t.last_traded_quantity,
+                        t.volume_traded,
+                        t.total_buy_quantity,
+                        t.total_sell_quantity,
+                        t.average_trade_price,
+                        t.oi,
+                        t.ohlc.open,
+                        t.ohlc.high,
+                        t.ohlc.low,
+                        t.ohlc.close,
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+        .await
+    }
+
+    async fn write_candles(
+        &self,
+        instrument_token: u32,
+        interval: &str,
+        candles: &[HistoricalData],
+    ) -> Result<(), KiteConnectError> {
+        let candles = candles.to_vec();
+        let interval = interval.to_owned();
+        self.blocking(move |conn| {
+            let tx = conn.transaction()?;
+            {
+                let mut stmt = tx.prepare_cached(
+                    "INSERT OR REPLACE INTO candles VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9)",
+                )?;
+                for c in &candles {
+                    let Some(ts) = c.date.as_datetime() else {
+                        continue;
+                    };
+                    stmt.execute(params![
+                        instrument_token,
+                        interval,
+                        ts.timestamp(),
+                        c.open,
+                        c.high,
+                        c.low,
+                        c.close,
+                        c.volume,
+                        c.oi,
+                    ])?;
+                }
+            }
+            tx.commit()
+        })
+        .await
+    }
+}
+
+fn sqlite_error(e: rusqlite::Error) -> KiteConnectError {
+    KiteConnectError::storage(format!("sqlite: {}", e))
+}
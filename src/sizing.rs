@@ -0,0 +1,193 @@
+//! Position sizing from the risk taken per trade.
+//!
+//! [`position_size`] turns an entry, a stop and the amount willing to be lost into a
+//! quantity of whole lots. [`KiteConnect::position_size_with_margin`] also asks the
+//! margin calculator what a lot costs and caps the size at the margin available in the
+//! segment. The resulting [`PositionSize`] fills in an [`OrderParamsBuilder`]:
+//!
+//! ```ignore
+//! let size = kite
+//!     .position_size_with_margin(2000.0, 1960.0, 5000.0, &instrument, Product::Mis)
+//!     .await?;
+//! let order = size.order_builder(&instrument).product(Product::Mis).build()?;
+//! ```
+
+use serde::{Deserialize, Serialize};
+
+use crate::KiteConnect;
+use crate::instruments::{round_to_lot, round_to_tick};
+use crate::labels::{Exchange, OrderType, Product, TransactionType, Variety};
+use crate::margins::{GetMarginParams, MarginMode, OrderMarginParam};
+use crate::markets::Instrument;
+use crate::models::KiteConnectError;
+use crate::orders::{OrderParams, OrderParamsBuilder};
+
+/// What capped the quantity of a [`PositionSize`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SizeLimit {
+    /// The risk budget.
+    Risk,
+    /// The margin available in the segment.
+    Margin,
+}
+
+/// PositionSize is a sizing recommendation. Prices are rounded to the tick size and the
+/// quantity to whole lots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PositionSize {
+    /// BUY when the stop is below the entry, SELL when above.
+    pub transaction_type: TransactionType,
+    pub entry: f64,
+    pub stop: f64,
+    pub quantity: i32,
+    pub lots: i32,
+    /// Loss per unit if the stop is hit.
+    pub risk_per_unit: f64,
+    /// Loss at `quantity` if the stop is hit, at most the risk budget.
+    pub risk: f64,
+    /// Entry value at `quantity`.
+    pub value: f64,
+    /// Margin blocked per lot, when sized against margins.
+    pub margin_per_lot: Option<f64>,
+    /// Margin available in the segment, when sized against margins.
+    pub available_margin: Option<f64>,
+    pub limited_by: SizeLimit,
+}
+
+impl PositionSize {
+    /// A LIMIT order at the entry price for the recommended quantity.
+    pub fn order_builder(&self, instrument: &Instrument) -> OrderParamsBuilder {
+        OrderParams::builder()
+            .exchange(instrument.exchange.as_str())
+            .tradingsymbol(instrument.tradingsymbol.as_str())
+            .transaction_type(self.transaction_type)
+            .order_type(OrderType::Limit)
+            .quantity(self.quantity)
+            .price(self.entry)
+    }
+
+    /// Cap the size at the lots `available_margin` pays for at `margin_per_lot`.
+    fn cap_to_margin(&mut self, lot_size: i32, margin_per_lot: f64, available_margin: f64) {
+        self.margin_per_lot = Some(margin_per_lot);
+        self.available_margin = Some(available_margin);
+        if margin_per_lot <= 0.0 {
+            return;
+        }
+        let affordable = (available_margin.max(0.0) / margin_per_lot).floor() as i32;
+        if affordable < self.lots {
+            self.lots = affordable;
+            self.quantity = affordable * lot_size;
+            self.risk = self.risk_per_unit * self.quantity as f64;
+            self.value = self.entry * self.quantity as f64;
+            self.limited_by = SizeLimit::Margin;
+        }
+    }
+}
+
+/// Size a trade entered at `entry` with a stop at `stop` so that no more than
+/// `risk_amount` is lost when the stop is hit. Fails when the risk of one lot exceeds the
+/// budget.
+pub fn position_size(
+    entry: f64,
+    stop: f64,
+    risk_amount: f64,
+    instrument: &Instrument,
+) -> Result<PositionSize, KiteConnectError> {
+    let entry = round_to_tick(entry, instrument);
+    let stop = round_to_tick(stop, instrument);
+    let risk_per_unit = (entry - stop).abs();
+    if entry <= 0.0 || risk_per_unit <= 0.0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "entry {} and stop {} must be positive and at least a tick apart",
+            entry, stop
+        )));
+    }
+    if risk_amount <= 0.0 {
+        return Err(KiteConnectError::invalid_params(
+            "risk amount must be positive",
+        ));
+    }
+
+    let lot_size = lot_size(instrument);
+    let quantity = round_to_lot((risk_amount / risk_per_unit).floor() as i32, instrument);
+    if quantity <= 0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "risk of one lot of {} ({:.2}) exceeds the risk amount {:.2}",
+            instrument.tradingsymbol,
+            risk_per_unit * lot_size as f64,
+            risk_amount
+        )));
+    }
+
+    Ok(PositionSize {
+        transaction_type: if stop < entry {
+            TransactionType::Buy
+        } else {
+            TransactionType::Sell
+        },
+        entry,
+        stop,
+        quantity,
+        lots: quantity / lot_size,
+        risk_per_unit,
+        risk: risk_per_unit * quantity as f64,
+        value: entry * quantity as f64,
+        margin_per_lot: None,
+        available_margin: None,
+        limited_by: SizeLimit::Risk,
+    })
+}
+
+fn lot_size(instrument: &Instrument) -> i32 {
+    (instrument.lot_size.round() as i32).max(1)
+}
+
+impl KiteConnect {
+    /// [`position_size`], capped at the lots the margin available in the instrument's
+    /// segment pays for with `product`.
+    pub async fn position_size_with_margin(
+        &self,
+        entry: f64,
+        stop: f64,
+        risk_amount: f64,
+        instrument: &Instrument,
+        product: Product,
+    ) -> Result<PositionSize, KiteConnectError> {
+        let mut size = position_size(entry, stop, risk_amount, instrument)?;
+        let lot_size = lot_size(instrument);
+
+        let margins = self
+            .get_order_margins(GetMarginParams {
+                order_params: vec![OrderMarginParam {
+                    exchange: instrument.exchange.clone(),
+                    trading_symbol: instrument.tradingsymbol.clone(),
+                    transaction_type: size.transaction_type.as_str().to_string(),
+                    variety: Variety::Regular.as_str().to_string(),
+                    product: product.as_str().to_string(),
+                    order_type: OrderType::Limit.as_str().to_string(),
+                    quantity: lot_size as f64,
+                    price: Some(size.entry),
+                    trigger_price: None,
+                }],
+                mode: MarginMode::Full,
+            })
+            .await?;
+        let margin_per_lot: f64 = margins.iter().map(|m| m.total).sum();
+
+        let segment = match instrument.exchange.parse::<Exchange>() {
+            Ok(Exchange::Mcx) => "commodity",
+            _ => "equity",
+        };
+        let available = self.get_user_segment_margins(segment).await?.net;
+
+        size.cap_to_margin(lot_size, margin_per_lot, available);
+        if size.quantity <= 0 {
+            return Err(KiteConnectError::invalid_params(format!(
+                "available margin {:.2} does not cover one lot of {} at {:.2}",
+                available, instrument.tradingsymbol, margin_per_lot
+            )));
+        }
+        Ok(size)
+    }
+}
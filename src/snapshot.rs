@@ -0,0 +1,380 @@
+//! End-of-day account snapshots.
+//!
+//! [`KiteConnect::account_snapshot`] fetches holdings, positions, margins, orders and trades
+//! in one go, and a [`SnapshotWriter`] keeps one JSON file per trading day in a directory,
+//! so an account can be analysed historically without custom scripts:
+//!
+//! ```no_run
+//! # async fn run(kite: kiteconnect_rs::KiteConnect) -> Result<(), kiteconnect_rs::KiteConnectError> {
+//! use kiteconnect_rs::clock::MarketClock;
+//! use kiteconnect_rs::snapshot::SnapshotWriter;
+//!
+//! let writer = SnapshotWriter::new("snapshots")?;
+//! loop {
+//!     // Writes snapshots/snapshot-<date>.json after every close
+//!     writer.write_after_close(&kite, &MarketClock::nse()).await?;
+//! }
+//! # }
+//! ```
+//!
+//! Every file carries [`SNAPSHOT_VERSION`] so readers can tell layouts apart as it changes.
+//!
+//! [`SnapshotDiff`] lists what changed between two snapshots: holdings and positions that
+//! were opened, resized or closed, and margin deltas. Its `Display` output is a plain text
+//! summary for daily reports; the fields hold the same changes for programs.
+
+use chrono::{DateTime, NaiveDate, Utc};
+use futures_util::future::try_join5;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::KiteConnect;
+use crate::clock::MarketClock;
+use crate::corporate_actions::CorporateActions;
+use crate::models::KiteConnectError;
+use crate::orders::{Orders, Trades};
+use crate::portfolio::{Holdings, Positions};
+use crate::risk::ist_now_datetime;
+use crate::users::{AllMargins, Margins};
+
+/// Layout version written into every snapshot.
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// AccountSnapshot is the state of an account at one instant.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct AccountSnapshot {
+    pub version: u32,
+    pub taken_at: DateTime<Utc>,
+    /// IST trading date the snapshot belongs to.
+    pub date: NaiveDate,
+    pub holdings: Holdings,
+    pub positions: Positions,
+    pub margins: AllMargins,
+    pub orders: Orders,
+    pub trades: Trades,
+}
+
+impl AccountSnapshot {
+    /// Changes from this snapshot to the `newer` one.
+    pub fn diff(&self, newer: &AccountSnapshot) -> SnapshotDiff {
+        SnapshotDiff::between(self, newer)
+    }
+
+    /// Rescale the holdings for the corporate actions that went ex after this snapshot was
+    /// taken, so they diff cleanly against later snapshots.
+    pub fn adjust_for(&mut self, actions: &CorporateActions) {
+        actions.adjust_holdings(self.date, &mut self.holdings);
+    }
+}
+
+/// QuantityChange is a holding or net position whose quantity differs between two
+/// snapshots. `before` is 0 for a new one and `after` is 0 for a closed one.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct QuantityChange {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    pub product: String,
+    pub before: i32,
+    pub after: i32,
+}
+
+impl QuantityChange {
+    pub fn delta(&self) -> i32 {
+        self.after - self.before
+    }
+
+    pub fn is_new(&self) -> bool {
+        self.before == 0
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.after == 0
+    }
+}
+
+/// MarginChange is a margin figure of one segment that moved between two snapshots.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MarginChange {
+    /// `equity` or `commodity`.
+    pub segment: String,
+    /// `net`, `cash` or `debits`.
+    pub field: String,
+    pub before: f64,
+    pub after: f64,
+}
+
+impl MarginChange {
+    pub fn delta(&self) -> f64 {
+        self.after - self.before
+    }
+}
+
+/// SnapshotDiff holds the changes between two snapshots, sorted by exchange and symbol.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SnapshotDiff {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    /// Holdings whose quantity, including T1 quantity, changed.
+    pub holdings: Vec<QuantityChange>,
+    /// Net positions whose quantity changed.
+    pub positions: Vec<QuantityChange>,
+    /// Margin figures that moved by at least a paisa.
+    pub margins: Vec<MarginChange>,
+}
+
+impl SnapshotDiff {
+    pub fn between(from: &AccountSnapshot, to: &AccountSnapshot) -> Self {
+        let holding_quantities = |snapshot: &AccountSnapshot| {
+            snapshot
+                .holdings
+                .iter()
+                .map(|h| {
+                    let key = (
+                        h.exchange.clone(),
+                        h.tradingsymbol.clone(),
+                        h.product.clone(),
+                    );
+                    (key, h.quantity + h.t1_quantity)
+                })
+                .collect::<Vec<_>>()
+        };
+        let position_quantities = |snapshot: &AccountSnapshot| {
+            snapshot
+                .positions
+                .net
+                .iter()
+                .map(|p| {
+                    let key = (
+                        p.exchange.clone(),
+                        p.tradingsymbol.clone(),
+                        p.product.clone(),
+                    );
+                    (key, p.quantity)
+                })
+                .collect::<Vec<_>>()
+        };
+
+        let mut margins = Vec::new();
+        for (segment, before, after) in [
+            ("equity", &from.margins.equity, &to.margins.equity),
+            ("commodity", &from.margins.commodity, &to.margins.commodity),
+        ] {
+            margins.extend(margin_changes(segment, before, after));
+        }
+
+        Self {
+            from: from.date,
+            to: to.date,
+            holdings: quantity_changes(holding_quantities(from), holding_quantities(to)),
+            positions: quantity_changes(position_quantities(from), position_quantities(to)),
+            margins,
+        }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.holdings.is_empty() && self.positions.is_empty() && self.margins.is_empty()
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "Changes from {} to {}", self.from, self.to)?;
+        if self.is_empty() {
+            return write!(f, ": none");
+        }
+        for (title, changes) in [("Holdings", &self.holdings), ("Positions", &self.positions)] {
+            if changes.is_empty() {
+                continue;
+            }
+            write!(f, "\n{}:", title)?;
+            for change in changes {
+                let instrument = format!(
+                    "{}:{} {}",
+                    change.exchange, change.tradingsymbol, change.product
+                );
+                if change.is_new() {
+                    write!(f, "\n  + {} {} (new)", instrument, change.after)?;
+                } else if change.is_closed() {
+                    write!(f, "\n  - {} {} (closed)", instrument, change.before)?;
+                } else {
+                    write!(
+                        f,
+                        "\n  ~ {} {} -> {} ({:+})",
+                        instrument,
+                        change.before,
+                        change.after,
+                        change.delta()
+                    )?;
+                }
+            }
+        }
+        if !self.margins.is_empty() {
+            write!(f, "\nMargins:")?;
+            for change in &self.margins {
+                write!(
+                    f,
+                    "\n  {} {} {:.2} -> {:.2} ({:+.2})",
+                    change.segment,
+                    change.field,
+                    change.before,
+                    change.after,
+                    change.delta()
+                )?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl KiteConnect {
+    /// Fetch holdings, positions, margins, orders and trades concurrently.
+    pub async fn account_snapshot(&self) -> Result<AccountSnapshot, KiteConnectError> {
+        let (holdings, positions, margins, orders, trades) = try_join5(
+            self.get_holdings(),
+            self.get_positions(),
+            self.get_user_margins(),
+            self.get_orders(),
+            self.get_trades(),
+        )
+        .await?;
+        let now = ist_now_datetime();
+        Ok(AccountSnapshot {
+            version: SNAPSHOT_VERSION,
+            taken_at: now.with_timezone(&Utc),
+            date: now.date_naive(),
+            holdings,
+            positions,
+            margins,
+            orders,
+            trades,
+        })
+    }
+}
+
+/// SnapshotWriter keeps snapshots as `snapshot-<date>.json` files in a directory. A
+/// second snapshot on the same date replaces the first.
+#[derive(Debug, Clone)]
+pub struct SnapshotWriter {
+    dir: PathBuf,
+}
+
+impl SnapshotWriter {
+    /// Write into `dir`, creating it if needed.
+    pub fn new(dir: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        let dir = dir.as_ref().to_path_buf();
+        std::fs::create_dir_all(&dir).map_err(io_error)?;
+        Ok(Self { dir })
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Path of the snapshot for `date`.
+    pub fn path_for(&self, date: NaiveDate) -> PathBuf {
+        self.dir.join(format!("snapshot-{}.json", date))
+    }
+
+    /// Write `snapshot` and return its path. The file is written under a temporary name
+    /// and renamed, so readers never see a partial snapshot.
+    pub fn write(&self, snapshot: &AccountSnapshot) -> Result<PathBuf, KiteConnectError> {
+        let path = self.path_for(snapshot.date);
+        let partial = path.with_extension("json.partial");
+        std::fs::write(&partial, serde_json::to_vec_pretty(snapshot)?).map_err(io_error)?;
+        std::fs::rename(&partial, &path).map_err(io_error)?;
+        Ok(path)
+    }
+
+    /// Take a snapshot through `kite` and write it.
+    pub async fn write_now(&self, kite: &KiteConnect) -> Result<PathBuf, KiteConnectError> {
+        self.write(&kite.account_snapshot().await?)
+    }
+
+    /// Wait for the next close of `clock`'s normal session, then take and write a
+    /// snapshot. Outside the session this first waits for the next open.
+    pub async fn write_after_close(
+        &self,
+        kite: &KiteConnect,
+        clock: &MarketClock,
+    ) -> Result<PathBuf, KiteConnectError> {
+        clock.wait_for_open().await;
+        clock.wait_for_close().await;
+        self.write_now(kite).await
+    }
+
+    /// Read the snapshot for `date`, or None when there is none.
+    pub fn read(&self, date: NaiveDate) -> Result<Option<AccountSnapshot>, KiteConnectError> {
+        match std::fs::read(self.path_for(date)) {
+            Ok(data) => Ok(Some(serde_json::from_slice(&data)?)),
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(None),
+            Err(e) => Err(io_error(e)),
+        }
+    }
+
+    /// Dates with a snapshot in the directory, oldest first.
+    pub fn dates(&self) -> Result<Vec<NaiveDate>, KiteConnectError> {
+        let mut dates = Vec::new();
+        for entry in std::fs::read_dir(&self.dir).map_err(io_error)? {
+            let name = entry.map_err(io_error)?.file_name();
+            let date = name
+                .to_str()
+                .and_then(|name| name.strip_prefix("snapshot-"))
+                .and_then(|name| name.strip_suffix(".json"))
+                .and_then(|date| date.parse::<NaiveDate>().ok());
+            dates.extend(date);
+        }
+        dates.sort();
+        Ok(dates)
+    }
+}
+
+type InstrumentKey = (String, String, String);
+
+fn quantity_changes(
+    before: Vec<(InstrumentKey, i32)>,
+    after: Vec<(InstrumentKey, i32)>,
+) -> Vec<QuantityChange> {
+    let mut quantities: BTreeMap<InstrumentKey, (i32, i32)> = BTreeMap::new();
+    for (key, quantity) in before {
+        quantities.entry(key).or_default().0 += quantity;
+    }
+    for (key, quantity) in after {
+        quantities.entry(key).or_default().1 += quantity;
+    }
+    quantities
+        .into_iter()
+        .filter(|(_, (before, after))| before != after)
+        .map(
+            |((exchange, tradingsymbol, product), (before, after))| QuantityChange {
+                exchange,
+                tradingsymbol,
+                product,
+                before,
+                after,
+            },
+        )
+        .collect()
+}
+
+fn margin_changes(segment: &str, before: &Margins, after: &Margins) -> Vec<MarginChange> {
+    [
+        ("net", before.net, after.net),
+        ("cash", before.available.cash, after.available.cash),
+        ("debits", before.used.debits, after.used.debits),
+    ]
+    .into_iter()
+    .filter(|(_, before, after)| (after - before).abs() >= 0.005)
+    .map(|(field, before, after)| MarginChange {
+        segment: segment.to_string(),
+        field: field.to_string(),
+        before,
+        after,
+    })
+    .collect()
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("snapshot: {}", e))
+}
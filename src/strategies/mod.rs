@@ -0,0 +1,14 @@
+//! Multi-leg option strategies.
+//!
+//! [`templates`] builds the legs of common option structures from the instrument dump;
+//! [`KiteConnect::get_strategy_margins`](crate::KiteConnect::get_strategy_margins) and
+//! [`KiteConnect::place_strategy`](crate::KiteConnect::place_strategy) price and place
+//! them as a basket. [`roll`] moves futures and options positions to another expiry.
+
+pub mod roll;
+pub mod templates;
+
+pub use roll::{RollOrders, RollResult, roll_position};
+pub use templates::{
+    OptionChain, OptionKind, Strategy, StrategyLeg, StrategyMargins, StrategyOrderResult,
+};
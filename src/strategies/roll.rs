@@ -0,0 +1,182 @@
+//! Rolling F&O positions to another expiry.
+//!
+//! [`roll_position`] finds the same contract (underlying, type and strike) in the target
+//! expiry and returns the order closing the current contract and the order opening the
+//! new one. [`KiteConnect::execute_roll`] places them, closing first, optionally as LIMIT
+//! orders no further than a slippage limit from the last price.
+
+use chrono::NaiveDate;
+use chrono_tz::Asia::Kolkata;
+
+use crate::KiteConnect;
+use crate::instruments::InstrumentStore;
+use crate::labels::{OrderType, TransactionType, Validity, Variety};
+use crate::markets::Instrument;
+use crate::models::KiteConnectError;
+use crate::orders::{OrderParams, OrderResponse};
+use crate::portfolio::Position;
+
+/// RollOrders are the two orders moving a position from one expiry to another.
+#[derive(Debug, Clone)]
+pub struct RollOrders {
+    pub from: Instrument,
+    pub to: Instrument,
+    /// MARKET order closing the position in `from`.
+    pub close: OrderParams,
+    /// MARKET order opening the same position in `to`.
+    pub open: OrderParams,
+}
+
+/// RollResult is the outcome of [`KiteConnect::execute_roll`]. `open` is None when the
+/// close was not placed.
+#[derive(Debug)]
+pub struct RollResult {
+    pub close: Result<OrderResponse, KiteConnectError>,
+    pub open: Option<Result<OrderResponse, KiteConnectError>>,
+}
+
+impl RollResult {
+    pub fn is_ok(&self) -> bool {
+        self.close.is_ok() && matches!(self.open, Some(Ok(_)))
+    }
+}
+
+/// The orders rolling `position` to the contract expiring on `to_expiry`, resolved in
+/// `store`. Only futures and options positions roll, and only to a later expiry.
+pub fn roll_position(
+    position: &Position,
+    store: &InstrumentStore,
+    to_expiry: NaiveDate,
+) -> Result<RollOrders, KiteConnectError> {
+    if position.quantity == 0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} is flat",
+            position.tradingsymbol
+        )));
+    }
+    let from = store
+        .get(position.instrument_token)
+        .or_else(|| store.get_by_symbol(&position.exchange, &position.tradingsymbol))
+        .ok_or_else(|| {
+            KiteConnectError::invalid_params(format!(
+                "{} is not in the instrument store",
+                position.tradingsymbol
+            ))
+        })?;
+    if !matches!(from.instrument_type.as_str(), "FUT" | "CE" | "PE") {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} is not a futures or options contract",
+            from.tradingsymbol
+        )));
+    }
+    if expiry_date(from).is_none_or(|expiry| expiry >= to_expiry) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} does not expire before {}",
+            from.tradingsymbol, to_expiry
+        )));
+    }
+
+    let to = store
+        .iter()
+        .find(|i| {
+            i.exchange == from.exchange
+                && i.name == from.name
+                && i.instrument_type == from.instrument_type
+                && (i.strike - from.strike).abs() < 1e-6
+                && expiry_date(i) == Some(to_expiry)
+        })
+        .ok_or_else(|| {
+            KiteConnectError::invalid_params(format!(
+                "no {} {} {} contract expiring on {}",
+                from.name, from.strike, from.instrument_type, to_expiry
+            ))
+        })?;
+
+    let side = if position.quantity > 0 {
+        TransactionType::Buy
+    } else {
+        TransactionType::Sell
+    };
+    let order = |instrument: &Instrument, transaction_type: TransactionType| OrderParams {
+        exchange: Some(instrument.exchange.clone()),
+        tradingsymbol: Some(instrument.tradingsymbol.clone()),
+        transaction_type: Some(transaction_type.into()),
+        order_type: Some(OrderType::Market.into()),
+        product: Some(position.product.clone()),
+        validity: Some(Validity::Day.into()),
+        quantity: Some(position.quantity.abs()),
+        ..Default::default()
+    };
+
+    Ok(RollOrders {
+        close: order(from, side.opposite()),
+        open: order(to, side),
+        from: from.clone(),
+        to: to.clone(),
+    })
+}
+
+impl KiteConnect {
+    /// Places the orders of a roll, the close first. The open order is only placed once
+    /// the close has been accepted.
+    ///
+    /// With `max_slippage_pct` both orders go out as LIMIT orders priced that many percent
+    /// through the last price, rounded to the tick size, so a thin book cannot fill them
+    /// far away from it.
+    pub async fn execute_roll(
+        &self,
+        roll: &RollOrders,
+        max_slippage_pct: Option<f64>,
+    ) -> Result<RollResult, KiteConnectError> {
+        let mut close = roll.close.clone();
+        let mut open = roll.open.clone();
+        if let Some(pct) = max_slippage_pct {
+            let keys = [instrument_key(&roll.from), instrument_key(&roll.to)];
+            let key_refs: Vec<&str> = keys.iter().map(String::as_str).collect();
+            let ltp = self.get_ltp(&key_refs).await?;
+            for ((order, instrument), key) in [(&mut close, &roll.from), (&mut open, &roll.to)]
+                .into_iter()
+                .zip(&keys)
+            {
+                let last_price = ltp
+                    .get(key)
+                    .map(|q| q.last_price)
+                    .ok_or_else(|| KiteConnectError::other(format!("no last price for {}", key)))?;
+                let buy = order.transaction_type.as_deref() == Some(TransactionType::Buy.as_str());
+                order.order_type = Some(OrderType::Limit.into());
+                order.price = Some(limit_price(last_price, pct, buy, instrument.tick_size));
+            }
+        }
+
+        let close = self.place_order(Variety::Regular.as_str(), close).await;
+        let open = match close {
+            Ok(_) => Some(self.place_order(Variety::Regular.as_str(), open).await),
+            Err(_) => None,
+        };
+        Ok(RollResult { close, open })
+    }
+}
+
+/// Price `pct` percent through `last_price`: above it for buys and below it for sells,
+/// rounded away from the last price to a multiple of `tick_size`.
+fn limit_price(last_price: f64, pct: f64, buy: bool, tick_size: f64) -> f64 {
+    let offset = last_price * pct.max(0.0) / 100.0;
+    let tick = if tick_size > 0.0 { tick_size } else { 0.05 };
+    let ticks = if buy {
+        ((last_price + offset) / tick - 1e-9).ceil()
+    } else {
+        ((last_price - offset) / tick + 1e-9).floor()
+    };
+    (ticks * tick * 100.0).round() / 100.0
+}
+
+fn expiry_date(instrument: &Instrument) -> Option<NaiveDate> {
+    instrument
+        .expiry
+        .as_datetime()
+        .map(|expiry| expiry.with_timezone(&Kolkata).date_naive())
+}
+
+fn instrument_key(instrument: &Instrument) -> String {
+    format!("{}:{}", instrument.exchange, instrument.tradingsymbol)
+}
@@ -0,0 +1,410 @@
+//! Option strategy templates.
+//!
+//! An [`OptionChain`] is the contracts of one underlying and expiry in an
+//! [`InstrumentStore`]. The templates pick strikes from it and return a [`Strategy`] whose
+//! legs carry the contract, side and number of lots:
+//!
+//! ```ignore
+//! let chain = OptionChain::new(&store, Exchange::Nfo, "NIFTY", expiry);
+//! let atm = chain.atm_strike(spot).unwrap();
+//! let strangle = Strategy::strangle(&chain, atm - 200.0, atm + 200.0, TransactionType::Sell, 1)?;
+//! let margins = kite.get_strategy_margins(&strangle, Product::Nrml).await?;
+//! let results = kite.place_strategy(&strangle, Product::Nrml, Some(10_000.0)).await?;
+//! ```
+
+use chrono::NaiveDate;
+use chrono_tz::Asia::Kolkata;
+
+use crate::KiteConnect;
+use crate::instruments::InstrumentStore;
+use crate::labels::{Exchange, OrderType, Product, TransactionType, Validity, Variety};
+use crate::margins::{GetBasketParams, MarginMode, OrderMarginParam};
+use crate::markets::Instrument;
+use crate::models::KiteConnectError;
+use crate::orders::{OrderParams, OrderResponse};
+
+// Strikes closer than this are the same strike.
+const STRIKE_EPSILON: f64 = 1e-6;
+
+/// Call or put.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum OptionKind {
+    Call,
+    Put,
+}
+
+impl OptionKind {
+    /// The instrument type in the instrument dump: `CE` or `PE`.
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            OptionKind::Call => "CE",
+            OptionKind::Put => "PE",
+        }
+    }
+}
+
+/// OptionChain is the options of one underlying and expiry in an instrument dump.
+#[derive(Debug, Clone)]
+pub struct OptionChain<'a> {
+    options: Vec<&'a Instrument>,
+}
+
+impl<'a> OptionChain<'a> {
+    /// Options on `exchange` whose `name` is `underlying` (e.g. `NIFTY`) expiring on
+    /// `expiry`.
+    pub fn new(
+        store: &'a InstrumentStore,
+        exchange: Exchange,
+        underlying: &str,
+        expiry: NaiveDate,
+    ) -> Self {
+        let options = store
+            .iter()
+            .filter(|i| {
+                i.exchange == exchange.as_str()
+                    && i.name == underlying
+                    && (i.instrument_type == "CE" || i.instrument_type == "PE")
+                    && i.expiry
+                        .as_datetime()
+                        .is_some_and(|e| e.with_timezone(&Kolkata).date_naive() == expiry)
+            })
+            .collect();
+        Self { options }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.options.is_empty()
+    }
+
+    /// Strikes listed for both calls and puts, ascending.
+    pub fn strikes(&self) -> Vec<f64> {
+        let mut strikes: Vec<f64> = self
+            .options
+            .iter()
+            .filter(|i| i.instrument_type == OptionKind::Call.as_str())
+            .map(|i| i.strike)
+            .filter(|&strike| self.get(strike, OptionKind::Put).is_some())
+            .collect();
+        strikes.sort_by(f64::total_cmp);
+        strikes.dedup_by(|a, b| (*a - *b).abs() < STRIKE_EPSILON);
+        strikes
+    }
+
+    /// The strike closest to `spot`.
+    pub fn atm_strike(&self, spot: f64) -> Option<f64> {
+        self.strikes()
+            .into_iter()
+            .min_by(|a, b| (a - spot).abs().total_cmp(&(b - spot).abs()))
+    }
+
+    pub fn get(&self, strike: f64, kind: OptionKind) -> Option<&'a Instrument> {
+        self.options.iter().copied().find(|i| {
+            i.instrument_type == kind.as_str() && (i.strike - strike).abs() < STRIKE_EPSILON
+        })
+    }
+
+    fn leg(
+        &self,
+        strike: f64,
+        kind: OptionKind,
+        transaction_type: TransactionType,
+        lots: u32,
+    ) -> Result<StrategyLeg, KiteConnectError> {
+        let instrument = self.get(strike, kind).ok_or_else(|| {
+            KiteConnectError::invalid_params(format!(
+                "no {} option at strike {}",
+                kind.as_str(),
+                strike
+            ))
+        })?;
+        Ok(StrategyLeg {
+            instrument: instrument.clone(),
+            transaction_type,
+            lots,
+        })
+    }
+}
+
+/// StrategyLeg is one contract of a strategy.
+#[derive(Debug, Clone)]
+pub struct StrategyLeg {
+    pub instrument: Instrument,
+    pub transaction_type: TransactionType,
+    pub lots: u32,
+}
+
+impl StrategyLeg {
+    pub fn quantity(&self) -> i32 {
+        (self.lots as f64 * self.instrument.lot_size.max(1.0)) as i32
+    }
+
+    /// A MARKET order for the leg.
+    pub fn order_params(&self, product: Product) -> OrderParams {
+        OrderParams {
+            exchange: Some(self.instrument.exchange.clone()),
+            tradingsymbol: Some(self.instrument.tradingsymbol.clone()),
+            transaction_type: Some(self.transaction_type.into()),
+            order_type: Some(OrderType::Market.into()),
+            product: Some(product.into()),
+            validity: Some(Validity::Day.into()),
+            quantity: Some(self.quantity()),
+            ..Default::default()
+        }
+    }
+
+    pub fn margin_params(&self, product: Product) -> OrderMarginParam {
+        OrderMarginParam {
+            exchange: self.instrument.exchange.clone(),
+            trading_symbol: self.instrument.tradingsymbol.clone(),
+            transaction_type: self.transaction_type.into(),
+            variety: Variety::Regular.into(),
+            product: product.into(),
+            order_type: OrderType::Market.into(),
+            quantity: self.quantity() as f64,
+            price: None,
+            trigger_price: None,
+        }
+    }
+}
+
+/// Strategy is a named set of option legs.
+#[derive(Debug, Clone)]
+pub struct Strategy {
+    pub name: &'static str,
+    pub legs: Vec<StrategyLeg>,
+}
+
+impl Strategy {
+    /// Call and put at the same strike. `side` is BUY for a long straddle.
+    pub fn straddle(
+        chain: &OptionChain,
+        strike: f64,
+        side: TransactionType,
+        lots: u32,
+    ) -> Result<Self, KiteConnectError> {
+        Self::build(
+            "straddle",
+            lots,
+            vec![
+                chain.leg(strike, OptionKind::Call, side, lots)?,
+                chain.leg(strike, OptionKind::Put, side, lots)?,
+            ],
+        )
+    }
+
+    /// Put at `put_strike` and call at the higher `call_strike`.
+    pub fn strangle(
+        chain: &OptionChain,
+        put_strike: f64,
+        call_strike: f64,
+        side: TransactionType,
+        lots: u32,
+    ) -> Result<Self, KiteConnectError> {
+        ordered(put_strike, call_strike)?;
+        Self::build(
+            "strangle",
+            lots,
+            vec![
+                chain.leg(call_strike, OptionKind::Call, side, lots)?,
+                chain.leg(put_strike, OptionKind::Put, side, lots)?,
+            ],
+        )
+    }
+
+    /// Buy the call at `lower` and sell the call at `upper`.
+    pub fn bull_call_spread(
+        chain: &OptionChain,
+        lower: f64,
+        upper: f64,
+        lots: u32,
+    ) -> Result<Self, KiteConnectError> {
+        ordered(lower, upper)?;
+        Self::build(
+            "bull_call_spread",
+            lots,
+            vec![
+                chain.leg(lower, OptionKind::Call, TransactionType::Buy, lots)?,
+                chain.leg(upper, OptionKind::Call, TransactionType::Sell, lots)?,
+            ],
+        )
+    }
+
+    /// Buy the put at `upper` and sell the put at `lower`.
+    pub fn bear_put_spread(
+        chain: &OptionChain,
+        lower: f64,
+        upper: f64,
+        lots: u32,
+    ) -> Result<Self, KiteConnectError> {
+        ordered(lower, upper)?;
+        Self::build(
+            "bear_put_spread",
+            lots,
+            vec![
+                chain.leg(upper, OptionKind::Put, TransactionType::Buy, lots)?,
+                chain.leg(lower, OptionKind::Put, TransactionType::Sell, lots)?,
+            ],
+        )
+    }
+
+    /// Short strangle at `put_strike`/`call_strike` hedged by long options `wing` away.
+    pub fn iron_condor(
+        chain: &OptionChain,
+        put_strike: f64,
+        call_strike: f64,
+        wing: f64,
+        lots: u32,
+    ) -> Result<Self, KiteConnectError> {
+        ordered(put_strike, call_strike)?;
+        if wing <= 0.0 {
+            return Err(KiteConnectError::invalid_params("wing must be positive"));
+        }
+        let (buy, sell) = (TransactionType::Buy, TransactionType::Sell);
+        Self::build(
+            "iron_condor",
+            lots,
+            vec![
+                chain.leg(put_strike - wing, OptionKind::Put, buy, lots)?,
+                chain.leg(call_strike + wing, OptionKind::Call, buy, lots)?,
+                chain.leg(put_strike, OptionKind::Put, sell, lots)?,
+                chain.leg(call_strike, OptionKind::Call, sell, lots)?,
+            ],
+        )
+    }
+
+    fn build(
+        name: &'static str,
+        lots: u32,
+        legs: Vec<StrategyLeg>,
+    ) -> Result<Self, KiteConnectError> {
+        if lots == 0 {
+            return Err(KiteConnectError::invalid_params("lots must be at least 1"));
+        }
+        Ok(Self { name, legs })
+    }
+
+    /// Orders for every leg, buys first so hedges are in place before the short legs and
+    /// the margin benefit applies to them.
+    pub fn order_params(&self, product: Product) -> Vec<OrderParams> {
+        self.legs_buys_first()
+            .map(|leg| leg.order_params(product))
+            .collect()
+    }
+
+    fn legs_buys_first(&self) -> impl Iterator<Item = &StrategyLeg> {
+        let buys = self
+            .legs
+            .iter()
+            .filter(|leg| leg.transaction_type == TransactionType::Buy);
+        let sells = self
+            .legs
+            .iter()
+            .filter(|leg| leg.transaction_type == TransactionType::Sell);
+        buys.chain(sells)
+    }
+}
+
+/// StrategyMargins compares the margin of the legs on their own with the margin of the
+/// basket.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct StrategyMargins {
+    /// Sum of the legs' margins without hedge benefit.
+    pub standalone: f64,
+    /// Margin of the legs as a basket.
+    pub basket: f64,
+}
+
+impl StrategyMargins {
+    pub fn benefit(&self) -> f64 {
+        self.standalone - self.basket
+    }
+}
+
+/// StrategyOrderResult is the outcome of placing one leg.
+#[derive(Debug)]
+pub struct StrategyOrderResult {
+    pub tradingsymbol: String,
+    pub transaction_type: TransactionType,
+    pub result: Result<OrderResponse, KiteConnectError>,
+}
+
+impl KiteConnect {
+    /// Margins of `strategy` from the basket margin calculator, ignoring open positions.
+    pub async fn get_strategy_margins(
+        &self,
+        strategy: &Strategy,
+        product: Product,
+    ) -> Result<StrategyMargins, KiteConnectError> {
+        let margins = self
+            .get_basket_margins(GetBasketParams {
+                order_params: strategy
+                    .legs
+                    .iter()
+                    .map(|leg| leg.margin_params(product))
+                    .collect(),
+                mode: MarginMode::Full,
+                consider_positions: false,
+            })
+            .await?;
+        let standalone = match &margins.initial {
+            Some(initial) => initial.total,
+            None => margins.orders.iter().map(|m| m.total).sum(),
+        };
+        let basket = margins
+            .final_margins
+            .as_ref()
+            .map_or(standalone, |m| m.total);
+        Ok(StrategyMargins { standalone, basket })
+    }
+
+    /// Places the legs of `strategy` one at a time, buys first.
+    ///
+    /// With `min_margin_benefit` set the basket margins are checked first and nothing is
+    /// placed if the hedge saves less than that. Placement stops at the first rejected
+    /// leg; the results list every leg tried, so the ones that went through can be exited.
+    pub async fn place_strategy(
+        &self,
+        strategy: &Strategy,
+        product: Product,
+        min_margin_benefit: Option<f64>,
+    ) -> Result<Vec<StrategyOrderResult>, KiteConnectError> {
+        if let Some(min) = min_margin_benefit {
+            let margins = self.get_strategy_margins(strategy, product).await?;
+            if margins.benefit() < min {
+                return Err(KiteConnectError::invalid_params(format!(
+                    "{} margin benefit {:.2} is below {:.2}",
+                    strategy.name,
+                    margins.benefit(),
+                    min
+                )));
+            }
+        }
+
+        let mut results = Vec::with_capacity(strategy.legs.len());
+        for leg in strategy.legs_buys_first() {
+            let result = self
+                .place_order(Variety::Regular.as_str(), leg.order_params(product))
+                .await;
+            let failed = result.is_err();
+            results.push(StrategyOrderResult {
+                tradingsymbol: leg.instrument.tradingsymbol.clone(),
+                transaction_type: leg.transaction_type,
+                result,
+            });
+            if failed {
+                break;
+            }
+        }
+        Ok(results)
+    }
+}
+
+fn ordered(lower: f64, upper: f64) -> Result<(), KiteConnectError> {
+    if lower >= upper {
+        return Err(KiteConnectError::invalid_params(format!(
+            "strike {} must be below {}",
+            lower, upper
+        )));
+    }
+    Ok(())
+}
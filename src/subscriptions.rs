@@ -0,0 +1,298 @@
+//! Keeping a ticker within a subscription budget.
+//!
+//! A scanner cycling through hundreds of instruments wants more tokens than it can stream
+//! at once. [`SubscriptionManager`] sits on a [`TickerHandle`] and keeps the subscribed set
+//! within a budget: tokens are requested with a [`SubscriptionPriority`], and when the
+//! budget is full the least recently used token of the lowest priority makes room.
+//! Subscribe and unsubscribe commands are issued as the interest set changes.
+//!
+//! ```ignore
+//! let manager = SubscriptionManager::new(handle.clone(), 200);
+//! manager.request(&[408065, 884737], SubscriptionPriority::High).await?;
+//! while let Ok(event) = events.recv().await {
+//!     manager.on_event(&event);
+//!     // ...
+//! }
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Mutex;
+use web_time::{Duration, Instant};
+
+use crate::ticker::{Mode, TickerError, TickerEvent, TickerHandle};
+
+/// Most tokens a single ticker connection may subscribe to.
+pub const MAX_TOKENS_PER_CONNECTION: usize = 3000;
+
+/// SubscriptionPriority decides which tokens make room for others. A token is only evicted
+/// for one of the same or higher priority, and pinned tokens are never evicted.
+#[derive(
+    Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Default, Serialize, Deserialize,
+)]
+#[serde(rename_all = "snake_case")]
+pub enum SubscriptionPriority {
+    Low,
+    #[default]
+    Normal,
+    High,
+    Pinned,
+}
+
+/// SubscriptionChange is what a [`SubscriptionManager`] call did to the ticker.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SubscriptionChange {
+    pub subscribed: Vec<u32>,
+    /// Tokens evicted or released.
+    pub unsubscribed: Vec<u32>,
+    /// Requested tokens that did not fit in the budget.
+    pub rejected: Vec<u32>,
+}
+
+impl SubscriptionChange {
+    pub fn is_empty(&self) -> bool {
+        self.subscribed.is_empty() && self.unsubscribed.is_empty() && self.rejected.is_empty()
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    priority: SubscriptionPriority,
+    // Order of last use, for LRU eviction
+    used: u64,
+    used_at: Instant,
+}
+
+#[derive(Debug, Default)]
+struct State {
+    entries: HashMap<u32, Entry>,
+    uses: u64,
+}
+
+impl State {
+    fn touch(&mut self, token: u32) {
+        self.uses += 1;
+        let uses = self.uses;
+        if let Some(entry) = self.entries.get_mut(&token) {
+            entry.used = uses;
+            entry.used_at = Instant::now();
+        }
+    }
+
+    fn insert(&mut self, token: u32, priority: SubscriptionPriority) {
+        self.uses += 1;
+        self.entries.insert(
+            token,
+            Entry {
+                priority,
+                used: self.uses,
+                used_at: Instant::now(),
+            },
+        );
+    }
+
+    // Least recently used token of the lowest priority that `priority` may evict
+    fn victim(&self, priority: SubscriptionPriority) -> Option<u32> {
+        self.entries
+            .iter()
+            .filter(|(_, e)| e.priority != SubscriptionPriority::Pinned && e.priority <= priority)
+            .min_by_key(|(_, e)| (e.priority, e.used))
+            .map(|(token, _)| *token)
+    }
+}
+
+/// SubscriptionManager subscribes tokens on a ticker within a budget.
+///
+/// The manager assumes it owns the handle's subscriptions; tokens subscribed on the handle
+/// directly are not counted.
+pub struct SubscriptionManager {
+    handle: TickerHandle,
+    budget: usize,
+    mode: Mode,
+    state: Mutex<State>,
+}
+
+impl SubscriptionManager {
+    /// A manager subscribing at most `budget` tokens, capped at
+    /// [`MAX_TOKENS_PER_CONNECTION`].
+    pub fn new(handle: TickerHandle, budget: usize) -> Self {
+        Self {
+            handle,
+            budget: budget.min(MAX_TOKENS_PER_CONNECTION),
+            mode: Mode::Quote,
+            state: Mutex::new(State::default()),
+        }
+    }
+
+    /// Stream newly subscribed tokens in `mode` instead of quote mode.
+    pub fn with_mode(mut self, mode: Mode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    pub fn budget(&self) -> usize {
+        self.budget
+    }
+
+    /// Request `tokens` at `priority`, evicting less important tokens when the budget is
+    /// full. Tokens already subscribed take the new priority and count as used.
+    pub async fn request(
+        &self,
+        tokens: &[u32],
+        priority: SubscriptionPriority,
+    ) -> Result<SubscriptionChange, TickerError> {
+        let change = {
+            let mut state = self.lock();
+            let mut change = SubscriptionChange::default();
+            for &token in tokens {
+                self.admit(&mut state, token, priority, &mut change);
+            }
+            change
+        };
+        self.apply(change).await
+    }
+
+    /// Make the subscribed set follow `interest`: tokens not in it are unsubscribed and the
+    /// rest requested, highest priority first.
+    pub async fn set_interest(
+        &self,
+        interest: &[(u32, SubscriptionPriority)],
+    ) -> Result<SubscriptionChange, TickerError> {
+        let change = {
+            let mut state = self.lock();
+            let mut change = SubscriptionChange::default();
+            let wanted: HashMap<u32, SubscriptionPriority> = interest.iter().copied().collect();
+            state.entries.retain(|token, _| {
+                let keep = wanted.contains_key(token);
+                if !keep {
+                    change.unsubscribed.push(*token);
+                }
+                keep
+            });
+            change.unsubscribed.sort_unstable();
+            let mut interest = interest.to_vec();
+            interest.sort_by_key(|(_, priority)| std::cmp::Reverse(*priority));
+            for (token, priority) in interest {
+                self.admit(&mut state, token, priority, &mut change);
+            }
+            change
+        };
+        self.apply(change).await
+    }
+
+    /// Unsubscribe `tokens`.
+    pub async fn release(&self, tokens: &[u32]) -> Result<SubscriptionChange, TickerError> {
+        let change = {
+            let mut state = self.lock();
+            SubscriptionChange {
+                unsubscribed: tokens
+                    .iter()
+                    .copied()
+                    .filter(|token| state.entries.remove(token).is_some())
+                    .collect(),
+                ..Default::default()
+            }
+        };
+        self.apply(change).await
+    }
+
+    /// Unsubscribe tokens, other than pinned ones, not used for `max_idle`.
+    pub async fn evict_idle(&self, max_idle: Duration) -> Result<SubscriptionChange, TickerError> {
+        let change = {
+            let mut state = self.lock();
+            let mut change = SubscriptionChange::default();
+            state.entries.retain(|token, entry| {
+                let keep = entry.priority == SubscriptionPriority::Pinned
+                    || entry.used_at.elapsed() < max_idle;
+                if !keep {
+                    change.unsubscribed.push(*token);
+                }
+                keep
+            });
+            change.unsubscribed.sort_unstable();
+            change
+        };
+        self.apply(change).await
+    }
+
+    /// Mark `token` as used, e.g. because the scanner looked at its ticks.
+    pub fn touch(&self, token: u32) {
+        self.lock().touch(token);
+    }
+
+    /// Mark the token of a tick as used; other events are ignored.
+    pub fn on_event(&self, event: &TickerEvent) {
+        if let TickerEvent::Tick(tick) = event {
+            self.touch(tick.instrument_token);
+        }
+    }
+
+    pub fn is_subscribed(&self, token: u32) -> bool {
+        self.lock().entries.contains_key(&token)
+    }
+
+    /// Subscribed tokens in ascending order.
+    pub fn subscribed(&self) -> Vec<u32> {
+        let mut tokens: Vec<u32> = self.lock().entries.keys().copied().collect();
+        tokens.sort_unstable();
+        tokens
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    fn admit(
+        &self,
+        state: &mut State,
+        token: u32,
+        priority: SubscriptionPriority,
+        change: &mut SubscriptionChange,
+    ) {
+        if let Some(entry) = state.entries.get_mut(&token) {
+            entry.priority = priority;
+            state.touch(token);
+            return;
+        }
+        if state.entries.len() >= self.budget {
+            let Some(victim) = state.victim(priority) else {
+                change.rejected.push(token);
+                return;
+            };
+            state.entries.remove(&victim);
+            // A token subscribed earlier in the same call never reaches the ticker
+            match change.subscribed.iter().position(|t| *t == victim) {
+                Some(i) => {
+                    change.subscribed.remove(i);
+                }
+                None => change.unsubscribed.push(victim),
+            }
+        }
+        state.insert(token, priority);
+        change.subscribed.push(token);
+    }
+
+    // Unsubscribe first so the ticker never holds more than the budget
+    async fn apply(&self, change: SubscriptionChange) -> Result<SubscriptionChange, TickerError> {
+        if !change.unsubscribed.is_empty() {
+            self.handle.unsubscribe(change.unsubscribed.clone()).await?;
+        }
+        if !change.subscribed.is_empty() {
+            self.handle.subscribe(change.subscribed.clone()).await?;
+            if self.mode != Mode::Quote {
+                self.handle
+                    .set_mode(self.mode, change.subscribed.clone())
+                    .await?;
+            }
+        }
+        Ok(change)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, State> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
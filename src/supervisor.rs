@@ -0,0 +1,94 @@
+//! Keeping a ticker running across access token expiry.
+//!
+//! Kite access tokens expire daily, and a ticker whose token is rejected stops with
+//! [`TickerErrorKind::AuthInvalid`]. A [`TickerSupervisor`] owns the ticker's serve loop:
+//! when it stops on a rejected token or after running out of reconnect attempts, the
+//! supervisor fetches a fresh token from a [`TokenProvider`] and starts the ticker again.
+//! The [`TickerHandle`](crate::ticker::TickerHandle) stays valid throughout and the
+//! subscriptions are sent again on the new connection:
+//!
+//! ```ignore
+//! let (ticker, handle) = Ticker::builder(&api_key, &access_token).build()?;
+//! tokio::spawn(TickerSupervisor::new(ticker, token_provider).run());
+//! handle.subscribe(vec![408065]).await?;
+//! ```
+
+use std::sync::Arc;
+use web_time::Duration;
+
+use crate::compat;
+use crate::session::TokenProvider;
+use crate::ticker::{Ticker, TickerError, TickerErrorKind, TickerEvent};
+
+/// TickerSupervisor restarts a [`Ticker`] with a fresh access token; see the
+/// [module docs](self).
+pub struct TickerSupervisor {
+    ticker: Ticker,
+    tokens: Arc<dyn TokenProvider>,
+    max_restarts: u32,
+    restart_delay: Duration,
+}
+
+impl TickerSupervisor {
+    pub fn new<P: TokenProvider + 'static>(ticker: Ticker, tokens: P) -> Self {
+        Self {
+            ticker,
+            tokens: Arc::new(tokens),
+            max_restarts: 5,
+            restart_delay: Duration::from_secs(2),
+        }
+    }
+
+    /// Give up after `count` restarts in a row that did not get a connection. Defaults
+    /// to 5.
+    pub fn max_restarts(mut self, count: u32) -> Self {
+        self.max_restarts = count;
+        self
+    }
+
+    /// Wait before asking for a new token. Defaults to 2 seconds.
+    pub fn restart_delay(mut self, delay: Duration) -> Self {
+        self.restart_delay = delay;
+        self
+    }
+
+    /// Serve the ticker, restarting it as needed. Returns when it stops for any other
+    /// reason, when the provider fails, or once the restarts run out.
+    pub async fn run(mut self) -> Result<(), TickerError> {
+        let mut resubscribe = false;
+        let mut failed_restarts = 0;
+        loop {
+            let connected_at = self.ticker.connected_at();
+            let error = match self.ticker.serve_connections(resubscribe).await {
+                Ok(()) => return Ok(()),
+                Err(error) => error,
+            };
+            if !matches!(
+                error.kind,
+                TickerErrorKind::AuthInvalid | TickerErrorKind::NoReconnect
+            ) {
+                return Err(error);
+            }
+            if self.ticker.connected_at() != connected_at {
+                failed_restarts = 0;
+            }
+            if failed_restarts >= self.max_restarts {
+                return Err(error);
+            }
+            failed_restarts += 1;
+
+            self.ticker
+                .send_event(TickerEvent::Warning(format!(
+                    "restarting the ticker with a new access token after: {}",
+                    error.message
+                )))
+                .await;
+            compat::sleep(self.restart_delay).await;
+            let token = self.tokens.access_token().await.map_err(|e| {
+                TickerError::new(format!("failed to get a new access token: {}", e))
+            })?;
+            self.ticker.set_access_token(token);
+            resubscribe = true;
+        }
+    }
+}
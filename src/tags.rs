@@ -0,0 +1,246 @@
+//! Namespaced order tags for strategies.
+//!
+//! Kite stores a free-form tag of up to [`MAX_TAG_LENGTH`] characters on every order and
+//! echoes it back in the order book and on order updates. [`TagRegistry`] generates tags of
+//! the form `strategy:leg:id`, falls back to a hashed form when that does not fit, and maps
+//! tags, and the orders and trades placed with them, back to the strategy and leg.
+//!
+//! ```
+//! use kiteconnect_rs::tags::TagRegistry;
+//!
+//! let registry = TagRegistry::new();
+//! let tag = registry.generate("ironfly", "ce1").unwrap();
+//! assert!(tag.starts_with("ironfly:ce1:"));
+//!
+//! let found = registry.lookup(&tag).unwrap();
+//! assert_eq!((found.strategy.as_str(), found.leg.as_str()), ("ironfly", "ce1"));
+//! ```
+//!
+//! Hashed tags cannot be reversed without the registry, so persist [`TagRegistry::entries`]
+//! if lookups have to survive a restart. A client given a registry through
+//! `KiteConnectBuilder::tag_registry` refuses orders tagged with anything the registry did
+//! not generate and records the orders it places. Tags are rewritten by clients configured
+//! with `with_tag_prefix`; use either a prefix or a registry on a given client, not both.
+
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use web_time::{SystemTime, UNIX_EPOCH};
+
+use crate::connect::MAX_TAG_LENGTH;
+use crate::models::{self, KiteConnectError};
+use crate::orders::{Order, OrderParams, Trade};
+
+/// Length of the random id in readable tags.
+const ID_LENGTH: usize = 6;
+/// Characters of the strategy name kept in hashed tags.
+const HASHED_PREFIX_LENGTH: usize = 6;
+
+/// StrategyTag is what a generated tag stands for.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct StrategyTag {
+    pub tag: String,
+    pub strategy: String,
+    pub leg: String,
+    /// Unique id of this tag within the strategy and leg.
+    pub id: String,
+}
+
+impl StrategyTag {
+    /// Whether `tag` is the readable `strategy:leg:id` form rather than a hashed one.
+    pub fn is_readable(&self) -> bool {
+        self.tag == format!("{}:{}:{}", self.strategy, self.leg, self.id)
+    }
+}
+
+/// Check that `tag` is accepted by Kite: 1 to 20 ASCII alphanumerics, `:`, `_` or `-`.
+pub fn validate_tag(tag: &str) -> Result<(), KiteConnectError> {
+    if tag.is_empty() || tag.len() > MAX_TAG_LENGTH {
+        return Err(KiteConnectError::invalid_params(format!(
+            "tag `{}` must be 1 to {} characters",
+            tag, MAX_TAG_LENGTH
+        )));
+    }
+    if let Some(c) = tag.chars().find(|c| !is_tag_char(*c)) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "tag `{}` contains invalid character `{}`",
+            tag, c
+        )));
+    }
+    Ok(())
+}
+
+#[derive(Debug, Default)]
+struct Registry {
+    by_tag: HashMap<String, StrategyTag>,
+    // Order id to tag, filled as orders are seen
+    by_order: HashMap<String, String>,
+    seq: u64,
+}
+
+/// TagRegistry generates strategy tags and resolves them back. Clones share the registry.
+#[derive(Debug, Clone, Default)]
+pub struct TagRegistry {
+    inner: Arc<Mutex<Registry>>,
+}
+
+impl TagRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Generate and register a new tag for one leg of a strategy.
+    ///
+    /// `strategy` and `leg` may only contain ASCII alphanumerics, `_` and `-`. The tag is
+    /// `strategy:leg:id` when that fits in [`MAX_TAG_LENGTH`] characters, otherwise the
+    /// first characters of the strategy followed by a hash of the full form.
+    pub fn generate(&self, strategy: &str, leg: &str) -> Result<String, KiteConnectError> {
+        validate_component("strategy", strategy)?;
+        validate_component("leg", leg)?;
+
+        let mut registry = self.lock();
+        loop {
+            registry.seq += 1;
+            let id = new_id(strategy, leg, registry.seq);
+            let readable = format!("{}:{}:{}", strategy, leg, id);
+            let tag = if readable.len() <= MAX_TAG_LENGTH {
+                readable
+            } else {
+                hashed_tag(strategy, &readable)
+            };
+            if registry.by_tag.contains_key(&tag) {
+                continue;
+            }
+
+            registry.by_tag.insert(
+                tag.clone(),
+                StrategyTag {
+                    tag: tag.clone(),
+                    strategy: strategy.to_owned(),
+                    leg: leg.to_owned(),
+                    id,
+                },
+            );
+            return Ok(tag);
+        }
+    }
+
+    /// Generate a tag and set it on `params`, replacing any existing tag.
+    pub fn tag_order(
+        &self,
+        params: &mut OrderParams,
+        strategy: &str,
+        leg: &str,
+    ) -> Result<String, KiteConnectError> {
+        let tag = self.generate(strategy, leg)?;
+        params.tag = Some(tag.clone());
+        Ok(tag)
+    }
+
+    /// Validate a tag about to be sent and return what it stands for. Fails for malformed
+    /// tags and for tags this registry did not generate.
+    pub fn check(&self, tag: &str) -> Result<StrategyTag, KiteConnectError> {
+        validate_tag(tag)?;
+        self.lookup(tag).ok_or_else(|| {
+            KiteConnectError::invalid_params(format!("tag `{}` is not registered", tag))
+        })
+    }
+
+    /// Register a tag restored from [`entries`](Self::entries) of an earlier registry.
+    pub fn insert(&self, entry: StrategyTag) -> Result<(), KiteConnectError> {
+        validate_tag(&entry.tag)?;
+        self.lock().by_tag.insert(entry.tag.clone(), entry);
+        Ok(())
+    }
+
+    /// Every registered tag.
+    pub fn entries(&self) -> Vec<StrategyTag> {
+        self.lock().by_tag.values().cloned().collect()
+    }
+
+    pub fn lookup(&self, tag: &str) -> Option<StrategyTag> {
+        self.lock().by_tag.get(tag).cloned()
+    }
+
+    /// Remember which tag an order was placed with, so its trades can be resolved.
+    pub fn record_order(&self, order_id: &str, tag: &str) {
+        self.lock()
+            .by_order
+            .insert(order_id.to_owned(), tag.to_owned());
+    }
+
+    /// Resolve an order from the order book by its tags, remembering the order id.
+    pub fn lookup_order(&self, order: &Order) -> Option<StrategyTag> {
+        let tags = order.tag.iter().chain(order.tags.iter().flatten());
+        self.resolve(&order.order_id, tags)
+    }
+
+    /// Resolve an order update from the ticker by its tags, remembering the order id.
+    pub fn lookup_order_update(&self, order: &models::Order) -> Option<StrategyTag> {
+        let tags = std::iter::once(&order.tag).chain(order.tags.iter());
+        self.resolve(&order.order_id, tags)
+    }
+
+    /// Resolve a trade through its order. Trades carry no tag, so the order must have been
+    /// seen by [`record_order`](Self::record_order) or one of the `lookup_order` methods.
+    pub fn lookup_trade(&self, trade: &Trade) -> Option<StrategyTag> {
+        let registry = self.lock();
+        let tag = registry.by_order.get(&trade.order_id)?;
+        registry.by_tag.get(tag).cloned()
+    }
+
+    fn resolve<'a>(
+        &self,
+        order_id: &str,
+        tags: impl Iterator<Item = &'a String>,
+    ) -> Option<StrategyTag> {
+        let mut registry = self.lock();
+        let entry = tags
+            .filter_map(|tag| registry.by_tag.get(tag))
+            .next()
+            .cloned()?;
+        registry
+            .by_order
+            .insert(order_id.to_owned(), entry.tag.clone());
+        Some(entry)
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, Registry> {
+        self.inner.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || matches!(c, ':' | '_' | '-')
+}
+
+fn validate_component(name: &str, value: &str) -> Result<(), KiteConnectError> {
+    if value.is_empty() || value.chars().any(|c| c == ':' || !is_tag_char(c)) {
+        return Err(KiteConnectError::invalid_params(format!(
+            "{} `{}` must be non-empty and contain only ASCII alphanumerics, `_` or `-`",
+            name, value
+        )));
+    }
+    Ok(())
+}
+
+fn new_id(strategy: &str, leg: &str, seq: u64) -> String {
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    let mut hasher = Sha256::new();
+    hasher.update(format!("{}:{}:{}:{}", strategy, leg, nanos, seq));
+    let digest = format!("{:x}", hasher.finalize());
+    digest[..ID_LENGTH].to_owned()
+}
+
+fn hashed_tag(strategy: &str, readable: &str) -> String {
+    let prefix: String = strategy.chars().take(HASHED_PREFIX_LENGTH).collect();
+    let mut hasher = Sha256::new();
+    hasher.update(readable);
+    let digest = format!("{:x}", hasher.finalize());
+    let hash_len = MAX_TAG_LENGTH - prefix.len() - 1;
+    format!("{}:{}", prefix, &digest[..hash_len])
+}
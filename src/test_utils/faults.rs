@@ -0,0 +1,314 @@
+//! Network fault injection for testing retry and reconnect handling.
+//!
+//! [`FaultProxy`] is a TCP proxy that sits between the client and a mock server (HTTP or
+//! WebSocket) and misbehaves on purpose: it adds latency, drops connections halfway
+//! through a response, truncates bodies, and can disconnect every open connection at once
+//! to simulate a ticker disconnect storm. Faults are drawn from a [`SeededRng`], so a
+//! failing run can be reproduced from its seed.
+//!
+//! ```ignore
+//! let server = KiteMockServer::new().await;
+//! let proxy = FaultProxy::start(&server.base_url, FaultConfig::new(7).drop_rate(0.3)).await?;
+//! let kite = KiteConnect::builder("api_key").base_url(&proxy.url()).build()?;
+//! ```
+
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::watch;
+use url::Url;
+
+use super::generator::SeededRng;
+use crate::compat::{self, TaskHandle};
+use crate::models::KiteConnectError;
+
+const COPY_BUFFER: usize = 8 * 1024;
+
+/// FaultConfig describes how often and how badly a [`FaultProxy`] misbehaves.
+///
+/// Rates are probabilities in `[0, 1]`, drawn once per connection.
+#[derive(Debug, Clone)]
+pub struct FaultConfig {
+    pub seed: u64,
+    /// Extra delay before the first response byte, picked uniformly from the range.
+    pub latency: Option<(Duration, Duration)>,
+    /// Close the connection after sending part of the first response chunk.
+    pub drop_rate: f64,
+    /// Close the connection once `truncate_after` response bytes have been sent.
+    pub truncate_rate: f64,
+    pub truncate_after: usize,
+}
+
+impl FaultConfig {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            latency: None,
+            drop_rate: 0.0,
+            truncate_rate: 0.0,
+            truncate_after: 64,
+        }
+    }
+
+    pub fn latency(mut self, min: Duration, max: Duration) -> Self {
+        self.latency = Some((min, max.max(min)));
+        self
+    }
+
+    pub fn drop_rate(mut self, rate: f64) -> Self {
+        self.drop_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn truncate_rate(mut self, rate: f64) -> Self {
+        self.truncate_rate = rate.clamp(0.0, 1.0);
+        self
+    }
+
+    pub fn truncate_after(mut self, bytes: usize) -> Self {
+        self.truncate_after = bytes;
+        self
+    }
+}
+
+/// The faults chosen for a single proxied connection.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum ConnectionFault {
+    None,
+    Drop,
+    Truncate(usize),
+}
+
+struct FaultState {
+    config: FaultConfig,
+    rng: SeededRng,
+}
+
+impl FaultState {
+    fn next(&mut self) -> (Option<Duration>, ConnectionFault) {
+        let latency = self.config.latency.map(|(min, max)| {
+            let secs = self.rng.range_f64(min.as_secs_f64(), max.as_secs_f64());
+            Duration::from_secs_f64(secs)
+        });
+
+        let roll = self.rng.next_f64();
+        let fault = if roll < self.config.drop_rate {
+            ConnectionFault::Drop
+        } else if roll < self.config.drop_rate + self.config.truncate_rate {
+            ConnectionFault::Truncate(self.config.truncate_after)
+        } else {
+            ConnectionFault::None
+        };
+        (latency, fault)
+    }
+}
+
+#[derive(Debug, Default)]
+struct FaultCounters {
+    connections: AtomicU64,
+    dropped: AtomicU64,
+    truncated: AtomicU64,
+}
+
+/// FaultProxy forwards TCP connections to an upstream server while injecting faults.
+pub struct FaultProxy {
+    addr: SocketAddr,
+    state: Arc<Mutex<FaultState>>,
+    counters: Arc<FaultCounters>,
+    disconnect: watch::Sender<u64>,
+    _task: TaskHandle,
+}
+
+impl FaultProxy {
+    /// Start proxying to `upstream`, given as a URL (`http://127.0.0.1:1234`,
+    /// `ws://...`) or a plain `host:port`.
+    pub async fn start(upstream: &str, config: FaultConfig) -> Result<Self, KiteConnectError> {
+        let upstream = upstream_addr(upstream)?;
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(io_error)?;
+        let addr = listener.local_addr().map_err(io_error)?;
+
+        let state = Arc::new(Mutex::new(FaultState {
+            rng: SeededRng::new(config.seed),
+            config,
+        }));
+        let counters = Arc::new(FaultCounters::default());
+        let (disconnect, _) = watch::channel(0);
+
+        let task = compat::spawn(accept_loop(
+            listener,
+            upstream,
+            state.clone(),
+            counters.clone(),
+            disconnect.clone(),
+        ));
+
+        Ok(Self {
+            addr,
+            state,
+            counters,
+            disconnect,
+            _task: task,
+        })
+    }
+
+    /// Base URL to give the HTTP client instead of the upstream's.
+    pub fn url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// URL to give the ticker instead of the upstream's.
+    pub fn ws_url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Replace the fault configuration for new connections. The RNG is reseeded.
+    pub fn set_config(&self, config: FaultConfig) {
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        state.rng = SeededRng::new(config.seed);
+        state.config = config;
+    }
+
+    /// Close every connection that is currently open.
+    pub fn disconnect_all(&self) {
+        self.disconnect.send_modify(|epoch| *epoch += 1);
+    }
+
+    /// Disconnect everything `times` times, `interval` apart, as a broker having a bad
+    /// minute would.
+    pub async fn disconnect_storm(&self, times: u32, interval: Duration) {
+        for _ in 0..times {
+            self.disconnect_all();
+            compat::sleep(interval).await;
+        }
+    }
+
+    /// Connections accepted so far.
+    pub fn connections(&self) -> u64 {
+        self.counters.connections.load(Ordering::Relaxed)
+    }
+
+    /// Connections dropped mid-response so far.
+    pub fn dropped(&self) -> u64 {
+        self.counters.dropped.load(Ordering::Relaxed)
+    }
+
+    /// Connections whose response was truncated so far.
+    pub fn truncated(&self) -> u64 {
+        self.counters.truncated.load(Ordering::Relaxed)
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    upstream: SocketAddr,
+    state: Arc<Mutex<FaultState>>,
+    counters: Arc<FaultCounters>,
+    disconnect: watch::Sender<u64>,
+) {
+    while let Ok((client, _)) = listener.accept().await {
+        counters.connections.fetch_add(1, Ordering::Relaxed);
+        let (latency, fault) = state.lock().unwrap_or_else(|e| e.into_inner()).next();
+        let counters = counters.clone();
+        let disconnect = disconnect.subscribe();
+
+        tokio::spawn(async move {
+            if let Err(e) =
+                proxy_connection(client, upstream, latency, fault, &counters, disconnect).await
+            {
+                log::debug!("fault proxy connection ended: {}", e);
+            }
+        });
+    }
+}
+
+async fn proxy_connection(
+    client: TcpStream,
+    upstream: SocketAddr,
+    latency: Option<Duration>,
+    fault: ConnectionFault,
+    counters: &FaultCounters,
+    mut disconnect: watch::Receiver<u64>,
+) -> std::io::Result<()> {
+    let server = TcpStream::connect(upstream).await?;
+    let (mut client_read, mut client_write) = client.into_split();
+    let (mut server_read, mut server_write) = server.into_split();
+
+    let requests = async {
+        let mut buf = vec![0; COPY_BUFFER];
+        loop {
+            let n = client_read.read(&mut buf).await?;
+            if n == 0 {
+                return server_write.shutdown().await;
+            }
+            server_write.write_all(&buf[..n]).await?;
+        }
+    };
+
+    let responses = async {
+        let mut buf = vec![0; COPY_BUFFER];
+        let mut sent = 0;
+        let mut first = true;
+        loop {
+            let n = server_read.read(&mut buf).await?;
+            if n == 0 {
+                return client_write.shutdown().await;
+            }
+            if first {
+                first = false;
+                if let Some(latency) = latency {
+                    tokio::time::sleep(latency).await;
+                }
+            }
+
+            let limit = match fault {
+                ConnectionFault::None => n,
+                ConnectionFault::Drop => {
+                    client_write.write_all(&buf[..n / 2]).await?;
+                    counters.dropped.fetch_add(1, Ordering::Relaxed);
+                    return Ok(());
+                }
+                ConnectionFault::Truncate(max) => n.min(max.saturating_sub(sent)),
+            };
+            client_write.write_all(&buf[..limit]).await?;
+            sent += limit;
+            if limit < n {
+                counters.truncated.fetch_add(1, Ordering::Relaxed);
+                return Ok(());
+            }
+        }
+    };
+
+    // Whichever side finishes first, or a disconnect, tears the whole connection down.
+    tokio::select! {
+        result = requests => result,
+        result = responses => result,
+        _ = disconnect.changed() => Ok(()),
+    }
+}
+
+fn upstream_addr(upstream: &str) -> Result<SocketAddr, KiteConnectError> {
+    let host_port = match Url::parse(upstream) {
+        Ok(url) if url.has_host() => {
+            let host = url.host_str().unwrap_or_default();
+            let port = url
+                .port_or_known_default()
+                .ok_or_else(|| KiteConnectError::invalid_params("upstream URL has no port"))?;
+            format!("{}:{}", host, port)
+        }
+        _ => upstream.to_owned(),
+    };
+    host_port.parse().map_err(|_| {
+        KiteConnectError::invalid_params(format!("bad upstream address: {}", upstream))
+    })
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("fault proxy: {}", e))
+}
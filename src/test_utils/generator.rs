@@ -0,0 +1,380 @@
+use chrono::{TimeZone, Utc};
+use std::collections::HashMap;
+
+use crate::{
+    instruments::tick_decimals,
+    markets::QuoteData,
+    models::{DepthItem, OHLC, Tick, time::Time},
+    orders::Order,
+    portfolio::Position,
+    ticker::Mode,
+};
+
+/// Default tick size used for generated prices.
+const DEFAULT_TICK_SIZE: f64 = 0.05;
+
+/// Maximum move of a single random-walk step, as a fraction of the current price.
+const MAX_STEP_FRACTION: f64 = 0.002;
+
+/// SeededRng is a small SplitMix64 generator so fixtures are reproducible from a seed.
+#[derive(Debug, Clone)]
+pub struct SeededRng {
+    state: u64,
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Returns a float in `[0, 1)`.
+    pub fn next_f64(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Returns an integer in `[low, high]`.
+    pub fn range_u32(&mut self, low: u32, high: u32) -> u32 {
+        if high <= low {
+            return low;
+        }
+        low + (self.next_u64() % (high - low + 1) as u64) as u32
+    }
+
+    /// Returns a float in `[low, high)`.
+    pub fn range_f64(&mut self, low: f64, high: f64) -> f64 {
+        low + (high - low) * self.next_f64()
+    }
+}
+
+/// Running state of a single instrument's random walk.
+#[derive(Debug, Clone)]
+struct InstrumentState {
+    ohlc: OHLC,
+    last_price: f64,
+    volume: u32,
+    total_buy: u32,
+    total_sell: u32,
+    oi: u32,
+    oi_day_high: u32,
+    oi_day_low: u32,
+    timestamp: i64,
+}
+
+/// MockDataGenerator fabricates realistic market and order fixtures.
+///
+/// Prices follow a per-instrument random walk rounded to the tick size, so successive
+/// ticks for the same token have consistent OHLC, monotonically increasing volume and a
+/// depth ladder that never crosses the last traded price.
+#[derive(Debug, Clone)]
+pub struct MockDataGenerator {
+    rng: SeededRng,
+    tick_size: f64,
+    start_timestamp: i64,
+    instruments: HashMap<u32, InstrumentState>,
+    order_seq: u64,
+}
+
+impl MockDataGenerator {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            rng: SeededRng::new(seed),
+            tick_size: DEFAULT_TICK_SIZE,
+            // 2024-01-15 09:15:00 IST
+            start_timestamp: 1_705_290_300,
+            instruments: HashMap::new(),
+            order_seq: 0,
+        }
+    }
+
+    /// Set the tick size prices are rounded to.
+    pub fn with_tick_size(mut self, tick_size: f64) -> Self {
+        if tick_size > 0.0 {
+            self.tick_size = tick_size;
+        }
+        self
+    }
+
+    /// Access the underlying RNG for custom fixtures.
+    pub fn rng(&mut self) -> &mut SeededRng {
+        &mut self.rng
+    }
+
+    fn round_to_tick(&self, price: f64) -> f64 {
+        let rounded = (price / self.tick_size).round() * self.tick_size;
+        // Trim floating point noise so equality checks in tests behave.
+        let scale = 10f64.powi(tick_decimals(self.tick_size) as i32);
+        (rounded * scale).round() / scale
+    }
+
+    fn state(&mut self, instrument_token: u32) -> &mut InstrumentState {
+        if !self.instruments.contains_key(&instrument_token) {
+            let base = self.rng.range_f64(50.0, 5000.0);
+            let open = self.round_to_tick(base);
+            let oi = self.rng.range_u32(0, 1_000_000);
+            let prev_close = open * self.rng.range_f64(0.98, 1.02);
+            let state = InstrumentState {
+                ohlc: OHLC {
+                    instrument_token: None,
+                    open,
+                    high: open,
+                    low: open,
+                    close: self.round_to_tick(prev_close),
+                },
+                last_price: open,
+                volume: 0,
+                total_buy: 0,
+                total_sell: 0,
+                oi,
+                oi_day_high: oi,
+                oi_day_low: oi,
+                timestamp: self.start_timestamp,
+            };
+            self.instruments.insert(instrument_token, state);
+        }
+        self.instruments.get_mut(&instrument_token).unwrap()
+    }
+
+    /// Advance the random walk of an instrument by one step and return the new last price.
+    pub fn next_price(&mut self, instrument_token: u32) -> f64 {
+        let step = self.rng.range_f64(-MAX_STEP_FRACTION, MAX_STEP_FRACTION);
+        let traded = self.rng.range_u32(1, 500);
+        let oi_change = self.rng.range_u32(0, 2000) as i64 - 1000;
+        let buy_share = self.rng.range_u32(0, traded);
+        let elapsed = self.rng.range_u32(0, 2) as i64;
+        let tick_size = self.tick_size;
+
+        let price = {
+            let state = self.state(instrument_token);
+            (state.last_price * (1.0 + step)).max(tick_size)
+        };
+        let price = self.round_to_tick(price);
+
+        let state = self.state(instrument_token);
+        state.last_price = price;
+        state.ohlc.high = state.ohlc.high.max(price);
+        state.ohlc.low = state.ohlc.low.min(price);
+        state.volume = state.volume.saturating_add(traded);
+        state.total_buy = state.total_buy.saturating_add(buy_share);
+        state.total_sell = state.total_sell.saturating_add(traded - buy_share);
+        state.oi = (state.oi as i64 + oi_change).max(0) as u32;
+        state.oi_day_high = state.oi_day_high.max(state.oi);
+        state.oi_day_low = state.oi_day_low.min(state.oi);
+        state.timestamp += elapsed;
+        price
+    }
+
+    /// Build a five level depth ladder around the last price.
+    pub fn depth(&mut self, last_price: f64) -> crate::models::Depth {
+        let mut depth = crate::models::Depth::default();
+        for level in 0..5 {
+            let offset = self.tick_size * (level as f64 + 1.0);
+            depth.buy[level] = DepthItem {
+                price: self.round_to_tick((last_price - offset).max(0.0)),
+                quantity: self.rng.range_u32(1, 5000),
+                orders: self.rng.range_u32(1, 50),
+            };
+            depth.sell[level] = DepthItem {
+                price: self.round_to_tick(last_price + offset),
+                quantity: self.rng.range_u32(1, 5000),
+                orders: self.rng.range_u32(1, 50),
+            };
+        }
+        depth
+    }
+
+    /// Generate the next tick for an instrument in the given mode.
+    pub fn tick(&mut self, instrument_token: u32, mode: Mode) -> Tick {
+        let last_price = self.next_price(instrument_token);
+        let last_traded_quantity = self.rng.range_u32(1, 500);
+        let state = self.state(instrument_token).clone();
+        let is_index = instrument_token & 0xFF == crate::ticker::INDICES;
+
+        let mut tick = Tick {
+            mode: mode.to_string(),
+            instrument_token,
+            is_tradable: !is_index,
+            is_index,
+            last_price,
+            ..Default::default()
+        };
+
+        if mode == Mode::LTP {
+            return tick;
+        }
+
+        tick.ohlc = state.ohlc.clone();
+        tick.net_change = last_price - state.ohlc.close;
+
+        if !is_index {
+            tick.last_traded_quantity = last_traded_quantity;
+            tick.average_trade_price = self.round_to_tick((state.ohlc.high + state.ohlc.low) / 2.0);
+            tick.volume_traded = state.volume;
+            tick.total_buy_quantity = state.total_buy;
+            tick.total_sell_quantity = state.total_sell;
+        }
+
+        if mode == Mode::Full {
+            tick.timestamp = Time::from_timestamp(state.timestamp);
+            if !is_index {
+                tick.last_trade_time = Time::from_timestamp(state.timestamp);
+                tick.oi = state.oi;
+                tick.oi_day_high = state.oi_day_high;
+                tick.oi_day_low = state.oi_day_low;
+                tick.depth = self.depth(last_price);
+            }
+        }
+
+        tick
+    }
+
+    /// Generate `count` successive ticks for an instrument.
+    pub fn ticks(&mut self, instrument_token: u32, mode: Mode, count: usize) -> Vec<Tick> {
+        (0..count)
+            .map(|_| self.tick(instrument_token, mode))
+            .collect()
+    }
+
+    /// Generate a full quote for an instrument, consistent with its random walk.
+    pub fn quote(&mut self, instrument_token: u32) -> QuoteData {
+        let tick = self.tick(instrument_token, Mode::Full);
+        let band = tick.ohlc.close * 0.2;
+        QuoteData {
+            instrument_token,
+            timestamp: tick.timestamp,
+            last_price: tick.last_price,
+            last_quantity: tick.last_traded_quantity,
+            last_trade_time: tick.last_trade_time,
+            average_price: tick.average_trade_price,
+            volume: tick.volume_traded,
+            buy_quantity: tick.total_buy_quantity,
+            sell_quantity: tick.total_sell_quantity,
+            net_change: tick.net_change,
+            oi: tick.oi as f64,
+            oi_day_high: tick.oi_day_high as f64,
+            oi_day_low: tick.oi_day_low as f64,
+            lower_circuit_limit: self.round_to_tick(tick.ohlc.close - band),
+            upper_circuit_limit: self.round_to_tick(tick.ohlc.close + band),
+            ohlc: tick.ohlc,
+            depth: tick.depth,
+        }
+    }
+
+    /// Generate an order. Orders alternate between COMPLETE and OPEN states.
+    pub fn order(&mut self, instrument_token: u32, tradingsymbol: &str) -> Order {
+        self.order_seq += 1;
+        let price = self.next_price(instrument_token);
+        let quantity = self.rng.range_u32(1, 100) as f64;
+        let complete = self.order_seq % 2 == 1;
+        let filled = if complete { quantity } else { 0.0 };
+        let transaction_type = if self.rng.next_f64() < 0.5 {
+            "BUY"
+        } else {
+            "SELL"
+        };
+        let timestamp = Utc
+            .timestamp_opt(self.start_timestamp + self.order_seq as i64, 0)
+            .single()
+            .map(Time::new)
+            .unwrap_or_default();
+
+        Order {
+            account_id: None,
+            placed_by: "AB1234".to_string(),
+            order_id: format!("{}", 240_115_000_000_000u64 + self.order_seq),
+            exchange_order_id: Some(format!("{}", 1_100_000_000_000_000u64 + self.order_seq)),
+            parent_order_id: None,
+            status: if complete { "COMPLETE" } else { "OPEN" }.to_string(),
+            status_message: None,
+            status_message_raw: None,
+            order_timestamp: timestamp,
+            exchange_update_timestamp: timestamp,
+            exchange_timestamp: timestamp,
+            variety: "regular".to_string(),
+            modified: false,
+            meta: HashMap::new(),
+            exchange: "NSE".to_string(),
+            tradingsymbol: tradingsymbol.to_string(),
+            instrument_token,
+            order_type: "LIMIT".to_string(),
+            transaction_type: transaction_type.to_string(),
+            validity: "DAY".to_string(),
+            validity_ttl: None,
+            product: "CNC".to_string(),
+            quantity,
+            disclosed_quantity: 0.0,
+            price,
+            trigger_price: 0.0,
+            average_price: if complete { price } else { 0.0 },
+            filled_quantity: filled,
+            pending_quantity: quantity - filled,
+            cancelled_quantity: 0.0,
+            auction_number: None,
+            tag: None,
+            tags: None,
+            market_protection: None,
+            guid: None,
+        }
+    }
+
+    /// Generate an open intraday position with consistent buy/sell legs and PnL.
+    pub fn position(&mut self, instrument_token: u32, tradingsymbol: &str) -> Position {
+        let last_price = self.next_price(instrument_token);
+        let close_price = self.state(instrument_token).ohlc.close;
+        let buy_quantity = self.rng.range_u32(1, 200) as i32;
+        let sell_quantity = self.rng.range_u32(0, buy_quantity as u32) as i32;
+        let buy_factor = self.rng.range_f64(0.98, 1.02);
+        let sell_factor = self.rng.range_f64(0.98, 1.02);
+        let buy_price = self.round_to_tick(last_price * buy_factor);
+        let sell_price = if sell_quantity > 0 {
+            self.round_to_tick(last_price * sell_factor)
+        } else {
+            0.0
+        };
+        let quantity = buy_quantity - sell_quantity;
+        let buy_value = buy_price * buy_quantity as f64;
+        let sell_value = sell_price * sell_quantity as f64;
+        let realised = (sell_price - buy_price) * sell_quantity as f64;
+        let unrealised = (last_price - buy_price) * quantity as f64;
+        let pnl = realised + unrealised;
+
+        Position {
+            tradingsymbol: tradingsymbol.to_string(),
+            exchange: "NSE".to_string(),
+            instrument_token,
+            product: "MIS".to_string(),
+            quantity,
+            overnight_quantity: 0,
+            multiplier: 1.0,
+            average_price: buy_price,
+            close_price,
+            last_price,
+            value: sell_value - buy_value,
+            pnl,
+            m2m: pnl,
+            unrealised,
+            realised,
+            buy_quantity,
+            buy_price,
+            buy_value,
+            buy_m2m: buy_value,
+            sell_quantity,
+            sell_price,
+            sell_value,
+            sell_m2m: sell_value,
+            day_buy_quantity: buy_quantity,
+            day_buy_price: buy_price,
+            day_buy_value: buy_value,
+            day_sell_quantity: sell_quantity,
+            day_sell_price: sell_price,
+            day_sell_value: sell_value,
+        }
+    }
+}
@@ -0,0 +1,287 @@
+//! A local stand-in for the Kite HTTP API, built on wiremock.
+//!
+//! Endpoints can be overridden one at a time with a fluent builder, which makes error
+//! paths (rate limits, expired tokens, broken payloads) easy to exercise:
+//!
+//! ```ignore
+//! let server = KiteMockServer::new().await;
+//! server
+//!     .endpoint("POST", "/orders/regular")
+//!     .token_exception()
+//!     .expect(1)
+//!     .mount()
+//!     .await;
+//!
+//! let kite = server.client();
+//! assert!(kite.place_order("regular", params).await.is_err());
+//! ```
+
+use serde_json::{Value, json};
+use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
+use url::form_urlencoded;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
+
+use crate::KiteConnect;
+
+/// API key used by [`KiteMockServer::client`].
+pub const MOCK_API_KEY: &str = "test_api_key";
+/// Access token used by [`KiteMockServer::client`].
+pub const MOCK_ACCESS_TOKEN: &str = "test_access_token";
+
+// Overrides win over fixtures mounted with the default wiremock priority (5).
+const OVERRIDE_PRIORITY: u8 = 1;
+
+/// KiteMockServer runs a mock Kite API on a random local port.
+pub struct KiteMockServer {
+    pub server: MockServer,
+    pub base_url: String,
+}
+
+impl KiteMockServer {
+    pub async fn new() -> Self {
+        let server = MockServer::start().await;
+        let base_url = server.uri();
+
+        Self { server, base_url }
+    }
+
+    /// A client pointed at this server, authenticated with [`MOCK_ACCESS_TOKEN`].
+    pub fn client(&self) -> KiteConnect {
+        KiteConnect::builder(MOCK_API_KEY)
+            .base_url(&self.base_url)
+            .access_token(MOCK_ACCESS_TOKEN)
+            .build()
+            .expect("mock server URL is a valid base URL")
+    }
+
+    /// Serve a fixture file for an endpoint. `.csv` files are served as `text/csv`,
+    /// everything else as JSON.
+    pub async fn mount_fixture(&self, http_method: &str, endpoint: &str, file: impl AsRef<Path>) {
+        let file = file.as_ref();
+        let contents = std::fs::read_to_string(file)
+            .unwrap_or_else(|_| panic!("Failed to read mock file: {}", file.display()));
+
+        let response = if file.extension().is_some_and(|ext| ext == "csv") {
+            ResponseTemplate::new(200)
+                .set_body_string(contents)
+                .insert_header("content-type", "text/csv")
+        } else {
+            let body: Value = serde_json::from_str(&contents)
+                .unwrap_or_else(|_| panic!("Failed to parse JSON from: {}", file.display()));
+            ResponseTemplate::new(200).set_body_json(body)
+        };
+
+        Mock::given(method(http_method))
+            .and(path(endpoint))
+            .respond_with(response)
+            .mount(&self.server)
+            .await;
+    }
+
+    /// Mount a set of `(method, path) -> file` fixtures relative to `dir`.
+    pub async fn mount_fixtures<'a, I>(&self, dir: impl AsRef<Path>, fixtures: I)
+    where
+        I: IntoIterator<Item = ((&'a str, &'a str), &'a str)>,
+    {
+        for ((http_method, endpoint), file) in fixtures {
+            self.mount_fixture(http_method, endpoint, dir.as_ref().join(file))
+                .await;
+        }
+    }
+
+    /// Start overriding the response of a single endpoint. Overrides take precedence over
+    /// fixtures regardless of the order they are mounted in.
+    pub fn endpoint(&self, http_method: &str, endpoint: &str) -> EndpointOverride<'_> {
+        EndpointOverride {
+            server: &self.server,
+            method: http_method.to_uppercase(),
+            path: endpoint.to_owned(),
+            status: 200,
+            body: ResponseBody::Json(json!({"status": "success", "data": null})),
+            headers: Vec::new(),
+            delay: None,
+            expect: None,
+        }
+    }
+
+    /// Requests received so far for `method` and `path`, in arrival order.
+    pub async fn received(&self, http_method: &str, endpoint: &str) -> Vec<ReceivedRequest> {
+        self.server
+            .received_requests()
+            .await
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|r| {
+                r.method.as_str().eq_ignore_ascii_case(http_method) && r.url.path() == endpoint
+            })
+            .map(|r| ReceivedRequest {
+                method: r.method.to_string(),
+                path: r.url.path().to_owned(),
+                query: r.url.query_pairs().into_owned().collect(),
+                headers: r
+                    .headers
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_str().unwrap_or_default().to_owned()))
+                    .collect(),
+                body: r.body,
+            })
+            .collect()
+    }
+
+    /// The single request received for `method` and `path`. Panics if there were none or
+    /// more than one.
+    pub async fn received_one(&self, http_method: &str, endpoint: &str) -> ReceivedRequest {
+        let mut requests = self.received(http_method, endpoint).await;
+        assert_eq!(
+            requests.len(),
+            1,
+            "expected exactly one {} {} request, got {}",
+            http_method,
+            endpoint,
+            requests.len()
+        );
+        requests.remove(0)
+    }
+}
+
+enum ResponseBody {
+    Json(Value),
+    Raw(String),
+}
+
+/// EndpointOverride configures the response for one endpoint. Call `mount` to install it.
+#[must_use = "the override does nothing until it is mounted"]
+pub struct EndpointOverride<'a> {
+    server: &'a MockServer,
+    method: String,
+    path: String,
+    status: u16,
+    body: ResponseBody,
+    headers: Vec<(String, String)>,
+    delay: Option<Duration>,
+    expect: Option<u64>,
+}
+
+impl EndpointOverride<'_> {
+    pub fn status(mut self, status: u16) -> Self {
+        self.status = status;
+        self
+    }
+
+    /// Respond with `data` wrapped in the usual `{"status": "success"}` envelope.
+    pub fn data(mut self, data: Value) -> Self {
+        self.body = ResponseBody::Json(json!({"status": "success", "data": data}));
+        self
+    }
+
+    /// Respond with an arbitrary JSON body, without the envelope.
+    pub fn json(mut self, body: Value) -> Self {
+        self.body = ResponseBody::Json(body);
+        self
+    }
+
+    /// Respond with a raw body, e.g. CSV or deliberately malformed JSON.
+    pub fn body(mut self, body: impl Into<String>) -> Self {
+        self.body = ResponseBody::Raw(body.into());
+        self
+    }
+
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_owned(), value.to_owned()));
+        self
+    }
+
+    /// Hold the response back, to exercise client timeouts.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+
+    /// Fail verification when the server is dropped unless the endpoint was hit exactly
+    /// `times` times.
+    pub fn expect(mut self, times: u64) -> Self {
+        self.expect = Some(times);
+        self
+    }
+
+    /// Respond with a Kite error envelope.
+    pub fn error(self, status: u16, error_type: &str, message: &str) -> Self {
+        self.status(status).json(json!({
+            "status": "error",
+            "message": message,
+            "data": null,
+            "error_type": error_type,
+        }))
+    }
+
+    /// 429 as returned when the API rate limit is exceeded.
+    pub fn rate_limited(self) -> Self {
+        self.error(429, "NetworkException", "Too many requests")
+    }
+
+    /// 403 as returned for an expired or invalidated access token.
+    pub fn token_exception(self) -> Self {
+        self.error(
+            403,
+            "TokenException",
+            "Incorrect `api_key` or `access_token`.",
+        )
+    }
+
+    /// 200 with a body that is not valid JSON.
+    pub fn malformed_json(self) -> Self {
+        self.status(200)
+            .body("{\"status\": \"success\", \"data\": {")
+            .header("content-type", "application/json")
+    }
+
+    pub async fn mount(self) {
+        let mut response = ResponseTemplate::new(self.status);
+        response = match self.body {
+            ResponseBody::Json(body) => response.set_body_json(body),
+            ResponseBody::Raw(body) => response.set_body_string(body),
+        };
+        for (name, value) in &self.headers {
+            response = response.insert_header(name.as_str(), value.as_str());
+        }
+        if let Some(delay) = self.delay {
+            response = response.set_delay(delay);
+        }
+
+        let mut mock = Mock::given(method(self.method.as_str()))
+            .and(path(self.path.as_str()))
+            .respond_with(response)
+            .with_priority(OVERRIDE_PRIORITY);
+        if let Some(times) = self.expect {
+            mock = mock.expect(times);
+        }
+        mock.mount(self.server).await;
+    }
+}
+
+/// ReceivedRequest is a request captured by [`KiteMockServer`].
+#[derive(Debug, Clone)]
+pub struct ReceivedRequest {
+    pub method: String,
+    pub path: String,
+    pub query: HashMap<String, String>,
+    pub headers: HashMap<String, String>,
+    pub body: Vec<u8>,
+}
+
+impl ReceivedRequest {
+    /// The body decoded as `application/x-www-form-urlencoded`.
+    pub fn form(&self) -> HashMap<String, String> {
+        form_urlencoded::parse(&self.body).into_owned().collect()
+    }
+
+    /// The body decoded as JSON.
+    pub fn json(&self) -> Value {
+        serde_json::from_slice(&self.body).expect("request body is not JSON")
+    }
+}
@@ -0,0 +1,19 @@
+//! Utilities for testing code built on top of this crate.
+//!
+//! Enabled with the `test-utils` feature. Nothing in here talks to the real Kite API.
+
+#[cfg(not(target_arch = "wasm32"))]
+pub mod faults;
+pub mod generator;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod mock_server;
+#[cfg(not(target_arch = "wasm32"))]
+pub mod replay;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub use faults::{FaultConfig, FaultProxy};
+pub use generator::{MockDataGenerator, SeededRng};
+#[cfg(not(target_arch = "wasm32"))]
+pub use mock_server::{EndpointOverride, KiteMockServer, ReceivedRequest};
+#[cfg(not(target_arch = "wasm32"))]
+pub use replay::{ReplayTicker, encode_frame, encode_packet};
@@ -0,0 +1,317 @@
+//! A local stand-in for the Kite ticker WebSocket.
+//!
+//! [`ReplayTicker`] accepts ticker connections, tracks the `subscribe`, `unsubscribe` and
+//! `mode` messages each one sends and streams binary tick frames for the subscribed
+//! tokens, encoded exactly like the real feed. Ticks are either generated on the fly by a
+//! [`MockDataGenerator`] or replayed from a recorded list:
+//!
+//! ```ignore
+//! let replay = ReplayTicker::generated(7, Duration::from_millis(250)).await?;
+//! let (ticker, handle) = Ticker::builder("api_key", "access_token")
+//!     .url(replay.url())
+//!     .build()?;
+//! ```
+
+use futures_util::{SinkExt, StreamExt};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashSet, VecDeque};
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::tungstenite::handshake::server::{ErrorResponse, Request, Response};
+use tokio_tungstenite::tungstenite::http::StatusCode;
+
+use super::generator::MockDataGenerator;
+use crate::compat::{self, TaskHandle};
+use crate::models::time::Time;
+use crate::models::{KiteConnectError, Tick};
+use crate::ticker::{BSE_CD, INDICES, Mode, NSE_CD};
+
+/// Where a connection's ticks come from. Every connection gets its own copy, so
+/// reconnecting starts the stream over.
+#[derive(Debug, Clone)]
+enum TickSource {
+    Generated(MockDataGenerator),
+    Recorded(VecDeque<Tick>),
+}
+
+impl TickSource {
+    /// Ticks for the next frame. A generator produces one tick per subscribed token; a
+    /// recording yields its next tick for a subscribed token.
+    fn next_frame(&mut self, modes: &BTreeMap<u32, Mode>) -> Vec<Vec<u8>> {
+        match self {
+            TickSource::Generated(generator) => modes
+                .iter()
+                .map(|(&token, &mode)| encode_packet(&generator.tick(token, mode), mode))
+                .collect(),
+            TickSource::Recorded(ticks) => {
+                if modes.is_empty() {
+                    return Vec::new();
+                }
+                while let Some(tick) = ticks.pop_front() {
+                    if let Some(&mode) = modes.get(&tick.instrument_token) {
+                        return vec![encode_packet(&tick, mode)];
+                    }
+                }
+                Vec::new()
+            }
+        }
+    }
+}
+
+/// ReplayTicker serves the ticker protocol on a random local port.
+pub struct ReplayTicker {
+    addr: SocketAddr,
+    connections: Arc<AtomicU64>,
+    rejected_tokens: RejectedTokens,
+    _task: TaskHandle,
+}
+
+// Access tokens refused at the handshake, shared with the connection tasks
+type RejectedTokens = Arc<Mutex<HashSet<String>>>;
+
+impl ReplayTicker {
+    /// Stream ticks from a [`MockDataGenerator`] seeded with `seed`, one frame every
+    /// `interval`.
+    pub async fn generated(seed: u64, interval: Duration) -> Result<Self, KiteConnectError> {
+        Self::start(
+            TickSource::Generated(MockDataGenerator::new(seed)),
+            interval,
+        )
+        .await
+    }
+
+    /// Replay `ticks` in order, one every `interval`, skipping ticks for tokens that are
+    /// not subscribed. Each tick is sent in the mode its token is subscribed in.
+    pub async fn recorded(ticks: Vec<Tick>, interval: Duration) -> Result<Self, KiteConnectError> {
+        Self::start(TickSource::Recorded(ticks.into()), interval).await
+    }
+
+    async fn start(source: TickSource, interval: Duration) -> Result<Self, KiteConnectError> {
+        let listener = TcpListener::bind("127.0.0.1:0").await.map_err(io_error)?;
+        let addr = listener.local_addr().map_err(io_error)?;
+        let connections = Arc::new(AtomicU64::new(0));
+        let rejected_tokens = RejectedTokens::default();
+        let task = compat::spawn(accept_loop(
+            listener,
+            source,
+            interval,
+            connections.clone(),
+            rejected_tokens.clone(),
+        ));
+
+        Ok(Self {
+            addr,
+            connections,
+            rejected_tokens,
+            _task: task,
+        })
+    }
+
+    /// URL to give the ticker instead of the Kite one.
+    pub fn url(&self) -> String {
+        format!("ws://{}", self.addr)
+    }
+
+    pub fn addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Connections accepted so far.
+    pub fn connections(&self) -> u64 {
+        self.connections.load(Ordering::Relaxed)
+    }
+
+    /// Refuse connections with `access_token` from now on, with a 403 at the handshake
+    /// like Kite does for an expired token.
+    pub fn reject_access_token(&self, access_token: &str) {
+        self.rejected_tokens
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(access_token.to_string());
+    }
+}
+
+async fn accept_loop(
+    listener: TcpListener,
+    source: TickSource,
+    interval: Duration,
+    connections: Arc<AtomicU64>,
+    rejected_tokens: RejectedTokens,
+) {
+    while let Ok((stream, _)) = listener.accept().await {
+        connections.fetch_add(1, Ordering::Relaxed);
+        let source = source.clone();
+        let rejected_tokens = rejected_tokens.clone();
+        tokio::spawn(async move {
+            if let Err(e) = serve_connection(stream, source, interval, rejected_tokens).await {
+                log::debug!("replay ticker connection ended: {}", e);
+            }
+        });
+    }
+}
+
+// The handshake callback must return tungstenite's ErrorResponse
+#[allow(clippy::result_large_err)]
+async fn serve_connection(
+    stream: TcpStream,
+    mut source: TickSource,
+    interval: Duration,
+    rejected_tokens: RejectedTokens,
+) -> Result<(), tokio_tungstenite::tungstenite::Error> {
+    let check_token = |request: &Request, response: Response| {
+        let query = request.uri().query().unwrap_or_default();
+        let rejected = rejected_tokens.lock().unwrap_or_else(|e| e.into_inner());
+        let refused = url::form_urlencoded::parse(query.as_bytes())
+            .any(|(key, value)| key == "access_token" && rejected.contains(value.as_ref()));
+        if refused {
+            let mut error = ErrorResponse::new(Some("TokenException".to_string()));
+            *error.status_mut() = StatusCode::FORBIDDEN;
+            return Err(error);
+        }
+        Ok(response)
+    };
+    let mut ws = tokio_tungstenite::accept_hdr_async(stream, check_token).await?;
+    let mut modes = BTreeMap::new();
+    let mut frames = tokio::time::interval(interval);
+
+    loop {
+        tokio::select! {
+            message = ws.next() => match message {
+                Some(Ok(Message::Text(text))) => apply_message(&mut modes, &text),
+                Some(Ok(Message::Close(_))) | None => return Ok(()),
+                Some(Err(e)) => return Err(e),
+                Some(Ok(_)) => {}
+            },
+            _ = frames.tick() => {
+                let packets = source.next_frame(&modes);
+                if !packets.is_empty() {
+                    ws.send(Message::Binary(encode_frame(&packets).into())).await?;
+                }
+            }
+        }
+    }
+}
+
+/// Apply a client message to the subscribed tokens. New subscriptions start in quote
+/// mode, as on the real feed.
+fn apply_message(modes: &mut BTreeMap<u32, Mode>, text: &str) {
+    let Ok(message) = serde_json::from_str::<Value>(text) else {
+        return;
+    };
+    let tokens =
+        |value: &Value| -> Vec<u32> { serde_json::from_value(value.clone()).unwrap_or_default() };
+
+    match message["a"].as_str() {
+        Some("subscribe") => {
+            for token in tokens(&message["v"]) {
+                modes.entry(token).or_insert(Mode::Quote);
+            }
+        }
+        Some("unsubscribe") => {
+            for token in tokens(&message["v"]) {
+                modes.remove(&token);
+            }
+        }
+        Some("mode") => {
+            let Ok(mode) = serde_json::from_value::<Mode>(message["v"][0].clone()) else {
+                return;
+            };
+            for token in tokens(&message["v"][1]) {
+                modes.insert(token, mode);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// Encode a tick as a binary packet in `mode`, the inverse of `Ticker::parse_packet`.
+/// Fields the mode does not carry are left out.
+pub fn encode_packet(tick: &Tick, mode: Mode) -> Vec<u8> {
+    let token = tick.instrument_token;
+    let segment = token & 0xFF;
+    let price = |value: f64| encode_price(segment, value);
+    let mut packet = Vec::with_capacity(184);
+
+    put_u32(&mut packet, token);
+    put_u32(&mut packet, price(tick.last_price));
+    if mode == Mode::LTP {
+        return packet;
+    }
+
+    if segment == INDICES {
+        put_u32(&mut packet, price(tick.ohlc.high));
+        put_u32(&mut packet, price(tick.ohlc.low));
+        put_u32(&mut packet, price(tick.ohlc.open));
+        put_u32(&mut packet, price(tick.ohlc.close));
+        // Signed change from the close
+        put_u32(&mut packet, (tick.net_change * 100.0).round() as i32 as u32);
+        if mode == Mode::Full {
+            put_u32(&mut packet, timestamp(&tick.timestamp));
+        }
+        return packet;
+    }
+
+    put_u32(&mut packet, tick.last_traded_quantity);
+    put_u32(&mut packet, price(tick.average_trade_price));
+    put_u32(&mut packet, tick.volume_traded);
+    put_u32(&mut packet, tick.total_buy_quantity);
+    put_u32(&mut packet, tick.total_sell_quantity);
+    put_u32(&mut packet, price(tick.ohlc.open));
+    put_u32(&mut packet, price(tick.ohlc.high));
+    put_u32(&mut packet, price(tick.ohlc.low));
+    put_u32(&mut packet, price(tick.ohlc.close));
+    if mode == Mode::Quote {
+        return packet;
+    }
+
+    put_u32(&mut packet, timestamp(&tick.last_trade_time));
+    put_u32(&mut packet, tick.oi);
+    put_u32(&mut packet, tick.oi_day_high);
+    put_u32(&mut packet, tick.oi_day_low);
+    put_u32(&mut packet, timestamp(&tick.timestamp));
+    for item in tick.depth.buy.iter().chain(tick.depth.sell.iter()) {
+        put_u32(&mut packet, item.quantity);
+        put_u32(&mut packet, price(item.price));
+        packet.extend_from_slice(&(item.orders as u16).to_be_bytes());
+        packet.extend_from_slice(&[0, 0]);
+    }
+    packet
+}
+
+/// Wrap packets into one binary frame: a packet count followed by length-prefixed packets.
+pub fn encode_frame(packets: &[Vec<u8>]) -> Vec<u8> {
+    let mut frame = Vec::with_capacity(2 + packets.iter().map(|p| p.len() + 2).sum::<usize>());
+    frame.extend_from_slice(&(packets.len() as u16).to_be_bytes());
+    for packet in packets {
+        frame.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+        frame.extend_from_slice(packet);
+    }
+    frame
+}
+
+fn encode_price(segment: u32, value: f64) -> u32 {
+    let scale = match segment {
+        NSE_CD => 10_000_000.0,
+        BSE_CD => 10_000.0,
+        _ => 100.0,
+    };
+    (value * scale).round() as u32
+}
+
+fn timestamp(time: &Time) -> u32 {
+    time.as_datetime()
+        .map(|dt| dt.timestamp() as u32)
+        .unwrap_or_default()
+}
+
+fn put_u32(packet: &mut Vec<u8>, value: u32) {
+    packet.extend_from_slice(&value.to_be_bytes());
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("replay ticker: {}", e))
+}
@@ -1,13 +1,20 @@
+use crate::clock::MarketClock;
 use crate::compat::{self, TaskHandle, WsMessage};
+use crate::enrich::TickEnricher;
+use crate::instruments::{InstrumentStore, TokenCheck};
 use crate::models::time::Time;
 use crate::models::{DepthItem, Order, Tick, OHLC};
-use async_channel::{Receiver, Sender};
+use crate::risk::ist_now_datetime;
+use async_channel::{Receiver, RecvError, Sender, TryRecvError};
+use futures_util::future::{Either, select};
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
-use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::Arc;
+use std::collections::{HashMap, VecDeque};
+use std::pin::pin;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, Weak};
 use url::Url;
-use web_time::{Duration, SystemTime, UNIX_EPOCH};
+use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use zeroize::Zeroizing;
 
 #[cfg(not(target_arch = "wasm32"))]
 use tokio::sync::RwLock;
@@ -54,6 +61,22 @@ pub const MCX_FO: u32 = 7;
 pub const MCX_SX: u32 = 8;
 pub const INDICES: u32 = 9;
 
+/// Exchange name for the segment encoded in the low byte of an instrument token.
+pub fn token_exchange(instrument_token: u32) -> Option<&'static str> {
+    match instrument_token & 0xff {
+        NSE_CM => Some("NSE"),
+        NSE_FO => Some("NFO"),
+        NSE_CD => Some("CDS"),
+        BSE_CM => Some("BSE"),
+        BSE_FO => Some("BFO"),
+        BSE_CD => Some("BCD"),
+        MCX_FO => Some("MCX"),
+        MCX_SX => Some("MCXSX"),
+        INDICES => Some("INDICES"),
+        _ => None,
+    }
+}
+
 // Packet lengths for each mode
 const MODE_LTP_LENGTH: usize = 8;
 const MODE_QUOTE_INDEX_PACKET_LENGTH: usize = 28;
@@ -72,13 +95,77 @@ const DEFAULT_RECONNECT_MAX_DELAY: Duration = Duration::from_millis(60000);
 const DEFAULT_CONNECT_TIMEOUT: Duration = Duration::from_millis(7000);
 const CONNECTION_CHECK_INTERVAL: Duration = Duration::from_millis(2000);
 const DATA_TIMEOUT_INTERVAL: Duration = Duration::from_millis(5000);
+const DATA_TIMEOUT_MESSAGE: &str = "Data timeout: No data received for 5 seconds";
+// Maximum number of tokens sent in a single message when resubscribing after a reconnect.
+const RESUBSCRIBE_BATCH_SIZE: usize = 1000;
+// Number of recent tick latency samples kept for percentiles.
+const LATENCY_WINDOW: usize = 1000;
+// How long the command handler waits for further set_mode calls before sending a mode change.
+const MODE_COALESCE_WINDOW: Duration = Duration::from_millis(20);
 
 // Default ticker URL
 const TICKER_URL: &str = "wss://ws.kite.trade";
 
+/// Placeholder for credentials removed from URLs and messages.
+const REDACTED: &str = "REDACTED";
+
+/// `url` with the value of every query parameter replaced, so it can be logged without
+/// leaking the credentials the ticker adds to it.
+pub fn redact_url(url: &str) -> String {
+    let Ok(mut parsed) = Url::parse(url) else {
+        return match url.split_once('?') {
+            Some((base, _)) => format!("{}?{}", base, REDACTED),
+            None => url.to_string(),
+        };
+    };
+    let keys: Vec<String> = parsed.query_pairs().map(|(k, _)| k.into_owned()).collect();
+    if keys.is_empty() {
+        return parsed.to_string();
+    }
+    parsed
+        .query_pairs_mut()
+        .clear()
+        .extend_pairs(keys.iter().map(|k| (k.as_str(), REDACTED)));
+    parsed.to_string()
+}
+
 #[derive(Debug, Clone)]
 pub struct TickerError {
     pub message: String,
+    pub kind: TickerErrorKind,
+}
+
+/// TickerErrorKind identifies errors callers may want to handle on their own.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum TickerErrorKind {
+    #[default]
+    Other,
+    /// Strict subscriptions: these tokens are already subscribed.
+    AlreadySubscribed(Vec<u32>),
+    /// Strict subscriptions: these tokens are not subscribed.
+    NotSubscribed(Vec<u32>),
+    /// Token validation: these tokens are unknown or expired.
+    InvalidTokens(TokenCheck),
+    /// The connection was refused for the access token, which has expired or is invalid.
+    AuthInvalid,
+    /// The reconnect attempts ran out.
+    NoReconnect,
+}
+
+impl TickerError {
+    pub fn new(message: impl Into<String>) -> Self {
+        Self {
+            message: message.into(),
+            kind: TickerErrorKind::Other,
+        }
+    }
+
+    fn with_kind(message: impl Into<String>, kind: TickerErrorKind) -> Self {
+        Self {
+            message: message.into(),
+            kind,
+        }
+    }
 }
 
 impl std::fmt::Display for TickerError {
@@ -110,6 +197,7 @@ struct OrderUpdateMessage {
 }
 
 // Event types for the ticker
+#[allow(clippy::large_enum_variant)]
 #[derive(Debug, Clone)]
 pub enum TickerEvent {
     Tick(Tick),
@@ -120,6 +208,301 @@ pub enum TickerEvent {
     Reconnect(i32, Duration),
     NoReconnect(i32),
     OrderUpdate(Order),
+    // Emitted after a reconnect once all stored subscriptions were resent, with the token count.
+    Resubscribed(usize),
+    // A command was partly ignored, e.g. unsubscribing tokens that were never subscribed.
+    Warning(String),
+    // Nobody read the channel this arrives on in time and its oldest events were dropped.
+    // `missed` counts the events that channel dropped so far.
+    SubscriberLagged { missed: u64 },
+}
+
+impl TickerEvent {
+    /// Whether the event is about the connection rather than market data: Connect,
+    /// Close, Reconnect, NoReconnect and Error. These are also delivered to
+    /// [`TickerHandle::lifecycle_events`].
+    pub fn is_lifecycle(&self) -> bool {
+        matches!(
+            self,
+            TickerEvent::Connect
+                | TickerEvent::Close(..)
+                | TickerEvent::Reconnect(..)
+                | TickerEvent::NoReconnect(_)
+                | TickerEvent::Error(_)
+        )
+    }
+}
+
+/// Lifecycle events kept for [`TickerHandle::lifecycle_events`]; older ones are dropped
+/// when nobody reads them.
+pub const LIFECYCLE_EVENT_CAPACITY: usize = 64;
+
+/// Events kept for [`TickerHandle::subscribe_events`]; older ones are dropped when nobody
+/// reads them, so a ticker watched only through its lifecycle events stays bounded.
+pub const EVENT_CAPACITY: usize = 100_000;
+
+/// EventChannel names one of a ticker's event channels.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum EventChannel {
+    /// [`TickerHandle::subscribe_events`]
+    Events,
+    /// [`TickerHandle::lifecycle_events`]
+    Lifecycle,
+    /// A receiver from [`TickerHandle::subscribe_broadcast`]
+    Broadcast,
+}
+
+/// LagHook is called with the channel and the number of events it dropped so far each
+/// time an event channel overflows. It runs on the ticker task and should return quickly.
+pub type LagHook = Arc<dyn Fn(EventChannel, u64) + Send + Sync>;
+
+// A bounded event channel that pushes out its oldest event when full, with the drops it
+// counted and the send count at its last SubscriberLagged event
+#[derive(Debug, Clone)]
+struct LagMonitor {
+    channel: EventChannel,
+    sender: Sender<StampedEvent>,
+    missed: Arc<AtomicU64>,
+    sent: Arc<AtomicU64>,
+    reported_at: Arc<AtomicU64>,
+}
+
+impl LagMonitor {
+    fn new(channel: EventChannel, sender: Sender<StampedEvent>) -> Self {
+        Self {
+            channel,
+            sender,
+            missed: Arc::new(AtomicU64::new(0)),
+            sent: Arc::new(AtomicU64::new(0)),
+            reported_at: Arc::new(AtomicU64::new(0)),
+        }
+    }
+
+    // Send `event`, returning the drop count if it pushed out an unread event
+    #[allow(clippy::result_large_err)] // the error hands back the unsent event
+    fn force_send(
+        &self,
+        event: StampedEvent,
+    ) -> Result<Option<u64>, async_channel::SendError<StampedEvent>> {
+        self.sent.fetch_add(1, Ordering::Relaxed);
+        if self.sender.force_send(event)?.is_none() {
+            return Ok(None);
+        }
+        Ok(Some(self.missed.fetch_add(1, Ordering::Relaxed) + 1))
+    }
+
+    // Whether a lag report is due: one goes out at most once per channel capacity of
+    // sends, so a stalled reader finds at most one in its backlog
+    fn should_report(&self) -> bool {
+        let sent = self.sent.load(Ordering::Relaxed);
+        let reported_at = self.reported_at.load(Ordering::Relaxed);
+        let capacity = self.sender.capacity().unwrap_or(usize::MAX) as u64;
+        if reported_at != 0 && sent - reported_at < capacity {
+            return false;
+        }
+        self.reported_at.store(sent.max(1), Ordering::Relaxed);
+        true
+    }
+}
+
+/// StampedEvent is a [`TickerEvent`] with its sequence number and receive time.
+///
+/// Sequence numbers start at 1 and increase by one for every event a ticker emits, across
+/// reconnects, so gaps and reordering can be detected after events were buffered.
+/// Derefs to the event, so `match &*stamped { TickerEvent::Tick(tick) => .. }` works.
+#[derive(Debug, Clone)]
+pub struct StampedEvent {
+    pub seq: u64,
+    /// Monotonic time the frame carrying the event was read.
+    pub received_at: Instant,
+    /// Wall clock time the frame carrying the event was read, in Unix milliseconds.
+    pub received_at_ms: i64,
+    pub event: TickerEvent,
+}
+
+impl StampedEvent {
+    pub fn event(&self) -> &TickerEvent {
+        &self.event
+    }
+
+    pub fn into_event(self) -> TickerEvent {
+        self.event
+    }
+
+    /// Time since the event was received, e.g. how long it sat in the channel.
+    pub fn age(&self) -> Duration {
+        self.received_at.elapsed()
+    }
+}
+
+impl std::ops::Deref for StampedEvent {
+    type Target = TickerEvent;
+
+    fn deref(&self) -> &TickerEvent {
+        &self.event
+    }
+}
+
+// Receive time shared by every event parsed from one frame
+#[derive(Debug, Clone, Copy)]
+struct Stamp {
+    at: Instant,
+    at_ms: i64,
+}
+
+impl Stamp {
+    fn now() -> Self {
+        let at_ms = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_millis() as i64)
+            .unwrap_or_default();
+        Self {
+            at: Instant::now(),
+            at_ms,
+        }
+    }
+}
+
+// Sending side of the event channels, numbering events as they are sent. Lifecycle
+// events go to the lifecycle channel with the same number, and every event to each
+// broadcast receiver. When a channel pushes out an unread event, the drop is counted and
+// handed to the lag hook, and a SubscriberLagged event with the number of the event it
+// follows goes to the same channel.
+#[derive(Clone)]
+struct EventSender {
+    events: LagMonitor,
+    lifecycle: LagMonitor,
+    // Only the handle's weak references outlive the ticker, so broadcast receivers close
+    // with it
+    broadcast: Arc<Mutex<Vec<LagMonitor>>>,
+    seq: Arc<AtomicU64>,
+    lag_hook: Option<LagHook>,
+}
+
+impl std::fmt::Debug for EventSender {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("EventSender")
+            .field("events", &self.events)
+            .field("lifecycle", &self.lifecycle)
+            .field("broadcast", &self.broadcast)
+            .field("seq", &self.seq)
+            .field("lag_hook", &self.lag_hook.is_some())
+            .finish()
+    }
+}
+
+impl EventSender {
+    async fn send(&self, event: TickerEvent) -> Result<(), async_channel::SendError<StampedEvent>> {
+        self.send_stamped(event, Stamp::now()).await
+    }
+
+    async fn send_stamped(
+        &self,
+        event: TickerEvent,
+        stamp: Stamp,
+    ) -> Result<(), async_channel::SendError<StampedEvent>> {
+        let stamped = self.stamp(event, stamp);
+        if stamped.is_lifecycle() {
+            // The lifecycle channel is closed with the main one, so only that one reports it
+            let _ = self.push(&self.lifecycle, stamped.clone(), stamp);
+        }
+        {
+            let mut broadcast = self.broadcast.lock().unwrap_or_else(|e| e.into_inner());
+            // Receivers that were all dropped close their channel and are let go
+            broadcast.retain(|monitor| self.push(monitor, stamped.clone(), stamp).is_ok());
+        }
+        self.push(&self.events, stamped, stamp)
+    }
+
+    #[allow(clippy::result_large_err)] // the error hands back the unsent event
+    fn push(
+        &self,
+        monitor: &LagMonitor,
+        event: StampedEvent,
+        stamp: Stamp,
+    ) -> Result<(), async_channel::SendError<StampedEvent>> {
+        let seq = event.seq;
+        let Some(missed) = monitor.force_send(event)? else {
+            return Ok(());
+        };
+        self.report_lag(monitor, missed);
+        if monitor.should_report() {
+            let lagged = self.stamped(TickerEvent::SubscriberLagged { missed }, stamp, seq);
+            // The report itself can push out another unread event
+            if let Some(missed) = monitor.force_send(lagged)? {
+                self.report_lag(monitor, missed);
+            }
+        }
+        Ok(())
+    }
+
+    fn report_lag(&self, monitor: &LagMonitor, missed: u64) {
+        if let Some(hook) = &self.lag_hook {
+            hook(monitor.channel, missed);
+        }
+    }
+
+    fn stamp(&self, event: TickerEvent, stamp: Stamp) -> StampedEvent {
+        let seq = self.seq.fetch_add(1, Ordering::Relaxed) + 1;
+        self.stamped(event, stamp, seq)
+    }
+
+    fn stamped(&self, event: TickerEvent, stamp: Stamp, seq: u64) -> StampedEvent {
+        #[cfg(all(feature = "prometheus", not(target_arch = "wasm32")))]
+        crate::metrics::record_ticker_event(&event);
+        StampedEvent {
+            seq,
+            received_at: stamp.at,
+            received_at_ms: stamp.at_ms,
+            event,
+        }
+    }
+}
+
+/// EventReceiver is the receiving side of a ticker's event channel.
+///
+/// [`recv`](Self::recv) yields plain [`TickerEvent`]s; [`recv_stamped`](Self::recv_stamped)
+/// yields them with sequence number and receive time. Clones share the channel, so each
+/// event is delivered to one receiver; use [`TickerHandle::subscribe_broadcast`] for a
+/// channel of one's own.
+#[derive(Debug, Clone)]
+pub struct EventReceiver {
+    receiver: Receiver<StampedEvent>,
+}
+
+impl EventReceiver {
+    pub async fn recv(&self) -> Result<TickerEvent, RecvError> {
+        self.recv_stamped().await.map(StampedEvent::into_event)
+    }
+
+    pub async fn recv_stamped(&self) -> Result<StampedEvent, RecvError> {
+        self.receiver.recv().await
+    }
+
+    pub fn try_recv(&self) -> Result<TickerEvent, TryRecvError> {
+        self.try_recv_stamped().map(StampedEvent::into_event)
+    }
+
+    pub fn try_recv_stamped(&self) -> Result<StampedEvent, TryRecvError> {
+        self.receiver.try_recv()
+    }
+
+    /// Number of events waiting in the channel.
+    pub fn len(&self) -> usize {
+        self.receiver.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.receiver.is_empty()
+    }
+
+    pub fn close(&self) -> bool {
+        self.receiver.close()
+    }
+
+    pub fn is_closed(&self) -> bool {
+        self.receiver.is_closed()
+    }
 }
 
 // AtomicTime wrapper for safe concurrent access
@@ -145,6 +528,14 @@ impl AtomicTime {
             self.timestamp.store(duration.as_secs(), Ordering::Relaxed);
         }
     }
+
+    // Whether the stored time is older than the data timeout
+    fn timed_out(&self) -> bool {
+        SystemTime::now()
+            .duration_since(self.get())
+            .unwrap_or(Duration::ZERO)
+            > DATA_TIMEOUT_INTERVAL
+    }
 }
 
 impl Default for AtomicTime {
@@ -153,85 +544,563 @@ impl Default for AtomicTime {
     }
 }
 
+/// LatencyStats summarises `local receive time - exchange timestamp` over recent ticks.
+///
+/// Exchange timestamps have one-second resolution, so individual samples are coarse;
+/// the percentiles are what matter. Consistently negative values mean the local clock
+/// is behind the exchange clock.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct LatencyStats {
+    pub samples: usize,
+    pub min_ms: i64,
+    pub p50_ms: i64,
+    pub p90_ms: i64,
+    pub p99_ms: i64,
+    pub max_ms: i64,
+    pub mean_ms: f64,
+    /// Time from the latest (re)connect to the first tick on that connection.
+    pub first_tick: Option<Duration>,
+}
+
+// Rolling window of latency samples shared between the ticker and its handles, with the
+// connection state
+#[derive(Debug, Default)]
+struct LatencyTracker {
+    samples: VecDeque<i64>,
+    connected: bool,
+    connected_at: Option<SystemTime>,
+    first_tick: Option<Duration>,
+    last_tick_at: Option<SystemTime>,
+}
+
+impl LatencyTracker {
+    fn connected(&mut self, at: SystemTime) {
+        self.connected = true;
+        self.connected_at = Some(at);
+        self.first_tick = None;
+    }
+
+    fn disconnected(&mut self) {
+        self.connected = false;
+    }
+
+    fn record(&mut self, tick: &Tick, received_at: SystemTime) {
+        self.last_tick_at = Some(received_at);
+        if self.first_tick.is_none() {
+            if let Some(connected_at) = self.connected_at {
+                self.first_tick = received_at.duration_since(connected_at).ok();
+            }
+        }
+
+        let Some(exchange_time) = tick.timestamp.as_datetime() else {
+            return;
+        };
+        let Ok(received) = received_at.duration_since(UNIX_EPOCH) else {
+            return;
+        };
+        let latency = received.as_millis() as i64 - exchange_time.timestamp_millis();
+
+        if self.samples.len() == LATENCY_WINDOW {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(latency);
+    }
+
+    fn stats(&self) -> LatencyStats {
+        let mut sorted: Vec<i64> = self.samples.iter().copied().collect();
+        if sorted.is_empty() {
+            return LatencyStats {
+                first_tick: self.first_tick,
+                ..Default::default()
+            };
+        }
+        sorted.sort_unstable();
+
+        let percentile = |p: usize| sorted[((sorted.len() * p).div_ceil(100)).max(1) - 1];
+        LatencyStats {
+            samples: sorted.len(),
+            min_ms: sorted[0],
+            p50_ms: percentile(50),
+            p90_ms: percentile(90),
+            p99_ms: percentile(99),
+            max_ms: sorted[sorted.len() - 1],
+            mean_ms: sorted.iter().sum::<i64>() as f64 / sorted.len() as f64,
+            first_tick: self.first_tick,
+        }
+    }
+}
+
+// Subscription state shared between the ticker and its handles
+#[derive(Debug, Default)]
+struct Subscriptions {
+    // Effective mode of every subscribed token
+    modes: HashMap<u32, Mode>,
+    // Modes set on tokens that were not subscribed yet, applied when they are
+    pending_modes: HashMap<u32, Mode>,
+}
+
+impl Subscriptions {
+    // Apply a batch of commands and return the messages to send, in order, and warnings.
+    // Mode changes are coalesced per token, last one wins, and flushed before any
+    // subscribe or unsubscribe so they never cross one. Tokens that are already
+    // subscribed, or not subscribed on unsubscribe, are left out of the messages.
+    fn apply(&mut self, commands: Vec<TickerCommand>) -> (Vec<String>, Vec<String>) {
+        let mut messages = Vec::new();
+        let mut warnings = Vec::new();
+        let mut mode_changes = HashMap::new();
+
+        for command in commands {
+            match command {
+                TickerCommand::Subscribe(tokens) => {
+                    self.flush_modes(&mut mode_changes, &mut messages);
+                    let mut new_tokens = Vec::new();
+                    let mut groups: HashMap<Mode, Vec<u32>> = HashMap::new();
+                    for token in tokens {
+                        if self.modes.contains_key(&token) {
+                            continue;
+                        }
+                        let mode = self.pending_modes.remove(&token).unwrap_or(Mode::Quote);
+                        self.modes.insert(token, mode);
+                        new_tokens.push(token);
+                        groups.entry(mode).or_default().push(token);
+                    }
+                    if new_tokens.is_empty() {
+                        continue;
+                    }
+                    messages.extend(input_message("subscribe", &new_tokens));
+                    // Subscribe puts tokens in quote mode, so only other modes need a message
+                    for mode in [Mode::LTP, Mode::Full] {
+                        if let Some(tokens) = groups.get(&mode) {
+                            messages.extend(input_message("mode", &(mode.to_string(), tokens)));
+                        }
+                    }
+                }
+                TickerCommand::Unsubscribe(tokens) => {
+                    self.flush_modes(&mut mode_changes, &mut messages);
+                    let (subscribed, unknown): (Vec<u32>, Vec<u32>) = tokens
+                        .into_iter()
+                        .partition(|token| self.modes.contains_key(token));
+                    for token in &subscribed {
+                        self.modes.remove(token);
+                    }
+                    for token in &unknown {
+                        self.pending_modes.remove(token);
+                    }
+                    if !unknown.is_empty() {
+                        warnings.push(format!(
+                            "Unsubscribe of tokens not subscribed: {:?}",
+                            unknown
+                        ));
+                    }
+                    if !subscribed.is_empty() {
+                        messages.extend(input_message("unsubscribe", &subscribed));
+                    }
+                }
+                TickerCommand::SetMode(mode, tokens) => {
+                    for token in tokens {
+                        mode_changes.insert(token, mode);
+                    }
+                }
+            }
+        }
+
+        self.flush_modes(&mut mode_changes, &mut messages);
+        (messages, warnings)
+    }
+
+    fn flush_modes(&mut self, changes: &mut HashMap<u32, Mode>, messages: &mut Vec<String>) {
+        let mut groups: HashMap<Mode, Vec<u32>> = HashMap::new();
+        for (token, mode) in changes.drain() {
+            match self.modes.get_mut(&token) {
+                Some(current) if *current == mode => {}
+                Some(current) => {
+                    *current = mode;
+                    groups.entry(mode).or_default().push(token);
+                }
+                None => {
+                    self.pending_modes.insert(token, mode);
+                }
+            }
+        }
+
+        for mode in [Mode::LTP, Mode::Quote, Mode::Full] {
+            if let Some(mut tokens) = groups.remove(&mode) {
+                tokens.sort_unstable();
+                messages.extend(input_message("mode", &(mode.to_string(), tokens)));
+            }
+        }
+    }
+}
+
+// Whether the websocket handshake was refused with 401 or 403, as Kite does for an
+// expired or invalid access token
+fn is_auth_rejection(error: &compat::WsError) -> bool {
+    matches!(error.status, Some(401 | 403))
+}
+
+fn input_message<T: Serialize>(action_type: &str, value: &T) -> Option<String> {
+    let input = TickerInput {
+        action_type: action_type.to_string(),
+        value: serde_json::to_value(value).ok()?,
+    };
+    serde_json::to_string(&input).ok()
+}
+
+/// What the ticker does with subscribe calls that fail token validation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenValidation {
+    /// Subscribe anyway and emit a [`TickerEvent::Warning`].
+    Warn,
+    /// Fail the call with [`TickerErrorKind::InvalidTokens`].
+    Reject,
+}
+
+// Instrument dump that subscribe calls are checked against
+struct TokenValidator {
+    store: Arc<InstrumentStore>,
+    action: TokenValidation,
+}
+
+impl TokenValidator {
+    fn check(&self, tokens: &[u32]) -> TokenCheck {
+        self.store
+            .check_tokens(tokens, ist_now_datetime().date_naive())
+    }
+}
+
+// Applies handle commands to the subscription state and queues the resulting messages
+#[derive(Clone)]
+struct CommandProcessor {
+    command_rx: Receiver<TickerCommand>,
+    subscriptions: Arc<RwLock<Subscriptions>>,
+    token_validator: Arc<Mutex<Option<TokenValidator>>>,
+    sender: EventSender,
+    ws_tx: Sender<String>,
+}
+
+impl CommandProcessor {
+    // Process `command` together with the commands queued behind it
+    async fn process(&self, command: TickerCommand) {
+        let mut commands = vec![command];
+        while let Ok(command) = self.command_rx.try_recv() {
+            commands.push(command);
+        }
+        // Give rapid set_mode calls a moment to arrive so they go out as one message
+        if commands
+            .iter()
+            .any(|c| matches!(c, TickerCommand::SetMode(..)))
+        {
+            compat::sleep(MODE_COALESCE_WINDOW).await;
+            while let Ok(command) = self.command_rx.try_recv() {
+                commands.push(command);
+            }
+        }
+
+        let mut warnings = Vec::new();
+        if let Some(validator) = self
+            .token_validator
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+        {
+            for command in &commands {
+                if let TickerCommand::Subscribe(tokens) = command {
+                    let check = validator.check(tokens);
+                    if !check.is_ok() {
+                        warnings.push(format!("Subscribed to {}", check));
+                    }
+                }
+            }
+        }
+
+        let messages = {
+            #[cfg(not(target_arch = "wasm32"))]
+            let mut subscriptions = self.subscriptions.write().await;
+            #[cfg(target_arch = "wasm32")]
+            let mut subscriptions = self.subscriptions.write().unwrap();
+            let (messages, skipped) = subscriptions.apply(commands);
+            warnings.extend(skipped);
+            messages
+        };
+        for warning in warnings {
+            let _ = self.sender.send(TickerEvent::Warning(warning)).await;
+        }
+
+        for msg in messages {
+            if let Err(e) = self.ws_tx.send(msg).await {
+                let _ = self
+                    .sender
+                    .send(TickerEvent::Error(format!(
+                        "Failed to queue WebSocket message: {}",
+                        e
+                    )))
+                    .await;
+            }
+        }
+    }
+}
+
 // Handle for controlling the ticker after it starts
 #[derive(Clone)]
 pub struct TickerHandle {
     command_sender: Sender<TickerCommand>,
-    event_receiver: Receiver<TickerEvent>,
+    event_receiver: EventReceiver,
+    lifecycle_receiver: EventReceiver,
+    latency: Arc<Mutex<LatencyTracker>>,
+    subscriptions: Arc<RwLock<Subscriptions>>,
+    strict_subscriptions: Arc<AtomicBool>,
+    token_validator: Arc<Mutex<Option<TokenValidator>>>,
+    lifecycle_missed: Arc<AtomicU64>,
+    missed: Arc<AtomicU64>,
+    broadcast: Weak<Mutex<Vec<LagMonitor>>>,
 }
 
 impl TickerHandle {
+    /// Subscribe to `tokens` in quote mode. Tokens that are already subscribed are skipped,
+    /// or rejected with [`TickerErrorKind::AlreadySubscribed`] under strict subscriptions.
     pub async fn subscribe(&self, tokens: Vec<u32>) -> Result<(), TickerError> {
+        if let Some(validator) = self
+            .token_validator
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .filter(|validator| validator.action == TokenValidation::Reject)
+        {
+            let check = validator.check(&tokens);
+            if !check.is_ok() {
+                return Err(TickerError::with_kind(
+                    format!("Invalid tokens: {}", check),
+                    TickerErrorKind::InvalidTokens(check),
+                ));
+            }
+        }
+        if self.strict_subscriptions.load(Ordering::Relaxed) {
+            let subscribed = self.filter_tokens(&tokens, true).await;
+            if !subscribed.is_empty() {
+                return Err(TickerError::with_kind(
+                    format!("Tokens already subscribed: {:?}", subscribed),
+                    TickerErrorKind::AlreadySubscribed(subscribed),
+                ));
+            }
+        }
         self.command_sender
             .send(TickerCommand::Subscribe(tokens))
             .await
-            .map_err(|_| TickerError {
-                message: "Failed to send subscribe command".to_string(),
-            })
+            .map_err(|_| TickerError::new("Failed to send subscribe command"))
     }
 
+    /// Unsubscribe from `tokens`. Tokens that are not subscribed are skipped with a
+    /// [`TickerEvent::Warning`], or rejected with [`TickerErrorKind::NotSubscribed`] under
+    /// strict subscriptions.
     pub async fn unsubscribe(&self, tokens: Vec<u32>) -> Result<(), TickerError> {
+        if self.strict_subscriptions.load(Ordering::Relaxed) {
+            let unknown = self.filter_tokens(&tokens, false).await;
+            if !unknown.is_empty() {
+                return Err(TickerError::with_kind(
+                    format!("Tokens not subscribed: {:?}", unknown),
+                    TickerErrorKind::NotSubscribed(unknown),
+                ));
+            }
+        }
         self.command_sender
             .send(TickerCommand::Unsubscribe(tokens))
             .await
-            .map_err(|_| TickerError {
-                message: "Failed to send unsubscribe command".to_string(),
-            })
+            .map_err(|_| TickerError::new("Failed to send unsubscribe command"))
     }
 
     pub async fn set_mode(&self, mode: Mode, tokens: Vec<u32>) -> Result<(), TickerError> {
         self.command_sender
             .send(TickerCommand::SetMode(mode, tokens))
             .await
-            .map_err(|_| TickerError {
-                message: "Failed to send set_mode command".to_string(),
-            })
+            .map_err(|_| TickerError::new("Failed to send set_mode command"))
     }
 
-    pub fn subscribe_events(&self) -> Receiver<TickerEvent> {
+    /// All events: market data, order updates and connection lifecycle. Up to
+    /// [`EVENT_CAPACITY`] unread events are kept, dropping the oldest; dropped events show
+    /// as gaps in the sequence numbers and are counted by
+    /// [`missed_events`](Self::missed_events).
+    ///
+    /// The channel is shared: every clone of the receiver, and of this handle, takes events
+    /// from the same queue. Components that each need every event, such as a strategy
+    /// runner next to a dashboard, should use [`subscribe_broadcast`](Self::subscribe_broadcast).
+    pub fn subscribe_events(&self) -> EventReceiver {
         self.event_receiver.clone()
     }
+
+    /// A channel of its own that receives every event sent from now on, whoever else reads
+    /// the ticker's events. Up to [`EVENT_CAPACITY`] unread events are kept, dropping the
+    /// oldest; drops are reported to the lag hook and, once per [`EVENT_CAPACITY`] events,
+    /// as a [`TickerEvent::SubscriberLagged`] on the channel. The channel closes when the
+    /// ticker stops, and is closed from the start if it already has.
+    pub fn subscribe_broadcast(&self) -> EventReceiver {
+        let (sender, receiver) = async_channel::bounded(EVENT_CAPACITY);
+        if let Some(broadcast) = self.broadcast.upgrade() {
+            broadcast
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .push(LagMonitor::new(EventChannel::Broadcast, sender));
+        }
+        EventReceiver { receiver }
+    }
+
+    /// Only the connection lifecycle events (see [`TickerEvent::is_lifecycle`]), for
+    /// health checks that should not wade through ticks. The events are also delivered to
+    /// [`subscribe_events`](Self::subscribe_events) with the same sequence numbers. Up to
+    /// [`LIFECYCLE_EVENT_CAPACITY`] unread events are kept, dropping the oldest.
+    pub fn lifecycle_events(&self) -> EventReceiver {
+        self.lifecycle_receiver.clone()
+    }
+
+    /// Lifecycle events dropped because [`lifecycle_events`](Self::lifecycle_events) was not
+    /// read in time. Drops are reported to the lag hook and, once per
+    /// [`LIFECYCLE_EVENT_CAPACITY`] events, as a [`TickerEvent::SubscriberLagged`] on that
+    /// channel.
+    pub fn missed_lifecycle_events(&self) -> u64 {
+        self.lifecycle_missed.load(Ordering::Relaxed)
+    }
+
+    /// Events dropped because [`subscribe_events`](Self::subscribe_events) was not read in
+    /// time. Drops are reported to the lag hook and, once per [`EVENT_CAPACITY`] events, as
+    /// a [`TickerEvent::SubscriberLagged`] on that channel.
+    pub fn missed_events(&self) -> u64 {
+        self.missed.load(Ordering::Relaxed)
+    }
+
+    /// The mode `token` is streamed in, or `None` if it is not subscribed.
+    ///
+    /// Subscribed tokens are in quote mode until `set_mode` changes them. Commands are
+    /// applied by the ticker while connected, so a call made just before this one may not
+    /// be reflected yet.
+    pub async fn mode_of(&self, token: u32) -> Option<Mode> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let subscriptions = self.subscriptions.read().await;
+        #[cfg(target_arch = "wasm32")]
+        let subscriptions = self.subscriptions.read().unwrap();
+        subscriptions.modes.get(&token).copied()
+    }
+
+    // Tokens from `tokens` whose subscribed state matches `subscribed`
+    async fn filter_tokens(&self, tokens: &[u32], subscribed: bool) -> Vec<u32> {
+        #[cfg(not(target_arch = "wasm32"))]
+        let subscriptions = self.subscriptions.read().await;
+        #[cfg(target_arch = "wasm32")]
+        let subscriptions = self.subscriptions.read().unwrap();
+        tokens
+            .iter()
+            .copied()
+            .filter(|token| subscriptions.modes.contains_key(token) == subscribed)
+            .collect()
+    }
+
+    /// Feed latency and first-tick timing over the most recent ticks.
+    pub fn latency_stats(&self) -> LatencyStats {
+        self.latency.lock().unwrap_or_else(|e| e.into_inner()).stats()
+    }
+
+    /// Whether the ticker holds an open websocket connection.
+    pub fn is_connected(&self) -> bool {
+        self.latency
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .connected
+    }
+
+    /// Time since the last tick arrived, on any connection. `None` before the first tick.
+    pub fn last_tick_age(&self) -> Option<Duration> {
+        let latency = self.latency.lock().unwrap_or_else(|e| e.into_inner());
+        let last_tick_at = latency.last_tick_at?;
+        Some(
+            SystemTime::now()
+                .duration_since(last_tick_at)
+                .unwrap_or_default(),
+        )
+    }
 }
 
 pub struct Ticker {
     api_key: String,
-    access_token: String,
+    access_token: Zeroizing<String>,
     url: String,
     auto_reconnect: bool,
     reconnect_max_retries: i32,
     reconnect_max_delay: Duration,
     connect_timeout: Duration,
-    subscribed_tokens: Arc<RwLock<HashMap<u32, Option<Mode>>>>,
+    ws_options: compat::WsConnectOptions,
+    subscriptions: Arc<RwLock<Subscriptions>>,
+    strict_subscriptions: Arc<AtomicBool>,
+    token_validator: Arc<Mutex<Option<TokenValidator>>>,
+    enricher: Option<TickEnricher>,
+    single_task_mode: bool,
     last_ping_time: Arc<AtomicTime>,
+    latency: Arc<Mutex<LatencyTracker>>,
     // channels
-    event_sender: Sender<TickerEvent>,
+    event_sender: EventSender,
     command_receiver: Option<Receiver<TickerCommand>>,
-    command_sender: Sender<TickerCommand>,
+}
+
+// Credentials are left out so a logged ticker cannot leak them
+impl std::fmt::Debug for Ticker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Ticker")
+            .field("url", &self.redacted_url())
+            .field("auto_reconnect", &self.auto_reconnect)
+            .field("connect_timeout", &self.connect_timeout)
+            .finish_non_exhaustive()
+    }
 }
 
 impl Ticker {
     pub fn new(api_key: String, access_token: String) -> (Self, TickerHandle) {
-        let (event_tx, event_rx) = async_channel::unbounded();
+        let (event_tx, event_rx) = async_channel::bounded(EVENT_CAPACITY);
+        let (lifecycle_tx, lifecycle_rx) = async_channel::bounded(LIFECYCLE_EVENT_CAPACITY);
         let (command_tx, command_rx) = async_channel::unbounded();
+        let latency = Arc::new(Mutex::new(LatencyTracker::default()));
+        let subscriptions = Arc::new(RwLock::new(Subscriptions::default()));
+        let strict_subscriptions = Arc::new(AtomicBool::new(false));
+        let token_validator = Arc::new(Mutex::new(None));
+        let events = LagMonitor::new(EventChannel::Events, event_tx);
+        let lifecycle = LagMonitor::new(EventChannel::Lifecycle, lifecycle_tx);
+        let broadcast = Arc::new(Mutex::new(Vec::new()));
 
         let ticker = Self {
             api_key,
-            access_token,
+            access_token: Zeroizing::new(access_token),
             url: TICKER_URL.to_string(),
             auto_reconnect: true,
             reconnect_max_retries: DEFAULT_RECONNECT_MAX_ATTEMPTS,
             reconnect_max_delay: DEFAULT_RECONNECT_MAX_DELAY,
             connect_timeout: DEFAULT_CONNECT_TIMEOUT,
-            subscribed_tokens: Arc::new(RwLock::new(HashMap::new())),
+            ws_options: compat::WsConnectOptions::default(),
+            subscriptions: subscriptions.clone(),
+            strict_subscriptions: strict_subscriptions.clone(),
+            token_validator: token_validator.clone(),
+            enricher: None,
+            single_task_mode: false,
             last_ping_time: Arc::new(AtomicTime::new()),
-            event_sender: event_tx.clone(),
+            latency: latency.clone(),
+            event_sender: EventSender {
+                events: events.clone(),
+                lifecycle: lifecycle.clone(),
+                broadcast: broadcast.clone(),
+                seq: Arc::new(AtomicU64::new(0)),
+                lag_hook: None,
+            },
             command_receiver: Some(command_rx),
-            command_sender: command_tx.clone(),
         };
 
         let handle = TickerHandle {
             command_sender: command_tx,
-            event_receiver: event_rx,
+            event_receiver: EventReceiver { receiver: event_rx },
+            lifecycle_receiver: EventReceiver {
+                receiver: lifecycle_rx,
+            },
+            latency,
+            subscriptions,
+            strict_subscriptions,
+            token_validator,
+            lifecycle_missed: lifecycle.missed,
+            missed: events.missed,
+            broadcast: Arc::downgrade(&broadcast),
         };
 
         (ticker, handle)
@@ -242,25 +1111,115 @@ impl Ticker {
     }
 
     pub fn set_access_token(&mut self, access_token: String) {
-        self.access_token = access_token;
+        self.access_token = Zeroizing::new(access_token);
+    }
+
+    // When the latest connection was opened, to tell whether a serve loop got anywhere
+    pub(crate) fn connected_at(&self) -> Option<SystemTime> {
+        self.latency
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .connected_at
+    }
+
+    pub(crate) async fn send_event(&self, event: TickerEvent) {
+        let _ = self.event_sender.send(event).await;
+    }
+
+    /// The URL the ticker connects to, with the api_key and access_token values replaced.
+    pub fn redacted_url(&self) -> String {
+        match self.connection_url() {
+            Ok(url) => redact_url(url.as_str()),
+            Err(_) => redact_url(&self.url),
+        }
+    }
+
+    // Ticker URL with the credentials as query params, wiped once connected
+    fn connection_url(&self) -> Result<Zeroizing<String>, TickerError> {
+        let mut url =
+            Url::parse(&self.url).map_err(|e| TickerError::new(format!("Invalid URL: {}", e)))?;
+        url.query_pairs_mut()
+            .append_pair("api_key", &self.api_key)
+            .append_pair("access_token", &self.access_token);
+        Ok(Zeroizing::new(String::from(url)))
+    }
+
+    // Remove the credentials from a message that may quote the connection URL
+    fn redact(&self, message: &str) -> String {
+        let mut message = message.to_string();
+        for secret in [self.access_token.as_str(), self.api_key.as_str()] {
+            if !secret.is_empty() {
+                message = message.replace(secret, REDACTED);
+            }
+        }
+        message
     }
 
     pub fn set_connect_timeout(&mut self, timeout: Duration) {
         self.connect_timeout = timeout;
     }
 
+    /// Configure TLS for `wss://` connections (custom roots, system trust store).
+    pub fn set_tls_options(&mut self, tls: compat::TlsOptions) {
+        self.ws_options.tls = tls;
+    }
+
+    /// Make handles reject duplicate subscribes and unknown unsubscribes with typed errors
+    /// instead of skipping them.
+    ///
+    /// The check runs against the subscriptions the ticker has applied, so it needs a
+    /// connection to see earlier calls.
+    pub fn set_strict_subscriptions(&mut self, enable: bool) {
+        self.strict_subscriptions.store(enable, Ordering::Relaxed);
+    }
+
+    /// Check tokens passed to `subscribe` against an instrument dump, so tokens that do not
+    /// exist or belong to expired contracts are caught instead of silently getting no ticks.
+    pub fn set_token_validation(&mut self, store: Arc<InstrumentStore>, action: TokenValidation) {
+        *self
+            .token_validator
+            .lock()
+            .unwrap_or_else(|e| e.into_inner()) = Some(TokenValidator { store, action });
+    }
+
+    /// Fill `Tick::change` with the open interest, volume and traded value change since the
+    /// previous tick of each instrument.
+    pub fn set_tick_enrichment(&mut self, enable: bool) {
+        self.enricher = enable.then(TickEnricher::new);
+    }
+
+    /// Mark ticks of the pre-open session, as placed by `clock`, as `Tick::indicative`.
+    /// Turns on tick enrichment.
+    pub fn set_pre_open(&mut self, clock: MarketClock) {
+        self.enricher = Some(self.enricher.take().unwrap_or_default().pre_open(clock));
+    }
+
+    /// Run the whole connection in the task that awaits `serve`.
+    ///
+    /// By default the ticker spawns a task for handle commands and one for the data timeout
+    /// next to the read loop. In single task mode the read loop selects over the socket and
+    /// the command channel itself and checks the timeout between reads, so `serve` can run on
+    /// a `current_thread` runtime or inside a `LocalSet` without relying on other tasks
+    /// getting polled.
+    pub fn set_single_task_mode(&mut self, enable: bool) {
+        self.single_task_mode = enable;
+    }
+
+    /// Call `hook` whenever an unread event is dropped, see [`LagHook`].
+    pub fn set_lag_hook(&mut self, hook: impl Fn(EventChannel, u64) + Send + Sync + 'static) {
+        self.event_sender.lag_hook = Some(Arc::new(hook));
+    }
+
     pub fn set_auto_reconnect(&mut self, enable: bool) {
         self.auto_reconnect = enable;
     }
 
     pub fn set_reconnect_max_delay(&mut self, delay: Duration) -> Result<(), TickerError> {
         if delay < RECONNECT_MIN_DELAY {
-            return Err(TickerError {
-                message: format!(
-                    "ReconnectMaxDelay can't be less than {}ms",
-                    RECONNECT_MIN_DELAY.as_millis()
-                ),
-            });
+            return Err(TickerError::new(format!(
+                "ReconnectMaxDelay can't be less than {}ms",
+                RECONNECT_MIN_DELAY.as_millis()
+            )));
         }
         self.reconnect_max_delay = delay;
         Ok(())
@@ -271,6 +1230,12 @@ impl Ticker {
     }
 
     pub async fn serve(mut self) -> Result<(), TickerError> {
+        self.serve_connections(false).await
+    }
+
+    /// Connect, and reconnect as configured, until the ticker gives up. With `resubscribe`
+    /// the stored subscriptions are sent on the first connection too, as after a restart.
+    pub(crate) async fn serve_connections(&mut self, resubscribe: bool) -> Result<(), TickerError> {
         let mut reconnect_attempt = 0;
         // Track whether we received valid data in the last connection
         // This prevents infinite reconnects when auth fails (connection succeeds but closes immediately)
@@ -283,9 +1248,10 @@ impl Ticker {
                     .event_sender
                     .send(TickerEvent::NoReconnect(reconnect_attempt))
                     .await;
-                return Err(TickerError {
-                    message: "Maximum reconnect attempts reached".to_string(),
-                });
+                return Err(TickerError::with_kind(
+                    "Maximum reconnect attempts reached",
+                    TickerErrorKind::NoReconnect,
+                ));
             }
 
             // If its a reconnect then wait exponentially based on reconnect attempt
@@ -300,21 +1266,14 @@ impl Ticker {
                 compat::sleep(next_delay).await;
             }
 
-            // Prepare ticker URL with required params.
-            let mut url = Url::parse(&self.url).map_err(|e| TickerError {
-                message: format!("Invalid URL: {}", e),
-            })?;
-
-            url.query_pairs_mut()
-                .append_pair("api_key", &self.api_key)
-                .append_pair("access_token", &self.access_token);
+            let url = self.connection_url()?;
 
             // Connect to WebSocket with timeout
-            let connection_future = compat::connect_ws(url.as_str());
+            let connection_future = compat::connect_ws(url.as_str(), &self.ws_options);
             match compat::timeout(self.connect_timeout, connection_future).await {
                 Ok(Ok(ws_stream)) => {
                     // Track if this is a reconnection
-                    let is_reconnect = reconnect_attempt > 0;
+                    let is_reconnect = resubscribe || reconnect_attempt > 0;
 
                     // Reset the received_data flag for this connection attempt
                     received_data.store(false, Ordering::SeqCst);
@@ -324,28 +1283,29 @@ impl Ticker {
 
                     // Set last ping time
                     self.last_ping_time.set(SystemTime::now());
+                    self.latency
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .connected(SystemTime::now());
 
-                    // Resubscribe to stored tokens if this is a reconnect
-                    if is_reconnect {
-                        if let Err(e) = self.resubscribe().await {
-                            let _ = self
-                                .event_sender
-                                .send(TickerEvent::Error(format!("Resubscribe failed: {}", e)))
-                                .await;
-                        }
-                    }
-
-                    // Handle the WebSocket connection
+                    // Handle the WebSocket connection, resubscribing to stored tokens on a reconnect
                     let received_data_clone = received_data.clone();
-                    if let Err(e) = self.handle_connection(ws_stream, received_data_clone).await {
-                        let error_msg = e.message.clone();
+                    let handled = self
+                        .handle_connection(ws_stream, received_data_clone, is_reconnect)
+                        .await;
+                    self.latency
+                        .lock()
+                        .unwrap_or_else(|e| e.into_inner())
+                        .disconnected();
+                    if let Err(e) = handled {
+                        let error_msg = self.redact(&e.message);
                         let _ = self
                             .event_sender
                             .send(TickerEvent::Error(error_msg.clone()))
                             .await;
 
                         if !self.auto_reconnect {
-                            return Err(TickerError { message: error_msg });
+                            return Err(TickerError::new(error_msg));
                         }
                     }
 
@@ -356,14 +1316,26 @@ impl Ticker {
                     }
                 }
                 Ok(Err(e)) => {
-                    let error_msg = format!("Connection failed: {}", e);
+                    let error_msg = self.redact(&format!("Connection failed: {}", e));
                     let _ = self
                         .event_sender
                         .send(TickerEvent::Error(error_msg.clone()))
                         .await;
 
+                    // Reconnecting with a rejected access token cannot succeed
+                    if is_auth_rejection(&e) {
+                        let _ = self
+                            .event_sender
+                            .send(TickerEvent::NoReconnect(reconnect_attempt))
+                            .await;
+                        return Err(TickerError::with_kind(
+                            error_msg,
+                            TickerErrorKind::AuthInvalid,
+                        ));
+                    }
+
                     if !self.auto_reconnect {
-                        return Err(TickerError { message: error_msg });
+                        return Err(TickerError::new(error_msg));
                     }
                 }
                 Err(_) => {
@@ -375,7 +1347,7 @@ impl Ticker {
                         .await;
 
                     if !self.auto_reconnect {
-                        return Err(TickerError { message: error_msg });
+                        return Err(TickerError::new(error_msg));
                     }
                 }
             }
@@ -388,30 +1360,56 @@ impl Ticker {
         &mut self,
         mut ws_stream: Box<dyn compat::WebSocketStream>,
         received_data: Arc<std::sync::atomic::AtomicBool>,
+        resubscribe: bool,
     ) -> Result<(), TickerError> {
+        if resubscribe {
+            match self.resubscribe(ws_stream.as_mut()).await {
+                Ok(count) => {
+                    let _ = self.event_sender.send(TickerEvent::Resubscribed(count)).await;
+                }
+                Err(e) => {
+                    let _ = self
+                        .event_sender
+                        .send(TickerEvent::Error(format!("Resubscribe failed: {}", e)))
+                        .await;
+                }
+            }
+        }
+
         // Channel for outgoing WebSocket messages
         let (ws_tx, ws_rx) = async_channel::unbounded::<String>();
 
+        let processor = self
+            .command_receiver
+            .clone()
+            .map(|command_rx| CommandProcessor {
+                command_rx,
+                subscriptions: self.subscriptions.clone(),
+                token_validator: self.token_validator.clone(),
+                sender: self.event_sender.clone(),
+                ws_tx: ws_tx.clone(),
+            });
+        let single_task = self.single_task_mode;
+
+        // Set once no data arrived for DATA_TIMEOUT_INTERVAL; the loop below then drops the
+        // connection so `serve` reconnects
+        let stale = Arc::new(AtomicBool::new(false));
+
         // Run watcher to check last ping time and reconnect if required
-        let reconnect_handler: Option<TaskHandle> = if self.auto_reconnect {
+        let reconnect_handler: Option<TaskHandle> = if self.auto_reconnect && !single_task {
             let sender_checker = self.event_sender.clone();
             let last_ping_time = self.last_ping_time.clone();
+            let stale = stale.clone();
 
             Some(compat::spawn(async move {
                 loop {
                     compat::sleep(CONNECTION_CHECK_INTERVAL).await;
-                    let last_ping = last_ping_time.get();
-                    if SystemTime::now()
-                        .duration_since(last_ping)
-                        .unwrap_or(Duration::ZERO)
-                        > DATA_TIMEOUT_INTERVAL
-                    {
+                    if last_ping_time.timed_out() {
                         // Connection timeout detected - send error event
                         let _ = sender_checker
-                            .send(TickerEvent::Error(
-                                "Data timeout: No data received for 5 seconds".to_string(),
-                            ))
+                            .send(TickerEvent::Error(DATA_TIMEOUT_MESSAGE.to_string()))
                             .await;
+                        stale.store(true, Ordering::SeqCst);
                         return;
                     }
                 }
@@ -421,89 +1419,23 @@ impl Ticker {
         };
 
         // Task to handle command processing
-        let command_handler: Option<TaskHandle> = if let Some(command_rx) = self.command_receiver.take() {
-            let subscribed_tokens = self.subscribed_tokens.clone();
-            let sender = self.event_sender.clone();
-            let ws_tx_clone = ws_tx.clone();
-
-            Some(compat::spawn(async move {
-                while let Ok(command) = command_rx.recv().await {
-                    let message = match command {
-                        TickerCommand::Subscribe(tokens) => {
-                            // Store tokens
-                            {
-                                #[cfg(not(target_arch = "wasm32"))]
-                                let mut subscribed = subscribed_tokens.write().await;
-                                #[cfg(target_arch = "wasm32")]
-                                let mut subscribed = subscribed_tokens.write().unwrap();
-                                for token in &tokens {
-                                    subscribed.insert(*token, None);
-                                }
-                            }
-
-                            let input = TickerInput {
-                                action_type: "subscribe".to_string(),
-                                value: serde_json::to_value(&tokens).unwrap(),
-                            };
-                            serde_json::to_string(&input).ok()
-                        }
-                        TickerCommand::Unsubscribe(tokens) => {
-                            // Remove tokens
-                            {
-                                #[cfg(not(target_arch = "wasm32"))]
-                                let mut subscribed = subscribed_tokens.write().await;
-                                #[cfg(target_arch = "wasm32")]
-                                let mut subscribed = subscribed_tokens.write().unwrap();
-                                for token in &tokens {
-                                    subscribed.remove(token);
-                                }
-                            }
-
-                            let input = TickerInput {
-                                action_type: "unsubscribe".to_string(),
-                                value: serde_json::to_value(&tokens).unwrap(),
-                            };
-                            serde_json::to_string(&input).ok()
-                        }
-                        TickerCommand::SetMode(mode, tokens) => {
-                            // Update mode
-                            {
-                                #[cfg(not(target_arch = "wasm32"))]
-                                let mut subscribed = subscribed_tokens.write().await;
-                                #[cfg(target_arch = "wasm32")]
-                                let mut subscribed = subscribed_tokens.write().unwrap();
-                                for token in &tokens {
-                                    subscribed.insert(*token, Some(mode));
-                                }
-                            }
-
-                            let input = TickerInput {
-                                action_type: "mode".to_string(),
-                                value: serde_json::to_value(&(mode.to_string(), &tokens)).unwrap(),
-                            };
-                            serde_json::to_string(&input).ok()
-                        }
-                    };
-
-                    if let Some(msg) = message {
-                        if let Err(e) = ws_tx_clone.send(msg).await {
-                            let _ = sender
-                                .send(TickerEvent::Error(format!(
-                                    "Failed to queue WebSocket message: {}",
-                                    e
-                                )))
-                                .await;
-                        }
-                    }
+        let command_handler: Option<TaskHandle> = match processor.clone() {
+            Some(processor) if !single_task => Some(compat::spawn(async move {
+                while let Ok(command) = processor.command_rx.recv().await {
+                    processor.process(command).await;
                 }
-            }))
-        } else {
-            None
+            })),
+            _ => None,
         };
 
+        // In single task mode commands and the data timeout are handled by the loop below
+        let mut inline_processor = processor.filter(|_| single_task);
+        let watch_data_timeout = single_task && self.auto_reconnect;
+
         // Main WebSocket loop - handles both reading and writing
         let event_sender = self.event_sender.clone();
         let last_ping_time = self.last_ping_time.clone();
+        let latency = self.latency.clone();
 
         loop {
             // First, send any pending messages (non-blocking)
@@ -518,23 +1450,74 @@ impl Ticker {
                 }
             }
 
+            if watch_data_timeout && last_ping_time.timed_out() {
+                let _ = event_sender
+                    .send(TickerEvent::Error(DATA_TIMEOUT_MESSAGE.to_string()))
+                    .await;
+                stale.store(true, Ordering::SeqCst);
+            }
+            if stale.load(Ordering::SeqCst) {
+                let _ = ws_stream.close().await;
+                break;
+            }
+
             // Then, receive from WebSocket with a short timeout to allow checking for sends
-            let recv_result = compat::timeout(Duration::from_millis(100), ws_stream.recv()).await;
+            let ws_recv = compat::timeout(Duration::from_millis(100), ws_stream.recv());
+            let outcome = match &inline_processor {
+                Some(processor) => {
+                    match select(pin!(ws_recv), pin!(processor.command_rx.recv())).await {
+                        Either::Left((result, _)) => Ok(result),
+                        Either::Right((command, _)) => Err(command),
+                    }
+                }
+                None => Ok(ws_recv.await),
+            };
+            let recv_result = match outcome {
+                Ok(result) => result,
+                Err(Ok(command)) => {
+                    if let Some(processor) = &inline_processor {
+                        processor.process(command).await;
+                    }
+                    continue;
+                }
+                Err(Err(_)) => {
+                    // Every handle was dropped
+                    inline_processor = None;
+                    continue;
+                }
+            };
 
             match recv_result {
                 Ok(Some(Ok(WsMessage::Binary(data)))) => {
+                    let stamp = Stamp::now();
                     // Mark that we received valid data (prevents infinite reconnect on auth failure)
                     received_data.store(true, Ordering::SeqCst);
                     // Update last ping time
                     last_ping_time.set(SystemTime::now());
                     // Trigger message event
-                    let _ = event_sender.send(TickerEvent::Message(data.clone())).await;
+                    let _ = event_sender
+                        .send_stamped(TickerEvent::Message(data.clone()), stamp)
+                        .await;
 
                     // Parse binary message and trigger tick events
                     match Ticker::parse_binary(&data) {
-                        Ok(ticks) => {
+                        Ok(mut ticks) => {
+                            if let Some(enricher) = self.enricher.as_mut() {
+                                for tick in &mut ticks {
+                                    enricher.enrich(tick);
+                                }
+                            }
+                            {
+                                let received_at = SystemTime::now();
+                                let mut latency = latency.lock().unwrap_or_else(|e| e.into_inner());
+                                for tick in &ticks {
+                                    latency.record(tick, received_at);
+                                }
+                            }
                             for tick in ticks {
-                                let _ = event_sender.send(TickerEvent::Tick(tick)).await;
+                                let _ = event_sender
+                                    .send_stamped(TickerEvent::Tick(tick), stamp)
+                                    .await;
                             }
                         }
                         Err(e) => {
@@ -568,7 +1551,9 @@ impl Ticker {
                 }
                 Ok(Some(Err(e))) => {
                     let _ = event_sender
-                        .send(TickerEvent::Error(format!("WebSocket error: {}", e)))
+                        .send(TickerEvent::Error(
+                            self.redact(&format!("WebSocket error: {}", e)),
+                        ))
                         .await;
                     break;
                 }
@@ -594,7 +1579,7 @@ impl Ticker {
         Ok(())
     }
 
-    async fn process_text_message(text: &str, sender: &Sender<TickerEvent>) {
+    async fn process_text_message(text: &str, sender: &EventSender) {
         if let Ok(msg) = serde_json::from_str::<IncomingMessage>(text) {
             match msg.message_type.as_str() {
                 MESSAGE_ERROR => {
@@ -612,46 +1597,71 @@ impl Ticker {
         }
     }
 
-    async fn resubscribe(&self) -> Result<(), TickerError> {
-        let mut tokens = Vec::new();
-        let mut mode_groups: HashMap<Mode, Vec<u32>> = HashMap::new();
-
-        {
+    /// Resend every stored subscription on a fresh connection and return the token count.
+    async fn resubscribe(
+        &self,
+        ws_stream: &mut dyn compat::WebSocketStream,
+    ) -> Result<usize, TickerError> {
+        let (count, messages) = {
             #[cfg(not(target_arch = "wasm32"))]
-            let subscribed = self.subscribed_tokens.read().await;
+            let subscriptions = self.subscriptions.read().await;
             #[cfg(target_arch = "wasm32")]
-            let subscribed = self.subscribed_tokens.read().unwrap();
-            for (&token, &mode_opt) in subscribed.iter() {
-                tokens.push(token);
-                if let Some(mode) = mode_opt {
-                    mode_groups.entry(mode).or_insert_with(Vec::new).push(token);
-                }
-            }
-        }
+            let subscriptions = self.subscriptions.read().unwrap();
+            (
+                subscriptions.modes.len(),
+                Self::resubscribe_messages(&subscriptions.modes, RESUBSCRIBE_BATCH_SIZE),
+            )
+        };
 
-        // Resubscribe to tokens
-        if !tokens.is_empty() {
-            self.command_sender
-                .send(TickerCommand::Subscribe(tokens))
+        for message in messages {
+            ws_stream
+                .send_text(message)
                 .await
-                .map_err(|_| TickerError {
-                    message: "Failed to resubscribe".to_string(),
-                })?;
-        }
-
-        // Set modes for tokens
-        for (mode, mode_tokens) in mode_groups {
-            if !mode_tokens.is_empty() {
-                self.command_sender
-                    .send(TickerCommand::SetMode(mode, mode_tokens))
-                    .await
-                    .map_err(|_| TickerError {
-                        message: "Failed to set mode during resubscribe".to_string(),
-                    })?;
+                .map_err(|e| TickerError::new(e.to_string()))?;
+        }
+
+        Ok(count)
+    }
+
+    /// Build the messages that restore `subscriptions` on a new connection.
+    ///
+    /// Tokens are grouped by mode and sent as a subscribe followed by a mode message per
+    /// batch of at most `batch_size` tokens. Quote mode tokens only get the subscribe, as
+    /// quote is the mode the server subscribes in.
+    pub fn resubscribe_messages(
+        subscriptions: &HashMap<u32, Mode>,
+        batch_size: usize,
+    ) -> Vec<String> {
+        let mut groups: HashMap<Mode, Vec<u32>> = HashMap::new();
+        for (&token, &mode) in subscriptions {
+            groups.entry(mode).or_default().push(token);
+        }
+
+        let mut messages = Vec::new();
+        for mode in [Mode::Quote, Mode::LTP, Mode::Full] {
+            let Some(mut tokens) = groups.remove(&mode) else {
+                continue;
+            };
+            tokens.sort_unstable();
+
+            for batch in tokens.chunks(batch_size.max(1)) {
+                let subscribe = TickerInput {
+                    action_type: "subscribe".to_string(),
+                    value: serde_json::to_value(batch).unwrap(),
+                };
+                messages.extend(serde_json::to_string(&subscribe).ok());
+
+                if mode != Mode::Quote {
+                    let set_mode = TickerInput {
+                        action_type: "mode".to_string(),
+                        value: serde_json::to_value((mode.to_string(), batch)).unwrap(),
+                    };
+                    messages.extend(serde_json::to_string(&set_mode).ok());
+                }
             }
         }
 
-        Ok(())
+        messages
     }
 
     // Binary parsing methods remain the same
@@ -698,9 +1708,7 @@ impl Ticker {
 
     pub fn parse_packet(data: &[u8]) -> Result<Tick, TickerError> {
         if data.len() < 4 {
-            return Err(TickerError {
-                message: "Packet too short".to_string(),
-            });
+            return Err(TickerError::new("Packet too short"));
         }
 
         let instrument_token = u32::from_be_bytes([data[0], data[1], data[2], data[3]]);
@@ -811,9 +1819,10 @@ impl Ticker {
                 }
             }
             _ => {
-                return Err(TickerError {
-                    message: format!("Unknown packet length: {}", data.len()),
-                });
+                return Err(TickerError::new(format!(
+                    "Unknown packet length: {}",
+                    data.len()
+                )));
             }
         }
 
@@ -851,24 +1860,50 @@ impl Ticker {
 
 pub struct TickerBuilder {
     api_key: String,
-    access_token: String,
+    access_token: Zeroizing<String>,
     url: Option<String>,
     auto_reconnect: Option<bool>,
     reconnect_max_retries: Option<i32>,
     reconnect_max_delay: Option<Duration>,
     connect_timeout: Option<Duration>,
+    strict_subscriptions: Option<bool>,
+    token_validation: Option<(Arc<InstrumentStore>, TokenValidation)>,
+    enrich_ticks: Option<bool>,
+    pre_open: Option<MarketClock>,
+    single_task_mode: Option<bool>,
+    lag_hook: Option<LagHook>,
+    tls: compat::TlsOptions,
+    root_certificates_pem: Vec<Vec<u8>>,
+}
+
+impl std::fmt::Debug for TickerBuilder {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("TickerBuilder")
+            .field("api_key", &REDACTED)
+            .field("access_token", &REDACTED)
+            .field("url", &self.url.as_deref().map(redact_url))
+            .finish_non_exhaustive()
+    }
 }
 
 impl TickerBuilder {
     pub fn new(api_key: &str, access_token: &str) -> Self {
         Self {
             api_key: api_key.to_owned(),
-            access_token: access_token.to_owned(),
+            access_token: Zeroizing::new(access_token.to_owned()),
             url: None,
             auto_reconnect: None,
             reconnect_max_retries: None,
             reconnect_max_delay: None,
             connect_timeout: None,
+            strict_subscriptions: None,
+            token_validation: None,
+            enrich_ticks: None,
+            pre_open: None,
+            single_task_mode: None,
+            lag_hook: None,
+            tls: compat::TlsOptions::default(),
+            root_certificates_pem: Vec::new(),
         }
     }
 
@@ -897,8 +1932,98 @@ impl TickerBuilder {
         self
     }
 
-    pub fn build(self) -> Result<(Ticker, TickerHandle), TickerError> {
-        let (mut ticker, handle) = Ticker::new(self.api_key, self.access_token);
+    pub fn strict_subscriptions(mut self, enable: bool) -> Self {
+        self.strict_subscriptions = Some(enable);
+        self
+    }
+
+    pub fn validate_tokens(mut self, store: Arc<InstrumentStore>, action: TokenValidation) -> Self {
+        self.token_validation = Some((store, action));
+        self
+    }
+
+    pub fn enrich_ticks(mut self, enable: bool) -> Self {
+        self.enrich_ticks = Some(enable);
+        self
+    }
+
+    pub fn pre_open(mut self, clock: MarketClock) -> Self {
+        self.pre_open = Some(clock);
+        self
+    }
+
+    pub fn single_task_mode(mut self, enable: bool) -> Self {
+        self.single_task_mode = Some(enable);
+        self
+    }
+
+    pub fn lag_hook(mut self, hook: impl Fn(EventChannel, u64) + Send + Sync + 'static) -> Self {
+        self.lag_hook = Some(Arc::new(hook));
+        self
+    }
+
+    /// Trust an additional root certificate in DER form for the wss connection.
+    pub fn root_certificate_der(mut self, der: Vec<u8>) -> Self {
+        self.tls.root_certificates.push(der);
+        self
+    }
+
+    /// Trust the root certificate(s) in a PEM bundle. Parse errors surface from `build`.
+    pub fn root_certificate_pem(mut self, pem: &[u8]) -> Self {
+        self.root_certificates_pem.push(pem.to_vec());
+        self
+    }
+
+    /// Whether to trust the platform certificate store (enabled by default).
+    /// Disable it together with a custom root to pin the CA.
+    pub fn tls_system_roots(mut self, enable: bool) -> Self {
+        self.tls.use_system_roots = enable;
+        self
+    }
+
+    pub fn build(mut self) -> Result<(Ticker, TickerHandle), TickerError> {
+        if let Err(e) = crate::connect::validate_api_key(&self.api_key) {
+            return Err(TickerError::new(e.to_string()));
+        }
+
+        if self.access_token.trim().is_empty() {
+            return Err(TickerError::new("access_token must not be empty"));
+        }
+
+        if let Some(ref url) = self.url {
+            let parsed =
+                Url::parse(url).map_err(|e| TickerError::new(format!("Invalid URL: {}", e)))?;
+            if !matches!(parsed.scheme(), "ws" | "wss") {
+                return Err(TickerError::new(format!(
+                    "Invalid URL: unsupported scheme '{}', expected ws or wss",
+                    parsed.scheme()
+                )));
+            }
+        }
+
+        if let Some(timeout) = self.connect_timeout {
+            if timeout.is_zero() {
+                return Err(TickerError::new(
+                    "connect_timeout must be greater than zero",
+                ));
+            }
+        }
+
+        for pem in &self.root_certificates_pem {
+            let certs = parse_pem_certificates(pem)?;
+            self.tls.root_certificates.extend(certs);
+        }
+
+        if !self.tls.use_system_roots && self.tls.root_certificates.is_empty() {
+            return Err(TickerError::new(
+                "tls_system_roots(false) requires at least one root certificate",
+            ));
+        }
+
+        // Moves the token's buffer into the ticker instead of copying it
+        let access_token = std::mem::take(&mut *self.access_token);
+        let (mut ticker, handle) = Ticker::new(self.api_key, access_token);
+        ticker.set_tls_options(self.tls);
 
         if let Some(url) = self.url {
             ticker.set_root_url(url);
@@ -920,6 +2045,50 @@ impl TickerBuilder {
             ticker.set_connect_timeout(timeout);
         }
 
+        if let Some(strict) = self.strict_subscriptions {
+            ticker.set_strict_subscriptions(strict);
+        }
+
+        if let Some((store, action)) = self.token_validation {
+            ticker.set_token_validation(store, action);
+        }
+
+        if let Some(enable) = self.enrich_ticks {
+            ticker.set_tick_enrichment(enable);
+        }
+
+        if let Some(clock) = self.pre_open {
+            ticker.set_pre_open(clock);
+        }
+
+        if let Some(enable) = self.single_task_mode {
+            ticker.set_single_task_mode(enable);
+        }
+
+        if let Some(hook) = self.lag_hook {
+            ticker.event_sender.lag_hook = Some(hook);
+        }
+
         Ok((ticker, handle))
     }
 }
+
+#[cfg(not(target_arch = "wasm32"))]
+fn parse_pem_certificates(pem: &[u8]) -> Result<Vec<Vec<u8>>, TickerError> {
+    use rustls::pki_types::{CertificateDer, pem::PemObject};
+
+    let certs = CertificateDer::pem_slice_iter(pem)
+        .map(|cert| cert.map(|c| c.to_vec()))
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(|e| TickerError::new(format!("Invalid PEM certificate: {}", e)))?;
+    if certs.is_empty() {
+        return Err(TickerError::new("PEM bundle contains no certificates"));
+    }
+    Ok(certs)
+}
+
+#[cfg(target_arch = "wasm32")]
+fn parse_pem_certificates(_pem: &[u8]) -> Result<Vec<Vec<u8>>, TickerError> {
+    // The browser owns TLS; custom roots cannot be applied.
+    Ok(Vec::new())
+}
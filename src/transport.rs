@@ -0,0 +1,100 @@
+//! Pluggable HTTP transport.
+//!
+//! Every API call builds a `reqwest::Request` and hands it to the client's [`Transport`].
+//! Without one the request goes out through the client's `reqwest::Client`. A custom
+//! transport can record, replay or rewrite requests, e.g. the cassettes in `vcr`.
+
+use async_trait::async_trait;
+use reqwest::{Client, Request, StatusCode};
+
+use crate::models::KiteConnectError;
+
+/// TransportResponse is the part of an HTTP response the client needs to parse it.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TransportResponse {
+    pub status: StatusCode,
+    pub body: String,
+}
+
+impl TransportResponse {
+    pub fn new(status: StatusCode, body: impl Into<String>) -> Self {
+        Self {
+            status,
+            body: body.into(),
+        }
+    }
+
+    /// Read the status and the body of a `reqwest` response.
+    pub async fn read(response: reqwest::Response) -> Result<Self, KiteConnectError> {
+        let status = response.status();
+        let body = response.text().await?;
+        Ok(Self { status, body })
+    }
+
+    /// Like [`read`](Self::read), but fail with `ResponseTooLarge` once the body is longer
+    /// than `limit` bytes. On native targets the body is read in chunks, so an oversized
+    /// body is never held in memory.
+    pub async fn read_limited(
+        response: reqwest::Response,
+        limit: Option<usize>,
+    ) -> Result<Self, KiteConnectError> {
+        let Some(limit) = limit else {
+            return Self::read(response).await;
+        };
+        if response
+            .content_length()
+            .is_some_and(|length| length > limit as u64)
+        {
+            return Err(KiteConnectError::response_too_large(limit));
+        }
+        let status = response.status();
+
+        #[cfg(not(target_arch = "wasm32"))]
+        let body = {
+            let mut response = response;
+            let mut body = Vec::new();
+            while let Some(chunk) = response.chunk().await? {
+                if body.len() + chunk.len() > limit {
+                    return Err(KiteConnectError::response_too_large(limit));
+                }
+                body.extend_from_slice(&chunk);
+            }
+            String::from_utf8_lossy(&body).into_owned()
+        };
+        // The browser has already buffered the body
+        #[cfg(target_arch = "wasm32")]
+        let body = response.text().await?;
+
+        if body.len() > limit {
+            return Err(KiteConnectError::response_too_large(limit));
+        }
+        Ok(Self { status, body })
+    }
+}
+
+/// Transport sends a fully built request and returns the response.
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+pub trait Transport: Send + Sync {
+    async fn execute(&self, request: Request) -> Result<TransportResponse, KiteConnectError>;
+}
+
+/// The default transport: sends requests with a `reqwest::Client`.
+#[derive(Debug, Clone, Default)]
+pub struct HttpTransport {
+    client: Client,
+}
+
+impl HttpTransport {
+    pub fn new(client: Client) -> Self {
+        Self { client }
+    }
+}
+
+#[cfg_attr(not(target_arch = "wasm32"), async_trait)]
+#[cfg_attr(target_arch = "wasm32", async_trait(?Send))]
+impl Transport for HttpTransport {
+    async fn execute(&self, request: Request) -> Result<TransportResponse, KiteConnectError> {
+        TransportResponse::read(self.client.execute(request).await?).await
+    }
+}
@@ -0,0 +1,411 @@
+//! TWAP execution.
+//!
+//! [`Twap`] works a parent order by slicing its quantity over a time window and executing
+//! each slice with an [`Executor`]. Slice sizes and start times can be randomised so the
+//! children do not form an obvious pattern in the book. Quantity a slice leaves unfilled
+//! is carried into the next one.
+//!
+//! ```ignore
+//! let config = TwapConfig::default()
+//!     .duration(Duration::from_secs(600))
+//!     .slices(10)
+//!     .size_jitter_pct(20.0);
+//! let twap = Twap::start(executor.clone(), token, order_params, 0.05, config)?;
+//! while let Ok(event) = twap.subscribe_events().recv().await {
+//!     if let TwapEvent::Finished(state) = event {
+//!         println!("{:?}: {:?}", state, twap.progress());
+//!         break;
+//!     }
+//! }
+//! ```
+
+use async_channel::{Receiver, Sender};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use web_time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+use crate::compat::{self, TaskHandle};
+use crate::executor::{ExecutionOutcome, ExecutionReport, Executor};
+use crate::models::{KiteConnectError, Order};
+use crate::orders::OrderParams;
+use crate::ticker::TickerEvent;
+
+/// How often a cancelled schedule notices the cancellation while waiting for a slice.
+const CANCEL_POLL_INTERVAL: Duration = Duration::from_millis(100);
+
+/// TwapConfig configures how a parent order is sliced.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwapConfig {
+    /// Window over which the slices are started.
+    pub duration: Duration,
+    pub slices: u32,
+    /// Slice quantities are multiples of this.
+    pub lot_size: i32,
+    /// Each slice is up to this many percent larger or smaller than an even split.
+    pub size_jitter_pct: f64,
+    /// Each slice starts up to this many percent of the slice interval late.
+    pub timing_jitter_pct: f64,
+    /// Seed for the jitter; taken from the clock when None.
+    pub seed: Option<u64>,
+}
+
+impl Default for TwapConfig {
+    fn default() -> Self {
+        Self {
+            duration: Duration::from_secs(300),
+            slices: 10,
+            lot_size: 1,
+            size_jitter_pct: 0.0,
+            timing_jitter_pct: 0.0,
+            seed: None,
+        }
+    }
+}
+
+impl TwapConfig {
+    pub fn duration(mut self, duration: Duration) -> Self {
+        self.duration = duration;
+        self
+    }
+
+    pub fn slices(mut self, slices: u32) -> Self {
+        self.slices = slices;
+        self
+    }
+
+    pub fn lot_size(mut self, lot_size: i32) -> Self {
+        self.lot_size = lot_size;
+        self
+    }
+
+    pub fn size_jitter_pct(mut self, pct: f64) -> Self {
+        self.size_jitter_pct = pct;
+        self
+    }
+
+    pub fn timing_jitter_pct(mut self, pct: f64) -> Self {
+        self.timing_jitter_pct = pct;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+}
+
+/// TwapSlice is one child of a schedule: its quantity and when it starts, relative to
+/// the start of the schedule.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TwapSlice {
+    pub offset: Duration,
+    pub quantity: i32,
+}
+
+/// Split `total_quantity` into the slices described by `config`. Quantities add up to
+/// `total_quantity`, are multiples of the lot size and are never zero, so fewer slices
+/// than configured are returned when there are not enough lots to go round.
+pub fn plan_slices(
+    total_quantity: i32,
+    config: &TwapConfig,
+) -> Result<Vec<TwapSlice>, KiteConnectError> {
+    let lot_size = config.lot_size.max(1);
+    if total_quantity <= 0 || total_quantity % lot_size != 0 {
+        return Err(KiteConnectError::invalid_params(format!(
+            "quantity {} is not a positive multiple of the lot size {}",
+            total_quantity, lot_size
+        )));
+    }
+    if config.slices == 0 {
+        return Err(KiteConnectError::invalid_params(
+            "a TWAP needs at least one slice",
+        ));
+    }
+
+    let lots = total_quantity / lot_size;
+    let slices = (config.slices as i32).min(lots);
+    let interval = config.duration / slices as u32;
+    let size_jitter = config.size_jitter_pct.clamp(0.0, 100.0) / 100.0;
+    let timing_jitter = config.timing_jitter_pct.clamp(0.0, 100.0) / 100.0;
+    let mut rng = Jitter::new(config.seed.unwrap_or_else(clock_seed));
+
+    let mut remaining = lots;
+    let mut plan = Vec::with_capacity(slices as usize);
+    for i in 0..slices {
+        let left = slices - i;
+        let even = remaining as f64 / left as f64;
+        let lots = if left == 1 {
+            remaining
+        } else {
+            let jittered = (even * (1.0 + size_jitter * rng.symmetric())).round() as i32;
+            // Leave at least one lot for every later slice
+            jittered.clamp(1, remaining - (left - 1))
+        };
+        remaining -= lots;
+
+        let delay = interval.mul_f64(timing_jitter * rng.unit());
+        plan.push(TwapSlice {
+            offset: interval * i as u32 + delay,
+            quantity: lots * lot_size,
+        });
+    }
+    Ok(plan)
+}
+
+/// TwapChild is a slice that has been executed.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwapChild {
+    pub order_id: String,
+    pub quantity: i32,
+    /// Latest status, from the execution report or a later order update.
+    pub status: String,
+    pub filled_quantity: f64,
+    pub average_price: f64,
+}
+
+/// State of a schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub enum TwapState {
+    Running,
+    /// Every slice was executed.
+    Completed,
+    Cancelled,
+    /// A slice could not be placed; no further slices were started.
+    Failed(String),
+}
+
+/// TwapProgress is a snapshot of a schedule.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwapProgress {
+    pub state: TwapState,
+    pub target_quantity: i32,
+    pub total_slices: usize,
+    pub children: Vec<TwapChild>,
+}
+
+impl TwapProgress {
+    pub fn filled_quantity(&self) -> f64 {
+        self.children.iter().map(|c| c.filled_quantity).sum()
+    }
+
+    /// Volume-weighted average fill price of the children.
+    pub fn average_price(&self) -> Option<f64> {
+        let filled = self.filled_quantity();
+        (filled > 0.0).then(|| {
+            self.children
+                .iter()
+                .map(|c| c.filled_quantity * c.average_price)
+                .sum::<f64>()
+                / filled
+        })
+    }
+}
+
+/// TwapEvent is published by a running [`Twap`].
+#[derive(Debug, Clone, PartialEq)]
+pub enum TwapEvent {
+    /// A slice finished executing.
+    Slice(ExecutionReport),
+    /// The schedule ended.
+    Finished(TwapState),
+}
+
+/// Twap runs a slicing schedule from a background task.
+///
+/// Events go to an unbounded channel shared by every receiver from
+/// [`subscribe_events`](Self::subscribe_events). Dropping the handle does not stop the
+/// schedule; call [`cancel`](Self::cancel). A slice already being executed finishes its
+/// chase before the schedule stops.
+pub struct Twap {
+    progress: Arc<Mutex<TwapProgress>>,
+    cancelled: Arc<AtomicBool>,
+    event_receiver: Receiver<TwapEvent>,
+    executor: Executor,
+    _task: TaskHandle,
+}
+
+impl Twap {
+    /// Plan the slices of `order_params` and start executing them. Each slice is a copy
+    /// of `order_params` with its own quantity, placed through `executor`.
+    pub fn start(
+        executor: Executor,
+        instrument_token: u32,
+        order_params: OrderParams,
+        tick_size: f64,
+        config: TwapConfig,
+    ) -> Result<Self, KiteConnectError> {
+        let total_quantity = order_params
+            .quantity
+            .ok_or_else(|| KiteConnectError::invalid_params("order has no quantity"))?;
+        let slices = plan_slices(total_quantity, &config)?;
+
+        let progress = Arc::new(Mutex::new(TwapProgress {
+            state: TwapState::Running,
+            target_quantity: total_quantity,
+            total_slices: slices.len(),
+            children: Vec::new(),
+        }));
+        let cancelled = Arc::new(AtomicBool::new(false));
+        let (event_sender, event_receiver) = async_channel::unbounded();
+        let task = compat::spawn(run_schedule(Schedule {
+            executor: executor.clone(),
+            instrument_token,
+            order_params,
+            tick_size,
+            slices,
+            progress: progress.clone(),
+            cancelled: cancelled.clone(),
+            events: event_sender,
+        }));
+        Ok(Self {
+            progress,
+            cancelled,
+            event_receiver,
+            executor,
+            _task: task,
+        })
+    }
+
+    pub fn subscribe_events(&self) -> Receiver<TwapEvent> {
+        self.event_receiver.clone()
+    }
+
+    pub fn progress(&self) -> TwapProgress {
+        lock(&self.progress).clone()
+    }
+
+    /// Stop starting new slices.
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::SeqCst);
+    }
+
+    /// Feed a ticker event. Ticks go to the executor; order updates refresh the children
+    /// they belong to, so fills arriving after a slice was converted to MARKET are counted.
+    pub fn on_event(&self, event: &TickerEvent) {
+        match event {
+            TickerEvent::OrderUpdate(order) => self.on_order_update(order),
+            _ => self.executor.on_event(event),
+        }
+    }
+
+    pub fn on_order_update(&self, order: &Order) {
+        let mut progress = lock(&self.progress);
+        if let Some(child) = progress
+            .children
+            .iter_mut()
+            .find(|c| c.order_id == order.order_id)
+        {
+            child.status = order.status.clone();
+            child.filled_quantity = order.filled_quantity;
+            child.average_price = order.average_price;
+        }
+    }
+}
+
+struct Schedule {
+    executor: Executor,
+    instrument_token: u32,
+    order_params: OrderParams,
+    tick_size: f64,
+    slices: Vec<TwapSlice>,
+    progress: Arc<Mutex<TwapProgress>>,
+    cancelled: Arc<AtomicBool>,
+    events: Sender<TwapEvent>,
+}
+
+async fn run_schedule(schedule: Schedule) {
+    let state = execute_slices(&schedule).await;
+    lock(&schedule.progress).state = state.clone();
+    let _ = schedule.events.send(TwapEvent::Finished(state)).await;
+}
+
+async fn execute_slices(schedule: &Schedule) -> TwapState {
+    let started = Instant::now();
+    let mut carry = 0;
+    for slice in &schedule.slices {
+        while started.elapsed() < slice.offset {
+            if schedule.cancelled.load(Ordering::SeqCst) {
+                return TwapState::Cancelled;
+            }
+            let wait = slice.offset.saturating_sub(started.elapsed());
+            compat::sleep(wait.min(CANCEL_POLL_INTERVAL)).await;
+        }
+        if schedule.cancelled.load(Ordering::SeqCst) {
+            return TwapState::Cancelled;
+        }
+
+        let quantity = slice.quantity + carry;
+        let params = OrderParams {
+            quantity: Some(quantity),
+            ..schedule.order_params.clone()
+        };
+        let report = match schedule
+            .executor
+            .execute(schedule.instrument_token, params, schedule.tick_size)
+            .await
+        {
+            Ok(report) => report,
+            Err(e) => return TwapState::Failed(e.to_string()),
+        };
+
+        carry = match report.outcome {
+            ExecutionOutcome::Filled | ExecutionOutcome::ConvertedToMarket => 0,
+            ExecutionOutcome::Cancelled | ExecutionOutcome::Terminated(_) => {
+                (quantity - report.filled_quantity.round() as i32).max(0)
+            }
+        };
+        let status = match &report.outcome {
+            ExecutionOutcome::Filled => "COMPLETE",
+            ExecutionOutcome::ConvertedToMarket => "OPEN",
+            ExecutionOutcome::Cancelled | ExecutionOutcome::Terminated(_) => "CANCELLED",
+        };
+        lock(&schedule.progress).children.push(TwapChild {
+            order_id: report.order_id.clone(),
+            quantity,
+            status: status.to_string(),
+            filled_quantity: report.filled_quantity,
+            average_price: report.average_price,
+        });
+        // The schedule keeps running when nobody listens for events
+        let _ = schedule.events.send(TwapEvent::Slice(report)).await;
+    }
+    TwapState::Completed
+}
+
+fn lock(progress: &Mutex<TwapProgress>) -> std::sync::MutexGuard<'_, TwapProgress> {
+    progress.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+fn clock_seed() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos() as u64)
+        .unwrap_or_default()
+}
+
+/// SplitMix64, enough to decorrelate slice sizes and timings.
+struct Jitter(u64);
+
+impl Jitter {
+    fn new(seed: u64) -> Self {
+        Self(seed)
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform in `[0, 1)`.
+    fn unit(&mut self) -> f64 {
+        (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// Uniform in `[-1, 1)`.
+    fn symmetric(&mut self) -> f64 {
+        self.unit() * 2.0 - 1.0
+    }
+}
@@ -0,0 +1,249 @@
+//! API usage tracking.
+//!
+//! Every request made by the client is counted per endpoint category over the last second,
+//! the last minute and the current IST day. [`KiteConnect::usage_stats`] returns the
+//! counts next to Kite's published limits, so operators can alert before requests start
+//! getting throttled with HTTP 429.
+
+use chrono::NaiveDate;
+use futures_util::future::join_all;
+use reqwest::Method;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::future::Future;
+use std::sync::{Arc, Mutex};
+use web_time::{Duration, Instant};
+
+use crate::KiteConnect;
+use crate::compat;
+use crate::risk::ist_now_datetime;
+
+const SECOND: Duration = Duration::from_secs(1);
+const MINUTE: Duration = Duration::from_secs(60);
+/// How long a bulk request waits for room in a per-second limit.
+const RATE_LIMIT_WAIT: Duration = Duration::from_millis(100);
+
+/// Endpoint categories with their own rate limits.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ApiCategory {
+    /// `/quote`, `/quote/ltp`, `/quote/ohlc`.
+    Quote,
+    /// `/instruments/historical/...`.
+    Historical,
+    /// Placing, modifying and cancelling orders.
+    Orders,
+    /// Everything else.
+    Other,
+}
+
+impl ApiCategory {
+    pub const ALL: [ApiCategory; 4] = [
+        ApiCategory::Quote,
+        ApiCategory::Historical,
+        ApiCategory::Orders,
+        ApiCategory::Other,
+    ];
+
+    /// The category a request falls into.
+    pub fn of(method: &Method, endpoint: &str) -> Self {
+        let path = endpoint.split('?').next().unwrap_or_default();
+        if path == "/quote" || path.starts_with("/quote/") {
+            ApiCategory::Quote
+        } else if path.starts_with("/instruments/historical/") {
+            ApiCategory::Historical
+        } else if path.starts_with("/orders/") && method != Method::GET {
+            ApiCategory::Orders
+        } else {
+            ApiCategory::Other
+        }
+    }
+
+    /// Kite's documented request limits: (per second, per minute, per day).
+    pub fn limits(&self) -> (u32, Option<u32>, Option<u32>) {
+        match self {
+            ApiCategory::Quote => (1, None, None),
+            ApiCategory::Historical => (3, None, None),
+            ApiCategory::Orders => (10, Some(200), Some(3000)),
+            ApiCategory::Other => (10, None, None),
+        }
+    }
+
+    fn index(&self) -> usize {
+        match self {
+            ApiCategory::Quote => 0,
+            ApiCategory::Historical => 1,
+            ApiCategory::Orders => 2,
+            ApiCategory::Other => 3,
+        }
+    }
+}
+
+/// CategoryUsage is the request count of one category, next to its limits.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct CategoryUsage {
+    pub category: ApiCategory,
+    pub last_second: u32,
+    pub last_minute: u32,
+    pub today: u32,
+    pub limit_per_second: u32,
+    pub limit_per_minute: Option<u32>,
+    pub limit_per_day: Option<u32>,
+}
+
+impl CategoryUsage {
+    /// Highest fraction of any limit in use, e.g. `0.9` when at 90% of the per-minute limit.
+    pub fn utilization(&self) -> f64 {
+        let ratio = |used: u32, limit: Option<u32>| match limit {
+            Some(limit) if limit > 0 => used as f64 / limit as f64,
+            _ => 0.0,
+        };
+        ratio(self.last_second, Some(self.limit_per_second))
+            .max(ratio(self.last_minute, self.limit_per_minute))
+            .max(ratio(self.today, self.limit_per_day))
+    }
+}
+
+/// UsageStats is a snapshot of API usage across all categories.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct UsageStats {
+    pub categories: Vec<CategoryUsage>,
+}
+
+impl UsageStats {
+    pub fn category(&self, category: ApiCategory) -> &CategoryUsage {
+        &self.categories[category.index()]
+    }
+
+    /// Categories at or above `threshold` utilization.
+    pub fn near_limit(&self, threshold: f64) -> Vec<&CategoryUsage> {
+        self.categories
+            .iter()
+            .filter(|usage| usage.utilization() >= threshold)
+            .collect()
+    }
+}
+
+#[derive(Debug, Default)]
+struct CategoryWindow {
+    // Request times within the last minute
+    recent: VecDeque<Instant>,
+    today: u32,
+}
+
+#[derive(Debug, Default)]
+struct UsageState {
+    day: Option<NaiveDate>,
+    windows: [CategoryWindow; 4],
+}
+
+/// UsagePool counts requests per category. Clients sharing a pool share the counts.
+#[derive(Debug, Clone, Default)]
+pub struct UsagePool {
+    state: Arc<Mutex<UsageState>>,
+}
+
+impl UsagePool {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn record(&self, method: &Method, endpoint: &str) {
+        let category = ApiCategory::of(method, endpoint);
+        let now = Instant::now();
+        let today = ist_now_datetime().date_naive();
+
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        if state.day != Some(today) {
+            state.day = Some(today);
+            for window in &mut state.windows {
+                window.today = 0;
+            }
+        }
+
+        let window = &mut state.windows[category.index()];
+        prune(&mut window.recent, now);
+        window.recent.push_back(now);
+        window.today += 1;
+    }
+
+    /// Usage counted in this pool so far.
+    pub fn stats(&self) -> UsageStats {
+        let now = Instant::now();
+        let today = ist_now_datetime().date_naive();
+        let mut state = self.state.lock().unwrap_or_else(|e| e.into_inner());
+        let same_day = state.day == Some(today);
+
+        let categories = ApiCategory::ALL
+            .iter()
+            .map(|&category| {
+                let window = &mut state.windows[category.index()];
+                prune(&mut window.recent, now);
+                let last_second = window
+                    .recent
+                    .iter()
+                    .rev()
+                    .take_while(|t| now.duration_since(**t) < SECOND)
+                    .count() as u32;
+                let (limit_per_second, limit_per_minute, limit_per_day) = category.limits();
+
+                CategoryUsage {
+                    category,
+                    last_second,
+                    last_minute: window.recent.len() as u32,
+                    today: if same_day { window.today } else { 0 },
+                    limit_per_second,
+                    limit_per_minute,
+                    limit_per_day,
+                }
+            })
+            .collect();
+        UsageStats { categories }
+    }
+}
+
+fn prune(recent: &mut VecDeque<Instant>, now: Instant) {
+    while recent
+        .front()
+        .is_some_and(|t| now.duration_since(*t) >= MINUTE)
+    {
+        recent.pop_front();
+    }
+}
+
+impl KiteConnect {
+    /// Requests made by this client per endpoint category, next to Kite's limits.
+    pub fn usage_stats(&self) -> UsageStats {
+        self.usage.stats()
+    }
+
+    /// Run `request` on every item concurrently, as many at a time as the per-second limit
+    /// of `category` has room for given the requests this client's usage pool has already
+    /// made. The results are in the order of `items`.
+    pub(crate) async fn within_rate_limit<T, F, Fut>(
+        &self,
+        category: ApiCategory,
+        items: Vec<T>,
+        request: F,
+    ) -> Vec<Fut::Output>
+    where
+        F: Fn(T) -> Fut,
+        Fut: Future,
+    {
+        let mut pending = items;
+        let mut results = Vec::with_capacity(pending.len());
+        while !pending.is_empty() {
+            let usage = self.usage_stats();
+            let usage = usage.category(category);
+            let room = usage.limit_per_second.saturating_sub(usage.last_second) as usize;
+            if room == 0 {
+                compat::sleep(RATE_LIMIT_WAIT).await;
+                continue;
+            }
+
+            let batch: Vec<T> = pending.drain(..room.min(pending.len())).collect();
+            results.extend(join_all(batch.into_iter().map(&request)).await);
+        }
+        results
+    }
+}
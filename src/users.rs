@@ -1,14 +1,18 @@
 use serde::{Deserialize, Serialize};
 use sha2::{Digest, Sha256};
 use std::collections::HashMap;
+use std::fmt;
+use zeroize::{Zeroize, Zeroizing};
 
 use crate::{
     KiteConnect,
     constants::Endpoints,
-    models::{KiteConnectError, time},
+    models::{ConfigError, KiteConnectError, time},
 };
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// UserSession is the response of a session exchange. The tokens are wiped from memory
+/// when it is dropped and left out of its `Debug` output.
+#[derive(Clone, Serialize, Deserialize)]
 pub struct UserSession {
     pub user_id: String,
     pub user_name: String,
@@ -31,13 +35,50 @@ pub struct UserSession {
     pub login_time: time::Time,
 }
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+/// UserSessionTokens are the tokens of a session as kept by a session store. They are
+/// wiped from memory when dropped and left out of the `Debug` output.
+#[derive(Clone, PartialEq, Serialize, Deserialize)]
 pub struct UserSessionTokens {
     pub user_id: String,
     pub access_token: String,
     pub refresh_token: String,
 }
 
+impl fmt::Debug for UserSession {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserSession")
+            .field("user_id", &self.user_id)
+            .field("user_name", &self.user_name)
+            .field("user_type", &self.user_type)
+            .field("broker", &self.broker)
+            .field("api_key", &self.api_key)
+            .field("login_time", &self.login_time)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for UserSession {
+    fn drop(&mut self) {
+        self.access_token.zeroize();
+        self.refresh_token.zeroize();
+    }
+}
+
+impl fmt::Debug for UserSessionTokens {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("UserSessionTokens")
+            .field("user_id", &self.user_id)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Drop for UserSessionTokens {
+    fn drop(&mut self) {
+        self.access_token.zeroize();
+        self.refresh_token.zeroize();
+    }
+}
+
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Bank {
     pub name: String,
@@ -144,6 +185,38 @@ pub struct AllMargins {
     pub commodity: Margins,
 }
 
+/// The `checksum` parameter of a session or token renewal request: the hex SHA-256 of the
+/// API key, the request or refresh token, and the API secret.
+///
+/// Apps that keep the API secret on a server can compute it there and complete the
+/// exchange with the same hashing as [`KiteConnect::generate_session`].
+pub fn compute_checksum(api_key: &str, token: &str, api_secret: &str) -> String {
+    let mut hasher = Sha256::new();
+    let input = Zeroizing::new(format!("{}{}{}", api_key, token, api_secret));
+    hasher.update(input.as_bytes());
+    format!("{:x}", hasher.finalize())
+}
+
+/// Check a session returned by a session exchange done elsewhere before using it: the
+/// session must belong to `api_key` and carry an access token.
+pub fn verify_session_response(
+    api_key: &str,
+    session: &UserSession,
+) -> Result<(), KiteConnectError> {
+    if session.api_key != api_key {
+        return Err(KiteConnectError::invalid_params(format!(
+            "session is for API key {}, expected {}",
+            session.api_key, api_key
+        )));
+    }
+    if session.access_token.is_empty() {
+        return Err(KiteConnectError::invalid_params(
+            "session has no access token",
+        ));
+    }
+    Ok(())
+}
+
 impl KiteConnect {
     /// Generate session and get user details in exchange for request token.
     /// Access token is automatically set if the session is retrieved successfully.
@@ -152,9 +225,7 @@ impl KiteConnect {
         request_token: &str,
         api_secret: &str,
     ) -> Result<UserSession, KiteConnectError> {
-        let mut hasher = Sha256::new();
-        hasher.update(format!("{}{}{}", self.api_key, request_token, api_secret));
-        let checksum = format!("{:x}", hasher.finalize());
+        let checksum = compute_checksum(&self.api_key, request_token, api_secret);
 
         let mut params = HashMap::new();
         params.insert("api_key".to_string(), self.api_key.clone());
@@ -191,7 +262,7 @@ impl KiteConnect {
 
     /// Invalidate the current access token
     pub async fn invalidate_access_token(&mut self) -> Result<bool, KiteConnectError> {
-        match self.access_token.clone() {
+        match self.access_token() {
             Some(token) => {
                 let result = self.invalidate_token("access_token", &token).await?;
                 if result {
@@ -210,9 +281,7 @@ impl KiteConnect {
         refresh_token: &str,
         api_secret: &str,
     ) -> Result<UserSessionTokens, KiteConnectError> {
-        let mut hasher = Sha256::new();
-        hasher.update(format!("{}{}{}", self.api_key, refresh_token, api_secret));
-        let checksum = format!("{:x}", hasher.finalize());
+        let checksum = compute_checksum(&self.api_key, refresh_token, api_secret);
 
         let mut params = HashMap::new();
         params.insert("api_key".to_string(), self.api_key.clone());
@@ -240,6 +309,17 @@ impl KiteConnect {
         self.get(Endpoints::USER_PROFILE).await
     }
 
+    /// Validate the configured credentials by fetching the user profile.
+    ///
+    /// Useful as a startup check so strategies fail fast on an expired or missing
+    /// access token instead of on their first order.
+    pub async fn validate_credentials(&self) -> Result<UserProfile, KiteConnectError> {
+        if self.access_token().is_none() {
+            return Err(ConfigError::EmptyAccessToken.into());
+        }
+        self.get_user_profile().await
+    }
+
     /// Get full user profile
     pub async fn get_full_user_profile(&self) -> Result<FullUserProfile, KiteConnectError> {
         self.get(Endpoints::USER_FULL_PROFILE).await
@@ -0,0 +1,507 @@
+//! Exchange rules for orders, checked before they are sent.
+//!
+//! The exchanges and Kite reject orders priced off the tick grid, sized in part lots,
+//! priced outside the day's band, in a product the exchange does not offer, or breaking
+//! the rules of the AMO, cover and iceberg varieties. [`OrderValidator`] checks all of
+//! these at once and returns every [`Violation`], each with a machine-readable
+//! [`ViolationCode`], instead of stopping at the first. Set on the client with
+//! [`KiteConnect::set_order_validator`], it runs on every order `place_order` sends, and
+//! on the order as it will be after each `modify_order`, and fails it with
+//! [`KiteConnectErrorKind::ValidationFailed`]:
+//!
+//! ```ignore
+//! let mut validator = OrderValidator::new()
+//!     .instruments(store)
+//!     .market_clock(MarketClock::nse());
+//! validator.set_price_bands(&kite.get_quote(&["NSE:INFY"]).await?);
+//! for violation in validator.validate("regular", &params) {
+//!     println!("{} {}: {}", violation.code, violation.field, violation.message);
+//! }
+//! ```
+
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use chrono::DateTime;
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+
+use crate::KiteConnect;
+use crate::amo::{select_variety, validate_amo};
+use crate::clock::MarketClock;
+use crate::instruments::{InstrumentStore, Rounding, round_price};
+use crate::labels::{Exchange, OrderType, Product, TransactionType, Validity, Variety};
+use crate::markets::Quote;
+use crate::models::{KiteConnectError, KiteConnectErrorKind};
+use crate::orders::{ICEBERG_EXCHANGES, IcebergParams, OrderParams};
+use crate::risk::ist_now_datetime;
+
+/// ViolationCode identifies the rule an order breaks.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ViolationCode {
+    /// A field the order needs is not set.
+    MissingField,
+    /// A field holds a value Kite does not know, e.g. an unknown exchange.
+    UnknownValue,
+    /// A field is set to a value that can never be valid, e.g. a zero quantity.
+    InvalidValue,
+    /// A price is not a multiple of the instrument's tick size.
+    TickSize,
+    /// A quantity is not a multiple of the instrument's lot size.
+    LotSize,
+    /// A price is outside the day's circuit limits.
+    PriceBand,
+    /// The product is not offered on the exchange.
+    ProductNotAllowed,
+    /// The order cannot be placed as an after market order.
+    AmoNotAllowed,
+    /// The exchange does not take after market orders at this time.
+    AmoOutsideWindow,
+    /// The order breaks a rule of the `co` variety.
+    CoverOrder,
+    /// The order breaks a rule of the `iceberg` variety.
+    Iceberg,
+}
+
+impl ViolationCode {
+    pub const fn as_str(&self) -> &'static str {
+        match self {
+            ViolationCode::MissingField => "missing_field",
+            ViolationCode::UnknownValue => "unknown_value",
+            ViolationCode::InvalidValue => "invalid_value",
+            ViolationCode::TickSize => "tick_size",
+            ViolationCode::LotSize => "lot_size",
+            ViolationCode::PriceBand => "price_band",
+            ViolationCode::ProductNotAllowed => "product_not_allowed",
+            ViolationCode::AmoNotAllowed => "amo_not_allowed",
+            ViolationCode::AmoOutsideWindow => "amo_outside_window",
+            ViolationCode::CoverOrder => "cover_order",
+            ViolationCode::Iceberg => "iceberg",
+        }
+    }
+}
+
+impl fmt::Display for ViolationCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+/// Violation is one rule an order breaks.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Violation {
+    pub code: ViolationCode,
+    /// The [`OrderParams`] field at fault, or `variety`.
+    pub field: String,
+    pub message: String,
+}
+
+impl Violation {
+    pub fn new(code: ViolationCode, field: &str, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            field: field.to_string(),
+            message: message.into(),
+        }
+    }
+}
+
+impl fmt::Display for Violation {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}: {}", self.code, self.message)
+    }
+}
+
+/// OrderValidator checks orders against the exchange rules; see the
+/// [module docs](self).
+///
+/// Tick and lot sizes need [`instruments`](Self::instruments), price bands need
+/// [`set_price_bands`](Self::set_price_bands) and the AMO window needs a
+/// [`market_clock`](Self::market_clock); rules without their data are skipped, as are
+/// orders for instruments missing from the store.
+#[derive(Debug, Clone, Default)]
+pub struct OrderValidator {
+    instruments: Option<Arc<InstrumentStore>>,
+    market_clock: Option<MarketClock>,
+    // `EXCHANGE:TRADINGSYMBOL` to the lower and upper circuit limits
+    price_bands: HashMap<String, (f64, f64)>,
+}
+
+impl OrderValidator {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Check tick and lot sizes against `instruments`.
+    pub fn instruments(mut self, instruments: Arc<InstrumentStore>) -> Self {
+        self.instruments = Some(instruments);
+        self
+    }
+
+    /// Check that `amo` orders are placed inside the exchange's AMO window.
+    pub fn market_clock(mut self, clock: MarketClock) -> Self {
+        self.market_clock = Some(clock);
+        self
+    }
+
+    /// Set the day's price band of `tradingsymbol` on `exchange`; 0 for no limit.
+    pub fn set_price_band(
+        &mut self,
+        exchange: &str,
+        tradingsymbol: &str,
+        lower: f64,
+        upper: f64,
+    ) -> &mut Self {
+        self.price_bands
+            .insert(format!("{}:{}", exchange, tradingsymbol), (lower, upper));
+        self
+    }
+
+    /// Set the price bands of every instrument of a `get_quote` response from its circuit
+    /// limits.
+    pub fn set_price_bands(&mut self, quotes: &Quote) -> &mut Self {
+        for (key, quote) in quotes {
+            self.price_bands.insert(
+                key.clone(),
+                (quote.lower_circuit_limit, quote.upper_circuit_limit),
+            );
+        }
+        self
+    }
+
+    /// Every rule `params` breaks when placed with `variety`, empty for a valid order.
+    pub fn validate(&self, variety: &str, params: &OrderParams) -> Vec<Violation> {
+        self.validate_at(variety, params, ist_now_datetime())
+    }
+
+    /// [`validate`](Self::validate) with the AMO window checked at `at`.
+    pub fn validate_at(
+        &self,
+        variety: &str,
+        params: &OrderParams,
+        at: DateTime<Tz>,
+    ) -> Vec<Violation> {
+        let mut violations = Vec::new();
+        let variety = parse(&mut violations, "variety", Some(variety)).flatten();
+        let exchange = parse(&mut violations, "exchange", params.exchange.as_deref());
+        let order_type = parse(&mut violations, "order_type", params.order_type.as_deref());
+        parse::<TransactionType>(
+            &mut violations,
+            "transaction_type",
+            params.transaction_type.as_deref(),
+        );
+        let product = parse(&mut violations, "product", params.product.as_deref()).flatten();
+        let validity = parse(&mut violations, "validity", params.validity.as_deref()).flatten();
+        if params.tradingsymbol.is_none() {
+            violations.push(missing("tradingsymbol"));
+        }
+        match params.quantity {
+            None => violations.push(missing("quantity")),
+            Some(quantity) if quantity <= 0 => violations.push(Violation::new(
+                ViolationCode::InvalidValue,
+                "quantity",
+                format!("quantity must be positive, got {}", quantity),
+            )),
+            Some(_) => {}
+        }
+
+        if let Some(Some(order_type)) = order_type {
+            check_prices(&mut violations, order_type, params);
+        }
+        if let (Some(Some(exchange)), Some(product)) = (exchange, product) {
+            check_product(&mut violations, exchange, product);
+        }
+        self.check_instrument(&mut violations, params);
+        self.check_price_band(&mut violations, params);
+
+        match variety {
+            Some(Variety::Amo) => {
+                let Some(Some(exchange)) = exchange else {
+                    return violations;
+                };
+                if let Err(e) = validate_amo(params) {
+                    violations.push(Violation::new(
+                        ViolationCode::AmoNotAllowed,
+                        "variety",
+                        message(e),
+                    ));
+                }
+                if let Some(clock) = &self.market_clock {
+                    if !matches!(select_variety(clock, exchange, at), Ok(Variety::Amo)) {
+                        violations.push(Violation::new(
+                            ViolationCode::AmoOutsideWindow,
+                            "variety",
+                            format!("{} does not take after market orders at {}", exchange, at),
+                        ));
+                    }
+                }
+            }
+            Some(Variety::Cover) => check_cover(
+                &mut violations,
+                order_type.flatten(),
+                product,
+                validity,
+                params,
+            ),
+            Some(Variety::Iceberg) => check_iceberg(&mut violations, params),
+            _ => {}
+        }
+        violations
+    }
+
+    /// Fail with [`KiteConnectErrorKind::ValidationFailed`] if `params` breaks any rule.
+    pub fn check(&self, variety: &str, params: &OrderParams) -> Result<(), KiteConnectError> {
+        let violations = self.validate(variety, params);
+        if violations.is_empty() {
+            return Ok(());
+        }
+        Err(KiteConnectError::validation_failed(violations))
+    }
+
+    fn check_instrument(&self, violations: &mut Vec<Violation>, params: &OrderParams) {
+        let (Some(store), Some(exchange), Some(tradingsymbol)) =
+            (&self.instruments, &params.exchange, &params.tradingsymbol)
+        else {
+            return;
+        };
+        let Some(instrument) = store.get_by_symbol(exchange, tradingsymbol) else {
+            return;
+        };
+
+        let tick_size = instrument.tick_size;
+        for (field, price) in [
+            ("price", params.price),
+            ("trigger_price", params.trigger_price),
+        ] {
+            let Some(price) = price.filter(|p| *p > 0.0) else {
+                continue;
+            };
+            let on_grid = round_price(price, tick_size, Rounding::Nearest, None);
+            if (on_grid - price).abs() > 1e-6 {
+                violations.push(Violation::new(
+                    ViolationCode::TickSize,
+                    field,
+                    format!(
+                        "{} {} of {} is not a multiple of the tick size {}",
+                        field, price, tradingsymbol, tick_size
+                    ),
+                ));
+            }
+        }
+
+        let lot_size = instrument.lot_size.round() as i32;
+        if lot_size <= 1 {
+            return;
+        }
+        for (field, quantity) in [
+            ("quantity", params.quantity),
+            ("disclosed_quantity", params.disclosed_quantity),
+        ] {
+            if let Some(quantity) = quantity.filter(|q| q % lot_size != 0) {
+                violations.push(Violation::new(
+                    ViolationCode::LotSize,
+                    field,
+                    format!(
+                        "{} {} of {} is not a multiple of the lot size {}",
+                        field, quantity, tradingsymbol, lot_size
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn check_price_band(&self, violations: &mut Vec<Violation>, params: &OrderParams) {
+        let (Some(exchange), Some(tradingsymbol)) = (&params.exchange, &params.tradingsymbol)
+        else {
+            return;
+        };
+        let Some(&(lower, upper)) = self
+            .price_bands
+            .get(&format!("{}:{}", exchange, tradingsymbol))
+        else {
+            return;
+        };
+        for (field, price) in [
+            ("price", params.price),
+            ("trigger_price", params.trigger_price),
+        ] {
+            let Some(price) = price.filter(|p| *p > 0.0) else {
+                continue;
+            };
+            if (lower > 0.0 && price < lower) || (upper > 0.0 && price > upper) {
+                violations.push(Violation::new(
+                    ViolationCode::PriceBand,
+                    field,
+                    format!(
+                        "{} {} of {} is outside the band {} to {}",
+                        field, price, tradingsymbol, lower, upper
+                    ),
+                ));
+            }
+        }
+    }
+}
+
+impl KiteConnect {
+    /// Set or clear the validator `place_order` and `modify_order` check every order with.
+    pub fn set_order_validator(&mut self, validator: Option<OrderValidator>) {
+        self.order_validator = validator;
+    }
+
+    pub fn order_validator(&self) -> Option<&OrderValidator> {
+        self.order_validator.as_ref()
+    }
+}
+
+/// Parse an optional label; `None` when the field is not set, `Some(None)` when it does
+/// not parse.
+fn parse<T: std::str::FromStr>(
+    violations: &mut Vec<Violation>,
+    field: &str,
+    value: Option<&str>,
+) -> Option<Option<T>> {
+    let Some(value) = value else {
+        if matches!(field, "exchange" | "order_type" | "transaction_type") {
+            violations.push(missing(field));
+        }
+        return None;
+    };
+    let parsed = value.parse().ok();
+    if parsed.is_none() {
+        violations.push(Violation::new(
+            ViolationCode::UnknownValue,
+            field,
+            format!("unknown {} {}", field, value),
+        ));
+    }
+    Some(parsed)
+}
+
+fn missing(field: &str) -> Violation {
+    Violation::new(
+        ViolationCode::MissingField,
+        field,
+        format!("order has no {}", field),
+    )
+}
+
+fn check_prices(violations: &mut Vec<Violation>, order_type: OrderType, params: &OrderParams) {
+    let priced = matches!(order_type, OrderType::Limit | OrderType::Sl);
+    let triggered = matches!(order_type, OrderType::Sl | OrderType::SlM);
+    if priced && params.price.is_none_or(|p| p <= 0.0) {
+        violations.push(Violation::new(
+            ViolationCode::MissingField,
+            "price",
+            format!("{} orders need a price", order_type),
+        ));
+    }
+    if triggered && params.trigger_price.is_none_or(|p| p <= 0.0) {
+        violations.push(Violation::new(
+            ViolationCode::MissingField,
+            "trigger_price",
+            format!("{} orders need a trigger price", order_type),
+        ));
+    }
+}
+
+/// Delivery products on the cash segments, NRML on derivatives, MIS everywhere. BO and CO
+/// are varieties now and are not accepted as products.
+fn check_product(violations: &mut Vec<Violation>, exchange: Exchange, product: Product) {
+    let cash = matches!(exchange, Exchange::Nse | Exchange::Bse);
+    let allowed = match product {
+        Product::Mis => true,
+        Product::Cnc | Product::Mtf => cash,
+        Product::Nrml => !cash,
+        Product::Bo | Product::Co => false,
+    };
+    if !allowed {
+        violations.push(Violation::new(
+            ViolationCode::ProductNotAllowed,
+            "product",
+            format!("{} is not available on {}", product, exchange),
+        ));
+    }
+}
+
+fn check_cover(
+    violations: &mut Vec<Violation>,
+    order_type: Option<OrderType>,
+    product: Option<Product>,
+    validity: Option<Validity>,
+    params: &OrderParams,
+) {
+    let mut push = |field: &str, message: String| {
+        violations.push(Violation::new(ViolationCode::CoverOrder, field, message));
+    };
+    if let Some(order_type) =
+        order_type.filter(|t| !matches!(t, OrderType::Market | OrderType::Limit))
+    {
+        push(
+            "order_type",
+            format!(
+                "cover order entry must be MARKET or LIMIT, got {}",
+                order_type
+            ),
+        );
+    }
+    if let Some(product) = product.filter(|p| *p != Product::Mis) {
+        push(
+            "product",
+            format!("cover orders must be MIS, got {}", product),
+        );
+    }
+    if let Some(validity) = validity.filter(|v| *v != Validity::Day) {
+        push(
+            "validity",
+            format!("cover orders must be DAY, got {}", validity),
+        );
+    }
+    if params.trigger_price.is_none_or(|p| p <= 0.0) {
+        push(
+            "trigger_price",
+            "cover orders need a stoploss trigger price".to_string(),
+        );
+    }
+}
+
+fn check_iceberg(violations: &mut Vec<Violation>, params: &OrderParams) {
+    let exchange = params.exchange.as_deref().unwrap_or_default();
+    if !ICEBERG_EXCHANGES.contains(&exchange) {
+        violations.push(Violation::new(
+            ViolationCode::Iceberg,
+            "exchange",
+            format!(
+                "iceberg orders are not supported on exchange `{}`",
+                exchange
+            ),
+        ));
+    }
+    let (Some(legs), Some(leg_quantity)) = (params.iceberg_legs, params.iceberg_quantity) else {
+        violations.push(Violation::new(
+            ViolationCode::Iceberg,
+            "iceberg_legs",
+            "iceberg orders need iceberg_legs and iceberg_quantity",
+        ));
+        return;
+    };
+    let Some(quantity) = params.quantity else {
+        return;
+    };
+    let iceberg = IcebergParams { legs, leg_quantity };
+    if let Err(e) = iceberg.validate(quantity) {
+        violations.push(Violation::new(
+            ViolationCode::Iceberg,
+            "iceberg_legs",
+            message(e),
+        ));
+    }
+}
+
+fn message(error: KiteConnectError) -> String {
+    match error.kind {
+        KiteConnectErrorKind::InvalidParams(message) => message,
+        _ => error.to_string(),
+    }
+}
@@ -0,0 +1,311 @@
+//! Record API responses into cassettes and replay them in tests.
+//!
+//! [`Recorder`] is a [`Transport`] that forwards requests to another transport, usually the
+//! live API through [`HttpTransport`], and keeps every request and response as an
+//! [`Interaction`]. Saving writes them to a JSON cassette with credentials and personal
+//! details replaced by [`REDACTED`]. [`Replayer`] serves a saved cassette without any
+//! network access:
+//!
+//! ```no_run
+//! # async fn run() -> Result<(), kiteconnect_rs::KiteConnectError> {
+//! use kiteconnect_rs::KiteConnect;
+//! use kiteconnect_rs::transport::HttpTransport;
+//! use kiteconnect_rs::vcr::{Recorder, Replayer};
+//!
+//! // Once, against the live API
+//! let recorder = Recorder::new(HttpTransport::default());
+//! let kite = KiteConnect::builder("api_key")
+//!     .access_token("access_token")
+//!     .transport(recorder.clone())
+//!     .build()?;
+//! kite.get_positions().await?;
+//! recorder.save("tests/cassettes/positions.json")?;
+//!
+//! // In tests
+//! let kite = KiteConnect::builder("api_key")
+//!     .transport(Replayer::load("tests/cassettes/positions.json")?)
+//!     .build()?;
+//! let positions = kite.get_positions().await?;
+//! # Ok(())
+//! # }
+//! ```
+
+use async_trait::async_trait;
+use reqwest::{Request, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashSet;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use url::form_urlencoded;
+
+use crate::models::KiteConnectError;
+use crate::transport::{Transport, TransportResponse};
+
+/// Placeholder written in place of sanitized values.
+pub const REDACTED: &str = "REDACTED";
+
+/// Keys whose values are redacted from query strings, form bodies and JSON responses.
+/// Timestamps such as `login_time` are kept, as a redacted one would not parse on replay.
+pub const DEFAULT_REDACTED_KEYS: [&str; 15] = [
+    "api_key",
+    "access_token",
+    "refresh_token",
+    "public_token",
+    "enctoken",
+    "request_token",
+    "checksum",
+    "user_id",
+    "user_name",
+    "user_shortname",
+    "email",
+    "phone",
+    "pan",
+    "bank_account",
+    "avatar_url",
+];
+
+/// Interaction is one recorded request and its response.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Interaction {
+    pub method: String,
+    /// URL path without the query string.
+    pub path: String,
+    /// Query string as sent, with redacted values.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub query: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub request_body: Option<String>,
+    pub status: u16,
+    pub response_body: String,
+}
+
+/// Cassette is the on-disk form of a recording.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cassette {
+    pub interactions: Vec<Interaction>,
+}
+
+impl Cassette {
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        let data = std::fs::read(path).map_err(io_error)?;
+        Ok(serde_json::from_slice(&data)?)
+    }
+
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KiteConnectError> {
+        let path = path.as_ref();
+        if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+            std::fs::create_dir_all(dir).map_err(io_error)?;
+        }
+        let mut data = serde_json::to_vec_pretty(self)?;
+        data.push(b'\n');
+        std::fs::write(path, data).map_err(io_error)
+    }
+}
+
+/// Recorder forwards requests to an inner transport and records sanitized interactions.
+/// Clones share the recording.
+#[derive(Clone)]
+pub struct Recorder {
+    inner: Arc<dyn Transport>,
+    redacted_keys: Arc<HashSet<String>>,
+    interactions: Arc<Mutex<Vec<Interaction>>>,
+}
+
+impl Recorder {
+    pub fn new<T: Transport + 'static>(inner: T) -> Self {
+        Self {
+            inner: Arc::new(inner),
+            redacted_keys: Arc::new(
+                DEFAULT_REDACTED_KEYS
+                    .iter()
+                    .map(|k| k.to_string())
+                    .collect(),
+            ),
+            interactions: Arc::default(),
+        }
+    }
+
+    /// Also redact `key`, e.g. a custom order tag that identifies the account.
+    pub fn redact_key(mut self, key: &str) -> Self {
+        Arc::make_mut(&mut self.redacted_keys).insert(key.to_string());
+        self
+    }
+
+    /// The interactions recorded so far.
+    pub fn cassette(&self) -> Cassette {
+        Cassette {
+            interactions: self
+                .interactions
+                .lock()
+                .unwrap_or_else(|e| e.into_inner())
+                .clone(),
+        }
+    }
+
+    /// Write the interactions recorded so far to `path`.
+    pub fn save(&self, path: impl AsRef<Path>) -> Result<(), KiteConnectError> {
+        self.cassette().save(path)
+    }
+
+    fn sanitize_pairs(&self, encoded: &str) -> String {
+        let pairs = form_urlencoded::parse(encoded.as_bytes()).map(|(key, value)| {
+            let value = if self.redacted_keys.contains(key.as_ref()) {
+                REDACTED.into()
+            } else {
+                value
+            };
+            (key, value)
+        });
+        form_urlencoded::Serializer::new(String::new())
+            .extend_pairs(pairs)
+            .finish()
+    }
+
+    fn sanitize_body(&self, body: &str) -> String {
+        match serde_json::from_str::<Value>(body) {
+            Ok(mut value) => {
+                redact_json(&mut value, &self.redacted_keys);
+                value.to_string()
+            }
+            Err(_) if body.contains('=') => self.sanitize_pairs(body),
+            Err(_) => body.to_string(),
+        }
+    }
+}
+
+#[async_trait]
+impl Transport for Recorder {
+    async fn execute(&self, request: Request) -> Result<TransportResponse, KiteConnectError> {
+        let method = request.method().to_string();
+        let path = request.url().path().to_string();
+        let query = self.sanitize_pairs(request.url().query().unwrap_or_default());
+        let request_body = request
+            .body()
+            .and_then(|body| body.as_bytes())
+            .map(|bytes| self.sanitize_body(&String::from_utf8_lossy(bytes)));
+
+        let response = self.inner.execute(request).await?;
+        let interaction = Interaction {
+            method,
+            path,
+            query,
+            request_body,
+            status: response.status.as_u16(),
+            response_body: self.sanitize_body(&response.body),
+        };
+        self.interactions
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(interaction);
+        Ok(response)
+    }
+}
+
+/// Replayer answers requests from a cassette.
+///
+/// A request is matched on method, path and query string, with redacted values matching
+/// anything. Interactions are served in recorded order; once all matches have been
+/// served, the last one is repeated. Unmatched requests fail.
+pub struct Replayer {
+    interactions: Vec<Interaction>,
+    served: Mutex<Vec<bool>>,
+}
+
+impl Replayer {
+    pub fn new(cassette: Cassette) -> Self {
+        let served = Mutex::new(vec![false; cassette.interactions.len()]);
+        Self {
+            interactions: cassette.interactions,
+            served,
+        }
+    }
+
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, KiteConnectError> {
+        Cassette::load(path).map(Self::new)
+    }
+
+    /// Whether every recorded interaction has been served at least once.
+    pub fn all_served(&self) -> bool {
+        self.served
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .iter()
+            .all(|served| *served)
+    }
+
+    fn find(&self, method: &str, path: &str, query: &str) -> Option<&Interaction> {
+        let mut served = self.served.lock().unwrap_or_else(|e| e.into_inner());
+        let matching: Vec<usize> = self
+            .interactions
+            .iter()
+            .enumerate()
+            .filter(|(_, i)| i.method == method && i.path == path && query_matches(&i.query, query))
+            .map(|(index, _)| index)
+            .collect();
+        let index = matching
+            .iter()
+            .copied()
+            .find(|&index| !served[index])
+            .or_else(|| matching.last().copied())?;
+        served[index] = true;
+        Some(&self.interactions[index])
+    }
+}
+
+#[async_trait]
+impl Transport for Replayer {
+    async fn execute(&self, request: Request) -> Result<TransportResponse, KiteConnectError> {
+        let method = request.method().as_str();
+        let path = request.url().path();
+        let query = request.url().query().unwrap_or_default();
+        let interaction = self.find(method, path, query).ok_or_else(|| {
+            KiteConnectError::other(format!(
+                "cassette has no interaction for {} {}?{}",
+                method, path, query
+            ))
+        })?;
+        let status = StatusCode::from_u16(interaction.status)
+            .map_err(|e| KiteConnectError::other(format!("cassette: {}", e)))?;
+        Ok(TransportResponse::new(
+            status,
+            interaction.response_body.clone(),
+        ))
+    }
+}
+
+fn query_matches(recorded: &str, query: &str) -> bool {
+    let mut recorded: Vec<(String, String)> = form_urlencoded::parse(recorded.as_bytes())
+        .into_owned()
+        .collect();
+    let mut query: Vec<(String, String)> = form_urlencoded::parse(query.as_bytes())
+        .into_owned()
+        .collect();
+    recorded.sort();
+    query.sort();
+    recorded.len() == query.len()
+        && recorded
+            .iter()
+            .zip(&query)
+            .all(|((rk, rv), (k, v))| rk == k && (rv == v || rv == REDACTED))
+}
+
+fn redact_json(value: &mut Value, keys: &HashSet<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map.iter_mut() {
+                if keys.contains(key) && !value.is_null() {
+                    *value = Value::String(REDACTED.to_string());
+                } else {
+                    redact_json(value, keys);
+                }
+            }
+        }
+        Value::Array(values) => values.iter_mut().for_each(|v| redact_json(v, keys)),
+        _ => {}
+    }
+}
+
+fn io_error(e: std::io::Error) -> KiteConnectError {
+    KiteConnectError::other(format!("cassette: {}", e))
+}
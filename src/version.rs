@@ -0,0 +1,139 @@
+//! Kite Connect API versions.
+//!
+//! Every request carries the API version in the `X-Kite-Version` header, 3 unless the
+//! client is configured otherwise. The version can be changed for the whole client or for
+//! endpoints under a path prefix, so a new version can be tried one endpoint at a time.
+//!
+//! The response models of this crate follow v3. A version whose responses differ gets a
+//! [`ResponseAdapter`] that rewrites its bodies into the v3 shape before they are parsed,
+//! which keeps the models, and v3 users, unaffected:
+//!
+//! ```ignore
+//! let kite = KiteConnect::builder(api_key)
+//!     .endpoint_api_version("/orders", ApiVersion::new(4))
+//!     .build()?
+//!     .with_response_adapter(ApiVersion::new(4), OrdersV4Adapter);
+//! ```
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Arc;
+
+use crate::KiteConnect;
+use crate::models::KiteConnectError;
+use crate::transport::TransportResponse;
+
+/// ApiVersion is a Kite Connect API version number.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ApiVersion(u16);
+
+impl ApiVersion {
+    pub const V3: ApiVersion = ApiVersion(3);
+
+    pub const fn new(version: u16) -> Self {
+        ApiVersion(version)
+    }
+
+    pub fn as_u16(&self) -> u16 {
+        self.0
+    }
+}
+
+impl Default for ApiVersion {
+    fn default() -> Self {
+        ApiVersion::V3
+    }
+}
+
+impl fmt::Display for ApiVersion {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.0)
+    }
+}
+
+/// ResponseAdapter rewrites the response bodies of one API version into the shape of the
+/// v3 response, envelope included, so the v3 models can parse them.
+pub trait ResponseAdapter: Send + Sync {
+    /// The v3 form of `body`, returned by `endpoint` with HTTP `status`. Error responses
+    /// pass through here too.
+    fn adapt(&self, endpoint: &str, status: u16, body: String) -> Result<String, KiteConnectError>;
+}
+
+// Version settings of a client
+#[derive(Clone, Default)]
+pub(crate) struct ApiVersions {
+    pub(crate) default: ApiVersion,
+    // Path prefix and version, longest prefix wins
+    overrides: Vec<(String, ApiVersion)>,
+    adapters: HashMap<ApiVersion, Arc<dyn ResponseAdapter>>,
+}
+
+impl ApiVersions {
+    pub(crate) fn set_override(&mut self, prefix: &str, version: Option<ApiVersion>) {
+        self.overrides.retain(|(p, _)| p != prefix);
+        if let Some(version) = version {
+            self.overrides.push((prefix.to_owned(), version));
+        }
+    }
+
+    pub(crate) fn for_endpoint(&self, endpoint: &str) -> ApiVersion {
+        self.overrides
+            .iter()
+            .filter(|(prefix, _)| endpoint.starts_with(prefix.as_str()))
+            .max_by_key(|(prefix, _)| prefix.len())
+            .map_or(self.default, |(_, version)| *version)
+    }
+}
+
+impl KiteConnect {
+    /// Set the API version requested by every endpoint without an override.
+    pub fn set_api_version(&mut self, version: ApiVersion) {
+        self.api_versions.default = version;
+    }
+
+    pub fn api_version(&self) -> ApiVersion {
+        self.api_versions.default
+    }
+
+    /// Request `version` from endpoints whose path starts with `prefix`, e.g. `/orders`,
+    /// or remove the override with `None`.
+    pub fn set_endpoint_api_version(&mut self, prefix: &str, version: Option<ApiVersion>) {
+        self.api_versions.set_override(prefix, version);
+    }
+
+    /// The API version requested from `endpoint`.
+    pub fn api_version_for(&self, endpoint: &str) -> ApiVersion {
+        self.api_versions.for_endpoint(endpoint)
+    }
+
+    /// Parse responses of `version` through `adapter`.
+    pub fn with_response_adapter<A: ResponseAdapter + 'static>(
+        mut self,
+        version: ApiVersion,
+        adapter: A,
+    ) -> Self {
+        self.api_versions
+            .adapters
+            .insert(version, Arc::new(adapter));
+        self
+    }
+
+    // Run a response of `endpoint` through the adapter of its API version, if any
+    pub(crate) fn adapt_response(
+        &self,
+        endpoint: &str,
+        response: TransportResponse,
+    ) -> Result<TransportResponse, KiteConnectError> {
+        let version = self.api_version_for(endpoint);
+        let Some(adapter) = self.api_versions.adapters.get(&version) else {
+            return Ok(response);
+        };
+        let body = adapter.adapt(endpoint, response.status.as_u16(), response.body)?;
+        Ok(TransportResponse {
+            status: response.status,
+            body,
+        })
+    }
+}
@@ -0,0 +1,151 @@
+//! Kite market watch lists.
+//!
+//! These are the endpoints behind the watchlists in the Kite web and mobile apps. They are
+//! not part of the documented Kite Connect API and may change without notice, so this
+//! module is behind the `watchlists` feature.
+//!
+//! Requests go to the client's base URL like every other call. Point the client at a host
+//! that serves `/marketwatch` for the session in use.
+
+use serde::{Deserialize, Serialize};
+
+use crate::{KiteConnect, KiteConnectError, constants::Endpoints, markets::Quote};
+
+/// Watchlist is a named list of instruments.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct Watchlist {
+    pub id: u64,
+    pub name: String,
+    #[serde(default)]
+    pub items: Vec<WatchlistItem>,
+}
+
+impl Watchlist {
+    /// `EXCHANGE:TRADINGSYMBOL` keys of the items in display order, as taken by
+    /// `get_quote`, `get_ltp` and `get_ohlc`.
+    pub fn instrument_keys(&self) -> Vec<String> {
+        let mut items: Vec<&WatchlistItem> = self.items.iter().collect();
+        items.sort_by_key(|item| item.weight);
+        items
+            .into_iter()
+            .map(|item| format!("{}:{}", item.exchange, item.tradingsymbol))
+            .collect()
+    }
+
+    pub fn instrument_tokens(&self) -> Vec<u32> {
+        self.items
+            .iter()
+            .map(|item| item.instrument_token)
+            .collect()
+    }
+}
+
+/// WatchlistItem is an instrument in a watchlist.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchlistItem {
+    pub id: u64,
+    pub instrument_token: u32,
+    pub exchange: String,
+    pub tradingsymbol: String,
+    #[serde(default)]
+    pub segment: String,
+    /// Position within the watchlist, lowest first.
+    #[serde(default)]
+    pub weight: i32,
+}
+
+/// WatchlistItemParams adds an instrument to a watchlist.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WatchlistItemParams {
+    pub exchange: String,
+    pub tradingsymbol: String,
+    /// Position within the watchlist, appended at the end when not set.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub weight: Option<i32>,
+}
+
+#[derive(Serialize)]
+struct WatchlistNameParams<'a> {
+    name: &'a str,
+}
+
+impl KiteConnect {
+    /// Gets all watchlists of the user with their items.
+    pub async fn get_watchlists(&self) -> Result<Vec<Watchlist>, KiteConnectError> {
+        self.get(Endpoints::WATCHLISTS).await
+    }
+
+    pub async fn get_watchlist(&self, watchlist_id: u64) -> Result<Watchlist, KiteConnectError> {
+        self.get(&watchlist_endpoint(watchlist_id)).await
+    }
+
+    pub async fn create_watchlist(&self, name: &str) -> Result<Watchlist, KiteConnectError> {
+        validate_name(name)?;
+        self.post_form(Endpoints::WATCHLISTS, &WatchlistNameParams { name })
+            .await
+    }
+
+    pub async fn rename_watchlist(
+        &self,
+        watchlist_id: u64,
+        name: &str,
+    ) -> Result<Watchlist, KiteConnectError> {
+        validate_name(name)?;
+        self.put_form(
+            &watchlist_endpoint(watchlist_id),
+            &WatchlistNameParams { name },
+        )
+        .await
+    }
+
+    pub async fn delete_watchlist(&self, watchlist_id: u64) -> Result<(), KiteConnectError> {
+        let _: serde_json::Value = self.delete(&watchlist_endpoint(watchlist_id)).await?;
+        Ok(())
+    }
+
+    pub async fn add_watchlist_item(
+        &self,
+        watchlist_id: u64,
+        params: WatchlistItemParams,
+    ) -> Result<WatchlistItem, KiteConnectError> {
+        let endpoint =
+            Endpoints::WATCHLIST_ITEMS.replace("{watchlist_id}", &watchlist_id.to_string());
+        self.post_form(&endpoint, &params).await
+    }
+
+    pub async fn delete_watchlist_item(
+        &self,
+        watchlist_id: u64,
+        item_id: u64,
+    ) -> Result<(), KiteConnectError> {
+        let endpoint = Endpoints::WATCHLIST_ITEM
+            .replace("{watchlist_id}", &watchlist_id.to_string())
+            .replace("{item_id}", &item_id.to_string());
+        let _: serde_json::Value = self.delete(&endpoint).await?;
+        Ok(())
+    }
+
+    /// Gets full quotes for every instrument in a watchlist, keyed by `EXCHANGE:TRADINGSYMBOL`.
+    pub async fn get_watchlist_quotes(&self, watchlist_id: u64) -> Result<Quote, KiteConnectError> {
+        let watchlist = self.get_watchlist(watchlist_id).await?;
+        let keys = watchlist.instrument_keys();
+        if keys.is_empty() {
+            return Ok(Quote::new());
+        }
+        let keys: Vec<&str> = keys.iter().map(String::as_str).collect();
+        self.get_quote(&keys).await
+    }
+}
+
+fn watchlist_endpoint(watchlist_id: u64) -> String {
+    Endpoints::WATCHLIST.replace("{watchlist_id}", &watchlist_id.to_string())
+}
+
+fn validate_name(name: &str) -> Result<(), KiteConnectError> {
+    if name.trim().is_empty() {
+        return Err(KiteConnectError::invalid_params(
+            "watchlist name must not be empty",
+        ));
+    }
+    Ok(())
+}
@@ -0,0 +1,476 @@
+use async_trait::async_trait;
+use kiteconnect_rs::backtest::{Backtest, FillModel, LimitFills, OrderApi, Slippage, Strategy};
+use kiteconnect_rs::markets::HistoricalData;
+use kiteconnect_rs::models::time::Time;
+use kiteconnect_rs::models::{Depth, DepthItem};
+use kiteconnect_rs::{KiteConnectError, Order, OrderParams, Tick};
+
+fn candle(minute: i64, open: f64, high: f64, low: f64, close: f64) -> (u32, HistoricalData) {
+    let candle = HistoricalData {
+        date: Time::from_timestamp(1704167100 + minute * 60),
+        open,
+        high,
+        low,
+        close,
+        volume: 1000,
+        oi: 0,
+    };
+    (408065, candle)
+}
+
+fn order(symbol: &str, side: &str, order_type: &str, quantity: i32) -> OrderParams {
+    OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some(symbol.to_string()),
+        transaction_type: Some(side.to_string()),
+        order_type: Some(order_type.to_string()),
+        product: Some("MIS".to_string()),
+        quantity: Some(quantity),
+        ..Default::default()
+    }
+}
+
+// Buys on the first candle and sells at a limit once the buy has filled
+#[derive(Default)]
+struct BuyThenTakeProfit {
+    candles: usize,
+    statuses: Vec<String>,
+}
+
+#[async_trait]
+impl Strategy for BuyThenTakeProfit {
+    async fn on_candle(
+        &mut self,
+        orders: &dyn OrderApi,
+        _instrument_token: u32,
+        _candle: &HistoricalData,
+    ) -> Result<(), KiteConnectError> {
+        self.candles += 1;
+        if self.candles == 1 {
+            orders
+                .place_order("regular", order("INFY", "BUY", "MARKET", 10))
+                .await?;
+        }
+        Ok(())
+    }
+
+    async fn on_order_update(
+        &mut self,
+        orders: &dyn OrderApi,
+        order: &Order,
+    ) -> Result<(), KiteConnectError> {
+        self.statuses
+            .push(format!("{} {}", order.transaction_type, order.status));
+        if order.transaction_type == "BUY" && order.status == "COMPLETE" {
+            let mut sell = self::order("INFY", "SELL", "LIMIT", 10);
+            sell.price = Some(106.0);
+            orders.place_order("regular", sell).await?;
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_candles_fills_and_report() {
+    let mut strategy = BuyThenTakeProfit::default();
+    let report = Backtest::new()
+        .instrument(408065, "NSE", "INFY")
+        .fill_model(FillModel {
+            charges_per_order: 5.0,
+            ..Default::default()
+        })
+        .run_candles(
+            &mut strategy,
+            vec![
+                candle(3, 100.0, 100.0, 95.0, 96.0),
+                // Out of order on purpose: candles run in time order
+                candle(0, 100.0, 101.0, 99.0, 100.0),
+                candle(1, 102.0, 103.0, 100.0, 101.0),
+                candle(2, 104.0, 107.0, 103.0, 105.0),
+            ],
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(
+        strategy.statuses,
+        vec!["BUY OPEN", "BUY COMPLETE", "SELL OPEN", "SELL COMPLETE"]
+    );
+    // The market buy fills at the next open, the limit sell at its price
+    let prices: Vec<f64> = report.trades.iter().map(|t| t.average_price).collect();
+    assert_eq!(prices, vec![102.0, 106.0]);
+    assert_eq!(report.realized_pnl, 40.0);
+    assert_eq!(report.unrealized_pnl, 0.0);
+    assert_eq!(report.charges, 10.0);
+    assert_eq!(report.net_pnl(), 30.0);
+
+    let curve: Vec<f64> = report.equity_curve.iter().map(|p| p.pnl).collect();
+    assert_eq!(curve, vec![0.0, -15.0, 30.0, 30.0]);
+    assert_eq!(report.max_drawdown, 15.0);
+    assert_eq!(
+        report.equity_curve[1].time,
+        Time::from_timestamp(1704167160)
+    );
+}
+
+// Shorts on a stop, covers at market, and cancels a resting limit
+#[derive(Default)]
+struct StopShort {
+    ticks: usize,
+    limit_order_id: Option<String>,
+    rejected: Option<String>,
+    statuses: Vec<String>,
+}
+
+#[async_trait]
+impl Strategy for StopShort {
+    async fn on_tick(
+        &mut self,
+        orders: &dyn OrderApi,
+        _tick: &Tick,
+    ) -> Result<(), KiteConnectError> {
+        self.ticks += 1;
+        match self.ticks {
+            1 => {
+                let mut stop = order("RELIANCE", "SELL", "SL-M", 5);
+                stop.trigger_price = Some(98.0);
+                orders.place_order("regular", stop).await?;
+                let mut limit = order("RELIANCE", "BUY", "LIMIT", 5);
+                limit.price = Some(90.0);
+                self.limit_order_id = Some(orders.place_order("regular", limit).await?.order_id);
+                let unknown = orders
+                    .place_order("regular", order("TCS", "BUY", "MARKET", 1))
+                    .await;
+                self.rejected = unknown.err().map(|e| e.to_string());
+            }
+            2 => {
+                let order_id = self.limit_order_id.as_deref().unwrap();
+                orders.cancel_order("regular", order_id, None).await?;
+                assert!(
+                    orders
+                        .cancel_order("regular", order_id, None)
+                        .await
+                        .is_err()
+                );
+            }
+            _ => {}
+        }
+        Ok(())
+    }
+
+    async fn on_order_update(
+        &mut self,
+        orders: &dyn OrderApi,
+        order: &Order,
+    ) -> Result<(), KiteConnectError> {
+        self.statuses
+            .push(format!("{} {}", order.order_type, order.status));
+        if order.order_type == "SL-M" && order.status == "COMPLETE" {
+            orders
+                .place_order("regular", self::order("RELIANCE", "BUY", "MARKET", 5))
+                .await?;
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_ticks_stops_cancels_and_slippage() {
+    let ticks = [100.0, 99.0, 97.0, 98.0, 103.0].map(|last_price| Tick {
+        instrument_token: 738561,
+        mode: "ltp".to_string(),
+        last_price,
+        ..Default::default()
+    });
+    let mut strategy = StopShort::default();
+    let report = Backtest::new()
+        .instrument(738561, "NSE", "RELIANCE")
+        .fill_model(FillModel {
+            slippage: Slippage::Percent(1.0),
+            ..Default::default()
+        })
+        .run_ticks(&mut strategy, ticks)
+        .await
+        .unwrap();
+
+    assert!(strategy.rejected.unwrap().contains("NSE:TCS"));
+    assert_eq!(
+        strategy.statuses,
+        vec![
+            "SL-M TRIGGER PENDING",
+            "LIMIT OPEN",
+            "LIMIT CANCELLED",
+            "SL-M COMPLETE",
+            "MARKET OPEN",
+            "MARKET COMPLETE"
+        ]
+    );
+    // The stop fills below its trigger at the gap down, less slippage; the cover pays slippage
+    let prices: Vec<f64> = report.trades.iter().map(|t| t.average_price).collect();
+    assert!((prices[0] - 96.03).abs() < 1e-9);
+    assert!((prices[1] - 98.98).abs() < 1e-9);
+    assert!((report.realized_pnl + 14.75).abs() < 1e-9);
+    assert_eq!(report.orders.len(), 3);
+    assert_eq!(report.orders[1].cancelled_quantity, 5.0);
+    assert_eq!(report.equity_curve.len(), 5);
+}
+
+// Places one order on the first candle or tick and records its updates
+struct PlaceOnce {
+    params: Option<OrderParams>,
+    updates: Vec<(String, f64, f64)>,
+}
+
+impl PlaceOnce {
+    fn new(params: OrderParams) -> Self {
+        Self {
+            params: Some(params),
+            updates: Vec::new(),
+        }
+    }
+
+    async fn place(&mut self, orders: &dyn OrderApi) -> Result<(), KiteConnectError> {
+        if let Some(params) = self.params.take() {
+            orders.place_order("regular", params).await?;
+        }
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl Strategy for PlaceOnce {
+    async fn on_candle(
+        &mut self,
+        orders: &dyn OrderApi,
+        _instrument_token: u32,
+        _candle: &HistoricalData,
+    ) -> Result<(), KiteConnectError> {
+        self.place(orders).await
+    }
+
+    async fn on_tick(
+        &mut self,
+        orders: &dyn OrderApi,
+        _tick: &Tick,
+    ) -> Result<(), KiteConnectError> {
+        self.place(orders).await
+    }
+
+    async fn on_order_update(
+        &mut self,
+        _orders: &dyn OrderApi,
+        order: &Order,
+    ) -> Result<(), KiteConnectError> {
+        self.updates.push((
+            order.status.clone(),
+            order.filled_quantity,
+            order.average_price,
+        ));
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_partial_and_immediate_fills() {
+    let candles = || {
+        vec![
+            candle(0, 99.0, 101.0, 98.0, 100.0),
+            candle(1, 102.0, 103.0, 101.0, 102.0),
+            candle(2, 104.0, 105.0, 103.0, 104.0),
+            candle(3, 106.0, 107.0, 105.0, 106.0),
+        ]
+    };
+
+    // At most 10% of each candle's 1000 volume fills
+    let mut strategy = PlaceOnce::new(order("INFY", "BUY", "MARKET", 250));
+    let report = Backtest::new()
+        .instrument(408065, "NSE", "INFY")
+        .fill_model(FillModel {
+            slippage: Slippage::Fixed(0.5),
+            participation: Some(0.1),
+            charges_per_order: 20.0,
+            ..Default::default()
+        })
+        .run_candles(&mut strategy, candles())
+        .await
+        .unwrap();
+    assert_eq!(
+        strategy.updates,
+        vec![
+            ("OPEN".to_string(), 0.0, 0.0),
+            ("OPEN".to_string(), 100.0, 102.5),
+            ("OPEN".to_string(), 200.0, 103.5),
+            ("COMPLETE".to_string(), 250.0, 104.1),
+        ]
+    );
+    let fills: Vec<_> = report
+        .trades
+        .iter()
+        .map(|t| (t.quantity, t.average_price))
+        .collect();
+    assert_eq!(fills, vec![(100.0, 102.5), (100.0, 104.5), (50.0, 106.5)]);
+    assert_eq!(report.charges, 20.0);
+
+    // Immediate fills happen on placement, at the close of the candle reacted to
+    let mut strategy = PlaceOnce::new(order("INFY", "BUY", "MARKET", 250));
+    let report = Backtest::new()
+        .instrument(408065, "NSE", "INFY")
+        .fill_model(FillModel {
+            immediate: true,
+            slippage: Slippage::Percent(1.0),
+            ..Default::default()
+        })
+        .run_candles(&mut strategy, candles())
+        .await
+        .unwrap();
+    assert_eq!(
+        strategy.updates,
+        vec![
+            ("OPEN".to_string(), 0.0, 0.0),
+            ("COMPLETE".to_string(), 250.0, 101.0),
+        ]
+    );
+    assert_eq!(
+        report.trades[0].fill_timestamp,
+        Time::from_timestamp(1704167100)
+    );
+}
+
+fn depth_tick(last_price: f64, volume_traded: u32, bids: &[(f64, u32)]) -> Tick {
+    let mut depth = Depth::default();
+    for (level, &(price, quantity)) in depth.buy.iter_mut().zip(bids) {
+        *level = DepthItem {
+            price,
+            quantity,
+            orders: 1,
+        };
+    }
+    Tick {
+        instrument_token: 408065,
+        mode: "full".to_string(),
+        last_price,
+        volume_traded,
+        depth,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_queue_position_limit_fills() {
+    let ticks = || {
+        vec![
+            // 300 queued ahead at 100 when the order joins
+            depth_tick(100.5, 1000, &[(100.0, 300), (99.95, 500)]),
+            // A cancellation ahead leaves 200
+            depth_tick(100.5, 1200, &[(100.0, 200), (99.95, 500)]),
+            // 150 trade at the limit, then 70: the last 20 reach the order
+            depth_tick(100.0, 1350, &[]),
+            depth_tick(100.0, 1420, &[]),
+            // Trading through the limit fills the rest, at the better price
+            depth_tick(99.9, 1500, &[]),
+        ]
+    };
+    let mut buy = order("INFY", "BUY", "LIMIT", 50);
+    buy.price = Some(100.0);
+
+    let mut strategy = PlaceOnce::new(buy.clone());
+    let report = Backtest::new()
+        .instrument(408065, "NSE", "INFY")
+        .fill_model(FillModel {
+            limit_fills: LimitFills::Queue,
+            ..Default::default()
+        })
+        .run_ticks(&mut strategy, ticks())
+        .await
+        .unwrap();
+    assert_eq!(
+        strategy.updates,
+        vec![
+            ("OPEN".to_string(), 0.0, 0.0),
+            ("OPEN".to_string(), 20.0, 100.0),
+            ("COMPLETE".to_string(), 50.0, 99.94),
+        ]
+    );
+    assert_eq!(report.trades.len(), 2);
+
+    // Filled in full as soon as the price touches the limit
+    let mut strategy = PlaceOnce::new(buy);
+    let report = Backtest::new()
+        .instrument(408065, "NSE", "INFY")
+        .run_ticks(&mut strategy, ticks())
+        .await
+        .unwrap();
+    assert_eq!(strategy.updates.len(), 2);
+    assert_eq!(report.trades[0].quantity, 50.0);
+}
+
+#[tokio::test]
+async fn test_backtest_skips_indicative_ticks() {
+    let tick = |last_price, indicative| Tick {
+        instrument_token: 738561,
+        mode: "ltp".to_string(),
+        last_price,
+        indicative,
+        ..Default::default()
+    };
+    let mut sell = order("RELIANCE", "SELL", "LIMIT", 5);
+    sell.price = Some(110.0);
+    let mut strategy = PlaceOnce::new(sell);
+    // The pre-open price discovery crosses the limit, but nothing trades at it
+    let report = Backtest::new()
+        .instrument(738561, "NSE", "RELIANCE")
+        .run_ticks(
+            &mut strategy,
+            [tick(100.0, false), tick(112.0, true), tick(104.0, false)],
+        )
+        .await
+        .unwrap();
+
+    assert!(report.trades.is_empty());
+    assert_eq!(report.orders[0].status, "OPEN");
+}
+
+// Answers every order update with another order, so it never settles
+struct Churn;
+
+#[async_trait]
+impl Strategy for Churn {
+    async fn on_tick(
+        &mut self,
+        orders: &dyn OrderApi,
+        _tick: &Tick,
+    ) -> Result<(), KiteConnectError> {
+        orders
+            .place_order("regular", order("RELIANCE", "BUY", "MARKET", 1))
+            .await?;
+        Ok(())
+    }
+
+    async fn on_order_update(
+        &mut self,
+        orders: &dyn OrderApi,
+        _order: &Order,
+    ) -> Result<(), KiteConnectError> {
+        orders
+            .place_order("regular", order("RELIANCE", "BUY", "MARKET", 1))
+            .await?;
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_backtest_stops_a_strategy_that_never_settles() {
+    let tick = Tick {
+        instrument_token: 738561,
+        mode: "ltp".to_string(),
+        last_price: 100.0,
+        ..Default::default()
+    };
+    let err = Backtest::new()
+        .instrument(738561, "NSE", "RELIANCE")
+        .run_ticks(&mut Churn, [tick])
+        .await
+        .unwrap_err();
+    let rounds = format!("{} rounds", kiteconnect_rs::backtest::MAX_UPDATE_ROUNDS);
+    assert!(err.to_string().contains(&rounds));
+}
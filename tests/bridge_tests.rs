@@ -0,0 +1,193 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use async_trait::async_trait;
+use kiteconnect_rs::bridge::{Encoding, Publisher, TickPublisher, TopicTemplate};
+use kiteconnect_rs::codec::TickCodec;
+use kiteconnect_rs::markets::HistoricalData;
+use kiteconnect_rs::models::time::Time;
+use kiteconnect_rs::{DepthItem, KiteConnectError, OHLC, Tick, TickChange, TickerEvent};
+use std::sync::Mutex;
+
+#[derive(Default)]
+struct MemoryPublisher {
+    messages: Mutex<Vec<(String, Vec<u8>)>>,
+}
+
+#[async_trait]
+impl Publisher for MemoryPublisher {
+    async fn publish(&self, topic: &str, payload: Vec<u8>) -> Result<(), KiteConnectError> {
+        self.messages
+            .lock()
+            .unwrap()
+            .push((topic.to_owned(), payload));
+        Ok(())
+    }
+}
+
+fn sample_tick() -> Tick {
+    Tick {
+        instrument_token: 408065,
+        mode: "full".to_string(),
+        last_price: 1480.5,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_json_encoding_roundtrip() {
+    let tick = sample_tick();
+    let bytes = Encoding::Json.encode(&tick).unwrap();
+    let decoded: Tick = serde_json::from_slice(&bytes).unwrap();
+    assert_eq!(decoded, tick);
+}
+
+#[cfg(feature = "msgpack")]
+#[test]
+fn test_msgpack_encoding_is_compact() {
+    let tick = sample_tick();
+    let json = Encoding::Json.encode(&tick).unwrap();
+    let msgpack = Encoding::MsgPack.encode(&tick).unwrap();
+    assert!(!msgpack.is_empty());
+    assert!(msgpack.len() < json.len());
+}
+
+#[test]
+fn test_topic_template_renders_tick() {
+    let template = TopicTemplate::new("kite/ticks/{exchange}/{token}/{mode}");
+    assert_eq!(
+        template.render_tick(&sample_tick()),
+        "kite/ticks/NSE/408065/full"
+    );
+
+    let index = Tick {
+        instrument_token: 256265,
+        ..Default::default()
+    };
+    assert_eq!(
+        TopicTemplate::new("kite.ticks.{exchange}.{token}").render_tick(&index),
+        "kite.ticks.INDICES.256265"
+    );
+}
+
+#[tokio::test]
+async fn test_tick_publisher_forwards_ticks() {
+    let publisher = TickPublisher::new(MemoryPublisher::default());
+    publisher
+        .forward(&TickerEvent::Tick(sample_tick()))
+        .await
+        .unwrap();
+    publisher.forward(&TickerEvent::Connect).await.unwrap();
+
+    let messages = publisher.publisher().messages.lock().unwrap();
+    assert_eq!(messages.len(), 1);
+    assert_eq!(messages[0].0, "kite/ticks/NSE/408065");
+    let decoded: Tick = serde_json::from_slice(&messages[0].1).unwrap();
+    assert_eq!(decoded, sample_tick());
+}
+
+fn full_tick() -> Tick {
+    let mut tick = sample_tick();
+    tick.is_tradable = true;
+    tick.timestamp = Time::from_timestamp(1_717_386_300);
+    tick.volume_traded = 120_000;
+    tick.net_change = -12.25;
+    tick.ohlc = OHLC {
+        instrument_token: Some(408065),
+        open: 1490.0,
+        high: 1495.0,
+        low: 1470.0,
+        close: 1492.75,
+    };
+    tick.depth.buy[0] = DepthItem {
+        price: 1480.0,
+        quantity: 25,
+        orders: 2,
+    };
+    tick.depth.sell[4] = DepthItem {
+        price: 1481.5,
+        quantity: 10,
+        orders: 1,
+    };
+    tick.change = Some(TickChange {
+        oi_change: -200,
+        volume_delta: 50,
+        traded_value_delta: 74_000.0,
+    });
+    tick
+}
+
+fn sample_candle() -> HistoricalData {
+    HistoricalData {
+        date: Time::from_timestamp(1_717_386_300),
+        open: 1490.0,
+        high: 1495.0,
+        low: 1470.0,
+        close: 1492.75,
+        volume: 120_000,
+        oi: 0,
+    }
+}
+
+#[test]
+fn test_encoding_codec_roundtrip() {
+    let codecs = vec![
+        Encoding::Json,
+        #[cfg(feature = "msgpack")]
+        Encoding::MsgPack,
+    ];
+    for codec in codecs {
+        let tick = full_tick();
+        let bytes = codec.encode_tick(&tick).unwrap();
+        assert_eq!(codec.decode_tick(&bytes).unwrap(), tick, "{}", codec.name());
+        let candle = codec
+            .decode_candle(&codec.encode_candle(&sample_candle()).unwrap())
+            .unwrap();
+        assert_eq!(candle.date, sample_candle().date);
+        assert_eq!(candle.close, 1492.75);
+    }
+}
+
+#[cfg(feature = "protobuf")]
+#[test]
+fn test_protobuf_codec_roundtrip() {
+    use kiteconnect_rs::codec::ProtobufCodec;
+
+    let codec = ProtobufCodec;
+    for tick in [sample_tick(), full_tick(), Tick::default()] {
+        let bytes = codec.encode_tick(&tick).unwrap();
+        assert_eq!(codec.decode_tick(&bytes).unwrap(), tick);
+    }
+    let bytes = codec.encode_tick(&full_tick()).unwrap();
+    assert!(bytes.len() < Encoding::Json.encode_tick(&full_tick()).unwrap().len());
+
+    let candle = codec
+        .decode_candle(&codec.encode_candle(&sample_candle()).unwrap())
+        .unwrap();
+    assert_eq!(candle.date, sample_candle().date);
+    assert_eq!(
+        (candle.open, candle.high, candle.low, candle.close),
+        (1490.0, 1495.0, 1470.0, 1492.75)
+    );
+    assert_eq!((candle.volume, candle.oi), (120_000, 0));
+
+    // Fields added to the schema later are skipped; cut-off messages are errors
+    let mut extended = bytes.clone();
+    extended.extend_from_slice(&[0xf8, 0x01, 0x07]); // field 31, varint 7
+    assert_eq!(codec.decode_tick(&extended).unwrap(), full_tick());
+    assert!(codec.decode_tick(&bytes[..bytes.len() - 3]).is_err());
+}
+
+#[cfg(feature = "protobuf")]
+#[tokio::test]
+async fn test_tick_publisher_uses_tick_codec() {
+    use kiteconnect_rs::codec::ProtobufCodec;
+
+    let publisher = TickPublisher::new(MemoryPublisher::default()).tick_codec(ProtobufCodec);
+    publisher.publish_tick(&full_tick()).await.unwrap();
+
+    let messages = publisher.publisher().messages.lock().unwrap();
+    assert_eq!(
+        ProtobufCodec.decode_tick(&messages[0].1).unwrap(),
+        full_tick()
+    );
+}
@@ -0,0 +1,88 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use chrono::{NaiveDate, TimeZone, Utc};
+use kiteconnect_rs::corporate_actions::{CorporateAction, CorporateActions};
+use kiteconnect_rs::markets::HistoricalData;
+use kiteconnect_rs::models::time::Time;
+use kiteconnect_rs::portfolio::Holding;
+use serde_json::json;
+
+fn date(day: u32) -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 6, day).unwrap()
+}
+
+fn candle(day: u32, close: f64, volume: u32) -> HistoricalData {
+    HistoricalData {
+        // 09:15 IST
+        date: Time::new(Utc.with_ymd_and_hms(2024, 6, day, 3, 45, 0).unwrap()),
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume,
+        oi: 0,
+    }
+}
+
+fn actions() -> CorporateActions {
+    // 1:5 split on the 4th, 1:1 bonus on the 6th
+    CorporateActions::new()
+        .with(CorporateAction::split("INFY", date(4), 1, 5))
+        .with(CorporateAction::bonus("INFY", date(6), 1, 1))
+}
+
+#[test]
+fn test_adjust_candles_across_split_and_bonus() {
+    let mut candles = vec![
+        candle(3, 1000.0, 100),
+        candle(4, 200.0, 500),
+        candle(5, 200.0, 500),
+        candle(6, 100.0, 1000),
+    ];
+    actions().adjust_candles("INFY", &mut candles);
+
+    let closes: Vec<f64> = candles.iter().map(|c| c.close).collect();
+    assert_eq!(closes, [100.0, 100.0, 100.0, 100.0]);
+    let volumes: Vec<u32> = candles.iter().map(|c| c.volume).collect();
+    assert_eq!(volumes, [1000, 1000, 1000, 1000]);
+
+    // Other symbols are untouched
+    let mut other = vec![candle(3, 1000.0, 100)];
+    actions().adjust_candles("TCS", &mut other);
+    assert_eq!(other[0].close, 1000.0);
+}
+
+#[test]
+fn test_adjust_holdings_keeps_value_and_pnl() {
+    let mut holdings: Vec<Holding> = vec![
+        serde_json::from_value(json!({
+            "tradingsymbol": "INFY", "exchange": "NSE", "instrument_token": 1, "isin": "INE009A01021",
+            "product": "CNC", "price": 0.0, "used_quantity": 0, "quantity": 10,
+            "t1_quantity": 2, "realised_quantity": 10, "authorised_quantity": 0,
+            "authorised_date": "2024-06-03 00:00:00", "opening_quantity": 10,
+            "collateral_quantity": 0, "collateral_type": "", "discrepancy": false,
+            "average_price": 900.0, "last_price": 1000.0, "close_price": 990.0, "pnl": 1200.0,
+            "day_change": 10.0, "day_change_percentage": 1.0,
+            "mtf": {"quantity": 0, "used_quantity": 0, "average_price": 0.0, "value": 0.0, "initial_margin": 0.0}
+        }))
+        .unwrap(),
+    ];
+    actions().adjust_holdings(date(5), &mut holdings);
+
+    let holding = &holdings[0];
+    assert_eq!(holding.quantity, 20);
+    assert_eq!(holding.t1_quantity, 4);
+    assert_eq!(holding.average_price, 450.0);
+    assert_eq!(holding.last_price, 500.0);
+    assert_eq!(holding.pnl, 1200.0);
+}
+
+#[tokio::test]
+async fn test_load_from_source() {
+    let loaded = CorporateActions::load(&actions(), &["INFY", "TCS"])
+        .await
+        .unwrap();
+    assert_eq!(loaded, actions());
+    assert_eq!(loaded.price_factor("INFY", date(3)), 0.1);
+    assert_eq!(loaded.price_factor("TCS", date(3)), 1.0);
+}
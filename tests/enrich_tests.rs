@@ -0,0 +1,114 @@
+use kiteconnect_rs::{Tick, TickChange, TickEnricher};
+
+fn tick(token: u32, volume: u32, average_price: f64, oi: u32) -> Tick {
+    Tick {
+        mode: "full".to_string(),
+        instrument_token: token,
+        volume_traded: volume,
+        average_trade_price: average_price,
+        oi,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_enricher_tracks_changes_per_instrument() {
+    let mut enricher = TickEnricher::new();
+
+    let mut first = tick(1, 100, 10.0, 5000);
+    enricher.enrich(&mut first);
+    assert_eq!(first.change, None);
+    assert_eq!(first.traded_value(), 1000.0);
+
+    let mut other = tick(2, 10, 1.0, 0);
+    enricher.enrich(&mut other);
+    assert_eq!(other.change, None);
+
+    let mut second = tick(1, 150, 12.0, 4800);
+    enricher.enrich(&mut second);
+    assert_eq!(
+        second.change,
+        Some(TickChange {
+            oi_change: -200,
+            volume_delta: 50,
+            traded_value_delta: 800.0,
+        })
+    );
+
+    // LTP ticks carry no volume and don't disturb the state
+    let mut ltp = Tick {
+        mode: "ltp".to_string(),
+        instrument_token: 1,
+        ..Default::default()
+    };
+    enricher.enrich(&mut ltp);
+    assert_eq!(ltp.change, None);
+
+    // A lower volume is a new session
+    let mut next_day = tick(1, 20, 11.0, 4900);
+    enricher.enrich(&mut next_day);
+    let change = next_day.change.unwrap();
+    assert_eq!(change.volume_delta, 20);
+    assert_eq!(change.traded_value_delta, 220.0);
+    assert_eq!(change.oi_change, 100);
+
+    enricher.forget(1);
+    let mut after_forget = tick(1, 30, 11.0, 4900);
+    enricher.enrich(&mut after_forget);
+    assert_eq!(after_forget.change, None);
+}
+
+#[test]
+fn test_enricher_marks_pre_open_ticks_indicative() {
+    use kiteconnect_rs::clock::MarketClock;
+    use kiteconnect_rs::models::time::Time;
+
+    // 2024-01-02 09:05 and 09:15 IST
+    let pre_open = Time::from_timestamp(1704166500);
+    let open = Time::from_timestamp(1704167100);
+    let mut enricher = TickEnricher::new().pre_open(MarketClock::nse());
+
+    let mut indicative = Tick {
+        last_price: 1510.0,
+        timestamp: pre_open,
+        ..tick(1, 4000, 0.0, 0)
+    };
+    enricher.enrich(&mut indicative);
+    assert!(indicative.indicative);
+    assert_eq!(indicative.change, None);
+    assert_eq!(indicative.equilibrium_price(), Some(1510.0));
+    let price = enricher.equilibrium_price(1).unwrap();
+    assert_eq!((price.price, price.quantity), (1510.0, 4000));
+
+    // The indicative volume is not a trade, so the first regular tick has no change
+    let mut first = Tick {
+        timestamp: open,
+        ..tick(1, 4200, 1510.0, 0)
+    };
+    enricher.enrich(&mut first);
+    assert!(!first.indicative);
+    assert_eq!(first.equilibrium_price(), None);
+    assert_eq!(first.change, None);
+    assert_eq!(enricher.equilibrium_price(1).unwrap().price, 1510.0);
+
+    let json = serde_json::to_value(&indicative).unwrap();
+    assert_eq!(json["indicative"], true);
+    assert!(
+        serde_json::to_value(&first)
+            .unwrap()
+            .get("indicative")
+            .is_none()
+    );
+
+    // Only the cash segment holds the auction: an NFO tick at the same time is regular
+    let mut future = Tick {
+        timestamp: pre_open,
+        ..tick(13_238_786, 100, 20_000.0, 0)
+    };
+    enricher.enrich(&mut future);
+    assert!(!future.indicative);
+    assert!(enricher.equilibrium_price(13_238_786).is_none());
+
+    enricher.forget(1);
+    assert!(enricher.equilibrium_price(1).is_none());
+}
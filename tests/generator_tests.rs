@@ -0,0 +1,171 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use kiteconnect_rs::Mode;
+use kiteconnect_rs::test_utils::MockDataGenerator;
+
+#[test]
+fn test_generator_is_reproducible() {
+    let mut a = MockDataGenerator::new(42);
+    let mut b = MockDataGenerator::new(42);
+
+    assert_eq!(
+        a.ticks(408065, Mode::Full, 20),
+        b.ticks(408065, Mode::Full, 20)
+    );
+
+    let mut c = MockDataGenerator::new(7);
+    assert_ne!(
+        MockDataGenerator::new(42)
+            .tick(408065, Mode::LTP)
+            .last_price,
+        c.tick(408065, Mode::LTP).last_price
+    );
+}
+
+#[test]
+fn test_generated_ticks_are_consistent() {
+    let mut generator = MockDataGenerator::new(1);
+    let ticks = generator.ticks(408065, Mode::Full, 200);
+
+    let mut previous_volume = 0;
+    for tick in &ticks {
+        assert!(tick.ohlc.low <= tick.last_price && tick.last_price <= tick.ohlc.high);
+        assert!(tick.volume_traded >= previous_volume);
+        previous_volume = tick.volume_traded;
+
+        for level in 0..5 {
+            assert!(tick.depth.buy[level].price < tick.last_price);
+            assert!(tick.depth.sell[level].price > tick.last_price);
+            if level > 0 {
+                assert!(tick.depth.buy[level].price < tick.depth.buy[level - 1].price);
+                assert!(tick.depth.sell[level].price > tick.depth.sell[level - 1].price);
+            }
+        }
+    }
+
+    // Index tokens carry no depth or volume
+    let index_tick = generator.tick(256265, Mode::Full);
+    assert!(index_tick.is_index);
+    assert_eq!(index_tick.volume_traded, 0);
+}
+
+#[test]
+fn test_prices_stay_on_fine_tick_grids() {
+    let mut generator = MockDataGenerator::new(5).with_tick_size(0.0025);
+    let prices: Vec<f64> = generator
+        .ticks(2885, Mode::Full, 100)
+        .iter()
+        .flat_map(|tick| [tick.last_price, tick.ohlc.low, tick.ohlc.high])
+        .collect();
+
+    let on_grid = |price: f64, tick_size: f64| {
+        let ticks = price / tick_size;
+        (ticks - ticks.round()).abs() < 1e-6
+    };
+    assert!(prices.iter().all(|&price| on_grid(price, 0.0025)));
+    // Not cut to two decimals
+    assert!(prices.iter().any(|&price| !on_grid(price, 0.01)));
+}
+
+#[test]
+fn test_generated_orders_and_positions() {
+    let mut generator = MockDataGenerator::new(3);
+
+    let first = generator.order(408065, "INFY");
+    let second = generator.order(408065, "INFY");
+    assert_ne!(first.order_id, second.order_id);
+    assert_eq!(first.status, "COMPLETE");
+    assert_eq!(first.filled_quantity, first.quantity);
+    assert_eq!(second.status, "OPEN");
+    assert_eq!(second.pending_quantity, second.quantity);
+
+    let position = generator.position(408065, "INFY");
+    assert_eq!(
+        position.quantity,
+        position.buy_quantity - position.sell_quantity
+    );
+    assert!((position.pnl - (position.realised + position.unrealised)).abs() < 1e-6);
+
+    let quote = generator.quote(408065);
+    assert!(quote.lower_circuit_limit < quote.last_price);
+    assert!(quote.upper_circuit_limit > quote.last_price);
+}
+
+#[test]
+fn test_encoded_packets_parse_back() {
+    use kiteconnect_rs::test_utils::{encode_frame, encode_packet};
+    use kiteconnect_rs::ticker::Ticker;
+
+    let mut generator = MockDataGenerator::new(5);
+    for mode in [Mode::LTP, Mode::Quote, Mode::Full] {
+        for token in [408065, 256265] {
+            let tick = generator.tick(token, mode);
+            let parsed = Ticker::parse_packet(&encode_packet(&tick, mode)).unwrap();
+            assert_eq!(parsed, tick, "{} {}", token, mode);
+        }
+    }
+
+    let ticks = generator.ticks(408065, Mode::Quote, 3);
+    let packets: Vec<Vec<u8>> = ticks
+        .iter()
+        .map(|t| encode_packet(t, Mode::Quote))
+        .collect();
+    assert_eq!(
+        Ticker::parse_binary(&encode_frame(&packets)).unwrap(),
+        ticks
+    );
+}
+
+#[tokio::test]
+async fn test_replay_ticker_streams_subscribed_tokens() {
+    use kiteconnect_rs::test_utils::ReplayTicker;
+    use kiteconnect_rs::ticker::{Ticker, TickerEvent};
+    use std::time::Duration;
+
+    let recorded = MockDataGenerator::new(9).ticks(408065, Mode::Full, 3);
+    let mut ticks = recorded.clone();
+    ticks.insert(1, MockDataGenerator::new(9).tick(738561, Mode::Full));
+    let replay = ReplayTicker::recorded(ticks, Duration::from_millis(20))
+        .await
+        .unwrap();
+
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let received = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut received = Vec::new();
+        while let Ok(event) = events.recv().await {
+            match event {
+                TickerEvent::Connect => {
+                    handle.subscribe(vec![408065]).await.unwrap();
+                    handle.set_mode(Mode::Full, vec![408065]).await.unwrap();
+                }
+                TickerEvent::Tick(tick) => {
+                    received.push(tick);
+                    if received.len() == 3 {
+                        break;
+                    }
+                }
+                _ => {}
+            }
+        }
+        received
+    })
+    .await
+    .expect("replay ticker sent too few ticks");
+
+    // The tick for the unsubscribed token is skipped. A tick sent before the mode change
+    // arrives in quote mode, so compare prices rather than whole ticks.
+    let prices = |ticks: &[kiteconnect_rs::Tick]| -> Vec<f64> {
+        ticks.iter().map(|t| t.last_price).collect()
+    };
+    assert_eq!(prices(&received), prices(&recorded));
+    assert_eq!(replay.connections(), 1);
+
+    serve.abort();
+}
@@ -0,0 +1,212 @@
+use chrono::NaiveDate;
+use kiteconnect_rs::InstrumentStore;
+use kiteconnect_rs::instruments::{
+    OrderRounding, Rounding, round_price, round_quantity, round_to_lot, round_to_tick,
+};
+use kiteconnect_rs::labels::TransactionType;
+use kiteconnect_rs::markets::{InstrumentFilter, parse_instruments_filtered};
+use kiteconnect_rs::orders::OrderParams;
+use kiteconnect_rs::sizing::{SizeLimit, position_size};
+use std::sync::Arc;
+
+const INSTRUMENTS_CSV: &str = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+128053508,500209,INFY,INFOSYS,0,,0,0.05,1,EQ,BSE,BSE
+12345602,48225,NIFTY24JUN22000CE,NIFTY,0,2024-06-27,22000,0.05,50,CE,NFO-OPT,NFO
+12346370,48228,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO
+";
+
+fn store() -> InstrumentStore {
+    parse_instruments_filtered(INSTRUMENTS_CSV.as_bytes(), &InstrumentFilter::new())
+        .unwrap()
+        .into()
+}
+
+#[test]
+fn test_store_lookups() {
+    let store = store();
+    assert_eq!(store.len(), 4);
+    assert_eq!(store.token_of("NSE", "INFY"), Some(408065));
+    assert_eq!(store.token_of("BSE", "INFY"), Some(128053508));
+    assert_eq!(store.token_of("NSE", "NIFTY24JUNFUT"), None);
+    assert_eq!(
+        store.get(12345602).unwrap().tradingsymbol,
+        "NIFTY24JUN22000CE"
+    );
+    assert!(store.contains(12346370));
+    assert!(!store.contains(1));
+}
+
+#[test]
+fn test_check_tokens() {
+    let store = store();
+    let expiry_day = NaiveDate::from_ymd_opt(2024, 6, 27).unwrap();
+
+    let check = store.check_tokens(&[408065, 12345602, 1], expiry_day);
+    assert_eq!(check.unknown, vec![1]);
+    assert!(check.expired.is_empty());
+
+    let check = store.check_tokens(
+        &[408065, 12345602, 12346370],
+        expiry_day.succ_opt().unwrap(),
+    );
+    assert!(check.unknown.is_empty());
+    assert_eq!(check.expired, vec![12345602, 12346370]);
+    assert!(!check.is_ok());
+    assert!(store.check_tokens(&[408065], expiry_day).is_ok());
+}
+
+#[cfg(feature = "mmap")]
+#[test]
+fn test_mapped_dump_roundtrip() {
+    use kiteconnect_rs::instruments::MappedInstruments;
+    use std::time::Duration;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("instruments.bin");
+    store().save(&path).unwrap();
+
+    let mapped = MappedInstruments::open(&path).unwrap();
+    assert_eq!(mapped.len(), 4);
+    assert!(!mapped.is_older_than(Duration::from_secs(60)));
+    assert_eq!(mapped.token_of("BSE", "INFY"), Some(128053508));
+    assert_eq!(mapped.token_of("NSE", "MISSING"), None);
+
+    let option = mapped.get_by_symbol("NFO", "NIFTY24JUN22000CE").unwrap();
+    let original = store();
+    let original = original.get(12345602).unwrap();
+    assert_eq!(option.expiry, original.expiry);
+    assert_eq!(option.strike, 22000.0);
+    assert_eq!(option.lot_size, 50.0);
+
+    let reloaded = mapped.to_store().unwrap();
+    assert_eq!(reloaded.len(), 4);
+    assert!(reloaded.get(408065).unwrap().expiry.is_null());
+
+    // Saving again replaces the file by rename, the open mapping is unaffected
+    store().save(&path).unwrap();
+    assert_eq!(mapped.token_of("BSE", "INFY"), Some(128053508));
+    assert_eq!(std::fs::read_dir(dir.path()).unwrap().count(), 1);
+
+    drop(mapped);
+    std::fs::write(&path, b"not a dump").unwrap();
+    assert!(MappedInstruments::open(&path).is_err());
+}
+
+#[test]
+fn test_round_to_tick_and_lot() {
+    let store = store();
+    let infy = store.get(408065).unwrap();
+    let option = store.get(12345602).unwrap();
+
+    assert_eq!(round_to_tick(1500.12, infy), 1500.1);
+    assert_eq!(round_to_tick(1500.13, infy), 1500.15);
+    assert_eq!(round_to_tick(100.05, infy), 100.05);
+    assert_eq!(round_to_lot(120, option), 100);
+    assert_eq!(round_to_lot(30, option), 0);
+    assert_eq!(round_to_lot(7, infy), 7);
+
+    let buy = Some(TransactionType::Buy);
+    let sell = Some(TransactionType::Sell);
+    assert_eq!(round_price(10.07, 0.05, Rounding::Passive, buy), 10.05);
+    assert_eq!(round_price(10.07, 0.05, Rounding::Passive, sell), 10.1);
+    assert_eq!(round_price(10.07, 0.05, Rounding::Up, buy), 10.1);
+    assert_eq!(round_price(10.1, 0.05, Rounding::Up, None), 10.1);
+    assert_eq!(round_quantity(120, 50, Rounding::Up), 150);
+    assert_eq!(round_quantity(120, 50, Rounding::Nearest), 100);
+}
+
+#[test]
+fn test_order_rounding() {
+    let rounding = OrderRounding::new(Arc::new(store()));
+    let order = |symbol: &str, exchange: &str, quantity: i32| OrderParams {
+        exchange: Some(exchange.to_string()),
+        tradingsymbol: Some(symbol.to_string()),
+        transaction_type: Some("SELL".to_string()),
+        quantity: Some(quantity),
+        price: Some(101.02),
+        trigger_price: Some(101.33),
+        ..Default::default()
+    };
+
+    let mut params = order("NIFTY24JUN22000CE", "NFO", 130);
+    rounding.apply(&mut params).unwrap();
+    assert_eq!(params.quantity, Some(100));
+    assert_eq!(params.price, Some(101.05));
+    assert_eq!(params.trigger_price, Some(101.35));
+
+    let mut params = order("NIFTY24JUN22000CE", "NFO", 40);
+    assert!(rounding.apply(&mut params).is_err());
+
+    // Unknown instruments pass through untouched
+    let mut params = order("TCS", "NSE", 3);
+    rounding.apply(&mut params).unwrap();
+    assert_eq!(params.price, Some(101.02));
+}
+
+#[test]
+fn test_position_size() {
+    let store = store();
+    let infy = store.get(408065).unwrap();
+    let future = store.get(12346370).unwrap();
+
+    let size = position_size(1500.02, 1480.0, 1000.0, infy).unwrap();
+    assert_eq!(size.transaction_type, TransactionType::Buy);
+    assert_eq!(size.entry, 1500.0);
+    assert_eq!(size.quantity, 50);
+    assert_eq!(size.risk, 1000.0);
+    assert_eq!(size.limited_by, SizeLimit::Risk);
+
+    // A stop above the entry sizes a short, rounded down to whole lots
+    let size = position_size(22000.0, 22030.0, 5000.0, future).unwrap();
+    assert_eq!(size.transaction_type, TransactionType::Sell);
+    assert_eq!((size.quantity, size.lots), (150, 3));
+    assert_eq!(size.risk, 4500.0);
+    let order = size.order_builder(future).build().unwrap();
+    assert_eq!(order.tradingsymbol.as_deref(), Some("NIFTY24JUNFUT"));
+    assert_eq!(order.quantity, Some(150));
+    assert_eq!(order.price, Some(22000.0));
+
+    let err = position_size(22000.0, 22030.0, 1000.0, future).unwrap_err();
+    assert!(err.to_string().contains("exceeds the risk amount"));
+    assert!(position_size(1500.0, 1500.01, 1000.0, infy).is_err());
+    assert!(position_size(1500.0, 1480.0, 0.0, infy).is_err());
+}
+
+#[test]
+fn test_price_formatting_by_segment() {
+    use kiteconnect_rs::instruments::{PriceFormatter, format_price, price_decimals};
+
+    const SEGMENTS_CSV: &str = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+412675,1612,USDINR24JUNFUT,USDINR,0,2024-06-26,0,0.0025,1,FUT,CDS-FUT,CDS
+109134855,426308,CRUDEOIL24JUNFUT,CRUDEOIL,0,2024-06-19,0,1,100,FUT,MCX-FUT,MCX
+109128711,426284,SILVERM24JUNFUT,SILVERM,0,2024-06-28,0,1,5,FUT,MCX-FUT,MCX
+";
+    let store: InstrumentStore =
+        parse_instruments_filtered(SEGMENTS_CSV.as_bytes(), &InstrumentFilter::new())
+            .unwrap()
+            .into();
+
+    let infy = store.get(408065).unwrap();
+    let usdinr = store.get(412675).unwrap();
+    let crude = store.get(109134855).unwrap();
+    assert_eq!(price_decimals(infy), 2);
+    assert_eq!(price_decimals(usdinr), 4);
+    assert_eq!(price_decimals(crude), 0);
+
+    assert_eq!(format_price(infy, 1573.7), "1573.70");
+    assert_eq!(format_price(infy, 1573.72), "1573.70");
+    assert_eq!(format_price(usdinr, 83.4512), "83.4500");
+    assert_eq!(format_price(crude, 6543.6), "6544");
+
+    let formatter = PriceFormatter::new(Arc::new(store));
+    assert_eq!(formatter.format(412675, 83.4525), "83.4525");
+    assert_eq!(
+        formatter.format_symbol("MCX", "SILVERM24JUNFUT", 90210.4),
+        "90210"
+    );
+    assert_eq!(formatter.format(1, 12.3456), "12.35");
+}
@@ -1,7 +1,8 @@
 use crate::integration::mock_server::KiteMockServer;
 use kiteconnect_rs::{
-    KiteConnect, KiteConnectError, KiteConnectErrorKind,
-    alerts::{AlertOperator, AlertParams, AlertStatus, AlertType},
+    KiteConnect, KiteConnectError, KiteConnectErrorKind, Tick, TickerEvent,
+    alerts::{Alert, AlertOperator, AlertParams, AlertStatus, AlertType, AlertWatcher},
+    markets::Instrument,
 };
 use std::collections::HashMap;
 
@@ -55,7 +56,7 @@ async fn test_create_alert() {
     assert_eq!(alert.name, "NIFTY 50");
     assert_eq!(alert.lhs_exchange, "INDICES");
     assert_eq!(alert.r#type, AlertType::Simple);
-    assert!(alert.uuid.len() > 0);
+    assert!(!alert.uuid.is_empty());
 }
 
 #[tokio::test]
@@ -66,7 +67,7 @@ async fn test_get_alerts() {
     assert!(result.is_ok(), "Failed to get alerts: {:?}", result.err());
 
     let alerts = result.unwrap();
-    assert!(alerts.len() > 0, "No alerts returned");
+    assert!(!alerts.is_empty(), "No alerts returned");
 
     let first_alert = &alerts[0];
     assert!(!first_alert.uuid.is_empty(), "Alert UUID is empty");
@@ -289,3 +290,118 @@ async fn test_alert_params_validation() {
         result.err()
     );
 }
+
+fn price_alert(uuid: &str, symbol: &str, operator: &str, rhs: serde_json::Value) -> Alert {
+    let mut alert = serde_json::json!({
+        "type": "simple",
+        "user_id": "AB1234",
+        "uuid": uuid,
+        "name": uuid,
+        "status": "enabled",
+        "disabled_reason": "",
+        "lhs_attribute": "last_price",
+        "lhs_exchange": "NSE",
+        "lhs_tradingsymbol": symbol,
+        "operator": operator,
+        "rhs_type": "constant",
+        "rhs_attribute": "",
+        "rhs_exchange": "",
+        "rhs_tradingsymbol": "",
+        "rhs_constant": null,
+        "alert_count": 0,
+        "created_at": null,
+        "updated_at": null,
+        "basket": null
+    });
+    alert
+        .as_object_mut()
+        .unwrap()
+        .extend(rhs.as_object().unwrap().clone());
+    serde_json::from_value(alert).unwrap()
+}
+
+fn nse_instrument(token: u32, symbol: &str) -> Instrument {
+    serde_json::from_value(serde_json::json!({
+        "instrument_token": token,
+        "exchange_token": token / 256,
+        "tradingsymbol": symbol,
+        "name": symbol,
+        "last_price": 0.0,
+        "strike": 0.0,
+        "tick_size": 0.05,
+        "lot_size": 1.0,
+        "instrument_type": "EQ",
+        "segment": "NSE",
+        "exchange": "NSE"
+    }))
+    .unwrap()
+}
+
+fn tick(token: u32, last_price: f64) -> TickerEvent {
+    TickerEvent::Tick(Tick {
+        instrument_token: token,
+        last_price,
+        ..Default::default()
+    })
+}
+
+#[test]
+fn test_alert_watcher_emits_local_crossings() {
+    let instruments = [
+        nse_instrument(408065, "INFY"),
+        nse_instrument(2953217, "TCS"),
+    ];
+    let alerts = vec![
+        price_alert(
+            "above",
+            "INFY",
+            ">=",
+            serde_json::json!({"rhs_constant": 1500.0}),
+        ),
+        price_alert(
+            "pair",
+            "TCS",
+            ">",
+            serde_json::json!({
+                "rhs_type": "instrument",
+                "rhs_attribute": "last_price",
+                "rhs_exchange": "NSE",
+                "rhs_tradingsymbol": "INFY"
+            }),
+        ),
+        price_alert(
+            "unknown",
+            "NOPE",
+            "<",
+            serde_json::json!({"rhs_constant": 1.0}),
+        ),
+        price_alert(
+            "volume",
+            "INFY",
+            ">",
+            serde_json::json!({"lhs_attribute": "volume", "rhs_constant": 1.0}),
+        ),
+    ];
+
+    let mut watcher = AlertWatcher::new(alerts, &instruments);
+    assert_eq!(watcher.tokens(), vec![408065, 2953217]);
+    let skipped: Vec<&str> = watcher.skipped().iter().map(|a| a.uuid.as_str()).collect();
+    assert_eq!(skipped, vec!["unknown", "volume"]);
+
+    assert!(watcher.on_event(&tick(408065, 1490.0)).is_empty());
+    let crossed = watcher.on_event(&tick(408065, 1500.0));
+    assert_eq!(crossed.len(), 1);
+    assert_eq!(crossed[0].alert.uuid, "above");
+    assert_eq!(crossed[0].threshold, 1500.0);
+    // Stays true, not emitted again until it goes false
+    assert!(watcher.on_event(&tick(408065, 1510.0)).is_empty());
+    assert!(watcher.on_event(&tick(408065, 1499.0)).is_empty());
+    assert_eq!(watcher.on_event(&tick(408065, 1501.0)).len(), 1);
+
+    // Instrument-vs-instrument alert needs both prices
+    let crossed = watcher.on_event(&tick(2953217, 3500.0));
+    assert_eq!(crossed.len(), 1);
+    assert_eq!(crossed[0].alert.uuid, "pair");
+    assert_eq!(crossed[0].threshold, 1501.0);
+    assert!(watcher.on_event(&TickerEvent::Connect).is_empty());
+}
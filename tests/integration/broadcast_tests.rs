@@ -0,0 +1,136 @@
+use futures_util::{SinkExt, StreamExt};
+use kiteconnect_rs::Ticker;
+use kiteconnect_rs::broadcast::BroadcastServer;
+use kiteconnect_rs::test_utils::ReplayTicker;
+use kiteconnect_rs::ticker::TickerHandle;
+use serde_json::Value;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::task::JoinHandle;
+use tokio_tungstenite::tungstenite::Message;
+use tokio_tungstenite::{MaybeTlsStream, WebSocketStream};
+
+type WsClient = WebSocketStream<MaybeTlsStream<tokio::net::TcpStream>>;
+
+// Replay generated ticks for two instruments through a ticker
+async fn replay_ticker() -> (ReplayTicker, TickerHandle, JoinHandle<()>) {
+    let replay = ReplayTicker::generated(11, Duration::from_millis(10))
+        .await
+        .unwrap();
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let serve = tokio::spawn(async move {
+        let _ = ticker.serve().await;
+    });
+    handle.subscribe(vec![408065, 738561]).await.unwrap();
+    (replay, handle, serve)
+}
+
+async fn start(server: BroadcastServer) -> SocketAddr {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    tokio::spawn(async move { axum::serve(listener, server.router()).await });
+    addr
+}
+
+async fn get(addr: SocketAddr, path: &str) -> tokio::net::TcpStream {
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    let request = format!(
+        "GET {} HTTP/1.1\r\nHost: localhost\r\nConnection: close\r\n\r\n",
+        path
+    );
+    stream.write_all(request.as_bytes()).await.unwrap();
+    stream
+}
+
+#[tokio::test]
+async fn test_sse_relays_filtered_ticks() {
+    let (_replay, handle, serve) = replay_ticker().await;
+    let server = BroadcastServer::new(handle)
+        .cors_origin("http://localhost:3000")
+        .unwrap();
+    let addr = start(server).await;
+
+    let mut response = String::new();
+    let mut stream = get(addr, "/events?tokens=738561&types=tick").await;
+    tokio::time::timeout(Duration::from_secs(5), async {
+        let mut buf = [0u8; 4096];
+        while response.matches("event: tick").count() < 3 {
+            let n = stream.read(&mut buf).await.unwrap();
+            assert!(n > 0, "server closed the stream");
+            response.push_str(&String::from_utf8_lossy(&buf[..n]));
+        }
+    })
+    .await
+    .expect("too few ticks relayed");
+
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("access-control-allow-origin: http://localhost:3000"));
+    let ticks: Vec<Value> = response
+        .lines()
+        .filter_map(|line| line.strip_prefix("data: "))
+        .map(|data| serde_json::from_str(data).unwrap())
+        .collect();
+    assert!(!ticks.is_empty());
+    for tick in ticks {
+        assert_eq!(tick["type"], "tick");
+        assert_eq!(tick["data"]["instrument_token"], 738561);
+    }
+
+    let mut stream = get(addr, "/events?tokens=INFY").await;
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 400"));
+    assert!(response.contains("invalid instrument token 'INFY'"));
+
+    serve.abort();
+}
+
+// Token of the next tick a WebSocket client receives
+async fn next_tick(socket: &mut WsClient) -> u64 {
+    loop {
+        if let Message::Text(text) = socket.next().await.unwrap().unwrap() {
+            let event: Value = serde_json::from_str(&text).unwrap();
+            assert_eq!(event["type"], "tick");
+            return event["data"]["instrument_token"].as_u64().unwrap();
+        }
+    }
+}
+
+#[tokio::test]
+async fn test_websocket_client_updates_its_filter() {
+    let (_replay, handle, serve) = replay_ticker().await;
+    let addr = start(BroadcastServer::new(handle)).await;
+    let (mut socket, _) =
+        tokio_tungstenite::connect_async(format!("ws://{}/ws?tokens=408065&types=tick", addr))
+            .await
+            .unwrap();
+
+    tokio::time::timeout(Duration::from_secs(5), async {
+        for _ in 0..3 {
+            assert_eq!(next_tick(&mut socket).await, 408065);
+        }
+    })
+    .await
+    .expect("too few ticks relayed");
+
+    socket
+        .send(Message::Text(r#"{"tokens": [738561]}"#.into()))
+        .await
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        // Ticks already on their way may still be for the old token
+        while next_tick(&mut socket).await != 738561 {}
+        for _ in 0..3 {
+            assert_eq!(next_tick(&mut socket).await, 738561);
+        }
+    })
+    .await
+    .expect("filter update was not applied");
+
+    serve.abort();
+}
@@ -0,0 +1,333 @@
+use kiteconnect_rs::executor::{ExecutionOutcome, Executor, ExecutorConfig, chase_price};
+use kiteconnect_rs::labels::TransactionType;
+use kiteconnect_rs::models::{Depth, DepthItem, Tick};
+use kiteconnect_rs::orders::OrderParams;
+use kiteconnect_rs::test_utils::MockDataGenerator;
+use kiteconnect_rs::twap::{Twap, TwapConfig, TwapEvent, TwapState, plan_slices};
+use kiteconnect_rs::{KiteConnectErrorKind, Mode, TickerEvent};
+use serde_json::{Value, json};
+use std::time::Duration;
+use wiremock::{
+    Mock, ResponseTemplate,
+    matchers::{method, path},
+};
+
+use super::mock_server::KiteMockServer;
+
+const INFY: u32 = 408065;
+
+fn depth(bid: f64, ask: f64) -> Depth {
+    let level = |price: f64| DepthItem {
+        price,
+        quantity: 100,
+        orders: 1,
+    };
+    let mut depth = Depth::default();
+    depth.buy[0] = level(bid);
+    depth.sell[0] = level(ask);
+    depth
+}
+
+fn depth_tick(bid: f64, ask: f64) -> Tick {
+    let mut tick = MockDataGenerator::new(7).tick(INFY, Mode::Full);
+    tick.depth = depth(bid, ask);
+    tick
+}
+
+fn order_history(status: &str) -> Value {
+    let complete = status == "COMPLETE";
+    json!({"status": "success", "data": [{
+        "placed_by": "AB1234", "order_id": "777", "exchange_order_id": null,
+        "parent_order_id": null, "status": status, "status_message": null,
+        "status_message_raw": null, "variety": "regular", "exchange": "NSE",
+        "tradingsymbol": "INFY", "instrument_token": INFY, "order_type": "LIMIT",
+        "transaction_type": "BUY", "validity": "DAY", "validity_ttl": null, "product": "CNC",
+        "quantity": 10, "disclosed_quantity": 0, "price": 100.0, "trigger_price": 0,
+        "average_price": if complete { 100.05 } else { 0.0 },
+        "filled_quantity": if complete { 10 } else { 0 },
+        "pending_quantity": if complete { 0 } else { 10 }, "cancelled_quantity": 0,
+        "auction_number": null, "tag": null, "tags": null, "market_protection": null,
+        "guid": null
+    }]})
+}
+
+fn buy_infy() -> OrderParams {
+    OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        product: Some("CNC".to_string()),
+        validity: Some("DAY".to_string()),
+        quantity: Some(10),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_chase_price_steps_towards_opposite_touch() {
+    let book = depth(100.0, 100.2);
+    assert_eq!(
+        chase_price(TransactionType::Buy, &book, 0, 0.05),
+        Some(100.0)
+    );
+    assert_eq!(
+        chase_price(TransactionType::Buy, &book, 2, 0.05),
+        Some(100.1)
+    );
+    assert_eq!(
+        chase_price(TransactionType::Buy, &book, 9, 0.05),
+        Some(100.2)
+    );
+    assert_eq!(
+        chase_price(TransactionType::Sell, &book, 1, 0.05),
+        Some(100.15)
+    );
+    assert_eq!(
+        chase_price(TransactionType::Sell, &book, 9, 0.05),
+        Some(100.0)
+    );
+    assert_eq!(
+        chase_price(TransactionType::Buy, &Depth::default(), 0, 0.05),
+        None
+    );
+
+    // Prices off the tick grid round away from the market
+    let off_grid = depth(100.03, 100.17);
+    assert_eq!(
+        chase_price(TransactionType::Buy, &off_grid, 0, 0.05),
+        Some(100.0)
+    );
+    assert_eq!(
+        chase_price(TransactionType::Sell, &off_grid, 0, 0.05),
+        Some(100.2)
+    );
+}
+
+#[tokio::test]
+async fn test_executor_reprices_until_filled() {
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "777"}))
+        .mount()
+        .await;
+    server
+        .endpoint("PUT", "/orders/regular/777")
+        .data(json!({"order_id": "777"}))
+        .mount()
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/orders/777"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(order_history("OPEN")))
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&server.server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/orders/777"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(order_history("COMPLETE")))
+        .mount(&server.server)
+        .await;
+
+    let config = ExecutorConfig::default().reprice_interval(Duration::from_millis(10));
+    let executor = Executor::new(server.client(), config);
+    assert!(executor.execute(INFY, buy_infy(), 0.05).await.is_err());
+
+    executor.on_event(&TickerEvent::Tick(depth_tick(100.0, 100.5)));
+    let report = executor.execute(INFY, buy_infy(), 0.05).await.unwrap();
+    assert_eq!(report.outcome, ExecutionOutcome::Filled);
+    assert_eq!(report.reprices, 1);
+    assert_eq!(report.last_price, 100.05);
+    assert_eq!(report.filled_quantity, 10.0);
+
+    let placed = server.received_one("POST", "/orders/regular").await.form();
+    assert_eq!(placed["order_type"], "LIMIT");
+    assert_eq!(placed["price"], "100.0");
+    let modified = server
+        .received_one("PUT", "/orders/regular/777")
+        .await
+        .form();
+    assert_eq!(modified["price"], "100.05");
+}
+
+#[tokio::test]
+async fn test_executor_converts_to_market_outside_band() {
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "777"}))
+        .mount()
+        .await;
+    server
+        .endpoint("PUT", "/orders/regular/777")
+        .data(json!({"order_id": "777"}))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/orders/777")
+        .json(order_history("OPEN"))
+        .mount()
+        .await;
+
+    let config = ExecutorConfig::default()
+        .reprice_interval(Duration::from_millis(10))
+        .band_pct(0.01)
+        .market_on_band(true);
+    let executor = Executor::new(server.client(), config);
+    executor.on_tick(&depth_tick(100.0, 101.0));
+
+    let report = executor.execute(INFY, buy_infy(), 0.05).await.unwrap();
+    assert_eq!(report.outcome, ExecutionOutcome::ConvertedToMarket);
+    assert_eq!(report.reprices, 0);
+    let modified = server
+        .received_one("PUT", "/orders/regular/777")
+        .await
+        .form();
+    assert_eq!(modified["order_type"], "MARKET");
+}
+
+#[tokio::test]
+async fn test_executor_failure_keeps_the_report() {
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "777"}))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/orders/777")
+        .json(order_history("OPEN"))
+        .mount()
+        .await;
+    server
+        .endpoint("DELETE", "/orders/regular/777")
+        .error(400, "OrderException", "Order cannot be cancelled")
+        .mount()
+        .await;
+
+    let config = ExecutorConfig::default()
+        .reprice_interval(Duration::from_millis(10))
+        .band_pct(0.01);
+    let executor = Executor::new(server.client(), config);
+    executor.on_tick(&depth_tick(100.0, 101.0));
+
+    let err = executor.execute(INFY, buy_infy(), 0.05).await.unwrap_err();
+    let KiteConnectErrorKind::ExecutionFailed { report, source } = &err.kind else {
+        panic!("expected an execution failure, got {}", err);
+    };
+    assert_eq!(report.order_id, "777");
+    assert_eq!(report.last_price, 100.0);
+    assert!(matches!(source.kind, KiteConnectErrorKind::ApiError(_)));
+}
+
+#[test]
+fn test_plan_slices() {
+    let config = TwapConfig::default()
+        .duration(Duration::from_secs(100))
+        .slices(4)
+        .lot_size(25)
+        .size_jitter_pct(40.0)
+        .timing_jitter_pct(50.0)
+        .seed(3);
+    let plan = plan_slices(1000, &config).unwrap();
+    assert_eq!(plan.len(), 4);
+    assert_eq!(plan.iter().map(|s| s.quantity).sum::<i32>(), 1000);
+    assert!(plan.iter().all(|s| s.quantity > 0 && s.quantity % 25 == 0));
+    for (i, slice) in plan.iter().enumerate() {
+        let slot = Duration::from_secs(25 * i as u64);
+        assert!(slice.offset >= slot && slice.offset <= slot + Duration::from_millis(12_500));
+    }
+    assert_eq!(plan_slices(1000, &config).unwrap(), plan);
+
+    let even = plan_slices(30, &TwapConfig::default().slices(3)).unwrap();
+    assert!(even.iter().all(|s| s.quantity == 10));
+    assert_eq!(even[1].offset, Duration::from_secs(100));
+
+    // Two lots cannot be cut into four slices
+    assert_eq!(plan_slices(50, &config).unwrap().len(), 2);
+    assert!(plan_slices(60, &config).is_err());
+    assert!(plan_slices(100, &config.clone().slices(0)).is_err());
+}
+
+async fn filling_server() -> KiteMockServer {
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "777"}))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/orders/777")
+        .json(order_history("COMPLETE"))
+        .mount()
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_twap_executes_all_slices() {
+    let server = filling_server().await;
+    let executor = Executor::new(
+        server.client(),
+        ExecutorConfig::default().reprice_interval(Duration::from_millis(5)),
+    );
+    executor.on_tick(&depth_tick(100.0, 100.5));
+
+    let parent = OrderParams {
+        quantity: Some(30),
+        ..buy_infy()
+    };
+    let config = TwapConfig::default()
+        .duration(Duration::from_millis(30))
+        .slices(3);
+    let twap = Twap::start(executor, INFY, parent, 0.05, config).unwrap();
+    let events = twap.subscribe_events();
+
+    let mut slices = 0;
+    while let Ok(event) = events.recv().await {
+        match event {
+            TwapEvent::Slice(report) => {
+                assert_eq!(report.outcome, ExecutionOutcome::Filled);
+                slices += 1;
+            }
+            TwapEvent::Finished(state) => {
+                assert_eq!(state, TwapState::Completed);
+                break;
+            }
+        }
+    }
+    assert_eq!(slices, 3);
+
+    let progress = twap.progress();
+    assert_eq!(progress.state, TwapState::Completed);
+    assert_eq!(progress.filled_quantity(), 30.0);
+    assert_eq!(progress.average_price(), Some(100.05));
+    let placed = server.received("POST", "/orders/regular").await;
+    assert_eq!(placed.len(), 3);
+    assert!(placed.iter().all(|r| r.form()["quantity"] == "10"));
+}
+
+#[tokio::test]
+async fn test_twap_cancel_stops_scheduling() {
+    let server = filling_server().await;
+    let executor = Executor::new(
+        server.client(),
+        ExecutorConfig::default().reprice_interval(Duration::from_millis(5)),
+    );
+    executor.on_tick(&depth_tick(100.0, 100.5));
+
+    let config = TwapConfig::default()
+        .duration(Duration::from_secs(60))
+        .slices(2);
+    let twap = Twap::start(executor, INFY, buy_infy(), 0.05, config).unwrap();
+    twap.cancel();
+
+    let events = twap.subscribe_events();
+    while let Ok(event) = events.recv().await {
+        if let TwapEvent::Finished(state) = event {
+            assert_eq!(state, TwapState::Cancelled);
+            break;
+        }
+    }
+    assert!(twap.progress().children.len() <= 1);
+}
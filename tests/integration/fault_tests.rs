@@ -0,0 +1,237 @@
+use kiteconnect_rs::orders::OrderParams;
+use kiteconnect_rs::test_utils::{FaultConfig, FaultProxy};
+use kiteconnect_rs::{KiteConnect, TickerBuilder, TickerEvent};
+use serde_json::json;
+use std::time::Duration;
+
+use super::mock_server::KiteMockServer;
+
+async fn orders_server() -> KiteMockServer {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+    mock_server
+}
+
+fn client(base_url: &str, timeout: Duration) -> KiteConnect {
+    KiteConnect::builder("test_api_key")
+        .base_url(base_url)
+        .access_token("test_access_token")
+        .timeout(timeout)
+        .build()
+        .unwrap()
+}
+
+#[tokio::test]
+async fn test_fault_proxy_passes_through_without_faults() {
+    let mock_server = orders_server().await;
+    let proxy = FaultProxy::start(&mock_server.base_url, FaultConfig::new(1))
+        .await
+        .unwrap();
+
+    let kite = client(&proxy.url(), Duration::from_secs(5));
+    assert!(kite.get_orders().await.unwrap().is_empty());
+    assert_eq!(proxy.connections(), 1);
+}
+
+#[tokio::test]
+async fn test_fault_proxy_drops_and_truncates() {
+    let mock_server = orders_server().await;
+    let proxy = FaultProxy::start(&mock_server.base_url, FaultConfig::new(1).drop_rate(1.0))
+        .await
+        .unwrap();
+    let kite = client(&proxy.url(), Duration::from_secs(5));
+
+    let err = kite.get_orders().await.unwrap_err();
+    assert!(err.is_retriable());
+    assert_eq!(proxy.dropped(), 1);
+
+    proxy.set_config(FaultConfig::new(1).truncate_rate(1.0).truncate_after(20));
+    assert!(kite.get_orders().await.is_err());
+    assert_eq!(proxy.truncated(), 1);
+
+    proxy.set_config(FaultConfig::new(1));
+    assert!(kite.get_orders().await.is_ok());
+}
+
+#[tokio::test]
+async fn test_fault_proxy_latency() {
+    let mock_server = orders_server().await;
+    let delay = Duration::from_millis(300);
+    let proxy = FaultProxy::start(
+        &mock_server.base_url,
+        FaultConfig::new(1).latency(delay, delay),
+    )
+    .await
+    .unwrap();
+
+    let err = client(&proxy.url(), Duration::from_millis(100))
+        .get_orders()
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        kiteconnect_rs::KiteConnectErrorKind::HttpError(_)
+    ));
+    assert!(err.is_retriable());
+}
+
+#[tokio::test]
+async fn test_error_categories() {
+    use kiteconnect_rs::{ErrorCategory, KiteConnectError, KiteConnectErrorKind};
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .rate_limited()
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/trades")
+        .status(502)
+        .body("<html>Bad Gateway</html>")
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/holdings")
+        .token_exception()
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .error(400, "InputException", "Invalid segment")
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    assert!(kite.get_orders().await.unwrap_err().is_retriable());
+    let err = kite.get_trades().await.unwrap_err();
+    assert!(
+        matches!(&err.kind, KiteConnectErrorKind::ApiError(e) if e.error_type == "GeneralException"),
+        "{:?}",
+        err.kind
+    );
+    assert_eq!(err.category(), ErrorCategory::Retriable);
+    assert!(!kite.get_holdings().await.unwrap_err().is_retriable());
+    assert!(!kite.get_positions().await.unwrap_err().is_retriable());
+
+    assert!(!KiteConnectError::invalid_params("bad quantity").is_retriable());
+    assert!(!KiteConnectError::response_too_large(1024).is_retriable());
+    assert!(!KiteConnectError::other("journal entry is corrupt").is_retriable());
+    assert!(KiteConnectError::storage("database is locked").is_retriable());
+}
+
+#[tokio::test]
+async fn test_failed_order_placement_is_ambiguous() {
+    use kiteconnect_rs::ErrorCategory;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .error(503, "NetworkException", "Upstream unavailable")
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/amo")
+        .rate_limited()
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/orders/regular/151")
+        .error(503, "NetworkException", "Upstream unavailable")
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/orders/regular/151")
+        .error(504, "NetworkException", "Upstream timed out")
+        .mount()
+        .await;
+    let kite = mock_server.client();
+    let params = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("MARKET".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(1),
+        ..Default::default()
+    };
+
+    // The order may exist, so it must not be placed again blindly
+    let err = kite
+        .place_order("regular", params.clone())
+        .await
+        .unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Ambiguous);
+    assert!(!err.is_retriable());
+
+    // A rate limited request was never executed
+    let err = kite.place_order("amo", params).await.unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Retriable);
+
+    // Modifications and cancellations may have gone through just the same
+    let err = kite
+        .modify_order("regular", "151", OrderParams::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Ambiguous);
+    let err = kite.cancel_order("regular", "151", None).await.unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Ambiguous);
+
+    let proxy = FaultProxy::start(&mock_server.base_url, FaultConfig::new(1).drop_rate(1.0))
+        .await
+        .unwrap();
+    let err = client(&proxy.url(), Duration::from_secs(5))
+        .place_order("regular", OrderParams::default())
+        .await
+        .unwrap_err();
+    assert_eq!(err.category(), ErrorCategory::Ambiguous);
+}
+
+#[tokio::test]
+async fn test_fault_proxy_disconnects_ticker() {
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let mut sockets = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            sockets.push(tokio_tungstenite::accept_async(stream).await.unwrap());
+        }
+    });
+
+    let proxy = FaultProxy::start(&addr.to_string(), FaultConfig::new(1))
+        .await
+        .unwrap();
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(proxy.ws_url())
+        .auto_reconnect(true)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let wait_for = |want: fn(&TickerEvent) -> bool| {
+        let events = events.clone();
+        async move {
+            tokio::time::timeout(Duration::from_secs(5), async {
+                loop {
+                    if want(&events.recv().await.unwrap()) {
+                        return;
+                    }
+                }
+            })
+            .await
+            .expect("ticker event not received")
+        }
+    };
+
+    wait_for(|e| matches!(e, TickerEvent::Connect)).await;
+    proxy.disconnect_all();
+    wait_for(|e| matches!(e, TickerEvent::Reconnect(1, _))).await;
+
+    serve.abort();
+    server.abort();
+}
@@ -0,0 +1,283 @@
+use kiteconnect_rs::codec::proto::Tick as TickMessage;
+use kiteconnect_rs::gateway::MarketDataGateway;
+use kiteconnect_rs::gateway::proto::{
+    GetQuoteRequest, GetQuoteResponse, PlaceOrderRequest, PlaceOrderResponse, SubscribeTicksRequest,
+};
+use kiteconnect_rs::test_utils::ReplayTicker;
+use kiteconnect_rs::ticker::TickerHandle;
+use kiteconnect_rs::{KiteConnect, Ticker};
+use serde_json::json;
+use std::net::SocketAddr;
+use std::time::Duration;
+use tonic::codec::ProstCodec;
+use tonic::codegen::http::uri::PathAndQuery;
+use tonic::transport::Channel;
+use tonic::{Code, Request};
+
+use super::mock_server::KiteMockServer;
+
+const GATEWAY_TOKEN: &str = "gateway_secret";
+
+// Serve the gateway on a free local port and connect a client to it
+async fn start(gateway: MarketDataGateway) -> tonic::client::Grpc<Channel> {
+    let addr: SocketAddr = std::net::TcpListener::bind("127.0.0.1:0")
+        .unwrap()
+        .local_addr()
+        .unwrap();
+    tokio::spawn(gateway.serve(addr));
+    for _ in 0..50 {
+        if let Ok(channel) = Channel::from_shared(format!("http://{}", addr))
+            .unwrap()
+            .connect()
+            .await
+        {
+            return tonic::client::Grpc::new(channel);
+        }
+        tokio::time::sleep(Duration::from_millis(20)).await;
+    }
+    panic!("gateway did not start");
+}
+
+fn idle_ticker() -> TickerHandle {
+    Ticker::new("test_api_key".to_string(), "test_access_token".to_string()).1
+}
+
+fn authorized<T>(message: T) -> Request<T> {
+    let mut request = Request::new(message);
+    request.metadata_mut().insert(
+        "authorization",
+        format!("Bearer {}", GATEWAY_TOKEN).parse().unwrap(),
+    );
+    request
+}
+
+async fn get_quote(
+    client: &mut tonic::client::Grpc<Channel>,
+    request: Request<GetQuoteRequest>,
+) -> Result<GetQuoteResponse, tonic::Status> {
+    client.ready().await.unwrap();
+    client
+        .unary(
+            request,
+            PathAndQuery::from_static("/kiteconnect.MarketData/GetQuote"),
+            ProstCodec::default(),
+        )
+        .await
+        .map(|response| response.into_inner())
+}
+
+async fn place_order(
+    client: &mut tonic::client::Grpc<Channel>,
+    request: Request<PlaceOrderRequest>,
+) -> Result<PlaceOrderResponse, tonic::Status> {
+    client.ready().await.unwrap();
+    client
+        .unary(
+            request,
+            PathAndQuery::from_static("/kiteconnect.MarketData/PlaceOrder"),
+            ProstCodec::default(),
+        )
+        .await
+        .map(|response| response.into_inner())
+}
+
+fn quote_json() -> serde_json::Value {
+    let depth = json!(vec![json!({"price": 1480.0, "quantity": 25, "orders": 2}); 5]);
+    json!({
+        "NSE:INFY": {
+            "instrument_token": 408065, "timestamp": "2024-06-03 09:15:00",
+            "last_price": 1480.5, "last_quantity": 5, "last_trade_time": "2024-06-03 09:14:59",
+            "average_price": 1478.2, "volume": 120000, "buy_quantity": 500, "sell_quantity": 700,
+            "ohlc": {"open": 1490.0, "high": 1495.0, "low": 1470.0, "close": 1492.75},
+            "net_change": -12.25, "oi": 0, "oi_day_high": 0, "oi_day_low": 0,
+            "lower_circuit_limit": 1332.0, "upper_circuit_limit": 1628.0,
+            "depth": {"buy": depth, "sell": depth}
+        }
+    })
+}
+
+#[tokio::test]
+async fn test_gateway_proxies_quotes_and_orders_with_auth() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote")
+        .data(quote_json())
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151220000000000"}))
+        .expect(1)
+        .mount()
+        .await;
+    let gateway =
+        MarketDataGateway::new(mock_server.client(), idle_ticker()).auth_token(GATEWAY_TOKEN);
+    let mut client = start(gateway).await;
+
+    let request = || GetQuoteRequest {
+        instruments: vec!["NSE:INFY".to_string()],
+    };
+    let err = get_quote(&mut client, Request::new(request()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+
+    let quotes = get_quote(&mut client, authorized(request()))
+        .await
+        .unwrap()
+        .quotes;
+    let quote = &quotes["NSE:INFY"];
+    assert_eq!(quote.instrument_token, 408065);
+    assert_eq!(quote.last_price, 1480.5);
+    assert_eq!(quote.ohlc.as_ref().unwrap().close, 1492.75);
+    assert_eq!(quote.depth.as_ref().unwrap().buy.len(), 5);
+
+    let order = PlaceOrderRequest {
+        exchange: "NSE".to_string(),
+        tradingsymbol: "INFY".to_string(),
+        transaction_type: "BUY".to_string(),
+        order_type: "LIMIT".to_string(),
+        product: "CNC".to_string(),
+        validity: "DAY".to_string(),
+        quantity: 10,
+        price: Some(1480.0),
+        ..Default::default()
+    };
+    let response = place_order(&mut client, authorized(order)).await.unwrap();
+    assert_eq!(response.order_id, "151220000000000");
+    let form = mock_server
+        .received_one("POST", "/orders/regular")
+        .await
+        .form();
+    assert_eq!(form["tradingsymbol"], "INFY");
+    assert_eq!(form["price"], "1480.0");
+}
+
+#[tokio::test]
+async fn test_gateway_without_token_refuses_orders_and_maps_errors() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote")
+        .error(
+            403,
+            "TokenException",
+            "Incorrect `api_key` or `access_token`.",
+        )
+        .mount()
+        .await;
+    let kite: KiteConnect = mock_server.client();
+    let mut client = start(MarketDataGateway::new(kite, idle_ticker())).await;
+
+    let err = place_order(&mut client, Request::new(PlaceOrderRequest::default()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::PermissionDenied);
+    assert!(
+        mock_server
+            .received("POST", "/orders/regular")
+            .await
+            .is_empty()
+    );
+
+    let err = get_quote(
+        &mut client,
+        Request::new(GetQuoteRequest {
+            instruments: vec!["NSE:INFY".to_string()],
+        }),
+    )
+    .await
+    .unwrap_err();
+    assert_eq!(err.code(), Code::Unauthenticated);
+    assert!(err.message().contains("Incorrect"));
+
+    let err = get_quote(&mut client, Request::new(GetQuoteRequest::default()))
+        .await
+        .unwrap_err();
+    assert_eq!(err.code(), Code::InvalidArgument);
+}
+
+#[tokio::test]
+async fn test_gateway_streams_ticks_from_shared_ticker() {
+    let replay = ReplayTicker::generated(7, Duration::from_millis(20))
+        .await
+        .unwrap();
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    // The app's own loop reads the same handle without taking ticks from the gateway
+    let app_events = handle.subscribe_events();
+    let app_ticks = tokio::spawn(async move {
+        let mut ticks = 0;
+        while ticks < 3 {
+            if let kiteconnect_rs::TickerEvent::Tick(_) = app_events.recv().await.unwrap() {
+                ticks += 1;
+            }
+        }
+    });
+    let serve = tokio::spawn(ticker.serve());
+    let mut client = start(MarketDataGateway::new(
+        KiteMockServer::new().await.client(),
+        handle.clone(),
+    ))
+    .await;
+
+    let mut streams = Vec::new();
+    for tokens in [vec![408065], vec![408065, 738561]] {
+        client.ready().await.unwrap();
+        let stream = client
+            .server_streaming::<_, TickMessage, _>(
+                Request::new(SubscribeTicksRequest {
+                    instrument_tokens: tokens,
+                }),
+                PathAndQuery::from_static("/kiteconnect.MarketData/SubscribeTicks"),
+                ProstCodec::default(),
+            )
+            .await
+            .unwrap()
+            .into_inner();
+        streams.push(stream);
+    }
+
+    let received = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut first = Vec::new();
+        while first.len() < 3 {
+            first.push(streams[0].message().await.unwrap().unwrap());
+        }
+        let mut second = Vec::new();
+        while !second
+            .iter()
+            .any(|t: &TickMessage| t.instrument_token == 738561)
+        {
+            second.push(streams[1].message().await.unwrap().unwrap());
+        }
+        (first, second)
+    })
+    .await
+    .expect("gateway streamed too few ticks");
+    assert!(
+        received
+            .0
+            .iter()
+            .all(|tick| tick.instrument_token == 408065)
+    );
+    assert!(received.0.iter().all(|tick| tick.last_price > 0.0));
+    tokio::time::timeout(Duration::from_secs(5), app_ticks)
+        .await
+        .expect("the app's loop got too few ticks")
+        .unwrap();
+
+    // Closing the only stream that wants a token unsubscribes it; shared tokens stay
+    drop(streams.pop());
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while handle.mode_of(738561).await.is_some() {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+    })
+    .await
+    .expect("token of the closed stream stayed subscribed");
+    assert!(handle.mode_of(408065).await.is_some());
+
+    serve.abort();
+}
@@ -0,0 +1,204 @@
+use std::sync::Arc;
+
+use chrono::{Duration, Utc};
+use kiteconnect_rs::audit::JsonlAuditLog;
+use kiteconnect_rs::gtt::{GTT_TYPE_OCO, GTT_TYPE_SINGLE, Gtt, orphaned_gtts};
+use kiteconnect_rs::{
+    AuditAction, AuditRecord, Holding, KiteConnectErrorKind, PortfolioTracker, Position,
+    RiskLimits, RiskViolation,
+};
+use serde_json::{Value, json};
+
+use super::mock_server::KiteMockServer;
+
+fn gtt_json(id: u64, r#type: &str, symbol: &str, side: &str, expires_in_days: i64) -> Value {
+    let expires_at = (Utc::now() + Duration::days(expires_in_days)).format("%Y-%m-%d %H:%M:%S");
+    let order = json!({
+        "exchange": "NSE", "tradingsymbol": symbol, "product": "CNC", "order_type": "LIMIT",
+        "transaction_type": side, "quantity": 10, "price": 100.0, "result": null
+    });
+    let legs = if r#type == GTT_TYPE_OCO { 2 } else { 1 };
+    json!({
+        "id": id, "user_id": "AB1234", "parent_trigger": null, "type": r#type,
+        "created_at": "2024-01-15 09:15:00", "updated_at": "2024-01-15 09:15:00",
+        "expires_at": expires_at.to_string(), "status": "active",
+        "condition": {
+            "exchange": "NSE", "tradingsymbol": symbol, "instrument_token": 1,
+            "last_price": 100.0, "trigger_values": vec![95.0; legs]
+        },
+        "orders": vec![order; legs],
+        "meta": null
+    })
+}
+
+#[tokio::test]
+async fn test_gtt_expiry_orphans_and_bulk_delete() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/gtt/triggers")
+        .data(json!([
+            gtt_json(1, GTT_TYPE_SINGLE, "INFY", "SELL", 300),
+            gtt_json(2, GTT_TYPE_OCO, "TCS", "SELL", 5),
+            gtt_json(3, GTT_TYPE_SINGLE, "SBIN", "BUY", 2),
+        ]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/gtt/triggers/1")
+        .data(json!({"trigger_id": 1}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/gtt/triggers/2")
+        .error(400, "InputException", "Invalid trigger")
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let expiring = kite.get_gtts_expiring_within(7).await.unwrap();
+    let ids: Vec<u64> = expiring.iter().map(|g| g.id).collect();
+    assert_eq!(ids, vec![3, 2]);
+
+    // INFY was sold and TCS is still held, so only the INFY stoploss is orphaned; the
+    // SBIN buy is an entry, not an exit
+    let gtts: Vec<Gtt> = kite.get_gtts().await.unwrap();
+    let tracker = PortfolioTracker::new();
+    let holding: Holding = serde_json::from_value(json!({
+        "tradingsymbol": "TCS", "exchange": "BSE", "instrument_token": 2, "isin": "INE467B01029",
+        "product": "CNC", "price": 0.0, "used_quantity": 0, "quantity": 10, "t1_quantity": 0,
+        "realised_quantity": 10, "authorised_quantity": 0, "authorised_date": "2024-01-15 00:00:00",
+        "opening_quantity": 10, "collateral_quantity": 0, "collateral_type": "",
+        "discrepancy": false, "average_price": 100.0, "last_price": 101.0, "close_price": 100.0,
+        "pnl": 0.0, "day_change": 0.0, "day_change_percentage": 0.0,
+        "mtf": {"quantity": 0, "used_quantity": 0, "average_price": 0.0, "value": 0.0, "initial_margin": 0.0}
+    }))
+    .unwrap();
+    tracker.update(&[holding], &[] as &[Position]);
+    let orphaned: Vec<u64> = orphaned_gtts(&gtts, &tracker)
+        .iter()
+        .map(|g| g.id)
+        .collect();
+    assert_eq!(orphaned, vec![1]);
+
+    let results = kite.delete_gtts(&[1, 2]).await;
+    assert_eq!(results.len(), 2);
+    assert!(results[0].is_ok());
+    assert_eq!(results[1].trigger_id, 2);
+    let error = results[1].result.as_ref().unwrap_err();
+    assert!(error.to_string().contains("Invalid trigger"));
+}
+
+#[tokio::test]
+async fn test_place_gtt_sends_json_fields() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/gtt/triggers")
+        .data(json!({"trigger_id": 7}))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let gtt: Gtt =
+        serde_json::from_value(gtt_json(7, GTT_TYPE_SINGLE, "INFY", "SELL", 30)).unwrap();
+    let response = kite.place_gtt(&gtt.params()).await.unwrap();
+    assert_eq!(response.trigger_id, 7);
+
+    let form = mock_server
+        .received_one("POST", "/gtt/triggers")
+        .await
+        .form();
+    assert_eq!(form["type"], "single");
+    let condition: Value = serde_json::from_str(&form["condition"]).unwrap();
+    assert_eq!(condition["trigger_values"], json!([95.0]));
+    let orders: Value = serde_json::from_str(&form["orders"]).unwrap();
+    assert_eq!(orders[0]["transaction_type"], "SELL");
+    assert!(orders[0].get("result").is_none());
+}
+
+#[tokio::test]
+async fn test_gtt_orders_go_through_risk_checks_and_audit() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/gtt/triggers")
+        .data(json!({"trigger_id": 7}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/gtt/triggers/7")
+        .data(json!({"trigger_id": 7}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/gtt/triggers/7")
+        .data(json!({"trigger_id": 7}))
+        .mount()
+        .await;
+    let dir = tempfile::tempdir().unwrap();
+    let audit_path = dir.path().join("audit.jsonl");
+    let mut kite = mock_server.client();
+    kite.set_risk_limits(Some(RiskLimits::new().max_quantity(20)));
+    kite.set_audit_log(Some(Arc::new(JsonlAuditLog::open(&audit_path).unwrap())));
+
+    let gtt: Gtt = serde_json::from_value(gtt_json(7, GTT_TYPE_OCO, "INFY", "SELL", 30)).unwrap();
+    kite.place_gtt(&gtt.params()).await.unwrap();
+
+    let mut params = gtt.params();
+    params.orders[1].quantity = 50;
+    let err = kite.modify_gtt(7, &params).await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::RiskViolation(RiskViolation::MaxQuantity { quantity: 50, .. })
+    ));
+    assert!(
+        mock_server
+            .received("PUT", "/gtt/triggers/7")
+            .await
+            .is_empty()
+    );
+    kite.delete_gtt(7).await.unwrap();
+
+    let records: Vec<AuditRecord> = std::fs::read_to_string(&audit_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[0].action, AuditAction::Place);
+    assert_eq!(records[0].variety.as_deref(), Some("gtt"));
+    assert_eq!(records[0].order_id.as_deref(), Some("7"));
+    assert_eq!(records[1].action, AuditAction::Modify);
+    assert_eq!(records[1].order_id.as_deref(), Some("7"));
+    assert!(records[1].error.is_some());
+    assert_eq!(records[2].action, AuditAction::Cancel);
+    assert_eq!(records[2].order_id.as_deref(), Some("7"));
+    assert!(records[2].response.is_some());
+}
+
+#[tokio::test]
+async fn test_bulk_gtt_deletes_stay_within_rate_limit() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .server
+        .register(
+            wiremock::Mock::given(wiremock::matchers::method("DELETE"))
+                .and(wiremock::matchers::path_regex("^/gtt/triggers/[0-9]+$"))
+                .respond_with(
+                    wiremock::ResponseTemplate::new(200)
+                        .set_body_json(json!({"status": "success", "data": {"trigger_id": 1}})),
+                ),
+        )
+        .await;
+    let kite = mock_server.client();
+
+    // Twelve deletes against a limit of ten per second
+    let trigger_ids: Vec<u64> = (1..=12).collect();
+    let started = std::time::Instant::now();
+    let results = kite.delete_gtts(&trigger_ids).await;
+    assert!(started.elapsed() >= std::time::Duration::from_millis(900));
+    assert_eq!(results.len(), 12);
+    assert!(results.iter().all(|r| r.is_ok()));
+    assert_eq!(
+        results.iter().map(|r| r.trigger_id).collect::<Vec<_>>(),
+        trigger_ids
+    );
+}
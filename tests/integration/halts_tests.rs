@@ -0,0 +1,99 @@
+use kiteconnect_rs::Tick;
+use kiteconnect_rs::halts::{CircuitSide, HaltDetector, HaltEvent};
+use kiteconnect_rs::markets::Quote;
+use serde_json::json;
+use std::time::Duration;
+
+fn tick(instrument_token: u32, last_price: f64) -> Tick {
+    Tick {
+        instrument_token,
+        mode: "ltp".to_string(),
+        last_price,
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_halt_detector_circuits_and_silence() {
+    let depth = json!(vec![json!({"price": 0.0, "quantity": 0, "orders": 0}); 5]);
+    let quote = |token: u32, last_price: f64, lower: f64, upper: f64| {
+        json!({
+            "instrument_token": token, "last_price": last_price, "last_quantity": 1,
+            "average_price": last_price, "volume": 100, "buy_quantity": 0,
+            "sell_quantity": 0, "net_change": 0.0, "oi": 0.0, "oi_day_high": 0.0,
+            "oi_day_low": 0.0, "lower_circuit_limit": lower, "upper_circuit_limit": upper,
+            "ohlc": {"open": last_price, "high": last_price, "low": last_price, "close": last_price},
+            "depth": {"buy": depth, "sell": depth}
+        })
+    };
+    let quotes: Quote = serde_json::from_value(json!({
+        "NSE:INFY": quote(408065, 1500.0, 1350.0, 1650.0),
+        "NSE:IDEA": quote(3677697, 9.0, 9.0, 11.0),
+        "NFO:NIFTY24JUNFUT": quote(12346370, 22000.0, 0.0, 0.0)
+    }))
+    .unwrap();
+
+    let mut halts = HaltDetector::new(Duration::from_millis(50));
+    let events = halts.watch_quotes(&quotes);
+    assert_eq!(
+        events,
+        vec![HaltEvent::CircuitHit {
+            instrument_token: 3677697,
+            side: CircuitSide::Lower,
+            last_price: 9.0,
+            limit: 9.0,
+        }]
+    );
+    assert_eq!(halts.halted(), vec![3677697]);
+
+    assert_eq!(
+        halts.on_tick(&tick(408065, 1650.0)),
+        vec![HaltEvent::CircuitHit {
+            instrument_token: 408065,
+            side: CircuitSide::Upper,
+            last_price: 1650.0,
+            limit: 1650.0,
+        }]
+    );
+    // Still at the limit, nothing new
+    assert!(halts.on_tick(&tick(408065, 1650.0)).is_empty());
+    assert_eq!(halts.circuit(408065), Some(CircuitSide::Upper));
+    assert_eq!(
+        halts.on_tick(&tick(408065, 1649.0)),
+        vec![HaltEvent::CircuitReleased {
+            instrument_token: 408065,
+            last_price: 1649.0,
+        }]
+    );
+    // Contracts without circuit limits are never at one
+    assert!(halts.on_tick(&tick(12346370, 0.05)).is_empty());
+    // Unwatched instruments are ignored
+    assert!(halts.on_tick(&tick(1, 1.0)).is_empty());
+    assert!(halts.check().is_empty());
+
+    std::thread::sleep(Duration::from_millis(80));
+    halts.on_tick(&tick(408065, 1600.0));
+    let stalled: Vec<u32> = halts
+        .check()
+        .iter()
+        .map(HaltEvent::instrument_token)
+        .collect();
+    assert_eq!(stalled, vec![3677697, 12346370]);
+    assert!(halts.check().is_empty());
+    assert!(halts.is_halted(12346370));
+    assert!(!halts.is_halted(408065));
+
+    assert_eq!(
+        halts.on_tick(&tick(12346370, 22010.0)),
+        vec![HaltEvent::Resumed {
+            instrument_token: 12346370
+        }]
+    );
+    assert_eq!(halts.halted(), vec![3677697]);
+
+    let body = serde_json::to_value(HaltEvent::Resumed {
+        instrument_token: 1,
+    })
+    .unwrap();
+    assert_eq!(body, json!({"type": "resumed", "instrument_token": 1}));
+}
@@ -0,0 +1,110 @@
+use kiteconnect_rs::health::HealthMonitor;
+use kiteconnect_rs::test_utils::ReplayTicker;
+use kiteconnect_rs::{Tick, Ticker, TickerEvent};
+use serde_json::json;
+use std::time::Duration;
+
+use super::mock_server::KiteMockServer;
+
+#[tokio::test]
+async fn test_health_tracks_auth_and_rate_limits() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/holdings")
+        .token_exception()
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+    let health = HealthMonitor::new().kite(kite.clone());
+
+    let state = health.state();
+    assert!(state.is_ready());
+    assert_eq!(state.readiness_status(), 200);
+    assert_eq!(state.authenticated, Some(true));
+    assert_eq!(state.ticker_connected, None);
+
+    kite.get_holdings().await.unwrap_err();
+    let state = health.state();
+    assert!(!state.is_ready());
+    assert_eq!(state.readiness_status(), 503);
+    // A rejected token does not go away on a restart
+    assert!(state.is_live());
+    assert_eq!(state.liveness_status(), 200);
+    assert_eq!(state.authenticated, Some(false));
+    assert_eq!(
+        state.problems,
+        vec!["access token is missing or was rejected"]
+    );
+    let body = serde_json::to_value(&state).unwrap();
+    assert_eq!(body["ready"], false);
+    assert_eq!(body["live"], true);
+    assert_eq!(body["authenticated"], false);
+
+    // A request that goes through shows the token works again
+    kite.get_orders().await.unwrap();
+    let state = health.state();
+    assert!(state.is_ready());
+    assert!(state.rate_limit_utilization.unwrap() > 0.0);
+
+    let strict = health.max_rate_limit_utilization(0.0);
+    let state = strict.state();
+    assert!(!state.is_ready());
+    assert!(state.is_live());
+    assert!(state.problems[0].contains("of the rate limit"));
+}
+
+#[tokio::test]
+async fn test_health_tracks_ticker_connection_and_tick_age() {
+    let replay = ReplayTicker::recorded(
+        vec![Tick {
+            instrument_token: 408065,
+            mode: "ltp".to_string(),
+            last_price: 1500.0,
+            ..Default::default()
+        }],
+        Duration::from_millis(20),
+    )
+    .await
+    .unwrap();
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let health = HealthMonitor::new().ticker(handle.clone());
+
+    let state = health.state();
+    assert_eq!(state.ticker_connected, Some(false));
+    assert_eq!(state.problems, vec!["ticker is disconnected"]);
+    assert!(!state.is_ready());
+    assert!(state.is_live());
+
+    let serve = tokio::spawn(ticker.serve());
+    handle.subscribe(vec![408065]).await.unwrap();
+    let events = handle.subscribe_events();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        while !matches!(events.recv().await, Ok(TickerEvent::Tick(_))) {}
+    })
+    .await
+    .expect("no tick arrived");
+
+    let state = health.state();
+    assert!(state.is_ready(), "{:?}", state.problems);
+    assert!(state.is_live());
+    assert_eq!(state.ticker_connected, Some(true));
+    assert!(state.last_tick_age_ms.is_some());
+
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let stalled = health.max_tick_age(Duration::ZERO).state();
+    assert_eq!(stalled.problems, vec!["no tick for 0s"]);
+    assert!(!stalled.is_ready());
+    assert!(!stalled.is_live());
+    assert_eq!(stalled.liveness_status(), 503);
+
+    serve.abort();
+}
@@ -0,0 +1,207 @@
+use kiteconnect_rs::journal::{
+    Intent, JournalEvent, OrderJournal, OrderStatusChange, UntrackedOrder,
+};
+use kiteconnect_rs::orders::OrderParams;
+use kiteconnect_rs::{AuditAction, AuditLog, AuditRecord, KiteConnect};
+use serde_json::{Value, json};
+use std::io::Write;
+
+use super::mock_server::KiteMockServer;
+
+fn order_json(order_id: &str, status: &str, filled_quantity: f64) -> Value {
+    json!({
+        "placed_by": "XXXXXX",
+        "order_id": order_id,
+        "status": status,
+        "variety": "regular",
+        "exchange": "NSE",
+        "tradingsymbol": "INFY",
+        "instrument_token": 408065,
+        "order_type": "LIMIT",
+        "transaction_type": "BUY",
+        "validity": "DAY",
+        "product": "CNC",
+        "quantity": 10.0,
+        "disclosed_quantity": 0.0,
+        "price": 1500.0,
+        "trigger_price": 0.0,
+        "average_price": 1500.0,
+        "filled_quantity": filled_quantity,
+        "pending_quantity": 10.0 - filled_quantity,
+        "cancelled_quantity": 0.0
+    })
+}
+
+fn order_update(order_id: &str, status: &str) -> AuditRecord {
+    let mut record = AuditRecord::new(AuditAction::OrderUpdate);
+    record.order_id = Some(order_id.to_string());
+    record.response = Some(order_json(order_id, status, 0.0));
+    record
+}
+
+#[tokio::test]
+async fn test_order_journal_replays_and_recovers() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151220000000000"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/orders/regular/151220000000000")
+        .data(json!({"order_id": "151220000000000"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([
+            order_json("151220000000000", "COMPLETE", 10.0),
+            order_json("999", "OPEN", 0.0)
+        ]))
+        .mount()
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("orders.journal");
+    let journal = OrderJournal::open(&path).unwrap();
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .access_token("test_access_token")
+        .audit_log(journal.clone())
+        .build()
+        .unwrap();
+
+    let params = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(10),
+        price: Some(1500.0),
+        ..Default::default()
+    };
+    let order_id = kite.place_order("regular", params).await.unwrap().order_id;
+    let modify = OrderParams {
+        price: Some(1490.0),
+        ..Default::default()
+    };
+    kite.modify_order("regular", &order_id, modify)
+        .await
+        .unwrap();
+    journal.append(&order_update(&order_id, "OPEN")).unwrap();
+
+    // An order from an earlier run, gone from today's order book
+    let mut earlier = AuditRecord::new(AuditAction::Place);
+    earlier.variety = Some("regular".to_string());
+    earlier.order_id = Some("100".to_string());
+    earlier.response = Some(json!({"order_id": "100"}));
+    journal.append_event(&JournalEvent::Order(earlier)).unwrap();
+
+    let exit = Intent::new("exit-1", "oco")
+        .order_ids(vec![order_id.clone()])
+        .data(json!({"target": 1600.0, "stop": 1450.0}));
+    journal.record_intent(&exit).unwrap();
+    journal.record_intent(&Intent::new("gtt-1", "gtt")).unwrap();
+    journal.complete_intent("gtt-1").unwrap();
+
+    // A crash mid-write leaves a partial last line
+    let mut file = std::fs::OpenOptions::new()
+        .append(true)
+        .open(&path)
+        .unwrap();
+    file.write_all(br#"{"event":"intent","da"#).unwrap();
+    drop(file);
+
+    let state = OrderJournal::open(&path).unwrap().replay().unwrap();
+    assert_eq!(state.orders.len(), 2);
+    let order = &state.orders[&order_id];
+    assert_eq!(order.status.as_deref(), Some("OPEN"));
+    assert_eq!(order.params.as_ref().unwrap().price, Some(1490.0));
+    assert_eq!(order.params.as_ref().unwrap().quantity, Some(10));
+    assert_eq!(state.open_orders().count(), 2);
+    assert_eq!(state.intents.len(), 1);
+    assert_eq!(state.intents["exit-1"], exit);
+
+    // Recovery catches up with the order book and journals what changed
+    let (state, reconciliation) = journal.recover(&kite).await.unwrap();
+    assert_eq!(
+        reconciliation.changed,
+        vec![OrderStatusChange {
+            order_id: order_id.clone(),
+            journaled: Some("OPEN".to_string()),
+            current: "COMPLETE".to_string(),
+        }]
+    );
+    assert_eq!(reconciliation.missing, vec!["100"]);
+    assert_eq!(state.orders[&order_id].filled_quantity, 10.0);
+    assert!(!state.orders.contains_key("999"));
+
+    let state = journal.replay().unwrap();
+    let open: Vec<_> = state.open_orders().map(|o| o.order_id.as_str()).collect();
+    assert_eq!(open, vec!["100"]);
+}
+
+#[tokio::test]
+async fn test_order_journal_resolves_unknown_placements_by_tag() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .error(503, "NetworkException", "Gateway timed out")
+        .mount()
+        .await;
+    let mut placed = order_json("777", "OPEN", 0.0);
+    placed["tag"] = json!("exit-7");
+    let mut manual = order_json("888", "OPEN", 0.0);
+    manual["tag"] = json!("manual");
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([manual, placed]))
+        .mount()
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let journal = OrderJournal::open(dir.path().join("orders.journal")).unwrap();
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .access_token("test_access_token")
+        .audit_log(journal.clone())
+        .build()
+        .unwrap();
+
+    let params = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(10),
+        price: Some(1500.0),
+        tag: Some("exit-7".to_string()),
+        ..Default::default()
+    };
+    kite.place_order("regular", params).await.unwrap_err();
+
+    let state = journal.replay().unwrap();
+    assert!(state.orders.is_empty());
+    assert_eq!(state.unknown.len(), 1);
+    let request_id = state.unknown[0].request_id.clone();
+
+    // Only the order tagged like the failed placement is taken as its order
+    let (state, reconciliation) = journal.recover(&kite).await.unwrap();
+    assert_eq!(
+        reconciliation.untracked,
+        vec![UntrackedOrder {
+            order_id: "777".to_string(),
+            request_id,
+        }]
+    );
+    assert!(state.unknown.is_empty());
+    assert!(!state.orders.contains_key("888"));
+
+    let state = journal.replay().unwrap();
+    assert!(state.unknown.is_empty());
+    let order = &state.orders["777"];
+    assert_eq!(order.status.as_deref(), Some("OPEN"));
+    assert_eq!(order.params.as_ref().unwrap().quantity, Some(10));
+}
@@ -0,0 +1,336 @@
+use async_trait::async_trait;
+use kiteconnect_rs::backtest::{LiveRunner, OrderApi, Strategy};
+use kiteconnect_rs::clock::MarketClock;
+use kiteconnect_rs::markets::HistoricalData;
+use kiteconnect_rs::models::time::Time;
+use kiteconnect_rs::risk::RiskLimits;
+use kiteconnect_rs::test_utils::ReplayTicker;
+use kiteconnect_rs::{KiteConnectError, Mode, OrderParams, Tick, Ticker};
+use serde_json::json;
+use std::time::Duration;
+
+use super::mock_server::KiteMockServer;
+
+// 2024-01-02 09:15:00 IST
+const OPEN: i64 = 1704167100;
+
+fn tick(seconds: i64, last_price: f64, volume_traded: u32) -> Tick {
+    Tick {
+        instrument_token: 408065,
+        mode: "full".to_string(),
+        is_tradable: true,
+        last_price,
+        volume_traded,
+        timestamp: Time::from_timestamp(OPEN + seconds),
+        ..Default::default()
+    }
+}
+
+fn limit_buy(quantity: i32) -> OrderParams {
+    OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(quantity),
+        price: Some(100.0),
+        ..Default::default()
+    }
+}
+
+#[derive(Default)]
+struct Recorder {
+    ticks: usize,
+    candles: Vec<HistoricalData>,
+    refused: Vec<String>,
+    order_ids: Vec<String>,
+}
+
+#[async_trait]
+impl Strategy for Recorder {
+    async fn on_candle(
+        &mut self,
+        _orders: &dyn OrderApi,
+        _instrument_token: u32,
+        candle: &HistoricalData,
+    ) -> Result<(), KiteConnectError> {
+        self.candles.push(candle.clone());
+        Ok(())
+    }
+
+    async fn on_tick(
+        &mut self,
+        orders: &dyn OrderApi,
+        _tick: &Tick,
+    ) -> Result<(), KiteConnectError> {
+        self.ticks += 1;
+        if self.ticks == 1 {
+            for quantity in [20, 5, 5] {
+                match orders.place_order("regular", limit_buy(quantity)).await {
+                    Ok(response) => self.order_ids.push(response.order_id),
+                    Err(e) => self.refused.push(e.to_string()),
+                }
+            }
+        }
+        if self.ticks == 5 {
+            return Err(KiteConnectError::other("done"));
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_live_runner_guards_orders_and_builds_candles() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151220000000000"}))
+        .expect(1)
+        .mount()
+        .await;
+
+    let replay = ReplayTicker::recorded(
+        vec![
+            // May arrive before the mode change, without an exchange time
+            tick(0, 102.0, 1500),
+            tick(30, 102.0, 1500),
+            tick(70, 101.0, 1800),
+            tick(100, 103.0, 2600),
+            tick(125, 104.0, 3000),
+        ],
+        Duration::from_millis(20),
+    )
+    .await
+    .unwrap();
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let serve = tokio::spawn(ticker.serve());
+    handle.subscribe(vec![408065]).await.unwrap();
+    handle.set_mode(Mode::Full, vec![408065]).await.unwrap();
+
+    let mut strategy = Recorder::default();
+    let runner = LiveRunner::new(mock_server.client(), handle)
+        .risk_limits(RiskLimits::new().max_quantity(10))
+        .max_orders(1)
+        .candles("minute")
+        .unwrap();
+    let err = tokio::time::timeout(Duration::from_secs(5), runner.run(&mut strategy))
+        .await
+        .expect("runner did not finish")
+        .unwrap_err();
+    assert!(err.to_string().contains("done"));
+
+    assert_eq!(strategy.order_ids, vec!["151220000000000"]);
+    assert_eq!(strategy.refused.len(), 2);
+    assert!(strategy.refused[0].contains("quantity 20 exceeds limit 10"));
+    assert!(strategy.refused[1].contains("1 orders placed, limit is 1"));
+    let form = mock_server
+        .received_one("POST", "/orders/regular")
+        .await
+        .form();
+    assert_eq!(form["quantity"], "5");
+
+    // The fifth tick opens a third candle, completing the second
+    let candles: Vec<_> = strategy
+        .candles
+        .iter()
+        .map(|c| (c.date, c.open, c.high, c.low, c.close, c.volume))
+        .collect();
+    assert_eq!(
+        candles,
+        vec![
+            (Time::from_timestamp(OPEN), 102.0, 102.0, 102.0, 102.0, 0),
+            (
+                Time::from_timestamp(OPEN + 60),
+                101.0,
+                103.0,
+                101.0,
+                103.0,
+                800
+            ),
+        ]
+    );
+
+    serve.abort();
+}
+
+#[tokio::test]
+async fn test_live_runner_leaves_pre_open_ticks_out_of_candles() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151220000000000"}))
+        .mount()
+        .await;
+
+    let replay = ReplayTicker::recorded(
+        vec![
+            tick(0, 102.0, 1500),
+            // Indicative prices of the pre-open auction, 09:05 and 09:10
+            tick(-600, 99.0, 0),
+            tick(-300, 98.0, 0),
+            tick(30, 102.0, 1500),
+            tick(70, 101.0, 1800),
+        ],
+        Duration::from_millis(20),
+    )
+    .await
+    .unwrap();
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .pre_open(MarketClock::nse())
+        .build()
+        .unwrap();
+    let serve = tokio::spawn(ticker.serve());
+    handle.subscribe(vec![408065]).await.unwrap();
+    handle.set_mode(Mode::Full, vec![408065]).await.unwrap();
+
+    let mut strategy = Recorder::default();
+    let runner = LiveRunner::new(mock_server.client(), handle)
+        .candles("minute")
+        .unwrap();
+    tokio::time::timeout(Duration::from_secs(5), runner.run(&mut strategy))
+        .await
+        .expect("runner did not finish")
+        .unwrap_err();
+
+    let opens: Vec<_> = strategy.candles.iter().map(|c| (c.date, c.open)).collect();
+    assert_eq!(opens, vec![(Time::from_timestamp(OPEN), 102.0)]);
+
+    serve.abort();
+}
+
+#[derive(Default)]
+struct Splitter {
+    ticks: usize,
+    placed: Vec<Vec<String>>,
+    refused: Vec<String>,
+}
+
+#[async_trait]
+impl Strategy for Splitter {
+    async fn on_tick(
+        &mut self,
+        orders: &dyn OrderApi,
+        _tick: &Tick,
+    ) -> Result<(), KiteConnectError> {
+        self.ticks += 1;
+        if self.ticks > 1 {
+            return Err(KiteConnectError::other("done"));
+        }
+        let future = OrderParams {
+            exchange: Some("NFO".to_string()),
+            tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+            order_type: Some("MARKET".to_string()),
+            product: Some("NRML".to_string()),
+            price: None,
+            quantity: Some(4000),
+            ..limit_buy(0)
+        };
+        for params in [future, limit_buy(5), limit_buy(5)] {
+            match orders.place_order("regular", params).await {
+                Ok(response) => {
+                    let mut ids = vec![response.order_id];
+                    ids.extend(response.split_order_ids);
+                    self.placed.push(ids);
+                }
+                Err(e) => self.refused.push(e.to_string()),
+            }
+        }
+        let changes = OrderParams {
+            quantity: Some(6000),
+            ..Default::default()
+        };
+        if let Err(e) = orders.modify_order("regular", "181", changes).await {
+            self.refused.push(e.to_string());
+        }
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_live_runner_counts_split_orders_once_and_checks_modifications() {
+    use kiteconnect_rs::freeze::FreezeQuantities;
+    use wiremock::matchers::{method, path};
+    use wiremock::{Mock, ResponseTemplate};
+
+    let mock_server = KiteMockServer::new().await;
+    for (priority, order_id) in [(1, "181"), (2, "182"), (3, "183"), (4, "184")] {
+        Mock::given(method("POST"))
+            .and(path("/orders/regular"))
+            .respond_with(
+                ResponseTemplate::new(200)
+                    .set_body_json(json!({"status": "success", "data": {"order_id": order_id}})),
+            )
+            .up_to_n_times(1)
+            .with_priority(priority)
+            .mount(&mock_server.server)
+            .await;
+    }
+    mock_server
+        .endpoint("GET", "/orders/181")
+        .data(json!([{
+            "placed_by": "AB1234", "order_id": "181", "exchange_order_id": null,
+            "parent_order_id": null, "status": "OPEN", "status_message": null,
+            "status_message_raw": null, "variety": "regular", "exchange": "NFO",
+            "tradingsymbol": "NIFTY24JUNFUT", "instrument_token": 1, "order_type": "MARKET",
+            "transaction_type": "BUY", "validity": "DAY", "validity_ttl": null,
+            "product": "NRML", "quantity": 1800, "disclosed_quantity": 0, "price": 0,
+            "trigger_price": 0, "average_price": 0, "filled_quantity": 0,
+            "pending_quantity": 1800, "cancelled_quantity": 0, "auction_number": null,
+            "tag": null, "tags": null, "market_protection": null, "guid": null
+        }]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/orders/regular/181")
+        .data(json!({"order_id": "181"}))
+        .expect(0)
+        .mount()
+        .await;
+
+    let replay = ReplayTicker::recorded(
+        vec![tick(0, 102.0, 1500), tick(30, 102.0, 1500)],
+        Duration::from_millis(20),
+    )
+    .await
+    .unwrap();
+    let (ticker, handle) = Ticker::builder("test_api_key", "test_access_token")
+        .url(replay.url())
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let serve = tokio::spawn(ticker.serve());
+    handle.subscribe(vec![408065]).await.unwrap();
+
+    let mut kite = mock_server.client();
+    kite.set_freeze_quantities(Some(FreezeQuantities::default()));
+    let mut strategy = Splitter::default();
+    let runner = LiveRunner::new(kite, handle)
+        .risk_limits(RiskLimits::new().max_quantity(5000))
+        .max_orders(2);
+    tokio::time::timeout(Duration::from_secs(5), runner.run(&mut strategy))
+        .await
+        .expect("runner did not finish")
+        .unwrap_err();
+
+    // The split future counts as one order, so the second order still goes through
+    assert_eq!(
+        strategy.placed,
+        vec![vec!["181", "182", "183"], vec!["184"]]
+    );
+    assert_eq!(strategy.refused.len(), 2);
+    assert!(strategy.refused[0].contains("2 orders placed, limit is 2"));
+    assert!(
+        strategy.refused[1].contains("quantity 6000 exceeds limit 5000"),
+        "{:?}",
+        strategy.refused
+    );
+
+    serve.abort();
+}
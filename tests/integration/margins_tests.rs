@@ -29,7 +29,7 @@ async fn test_get_order_margins() {
     let compact_result = kite
         .get_order_margins(GetMarginParams {
             order_params: vec![params.clone()],
-            compact: true,
+            mode: MarginMode::Compact,
         })
         .await;
 
@@ -43,7 +43,7 @@ async fn test_get_order_margins() {
     let detailed_result = kite
         .get_order_margins(GetMarginParams {
             order_params: vec![params],
-            compact: false,
+            mode: MarginMode::Full,
         })
         .await;
 
@@ -84,7 +84,7 @@ async fn test_get_basket_margins() {
     let result = kite
         .get_basket_margins(GetBasketParams {
             order_params: vec![params],
-            compact: true,
+            mode: MarginMode::Compact,
             consider_positions: true,
         })
         .await;
@@ -152,3 +152,368 @@ async fn test_get_order_charges() {
     let order_charges = result.unwrap();
     assert_eq!(order_charges.len(), 3);
 }
+
+const LEDGER_CSV: &str = "\
+Particulars,Posting Date,Cost Center,Voucher Type,Debit,Credit,Net Balance
+Opening Balance,,,,0,0,1000.00
+Funds added using UPI from XXXX1234,2024-04-01,NSE-EQ - Z,Bank Receipts,0,\"5,000.00\",6000.00
+Net settlement for Eq (T+1) 2024067 from 2024-04-01,2024-04-02,NSE-EQ - Z,Book Voucher,4200.50,0,1799.50
+DP Charges for Sale of INFY on 02/04/2024,2024-04-02,NSE-EQ - Z,Delivery Voucher,15.93,0,1783.57
+AMC for Demat Account for the period 01-Apr-2024 to 30-Jun-2024,2024-04-03,NSE-EQ - Z,Journal Entry,88.50,0,1695.07
+Payout of ₹500 to bank account XXXX1234,2024-04-04,NSE-EQ - Z,Bank Payments,500.00,0,1195.07
+Closing Balance,,,,0,0,1195.07
+";
+
+#[test]
+fn test_parse_ledger_csv() {
+    use kiteconnect_rs::ledger::{LedgerEntryKind, LedgerSummary, parse_ledger_csv};
+
+    let entries = parse_ledger_csv(LEDGER_CSV.as_bytes()).unwrap();
+    let kinds: Vec<_> = entries.iter().map(|e| e.kind).collect();
+    assert_eq!(
+        kinds,
+        vec![
+            LedgerEntryKind::OpeningBalance,
+            LedgerEntryKind::Payin,
+            LedgerEntryKind::Settlement,
+            LedgerEntryKind::Charges,
+            LedgerEntryKind::Amc,
+            LedgerEntryKind::Payout,
+            LedgerEntryKind::ClosingBalance,
+        ]
+    );
+    assert_eq!(entries[1].credit, 5000.0);
+    assert_eq!(
+        entries[1].posting_date,
+        chrono::NaiveDate::from_ymd_opt(2024, 4, 1)
+    );
+    assert!(entries[0].posting_date.is_none());
+
+    let summary = LedgerSummary::from_entries(&entries);
+    assert_eq!(summary.payins, 5000.0);
+    assert_eq!(summary.payouts, 500.0);
+    assert!((summary.computed_balance() - 1195.07).abs() < 0.001);
+    assert_eq!(summary.balance(), 1195.07);
+
+    let err = parse_ledger_csv("Particulars,Debit\nx,1\n".as_bytes()).unwrap_err();
+    assert!(err.to_string().contains("postingdate"));
+}
+
+#[tokio::test]
+async fn test_reconcile_ledger_with_margins() {
+    use kiteconnect_rs::ledger::parse_ledger_csv;
+    use serde_json::json;
+
+    let mock_server = KiteMockServer::new().await;
+    let used = json!({
+        "debits": 0.0, "exposure": 0.0, "m2m_realised": 0.0, "m2m_unrealised": 0.0,
+        "option_premium": 0.0, "payout": 0.0, "span": 0.0, "holding_sales": 0.0,
+        "turnover": 0.0, "liquid_collateral": 0.0, "stock_collateral": 0.0, "delivery": 0.0
+    });
+    mock_server
+        .endpoint("GET", "/user/margins/equity")
+        .data(json!({
+            "enabled": true,
+            "net": 1195.07,
+            "available": {
+                "adhoc_margin": 0.0, "cash": 1195.07, "collateral": 0.0,
+                "intraday_payin": 0.0, "live_balance": 1195.07, "opening_balance": 1195.07
+            },
+            "utilised": used
+        }))
+        .mount()
+        .await;
+
+    let entries = parse_ledger_csv(LEDGER_CSV.as_bytes()).unwrap();
+    let reconciliation = mock_server
+        .client()
+        .reconcile_ledger(&entries)
+        .await
+        .unwrap();
+    assert!(reconciliation.matches(), "{:?}", reconciliation);
+
+    // Dropping the payout line leaves the ledger inconsistent with its closing balance
+    let partial: Vec<_> = entries
+        .into_iter()
+        .filter(|e| !e.particulars.starts_with("Payout"))
+        .collect();
+    let reconciliation = mock_server
+        .client()
+        .reconcile_ledger(&partial)
+        .await
+        .unwrap();
+    assert!(!reconciliation.internally_consistent);
+    assert!(!reconciliation.matches());
+}
+
+pub(crate) fn margins_json(net: f64, debits: f64) -> serde_json::Value {
+    use serde_json::json;
+
+    let segment = json!({
+        "enabled": true,
+        "net": net,
+        "available": {
+            "adhoc_margin": 0.0, "cash": net, "collateral": 0.0,
+            "intraday_payin": 0.0, "live_balance": net, "opening_balance": net + debits
+        },
+        "utilised": {
+            "debits": debits, "exposure": 0.0, "m2m_realised": 0.0, "m2m_unrealised": 0.0,
+            "option_premium": 0.0, "payout": 0.0, "span": debits, "holding_sales": 0.0,
+            "turnover": 0.0, "liquid_collateral": 0.0, "stock_collateral": 0.0, "delivery": 0.0
+        }
+    });
+    json!({"equity": segment, "commodity": segment})
+}
+
+#[test]
+fn test_margin_levels() {
+    use kiteconnect_rs::margin_watch::{MarginLevels, segment_utilisation_pct};
+    use kiteconnect_rs::{AllMargins, MarginEvent};
+
+    let margins: AllMargins = serde_json::from_value(margins_json(20000.0, 80000.0)).unwrap();
+    let pct = segment_utilisation_pct(&margins, Default::default());
+    assert!((pct - 80.0).abs() < 1e-9);
+
+    let mut levels = MarginLevels::new(75.0, 90.0);
+    assert_eq!(levels.update(50.0), None);
+    assert_eq!(levels.update(80.0), Some(MarginEvent::Warning(80.0)));
+    assert_eq!(levels.update(85.0), None);
+    assert_eq!(levels.update(95.0), Some(MarginEvent::Critical(95.0)));
+    assert_eq!(levels.update(80.0), Some(MarginEvent::Warning(80.0)));
+    assert_eq!(levels.update(10.0), Some(MarginEvent::Recovered(10.0)));
+}
+
+#[tokio::test]
+async fn test_margin_watcher_emits_events() {
+    use kiteconnect_rs::{MarginEvent, MarginWatchConfig, MarginWatcher};
+    use std::time::Duration;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/user/margins")
+        .data(margins_json(5000.0, 95000.0))
+        .mount()
+        .await;
+
+    let watcher = MarginWatcher::spawn(mock_server.client(), MarginWatchConfig::default());
+    let events = watcher.subscribe_events();
+    let event = tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .unwrap()
+        .unwrap();
+    assert_eq!(event, MarginEvent::Critical(95.0));
+    assert_eq!(watcher.last_utilisation(), Some(95.0));
+
+    watcher.stop();
+    assert!(events.recv().await.is_err());
+}
+
+#[tokio::test]
+async fn test_position_size_with_margin() {
+    use kiteconnect_rs::labels::Product;
+    use kiteconnect_rs::markets::{InstrumentFilter, parse_instruments_filtered};
+    use kiteconnect_rs::sizing::SizeLimit;
+    use serde_json::json;
+
+    let csv = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+12346370,48228,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO
+";
+    let instruments = parse_instruments_filtered(csv.as_bytes(), &InstrumentFilter::new()).unwrap();
+    let future = &instruments[0];
+
+    let mock_server = KiteMockServer::new().await;
+    let charges = json!({
+        "transaction_tax": 0.0, "transaction_tax_type": "stt", "exchange_turnover_charge": 0.0,
+        "sebi_turnover_charge": 0.0, "brokerage": 0.0, "stamp_duty": 0.0,
+        "gst": {"igst": 0.0, "cgst": 0.0, "sgst": 0.0, "total": 0.0}, "total": 0.0
+    });
+    mock_server
+        .endpoint("POST", "/margins/orders")
+        .data(json!([{
+            "type": "equity", "tradingsymbol": "NIFTY24JUNFUT", "exchange": "NFO",
+            "span": 90000.0, "exposure": 20000.0, "charges": charges, "total": 110000.0
+        }]))
+        .mount()
+        .await;
+    let used = json!({
+        "debits": 0.0, "exposure": 0.0, "m2m_realised": 0.0, "m2m_unrealised": 0.0,
+        "option_premium": 0.0, "payout": 0.0, "span": 0.0, "holding_sales": 0.0,
+        "turnover": 0.0, "liquid_collateral": 0.0, "stock_collateral": 0.0, "delivery": 0.0
+    });
+    mock_server
+        .endpoint("GET", "/user/margins/equity")
+        .data(json!({
+            "enabled": true,
+            "net": 250000.0,
+            "available": {
+                "adhoc_margin": 0.0, "cash": 250000.0, "collateral": 0.0,
+                "intraday_payin": 0.0, "live_balance": 250000.0, "opening_balance": 250000.0
+            },
+            "utilised": used
+        }))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    // Risk allows 5 lots but the margin only pays for 2
+    let size = kite
+        .position_size_with_margin(22000.0, 21980.0, 5000.0, future, Product::Nrml)
+        .await
+        .unwrap();
+    assert_eq!((size.quantity, size.lots), (100, 2));
+    assert_eq!(size.limited_by, SizeLimit::Margin);
+    assert_eq!(size.margin_per_lot, Some(110000.0));
+    assert_eq!(size.available_margin, Some(250000.0));
+    assert_eq!(size.risk, 2000.0);
+    let margins = mock_server.received_one("POST", "/margins/orders").await;
+    assert_eq!(margins.json()[0]["quantity"], 50.0);
+    assert_eq!(margins.json()[0]["product"], "NRML");
+
+    let size = kite
+        .position_size_with_margin(22000.0, 21980.0, 2000.0, future, Product::Nrml)
+        .await
+        .unwrap();
+    assert_eq!(size.lots, 2);
+    assert_eq!(size.limited_by, SizeLimit::Risk);
+}
+
+fn order_margin_param(trading_symbol: &str) -> OrderMarginParam {
+    OrderMarginParam {
+        exchange: "NSE".to_string(),
+        trading_symbol: trading_symbol.to_string(),
+        transaction_type: "BUY".to_string(),
+        variety: "regular".to_string(),
+        product: "CNC".to_string(),
+        order_type: "MARKET".to_string(),
+        quantity: 1.0,
+        price: None,
+        trigger_price: None,
+    }
+}
+
+// Margin Calculator response for one order in either mode
+fn order_margins_json(trading_symbol: &str, total: f64, mode: MarginMode) -> serde_json::Value {
+    use serde_json::json;
+
+    match mode {
+        MarginMode::Compact => json!({
+            "type": "equity", "tradingsymbol": trading_symbol, "exchange": "NSE", "total": total
+        }),
+        MarginMode::Full => json!({
+            "type": "equity", "tradingsymbol": trading_symbol, "exchange": "NSE",
+            "span": 0.0, "exposure": 0.0, "option_premium": 0.0, "additional": 0.0, "bo": 0.0,
+            "cash": 0.0, "var": total, "pnl": {"realised": 0.0, "unrealised": 0.0},
+            "leverage": 1.0,
+            "charges": {
+                "transaction_tax": 1.5, "transaction_tax_type": "stt",
+                "exchange_turnover_charge": 0.05, "sebi_turnover_charge": 0.0, "brokerage": 0.0,
+                "stamp_duty": 0.23, "gst": {"igst": 0.01, "cgst": 0.0, "sgst": 0.0, "total": 0.01},
+                "total": 1.79
+            },
+            "total": total
+        }),
+    }
+}
+
+#[tokio::test]
+async fn test_order_margins_modes() {
+    use serde_json::json;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/margins/orders")
+        .data(json!([order_margins_json(
+            "INFY",
+            1450.5,
+            MarginMode::Full
+        )]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let margins = kite
+        .get_order_margins(GetMarginParams {
+            order_params: vec![order_margin_param("INFY")],
+            mode: MarginMode::Full,
+        })
+        .await
+        .unwrap();
+    assert_eq!(margins[0].total, 1450.5);
+    assert_eq!(margins[0].charges.total, 1.79);
+    let request = mock_server.received_one("POST", "/margins/orders").await;
+    assert!(!request.query.contains_key("mode"));
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/margins/orders")
+        .data(json!([order_margins_json(
+            "INFY",
+            1450.5,
+            MarginMode::Compact
+        )]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let compact = kite
+        .get_order_margins_compact(GetMarginParams {
+            order_params: vec![order_margin_param("INFY")],
+            mode: MarginMode::Full,
+        })
+        .await
+        .unwrap();
+    assert_eq!(compact[0].trading_symbol, "INFY");
+    assert_eq!(compact[0].order_type, "equity");
+    assert_eq!(compact[0].total, 1450.5);
+    let request = mock_server.received_one("POST", "/margins/orders").await;
+    assert_eq!(request.query["mode"], "compact");
+
+    // The full type still parses a compact response, without charges
+    let margins = kite
+        .get_order_margins(GetMarginParams {
+            order_params: vec![order_margin_param("INFY")],
+            mode: MarginMode::Compact,
+        })
+        .await
+        .unwrap();
+    assert_eq!(margins[0].total, 1450.5);
+    assert_eq!(margins[0].charges.total, 0.0);
+}
+
+#[tokio::test]
+async fn test_basket_margins_compact() {
+    use serde_json::json;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/margins/basket")
+        .data(json!({
+            "initial": order_margins_json("", 4200.0, MarginMode::Compact),
+            "final": order_margins_json("", 3900.0, MarginMode::Compact),
+            "orders": [
+                order_margins_json("INFY", 1450.5, MarginMode::Compact),
+                order_margins_json("TCS", 2749.5, MarginMode::Compact)
+            ]
+        }))
+        .mount()
+        .await;
+
+    let basket = mock_server
+        .client()
+        .get_basket_margins_compact(GetBasketParams {
+            order_params: vec![order_margin_param("INFY"), order_margin_param("TCS")],
+            mode: MarginMode::Full,
+            consider_positions: true,
+        })
+        .await
+        .unwrap();
+    assert_eq!(basket.orders.len(), 2);
+    assert_eq!(basket.orders[1].trading_symbol, "TCS");
+    assert_eq!(basket.initial.unwrap().total, 4200.0);
+    assert_eq!(basket.final_margins.unwrap().total, 3900.0);
+
+    let request = mock_server.received_one("POST", "/margins/basket").await;
+    assert_eq!(request.query["mode"], "compact");
+    assert_eq!(request.query["consider_positions"], "true");
+}
@@ -282,3 +282,517 @@ async fn test_get_mf_instruments() {
         assert!(!instrument.tradingsymbol.is_empty());
     }
 }
+
+fn candle_at(rfc3339: &str, close: f64) -> kiteconnect_rs::HistoricalData {
+    let date = chrono::DateTime::parse_from_rfc3339(rfc3339)
+        .unwrap()
+        .with_timezone(&chrono::Utc);
+    kiteconnect_rs::HistoricalData {
+        date: kiteconnect_rs::models::time::Time::new(date),
+        open: close,
+        high: close,
+        low: close,
+        close,
+        volume: 100,
+        oi: 0,
+    }
+}
+
+#[test]
+fn test_ohlc_series_merge_and_gaps() {
+    use kiteconnect_rs::OhlcSeries;
+
+    let mut series = OhlcSeries::new();
+    // Live candles arrive first, then an overlapping backfill
+    series.append(1, "minute", candle_at("2024-01-02T09:18:00+05:30", 13.0));
+    series.append(1, "minute", candle_at("2024-01-02T09:18:00+05:30", 13.5));
+    series.extend(
+        1,
+        "minute",
+        vec![
+            candle_at("2024-01-01T15:29:00+05:30", 9.0),
+            candle_at("2024-01-02T09:15:00+05:30", 10.0),
+            candle_at("2024-01-02T09:16:00+05:30", 11.0),
+            candle_at("2024-01-02T09:18:00+05:30", 13.0),
+        ],
+    );
+
+    let candles = series.candles(1, "minute");
+    let closes: Vec<f64> = candles.iter().map(|c| c.candle.close).collect();
+    assert_eq!(closes, vec![9.0, 10.0, 11.0, 13.0]);
+    let gaps: Vec<u32> = candles.iter().map(|c| c.missing_before).collect();
+    // Overnight break is not a gap, the missing 09:17 candle is
+    assert_eq!(gaps, vec![0, 0, 0, 1]);
+    assert_eq!(series.len(1, "minute"), 4);
+    assert_eq!(series.len(1, "day"), 0);
+
+    let mut daily = OhlcSeries::new().with_max_len(3);
+    daily.extend(
+        2,
+        "day",
+        vec![
+            // Friday, Monday, Wednesday, Thursday
+            candle_at("2024-01-05T00:00:00+05:30", 1.0),
+            candle_at("2024-01-08T00:00:00+05:30", 2.0),
+            candle_at("2024-01-10T00:00:00+05:30", 3.0),
+            candle_at("2024-01-11T00:00:00+05:30", 4.0),
+        ],
+    );
+    let gaps: Vec<u32> = daily
+        .candles(2, "day")
+        .iter()
+        .map(|c| c.missing_before)
+        .collect();
+    assert_eq!(gaps, vec![0, 1, 0]);
+    assert_eq!(daily.latest(2, "day").unwrap().close, 4.0);
+}
+
+#[test]
+fn test_ohlcv_adapters() {
+    use kiteconnect_rs::{OhlcSeries, Ohlcv, OhlcvExt};
+
+    let mut candles = vec![
+        candle_at("2024-01-02T09:15:00+05:30", 10.0),
+        candle_at("2024-01-02T09:16:00+05:30", 11.0),
+    ];
+    candles[1].high = 12.5;
+    candles[1].low = 9.5;
+    assert_eq!(
+        candles.iter().closes().collect::<Vec<_>>(),
+        vec![10.0, 11.0]
+    );
+    assert_eq!(candles[1].typical_price(), 11.0);
+    let tuples: Vec<_> = candles.iter().ohlcv_tuples().collect();
+    assert_eq!(tuples[1], (11.0, 12.5, 9.5, 11.0, 100.0));
+    let tuple: (f64, f64, f64, f64, f64) = (&candles[0]).into();
+    assert_eq!(tuple, (10.0, 10.0, 10.0, 10.0, 100.0));
+
+    // Series candles and raw API rows adapt the same way
+    let mut series = OhlcSeries::new();
+    series.extend(1, "minute", candles.clone());
+    assert_eq!(
+        series
+            .candles(1, "minute")
+            .iter()
+            .closes()
+            .collect::<Vec<_>>(),
+        vec![10.0, 11.0]
+    );
+    let row: kiteconnect_rs::markets::CandleRow =
+        serde_json::from_str(r#"["2024-01-02T09:15:00+0530", 1, 2, 0.5, 1.5, 300]"#).unwrap();
+    assert_eq!(row.to_tuple(), (1.0, 2.0, 0.5, 1.5, 300.0));
+}
+
+#[test]
+fn test_compact_serialization() {
+    use kiteconnect_rs::compact::{CompactCandle, CompactTick};
+    use kiteconnect_rs::{OHLC, Tick};
+    use serde_json::json;
+
+    let candle = candle_at("2024-01-02T09:15:00+05:30", 10.5);
+    assert_eq!(
+        serde_json::to_value(CompactCandle(&candle)).unwrap(),
+        json!([1704167100, 10.5, 10.5, 10.5, 10.5, 100])
+    );
+
+    let ltp_tick = Tick {
+        mode: "ltp".to_string(),
+        instrument_token: 408065,
+        last_price: 1500.25,
+        ..Default::default()
+    };
+    assert_eq!(
+        serde_json::to_value(CompactTick(&ltp_tick)).unwrap(),
+        json!({"i": 408065, "p": 1500.25})
+    );
+
+    let quote_tick = Tick {
+        volume_traded: 1200,
+        net_change: -0.5,
+        ohlc: OHLC {
+            instrument_token: None,
+            open: 1490.0,
+            high: 1510.0,
+            low: 1480.0,
+            close: 1501.0,
+        },
+        ..ltp_tick
+    };
+    assert_eq!(
+        serde_json::to_value(CompactTick(&quote_tick)).unwrap(),
+        json!({
+            "i": 408065,
+            "p": 1500.25,
+            "v": 1200,
+            "ch": -0.5,
+            "ohlc": [1490.0, 1510.0, 1480.0, 1501.0]
+        })
+    );
+
+    #[derive(serde::Serialize)]
+    struct ChartPayload {
+        #[serde(serialize_with = "kiteconnect_rs::compact::candles::serialize")]
+        candles: Vec<kiteconnect_rs::HistoricalData>,
+    }
+    let payload = ChartPayload {
+        candles: vec![candle.clone(), candle],
+    };
+    assert_eq!(
+        serde_json::to_value(&payload).unwrap()["candles"]
+            .as_array()
+            .unwrap()
+            .len(),
+        2
+    );
+}
+
+#[test]
+fn test_market_clock_phases() {
+    use chrono::{NaiveDate, TimeZone};
+    use chrono_tz::Asia::Kolkata;
+    use kiteconnect_rs::{MarketClock, MarketPhase};
+
+    let holiday = NaiveDate::from_ymd_opt(2024, 6, 17).unwrap();
+    let clock = MarketClock::nse().with_holidays([holiday]);
+    // Monday 2024-06-03
+    let at = |d: u32, h: u32, m: u32| Kolkata.with_ymd_and_hms(2024, 6, d, h, m, 0).unwrap();
+
+    assert_eq!(clock.phase_at(at(3, 8, 59)), MarketPhase::Closed);
+    assert_eq!(clock.phase_at(at(3, 9, 0)), MarketPhase::PreOpen);
+    assert_eq!(clock.phase_at(at(3, 9, 15)), MarketPhase::Normal);
+    assert_eq!(clock.phase_at(at(3, 15, 35)), MarketPhase::Closing);
+    assert_eq!(clock.phase_at(at(3, 15, 45)), MarketPhase::PostClose);
+    assert_eq!(clock.phase_at(at(3, 16, 0)), MarketPhase::Closed);
+    assert_eq!(clock.phase_at(at(8, 11, 0)), MarketPhase::Closed);
+    assert_eq!(clock.phase_at(at(17, 11, 0)), MarketPhase::Closed);
+
+    assert_eq!(clock.time_to_open_at(at(3, 10, 0)).as_secs(), 0);
+    assert_eq!(
+        clock.time_to_close_at(at(3, 15, 0)).unwrap().as_secs(),
+        30 * 60
+    );
+    assert!(clock.time_to_close_at(at(3, 15, 35)).is_none());
+
+    // Friday after close opens on Monday, skipping the weekend
+    assert_eq!(clock.next_open_at(at(7, 16, 0)), at(10, 9, 15));
+    // Friday before a Monday holiday opens on Tuesday
+    assert_eq!(clock.next_open_at(at(14, 15, 30)), at(18, 9, 15));
+    assert_eq!(
+        clock.time_to_open_at(at(3, 15, 30)).as_secs(),
+        (17 * 60 + 45) * 60
+    );
+}
+
+#[test]
+fn test_historical_rows_deserialize_as_tuples() {
+    use kiteconnect_rs::{HistoricalData, HistoricalDataResponse};
+
+    let response: HistoricalDataResponse = serde_json::from_str(
+        r#"{"candles": [
+            ["2024-01-15T09:15:00+0530", 1500.0, 1510.5, 1495.0, 1505.25, 12000],
+            ["2024-01-15T09:16:00+0530", 1505.25, 1506.0, 1501.0, 1502.0, 8000, 45000]
+        ]}"#,
+    )
+    .unwrap();
+    let candles: Vec<HistoricalData> = response.into();
+
+    assert_eq!(candles.len(), 2);
+    assert_eq!(candles[0].high, 1510.5);
+    assert_eq!(candles[0].volume, 12000);
+    assert_eq!(candles[0].oi, 0);
+    assert_eq!(candles[1].oi, 45000);
+    assert_eq!(
+        candles[1].date.as_datetime().unwrap().to_rfc3339(),
+        "2024-01-15T03:46:00+00:00"
+    );
+
+    let err = serde_json::from_str::<HistoricalDataResponse>(
+        r#"{"candles": [["2024-01-15T09:15:00+0530", 1500.0, 1510.5]]}"#,
+    )
+    .unwrap_err();
+    assert!(err.to_string().contains("invalid length 3"), "{}", err);
+}
+
+const INSTRUMENTS_CSV: &str = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+256265,1001,NIFTY 50,NIFTY 50,0,,0,0,0,EQ,INDICES,NSE
+12345602,48225,NIFTY24JUN22000CE,NIFTY,0,2024-06-27,22000,0.05,50,CE,NFO-OPT,NFO
+12345858,48226,NIFTY24JUN22000PE,NIFTY,0,2024-06-27,22000,0.05,50,PE,NFO-OPT,NFO
+12346114,48227,BANKNIFTY24JUN48000CE,BANKNIFTY,0,2024-06-26,48000,0.05,15,CE,NFO-OPT,NFO
+12346370,48228,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO
+";
+
+#[tokio::test]
+async fn test_get_instruments_filtered() {
+    use kiteconnect_rs::InstrumentFilter;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/instruments/NFO")
+        .body(INSTRUMENTS_CSV)
+        .header("content-type", "text/csv")
+        .expect(1)
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/instruments")
+        .body(INSTRUMENTS_CSV)
+        .header("content-type", "text/csv")
+        .expect(1)
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    // A single exchange downloads only that exchange's dump
+    let filter = InstrumentFilter::new()
+        .exchange("NFO")
+        .segment("NFO-OPT")
+        .name("NIFTY");
+    let options = kite.get_instruments_filtered(&filter).await.unwrap();
+    let symbols: Vec<&str> = options.iter().map(|i| i.tradingsymbol.as_str()).collect();
+    assert_eq!(symbols, vec!["NIFTY24JUN22000CE", "NIFTY24JUN22000PE"]);
+    assert!(options.iter().all(|i| filter.matches(i)));
+
+    let equities = kite
+        .get_instruments_filtered(&InstrumentFilter::new().instrument_type("EQ").segment("NSE"))
+        .await
+        .unwrap();
+    assert_eq!(equities.len(), 1);
+    assert_eq!(equities[0].instrument_token, 408065);
+}
+
+#[tokio::test]
+async fn test_max_response_size() {
+    use kiteconnect_rs::KiteConnectErrorKind;
+    use serde_json::json;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({"net": [], "day": [], "padding": "x".repeat(4096)}))
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    kite.get_positions().await.unwrap();
+
+    kite.set_max_response_size(Some(1024));
+    let err = kite.get_positions().await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::ResponseTooLarge { limit: 1024 }
+    ));
+
+    kite.set_max_response_size(Some(64 * 1024));
+    kite.get_positions().await.unwrap();
+}
+
+#[tokio::test]
+async fn test_stream_instruments() {
+    use kiteconnect_rs::markets::InstrumentFilter;
+
+    let mut csv = String::from(
+        "instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange\r\n",
+    );
+    for i in 0..2000 {
+        csv.push_str(&format!(
+            "{},{},SYM{},NAME,0,,0,0.05,1,EQ,NSE,NSE\r\n",
+            1000 + i,
+            i,
+            i
+        ));
+    }
+    csv.push_str("9999,9,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO");
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/instruments")
+        .status(200)
+        .body(csv)
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    // The limit does not apply to streamed dumps
+    kite.set_max_response_size(Some(1024));
+
+    let mut tokens = Vec::new();
+    let count = kite
+        .stream_instruments(&InstrumentFilter::new(), |i| {
+            tokens.push(i.instrument_token)
+        })
+        .await
+        .unwrap();
+    assert_eq!(count, 2001);
+    assert_eq!(tokens.first(), Some(&1000));
+    assert_eq!(tokens.last(), Some(&9999));
+
+    let filter = InstrumentFilter::new().segment("NFO-FUT");
+    let mut futures = Vec::new();
+    kite.stream_instruments(&filter, |i| futures.push(i))
+        .await
+        .unwrap();
+    assert_eq!(futures.len(), 1);
+    assert_eq!(futures[0].tradingsymbol, "NIFTY24JUNFUT");
+    assert_eq!(futures[0].lot_size, 50.0);
+}
+
+#[tokio::test]
+async fn test_get_historical_data_full_skips_non_trading_days() {
+    use chrono::NaiveDate;
+    use kiteconnect_rs::MarketClock;
+    use kiteconnect_rs::markets::historical_windows;
+    use serde_json::json;
+
+    let date = |m, d| NaiveDate::from_ymd_opt(2024, m, d).unwrap();
+    // Good Friday
+    let clock = MarketClock::nse().with_holidays([date(3, 29)]);
+
+    // Minute candles allow 60 days per request; the weekend and holiday at the end of
+    // March are trimmed off the second window
+    assert_eq!(
+        historical_windows("minute", date(1, 1), date(3, 31), &clock),
+        [(date(1, 1), date(2, 29)), (date(3, 1), date(3, 28))]
+    );
+    assert!(historical_windows("day", date(6, 8), date(6, 9), &clock).is_empty());
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/instruments/historical/408065/day")
+        .data(json!({"candles": [
+            ["2024-06-07T00:00:00+0530", 1500.0, 1510.0, 1490.0, 1505.0, 1000],
+            ["2024-06-11T00:00:00+0530", 1505.0, 1520.0, 1500.0, 1515.0, 1200],
+        ]}))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    // Friday to Wednesday: nothing is fetched for the weekend, Wednesday has no candle
+    let full = kite
+        .get_historical_data_full(408065, "day", date(6, 7), date(6, 12), false, false, &clock)
+        .await
+        .unwrap();
+    assert_eq!(full.candles.len(), 2);
+    assert_eq!(full.gaps, [date(6, 10), date(6, 12)]);
+    assert!(full.is_gap(date(6, 12)));
+    assert!(!full.is_gap(date(6, 8)));
+
+    let request = mock_server
+        .received_one("GET", "/instruments/historical/408065/day")
+        .await;
+    assert_eq!(request.query["from"], "2024-06-07 00:00:00");
+    assert_eq!(request.query["to"], "2024-06-12 23:59:59");
+
+    // A weekend alone needs no request at all
+    let full = kite
+        .get_historical_data_full(408065, "day", date(6, 8), date(6, 9), false, false, &clock)
+        .await
+        .unwrap();
+    assert!(full.candles.is_empty() && full.gaps.is_empty());
+    assert_eq!(
+        mock_server
+            .received("GET", "/instruments/historical/408065/day")
+            .await
+            .len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_isin_map_and_quote_fallback() {
+    use kiteconnect_rs::InstrumentStore;
+    use kiteconnect_rs::instruments::{ExchangePolicy, IsinMap};
+    use kiteconnect_rs::markets::{InstrumentFilter, parse_instruments_filtered};
+    use serde_json::json;
+
+    let csv = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+128053508,500209,INFY,INFOSYS,0,,0,0.05,1,EQ,BSE,BSE
+2953217,11536,TCS,TATA CONSULTANCY,0,,0,0.05,1,EQ,NSE,NSE
+136472580,532540,TCS-BSE,TATA CONSULTANCY,0,,0,0.05,1,EQ,BSE,BSE
+";
+    let store: InstrumentStore =
+        parse_instruments_filtered(csv.as_bytes(), &InstrumentFilter::new())
+            .unwrap()
+            .into();
+    let map = IsinMap::build(
+        &store,
+        [
+            ("NSE", "INFY", "INE009A01021"),
+            ("BSE", "INFY", "INE009A01021"),
+            ("NSE", "TCS", "INE467B01029"),
+            ("BSE", "TCS-BSE", "INE467B01029"),
+            ("NSE", "UNLISTED", "INE000000000"),
+        ],
+    );
+    assert_eq!(map.len(), 2);
+    let infy = map.equivalent_symbols("INE009A01021");
+    assert_eq!(infy.len(), 2);
+    assert_eq!(infy[1].instrument_token, 128053508);
+    assert_eq!(map.isin_of("BSE", "TCS-BSE"), Some("INE467B01029"));
+    assert!(map.equivalent_symbols("INE000000000").is_empty());
+    // Listings are only matched by ISIN, never by a shared symbol
+    let nse_only = IsinMap::build(&store, [("NSE", "INFY", "INE009A01021")]);
+    assert_eq!(nse_only.equivalent_symbols("INE009A01021").len(), 1);
+    assert_eq!(nse_only.isin_of("BSE", "INFY"), None);
+
+    let nse_first = ExchangePolicy::nse_first();
+    let preferred = |policy: &ExchangePolicy| {
+        map.preferred_exchange("INE467B01029", policy)
+            .unwrap()
+            .clone()
+    };
+    assert_eq!(preferred(&nse_first).tradingsymbol, "TCS");
+    assert_eq!(
+        preferred(&ExchangePolicy::bse_first()).tradingsymbol,
+        "TCS-BSE"
+    );
+    let halted = nse_first.clone().unavailable("NSE", "TCS");
+    assert_eq!(preferred(&halted).exchange, "BSE");
+    assert!(
+        map.preferred_exchange("INE009A01021", &ExchangePolicy::new(["MCX"]))
+            .is_none()
+    );
+
+    let depth = json!(vec![json!({"price": 0.0, "quantity": 0, "orders": 0}); 5]);
+    let quote = |token: u32, last_price: f64| {
+        json!({
+            "instrument_token": token, "last_price": last_price, "last_quantity": 1,
+            "average_price": last_price, "volume": 100, "buy_quantity": 0,
+            "sell_quantity": 0, "net_change": 0.0, "oi": 0.0, "oi_day_high": 0.0,
+            "oi_day_low": 0.0, "lower_circuit_limit": 1350.0, "upper_circuit_limit": 1650.0,
+            "ohlc": {"open": 1500.0, "high": 1650.0, "low": 1500.0, "close": 1500.0},
+            "depth": {"buy": depth, "sell": depth}
+        })
+    };
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote")
+        .data(json!({
+            "NSE:INFY": quote(408065, 1650.0),
+            "BSE:INFY": quote(128053508, 1600.0)
+        }))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    // NSE is stuck at the upper circuit, so the BSE quote is used
+    let (listing, data) = kite
+        .get_quote_by_isin(&map, "INE009A01021", &nse_first)
+        .await
+        .unwrap();
+    assert_eq!(listing.exchange, "BSE");
+    assert_eq!(data.last_price, 1600.0);
+
+    let bse_halted = nse_first.unavailable("BSE", "INFY");
+    assert!(
+        kite.get_quote_by_isin(&map, "INE009A01021", &bse_halted)
+            .await
+            .is_err()
+    );
+    assert!(
+        kite.get_quote_by_isin(&map, "INE000000000", &ExchangePolicy::default())
+            .await
+            .is_err()
+    );
+}
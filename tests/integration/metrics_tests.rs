@@ -0,0 +1,77 @@
+use kiteconnect_rs::metrics::{endpoint_label, gather, serve};
+use serde_json::json;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+
+use super::mock_server::KiteMockServer;
+
+#[test]
+fn test_endpoint_label() {
+    assert_eq!(endpoint_label("/orders/151220000000000"), "/orders/:id");
+    assert_eq!(
+        endpoint_label("/instruments/historical/408065/minute?from=x"),
+        "/instruments/historical/:id/minute"
+    );
+    assert_eq!(
+        endpoint_label("/alerts/550e8400-e29b-41d4-a716-446655440000"),
+        "/alerts/:id"
+    );
+    assert_eq!(
+        endpoint_label("/user/margins/equity"),
+        "/user/margins/equity"
+    );
+}
+
+#[tokio::test]
+async fn test_requests_are_recorded() {
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("GET", "/orders/900001/trades")
+        .data(json!([]))
+        .mount()
+        .await;
+    server
+        .endpoint("GET", "/quote/ltp")
+        .status(429)
+        .json(json!({
+            "status": "error",
+            "message": "Too many requests",
+            "error_type": "NetworkException"
+        }))
+        .mount()
+        .await;
+    let kite = server.client();
+
+    kite.get_order_trades("900001").await.unwrap();
+    assert!(kite.get_ltp(&["NSE:INFY"]).await.is_err());
+
+    let metrics = gather();
+    assert!(
+        metrics.contains(
+            r#"kite_http_requests_total{endpoint="/orders/:id/trades",method="GET",status="200"}"#
+        ),
+        "{}",
+        metrics
+    );
+    assert!(
+        metrics.contains(
+            r#"kite_http_requests_total{endpoint="/quote/ltp",method="GET",status="429"}"#
+        )
+    );
+    assert!(metrics.contains(r#"kite_http_throttled_total{category="quote"}"#));
+    assert!(metrics.contains(r#"kite_http_request_duration_seconds_count{category="other"}"#));
+
+    // The scrape endpoint serves the same text
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let scrape = tokio::spawn(serve(listener));
+    let mut stream = tokio::net::TcpStream::connect(addr).await.unwrap();
+    stream
+        .write_all(b"GET /metrics HTTP/1.1\r\nHost: localhost\r\n\r\n")
+        .await
+        .unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).await.unwrap();
+    assert!(response.starts_with("HTTP/1.1 200 OK"));
+    assert!(response.contains("kite_http_throttled_total"));
+    scrape.abort();
+}
@@ -1,11 +1,10 @@
-use serde_json::Value;
 use std::collections::HashMap;
-use wiremock::{
-    Mock, MockServer, ResponseTemplate,
-    matchers::{method, path},
-};
+use std::ops::Deref;
 
 use kiteconnect_rs::constants::Endpoints;
+use kiteconnect_rs::test_utils;
+
+const MOCKS_DIR: &str = "tests/mocks";
 
 pub struct ApiEndpointMappings;
 
@@ -134,81 +133,43 @@ impl ApiEndpointMappings {
     }
 }
 
+/// The crate's mock server preloaded with the fixtures from `tests/mocks`.
 pub struct KiteMockServer {
-    pub server: MockServer,
+    inner: test_utils::KiteMockServer,
     pub base_url: String,
 }
 
 impl KiteMockServer {
     pub async fn new() -> Self {
-        let server = MockServer::start().await;
-        let base_url = server.uri();
+        let inner = test_utils::KiteMockServer::new().await;
+        let base_url = inner.base_url.clone();
 
-        Self { server, base_url }
+        Self { inner, base_url }
     }
 
     pub async fn setup_all_mocks(&self) {
-        let endpoints = ApiEndpointMappings::get_endpoints();
-
-        for ((http_method, endpoint_path), mock_file) in endpoints {
-            let mock_data = Self::load_mock_data(mock_file);
-
-            Mock::given(method(http_method))
-                .and(path(endpoint_path))
-                .respond_with(ResponseTemplate::new(200).set_body_json(mock_data))
-                .mount(&self.server)
-                .await;
-        }
-
-        // Setup CSV endpoints separately
-        self.setup_csv_mocks().await;
-    }
-
-    pub async fn setup_csv_mocks(&self) {
-        // Instruments endpoints return CSV, not JSON
-        Mock::given(method("GET"))
-            .and(path(Endpoints::GET_INSTRUMENTS))
-            .respond_with(
-                ResponseTemplate::new(200)
-                    .set_body_string(Self::load_csv_data("instruments_all.csv"))
-                    .insert_header("content-type", "text/csv"),
-            )
-            .mount(&self.server)
+        self.inner
+            .mount_fixtures(MOCKS_DIR, ApiEndpointMappings::get_endpoints())
             .await;
 
-        Mock::given(method("GET"))
-            .and(path("/instruments/nse"))
-            .respond_with(
-                ResponseTemplate::new(200)
-                    .set_body_string(Self::load_csv_data("instruments_nse.csv"))
-                    .insert_header("content-type", "text/csv"),
-            )
-            .mount(&self.server)
-            .await;
-
-        Mock::given(method("GET"))
-            .and(path(Endpoints::GET_MF_INSTRUMENTS))
-            .respond_with(
-                ResponseTemplate::new(200)
-                    .set_body_string(Self::load_csv_data("mf_instruments.csv"))
-                    .insert_header("content-type", "text/csv"),
+        // Instruments endpoints return CSV, not JSON
+        self.inner
+            .mount_fixtures(
+                MOCKS_DIR,
+                [
+                    (("GET", Endpoints::GET_INSTRUMENTS), "instruments_all.csv"),
+                    (("GET", "/instruments/nse"), "instruments_nse.csv"),
+                    (("GET", Endpoints::GET_MF_INSTRUMENTS), "mf_instruments.csv"),
+                ],
             )
-            .mount(&self.server)
             .await;
     }
+}
 
-    pub fn load_csv_data(filename: &str) -> String {
-        let mock_path = format!("tests/mocks/{}", filename);
-        std::fs::read_to_string(&mock_path)
-            .unwrap_or_else(|_| panic!("Failed to read mock CSV file: {}", mock_path))
-    }
-
-    pub fn load_mock_data(filename: &str) -> Value {
-        let mock_path = format!("tests/mocks/{}", filename);
-        let mock_data = std::fs::read_to_string(&mock_path)
-            .unwrap_or_else(|_| panic!("Failed to read mock file: {}", mock_path));
+impl Deref for KiteMockServer {
+    type Target = test_utils::KiteMockServer;
 
-        serde_json::from_str(&mock_data)
-            .unwrap_or_else(|_| panic!("Failed to parse JSON from: {}", mock_path))
+    fn deref(&self) -> &Self::Target {
+        &self.inner
     }
 }
@@ -0,0 +1,238 @@
+use kiteconnect_rs::{KiteConnect, KiteConnectErrorKind, orders::OrderParams};
+use serde_json::json;
+use std::time::Duration;
+
+use super::mock_server::KiteMockServer;
+
+fn market_order() -> OrderParams {
+    OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("MARKET".to_string()),
+        quantity: Some(5),
+        product: Some("CNC".to_string()),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_override_data_and_inspect_request() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "777"}))
+        .expect(1)
+        .mount()
+        .await;
+
+    let kite = mock_server.client();
+    let response = kite.place_order("regular", market_order()).await.unwrap();
+    assert_eq!(response.order_id, "777");
+
+    let request = mock_server.received_one("POST", "/orders/regular").await;
+    let form = request.form();
+    assert_eq!(form.get("tradingsymbol").map(String::as_str), Some("INFY"));
+    assert_eq!(form.get("quantity").map(String::as_str), Some("5"));
+    assert_eq!(
+        request.headers.get("authorization").map(String::as_str),
+        Some("token test_api_key:test_access_token")
+    );
+}
+
+#[tokio::test]
+async fn test_override_error_responses() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .rate_limited()
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .token_exception()
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/holdings")
+        .malformed_json()
+        .mount()
+        .await;
+
+    let kite = mock_server.client();
+
+    let err = kite.get_orders().await.unwrap_err();
+    match err.kind {
+        KiteConnectErrorKind::ApiError(e) => assert_eq!(e.error_type, "NetworkException"),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+
+    let err = kite.get_positions().await.unwrap_err();
+    match err.kind {
+        KiteConnectErrorKind::ApiError(e) => assert_eq!(e.error_type, "TokenException"),
+        other => panic!("expected an API error, got {:?}", other),
+    }
+
+    let err = kite.get_holdings().await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::SerializationError(_)
+    ));
+}
+
+#[tokio::test]
+async fn test_override_takes_precedence_over_fixtures() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(
+        dir.path().join("orders.json"),
+        r#"{"status": "success", "data": []}"#,
+    )
+    .unwrap();
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .token_exception()
+        .mount()
+        .await;
+    mock_server
+        .mount_fixtures(dir.path(), [(("GET", "/orders"), "orders.json")])
+        .await;
+
+    let err = mock_server.client().get_orders().await.unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::ApiError(_)));
+}
+
+#[tokio::test]
+async fn test_override_delay_hits_client_timeout() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .delay(Duration::from_millis(500))
+        .mount()
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .access_token("test_access_token")
+        .timeout(Duration::from_millis(100))
+        .build()
+        .unwrap();
+    let err = kite.get_orders().await.unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::HttpError(_)));
+}
+
+#[tokio::test]
+async fn test_usage_stats_by_category() {
+    use kiteconnect_rs::ApiCategory;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote/ltp")
+        .data(json!({}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "1"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+
+    let kite = mock_server.client();
+    kite.get_ltp(&["NSE:INFY"]).await.unwrap();
+    kite.get_ltp(&["NSE:TCS"]).await.unwrap();
+    kite.place_order("regular", market_order()).await.unwrap();
+    kite.get_orders().await.unwrap();
+
+    let stats = kite.usage_stats();
+    let quote = stats.category(ApiCategory::Quote);
+    assert_eq!(
+        (quote.last_second, quote.last_minute, quote.today),
+        (2, 2, 2)
+    );
+    // Two quote calls in the same second is twice the 1/s limit
+    assert_eq!(quote.utilization(), 2.0);
+
+    let orders = stats.category(ApiCategory::Orders);
+    assert_eq!(orders.today, 1);
+    assert_eq!(orders.limit_per_day, Some(3000));
+    // Listing orders is not an order placement
+    assert_eq!(stats.category(ApiCategory::Other).today, 1);
+    assert_eq!(stats.category(ApiCategory::Historical).today, 0);
+
+    let hot: Vec<_> = stats.near_limit(0.8).iter().map(|u| u.category).collect();
+    assert_eq!(hot, vec![ApiCategory::Quote]);
+}
+
+#[tokio::test]
+async fn test_scoped_clones() {
+    use kiteconnect_rs::{ApiCategory, UsagePool};
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "1"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .delay(Duration::from_millis(300))
+        .mount()
+        .await;
+
+    let mut kite = mock_server.client();
+    let momentum = kite
+        .clone()
+        .with_tag_prefix("mom")
+        .with_usage_pool(UsagePool::new());
+    let hedge = kite
+        .clone()
+        .with_tag_prefix("hedge-")
+        .with_timeout(Duration::from_millis(100));
+
+    momentum
+        .place_order("regular", market_order())
+        .await
+        .unwrap();
+    let tagged = OrderParams {
+        tag: Some("nifty-weekly-straddle".to_string()),
+        ..market_order()
+    };
+    hedge.place_order("regular", tagged).await.unwrap();
+
+    // Auth state is shared: a token set on the original is used by its clones
+    kite.set_access_token("renewed_token");
+    kite.place_order("regular", market_order()).await.unwrap();
+
+    let requests = mock_server.received("POST", "/orders/regular").await;
+    let tags: Vec<Option<String>> = requests.iter().map(|r| r.form().remove("tag")).collect();
+    assert_eq!(
+        tags,
+        vec![
+            Some("mom".to_string()),
+            Some("hedge-nifty-weekly-s".to_string()),
+            None
+        ]
+    );
+    assert_eq!(
+        requests[2].headers.get("authorization").map(String::as_str),
+        Some("token test_api_key:renewed_token")
+    );
+
+    // Only the hedge clone has the short timeout
+    assert!(hedge.get_orders().await.is_err());
+    assert!(momentum.get_orders().await.is_ok());
+
+    // The momentum clone counts in its own pool, the others share the original one
+    let own = momentum.usage_stats();
+    assert_eq!(own.category(ApiCategory::Orders).today, 1);
+    assert_eq!(own.category(ApiCategory::Other).today, 1);
+    assert_eq!(kite.usage_stats().category(ApiCategory::Orders).today, 2);
+}
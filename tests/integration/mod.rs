@@ -1,9 +1,25 @@
 // Integration test modules
 pub mod alerts_tests;
+pub mod broadcast_tests;
+pub mod executor_tests;
+pub mod fault_tests;
+pub mod gateway_tests;
+pub mod gtt_tests;
+pub mod halts_tests;
+pub mod health_tests;
+pub mod journal_tests;
+pub mod live_runner_tests;
 pub mod margins_tests;
+pub mod metrics_tests;
 pub mod markets_tests;
 pub mod mf_tests;
 pub mod mock_server;
+pub mod mock_server_tests;
 pub mod order_tests;
+pub mod pagination_tests;
 pub mod portfolio_tests;
+pub mod strategy_tests;
 pub mod user_auth_tests;
+pub mod vcr_tests;
+pub mod version_tests;
+pub mod watchlist_tests;
@@ -1,5 +1,23 @@
-use kiteconnect_rs::{KiteConnect, orders::OrderParams};
+use chrono::{NaiveTime, TimeZone};
+use chrono_tz::Asia::Kolkata;
+use kiteconnect_rs::{
+    AuditAction, AuditLog, AuditRecord, InstrumentStore, KiteConnect, KiteConnectErrorKind,
+    KiteError, RiskLimits, RiskViolation, TagRegistry,
+    audit::JsonlAuditLog,
+    clock::MarketClock,
+    freeze::{FreezeQuantities, split_quantity},
+    instruments::OrderRounding,
+    markets::{InstrumentFilter, parse_instruments_filtered},
+    orders::OrderParams,
+    validation::{OrderValidator, Violation, ViolationCode},
+};
+use serde_json::json;
+use std::sync::Arc;
 use std::time::Duration;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path},
+};
 
 use super::mock_server::KiteMockServer;
 
@@ -419,3 +437,1491 @@ async fn test_order_error_handling() {
     let place_result = kite.place_order("regular", empty_params).await;
     assert!(place_result.is_err(), "Expected error for invalid URL");
 }
+
+#[test]
+fn test_order_params_merge() {
+    let existing = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(10),
+        price: Some(1500.0),
+        ..Default::default()
+    };
+
+    let merged = existing
+        .clone()
+        .merge(OrderParams {
+            price: Some(1495.5),
+            ..Default::default()
+        })
+        .expect("Price change should be allowed");
+    assert_eq!(merged.price, Some(1495.5));
+    assert_eq!(merged.quantity, Some(10));
+    assert_eq!(merged.tradingsymbol.as_deref(), Some("INFY"));
+
+    let rejected = existing.merge(OrderParams {
+        transaction_type: Some("SELL".to_string()),
+        ..Default::default()
+    });
+    assert!(
+        rejected.is_err(),
+        "Transaction type change should be rejected"
+    );
+}
+
+fn limit_order(symbol: &str, quantity: i32, price: f64) -> OrderParams {
+    OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some(symbol.to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(quantity),
+        price: Some(price),
+        ..Default::default()
+    }
+}
+
+#[test]
+fn test_risk_limits_check() {
+    let noon = NaiveTime::from_hms_opt(12, 0, 0).unwrap();
+    let limits = RiskLimits::new()
+        .max_order_value(100_000.0)
+        .max_quantity(50)
+        .max_open_orders(3)
+        .deny_symbol("NSE:YESBANK")
+        .trading_hours(
+            NaiveTime::from_hms_opt(9, 15, 0).unwrap(),
+            NaiveTime::from_hms_opt(15, 30, 0).unwrap(),
+        );
+
+    let order = limit_order("INFY", 10, 1500.0);
+    assert!(limits.check(&order, Some(15_000.0), Some(0), noon).is_ok());
+
+    assert_eq!(
+        limits.check(&limit_order("INFY", 60, 1500.0), None, None, noon),
+        Err(RiskViolation::MaxQuantity {
+            quantity: 60,
+            limit: 50
+        })
+    );
+    assert!(matches!(
+        limits.check(&order, Some(150_000.0), None, noon),
+        Err(RiskViolation::MaxOrderValue { .. })
+    ));
+    assert!(matches!(
+        limits.check(&order, None, Some(3), noon),
+        Err(RiskViolation::MaxOpenOrders { open: 3, limit: 3 })
+    ));
+    assert_eq!(
+        limits.check(&limit_order("YESBANK", 1, 10.0), None, None, noon),
+        Err(RiskViolation::SymbolDenied("NSE:YESBANK".to_string()))
+    );
+    assert!(matches!(
+        limits.check(
+            &order,
+            None,
+            None,
+            NaiveTime::from_hms_opt(15, 30, 0).unwrap()
+        ),
+        Err(RiskViolation::OutsideTradingHours { .. })
+    ));
+
+    let allow_only = RiskLimits::new().allow_symbol("TCS");
+    assert!(
+        allow_only
+            .check(&limit_order("TCS", 1, 1.0), None, None, noon)
+            .is_ok()
+    );
+    assert!(matches!(
+        allow_only.check(&order, None, None, noon),
+        Err(RiskViolation::SymbolNotAllowed(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_place_order_blocked_by_risk_limits() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"order_id": "1"}})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .risk_limits(RiskLimits::new().max_order_value(50_000.0))
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let err = kite
+        .place_order("regular", limit_order("INFY", 100, 1500.0))
+        .await
+        .expect_err("order above the value limit should be blocked");
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::RiskViolation(RiskViolation::MaxOrderValue { .. })
+    ));
+
+    kite.place_order("regular", limit_order("INFY", 10, 1500.0))
+        .await
+        .expect("order within limits should be placed");
+}
+
+#[tokio::test]
+async fn test_audit_log_records_order_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"order_id": "42"}})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/orders/regular/42"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "status": "error",
+            "message": "Order is already complete",
+            "error_type": "InputException"
+        })))
+        .mount(&server)
+        .await;
+
+    let dir = tempfile::tempdir().unwrap();
+    let audit_path = dir.path().join("audit.jsonl");
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .risk_limits(RiskLimits::new().max_quantity(50))
+        .audit_log(JsonlAuditLog::open(&audit_path).unwrap())
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    kite.place_order("regular", limit_order("INFY", 10, 1500.0))
+        .await
+        .unwrap();
+    kite.place_order("regular", limit_order("INFY", 100, 1500.0))
+        .await
+        .expect_err("order above the quantity limit should be blocked");
+    kite.cancel_order("regular", "42", None)
+        .await
+        .expect_err("cancel should fail");
+
+    let records: Vec<AuditRecord> = std::fs::read_to_string(&audit_path)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    assert_eq!(records.len(), 3);
+
+    assert_eq!(records[0].action, AuditAction::Place);
+    assert_eq!(records[0].order_id.as_deref(), Some("42"));
+    assert_eq!(records[0].request.as_ref().unwrap()["quantity"], 10);
+    assert_eq!(records[0].response.as_ref().unwrap()["order_id"], "42");
+
+    assert_eq!(records[1].action, AuditAction::Place);
+    assert!(records[1].order_id.is_none());
+    assert!(records[1].error.is_some());
+
+    assert_eq!(records[2].action, AuditAction::Cancel);
+    assert_eq!(records[2].order_id.as_deref(), Some("42"));
+    assert!(records[2].error.is_some());
+
+    assert_ne!(records[0].request_id, records[1].request_id);
+}
+
+struct SlowAuditLog(std::sync::Mutex<Vec<AuditRecord>>);
+
+impl AuditLog for SlowAuditLog {
+    fn append(&self, record: &AuditRecord) -> Result<(), kiteconnect_rs::KiteConnectError> {
+        std::thread::sleep(Duration::from_millis(300));
+        self.0.lock().unwrap().push(record.clone());
+        Ok(())
+    }
+}
+
+#[tokio::test]
+async fn test_audit_log_writes_do_not_block_the_runtime() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "42"}))
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    let audit_log = Arc::new(SlowAuditLog(Default::default()));
+    kite.set_audit_log(Some(audit_log.clone()));
+
+    // The test runtime has a single thread, which a blocking write would hold
+    let started = std::time::Instant::now();
+    let (placed, timer) = tokio::join!(
+        kite.place_order("regular", limit_order("INFY", 10, 1500.0)),
+        async {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            started.elapsed()
+        }
+    );
+    assert_eq!(placed.unwrap().order_id, "42");
+    assert!(timer < Duration::from_millis(250), "timer took {:?}", timer);
+    assert_eq!(audit_log.0.lock().unwrap().len(), 1);
+}
+
+#[test]
+fn test_label_enums_round_trip() {
+    use kiteconnect_rs::Labels;
+    use kiteconnect_rs::labels::{Exchange, OrderType, Product, TransactionType, Variety};
+
+    for variety in Variety::ALL {
+        assert_eq!(variety.as_str().parse::<Variety>().unwrap(), *variety);
+    }
+    for exchange in Exchange::ALL {
+        assert_eq!(
+            serde_json::from_value::<Exchange>(serde_json::to_value(exchange).unwrap()).unwrap(),
+            *exchange
+        );
+    }
+    assert_eq!(OrderType::SlM.as_str(), Labels::ORDER_TYPE_SL_M);
+    assert_eq!(Product::ALL.len(), 6);
+    assert_eq!(TransactionType::Buy.opposite(), TransactionType::Sell);
+
+    let err = "GTT".parse::<OrderType>().unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+    assert!(serde_json::from_str::<Variety>("\"nope\"").is_err());
+
+    let params = OrderParams {
+        order_type: Some(OrderType::Limit.into()),
+        ..Default::default()
+    };
+    assert_eq!(params.order_type.as_deref(), Some("LIMIT"));
+}
+
+#[test]
+fn test_tag_registry_generates_and_resolves_tags() {
+    let registry = TagRegistry::new();
+
+    let mut params = limit_order("INFY", 1, 1500.0);
+    let short = registry.tag_order(&mut params, "mr", "l1").unwrap();
+    assert!(short.starts_with("mr:l1:"));
+    assert_eq!(params.tag.as_deref(), Some(short.as_str()));
+    assert!(registry.check(&short).unwrap().is_readable());
+
+    // Too long for the readable form, falls back to a hashed tag
+    let long = registry
+        .generate("mean_reversion_nifty", "hedge_put")
+        .unwrap();
+    assert_eq!(long.len(), 20);
+    assert!(long.starts_with("mean_r:"));
+    let entry = registry.lookup(&long).unwrap();
+    assert_eq!(entry.strategy, "mean_reversion_nifty");
+    assert_eq!(entry.leg, "hedge_put");
+    assert!(!entry.is_readable());
+    assert_ne!(
+        registry
+            .generate("mean_reversion_nifty", "hedge_put")
+            .unwrap(),
+        long
+    );
+
+    assert!(registry.generate("bad:name", "l1").is_err());
+    assert!(registry.check("unknown").is_err());
+    let err = registry.check("this tag is way too long").unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+
+    // Trades resolve through the order they belong to
+    let trade: kiteconnect_rs::Trade = serde_json::from_value(json!({
+        "average_price": 1500.0,
+        "quantity": 1.0,
+        "trade_id": "T1",
+        "product": "CNC",
+        "exchange_order_id": "E1",
+        "order_id": "151220000000000",
+        "transaction_type": "BUY",
+        "tradingsymbol": "INFY",
+        "exchange": "NSE",
+        "instrument_token": 408065
+    }))
+    .unwrap();
+    assert!(registry.lookup_trade(&trade).is_none());
+    registry.record_order("151220000000000", &long);
+    assert_eq!(registry.lookup_trade(&trade).unwrap().leg, "hedge_put");
+
+    // Entries can be restored into a fresh registry
+    let restored = TagRegistry::new();
+    for entry in registry.entries() {
+        restored.insert(entry).unwrap();
+    }
+    assert_eq!(restored.lookup(&short).unwrap().strategy, "mr");
+}
+
+#[tokio::test]
+async fn test_place_order_checks_tags_against_registry() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151"}))
+        .mount()
+        .await;
+    let registry = TagRegistry::new();
+    let mut kite = mock_server.client();
+    kite.set_tag_registry(Some(registry.clone()));
+
+    let mut params = limit_order("INFY", 1, 1500.0);
+    params.tag = Some("unknown".to_string());
+    let err = kite
+        .place_order("regular", params.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+    assert!(
+        mock_server
+            .received("POST", "/orders/regular")
+            .await
+            .is_empty()
+    );
+
+    registry.tag_order(&mut params, "mr", "l1").unwrap();
+    kite.place_order("regular", params).await.unwrap();
+    let trade: kiteconnect_rs::Trade = serde_json::from_value(json!({
+        "average_price": 1500.0,
+        "quantity": 1.0,
+        "trade_id": "T1",
+        "product": "CNC",
+        "exchange_order_id": "E1",
+        "order_id": "151",
+        "transaction_type": "BUY",
+        "tradingsymbol": "INFY",
+        "exchange": "NSE",
+        "instrument_token": 408065
+    }))
+    .unwrap();
+    assert_eq!(registry.lookup_trade(&trade).unwrap().leg, "l1");
+
+    // Untagged orders are not checked
+    kite.place_order("regular", limit_order("INFY", 1, 1500.0))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_kill_switch_halts_order_placement() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .respond_with(ResponseTemplate::new(403).set_body_json(json!({
+            "status": "error",
+            "message": "Your account is blocked for trading as the kill switch is enabled.",
+            "data": null,
+            "error_type": "PermissionException"
+        })))
+        .up_to_n_times(1)
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"order_id": "1"}})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let err = kite
+        .place_order("regular", limit_order("INFY", 1, 1500.0))
+        .await
+        .expect_err("kill switch rejection");
+    assert!(err.is_trading_blocked());
+    assert!(kite.trading_block().is_some());
+
+    // Halted for every clone without reaching the API
+    let err = kite
+        .clone()
+        .with_tag_prefix("s1")
+        .place_order("regular", limit_order("INFY", 1, 1500.0))
+        .await
+        .expect_err("order placement should be halted");
+    assert!(matches!(err.kind, KiteConnectErrorKind::TradingBlocked(_)));
+
+    kite.clear_trading_block();
+    kite.place_order("regular", limit_order("INFY", 1, 1500.0))
+        .await
+        .expect("order placement resumes once cleared");
+}
+
+#[test]
+fn test_trading_blocked_classification() {
+    let error = |error_type: &str, message: &str| KiteError {
+        status: "error".into(),
+        message: message.into(),
+        data: None,
+        error_type: error_type.into(),
+    };
+
+    assert!(error("UserException", "Kill Switch is enabled for NSE segment").is_trading_blocked());
+    assert!(!error("NetworkException", "kill switch service unavailable").is_trading_blocked());
+    assert!(!error("InputException", "Invalid price").is_trading_blocked());
+
+    let err = kiteconnect_rs::KiteConnectError::from(error("OrderException", "Insufficient funds"));
+    assert!(matches!(err.kind, KiteConnectErrorKind::ApiError(_)));
+}
+
+#[test]
+fn test_amo_variety_selection_and_validation() {
+    use chrono::TimeZone;
+    use chrono_tz::Asia::Kolkata;
+    use kiteconnect_rs::MarketClock;
+    use kiteconnect_rs::amo::{select_variety, validate_amo};
+    use kiteconnect_rs::labels::{Exchange, Variety};
+
+    let clock = MarketClock::nse();
+    // Monday 2024-06-03
+    let at = |d: u32, h: u32, m: u32| Kolkata.with_ymd_and_hms(2024, 6, d, h, m, 0).unwrap();
+
+    assert_eq!(
+        select_variety(&clock, Exchange::Nse, at(3, 11, 0)).unwrap(),
+        Variety::Regular
+    );
+    assert_eq!(
+        select_variety(&clock, Exchange::Nse, at(3, 18, 0)).unwrap(),
+        Variety::Amo
+    );
+    assert_eq!(
+        select_variety(&clock, Exchange::Nse, at(3, 7, 30)).unwrap(),
+        Variety::Amo
+    );
+    // Saturday
+    assert_eq!(
+        select_variety(&clock, Exchange::Nfo, at(8, 12, 0)).unwrap(),
+        Variety::Amo
+    );
+    // Between the close and the start of the AMO window
+    assert!(select_variety(&clock, Exchange::Nse, at(3, 15, 35)).is_err());
+    assert!(select_variety(&clock, Exchange::Mcx, at(3, 18, 0)).is_err());
+
+    let mut params = limit_order("INFY", 1, 1500.0);
+    params.validity = Some("DAY".into());
+    validate_amo(&params).unwrap();
+
+    params.validity = Some("IOC".into());
+    assert!(validate_amo(&params).is_err());
+
+    let mut fo = limit_order("NIFTY24JUNFUT", 50, 22000.0);
+    fo.exchange = Some("NFO".into());
+    fo.order_type = Some("SL-M".into());
+    let err = validate_amo(&fo).unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+}
+
+#[tokio::test]
+async fn test_mtf_orders_margins_and_conversion() {
+    use kiteconnect_rs::labels::{Exchange, Product};
+    use kiteconnect_rs::mtf::{mtf_conversion, validate_mtf};
+    use kiteconnect_rs::test_utils::MockDataGenerator;
+
+    let mut params = limit_order("INFY", 10, 1500.0);
+    params.product = None;
+    validate_mtf(&params).unwrap();
+    params.product = Some("CNC".into());
+    assert!(validate_mtf(&params).is_err());
+    let mut fo = limit_order("NIFTY24JUNFUT", 50, 22000.0);
+    fo.exchange = Some("NFO".into());
+    fo.product = None;
+    assert!(validate_mtf(&fo).is_err());
+
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "333"}))
+        .mount()
+        .await;
+    let charges = json!({
+        "transaction_tax": 0.0, "transaction_tax_type": "stt", "exchange_turnover_charge": 0.0,
+        "sebi_turnover_charge": 0.0, "brokerage": 0.0, "stamp_duty": 0.0,
+        "gst": {"igst": 0.0, "cgst": 0.0, "sgst": 0.0, "total": 0.0}, "total": 0.0
+    });
+    server
+        .endpoint("POST", "/margins/orders")
+        .data(json!([{
+            "type": "equity", "tradingsymbol": "INFY", "exchange": "NSE",
+            "var": 3750.0, "charges": charges, "total": 3750.0
+        }]))
+        .mount()
+        .await;
+    server
+        .endpoint("PUT", "/portfolio/positions")
+        .data(json!(true))
+        .mount()
+        .await;
+    let kite = server.client();
+
+    params.product = None;
+    let response = kite.place_mtf_order(params).await.unwrap();
+    assert_eq!(response.order_id, "333");
+    let placed = server.received_one("POST", "/orders/regular").await;
+    assert_eq!(placed.form()["product"], "MTF");
+
+    let required = kite
+        .mtf_margin_required(Exchange::Nse, "INFY", 10, Some(1500.0))
+        .await
+        .unwrap();
+    assert_eq!(required, 3750.0);
+    let margins = server.received_one("POST", "/margins/orders").await;
+    assert_eq!(margins.json()[0]["product"], "MTF");
+    assert_eq!(margins.json()[0]["order_type"], "LIMIT");
+
+    let mut position = MockDataGenerator::new(1).position(408065, "INFY");
+    position.product = "MTF".to_string();
+    let convert = mtf_conversion(&position, Product::Cnc).unwrap();
+    assert_eq!(convert.old_product, "MTF");
+    assert_eq!(convert.new_product, "CNC");
+    assert_eq!(convert.quantity, position.quantity);
+    assert!(mtf_conversion(&position, Product::Mis).is_err());
+    assert!(
+        kite.convert_mtf_position(&position, Product::Cnc)
+            .await
+            .unwrap()
+    );
+    let converted = server.received_one("PUT", "/portfolio/positions").await;
+    assert_eq!(converted.form()["new_product"], "CNC");
+
+    position.quantity = -position.quantity.abs();
+    assert!(mtf_conversion(&position, Product::Cnc).is_err());
+}
+
+#[test]
+fn test_iceberg_split_and_builder() {
+    use kiteconnect_rs::IcebergParams;
+    use kiteconnect_rs::labels::{Exchange, OrderType, Product, TransactionType};
+
+    let iceberg = IcebergParams::split(1000, 3).unwrap();
+    assert_eq!(iceberg.leg_quantity, 334);
+    assert_eq!(iceberg.leg_quantities(1000), vec![334, 334, 332]);
+
+    let lots = IcebergParams::split_lots(1750, 4, 50).unwrap();
+    assert_eq!(lots.leg_quantity, 450);
+    assert_eq!(lots.leg_quantities(1750), vec![450, 450, 450, 400]);
+
+    assert!(IcebergParams::split(1000, 1).is_err());
+    assert!(IcebergParams::split(1000, 11).is_err());
+    assert!(IcebergParams::split(3, 5).is_err());
+    assert!(IcebergParams::split_lots(1720, 4, 50).is_err());
+    // Fewer legs than requested would actually be sent
+    let uneven = IcebergParams {
+        legs: 4,
+        leg_quantity: 500,
+    };
+    assert!(uneven.validate(1000).is_err());
+
+    let params = OrderParams::builder()
+        .exchange(Exchange::Nse)
+        .tradingsymbol("INFY")
+        .transaction_type(TransactionType::Buy)
+        .order_type(OrderType::Limit)
+        .product(Product::Cnc)
+        .quantity(1000)
+        .price(1500.0)
+        .iceberg(iceberg)
+        .build()
+        .unwrap();
+    assert_eq!(params.exchange.as_deref(), Some("NSE"));
+    assert_eq!(params.iceberg_legs, Some(3));
+    assert_eq!(params.iceberg_quantity, Some(334));
+
+    let err = OrderParams::builder()
+        .exchange(Exchange::Mcx)
+        .quantity(1000)
+        .iceberg(iceberg)
+        .build()
+        .unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+    assert!(
+        OrderParams::builder()
+            .exchange("NSE")
+            .quantity(500)
+            .iceberg(iceberg)
+            .build()
+            .is_err()
+    );
+}
+
+fn order_json(order_id: &str, parent_order_id: Option<&str>, status: &str) -> serde_json::Value {
+    json!({
+        "placed_by": "AB1234",
+        "order_id": order_id,
+        "exchange_order_id": null,
+        "parent_order_id": parent_order_id,
+        "status": status,
+        "status_message": null,
+        "status_message_raw": null,
+        "variety": "co",
+        "exchange": "NSE",
+        "tradingsymbol": "INFY",
+        "instrument_token": 408065,
+        "order_type": "SL-M",
+        "transaction_type": "SELL",
+        "validity": "DAY",
+        "validity_ttl": null,
+        "product": "MIS",
+        "quantity": 10,
+        "disclosed_quantity": 0,
+        "price": 0,
+        "trigger_price": 1480,
+        "average_price": 0,
+        "filled_quantity": 0,
+        "pending_quantity": 10,
+        "cancelled_quantity": 0,
+        "auction_number": null,
+        "tag": null,
+        "tags": null,
+        "market_protection": null,
+        "guid": null
+    })
+}
+
+#[tokio::test]
+async fn test_place_cover_order() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote/ltp")
+        .data(json!({"NSE:INFY": {"instrument_token": 408065, "last_price": 1500.0}}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/co")
+        .data(json!({"order_id": "100"}))
+        .expect(1)
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([
+            order_json("99", None, "COMPLETE"),
+            order_json("101", Some("100"), "TRIGGER PENDING"),
+        ]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let mut entry = limit_order("INFY", 10, 1500.0);
+    entry.order_type = Some("MARKET".into());
+    let ids = kite.place_cover_order(entry.clone(), 1480.0).await.unwrap();
+    assert_eq!(ids.entry_order_id, "100");
+    assert_eq!(ids.stoploss_order_id.as_deref(), Some("101"));
+
+    let form = mock_server.received_one("POST", "/orders/co").await.form();
+    assert_eq!(form.get("product").map(String::as_str), Some("MIS"));
+    assert_eq!(
+        form.get("trigger_price").map(String::as_str),
+        Some("1480.0")
+    );
+
+    // A buy cover order needs its stop-loss below the LTP
+    let err = kite.place_cover_order(entry, 1510.0).await.unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+}
+
+#[tokio::test]
+async fn test_cover_order_keeps_entry_id_when_order_book_fails() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote/ltp")
+        .data(json!({"NSE:INFY": {"instrument_token": 408065, "last_price": 1500.0}}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/co")
+        .data(json!({"order_id": "100"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .error(500, "GeneralException", "Order book unavailable")
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let mut entry = limit_order("INFY", 10, 1500.0);
+    entry.order_type = Some("MARKET".into());
+    let ids = kite.place_cover_order(entry, 1480.0).await.unwrap();
+    assert_eq!(ids.entry_order_id, "100");
+    assert!(ids.stoploss_order_id.is_none());
+}
+
+#[tokio::test]
+async fn test_place_order_with_rounding() {
+    let csv = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+12346370,48228,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO
+";
+    let store: InstrumentStore =
+        parse_instruments_filtered(csv.as_bytes(), &InstrumentFilter::new())
+            .unwrap()
+            .into();
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151"}))
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    kite.set_order_rounding(Some(OrderRounding::new(Arc::new(store))));
+
+    let params = OrderParams {
+        exchange: Some("NFO".to_string()),
+        tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("NRML".to_string()),
+        quantity: Some(75),
+        price: Some(22000.07),
+        ..Default::default()
+    };
+    kite.place_order("regular", params.clone()).await.unwrap();
+    let form = mock_server
+        .received_one("POST", "/orders/regular")
+        .await
+        .form();
+    assert_eq!(form["quantity"], "50");
+    assert_eq!(form["price"], "22000.05");
+
+    let too_small = OrderParams {
+        quantity: Some(25),
+        ..params
+    };
+    assert!(kite.place_order("regular", too_small).await.is_err());
+    assert_eq!(
+        mock_server.received("POST", "/orders/regular").await.len(),
+        1
+    );
+}
+
+fn validator_store() -> InstrumentStore {
+    let csv = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+12346370,48228,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+";
+    parse_instruments_filtered(csv.as_bytes(), &InstrumentFilter::new())
+        .unwrap()
+        .into()
+}
+
+#[test]
+fn test_order_validator_rules() {
+    let mut validator = OrderValidator::new()
+        .instruments(Arc::new(validator_store()))
+        .market_clock(MarketClock::nse());
+    validator.set_price_band("NSE", "INFY", 1350.0, 1650.0);
+    let codes = |violations: Vec<Violation>| -> Vec<(ViolationCode, String)> {
+        violations.into_iter().map(|v| (v.code, v.field)).collect()
+    };
+
+    let future = OrderParams {
+        exchange: Some("NFO".to_string()),
+        tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(75),
+        price: Some(22000.07),
+        ..Default::default()
+    };
+    assert_eq!(
+        codes(validator.validate("regular", &future)),
+        vec![
+            (ViolationCode::ProductNotAllowed, "product".to_string()),
+            (ViolationCode::TickSize, "price".to_string()),
+            (ViolationCode::LotSize, "quantity".to_string()),
+        ]
+    );
+
+    let equity = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("SELL".to_string()),
+        order_type: Some("SL".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(10),
+        price: Some(1700.0),
+        ..Default::default()
+    };
+    assert_eq!(
+        codes(validator.validate("regular", &equity)),
+        vec![
+            (ViolationCode::MissingField, "trigger_price".to_string()),
+            (ViolationCode::PriceBand, "price".to_string()),
+        ]
+    );
+    let empty = OrderParams {
+        quantity: Some(0),
+        trigger_price: Some(1500.0),
+        price: Some(1500.0),
+        ..equity.clone()
+    };
+    assert_eq!(
+        codes(validator.validate("regular", &empty)),
+        vec![(ViolationCode::InvalidValue, "quantity".to_string())]
+    );
+
+    // AMOs are only taken outside market hours, and never for cover orders
+    let limit = OrderParams {
+        order_type: Some("LIMIT".to_string()),
+        price: Some(1500.0),
+        ..equity.clone()
+    };
+    let wednesday = |hour| Kolkata.with_ymd_and_hms(2024, 6, 12, hour, 0, 0).unwrap();
+    assert!(
+        validator
+            .validate_at("amo", &limit, wednesday(20))
+            .is_empty()
+    );
+    assert_eq!(
+        codes(validator.validate_at("amo", &limit, wednesday(11))),
+        vec![(ViolationCode::AmoOutsideWindow, "variety".to_string())]
+    );
+    assert_eq!(
+        codes(validator.validate("co", &limit)),
+        vec![
+            (ViolationCode::CoverOrder, "product".to_string()),
+            (ViolationCode::CoverOrder, "trigger_price".to_string()),
+        ]
+    );
+
+    let iceberg = OrderParams {
+        iceberg_legs: Some(12),
+        iceberg_quantity: Some(1),
+        ..limit.clone()
+    };
+    let violations = validator.validate("iceberg", &iceberg);
+    assert_eq!(violations.len(), 1);
+    assert_eq!(violations[0].code, ViolationCode::Iceberg);
+    assert_eq!(
+        serde_json::to_value(violations[0].code).unwrap(),
+        json!("iceberg")
+    );
+}
+
+#[tokio::test]
+async fn test_place_order_with_validator() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "151"}))
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    kite.set_order_validator(Some(
+        OrderValidator::new().instruments(Arc::new(validator_store())),
+    ));
+
+    let params = OrderParams {
+        exchange: Some("NFO".to_string()),
+        tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("LIMIT".to_string()),
+        product: Some("NRML".to_string()),
+        quantity: Some(75),
+        price: Some(22000.05),
+        ..Default::default()
+    };
+    let err = kite
+        .place_order("regular", params.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::ValidationFailed(_)
+    ));
+    assert_eq!(err.violations().len(), 1);
+    assert_eq!(err.violations()[0].code, ViolationCode::LotSize);
+    assert!(
+        mock_server
+            .received("POST", "/orders/regular")
+            .await
+            .is_empty()
+    );
+
+    let valid = OrderParams {
+        quantity: Some(100),
+        ..params
+    };
+    kite.place_order("regular", valid).await.unwrap();
+}
+
+#[test]
+fn test_freeze_quantity_split() {
+    assert_eq!(
+        split_quantity(4000, 1800, 1).unwrap(),
+        vec![1800, 1800, 400]
+    );
+    assert_eq!(split_quantity(3600, 1800, 75).unwrap(), vec![1800, 1800]);
+    // 1000 is not a whole number of 75 lots; children are cut at 13 lots
+    assert_eq!(split_quantity(1500, 1000, 75).unwrap(), vec![975, 525]);
+    assert!(split_quantity(100, 1800, 75).is_err());
+    assert!(split_quantity(300, 50, 75).is_err());
+
+    let freeze = FreezeQuantities::default();
+    let order = |exchange: &str, symbol: &str, quantity: i32| OrderParams {
+        exchange: Some(exchange.to_string()),
+        tradingsymbol: Some(symbol.to_string()),
+        quantity: Some(quantity),
+        ..Default::default()
+    };
+    assert_eq!(
+        freeze
+            .split(&order("NFO", "BANKNIFTY24JUN48000CE", 2000))
+            .unwrap(),
+        vec![900, 900, 200]
+    );
+    assert_eq!(
+        freeze.split(&order("NFO", "NIFTY24JUNFUT", 1800)).unwrap(),
+        vec![1800]
+    );
+    assert_eq!(
+        freeze
+            .split(&order("NFO", "NIFTYNXT5024JUNFUT", 5000))
+            .unwrap(),
+        vec![5000]
+    );
+    assert_eq!(
+        freeze.split(&order("NSE", "NIFTY24JUNFUT", 5000)).unwrap(),
+        vec![5000]
+    );
+}
+
+#[tokio::test]
+async fn test_place_order_splits_at_freeze_quantity() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "161"}))
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    kite.set_freeze_quantities(Some(FreezeQuantities::default()));
+
+    let params = OrderParams {
+        exchange: Some("NFO".to_string()),
+        tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+        transaction_type: Some("SELL".to_string()),
+        order_type: Some("MARKET".to_string()),
+        product: Some("NRML".to_string()),
+        quantity: Some(4000),
+        ..Default::default()
+    };
+    let response = kite.place_order("regular", params).await.unwrap();
+    assert_eq!(response.order_id, "161");
+    assert_eq!(response.split_order_ids, vec!["161", "161"]);
+
+    let quantities: Vec<String> = mock_server
+        .received("POST", "/orders/regular")
+        .await
+        .iter()
+        .map(|r| r.form()["quantity"].clone())
+        .collect();
+    assert_eq!(quantities, vec!["1800", "1800", "400"]);
+}
+
+#[tokio::test]
+async fn test_split_order_checks_parent_and_keeps_placed_ids() {
+    let mock_server = KiteMockServer::new().await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"order_id": "171"}})),
+        )
+        .up_to_n_times(1)
+        .with_priority(1)
+        .mount(&mock_server.server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "status": "error", "message": "Insufficient funds", "error_type": "MarginException"
+        })))
+        .with_priority(2)
+        .mount(&mock_server.server)
+        .await;
+    let mut kite = mock_server.client();
+    kite.set_freeze_quantities(Some(FreezeQuantities::default()));
+    kite.set_risk_limits(Some(RiskLimits {
+        max_quantity: Some(3000),
+        ..Default::default()
+    }));
+
+    let params = OrderParams {
+        exchange: Some("NFO".to_string()),
+        tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+        transaction_type: Some("SELL".to_string()),
+        order_type: Some("MARKET".to_string()),
+        product: Some("NRML".to_string()),
+        quantity: Some(4000),
+        ..Default::default()
+    };
+    // Every child is under the limit, the order is not
+    let err = kite
+        .place_order("regular", params.clone())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::RiskViolation(RiskViolation::MaxQuantity { quantity: 4000, .. })
+    ));
+    assert!(
+        mock_server
+            .received("POST", "/orders/regular")
+            .await
+            .is_empty()
+    );
+
+    let within = OrderParams {
+        quantity: Some(2000),
+        ..params
+    };
+    let err = kite.place_order("regular", within).await.unwrap_err();
+    let KiteConnectErrorKind::PartialSplit { placed, source } = &err.kind else {
+        panic!("expected a partial split, got {:?}", err);
+    };
+    assert_eq!(placed, &vec!["171".to_string()]);
+    assert!(matches!(source.kind, KiteConnectErrorKind::ApiError(_)));
+    assert!(!err.is_retriable());
+}
+
+#[tokio::test]
+async fn test_cancel_replace_uses_pending_quantity() {
+    let mock_server = KiteMockServer::new().await;
+    let mut partly_filled = order_json("191", None, "OPEN");
+    partly_filled["variety"] = json!("regular");
+    partly_filled["filled_quantity"] = json!(4);
+    partly_filled["pending_quantity"] = json!(6);
+    let mut complete = partly_filled.clone();
+    complete["order_id"] = json!("192");
+    complete["status"] = json!("COMPLETE");
+    mock_server
+        .endpoint("GET", "/orders/191")
+        .data(json!([partly_filled]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders/192")
+        .data(json!([complete]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/orders/regular/191")
+        .data(json!({"order_id": "191"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "193"}))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let replaced = kite
+        .cancel_replace("191", OrderParams::default())
+        .await
+        .unwrap();
+    assert_eq!(replaced.order_id, "193");
+    let form = mock_server
+        .received_one("POST", "/orders/regular")
+        .await
+        .form();
+    assert_eq!(form["quantity"], "6");
+
+    let err = kite
+        .cancel_replace("192", OrderParams::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+    assert!(
+        mock_server
+            .received("DELETE", "/orders/regular/192")
+            .await
+            .is_empty()
+    );
+}
+
+#[tokio::test]
+async fn test_cancel_replace_checks_replacement_before_cancelling() {
+    let mock_server = KiteMockServer::new().await;
+    let mut order = order_json("191", None, "OPEN");
+    order["variety"] = json!("regular");
+    mock_server
+        .endpoint("GET", "/orders/191")
+        .data(json!([order]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/orders/regular/191")
+        .data(json!({"order_id": "191"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/orders/regular")
+        .error(400, "InputException", "Invalid trigger price.")
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    kite.set_risk_limits(Some(RiskLimits {
+        max_quantity: Some(50),
+        ..Default::default()
+    }));
+
+    let err = kite
+        .cancel_replace(
+            "191",
+            OrderParams {
+                quantity: Some(100),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::RiskViolation(RiskViolation::MaxQuantity { quantity: 100, .. })
+    ));
+    assert!(
+        mock_server
+            .received("DELETE", "/orders/regular/191")
+            .await
+            .is_empty()
+    );
+
+    // Kite rejecting the replacement after the cancel says the original is gone
+    let err = kite
+        .cancel_replace("191", OrderParams::default())
+        .await
+        .unwrap_err();
+    let KiteConnectErrorKind::ReplaceFailed { cancelled, source } = &err.kind else {
+        panic!("unexpected error {}", err);
+    };
+    assert_eq!(cancelled, "191");
+    assert!(matches!(source.kind, KiteConnectErrorKind::ApiError(_)));
+    assert_eq!(
+        mock_server
+            .received("DELETE", "/orders/regular/191")
+            .await
+            .len(),
+        1
+    );
+
+    mock_server
+        .endpoint("GET", "/orders/404")
+        .data(json!([]))
+        .mount()
+        .await;
+    let err = kite
+        .cancel_replace("404", OrderParams::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+}
+
+#[tokio::test]
+async fn test_modify_order_checks_risk_limits() {
+    let mock_server = KiteMockServer::new().await;
+    let mut order = order_json("181", None, "TRIGGER PENDING");
+    order["variety"] = json!("regular");
+    mock_server
+        .endpoint("GET", "/orders/181")
+        .data(json!([order]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/orders/regular/181")
+        .data(json!({"order_id": "181"}))
+        .mount()
+        .await;
+    let mut kite = mock_server.client();
+    kite.set_risk_limits(Some(RiskLimits {
+        max_quantity: Some(50),
+        max_open_orders: Some(1),
+        ..Default::default()
+    }));
+
+    let changes = |quantity| OrderParams {
+        quantity: Some(quantity),
+        ..Default::default()
+    };
+    let err = kite
+        .modify_order("regular", "181", changes(100))
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::RiskViolation(RiskViolation::MaxQuantity { quantity: 100, .. })
+    ));
+    assert!(
+        mock_server
+            .received("PUT", "/orders/regular/181")
+            .await
+            .is_empty()
+    );
+
+    // The order being modified does not count as another open order
+    kite.modify_order("regular", "181", changes(20))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn test_modify_order_with_validator() {
+    let mock_server = KiteMockServer::new().await;
+    let mut order = order_json("181", None, "TRIGGER PENDING");
+    order["variety"] = json!("regular");
+    mock_server
+        .endpoint("GET", "/orders/181")
+        .data(json!([order]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/orders/regular/181")
+        .data(json!({"order_id": "181"}))
+        .mount()
+        .await;
+    let mut validator = OrderValidator::new().instruments(Arc::new(validator_store()));
+    validator.set_price_band("NSE", "INFY", 1350.0, 1650.0);
+    let mut kite = mock_server.client();
+    kite.set_order_validator(Some(validator));
+
+    // The changes are checked merged into the order they modify
+    let err = kite
+        .modify_order(
+            "regular",
+            "181",
+            OrderParams {
+                quantity: Some(0),
+                trigger_price: Some(1700.0),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap_err();
+    let codes: Vec<_> = err.violations().iter().map(|v| v.code).collect();
+    assert_eq!(
+        codes,
+        vec![ViolationCode::InvalidValue, ViolationCode::PriceBand]
+    );
+    assert!(
+        mock_server
+            .received("PUT", "/orders/regular/181")
+            .await
+            .is_empty()
+    );
+
+    kite.modify_order(
+        "regular",
+        "181",
+        OrderParams {
+            trigger_price: Some(1490.0),
+            ..Default::default()
+        },
+    )
+    .await
+    .unwrap();
+}
+
+#[tokio::test]
+async fn test_cancel_all_orders() {
+    use kiteconnect_rs::{CancelFilter, Order};
+
+    let regular = |order_id: &str, product: &str, status: &str| {
+        let mut order = order_json(order_id, None, status);
+        order["variety"] = json!("regular");
+        order["product"] = json!(product);
+        order
+    };
+    let mut orders = vec![
+        order_json("101", Some("100"), "TRIGGER PENDING"),
+        regular("200", "CNC", "OPEN"),
+        regular("201", "MIS", "COMPLETE"),
+    ];
+    orders.extend((300..312).map(|id| regular(&id.to_string(), "MIS", "OPEN")));
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!(orders))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/orders/co/101")
+        .data(json!({"order_id": "101"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/orders/regular/305")
+        .error(400, "InputException", "Order cannot be cancelled")
+        .mount()
+        .await;
+    for id in (300..312).filter(|id| *id != 305) {
+        mock_server
+            .endpoint("DELETE", &format!("/orders/regular/{}", id))
+            .data(json!({"order_id": id.to_string()}))
+            .mount()
+            .await;
+    }
+    let kite = mock_server.client();
+
+    let results = kite
+        .cancel_all_orders(&CancelFilter::new().product("MIS"))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 13);
+    let failed: Vec<&str> = results
+        .iter()
+        .filter(|r| !r.is_cancelled())
+        .map(|r| r.order_id.as_str())
+        .collect();
+    assert_eq!(failed, vec!["305"]);
+    let rejected = results.iter().find(|r| r.order_id == "305").unwrap();
+    assert!(matches!(
+        &rejected.result.as_ref().unwrap_err().kind,
+        KiteConnectErrorKind::ApiError(e) if e.error_type == "InputException"
+    ));
+    let cover_leg = mock_server.received_one("DELETE", "/orders/co/101").await;
+    assert_eq!(cover_leg.form()["parent_order_id"], "100");
+    assert!(
+        mock_server
+            .received("DELETE", "/orders/regular/200")
+            .await
+            .is_empty()
+    );
+    assert!(
+        mock_server
+            .received("DELETE", "/orders/regular/201")
+            .await
+            .is_empty()
+    );
+    // Thirteen cancels do not fit in one second of the order limit
+    let usage = kite.usage_stats();
+    assert!(
+        usage
+            .category(kiteconnect_rs::usage::ApiCategory::Orders)
+            .last_second
+            <= 10
+    );
+
+    let mut tagged: Order = serde_json::from_value(regular("400", "MIS", "OPEN")).unwrap();
+    tagged.tags = Some(vec!["scalper".to_string()]);
+    assert!(CancelFilter::new().tag("scalper").matches(&tagged));
+    assert!(!CancelFilter::new().tag("swing").matches(&tagged));
+    assert!(!CancelFilter::new().variety("co").matches(&tagged));
+    assert!(CancelFilter::new().tradingsymbol("INFY").matches(&tagged));
+}
+
+#[tokio::test]
+async fn test_read_only_client_refuses_trading_calls() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([order_json("101", None, "OPEN")]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/margins/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .access_token("test_access_token")
+        .read_only(true)
+        .build()
+        .unwrap();
+    assert!(kite.clone().is_read_only());
+
+    let params = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("MARKET".to_string()),
+        product: Some("CNC".to_string()),
+        quantity: Some(1),
+        ..Default::default()
+    };
+    let err = kite.place_order("regular", params).await.unwrap_err();
+    assert!(err.is_read_only_mode());
+    assert!(!err.is_retriable());
+    assert!(err.to_string().contains("POST /orders/regular"));
+    let err = kite
+        .cancel_order("co", "101", Some("100"))
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::ReadOnlyMode(_)));
+    for (result, name) in [
+        (kite.delete::<serde_json::Value>("/mf/sips/123").await, "mf"),
+        (
+            kite.delete::<serde_json::Value>("/gtt/triggers/1").await,
+            "gtt",
+        ),
+        (
+            kite.put::<serde_json::Value>("/portfolio/positions").await,
+            "convert",
+        ),
+    ] {
+        assert!(result.unwrap_err().is_read_only_mode(), "{}", name);
+    }
+    assert!(
+        mock_server
+            .received("POST", "/orders/regular")
+            .await
+            .is_empty()
+    );
+    assert!(
+        mock_server
+            .received("DELETE", "/orders/co/101")
+            .await
+            .is_empty()
+    );
+
+    // Reads, including margin calculations sent as POST, still go through
+    assert_eq!(kite.get_orders().await.unwrap().len(), 1);
+    assert!(
+        kite.post_json::<Vec<serde_json::Value>, _>("/margins/orders", json!([]))
+            .await
+            .is_ok()
+    );
+}
+
+#[test]
+fn test_order_display_summary() {
+    let mut order: kiteconnect_rs::orders::Order =
+        serde_json::from_value(order_json("101", None, "TRIGGER PENDING")).unwrap();
+    assert_eq!(
+        order.to_string(),
+        "SELL 10 NSE:INFY SL-M trigger 1480 [TRIGGER PENDING] #101"
+    );
+
+    order.order_type = "LIMIT".to_string();
+    order.price = 1500.5;
+    order.trigger_price = 0.0;
+    order.filled_quantity = 4.0;
+    order.status = "OPEN".to_string();
+    assert_eq!(
+        order.to_string(),
+        "SELL 4/10 NSE:INFY LIMIT @ 1500.5 [OPEN] #101"
+    );
+}
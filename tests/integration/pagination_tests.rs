@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use futures_util::StreamExt;
+use kiteconnect_rs::{KiteConnect, PageParams};
+use serde_json::{Value, json};
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{method, path, query_param},
+};
+
+async fn mount_page(server: &MockServer, from: &str, to: &str, items: Value) {
+    Mock::given(method("GET"))
+        .and(path("/mf/orders"))
+        .and(query_param("from", from))
+        .and(query_param("to", to))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"status": "success", "data": items})),
+        )
+        .expect(1)
+        .mount(server)
+        .await;
+}
+
+#[tokio::test]
+async fn test_paginate_walks_date_windows() {
+    let server = MockServer::start().await;
+    mount_page(
+        &server,
+        "2024-01-01",
+        "2024-01-10",
+        json!([{"id": 1}, {"id": 2}]),
+    )
+    .await;
+    mount_page(&server, "2024-01-11", "2024-01-20", json!([])).await;
+    mount_page(&server, "2024-01-21", "2024-01-25", json!([{"id": 3}])).await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let params = PageParams::new(
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 1, 25).unwrap(),
+    )
+    .window_days(10);
+
+    let items: Vec<Value> = kite
+        .paginate::<Value>("/mf/orders", params)
+        .map(|item| item.expect("page request failed"))
+        .collect()
+        .await;
+
+    let ids: Vec<i64> = items.iter().map(|v| v["id"].as_i64().unwrap()).collect();
+    assert_eq!(ids, vec![1, 2, 3]);
+}
+
+#[tokio::test]
+async fn test_paginate_stops_on_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/mf/orders"))
+        .respond_with(ResponseTemplate::new(500).set_body_json(json!({
+            "status": "error",
+            "message": "Something went wrong",
+            "data": null,
+            "error_type": "GeneralException"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let params = PageParams::new(
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 3, 1).unwrap(),
+    )
+    .window_days(7);
+
+    let results: Vec<_> = kite.paginate::<Value>("/mf/orders", params).collect().await;
+    assert_eq!(results.len(), 1);
+    assert!(results[0].is_err());
+}
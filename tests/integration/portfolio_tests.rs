@@ -1,8 +1,17 @@
 use kiteconnect_rs::{
-    KiteConnect,
-    portfolio::{ConvertPositionParams, HoldingAuthParams, HoldingsAuthInstruments},
+    Charges, DailyLossLimiter, KiteConnect, KiteConnectErrorKind, Order, OrderParams,
+    PortfolioChange, PortfolioTracker, RiskViolation,
+    portfolio::{
+        ConvertPositionParams, Holding, HoldingAuthParams, HoldingsAuthInstruments, Position,
+        SquareOffOutcome, SquareOffParams, check_sellable,
+    },
 };
+use serde_json::{Value, json};
 use std::time::Duration;
+use wiremock::{
+    Mock, MockServer, ResponseTemplate,
+    matchers::{body_string_contains, method, path},
+};
 
 use super::mock_server::KiteMockServer;
 
@@ -199,11 +208,7 @@ async fn test_convert_position() {
         "Failed to convert position: {:?}",
         result.err()
     );
-    assert_eq!(
-        result.unwrap(),
-        true,
-        "Position conversion should return true"
-    );
+    assert!(result.unwrap(), "Position conversion should return true");
 }
 
 #[tokio::test]
@@ -290,3 +295,616 @@ async fn test_portfolio_error_handling() {
     let auctions = kite.get_auction_instruments().await;
     assert!(auctions.is_err(), "Expected error for invalid URL");
 }
+
+fn position_json(symbol: &str, product: &str, quantity: i32) -> Value {
+    json!({
+        "tradingsymbol": symbol, "exchange": "NSE", "instrument_token": 1, "product": product,
+        "quantity": quantity, "overnight_quantity": 0, "multiplier": 1.0,
+        "average_price": 100.0, "close_price": 0.0, "last_price": 101.0, "value": 0.0,
+        "pnl": 0.0, "m2m": 0.0, "unrealised": 0.0, "realised": 0.0,
+        "buy_quantity": 0, "buy_price": 0.0, "buy_value": 0.0, "buy_m2m": 0.0,
+        "sell_quantity": 0, "sell_price": 0.0, "sell_value": 0.0, "sell_m2m": 0.0,
+        "day_buy_quantity": 0, "day_buy_price": 0.0, "day_buy_value": 0.0,
+        "day_sell_quantity": 0, "day_sell_price": 0.0, "day_sell_value": 0.0
+    })
+}
+
+async fn square_off_server() -> MockServer {
+    let server = MockServer::start().await;
+    let net = json!([
+        position_json("INFY", "MIS", 10),
+        position_json("TCS", "MIS", -5),
+        position_json("SBIN", "MIS", 0),
+        position_json("RELIANCE", "CNC", 3),
+    ]);
+    Mock::given(method("GET"))
+        .and(path("/portfolio/positions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"net": net, "day": []}})),
+        )
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn test_square_off_all_places_offsetting_orders() {
+    let server = square_off_server().await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .and(body_string_contains("tradingsymbol=INFY"))
+        .and(body_string_contains("transaction_type=SELL"))
+        .and(body_string_contains("quantity=10"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"order_id": "111"}})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .and(body_string_contains("tradingsymbol=TCS"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(
+            json!({"status": "error", "message": "RMS rejected", "error_type": "InputException"}),
+        ))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let results = kite
+        .square_off_all(SquareOffParams {
+            product: Some("MIS".to_string()),
+            ..Default::default()
+        })
+        .await
+        .expect("square off failed");
+
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].tradingsymbol, "INFY");
+    assert_eq!(results[0].transaction_type, "SELL");
+    assert!(matches!(&results[0].outcome, SquareOffOutcome::Placed(id) if id == "111"));
+    assert_eq!(results[1].tradingsymbol, "TCS");
+    assert_eq!(results[1].transaction_type, "BUY");
+    assert_eq!(results[1].quantity, 5);
+    match &results[1].outcome {
+        SquareOffOutcome::Failed(e) => assert!(!e.is_retriable()),
+        other => panic!("expected a failed square off, got {:?}", other),
+    }
+}
+
+#[tokio::test]
+async fn test_square_off_all_dry_run() {
+    let server = square_off_server().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let results = kite
+        .square_off_all(SquareOffParams {
+            dry_run: true,
+            ..Default::default()
+        })
+        .await
+        .expect("square off failed");
+
+    assert_eq!(results.len(), 3);
+    assert!(
+        results
+            .iter()
+            .all(|r| matches!(r.outcome, SquareOffOutcome::DryRun))
+    );
+}
+
+#[tokio::test]
+async fn test_daily_loss_limiter_blocks_orders_and_squares_off() {
+    let server = square_off_server().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(500))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let limiter = DailyLossLimiter::new(5_000.0).square_off_on_breach(SquareOffParams {
+        dry_run: true,
+        ..Default::default()
+    });
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .daily_loss_limiter(limiter.clone())
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    assert!(kite.feed_daily_pnl(-4_000.0).await.unwrap().is_none());
+    assert!(!limiter.is_breached());
+
+    let squared = kite
+        .feed_daily_pnl(-5_500.0)
+        .await
+        .expect("square off failed")
+        .expect("breach should trigger a square off");
+    assert_eq!(squared.len(), 3);
+    assert!(limiter.is_breached());
+
+    // Further updates do not square off again.
+    assert!(kite.feed_daily_pnl(-6_000.0).await.unwrap().is_none());
+
+    let order = OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("MARKET".to_string()),
+        product: Some("MIS".to_string()),
+        quantity: Some(1),
+        ..Default::default()
+    };
+    let err = kite
+        .place_order("regular", order)
+        .await
+        .expect_err("orders should be blocked after the loss limit");
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::RiskViolation(RiskViolation::DailyLossLimit { .. })
+    ));
+}
+
+fn holding_json(symbol: &str, quantities: [i32; 5]) -> Value {
+    let [
+        quantity,
+        t1_quantity,
+        used_quantity,
+        collateral_quantity,
+        authorised_quantity,
+    ] = quantities;
+    json!({
+        "tradingsymbol": symbol, "exchange": "NSE", "instrument_token": 1, "isin": "INE000000000",
+        "product": "CNC", "price": 0.0, "used_quantity": used_quantity, "quantity": quantity,
+        "t1_quantity": t1_quantity, "realised_quantity": quantity,
+        "authorised_quantity": authorised_quantity, "authorised_date": "2024-01-15 00:00:00",
+        "opening_quantity": quantity, "collateral_quantity": collateral_quantity,
+        "collateral_type": "", "discrepancy": false, "average_price": 100.0,
+        "last_price": 101.0, "close_price": 100.0, "pnl": 0.0, "day_change": 0.0,
+        "day_change_percentage": 0.0,
+        "mtf": {"quantity": 0, "used_quantity": 0, "average_price": 0.0, "value": 0.0, "initial_margin": 0.0}
+    })
+}
+
+#[test]
+fn test_holding_sellable_quantity() {
+    let holding = |quantities| -> Holding {
+        serde_json::from_value(holding_json("INFY", quantities)).unwrap()
+    };
+
+    // quantity, t1, used, collateral, authorised
+    assert_eq!(holding([10, 0, 0, 0, 0]).sellable_quantity(), 10);
+    assert_eq!(holding([10, 5, 0, 0, 0]).sellable_quantity(), 15);
+    assert_eq!(holding([10, 5, 3, 0, 0]).sellable_quantity(), 12);
+    assert_eq!(holding([10, 0, 0, 4, 0]).sellable_quantity(), 6);
+    // Authorisation caps settled shares only
+    assert_eq!(holding([10, 5, 0, 0, 2]).sellable_quantity(), 7);
+    assert_eq!(holding([10, 0, 0, 8, 5]).sellable_quantity(), 2);
+    assert_eq!(holding([10, 0, 12, 0, 0]).sellable_quantity(), 0);
+
+    let holdings = vec![holding([10, 5, 3, 0, 0])];
+    assert!(check_sellable(&holdings, "INFY", 12).is_ok());
+    let err = check_sellable(&holdings, "INFY", 13).unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+    assert!(check_sellable(&holdings, "TCS", 1).is_err());
+}
+
+#[tokio::test]
+async fn test_sell_holding_checks_holdings() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/portfolio/holdings"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(
+            json!({"status": "success", "data": [holding_json("INFY", [10, 0, 0, 4, 0])]}),
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/orders/regular"))
+        .and(body_string_contains("transaction_type=SELL"))
+        .and(body_string_contains("product=CNC"))
+        .and(body_string_contains("quantity=6"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"status": "success", "data": {"order_id": "222"}})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .build()
+        .expect("Failed to build KiteConnect client");
+    let order = |quantity| OrderParams {
+        exchange: Some("NSE".to_string()),
+        tradingsymbol: Some("INFY".to_string()),
+        order_type: Some("MARKET".to_string()),
+        quantity: Some(quantity),
+        ..Default::default()
+    };
+
+    let err = kite.sell_holding(order(7)).await.unwrap_err();
+    assert!(err.to_string().contains("insufficient holdings"), "{}", err);
+
+    let response = kite.sell_holding(order(6)).await.expect("sell failed");
+    assert_eq!(response.order_id, "222");
+}
+
+fn traded_position(symbol: &str, buy: (i32, f64), sell: (i32, f64)) -> Position {
+    let mut position: Position =
+        serde_json::from_value(position_json(symbol, "MIS", buy.0 - sell.0)).unwrap();
+    position.buy_quantity = buy.0;
+    position.buy_price = buy.1;
+    position.sell_quantity = sell.0;
+    position.sell_price = sell.1;
+    position.value = sell.0 as f64 * sell.1 - buy.0 as f64 * buy.1;
+    position
+}
+
+#[test]
+fn test_position_breakeven() {
+    let charges = |total: f64| Charges {
+        total,
+        ..Default::default()
+    };
+
+    let long = traded_position("INFY", (10, 100.0), (0, 0.0));
+    assert_eq!(long.breakeven(&charges(20.0)), Some(102.0));
+    assert_eq!(long.breakeven(&charges(0.0)), Some(100.0));
+
+    let short = traded_position("TCS", (0, 0.0), (5, 200.0));
+    assert_eq!(short.breakeven(&charges(10.0)), Some(198.0));
+
+    // Partly closed at a profit: the realised gain lowers the breakeven
+    let partial = traded_position("SBIN", (10, 100.0), (5, 110.0));
+    assert_eq!(partial.breakeven(&charges(0.0)), Some(90.0));
+
+    let flat = traded_position("SBIN", (10, 100.0), (10, 110.0));
+    assert_eq!(flat.breakeven(&charges(5.0)), None);
+    assert_eq!(flat.charges_params(true).len(), 2);
+
+    let legs = partial.charges_params(true);
+    assert_eq!(legs.len(), 3);
+    assert_eq!(legs[2].transaction_type, "SELL");
+    assert_eq!(legs[2].quantity, 5.0);
+    assert_eq!(legs[2].average_price, partial.last_price);
+
+    let total: Charges = [charges(1.5), charges(2.5)].iter().sum();
+    assert_eq!(total.total, 4.0);
+}
+
+#[tokio::test]
+async fn test_get_position_breakeven_uses_charges_api() {
+    let server = MockServer::start().await;
+    let order_charges = |transaction_type: &str, total: f64| {
+        json!({
+            "exchange": "NSE", "tradingsymbol": "INFY", "transaction_type": transaction_type,
+            "variety": "regular", "product": "MIS", "order_type": "MARKET",
+            "quantity": 10.0, "price": 100.0,
+            "charges": {
+                "transaction_tax": 0.0, "transaction_tax_type": "stt",
+                "exchange_turnover_charge": 0.0, "sebi_turnover_charge": 0.0,
+                "brokerage": total, "stamp_duty": 0.0,
+                "gst": {"igst": 0.0, "cgst": 0.0, "sgst": 0.0, "total": 0.0},
+                "total": total
+            }
+        })
+    };
+    Mock::given(method("POST"))
+        .and(path("/charges/orders"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "status": "success",
+            "data": [order_charges("BUY", 12.0), order_charges("SELL", 8.0)]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&server.uri())
+        .access_token("test_access_token")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let long = traded_position("INFY", (10, 100.0), (0, 0.0));
+    assert_eq!(
+        kite.get_position_breakeven(&long).await.unwrap(),
+        Some(102.0)
+    );
+
+    let flat = traded_position("INFY", (0, 0.0), (0, 0.0));
+    assert_eq!(kite.get_position_breakeven(&flat).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_account_snapshot_written_and_read_back() {
+    use kiteconnect_rs::snapshot::{SNAPSHOT_VERSION, SnapshotWriter};
+
+    let mock_server = KiteMockServer::new().await;
+    for (endpoint, data) in [
+        ("/portfolio/holdings", json!([])),
+        ("/portfolio/positions", json!({"net": [], "day": []})),
+        (
+            "/user/margins",
+            super::margins_tests::margins_json(1000.0, 0.0),
+        ),
+        ("/orders", json!([])),
+        ("/trades", json!([])),
+    ] {
+        mock_server
+            .endpoint("GET", endpoint)
+            .data(data)
+            .expect(1)
+            .mount()
+            .await;
+    }
+
+    let dir = tempfile::tempdir().unwrap();
+    let writer = SnapshotWriter::new(dir.path().join("snapshots")).unwrap();
+    let path = writer.write_now(&mock_server.client()).await.unwrap();
+
+    let dates = writer.dates().unwrap();
+    assert_eq!(dates.len(), 1);
+    assert_eq!(path, writer.path_for(dates[0]));
+    let snapshot = writer.read(dates[0]).unwrap().unwrap();
+    assert_eq!(snapshot.version, SNAPSHOT_VERSION);
+    assert_eq!(snapshot.margins.equity.net, 1000.0);
+    assert!(snapshot.holdings.is_empty() && snapshot.orders.is_empty());
+    assert!(writer.read(dates[0].pred_opt().unwrap()).unwrap().is_none());
+}
+
+#[test]
+fn test_snapshot_diff() {
+    use kiteconnect_rs::snapshot::{AccountSnapshot, QuantityChange};
+
+    let snapshot = |date: &str, holdings: Value, positions: Value, cash: f64| {
+        serde_json::from_value::<AccountSnapshot>(json!({
+            "version": 1,
+            "taken_at": format!("{}T10:30:00Z", date),
+            "date": date,
+            "holdings": holdings,
+            "positions": {"net": positions, "day": []},
+            "margins": super::margins_tests::margins_json(cash, 0.0),
+            "orders": [],
+            "trades": []
+        }))
+        .unwrap()
+    };
+    let monday = snapshot(
+        "2024-06-03",
+        json!([
+            holding_json("INFY", [10, 0, 0, 0, 0]),
+            holding_json("TCS", [5, 0, 0, 0, 0])
+        ]),
+        json!([position_json("NIFTY24JUNFUT", "NRML", 50)]),
+        1000.0,
+    );
+    let tuesday = snapshot(
+        "2024-06-04",
+        json!([
+            holding_json("INFY", [10, 4, 0, 0, 0]),
+            holding_json("SBIN", [0, 7, 0, 0, 0])
+        ]),
+        json!([position_json("NIFTY24JUNFUT", "NRML", 50)]),
+        1250.5,
+    );
+
+    let diff = monday.diff(&tuesday);
+    let change = |symbol: &str, before, after| QuantityChange {
+        exchange: "NSE".to_string(),
+        tradingsymbol: symbol.to_string(),
+        product: "CNC".to_string(),
+        before,
+        after,
+    };
+    assert_eq!(
+        diff.holdings,
+        vec![
+            change("INFY", 10, 14),
+            change("SBIN", 0, 7),
+            change("TCS", 5, 0)
+        ]
+    );
+    assert!(diff.positions.is_empty());
+    let fields: Vec<&str> = diff.margins.iter().map(|m| m.field.as_str()).collect();
+    assert_eq!(fields, ["net", "cash", "net", "cash"]);
+    assert_eq!(
+        diff.to_string(),
+        [
+            "Changes from 2024-06-03 to 2024-06-04",
+            "Holdings:",
+            "  ~ NSE:INFY CNC 10 -> 14 (+4)",
+            "  + NSE:SBIN CNC 7 (new)",
+            "  - NSE:TCS CNC 5 (closed)",
+            "Margins:",
+            "  equity net 1000.00 -> 1250.50 (+250.50)",
+            "  equity cash 1000.00 -> 1250.50 (+250.50)",
+            "  commodity net 1000.00 -> 1250.50 (+250.50)",
+            "  commodity cash 1000.00 -> 1250.50 (+250.50)",
+        ]
+        .join("\n")
+    );
+    assert!(monday.diff(&monday).is_empty());
+    assert_eq!(
+        monday.diff(&monday).to_string(),
+        "Changes from 2024-06-03 to 2024-06-03: none"
+    );
+}
+
+fn filled_order(order_id: &str, symbol: &str, side: &str, filled_quantity: f64) -> Value {
+    json!({
+        "placed_by": "XXXXXX", "order_id": order_id, "status": "COMPLETE", "variety": "regular",
+        "exchange": "NSE", "tradingsymbol": symbol, "instrument_token": 1,
+        "order_type": "MARKET", "transaction_type": side, "validity": "DAY", "product": "MIS",
+        "quantity": filled_quantity, "disclosed_quantity": 0.0, "price": 0.0,
+        "trigger_price": 0.0, "average_price": 100.0, "filled_quantity": filled_quantity,
+        "pending_quantity": 0.0, "cancelled_quantity": 0.0
+    })
+}
+
+#[tokio::test]
+async fn test_portfolio_tracker_changes() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/holdings")
+        .data(json!([holding_json("INFY", [10, 5, 0, 0, 0])]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({
+            "net": [position_json("TCS", "MIS", -5), position_json("SBIN", "MIS", 0)],
+            "day": []
+        }))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([filled_order("1", "TCS", "SELL", 5.0)]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let tracker = PortfolioTracker::new();
+    let changes = tracker.changes();
+    let refreshed = tracker.refresh(&kite).await.unwrap();
+    assert_eq!(refreshed.len(), 2);
+    assert!(matches!(&refreshed[0], PortfolioChange::HoldingAdded(h) if h.after == 15));
+    assert!(matches!(&refreshed[1], PortfolioChange::PositionOpened(p) if p.after == -5));
+    assert_eq!(changes.try_recv().unwrap(), refreshed[0]);
+    assert_eq!(changes.try_recv().unwrap(), refreshed[1]);
+
+    // A postback for a fill the refresh already counted does not move the position
+    let order = |value| -> Order { serde_json::from_value(value).unwrap() };
+    assert_eq!(
+        tracker.on_order_update(&order(filled_order("1", "TCS", "SELL", 5.0))),
+        None
+    );
+
+    let opened = tracker.on_order_update(&order(filled_order("2", "SBIN", "BUY", 3.0)));
+    assert!(matches!(opened, Some(PortfolioChange::PositionOpened(ref p)) if p.after == 3));
+    let partial = tracker.on_order_update(&order(filled_order("3", "TCS", "BUY", 2.0)));
+    let Some(PortfolioChange::QuantityChanged(update)) = partial else {
+        panic!("expected a quantity change, got {:?}", partial);
+    };
+    assert_eq!((update.before, update.after, update.delta()), (-5, -3, 2));
+    let closed = tracker.on_order_update(&order(filled_order("3", "TCS", "BUY", 5.0)));
+    assert!(matches!(closed, Some(PortfolioChange::PositionClosed(ref p)) if p.before == -3));
+    assert_eq!(tracker.position_quantity("NSE", "TCS"), 0);
+    assert_eq!(tracker.positions().len(), 1);
+
+    // A refresh agreeing with the fills reports only the holding sold off
+    let holdings: Vec<Holding> = vec![];
+    let positions: Vec<Position> = vec![
+        serde_json::from_value(position_json("SBIN", "MIS", 3)).unwrap(),
+        serde_json::from_value(position_json("TCS", "MIS", 0)).unwrap(),
+    ];
+    let updated = tracker.update(&holdings, &positions);
+    assert_eq!(updated.len(), 1);
+    assert!(matches!(&updated[0], PortfolioChange::HoldingRemoved(h) if h.before == 15));
+    assert!(!updated[0].is_position());
+    assert_eq!(changes.len(), 4);
+
+    let body = serde_json::to_value(&updated[0]).unwrap();
+    assert_eq!(body["type"], "holding_removed");
+    assert_eq!(body["data"]["tradingsymbol"], "INFY");
+}
+
+#[tokio::test]
+async fn test_portfolio_tracker_keeps_fills_during_refresh() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/holdings")
+        .data(json!([]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({"net": [position_json("SBIN", "MIS", 0)], "day": []}))
+        .delay(Duration::from_millis(200))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+    let tracker = PortfolioTracker::new();
+    let order = |value| -> Order { serde_json::from_value(value).unwrap() };
+
+    // The fill arrives while the positions are being fetched, and is not in them
+    let (refreshed, _) = tokio::join!(tracker.refresh(&kite), async {
+        tokio::time::sleep(Duration::from_millis(50)).await;
+        tracker.on_order_update(&order(filled_order("9", "SBIN", "BUY", 3.0)))
+    });
+    assert!(refreshed.unwrap().is_empty());
+    assert_eq!(tracker.position_quantity("NSE", "SBIN"), 3);
+    assert_eq!(
+        tracker.on_order_update(&order(filled_order("9", "SBIN", "BUY", 3.0))),
+        None
+    );
+
+    // Nobody reads the changes, so only the latest are kept
+    let changes = tracker.changes();
+    for i in 0..kiteconnect_rs::portfolio_tracker::CHANGE_CAPACITY + 10 {
+        let side = if i % 2 == 0 { "SELL" } else { "BUY" };
+        tracker.on_order_update(&order(filled_order(&format!("c{}", i), "SBIN", side, 1.0)));
+    }
+    assert_eq!(
+        changes.len(),
+        kiteconnect_rs::portfolio_tracker::CHANGE_CAPACITY
+    );
+}
+
+#[tokio::test]
+async fn test_portfolio_tracker_feeds_daily_loss_limiter() {
+    let mock_server = KiteMockServer::new().await;
+    let mut losing = position_json("TCS", "MIS", 5);
+    losing["m2m"] = json!(-3_500.0);
+    let mut winning = position_json("SBIN", "MIS", 0);
+    winning["m2m"] = json!(500.0);
+    mock_server
+        .endpoint("GET", "/portfolio/holdings")
+        .data(json!([]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({"net": [losing, winning], "day": []}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let limiter = DailyLossLimiter::new(2_000.0);
+    let tracker = PortfolioTracker::new().feed(&limiter);
+    tracker.refresh(&kite).await.unwrap();
+    assert_eq!(limiter.pnl(), -3_000.0);
+    assert!(limiter.is_breached());
+}
@@ -0,0 +1,232 @@
+use chrono::NaiveDate;
+use kiteconnect_rs::InstrumentStore;
+use kiteconnect_rs::labels::{Exchange, Product, TransactionType};
+use kiteconnect_rs::markets::{InstrumentFilter, parse_instruments_filtered};
+use kiteconnect_rs::portfolio::Position;
+use kiteconnect_rs::strategies::{OptionChain, OptionKind, Strategy, roll_position};
+use serde_json::{Value, json};
+
+use super::mock_server::KiteMockServer;
+
+fn option_chain_csv() -> String {
+    let mut csv = String::from(
+        "instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange\n",
+    );
+    let mut token = 1000;
+    for strike in (21800..=22200).step_by(100) {
+        for kind in ["CE", "PE"] {
+            token += 1;
+            csv.push_str(&format!(
+                "{token},{token},NIFTY24JUN{strike}{kind},NIFTY,0,2024-06-27,{strike},0.05,25,{kind},NFO-OPT,NFO\n"
+            ));
+        }
+    }
+    // Next expiry and another underlying must not leak into the chain
+    csv.push_str("2001,2001,NIFTY24JUL22000CE,NIFTY,0,2024-07-25,22000,0.05,25,CE,NFO-OPT,NFO\n");
+    csv.push_str(
+        "2002,2002,BANKNIFTY24JUN22000CE,BANKNIFTY,0,2024-06-27,22000,0.05,15,CE,NFO-OPT,NFO\n",
+    );
+    csv.push_str("3001,3001,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,25,FUT,NFO-FUT,NFO\n");
+    csv.push_str("3002,3002,NIFTY24JULFUT,NIFTY,0,2024-07-25,0,0.05,25,FUT,NFO-FUT,NFO\n");
+    csv
+}
+
+fn store() -> InstrumentStore {
+    parse_instruments_filtered(option_chain_csv().as_bytes(), &InstrumentFilter::new())
+        .unwrap()
+        .into()
+}
+
+fn expiry() -> NaiveDate {
+    NaiveDate::from_ymd_opt(2024, 6, 27).unwrap()
+}
+
+#[test]
+fn test_strategy_templates() {
+    let store = store();
+    let chain = OptionChain::new(&store, Exchange::Nfo, "NIFTY", expiry());
+    assert_eq!(
+        chain.strikes(),
+        vec![21800.0, 21900.0, 22000.0, 22100.0, 22200.0]
+    );
+    assert_eq!(chain.atm_strike(22040.0), Some(22000.0));
+    assert_eq!(
+        chain.get(22000.0, OptionKind::Put).unwrap().tradingsymbol,
+        "NIFTY24JUN22000PE"
+    );
+
+    let straddle = Strategy::straddle(&chain, 22000.0, TransactionType::Sell, 2).unwrap();
+    assert_eq!(straddle.legs.len(), 2);
+    assert!(straddle.legs.iter().all(|leg| leg.quantity() == 50));
+    assert!(
+        straddle
+            .legs
+            .iter()
+            .all(|leg| leg.transaction_type == TransactionType::Sell)
+    );
+
+    let condor = Strategy::iron_condor(&chain, 21900.0, 22100.0, 100.0, 1).unwrap();
+    let orders = condor.order_params(Product::Nrml);
+    let sides: Vec<(&str, &str)> = orders
+        .iter()
+        .map(|o| {
+            (
+                o.tradingsymbol.as_deref().unwrap(),
+                o.transaction_type.as_deref().unwrap(),
+            )
+        })
+        .collect();
+    assert_eq!(
+        sides,
+        vec![
+            ("NIFTY24JUN21800PE", "BUY"),
+            ("NIFTY24JUN22200CE", "BUY"),
+            ("NIFTY24JUN21900PE", "SELL"),
+            ("NIFTY24JUN22100CE", "SELL"),
+        ]
+    );
+    assert!(orders.iter().all(|o| o.product.as_deref() == Some("NRML")));
+
+    let spread = Strategy::bull_call_spread(&chain, 22000.0, 22100.0, 1).unwrap();
+    assert_eq!(spread.legs[0].transaction_type, TransactionType::Buy);
+    assert_eq!(spread.legs[0].instrument.strike, 22000.0);
+
+    assert!(Strategy::bull_call_spread(&chain, 22100.0, 22000.0, 1).is_err());
+    assert!(Strategy::straddle(&chain, 22050.0, TransactionType::Buy, 1).is_err());
+    assert!(Strategy::strangle(&chain, 21900.0, 22100.0, TransactionType::Buy, 0).is_err());
+    assert!(Strategy::iron_condor(&chain, 21800.0, 22200.0, 100.0, 1).is_err());
+}
+
+fn basket_margins(initial: f64, final_total: f64) -> Value {
+    let margins = |total: f64| {
+        json!({
+            "type": "equity", "tradingsymbol": "", "exchange": "NFO", "total": total,
+            "charges": {
+                "transaction_tax": 0.0, "transaction_tax_type": "stt",
+                "exchange_turnover_charge": 0.0, "sebi_turnover_charge": 0.0, "brokerage": 0.0,
+                "stamp_duty": 0.0, "gst": {"igst": 0.0, "cgst": 0.0, "sgst": 0.0, "total": 0.0},
+                "total": 0.0
+            }
+        })
+    };
+    json!({"initial": margins(initial), "final": margins(final_total), "orders": []})
+}
+
+#[tokio::test]
+async fn test_place_strategy_with_margin_benefit_check() {
+    let store = store();
+    let chain = OptionChain::new(&store, Exchange::Nfo, "NIFTY", expiry());
+    let spread = Strategy::bull_call_spread(&chain, 22000.0, 22100.0, 1).unwrap();
+
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("POST", "/margins/basket")
+        .data(basket_margins(150_000.0, 30_000.0))
+        .mount()
+        .await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "444"}))
+        .mount()
+        .await;
+    let kite = server.client();
+
+    let margins = kite
+        .get_strategy_margins(&spread, Product::Nrml)
+        .await
+        .unwrap();
+    assert_eq!(margins.benefit(), 120_000.0);
+
+    let err = kite
+        .place_strategy(&spread, Product::Nrml, Some(200_000.0))
+        .await
+        .unwrap_err();
+    assert!(err.to_string().contains("margin benefit"), "{}", err);
+    assert!(server.received("POST", "/orders/regular").await.is_empty());
+
+    let results = kite
+        .place_strategy(&spread, Product::Nrml, Some(100_000.0))
+        .await
+        .unwrap();
+    assert_eq!(results.len(), 2);
+    assert_eq!(results[0].transaction_type, TransactionType::Buy);
+    assert!(results.iter().all(|r| r.result.is_ok()));
+
+    let placed = server.received("POST", "/orders/regular").await;
+    assert_eq!(placed[0].form()["tradingsymbol"], "NIFTY24JUN22000CE");
+    assert_eq!(placed[0].form()["quantity"], "25");
+    assert_eq!(placed[1].form()["transaction_type"], "SELL");
+}
+
+fn nfo_position(symbol: &str, token: u32, quantity: i32) -> Position {
+    serde_json::from_value(json!({
+        "tradingsymbol": symbol, "exchange": "NFO", "instrument_token": token, "product": "NRML",
+        "quantity": quantity, "overnight_quantity": quantity, "multiplier": 1.0,
+        "average_price": 100.0, "close_price": 0.0, "last_price": 101.0, "value": 0.0,
+        "pnl": 0.0, "m2m": 0.0, "unrealised": 0.0, "realised": 0.0,
+        "buy_quantity": 0, "buy_price": 0.0, "buy_value": 0.0, "buy_m2m": 0.0,
+        "sell_quantity": 0, "sell_price": 0.0, "sell_value": 0.0, "sell_m2m": 0.0,
+        "day_buy_quantity": 0, "day_buy_price": 0.0, "day_buy_value": 0.0,
+        "day_sell_quantity": 0, "day_sell_price": 0.0, "day_sell_value": 0.0
+    }))
+    .unwrap()
+}
+
+#[test]
+fn test_roll_position_resolves_next_expiry() {
+    let store = store();
+    let next = NaiveDate::from_ymd_opt(2024, 7, 25).unwrap();
+
+    let roll = roll_position(&nfo_position("NIFTY24JUNFUT", 3001, 50), &store, next).unwrap();
+    assert_eq!(roll.to.tradingsymbol, "NIFTY24JULFUT");
+    assert_eq!(roll.close.tradingsymbol.as_deref(), Some("NIFTY24JUNFUT"));
+    assert_eq!(roll.close.transaction_type.as_deref(), Some("SELL"));
+    assert_eq!(roll.open.transaction_type.as_deref(), Some("BUY"));
+    assert_eq!(roll.open.quantity, Some(50));
+    assert_eq!(roll.open.product.as_deref(), Some("NRML"));
+
+    let short = nfo_position("NIFTY24JUN22000CE", 0, -25);
+    let roll = roll_position(&short, &store, next).unwrap();
+    assert_eq!(roll.to.tradingsymbol, "NIFTY24JUL22000CE");
+    assert_eq!(roll.close.transaction_type.as_deref(), Some("BUY"));
+    assert_eq!(roll.open.transaction_type.as_deref(), Some("SELL"));
+
+    // No July contract at this strike, a flat position and a backwards roll
+    assert!(roll_position(&nfo_position("NIFTY24JUN22100CE", 0, 25), &store, next).is_err());
+    assert!(roll_position(&nfo_position("NIFTY24JUNFUT", 3001, 0), &store, next).is_err());
+    assert!(roll_position(&nfo_position("NIFTY24JUNFUT", 3001, 25), &store, expiry()).is_err());
+}
+
+#[tokio::test]
+async fn test_execute_roll_with_slippage_limit() {
+    let store = store();
+    let next = NaiveDate::from_ymd_opt(2024, 7, 25).unwrap();
+    let roll = roll_position(&nfo_position("NIFTY24JUNFUT", 3001, 25), &store, next).unwrap();
+
+    let server = KiteMockServer::new().await;
+    server
+        .endpoint("GET", "/quote/ltp")
+        .data(json!({
+            "NFO:NIFTY24JUNFUT": {"instrument_token": 3001, "last_price": 22000.0},
+            "NFO:NIFTY24JULFUT": {"instrument_token": 3002, "last_price": 22150.0}
+        }))
+        .mount()
+        .await;
+    server
+        .endpoint("POST", "/orders/regular")
+        .data(json!({"order_id": "555"}))
+        .mount()
+        .await;
+    let kite = server.client();
+
+    let result = kite.execute_roll(&roll, Some(0.1)).await.unwrap();
+    assert!(result.is_ok());
+
+    let placed = server.received("POST", "/orders/regular").await;
+    assert_eq!(placed.len(), 2);
+    assert_eq!(placed[0].form()["tradingsymbol"], "NIFTY24JUNFUT");
+    assert_eq!(placed[0].form()["order_type"], "LIMIT");
+    assert_eq!(placed[0].form()["price"], "21978.0");
+    assert_eq!(placed[1].form()["tradingsymbol"], "NIFTY24JULFUT");
+    assert_eq!(placed[1].form()["price"], "22172.15");
+}
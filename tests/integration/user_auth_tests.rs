@@ -1,4 +1,4 @@
-use kiteconnect_rs::KiteConnect;
+use kiteconnect_rs::{ConfigError, KiteConnect, KiteConnectErrorKind, LoginCallback};
 use std::time::Duration;
 
 use super::mock_server::KiteMockServer;
@@ -300,3 +300,505 @@ fn test_login_url_generation() {
     assert!(login_url.contains("test_api_key"));
     assert!(login_url.contains("v=3"));
 }
+
+#[test]
+fn test_login_url_with_state_round_trip() {
+    let kite = KiteConnect::builder("test_api_key")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let login_url = kite.get_login_url_with_params(&[("state", "abc 123"), ("tenant", "t1")]);
+    assert!(login_url.contains("redirect_params=state%3Dabc%2B123%26tenant%3Dt1"));
+
+    let callback = LoginCallback::parse(
+        "https://example.com/callback?action=login&type=login&status=success&request_token=tok123&state=abc+123&tenant=t1",
+    )
+    .expect("Failed to parse callback");
+    assert_eq!(callback.request_token, "tok123");
+    assert_eq!(callback.state.as_deref(), Some("abc 123"));
+    assert_eq!(
+        callback.params.get("tenant").map(String::as_str),
+        Some("t1")
+    );
+
+    assert!(LoginCallback::parse("status=cancelled&request_token=tok").is_err());
+    assert!(LoginCallback::parse("https://example.com/callback?status=success").is_err());
+}
+
+#[test]
+fn test_builder_validation() {
+    let err = KiteConnect::builder("").build().err().unwrap();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::InvalidConfig(ConfigError::EmptyApiKey)
+    ));
+
+    let err = KiteConnect::builder("bad key").build().err().unwrap();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::InvalidConfig(ConfigError::InvalidApiKey(_))
+    ));
+
+    let err = KiteConnect::builder("test_api_key")
+        .base_url("not a url")
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::InvalidConfig(ConfigError::InvalidBaseUrl(_))
+    ));
+
+    let err = KiteConnect::builder("test_api_key")
+        .base_url("ftp://api.kite.trade")
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::InvalidConfig(ConfigError::InvalidBaseUrl(_))
+    ));
+
+    for timeout in [Duration::ZERO, Duration::from_micros(500)] {
+        let err = KiteConnect::builder("test_api_key")
+            .timeout(timeout)
+            .build()
+            .err()
+            .unwrap();
+        assert!(matches!(
+            err.kind,
+            KiteConnectErrorKind::InvalidConfig(ConfigError::InvalidTimeout(_))
+        ));
+    }
+    assert!(
+        KiteConnect::builder("test_api_key")
+            .timeout(Duration::from_millis(1))
+            .build()
+            .is_ok()
+    );
+
+    let err = KiteConnect::builder("test_api_key")
+        .access_token("")
+        .build()
+        .err()
+        .unwrap();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::InvalidConfig(ConfigError::EmptyAccessToken)
+    ));
+}
+
+#[tokio::test]
+async fn test_validate_credentials_without_token() {
+    let kite = KiteConnect::builder("test_api_key")
+        .build()
+        .expect("Failed to build KiteConnect client");
+
+    let err = kite.validate_credentials().await.err().unwrap();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::InvalidConfig(ConfigError::EmptyAccessToken)
+    ));
+}
+
+#[tokio::test]
+async fn test_restore_session_from_store() {
+    use kiteconnect_rs::session::{MemorySessionStore, SessionStore};
+    use kiteconnect_rs::users::UserSessionTokens;
+    use serde_json::json;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({"net": [], "day": []}))
+        .mount()
+        .await;
+    let mut kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .build()
+        .unwrap();
+
+    let store = MemorySessionStore::new();
+    assert!(!kite.restore_session(&store).await.unwrap());
+
+    store
+        .save(&UserSessionTokens {
+            user_id: "AB1234".to_string(),
+            access_token: "restored_token".to_string(),
+            refresh_token: String::new(),
+        })
+        .await
+        .unwrap();
+    assert!(kite.restore_session(&store).await.unwrap());
+    kite.get_positions().await.unwrap();
+
+    let request = mock_server
+        .received_one("GET", "/portfolio/positions")
+        .await;
+    assert_eq!(
+        request.headers.get("authorization").map(String::as_str),
+        Some("token test_api_key:restored_token")
+    );
+}
+
+#[tokio::test]
+async fn test_generate_session_uses_compute_checksum() {
+    use kiteconnect_rs::users::{compute_checksum, verify_session_response};
+    use serde_json::json;
+
+    let checksum = compute_checksum("test_api_key", "test_request_token", "test_api_secret");
+    assert_eq!(
+        checksum,
+        "db7982386217016217ea3d380e90d51dc63978441969b4f101ec3302f80bd06d"
+    );
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("POST", "/session/token")
+        .data(json!({
+            "user_id": "AB1234",
+            "user_name": "Test User",
+            "user_shortname": "Test",
+            "avatar_url": null,
+            "user_type": "individual",
+            "email": "test@example.com",
+            "broker": "ZERODHA",
+            "meta": {"demat_consent": "consent"},
+            "products": [],
+            "order_types": [],
+            "exchanges": [],
+            "access_token": "session_token",
+            "refresh_token": "",
+            "api_key": "test_api_key",
+            "public_token": "public",
+            "login_time": "2024-01-15 09:00:00"
+        }))
+        .mount()
+        .await;
+    let mut kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .build()
+        .unwrap();
+
+    let session = kite
+        .generate_session("test_request_token", "test_api_secret")
+        .await
+        .unwrap();
+    let form = mock_server
+        .received_one("POST", "/session/token")
+        .await
+        .form();
+    assert_eq!(form.get("checksum"), Some(&checksum));
+
+    assert!(verify_session_response("test_api_key", &session).is_ok());
+    let err = verify_session_response("other_api_key", &session).unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+    let mut empty = session.clone();
+    empty.access_token.clear();
+    assert!(verify_session_response("test_api_key", &empty).is_err());
+}
+
+#[tokio::test]
+async fn test_token_provider_refetches_after_token_exception() {
+    use async_trait::async_trait;
+    use kiteconnect_rs::KiteConnectError;
+    use kiteconnect_rs::session::TokenProvider;
+    use serde_json::json;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingProvider(AtomicUsize);
+
+    #[async_trait]
+    impl TokenProvider for CountingProvider {
+        async fn access_token(&self) -> Result<String, KiteConnectError> {
+            Ok(format!(
+                "token_{}",
+                self.0.fetch_add(1, Ordering::SeqCst) + 1
+            ))
+        }
+    }
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .data(json!({"net": [], "day": []}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/user/profile")
+        .token_exception()
+        .mount()
+        .await;
+    let kite = KiteConnect::builder("test_api_key")
+        .base_url(&mock_server.base_url)
+        .build()
+        .unwrap()
+        .with_token_provider(CountingProvider(AtomicUsize::new(0)));
+
+    kite.get_positions().await.unwrap();
+    kite.get_positions().await.unwrap();
+    assert!(kite.get_user_profile().await.is_err());
+    kite.get_positions().await.unwrap();
+
+    let authorizations: Vec<Option<String>> = mock_server
+        .received("GET", "/portfolio/positions")
+        .await
+        .iter()
+        .map(|r| r.headers.get("authorization").cloned())
+        .collect();
+    assert_eq!(
+        authorizations,
+        vec![
+            Some("token test_api_key:token_1".to_string()),
+            Some("token test_api_key:token_1".to_string()),
+            Some("token test_api_key:token_2".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_logout_scheduler_shutdown() {
+    use kiteconnect_rs::session::{
+        LogoutConfig, LogoutReason, LogoutScheduler, MemorySessionStore, SessionEvent, SessionStore,
+    };
+    use kiteconnect_rs::users::UserSessionTokens;
+    use std::sync::Arc;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("DELETE", "/session/token")
+        .data(serde_json::json!(true))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/user/profile")
+        .token_exception()
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let store = Arc::new(MemorySessionStore::new());
+    store
+        .save(&UserSessionTokens {
+            user_id: "AB1234".to_string(),
+            access_token: "test_access_token".to_string(),
+            refresh_token: String::new(),
+        })
+        .await
+        .unwrap();
+
+    let scheduler = LogoutScheduler::spawn(
+        kite.clone(),
+        LogoutConfig::default().clear_store(store.clone()),
+    );
+    let events = scheduler.subscribe_events();
+    assert!(scheduler.next_logout_at() > chrono::Utc::now());
+
+    assert!(scheduler.shutdown().await.unwrap());
+    assert_eq!(
+        events.recv().await.unwrap(),
+        SessionEvent::LoggedOut {
+            reason: LogoutReason::Shutdown,
+            invalidated: true,
+        }
+    );
+    assert!(store.load().await.unwrap().is_none());
+    mock_server.received_one("DELETE", "/session/token").await;
+
+    // The clone handed to the scheduler shared the token, so this one is logged out too
+    let _ = kite.get_user_profile().await;
+    let request = mock_server.received_one("GET", "/user/profile").await;
+    let authorization = request.headers.get("authorization");
+    assert!(authorization.is_none_or(|h| !h.contains("test_access_token")));
+}
+
+#[tokio::test]
+async fn test_logout_clears_token_when_invalidation_fails() {
+    use kiteconnect_rs::session::{
+        LogoutConfig, LogoutReason, LogoutScheduler, MemorySessionStore, SessionEvent, SessionStore,
+    };
+    use kiteconnect_rs::users::UserSessionTokens;
+    use std::sync::Arc;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("DELETE", "/session/token")
+        .error(500, "GeneralException", "Session service unavailable")
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/user/profile")
+        .token_exception()
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let store = Arc::new(MemorySessionStore::new());
+    store
+        .save(&UserSessionTokens {
+            user_id: "AB1234".to_string(),
+            access_token: "test_access_token".to_string(),
+            refresh_token: String::new(),
+        })
+        .await
+        .unwrap();
+
+    let scheduler = LogoutScheduler::spawn(
+        kite.clone(),
+        LogoutConfig::default().clear_store(store.clone()),
+    );
+    let events = scheduler.subscribe_events();
+
+    // Kite refusing to invalidate the token is reported, not raised
+    assert!(!scheduler.shutdown().await.unwrap());
+    assert_eq!(
+        events.recv().await.unwrap(),
+        SessionEvent::LoggedOut {
+            reason: LogoutReason::Shutdown,
+            invalidated: false,
+        }
+    );
+    assert!(store.load().await.unwrap().is_none());
+    let _ = kite.get_user_profile().await;
+    let request = mock_server.received_one("GET", "/user/profile").await;
+    let authorization = request.headers.get("authorization");
+    assert!(authorization.is_none_or(|h| !h.contains("test_access_token")));
+}
+
+#[test]
+fn test_next_logout_after() {
+    use chrono::{NaiveTime, TimeZone};
+    use chrono_tz::Asia::Kolkata;
+    use kiteconnect_rs::session::next_logout_after;
+
+    let at = NaiveTime::from_hms_opt(23, 30, 0).unwrap();
+    let morning = Kolkata.with_ymd_and_hms(2024, 6, 3, 10, 0, 0).unwrap();
+    assert_eq!(
+        next_logout_after(morning, at),
+        Kolkata.with_ymd_and_hms(2024, 6, 3, 23, 30, 0).unwrap()
+    );
+    let late = Kolkata.with_ymd_and_hms(2024, 6, 3, 23, 30, 0).unwrap();
+    assert_eq!(
+        next_logout_after(late, at),
+        Kolkata.with_ymd_and_hms(2024, 6, 4, 23, 30, 0).unwrap()
+    );
+}
+
+#[tokio::test]
+async fn test_capabilities_from_profile_and_probe() {
+    use chrono::NaiveDate;
+    use futures_util::StreamExt;
+    use kiteconnect_rs::Capabilities;
+    use kiteconnect_rs::orders::OrderParams;
+    use kiteconnect_rs::pagination::PageParams;
+    use serde_json::json;
+
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/user/profile")
+        .data(json!({
+            "user_id": "AB1234", "user_name": "AxAx Bxx", "user_shortname": "AxAx",
+            "avatar_url": null, "user_type": "individual", "email": "xxxyyy@gmail.com",
+            "broker": "ZERODHA", "meta": {"demat_consent": "physical"},
+            "products": ["CNC", "MIS"], "order_types": ["MARKET", "LIMIT"],
+            "exchanges": ["NSE", "BSE", "MF"]
+        }))
+        .expect(2)
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/mf/orders")
+        .error(
+            403,
+            "PermissionException",
+            "Insufficient permission for that call.",
+        )
+        .mount()
+        .await;
+    let kite = mock_server.client();
+    assert!(kite.known_capabilities().is_none());
+
+    let capabilities = kite.capabilities().await.unwrap();
+    assert!(capabilities.orders && capabilities.equity && capabilities.mutual_funds);
+    assert!(!capabilities.derivatives && !capabilities.commodity && !capabilities.currency);
+    assert!(!capabilities.probed);
+    // Cached for clones, so the profile is fetched once
+    assert_eq!(kite.clone().capabilities().await.unwrap(), capabilities);
+
+    let probed = kite.probe_capabilities().await.unwrap();
+    assert!(probed.probed && probed.orders);
+    assert!(!probed.mutual_funds);
+    let err = probed.require_mutual_funds().unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::MissingCapability(_)
+    ));
+
+    // Orders on a segment the account lacks fail before reaching the API
+    let params = OrderParams {
+        exchange: Some("NFO".to_string()),
+        tradingsymbol: Some("NIFTY24JUNFUT".to_string()),
+        transaction_type: Some("BUY".to_string()),
+        order_type: Some("MARKET".to_string()),
+        product: Some("MIS".to_string()),
+        quantity: Some(50),
+        ..Default::default()
+    };
+    let err = kite.place_order("regular", params).await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::MissingCapability(_)
+    ));
+    assert!(err.to_string().contains("exchange NFO"));
+    assert!(
+        mock_server
+            .received("POST", "/orders/regular")
+            .await
+            .is_empty()
+    );
+
+    // So do MF calls without MF access, and modifications and cancellations without
+    // order access
+    let err = kite.get_mf_holdings().await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::MissingCapability(_)
+    ));
+    let mut mf_orders = Box::pin(kite.get_mf_orders_paginated(PageParams::new(
+        NaiveDate::from_ymd_opt(2024, 1, 1).unwrap(),
+        NaiveDate::from_ymd_opt(2024, 3, 31).unwrap(),
+    )));
+    assert!(mf_orders.next().await.unwrap().is_err());
+    assert!(mf_orders.next().await.is_none());
+    assert_eq!(mock_server.received("GET", "/mf/orders").await.len(), 1);
+
+    kite.set_capabilities(Some(Capabilities {
+        orders: false,
+        ..probed
+    }));
+    let err = kite
+        .modify_order("regular", "1", OrderParams::default())
+        .await
+        .unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::MissingCapability(_)
+    ));
+    let err = kite.cancel_order("regular", "1", None).await.unwrap_err();
+    assert!(matches!(
+        err.kind,
+        KiteConnectErrorKind::MissingCapability(_)
+    ));
+    assert!(mock_server.received("GET", "/orders/1").await.is_empty());
+    assert!(
+        mock_server
+            .received("DELETE", "/orders/regular/1")
+            .await
+            .is_empty()
+    );
+}
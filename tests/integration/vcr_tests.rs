@@ -0,0 +1,81 @@
+use kiteconnect_rs::KiteConnect;
+use kiteconnect_rs::transport::HttpTransport;
+use kiteconnect_rs::vcr::{REDACTED, Recorder, Replayer};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+use super::mock_server::KiteMockServer;
+
+#[tokio::test]
+async fn test_record_and_replay_cassette() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/quote/ltp")
+        .data(json!({"NSE:INFY": {"instrument_token": 408065, "last_price": 1500.5}}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/user/profile")
+        .data(json!({"user_id": "AB1234", "email": "trader@example.com", "broker": "ZERODHA"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/session/token")
+        .data(json!({"access_token": "live-secret", "login_time": "2024-06-27 09:00:00"}))
+        .mount()
+        .await;
+
+    let recorder = Recorder::new(HttpTransport::default());
+    let kite = KiteConnect::builder("real_api_key")
+        .base_url(&mock_server.base_url)
+        .access_token("real_access_token")
+        .transport(recorder.clone())
+        .build()
+        .unwrap();
+    let ltp = kite.get_ltp(&["NSE:INFY"]).await.unwrap();
+    assert_eq!(ltp["NSE:INFY"].last_price, 1500.5);
+    let profile: Value = kite.get("/user/profile").await.unwrap();
+    // Recording does not alter what the caller sees
+    assert_eq!(profile["user_id"], "AB1234");
+    let mut form = HashMap::new();
+    form.insert("api_key", "real_api_key");
+    form.insert("request_token", "real_request_token");
+    let _: Value = kite.post_form("/session/token", form).await.unwrap();
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("cassettes/session.json");
+    recorder.save(&path).unwrap();
+
+    let saved = std::fs::read_to_string(&path).unwrap();
+    for secret in [
+        "real_api_key",
+        "real_access_token",
+        "real_request_token",
+        "live-secret",
+        "AB1234",
+        "trader@example.com",
+    ] {
+        assert!(!saved.contains(secret), "{} leaked into {}", secret, saved);
+    }
+    assert!(saved.contains(REDACTED));
+    assert!(saved.contains("ZERODHA"));
+    drop(mock_server);
+
+    let replayer = std::sync::Arc::new(Replayer::load(&path).unwrap());
+    let mut kite = KiteConnect::builder("test_api_key").build().unwrap();
+    kite.set_transport(Some(replayer.clone()));
+
+    let ltp = kite.get_ltp(&["NSE:INFY"]).await.unwrap();
+    assert_eq!(ltp["NSE:INFY"].instrument_token, 408065);
+    let profile: Value = kite.get("/user/profile").await.unwrap();
+    assert_eq!(profile["user_id"], REDACTED);
+    assert_eq!(profile["broker"], "ZERODHA");
+    assert!(!replayer.all_served());
+    let session: Value = kite.post("/session/token").await.unwrap();
+    assert_eq!(session["access_token"], REDACTED);
+    assert_eq!(session["login_time"], "2024-06-27 09:00:00");
+    assert!(replayer.all_served());
+
+    let err = kite.get_ltp(&["NSE:TCS"]).await.unwrap_err();
+    assert!(err.to_string().contains("no interaction"), "{}", err);
+}
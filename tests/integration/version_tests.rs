@@ -0,0 +1,62 @@
+use kiteconnect_rs::{ApiVersion, KiteConnectError, ResponseAdapter};
+use serde_json::{Value, json};
+
+use crate::integration::mock_server::KiteMockServer;
+
+// A made-up v4 that returns the payload under "result" instead of "data"
+struct ResultEnvelope;
+
+impl ResponseAdapter for ResultEnvelope {
+    fn adapt(
+        &self,
+        _endpoint: &str,
+        _status: u16,
+        body: String,
+    ) -> Result<String, KiteConnectError> {
+        let mut value: Value = serde_json::from_str(&body)
+            .map_err(|e| KiteConnectError::other(format!("bad v4 body: {}", e)))?;
+        let data = value["result"].take();
+        Ok(json!({"status": "success", "data": data}).to_string())
+    }
+}
+
+#[tokio::test]
+async fn test_api_version_overrides_and_adapter() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/portfolio/positions")
+        .body(json!({"result": {"net": [], "day": []}}).to_string())
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/orders")
+        .data(json!([]))
+        .mount()
+        .await;
+
+    let mut kite = mock_server
+        .client()
+        .with_response_adapter(ApiVersion::new(4), ResultEnvelope);
+    assert_eq!(kite.api_version(), ApiVersion::V3);
+    kite.set_endpoint_api_version("/portfolio", Some(ApiVersion::new(4)));
+    assert_eq!(
+        kite.api_version_for("/portfolio/positions"),
+        ApiVersion::new(4)
+    );
+    assert_eq!(kite.api_version_for("/orders"), ApiVersion::V3);
+
+    let positions = kite.get_positions().await.unwrap();
+    assert!(positions.net.is_empty());
+    kite.get_orders().await.unwrap();
+
+    let positions = mock_server
+        .received_one("GET", "/portfolio/positions")
+        .await;
+    assert_eq!(positions.headers["x-kite-version"], "4");
+    let orders = mock_server.received_one("GET", "/orders").await;
+    assert_eq!(orders.headers["x-kite-version"], "3");
+
+    kite.set_endpoint_api_version("/portfolio", None);
+    assert_eq!(kite.api_version_for("/portfolio/positions"), ApiVersion::V3);
+    assert!(kite.get_login_url().ends_with("&v=3"));
+}
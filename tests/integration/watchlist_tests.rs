@@ -0,0 +1,122 @@
+use kiteconnect_rs::KiteConnectErrorKind;
+use kiteconnect_rs::watchlists::WatchlistItemParams;
+use serde_json::json;
+
+use super::mock_server::KiteMockServer;
+
+fn watchlist_json() -> serde_json::Value {
+    json!({
+        "id": 101,
+        "name": "Banks",
+        "items": [
+            {"id": 2, "instrument_token": 1270529, "exchange": "NSE", "tradingsymbol": "ICICIBANK", "segment": "NSE", "weight": 2},
+            {"id": 1, "instrument_token": 341249, "exchange": "NSE", "tradingsymbol": "HDFCBANK", "segment": "NSE", "weight": 1}
+        ]
+    })
+}
+
+#[tokio::test]
+async fn test_watchlist_crud() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/marketwatch")
+        .data(json!([watchlist_json()]))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/marketwatch")
+        .data(json!({"id": 102, "name": "Momentum", "items": []}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("PUT", "/marketwatch/102")
+        .data(json!({"id": 102, "name": "Breakouts"}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("POST", "/marketwatch/102/items")
+        .data(json!({"id": 7, "instrument_token": 408065, "exchange": "NSE", "tradingsymbol": "INFY", "segment": "NSE", "weight": 1}))
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/marketwatch/102/items/7")
+        .data(json!(true))
+        .expect(1)
+        .mount()
+        .await;
+    mock_server
+        .endpoint("DELETE", "/marketwatch/102")
+        .data(json!(true))
+        .expect(1)
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let watchlists = kite.get_watchlists().await.unwrap();
+    assert_eq!(watchlists.len(), 1);
+    assert_eq!(
+        watchlists[0].instrument_keys(),
+        vec!["NSE:HDFCBANK", "NSE:ICICIBANK"]
+    );
+    assert_eq!(watchlists[0].instrument_tokens(), vec![1270529, 341249]);
+
+    let created = kite.create_watchlist("Momentum").await.unwrap();
+    assert_eq!(created.id, 102);
+    let form = mock_server
+        .received_one("POST", "/marketwatch")
+        .await
+        .form();
+    assert_eq!(form.get("name").map(String::as_str), Some("Momentum"));
+
+    let renamed = kite.rename_watchlist(102, "Breakouts").await.unwrap();
+    assert_eq!(renamed.name, "Breakouts");
+    assert!(renamed.items.is_empty());
+
+    let item = kite
+        .add_watchlist_item(
+            102,
+            WatchlistItemParams {
+                exchange: "NSE".to_string(),
+                tradingsymbol: "INFY".to_string(),
+                weight: None,
+            },
+        )
+        .await
+        .unwrap();
+    assert_eq!(item.instrument_token, 408065);
+    let form = mock_server
+        .received_one("POST", "/marketwatch/102/items")
+        .await
+        .form();
+    assert_eq!(form.get("tradingsymbol").map(String::as_str), Some("INFY"));
+    assert!(!form.contains_key("weight"));
+
+    kite.delete_watchlist_item(102, 7).await.unwrap();
+    kite.delete_watchlist(102).await.unwrap();
+
+    let err = kite.create_watchlist("  ").await.unwrap_err();
+    assert!(matches!(err.kind, KiteConnectErrorKind::InvalidParams(_)));
+}
+
+#[tokio::test]
+async fn test_get_watchlist_quotes() {
+    let mock_server = KiteMockServer::new().await;
+    mock_server
+        .endpoint("GET", "/marketwatch/101")
+        .data(watchlist_json())
+        .mount()
+        .await;
+    mock_server
+        .endpoint("GET", "/quote")
+        .data(json!({}))
+        .expect(1)
+        .mount()
+        .await;
+    let kite = mock_server.client();
+
+    let quotes = kite.get_watchlist_quotes(101).await.unwrap();
+    assert!(quotes.is_empty());
+
+    let request = mock_server.received_one("GET", "/quote").await;
+    assert!(request.query.contains_key("i"));
+}
@@ -0,0 +1,97 @@
+use chrono::{TimeZone, Utc};
+use kiteconnect_rs::order_state::{OrderPhase, OrderState, OrderStateEvent, OrderStateMachine};
+use kiteconnect_rs::test_utils::MockDataGenerator;
+
+fn state(status: &str, filled: f64, second: u32) -> OrderState {
+    OrderState {
+        order_id: "240115000000001".to_string(),
+        status: status.to_string(),
+        quantity: 10.0,
+        filled_quantity: filled,
+        pending_quantity: 10.0 - filled,
+        cancelled_quantity: 0.0,
+        price: 1500.0,
+        trigger_price: 0.0,
+        average_price: if filled > 0.0 { 1500.0 } else { 0.0 },
+        updated_at: Some(Utc.with_ymd_and_hms(2024, 1, 15, 4, 0, second).unwrap()),
+    }
+}
+
+#[test]
+fn test_order_state_machine_orders_updates() {
+    let mut machine = OrderStateMachine::new();
+
+    assert!(matches!(
+        machine.apply(state("OPEN PENDING", 0.0, 0)),
+        Some(OrderStateEvent::New { .. })
+    ));
+    let event = machine.apply(state("OPEN", 0.0, 1)).unwrap();
+    assert_eq!(
+        event,
+        OrderStateEvent::Changed {
+            from: "OPEN PENDING".to_string(),
+            state: state("OPEN", 0.0, 1),
+        }
+    );
+
+    // The same update from the poll, and a stale one from before it
+    assert!(machine.apply(state("OPEN", 0.0, 1)).is_none());
+    assert!(machine.apply(state("OPEN PENDING", 0.0, 0)).is_none());
+
+    // A partial fill, then a poll still showing the order unfilled but with a later time
+    assert!(machine.apply(state("OPEN", 4.0, 2)).is_some());
+    assert!(machine.apply(state("OPEN", 0.0, 3)).is_none());
+
+    let event = machine.apply(state("COMPLETE", 10.0, 4)).unwrap();
+    assert!(!event.is_gap());
+    assert_eq!(event.state().phase(), OrderPhase::Terminal);
+
+    // A completed order cannot be cancelled
+    let event = machine.apply(state("CANCELLED", 10.0, 5)).unwrap();
+    assert!(event.is_gap());
+    assert_eq!(
+        machine.state("240115000000001").unwrap().status,
+        "CANCELLED"
+    );
+
+    let stats = machine.stats();
+    assert_eq!((stats.duplicates, stats.stale, stats.gaps), (1, 2, 1));
+
+    machine.clear_terminal();
+    assert_eq!(machine.states().count(), 0);
+}
+
+#[test]
+fn test_order_state_machine_phase_regressions() {
+    let mut machine = OrderStateMachine::new();
+    machine.apply(state("TRIGGER PENDING", 0.0, 0));
+    assert_eq!(
+        machine.state("240115000000001").unwrap().phase(),
+        OrderPhase::Working
+    );
+
+    // The stop-loss triggered
+    assert!(machine.apply(state("OPEN", 0.0, 1)).is_some());
+
+    // An earlier phase with the same exchange time is a late copy; with a later one,
+    // the order really went back
+    assert!(machine.apply(state("OPEN PENDING", 0.0, 1)).is_none());
+    let event = machine.apply(state("OPEN PENDING", 0.0, 2)).unwrap();
+    assert!(matches!(event, OrderStateEvent::Changed { .. }));
+    assert_eq!(machine.stats().stale, 1);
+}
+
+#[test]
+fn test_order_state_machine_applies_polls_oldest_first() {
+    let mut generator = MockDataGenerator::new(7);
+    let first = generator.order(408065, "INFY");
+    let second = generator.order(408065, "INFY");
+
+    let mut machine = OrderStateMachine::new();
+    let events = machine.apply_orders(&[second.clone(), first.clone()]);
+    let ids: Vec<_> = events.iter().map(|e| e.state().order_id.as_str()).collect();
+    assert_eq!(ids, [first.order_id.as_str(), second.order_id.as_str()]);
+
+    assert!(machine.apply_orders(&[first, second]).is_empty());
+    assert_eq!(machine.stats().duplicates, 2);
+}
@@ -0,0 +1,72 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use kiteconnect_rs::Ticker;
+use proptest::prelude::*;
+
+// Valid packet lengths for ltp, quote index, full index, quote and full modes
+const PACKET_LENGTHS: [usize; 5] = [8, 28, 32, 44, 184];
+
+fn packet() -> impl Strategy<Value = Vec<u8>> {
+    prop::sample::select(PACKET_LENGTHS.to_vec())
+        .prop_flat_map(|len| prop::collection::vec(any::<u8>(), len))
+}
+
+fn frame(packets: &[Vec<u8>]) -> Vec<u8> {
+    let mut data = (packets.len() as u16).to_be_bytes().to_vec();
+    for packet in packets {
+        data.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+        data.extend_from_slice(packet);
+    }
+    data
+}
+
+proptest! {
+    #[test]
+    fn parse_binary_never_panics(data in prop::collection::vec(any::<u8>(), 0..1024)) {
+        let _ = Ticker::parse_binary(&data);
+    }
+
+    #[test]
+    fn parse_packet_never_panics(data in prop::collection::vec(any::<u8>(), 0..256)) {
+        let _ = Ticker::parse_packet(&data);
+    }
+
+    #[test]
+    fn split_packets_respects_lengths(data in prop::collection::vec(any::<u8>(), 0..1024)) {
+        let packets = Ticker::split_packets(&data);
+        let consumed: usize = packets.iter().map(|p| p.len() + 2).sum();
+        prop_assert!(consumed + 2 <= data.len().max(2));
+        if data.len() >= 2 {
+            let declared = u16::from_be_bytes([data[0], data[1]]) as usize;
+            prop_assert!(packets.len() <= declared);
+        }
+    }
+
+    #[test]
+    fn well_formed_frames_parse(packets in prop::collection::vec(packet(), 0..8)) {
+        let ticks = Ticker::parse_binary(&frame(&packets)).unwrap();
+        prop_assert_eq!(ticks.len(), packets.len());
+
+        for (tick, packet) in ticks.iter().zip(&packets) {
+            let token = u32::from_be_bytes([packet[0], packet[1], packet[2], packet[3]]);
+            prop_assert_eq!(tick.instrument_token, token);
+            prop_assert!(tick.last_price >= 0.0);
+            prop_assert_eq!(tick.is_index, !tick.is_tradable);
+            for item in tick.depth.buy.iter().chain(tick.depth.sell.iter()) {
+                prop_assert!(item.price >= 0.0);
+            }
+        }
+    }
+
+    #[test]
+    fn truncated_frames_never_yield_partial_packets(
+        packets in prop::collection::vec(packet(), 1..8),
+        cut in any::<prop::sample::Index>(),
+    ) {
+        let data = frame(&packets);
+        let truncated = &data[..cut.index(data.len())];
+        for packet in Ticker::split_packets(truncated) {
+            prop_assert!(PACKET_LENGTHS.contains(&packet.len()));
+        }
+    }
+}
@@ -0,0 +1,69 @@
+use kiteconnect_rs::KiteConnect;
+use kiteconnect_rs::labels::{Exchange, OrderType, Product, TransactionType, Variety};
+use kiteconnect_rs::orders::OrderParams;
+use kiteconnect_rs::publisher::Basket;
+use serde_json::{Value, json};
+
+fn order(symbol: &str, quantity: i32) -> OrderParams {
+    OrderParams::builder()
+        .exchange(Exchange::Nse)
+        .tradingsymbol(symbol)
+        .transaction_type(TransactionType::Buy)
+        .order_type(OrderType::Limit)
+        .product(Product::Cnc)
+        .quantity(quantity)
+        .price(1500.0)
+        .build()
+        .unwrap()
+}
+
+#[test]
+fn test_basket_payload() {
+    let kite = KiteConnect::builder("test_api_key").build().unwrap();
+    let mut basket = kite.publisher_basket();
+    basket
+        .add(Variety::Regular, order("INFY", 10))
+        .unwrap()
+        .add_readonly(Variety::Amo, order("TCS", 2))
+        .unwrap();
+
+    let payload: Value = serde_json::from_str(&basket.payload().unwrap()).unwrap();
+    assert_eq!(
+        payload[0],
+        json!({
+            "variety": "regular", "exchange": "NSE", "tradingsymbol": "INFY",
+            "transaction_type": "BUY", "order_type": "LIMIT", "product": "CNC",
+            "quantity": 10, "price": 1500.0, "readonly": false
+        })
+    );
+    assert_eq!(payload[1]["variety"], "amo");
+    assert_eq!(payload[1]["readonly"], true);
+
+    let fields = basket.form_fields().unwrap();
+    assert_eq!(fields[0], ("api_key", "test_api_key".to_string()));
+    assert_eq!(fields[1].0, "data");
+    assert_eq!(basket.url(), "https://kite.zerodha.com/connect/basket");
+}
+
+#[test]
+fn test_basket_validation_and_html_form() {
+    let mut basket = Basket::new("test_api_key");
+    assert!(basket.form_fields().is_err());
+
+    let mut no_quantity = order("INFY", 1);
+    no_quantity.quantity = Some(0);
+    assert!(basket.add(Variety::Regular, no_quantity).is_err());
+    let mut no_side = order("INFY", 1);
+    no_side.transaction_type = None;
+    assert!(basket.add(Variety::Regular, no_side).is_err());
+    assert!(basket.is_empty());
+
+    let mut tagged = order("INFY", 1);
+    tagged.tag = Some("a\"<b>".to_string());
+    basket.add(Variety::Regular, tagged).unwrap();
+    let html = basket.html_form().unwrap();
+    assert!(html.contains("action=\"https://kite.zerodha.com/connect/basket\""));
+    assert!(html.contains("name=\"api_key\" value=\"test_api_key\""));
+    assert!(html.contains("&quot;tag&quot;:&quot;a\\&quot;&lt;b&gt;&quot;"));
+    assert!(!html.contains("<b>"));
+}
@@ -0,0 +1,79 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+// Installs a process-wide runtime, so it lives in its own test binary.
+
+use async_trait::async_trait;
+use kiteconnect_rs::compat::{
+    self, BoxFuture, Runtime, TaskHandle, TokioRuntime, WebSocketStream, WsConnectOptions, WsError,
+};
+use kiteconnect_rs::{TickerBuilder, TickerEvent};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+#[derive(Default)]
+struct Counts {
+    spawns: AtomicUsize,
+    sleeps: AtomicUsize,
+    connects: AtomicUsize,
+}
+
+// Runs on tokio underneath but counts every call, standing in for an async-std or smol runtime.
+struct CountingRuntime(Arc<Counts>);
+
+#[async_trait]
+impl Runtime for CountingRuntime {
+    fn spawn(&self, future: BoxFuture<'static, ()>) -> TaskHandle {
+        self.0.spawns.fetch_add(1, Ordering::SeqCst);
+        let handle = tokio::spawn(future);
+        TaskHandle::new(move || handle.abort())
+    }
+
+    async fn sleep(&self, duration: Duration) {
+        self.0.sleeps.fetch_add(1, Ordering::SeqCst);
+        tokio::time::sleep(duration).await;
+    }
+
+    async fn connect_ws(
+        &self,
+        url: &str,
+        options: &WsConnectOptions,
+    ) -> Result<Box<dyn WebSocketStream>, WsError> {
+        self.0.connects.fetch_add(1, Ordering::SeqCst);
+        TokioRuntime.connect_ws(url, options).await
+    }
+}
+
+#[tokio::test]
+async fn test_ticker_uses_installed_runtime() {
+    let counts = Arc::new(Counts::default());
+    compat::set_runtime(CountingRuntime(counts.clone())).unwrap();
+    assert!(compat::set_runtime(CountingRuntime(counts.clone())).is_err());
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let _ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let connected = tokio::time::timeout(Duration::from_secs(5), events.recv()).await;
+    assert!(matches!(connected, Ok(Ok(TickerEvent::Connect))));
+    compat::sleep(Duration::from_millis(10)).await;
+
+    assert_eq!(counts.connects.load(Ordering::SeqCst), 1);
+    // Command handler and data timeout watcher
+    assert_eq!(counts.spawns.load(Ordering::SeqCst), 2);
+    assert!(counts.sleeps.load(Ordering::SeqCst) >= 1);
+
+    serve.abort();
+    server.abort();
+}
@@ -0,0 +1,96 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use kiteconnect_rs::session::{FileSessionStore, MemorySessionStore, SessionStore};
+use kiteconnect_rs::users::UserSessionTokens;
+
+fn tokens() -> UserSessionTokens {
+    UserSessionTokens {
+        user_id: "AB1234".to_string(),
+        access_token: "access_token_1".to_string(),
+        refresh_token: "refresh_token_1".to_string(),
+    }
+}
+
+#[tokio::test]
+async fn test_memory_store_roundtrip() {
+    let store = MemorySessionStore::new();
+    assert_eq!(store.load().await.unwrap(), None);
+    store.save(&tokens()).await.unwrap();
+    assert_eq!(store.load().await.unwrap(), Some(tokens()));
+    store.clear().await.unwrap();
+    assert_eq!(store.load().await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_file_store_roundtrip() {
+    let dir = tempfile::tempdir().unwrap();
+    let store = FileSessionStore::new(dir.path().join("session.json"));
+    assert_eq!(store.load().await.unwrap(), None);
+    store.clear().await.unwrap();
+
+    store.save(&tokens()).await.unwrap();
+    let reopened = FileSessionStore::new(store.path());
+    assert_eq!(reopened.load().await.unwrap(), Some(tokens()));
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = std::fs::metadata(store.path())
+            .unwrap()
+            .permissions()
+            .mode();
+        assert_eq!(mode & 0o777, 0o600);
+    }
+
+    reopened.clear().await.unwrap();
+    assert_eq!(store.load().await.unwrap(), None);
+
+    std::fs::write(store.path(), "not json").unwrap();
+    assert!(store.load().await.is_err());
+}
+
+#[test]
+fn test_session_tokens_are_left_out_of_debug() {
+    use kiteconnect_rs::users::UserSession;
+
+    let tokens = tokens();
+    let debug = format!("{:?}", tokens);
+    assert!(debug.contains("AB1234"));
+    assert!(!debug.contains("access_token_1") && !debug.contains("refresh_token_1"));
+
+    let session: UserSession = serde_json::from_value(serde_json::json!({
+        "user_id": "AB1234", "user_name": "AxAx Bxx", "user_shortname": "AxAx",
+        "avatar_url": null, "user_type": "individual", "email": "xxxyyy@gmail.com",
+        "broker": "ZERODHA", "meta": {"demat_consent": "physical"},
+        "products": [], "order_types": [], "exchanges": [],
+        "access_token": "access_token_1", "refresh_token": "refresh_token_1",
+        "api_key": "test_api_key", "public_token": "public_token_1",
+        "login_time": "2024-06-03 09:00:00"
+    }))
+    .unwrap();
+    let debug = format!("{:#?}", session);
+    assert!(debug.contains("AB1234"));
+    assert!(!debug.contains("access_token_1") && !debug.contains("refresh_token_1"));
+    // Clones keep their own copy of the tokens after the original is dropped and wiped
+    let clone = session.clone();
+    drop(session);
+    assert_eq!(clone.access_token, "access_token_1");
+}
+
+#[test]
+fn test_client_debug_leaves_out_credentials() {
+    use kiteconnect_rs::KiteConnect;
+
+    let builder = KiteConnect::builder("test_api_key").access_token("access_token_1");
+    let debug = format!("{:?}", builder);
+    assert!(!debug.contains("access_token_1"));
+
+    let kite = builder.build().unwrap();
+    let debug = format!("{:?}", kite);
+    assert!(!debug.contains("access_token_1"));
+    assert!(debug.contains("access_token: Some(\"REDACTED\")"));
+    // The API key is only hidden with the `redact` feature
+    assert_eq!(
+        debug.contains("test_api_key"),
+        cfg!(not(feature = "redact"))
+    );
+}
@@ -0,0 +1,146 @@
+#![cfg(not(target_arch = "wasm32"))]
+
+use async_trait::async_trait;
+use kiteconnect_rs::sinks::{SinkConfig, SinkWriter, TickSink};
+use kiteconnect_rs::{KiteConnectError, Tick};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Clone, Default)]
+struct RecordingSink {
+    batches: Arc<Mutex<Vec<usize>>>,
+    failures_left: Arc<Mutex<u32>>,
+}
+
+#[async_trait]
+impl TickSink for RecordingSink {
+    async fn write_ticks(&self, ticks: &[Tick]) -> Result<(), KiteConnectError> {
+        let mut failures = self.failures_left.lock().unwrap();
+        if *failures > 0 {
+            *failures -= 1;
+            return Err(KiteConnectError::storage("sink unavailable"));
+        }
+        self.batches.lock().unwrap().push(ticks.len());
+        Ok(())
+    }
+}
+
+fn tick(token: u32) -> Tick {
+    Tick {
+        instrument_token: token,
+        last_price: 100.0,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn test_sink_writer_batches_and_retries() {
+    let sink = RecordingSink::default();
+    *sink.failures_left.lock().unwrap() = 1;
+
+    let writer = SinkWriter::spawn(
+        sink.clone(),
+        SinkConfig::default()
+            .batch_size(4)
+            .flush_interval(Duration::from_millis(50))
+            .retry_backoff(Duration::from_millis(1)),
+    );
+    for token in 0..10 {
+        writer.write(tick(token)).await.unwrap();
+    }
+    writer.close().await;
+
+    let batches = sink.batches.lock().unwrap().clone();
+    assert_eq!(batches.iter().sum::<usize>(), 10);
+    assert!(batches.iter().all(|&len| len <= 4));
+}
+
+#[tokio::test]
+async fn test_sink_writer_drops_after_retries() {
+    let sink = RecordingSink::default();
+    *sink.failures_left.lock().unwrap() = u32::MAX;
+
+    let writer = SinkWriter::spawn(
+        sink.clone(),
+        SinkConfig::default()
+            .max_retries(2)
+            .retry_backoff(Duration::from_millis(1))
+            .flush_interval(Duration::from_millis(10)),
+    );
+    writer.write(tick(1)).await.unwrap();
+
+    tokio::time::timeout(Duration::from_secs(2), async {
+        while writer.dropped() == 0 {
+            tokio::time::sleep(Duration::from_millis(5)).await;
+        }
+    })
+    .await
+    .expect("batch was never dropped");
+    assert_eq!(writer.written(), 0);
+    assert_eq!(*sink.failures_left.lock().unwrap(), u32::MAX - 3);
+}
+
+#[cfg(feature = "sqlite")]
+#[tokio::test]
+async fn test_sqlite_sink_roundtrip() {
+    use kiteconnect_rs::HistoricalData;
+    use kiteconnect_rs::models::time::Time;
+    use kiteconnect_rs::sinks::SqliteSink;
+
+    let sink = SqliteSink::open_in_memory().unwrap();
+    sink.write_ticks(&[tick(1), tick(2)]).await.unwrap();
+
+    let candle = HistoricalData {
+        date: Time::from_timestamp(1_700_000_000),
+        open: 1.0,
+        high: 2.0,
+        low: 0.5,
+        close: 1.5,
+        volume: 10,
+        oi: 0,
+    };
+    sink.write_candles(1, "minute", std::slice::from_ref(&candle))
+        .await
+        .unwrap();
+    // Same candle again is an upsert, not a duplicate
+    sink.write_candles(1, "minute", &[candle]).await.unwrap();
+
+    let (ticks, candles): (i64, i64) = sink.with_connection(|conn| {
+        (
+            conn.query_row("SELECT COUNT(*) FROM ticks", [], |r| r.get(0))
+                .unwrap(),
+            conn.query_row("SELECT COUNT(*) FROM candles", [], |r| r.get(0))
+                .unwrap(),
+        )
+    });
+    assert_eq!((ticks, candles), (2, 1));
+}
+
+#[tokio::test]
+async fn test_sink_writer_does_not_retry_terminal_errors() {
+    #[derive(Clone, Default)]
+    struct RejectingSink {
+        attempts: Arc<Mutex<u32>>,
+    }
+
+    #[async_trait]
+    impl TickSink for RejectingSink {
+        async fn write_ticks(&self, _ticks: &[Tick]) -> Result<(), KiteConnectError> {
+            *self.attempts.lock().unwrap() += 1;
+            Err(KiteConnectError::invalid_params("tick rejected by schema"))
+        }
+    }
+
+    let sink = RejectingSink::default();
+    let writer = SinkWriter::spawn(
+        sink.clone(),
+        SinkConfig::default()
+            .max_retries(3)
+            .retry_backoff(Duration::from_millis(1))
+            .flush_interval(Duration::from_millis(10)),
+    );
+    writer.write(tick(1)).await.unwrap();
+    writer.close().await;
+
+    assert_eq!(*sink.attempts.lock().unwrap(), 1);
+}
@@ -2,6 +2,7 @@
 
 use base64::{Engine as _, engine::general_purpose};
 use kiteconnect_rs::{DepthItem, Mode, Ticker, TickerBuilder};
+use std::collections::HashMap;
 use std::fs;
 use std::time::Duration;
 
@@ -22,6 +23,47 @@ async fn test_ticker_builder() {
     assert!(result.is_ok());
 }
 
+#[test]
+fn test_ticker_builder_validation() {
+    assert!(TickerBuilder::new("", "test_access_token").build().is_err());
+    assert!(TickerBuilder::new("test_api_key", "").build().is_err());
+    assert!(
+        TickerBuilder::new("test_api_key", "test_access_token")
+            .url("https://ws.kite.trade".to_string())
+            .build()
+            .is_err()
+    );
+    assert!(
+        TickerBuilder::new("test_api_key", "test_access_token")
+            .connect_timeout(Duration::ZERO)
+            .build()
+            .is_err()
+    );
+}
+
+#[test]
+fn test_ticker_builder_tls_options() {
+    assert!(
+        TickerBuilder::new("test_api_key", "test_access_token")
+            .tls_system_roots(false)
+            .build()
+            .is_err()
+    );
+    assert!(
+        TickerBuilder::new("test_api_key", "test_access_token")
+            .root_certificate_pem(b"not a certificate")
+            .build()
+            .is_err()
+    );
+    assert!(
+        TickerBuilder::new("test_api_key", "test_access_token")
+            .root_certificate_der(vec![0x30, 0x03, 0x02, 0x01, 0x01])
+            .tls_system_roots(false)
+            .build()
+            .is_ok()
+    );
+}
+
 #[tokio::test]
 async fn test_reconnect_delay_validation() {
     let (mut ticker, _) = Ticker::new("test_api_key".to_string(), "test_access_token".to_string());
@@ -115,8 +157,8 @@ fn test_parse_quote_packet() {
     // Expected values from the Go test case
     assert_eq!(tick.mode, "quote");
     assert_eq!(tick.instrument_token, 408065);
-    assert_eq!(tick.is_tradable, true);
-    assert_eq!(tick.is_index, false);
+    assert!(tick.is_tradable);
+    assert!(!tick.is_index);
     assert_eq!(tick.last_price, 1573.15);
     assert_eq!(tick.last_traded_quantity, 1);
     assert_eq!(tick.total_buy_quantity, 256511);
@@ -157,8 +199,8 @@ fn test_parse_full_packet() {
     // Expected values from the Go test case
     assert_eq!(tick.mode, "full");
     assert_eq!(tick.instrument_token, 408065);
-    assert_eq!(tick.is_tradable, true);
-    assert_eq!(tick.is_index, false);
+    assert!(tick.is_tradable);
+    assert!(!tick.is_index);
     assert_eq!(tick.last_price, 1573.7);
     assert_eq!(tick.last_traded_quantity, 7);
     assert_eq!(tick.total_buy_quantity, 256443);
@@ -330,7 +372,7 @@ mod integration_tests {
             .build()
             .unwrap();
 
-        let mut event_receiver = handle.subscribe_events();
+        let event_receiver = handle.subscribe_events();
 
         // Start ticker
         let ticker_handle = tokio::spawn(async move { ticker.serve().await });
@@ -364,3 +406,959 @@ mod integration_tests {
         }
     }
 }
+
+#[test]
+fn test_resubscribe_messages_grouped_by_mode() {
+    let mut subscriptions = HashMap::new();
+    subscriptions.insert(3, Mode::Full);
+    subscriptions.insert(1, Mode::Full);
+    subscriptions.insert(2, Mode::Full);
+    subscriptions.insert(10, Mode::LTP);
+    subscriptions.insert(20, Mode::Quote);
+
+    let messages = Ticker::resubscribe_messages(&subscriptions, 2);
+    assert_eq!(
+        messages,
+        vec![
+            r#"{"a":"subscribe","v":[20]}"#,
+            r#"{"a":"subscribe","v":[10]}"#,
+            r#"{"a":"mode","v":["ltp",[10]]}"#,
+            r#"{"a":"subscribe","v":[1,2]}"#,
+            r#"{"a":"mode","v":["full",[1,2]]}"#,
+            r#"{"a":"subscribe","v":[3]}"#,
+            r#"{"a":"mode","v":["full",[3]]}"#,
+        ]
+    );
+
+    assert!(Ticker::resubscribe_messages(&HashMap::new(), 2).is_empty());
+}
+
+#[tokio::test]
+async fn test_latency_stats_from_local_server() {
+    use futures_util::SinkExt;
+    use kiteconnect_rs::TickerEvent;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let exchange_ts = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_secs() as u32
+        - 2;
+    // Full mode index packet for NIFTY 50 (segment 9) carrying the exchange timestamp.
+    let mut packet = 256265_u32.to_be_bytes().to_vec();
+    packet.extend_from_slice(&[0; 24]);
+    packet.extend_from_slice(&exchange_ts.to_be_bytes());
+    let mut frame = 1_u16.to_be_bytes().to_vec();
+    frame.extend_from_slice(&(packet.len() as u16).to_be_bytes());
+    frame.extend_from_slice(&packet);
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        ws.send(Message::Binary(frame.into())).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let tick = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let Ok(TickerEvent::Tick(tick)) = events.recv().await {
+                return tick;
+            }
+        }
+    })
+    .await
+    .expect("no tick received");
+    assert_eq!(tick.instrument_token, 256265);
+
+    let stats = handle.latency_stats();
+    assert_eq!(stats.samples, 1);
+    assert!((1000..5000).contains(&stats.p50_ms), "{:?}", stats);
+    assert_eq!(stats.min_ms, stats.max_ms);
+    assert!(stats.first_tick.is_some());
+
+    serve.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_broadcast_receivers_each_get_every_event() {
+    use futures_util::SinkExt;
+    use kiteconnect_rs::TickerEvent;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Two LTP packets in one frame, then the connection closes
+    let mut frame = 2_u16.to_be_bytes().to_vec();
+    for token in [408065_u32, 738561] {
+        frame.extend_from_slice(&8_u16.to_be_bytes());
+        frame.extend_from_slice(&token.to_be_bytes());
+        frame.extend_from_slice(&150000_u32.to_be_bytes());
+    }
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        ws.send(Message::Binary(frame.into())).await.unwrap();
+        ws.close(None).await.unwrap();
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let first = handle.subscribe_broadcast();
+    let second = handle.subscribe_broadcast();
+    tokio::time::timeout(Duration::from_secs(5), ticker.serve())
+        .await
+        .expect("ticker did not stop")
+        .ok();
+
+    // Receivers close with the ticker once drained
+    let drain = |receiver: kiteconnect_rs::EventReceiver| async move {
+        let mut seqs = Vec::new();
+        let mut ticks = 0;
+        while let Ok(event) = receiver.recv_stamped().await {
+            seqs.push(event.seq);
+            ticks += matches!(event.event, TickerEvent::Tick(_)) as usize;
+        }
+        (seqs, ticks)
+    };
+    let (first_seqs, first_ticks) = drain(first).await;
+    let (second_seqs, second_ticks) = drain(second).await;
+    let (main_seqs, main_ticks) = drain(events).await;
+    assert_eq!(first_ticks, 2);
+    assert_eq!(second_ticks, 2);
+    assert_eq!(main_ticks, 2);
+    assert_eq!(first_seqs, main_seqs);
+    assert_eq!(second_seqs, main_seqs);
+
+    // Subscribing after the ticker stopped gives a closed receiver
+    assert!(handle.subscribe_broadcast().recv().await.is_err());
+
+    server.await.unwrap();
+}
+
+#[tokio::test]
+async fn test_events_are_stamped_in_order() {
+    use futures_util::SinkExt;
+    use kiteconnect_rs::TickerEvent;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Two LTP packets in one frame
+    let mut frame = 2_u16.to_be_bytes().to_vec();
+    for token in [408065_u32, 738561] {
+        frame.extend_from_slice(&8_u16.to_be_bytes());
+        frame.extend_from_slice(&token.to_be_bytes());
+        frame.extend_from_slice(&150000_u32.to_be_bytes());
+    }
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        ws.send(Message::Binary(frame.into())).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let stamped = tokio::time::timeout(Duration::from_secs(5), async {
+        let mut stamped = Vec::new();
+        while stamped.len() < 4 {
+            stamped.push(events.recv_stamped().await.unwrap());
+        }
+        stamped
+    })
+    .await
+    .expect("events not received");
+
+    let seqs: Vec<u64> = stamped.iter().map(|e| e.seq).collect();
+    assert_eq!(seqs, vec![1, 2, 3, 4]);
+    assert!(matches!(*stamped[0], TickerEvent::Connect));
+    assert!(matches!(stamped[1].event(), TickerEvent::Message(_)));
+    // Ticks carry the receive time of the frame they came in
+    for event in &stamped[2..] {
+        assert!(matches!(&**event, TickerEvent::Tick(_)));
+        assert_eq!(event.received_at, stamped[1].received_at);
+        assert_eq!(event.received_at_ms, stamped[1].received_at_ms);
+    }
+    assert!(stamped[0].received_at <= stamped[1].received_at);
+    let now_ms = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .unwrap()
+        .as_millis() as i64;
+    assert!((now_ms - stamped[3].received_at_ms).abs() < 5000);
+
+    serve.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_lifecycle_events_skip_data() {
+    use futures_util::SinkExt;
+    use kiteconnect_rs::TickerEvent;
+    use tokio_tungstenite::tungstenite::Message;
+    use tokio_tungstenite::tungstenite::protocol::CloseFrame;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let mut frame = 1_u16.to_be_bytes().to_vec();
+    frame.extend_from_slice(&8_u16.to_be_bytes());
+    frame.extend_from_slice(&408065_u32.to_be_bytes());
+    frame.extend_from_slice(&150000_u32.to_be_bytes());
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        ws.send(Message::Binary(frame.into())).await.unwrap();
+        ws.send(Message::Close(Some(CloseFrame {
+            code: 1000.into(),
+            reason: "bye".into(),
+        })))
+        .await
+        .unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let lifecycle = handle.lifecycle_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let (connect, close) = tokio::time::timeout(Duration::from_secs(5), async {
+        (
+            lifecycle.recv_stamped().await.unwrap(),
+            lifecycle.recv_stamped().await.unwrap(),
+        )
+    })
+    .await
+    .expect("lifecycle events not received");
+    assert!(matches!(*connect, TickerEvent::Connect));
+    assert!(matches!(&*close, TickerEvent::Close(1000, reason) if reason == "bye"));
+
+    // The combined stream still carries everything, numbered the same
+    let combined: Vec<_> = std::iter::from_fn(|| events.try_recv_stamped().ok()).collect();
+    assert!(combined.iter().any(|e| matches!(**e, TickerEvent::Tick(_))));
+    let seq_of = |seq| combined.iter().find(|e| e.seq == seq).map(|e| e.event());
+    assert!(matches!(seq_of(connect.seq), Some(TickerEvent::Connect)));
+    assert!(matches!(seq_of(close.seq), Some(TickerEvent::Close(..))));
+    while let Ok(event) = lifecycle.try_recv() {
+        assert!(event.is_lifecycle(), "{:?}", event);
+    }
+
+    serve.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_mode_changes_are_batched_and_tracked() {
+    use futures_util::StreamExt;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                received.push(text.to_string());
+            }
+        }
+        received
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+
+    // Mode set before the subscribe, then rapid changes that should collapse to one message
+    handle.set_mode(Mode::Full, vec![1]).await.unwrap();
+    handle.subscribe(vec![1, 2]).await.unwrap();
+    handle.set_mode(Mode::LTP, vec![2]).await.unwrap();
+    handle.set_mode(Mode::Full, vec![2]).await.unwrap();
+    let serve = tokio::spawn(ticker.serve());
+
+    let received = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("messages not received")
+        .unwrap();
+    assert_eq!(
+        received,
+        vec![
+            r#"{"a":"subscribe","v":[1,2]}"#,
+            r#"{"a":"mode","v":["full",[1]]}"#,
+            r#"{"a":"mode","v":["full",[2]]}"#,
+        ]
+    );
+
+    assert_eq!(handle.mode_of(1).await, Some(Mode::Full));
+    assert_eq!(handle.mode_of(2).await, Some(Mode::Full));
+    assert_eq!(handle.mode_of(3).await, None);
+
+    serve.abort();
+}
+
+#[tokio::test]
+async fn test_duplicate_and_unknown_subscriptions() {
+    use futures_util::StreamExt;
+    use kiteconnect_rs::{TickerErrorKind, TickerEvent};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let mut received = Vec::new();
+        while received.len() < 3 {
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                received.push(text.to_string());
+            }
+        }
+        received
+    });
+
+    let (mut ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+
+    handle.subscribe(vec![1, 2]).await.unwrap();
+    handle.subscribe(vec![2, 3]).await.unwrap();
+    handle.unsubscribe(vec![1, 4]).await.unwrap();
+    ticker.set_strict_subscriptions(true);
+    let serve = tokio::spawn(ticker.serve());
+
+    let received = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("messages not received")
+        .unwrap();
+    assert_eq!(
+        received,
+        vec![
+            r#"{"a":"subscribe","v":[1,2]}"#,
+            r#"{"a":"subscribe","v":[3]}"#,
+            r#"{"a":"unsubscribe","v":[1]}"#,
+        ]
+    );
+
+    let warning = tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            if let TickerEvent::Warning(msg) = events.recv().await.unwrap() {
+                return msg;
+            }
+        }
+    })
+    .await
+    .expect("warning not received");
+    assert!(warning.contains("[4]"));
+
+    // Strict mode rejects the same calls with typed errors
+    let err = handle.subscribe(vec![2, 5]).await.unwrap_err();
+    assert_eq!(err.kind, TickerErrorKind::AlreadySubscribed(vec![2]));
+    let err = handle.unsubscribe(vec![3, 1]).await.unwrap_err();
+    assert_eq!(err.kind, TickerErrorKind::NotSubscribed(vec![1]));
+
+    serve.abort();
+}
+
+#[tokio::test]
+async fn test_token_validation_rejects_unknown_and_expired() {
+    use kiteconnect_rs::markets::{InstrumentFilter, parse_instruments_filtered};
+    use kiteconnect_rs::{InstrumentStore, TickerErrorKind, TokenValidation};
+    use std::sync::Arc;
+
+    let csv = "\
+instrument_token,exchange_token,tradingsymbol,name,last_price,expiry,strike,tick_size,lot_size,instrument_type,segment,exchange
+408065,1594,INFY,INFOSYS,0,,0,0.05,1,EQ,NSE,NSE
+12346370,48228,NIFTY24JUNFUT,NIFTY,0,2024-06-27,0,0.05,50,FUT,NFO-FUT,NFO
+";
+    let store: InstrumentStore =
+        parse_instruments_filtered(csv.as_bytes(), &InstrumentFilter::new())
+            .unwrap()
+            .into();
+
+    let (_ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .validate_tokens(Arc::new(store), TokenValidation::Reject)
+        .build()
+        .unwrap();
+
+    handle.subscribe(vec![408065]).await.unwrap();
+    let err = handle
+        .subscribe(vec![408065, 12346370, 7])
+        .await
+        .unwrap_err();
+    match err.kind {
+        TickerErrorKind::InvalidTokens(check) => {
+            assert_eq!(check.unknown, vec![7]);
+            assert_eq!(check.expired, vec![12346370]);
+        }
+        kind => panic!("unexpected error kind {:?}", kind),
+    }
+}
+
+// Serve and drive the ticker from one task: subscribe, then wait for the tick the local
+// server sends back once it saw the subscribe message.
+async fn subscribe_roundtrip_in_one_task(single_task: bool) {
+    use futures_util::{SinkExt, StreamExt};
+    use kiteconnect_rs::TickerEvent;
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        while let Some(Ok(message)) = ws.next().await {
+            if let Message::Text(text) = message {
+                assert_eq!(text.as_str(), r#"{"a":"subscribe","v":[408065]}"#);
+                break;
+            }
+        }
+        let mut frame = 1_u16.to_be_bytes().to_vec();
+        frame.extend_from_slice(&8_u16.to_be_bytes());
+        frame.extend_from_slice(&408065_u32.to_be_bytes());
+        frame.extend_from_slice(&150000_u32.to_be_bytes());
+        ws.send(Message::Binary(frame.into())).await.unwrap();
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .single_task_mode(single_task)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+
+    let client = async {
+        handle.subscribe(vec![408065]).await.unwrap();
+        loop {
+            if let TickerEvent::Tick(tick) = events.recv().await.unwrap() {
+                return tick;
+            }
+        }
+    };
+    let tick = tokio::time::timeout(Duration::from_secs(5), async {
+        tokio::select! {
+            result = ticker.serve() => panic!("serve returned early: {:?}", result.err()),
+            tick = client => tick,
+        }
+    })
+    .await
+    .expect("tick not received");
+
+    assert_eq!(tick.instrument_token, 408065);
+    assert_eq!(tick.last_price, 1500.0);
+    assert_eq!(handle.mode_of(408065).await, Some(Mode::Quote));
+    server.abort();
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_single_task_mode_on_current_thread_runtime() {
+    subscribe_roundtrip_in_one_task(true).await;
+}
+
+#[tokio::test(flavor = "current_thread")]
+async fn test_spawned_tasks_on_current_thread_runtime() {
+    subscribe_roundtrip_in_one_task(false).await;
+}
+
+// A server that accepts connections and never sends data: the ticker reports the data
+// timeout, drops the connection and reconnects with backoff.
+async fn data_timeout_reconnects(single_task: bool) {
+    use kiteconnect_rs::TickerEvent;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let mut connections = Vec::new();
+        while let Ok((stream, _)) = listener.accept().await {
+            connections.push(tokio_tungstenite::accept_async(stream).await.unwrap());
+        }
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .single_task_mode(single_task)
+        .build()
+        .unwrap();
+    let lifecycle = handle.lifecycle_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    let events = tokio::time::timeout(Duration::from_secs(15), async {
+        let mut events = Vec::new();
+        while events.len() < 4 {
+            events.push(lifecycle.recv().await.unwrap());
+        }
+        events
+    })
+    .await
+    .expect("ticker did not reconnect");
+
+    assert!(matches!(events[0], TickerEvent::Connect));
+    assert!(
+        matches!(&events[1], TickerEvent::Error(message) if message.starts_with("Data timeout"))
+    );
+    assert!(
+        matches!(events[2], TickerEvent::Reconnect(1, delay) if delay == Duration::from_secs(2))
+    );
+    assert!(matches!(events[3], TickerEvent::Connect));
+
+    serve.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_data_timeout_reconnects() {
+    data_timeout_reconnects(false).await;
+}
+
+#[tokio::test]
+async fn test_data_timeout_reconnects_in_single_task_mode() {
+    data_timeout_reconnects(true).await;
+}
+
+#[tokio::test]
+async fn test_lifecycle_overflow_reports_lag() {
+    use futures_util::SinkExt;
+    use kiteconnect_rs::TickerEvent;
+    use kiteconnect_rs::ticker::{EventChannel, LIFECYCLE_EVENT_CAPACITY};
+    use std::sync::atomic::{AtomicU64, Ordering};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // A single two byte packet, too short to parse, so every frame is an error
+    let mut frame = 1_u16.to_be_bytes().to_vec();
+    frame.extend_from_slice(&2_u16.to_be_bytes());
+    frame.extend_from_slice(&[0, 1]);
+    let frames = LIFECYCLE_EVENT_CAPACITY + 2;
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        for _ in 0..frames {
+            ws.send(Message::Binary(frame.clone().into()))
+                .await
+                .unwrap();
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let hook_missed = std::sync::Arc::new(AtomicU64::new(0));
+    let hook_seen = hook_missed.clone();
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .lag_hook(move |channel, missed| {
+            assert_eq!(channel, EventChannel::Lifecycle);
+            hook_seen.store(missed, Ordering::SeqCst);
+        })
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    // Connect plus one error per frame, against a lifecycle channel nobody reads
+    tokio::time::timeout(Duration::from_secs(5), async {
+        let mut errors = 0;
+        while errors < frames {
+            let event = events.recv().await.unwrap();
+            assert!(!matches!(event, TickerEvent::SubscriberLagged { .. }));
+            if matches!(event, TickerEvent::Error(_)) {
+                errors += 1;
+            }
+        }
+    })
+    .await
+    .expect("errors not received");
+
+    // The first drop is reported on the lifecycle channel, pushing out one more event
+    assert_eq!(handle.missed_lifecycle_events(), 4);
+    assert_eq!(hook_missed.load(Ordering::SeqCst), 4);
+    let lifecycle = handle.lifecycle_events();
+    assert_eq!(lifecycle.len(), LIFECYCLE_EVENT_CAPACITY);
+    let mut lagged = Vec::new();
+    while let Ok(event) = lifecycle.try_recv() {
+        if let TickerEvent::SubscriberLagged { missed } = event {
+            lagged.push(missed);
+        }
+    }
+    assert_eq!(lagged, [1]);
+
+    serve.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_unread_events_are_bounded() {
+    use futures_util::SinkExt;
+    use kiteconnect_rs::TickerEvent;
+    use kiteconnect_rs::ticker::{EVENT_CAPACITY, EventChannel};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // Enough LTP packets to overflow the event channel, which only the lifecycle
+    // receiver is watched through
+    let packets = 2000_u16;
+    let mut frame = packets.to_be_bytes().to_vec();
+    for _ in 0..packets {
+        frame.extend_from_slice(&8_u16.to_be_bytes());
+        frame.extend_from_slice(&408065_u32.to_be_bytes());
+        frame.extend_from_slice(&150000_u32.to_be_bytes());
+    }
+    let frames = EVENT_CAPACITY / packets as usize + 1;
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        for _ in 0..frames {
+            ws.send(Message::Binary(frame.clone().into()))
+                .await
+                .unwrap();
+        }
+        tokio::time::sleep(Duration::from_secs(5)).await;
+    });
+
+    let hook_channels = std::sync::Arc::new(std::sync::Mutex::new(Vec::new()));
+    let hook_seen = hook_channels.clone();
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .lag_hook(move |channel, _| hook_seen.lock().unwrap().push(channel))
+        .build()
+        .unwrap();
+    let lifecycle = handle.lifecycle_events();
+    let serve = tokio::spawn(ticker.serve());
+
+    assert!(matches!(
+        lifecycle.recv().await.unwrap(),
+        TickerEvent::Connect
+    ));
+    tokio::time::timeout(Duration::from_secs(10), async {
+        while handle.missed_events() == 0 {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+    })
+    .await
+    .expect("event channel never overflowed");
+    let events = handle.subscribe_events();
+    assert_eq!(events.len(), EVENT_CAPACITY);
+    assert!(
+        hook_channels
+            .lock()
+            .unwrap()
+            .iter()
+            .all(|channel| *channel == EventChannel::Events)
+    );
+
+    // The drop is reported on the channel that overflowed, numbered like the tick before it
+    let mut previous = None;
+    let lagged = loop {
+        let event = events.try_recv_stamped().expect("lag not reported");
+        if let TickerEvent::SubscriberLagged { missed } = event.event {
+            break (missed, event.seq, previous);
+        }
+        previous = Some(event.seq);
+    };
+    assert!(lagged.0 >= 1);
+    assert_eq!(Some(lagged.1), lagged.2);
+    assert!(lifecycle.is_empty());
+
+    serve.abort();
+    server.abort();
+}
+
+#[tokio::test]
+async fn test_subscription_manager_budget_and_eviction() {
+    use futures_util::StreamExt;
+    use kiteconnect_rs::{SubscriptionManager, SubscriptionPriority};
+    use tokio_tungstenite::tungstenite::Message;
+
+    let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    let server = tokio::spawn(async move {
+        let (stream, _) = listener.accept().await.unwrap();
+        let mut ws = tokio_tungstenite::accept_async(stream).await.unwrap();
+        let mut received = Vec::new();
+        while received.len() < 5 {
+            if let Some(Ok(Message::Text(text))) = ws.next().await {
+                received.push(text.to_string());
+            }
+        }
+        received
+    });
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(format!("ws://{}", addr))
+        .auto_reconnect(false)
+        .build()
+        .unwrap();
+    let events = handle.subscribe_events();
+    let serve = tokio::spawn(ticker.serve());
+    tokio::time::timeout(Duration::from_secs(5), events.recv())
+        .await
+        .expect("not connected")
+        .unwrap();
+
+    let manager = SubscriptionManager::new(handle.clone(), 3);
+    let change = manager
+        .request(&[1, 2, 3], SubscriptionPriority::Normal)
+        .await
+        .unwrap();
+    assert_eq!(change.subscribed, [1, 2, 3]);
+    manager
+        .request(&[4], SubscriptionPriority::Pinned)
+        .await
+        .unwrap();
+    assert_eq!(manager.subscribed(), [2, 3, 4]);
+
+    // 2 is used again, so 3 is now the least recently used
+    manager.touch(2);
+    let change = manager
+        .request(&[5], SubscriptionPriority::Normal)
+        .await
+        .unwrap();
+    assert_eq!(change.unsubscribed, [3]);
+    assert_eq!(manager.subscribed(), [2, 4, 5]);
+
+    // Nothing is low enough to make room for a low priority token
+    let change = manager
+        .request(&[6], SubscriptionPriority::Low)
+        .await
+        .unwrap();
+    assert_eq!(change.rejected, [6]);
+    assert!(change.subscribed.is_empty() && change.unsubscribed.is_empty());
+
+    let change = manager
+        .set_interest(&[
+            (4, SubscriptionPriority::Pinned),
+            (5, SubscriptionPriority::High),
+        ])
+        .await
+        .unwrap();
+    assert_eq!(change.unsubscribed, [2]);
+    assert!(change.subscribed.is_empty());
+    assert_eq!(manager.len(), 2);
+
+    let received = tokio::time::timeout(Duration::from_secs(5), server)
+        .await
+        .expect("messages not received")
+        .unwrap();
+    assert_eq!(
+        received,
+        vec![
+            r#"{"a":"subscribe","v":[1,2,3]}"#,
+            r#"{"a":"unsubscribe","v":[1]}"#,
+            r#"{"a":"subscribe","v":[4]}"#,
+            r#"{"a":"unsubscribe","v":[3]}"#,
+            r#"{"a":"subscribe","v":[5]}"#,
+        ]
+    );
+
+    serve.abort();
+}
+
+#[tokio::test]
+async fn test_ticker_credentials_are_redacted() {
+    use kiteconnect_rs::TickerEvent;
+    use kiteconnect_rs::ticker::redact_url;
+
+    const API_KEY: &str = "secretapikey01";
+    const ACCESS_TOKEN: &str = "secretaccesstoken02";
+    let leaks = |text: &str| text.contains(API_KEY) || text.contains(ACCESS_TOKEN);
+
+    assert_eq!(
+        redact_url("wss://ws.kite.trade/?api_key=a&access_token=b"),
+        "wss://ws.kite.trade/?api_key=REDACTED&access_token=REDACTED"
+    );
+    assert_eq!(redact_url("not a url?access_token=b"), "not a url?REDACTED");
+
+    // Nothing listens on the port, so connecting fails
+    let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    drop(listener);
+    let builder = TickerBuilder::new(API_KEY, ACCESS_TOKEN)
+        .url(format!("ws://{}/?debug=true", addr))
+        .auto_reconnect(false)
+        .connect_timeout(Duration::from_secs(2));
+    assert!(!leaks(&format!("{:?}", builder)));
+    let (ticker, handle) = builder.build().unwrap();
+
+    let url = ticker.redacted_url();
+    assert_eq!(
+        url,
+        format!(
+            "ws://{}/?debug=REDACTED&api_key=REDACTED&access_token=REDACTED",
+            addr
+        )
+    );
+    assert!(!leaks(&format!("{:?}", ticker)));
+
+    let events = handle.subscribe_events();
+    let err = ticker.serve().await.unwrap_err();
+    assert!(!leaks(&err.to_string()) && !leaks(&format!("{:?}", err)));
+    while let Ok(event) = events.try_recv() {
+        if let TickerEvent::Error(message) = &event {
+            assert!(message.starts_with("Connection failed"), "{}", message);
+        }
+        assert!(!leaks(&format!("{:?}", event)));
+    }
+}
+
+#[test]
+fn test_tick_display_summary() {
+    let mut tick = kiteconnect_rs::Tick {
+        mode: "ltp".to_string(),
+        instrument_token: 408065,
+        last_price: 1500.5,
+        ..Default::default()
+    };
+    assert_eq!(tick.to_string(), "408065 1500.5 (+0)");
+
+    tick.mode = "full".to_string();
+    tick.net_change = -12.25;
+    tick.volume_traded = 120000;
+    tick.timestamp = kiteconnect_rs::models::time::Time::parse("2024-06-03 09:15:00").unwrap();
+    assert_eq!(
+        tick.to_string(),
+        format!("408065 1500.5 (-12.25) vol 120000 at {}", tick.timestamp)
+    );
+}
+
+#[tokio::test]
+async fn test_supervisor_restarts_ticker_with_fresh_token() {
+    use async_trait::async_trait;
+    use kiteconnect_rs::session::TokenProvider;
+    use kiteconnect_rs::supervisor::TickerSupervisor;
+    use kiteconnect_rs::test_utils::ReplayTicker;
+    use kiteconnect_rs::{KiteConnectError, TickerErrorKind, TickerEvent};
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    struct CountingProvider {
+        calls: Arc<AtomicUsize>,
+        token: &'static str,
+    }
+
+    #[async_trait]
+    impl TokenProvider for CountingProvider {
+        async fn access_token(&self) -> Result<String, KiteConnectError> {
+            self.calls.fetch_add(1, Ordering::SeqCst);
+            Ok(self.token.to_string())
+        }
+    }
+
+    let replay = ReplayTicker::generated(3, Duration::from_millis(20))
+        .await
+        .unwrap();
+    replay.reject_access_token("expired_token");
+    let ticker = |auto_reconnect| {
+        Ticker::builder("test_api_key", "expired_token")
+            .url(replay.url())
+            .auto_reconnect(auto_reconnect)
+            .build()
+            .unwrap()
+    };
+
+    // A rejected token stops the ticker at once instead of retrying
+    let (plain, _handle) = ticker(true);
+    let err = tokio::time::timeout(Duration::from_secs(5), plain.serve())
+        .await
+        .expect("ticker kept retrying a rejected token")
+        .unwrap_err();
+    assert!(matches!(err.kind, TickerErrorKind::AuthInvalid));
+
+    let (supervised, handle) = ticker(true);
+    let calls = Arc::new(AtomicUsize::new(0));
+    let provider = CountingProvider {
+        calls: calls.clone(),
+        token: "fresh_token",
+    };
+    let events = handle.subscribe_events();
+    let run = tokio::spawn(
+        TickerSupervisor::new(supervised, provider)
+            .restart_delay(Duration::from_millis(10))
+            .run(),
+    );
+    handle.subscribe(vec![408065]).await.unwrap();
+    let mut seen = Vec::new();
+    tokio::time::timeout(Duration::from_secs(5), async {
+        loop {
+            match events.recv().await.unwrap() {
+                TickerEvent::Tick(tick) => {
+                    assert_eq!(tick.instrument_token, 408065);
+                    break;
+                }
+                TickerEvent::NoReconnect(_) => seen.push("no_reconnect"),
+                TickerEvent::Warning(message) if message.contains("restarting") => {
+                    seen.push("restart")
+                }
+                _ => {}
+            }
+        }
+    })
+    .await
+    .expect("no tick after the restart");
+    assert_eq!(seen, vec!["no_reconnect", "restart"]);
+    assert_eq!(calls.load(Ordering::SeqCst), 1);
+    assert_eq!(replay.connections(), 3);
+    run.abort();
+
+    // A provider handing out rejected tokens runs out of restarts
+    let (supervised, _handle) = ticker(true);
+    let provider = CountingProvider {
+        calls: calls.clone(),
+        token: "expired_token",
+    };
+    let err = TickerSupervisor::new(supervised, provider)
+        .restart_delay(Duration::from_millis(10))
+        .max_restarts(2)
+        .run()
+        .await
+        .unwrap_err();
+    assert!(matches!(err.kind, TickerErrorKind::AuthInvalid));
+    assert_eq!(calls.load(Ordering::SeqCst), 3);
+}
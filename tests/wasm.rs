@@ -3,6 +3,9 @@
 //! Run with Node.js: wasm-pack test --node -- --test wasm
 //! Run in browser:   wasm-pack test --headless --chrome -- --test wasm
 //!                   (requires uncommenting `run_in_browser` config below)
+//!
+//! The ticker reconnection test needs a WebSocket echo server, passed at build time:
+//!                   WASM_WS_ECHO_URL=ws://127.0.0.1:8080 wasm-pack test --node -- --test wasm
 
 #![cfg(target_arch = "wasm32")]
 
@@ -11,8 +14,10 @@ use wasm_bindgen_test::*;
 // Note: Remove this line to run tests in Node.js instead of browser
 // wasm_bindgen_test_configure!(run_in_browser);
 
-use kiteconnect_rs::compat::{sleep, timeout, TimeoutError};
-use kiteconnect_rs::{KiteConnect, TickerBuilder};
+use kiteconnect_rs::compat::{sleep, spawn, timeout, TimeoutError};
+use kiteconnect_rs::{KiteConnect, TickerBuilder, TickerEvent};
+use std::cell::Cell;
+use std::rc::Rc;
 use web_time::Duration;
 
 // ============================================================================
@@ -51,6 +56,20 @@ async fn test_timeout_expired() {
     assert!(result.is_err());
 }
 
+#[wasm_bindgen_test]
+async fn test_spawn_abort() {
+    let ran = Rc::new(Cell::new(false));
+    let flag = ran.clone();
+    let handle = spawn(async move {
+        sleep(Duration::from_millis(50)).await;
+        flag.set(true);
+    });
+    handle.abort();
+    sleep(Duration::from_millis(150)).await;
+
+    assert!(!ran.get(), "aborted task kept running");
+}
+
 // ============================================================================
 // KiteConnect Builder Tests
 // ============================================================================
@@ -333,6 +352,61 @@ fn test_parse_full_packet() {
     }
 }
 
+#[wasm_bindgen_test]
+fn test_ticks_to_js_matches_json() {
+    use kiteconnect_rs::js::{pack_ticks, ticks_to_js, PACKED_TICK_FIELDS};
+    use web_time::Instant;
+
+    let tick = Ticker::parse_packet(&decode_packet(TICKER_FULL_PACKET)).unwrap();
+    let ticks = vec![tick.clone(); 1000];
+
+    let start = Instant::now();
+    for tick in &ticks {
+        let json = serde_json::to_string(tick).unwrap();
+        js_sys::JSON::parse(&json).unwrap();
+    }
+    let via_json = start.elapsed();
+
+    let start = Instant::now();
+    let objects = ticks_to_js(&ticks);
+    let direct = start.elapsed();
+
+    let start = Instant::now();
+    let packed = pack_ticks(&ticks);
+    let packed_elapsed = start.elapsed();
+
+    console_log!(
+        "{} ticks: json {:?}, objects {:?}, packed {:?}",
+        ticks.len(),
+        via_json,
+        direct,
+        packed_elapsed
+    );
+
+    assert_eq!(objects.length(), ticks.len() as u32);
+    let first = objects.get(0);
+    let get = |key: &str| js_sys::Reflect::get(&first, &key.into()).unwrap();
+    assert_eq!(get("mode").as_string().as_deref(), Some("full"));
+    assert_eq!(get("instrument_token").as_f64(), Some(408065.0));
+    assert_eq!(get("last_price").as_f64(), Some(tick.last_price));
+    assert_eq!(get("volume_traded").as_f64(), Some(tick.volume_traded as f64));
+    let depth = js_sys::Reflect::get(&get("depth"), &"buy".into()).unwrap();
+    let level = js_sys::Reflect::get_u32(&depth, 0).unwrap();
+    let price = js_sys::Reflect::get(&level, &"price".into()).unwrap();
+    assert_eq!(price.as_f64(), Some(tick.depth.buy[0].price));
+
+    assert_eq!(
+        packed.length() as usize,
+        ticks.len() * PACKED_TICK_FIELDS.len()
+    );
+    assert_eq!(packed.get_index(0), 408065.0);
+    assert_eq!(packed.get_index(3), tick.last_price);
+    assert_eq!(
+        packed.get_index(PACKED_TICK_FIELDS.len() as u32 + 3),
+        tick.last_price
+    );
+}
+
 #[wasm_bindgen_test]
 fn test_parse_binary_with_multiple_packets() {
     let quote_data = decode_packet(TICKER_QUOTE_PACKET);
@@ -388,6 +462,67 @@ fn test_ticker_creation() {
     drop(ticker);
 }
 
+// The echo server only echoes the subscribe message, so no market data arrives: the ticker
+// reports the data timeout, drops the connection and reconnects, as on native.
+#[wasm_bindgen_test]
+async fn test_ticker_data_timeout_reconnects() {
+    let Some(url) = option_env!("WASM_WS_ECHO_URL") else {
+        return;
+    };
+
+    let (ticker, handle) = TickerBuilder::new("test_api_key", "test_access_token")
+        .url(url.to_string())
+        .build()
+        .unwrap();
+    let lifecycle = handle.lifecycle_events();
+    spawn(async move {
+        let _ = ticker.serve().await;
+    });
+    handle.subscribe(vec![408065]).await.unwrap();
+
+    let events = timeout(Duration::from_secs(15), async {
+        let mut events = Vec::new();
+        while events.len() < 4 {
+            events.push(lifecycle.recv().await.unwrap());
+        }
+        events
+    })
+    .await
+    .expect("ticker did not reconnect");
+
+    assert!(matches!(events[0], TickerEvent::Connect));
+    assert!(matches!(&events[1], TickerEvent::Error(message) if message.starts_with("Data timeout")));
+    assert!(matches!(events[2], TickerEvent::Reconnect(1, _)));
+    assert!(matches!(events[3], TickerEvent::Connect));
+}
+
+#[wasm_bindgen_test]
+async fn test_browser_storage_roundtrip() {
+    use kiteconnect_rs::session::{BrowserStorage, SessionStore};
+    use kiteconnect_rs::users::UserSessionTokens;
+
+    let tokens = UserSessionTokens {
+        user_id: "AB1234".to_string(),
+        access_token: "access_token_1".to_string(),
+        refresh_token: "refresh_token_1".to_string(),
+    };
+    let store = BrowserStorage::local("kiteconnect_test_session").passphrase("secret");
+    if store.clear().await.is_err() {
+        // No window, e.g. under Node.js
+        return;
+    }
+    assert_eq!(store.load().await.unwrap(), None);
+
+    store.save(&tokens).await.unwrap();
+    assert_eq!(store.load().await.unwrap(), Some(tokens));
+    let wrong = BrowserStorage::local("kiteconnect_test_session").passphrase("wrong");
+    assert!(wrong.load().await.is_err());
+    // Encrypted tokens are not readable as plain JSON
+    assert!(BrowserStorage::local("kiteconnect_test_session").load().await.is_err());
+
+    store.clear().await.unwrap();
+}
+
 #[wasm_bindgen_test]
 fn test_reconnect_delay_validation() {
     let (mut ticker, _handle) = Ticker::new("test_api_key".to_string(), "test_access_token".to_string());